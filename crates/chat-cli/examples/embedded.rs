@@ -0,0 +1,72 @@
+//! Runs a turn of q chat through [ChatEngine] against a mocked client, without a terminal.
+//!
+//! ```sh
+//! cargo run -p chat_cli --example embedded
+//! ```
+
+use std::io::Write;
+
+use async_trait::async_trait;
+use chat_cli::cli::{
+    Agent,
+    ChatEngine,
+    ChatEvent,
+    ToolApprover,
+    ToolDecision,
+    ToolRequest,
+};
+use chat_cli::os::Os;
+
+/// Approves every tool use, as a host application would after asking its user.
+struct ApproveAll;
+
+#[async_trait]
+impl ToolApprover for ApproveAll {
+    async fn approve(&mut self, request: &ToolRequest) -> ToolDecision {
+        println!("\n[approving {} {}]", request.name, request.args);
+        ToolDecision::Allow
+    }
+}
+
+fn print_event(event: ChatEvent) {
+    match event {
+        ChatEvent::AssistantDelta(text) => {
+            print!("{text}");
+            let _ = std::io::stdout().flush();
+        },
+        ChatEvent::ToolRequested(request) => println!("\n[tool requested: {} ({})]", request.name, request.id),
+        ChatEvent::ToolResult { name, success, .. } => println!("[tool result: {name}, success: {success}]"),
+        ChatEvent::Error { kind, message } => eprintln!("[error: {kind:?}: {message}]"),
+    }
+}
+
+#[tokio::main]
+async fn main() -> eyre::Result<()> {
+    let path = std::env::temp_dir().join("q_embedded_example.txt");
+
+    let mut os = Os::new().await?;
+    os.client.set_mock_output(serde_json::json!([
+        [
+            "I'll write the greeting to a file.",
+            {
+                "tool_use_id": "tooluse_1",
+                "name": "fs_write",
+                "args": {
+                    "command": "create",
+                    "file_text": "Hello from an embedded chat!",
+                    "path": path,
+                }
+            }
+        ],
+        [
+            "The greeting has been written.",
+        ],
+    ]));
+
+    let mut engine = ChatEngine::new(os, Agent::default(), Box::new(print_event), Box::new(ApproveAll)).await?;
+    engine.send("Write a greeting to a file").await?;
+    println!();
+
+    println!("\nWrote: {}", std::fs::read_to_string(&path)?.trim());
+    Ok(())
+}
@@ -4,9 +4,11 @@ mod delay_interceptor;
 mod endpoints;
 mod error;
 pub mod model;
+pub mod model_capabilities;
 mod opt_out;
 pub mod profile;
 mod retry_classifier;
+pub mod retry_policy;
 pub mod send_message_output;
 use std::sync::Arc;
 use std::time::Duration;
@@ -93,7 +95,7 @@ pub struct ApiClient {
     client: CodewhispererClient,
     streaming_client: Option<CodewhispererStreamingClient>,
     sigv4_streaming_client: Option<QDeveloperStreamingClient>,
-    mock_client: Option<Arc<Mutex<std::vec::IntoIter<Vec<ChatResponseStream>>>>>,
+    mock_client: Option<Arc<Mutex<std::vec::IntoIter<Result<Vec<ChatResponseStream>, ApiClientError>>>>>,
     profile: Option<AuthProfile>,
     model_cache: ModelCache,
 }
@@ -338,6 +340,11 @@ impl ApiClient {
     }
 
     pub async fn is_mcp_enabled(&self) -> Result<bool, ApiClientError> {
+        // A mocked client has no profile to read the opt-in features from
+        if self.mock_client.is_some() {
+            return Ok(true);
+        }
+
         let request = self
             .client
             .get_profile()
@@ -379,6 +386,13 @@ impl ApiClient {
 
         let model_id_opt: Option<String> = user_input_message.model_id.clone();
 
+        if let Some(client) = &self.mock_client {
+            let mut new_events = client.lock().next().unwrap_or(Ok(vec![]))?;
+            new_events.reverse();
+
+            return Ok(SendMessageOutput::Mock(new_events));
+        }
+
         if let Some(client) = &self.streaming_client {
             let conversation_state = amzn_codewhisperer_streaming_client::types::ConversationState::builder()
                 .set_conversation_id(conversation_id)
@@ -565,20 +579,22 @@ impl ApiClient {
                     Err(err.into())
                 },
             }
-        } else if let Some(client) = &self.mock_client {
-            let mut new_events = client.lock().next().unwrap_or_default().clone();
-            new_events.reverse();
-
-            return Ok(SendMessageOutput::Mock(new_events));
         } else {
             unreachable!("One of the clients must be created by this point");
         }
     }
 
-    /// Only meant for testing. Do not use outside of testing responses.
+    /// Only meant for testing and examples. Do not use outside of testing responses. Once set, the
+    /// mock responses are returned instead of sending messages to the service.
+    ///
+    /// A response given as the string `"ContextWindowOverflow"` fails with that error instead.
     pub fn set_mock_output(&mut self, json: serde_json::Value) {
         let mut mock = Vec::new();
         for response in json.as_array().unwrap() {
+            if response.as_str() == Some("ContextWindowOverflow") {
+                mock.push(Err(ApiClientError::ContextWindowOverflow { status_code: Some(400) }));
+                continue;
+            }
             let mut stream = Vec::new();
             for event in response.as_array().unwrap() {
                 match event {
@@ -593,7 +609,7 @@ impl ApiClient {
                     other => panic!("Unexpected value: {:?}", other),
                 }
             }
-            mock.push(stream);
+            mock.push(Ok(stream));
         }
 
         self.mock_client = Some(Arc::new(Mutex::new(mock.into_iter())));
@@ -707,7 +723,7 @@ mod tests {
             .unwrap();
 
         client.mock_client = Some(Arc::new(Mutex::new(
-            vec![vec![
+            vec![Ok(vec![
                 ChatResponseStream::AssistantResponseEvent {
                     content: "Hello!".to_owned(),
                 },
@@ -717,7 +733,7 @@ mod tests {
                 ChatResponseStream::AssistantResponseEvent {
                     content: " assist you today?".to_owned(),
                 },
-            ]]
+            ])]
             .into_iter(),
         )));
 
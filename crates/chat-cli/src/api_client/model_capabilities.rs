@@ -0,0 +1,61 @@
+//! Capabilities of the models returned by `ListAvailableModels` that the API does not describe.
+
+/// What a model can do besides generating text.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ModelCapabilities {
+    /// Whether the model supports extended thinking (reasoning before it answers)
+    pub thinking: bool,
+    /// Whether the model can call tools
+    pub tool_use: bool,
+}
+
+const THINKING_AND_TOOLS: ModelCapabilities = ModelCapabilities {
+    thinking: true,
+    tool_use: true,
+};
+
+const TOOLS_ONLY: ModelCapabilities = ModelCapabilities {
+    thinking: false,
+    tool_use: true,
+};
+
+/// Known model families, matched in order against the normalized id or name of a model.
+const KNOWN_MODELS: &[(&str, ModelCapabilities)] = &[
+    ("claude-sonnet-4", THINKING_AND_TOOLS),
+    ("claude-4-sonnet", THINKING_AND_TOOLS),
+    ("claude-opus-4", THINKING_AND_TOOLS),
+    ("claude-4-opus", THINKING_AND_TOOLS),
+    ("claude-3-7-sonnet", THINKING_AND_TOOLS),
+    ("claude-3-5", TOOLS_ONLY),
+    ("gpt-oss", THINKING_AND_TOOLS),
+];
+
+/// Returns the capabilities of the model with the given id or display name, or [None] if the
+/// model is not known.
+pub fn model_capabilities(model: &str) -> Option<ModelCapabilities> {
+    let normalized = model.to_lowercase().replace(['_', '.', ' '], "-");
+    KNOWN_MODELS
+        .iter()
+        .find(|(family, _)| normalized.contains(family))
+        .map(|(_, capabilities)| *capabilities)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_model_capabilities() {
+        for model in [
+            "CLAUDE_SONNET_4_20250514_V1_0",
+            "claude-sonnet-4",
+            "CLAUDE_3_7_SONNET_20250219_V1_0",
+            "claude-3.7-sonnet",
+            "OPENAI_GPT_OSS_120B_1_0",
+        ] {
+            assert_eq!(model_capabilities(model), Some(THINKING_AND_TOOLS), "{model}");
+        }
+        assert_eq!(model_capabilities("CLAUDE_3_5_SONNET_20241022_V2_0"), Some(TOOLS_ONLY));
+        assert_eq!(model_capabilities("some-new-model"), None);
+    }
+}
@@ -0,0 +1,252 @@
+use std::future::Future;
+use std::time::Duration;
+
+use tracing::warn;
+
+use super::ApiClientError;
+use crate::database::Database;
+use crate::database::settings::Setting;
+
+const DEFAULT_MAX_ATTEMPTS: u32 = 5;
+const DEFAULT_MAX_TOTAL_WAIT: Duration = Duration::from_secs(60);
+const BASE_DELAY: Duration = Duration::from_secs(1);
+const MAX_DELAY: Duration = Duration::from_secs(20);
+
+/// Progress of a request that is being retried, reported before waiting for the next attempt.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RetryStatus {
+    /// The attempt that is about to be made, starting from 2 for the first retry
+    pub attempt: u32,
+    pub max_attempts: u32,
+    /// How long we wait before making the attempt
+    pub delay: Duration,
+}
+
+/// Retries SendMessage requests that were throttled or failed with a server error, using jittered
+/// exponential backoff.
+///
+/// This is on top of the retries performed by the SDK, and exists so that a throttled turn is not
+/// lost outright. Configured with `api.retry.maxAttempts` and `api.retry.maxWaitSeconds`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ThrottleRetryPolicy {
+    /// Total number of attempts, including the first one
+    pub max_attempts: u32,
+    /// Upper bound on the time spent waiting between attempts, across all retries
+    pub max_total_wait: Duration,
+    pub base_delay: Duration,
+    pub max_delay: Duration,
+}
+
+impl Default for ThrottleRetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_attempts: DEFAULT_MAX_ATTEMPTS,
+            max_total_wait: DEFAULT_MAX_TOTAL_WAIT,
+            base_delay: BASE_DELAY,
+            max_delay: MAX_DELAY,
+        }
+    }
+}
+
+impl ThrottleRetryPolicy {
+    pub fn from_database(database: &Database) -> Self {
+        let settings = &database.settings;
+        let default = Self::default();
+        Self {
+            max_attempts: settings
+                .get_int(Setting::ApiRetryMaxAttempts)
+                .and_then(|i| u32::try_from(i).ok())
+                .map_or(default.max_attempts, |i| i.max(1)),
+            max_total_wait: settings
+                .get_int(Setting::ApiRetryMaxWaitSeconds)
+                .and_then(|i| u64::try_from(i).ok())
+                .map_or(default.max_total_wait, Duration::from_secs),
+            ..default
+        }
+    }
+
+    /// Whether `err` is worth retrying: throttling and server side errors are, whereas errors
+    /// caused by the request itself (e.g. auth or validation) are not.
+    pub fn is_retryable(err: &ApiClientError) -> bool {
+        match err {
+            ApiClientError::QuotaBreach { .. } | ApiClientError::ModelOverloadedError { .. } => true,
+            ApiClientError::CodewhispererGenerateAssistantResponse(_) | ApiClientError::QDeveloperSendMessage(_) => {
+                err.status_code().is_some_and(|status| status == 429 || status >= 500)
+            },
+            _ => false,
+        }
+    }
+
+    /// The delay before the given attempt, where `jitter` is in `[0, 1]`. Half of the
+    /// exponentially growing delay is fixed and the other half is scaled by `jitter`.
+    pub fn backoff(&self, attempt: u32, jitter: f64) -> Duration {
+        let exponent = attempt.saturating_sub(2).min(16);
+        let cap = self.base_delay.saturating_mul(1 << exponent).min(self.max_delay);
+        let half = cap / 2;
+        half + half.mul_f64(jitter.clamp(0.0, 1.0))
+    }
+
+    /// Calls `op` until it succeeds, returns an error that is not retryable, or the attempts or
+    /// total wait time run out. `on_retry` is called before waiting for each retry.
+    ///
+    /// Returns the final result along with the number of retries that were made.
+    pub async fn retry<T, E, Op, Fut>(
+        &self,
+        is_retryable: impl Fn(&E) -> bool,
+        mut op: Op,
+        mut on_retry: impl FnMut(RetryStatus),
+    ) -> (Result<T, E>, u32)
+    where
+        Op: FnMut() -> Fut,
+        Fut: Future<Output = Result<T, E>>,
+    {
+        let mut total_wait = Duration::ZERO;
+        let mut attempt = 1;
+        loop {
+            let err = match op().await {
+                Ok(res) => return (Ok(res), attempt - 1),
+                Err(err) => err,
+            };
+
+            let next_attempt = attempt + 1;
+            let delay = self.backoff(next_attempt, rand::random::<f64>());
+            if !is_retryable(&err) || next_attempt > self.max_attempts || total_wait + delay > self.max_total_wait {
+                return (Err(err), attempt - 1);
+            }
+
+            warn!(?delay, next_attempt, "Request failed with a retryable error, retrying");
+            on_retry(RetryStatus {
+                attempt: next_attempt,
+                max_attempts: self.max_attempts,
+                delay,
+            });
+            tokio::time::sleep(delay).await;
+            total_wait += delay;
+            attempt = next_attempt;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::VecDeque;
+    use std::sync::Mutex;
+
+    use super::*;
+
+    #[derive(Debug, PartialEq)]
+    enum MockError {
+        Throttled,
+        Validation,
+    }
+
+    /// Stands in for the transport, handing out the queued responses in order.
+    struct MockTransport {
+        responses: Mutex<VecDeque<Result<&'static str, MockError>>>,
+        calls: Mutex<u32>,
+    }
+
+    impl MockTransport {
+        fn new(responses: Vec<Result<&'static str, MockError>>) -> Self {
+            Self {
+                responses: Mutex::new(responses.into()),
+                calls: Mutex::new(0),
+            }
+        }
+
+        async fn send(&self) -> Result<&'static str, MockError> {
+            *self.calls.lock().unwrap() += 1;
+            self.responses.lock().unwrap().pop_front().expect("no more responses")
+        }
+
+        fn calls(&self) -> u32 {
+            *self.calls.lock().unwrap()
+        }
+    }
+
+    fn policy() -> ThrottleRetryPolicy {
+        ThrottleRetryPolicy {
+            max_attempts: 3,
+            max_total_wait: Duration::from_secs(1),
+            base_delay: Duration::from_millis(2),
+            max_delay: Duration::from_millis(10),
+        }
+    }
+
+    fn is_throttled(err: &MockError) -> bool {
+        *err == MockError::Throttled
+    }
+
+    #[test]
+    fn test_backoff() {
+        let policy = ThrottleRetryPolicy::default();
+        assert_eq!(policy.backoff(2, 0.0), Duration::from_millis(500));
+        assert_eq!(policy.backoff(2, 1.0), Duration::from_secs(1));
+        assert_eq!(policy.backoff(3, 1.0), Duration::from_secs(2));
+        assert_eq!(policy.backoff(4, 0.0), Duration::from_secs(2));
+        // Capped by max_delay
+        assert_eq!(policy.backoff(30, 1.0), MAX_DELAY);
+    }
+
+    #[tokio::test]
+    async fn test_retries_until_success() {
+        let transport = MockTransport::new(vec![Err(MockError::Throttled), Err(MockError::Throttled), Ok("done")]);
+        let mut statuses = Vec::new();
+        let (res, retries) = policy()
+            .retry(is_throttled, || transport.send(), |status| statuses.push(status))
+            .await;
+        assert_eq!(res, Ok("done"));
+        assert_eq!(retries, 2);
+        assert_eq!(transport.calls(), 3);
+        assert_eq!(statuses.iter().map(|s| s.attempt).collect::<Vec<_>>(), vec![2, 3]);
+        assert!(statuses.iter().all(|s| s.max_attempts == 3));
+    }
+
+    #[tokio::test]
+    async fn test_gives_up_after_max_attempts() {
+        let transport = MockTransport::new(vec![
+            Err(MockError::Throttled),
+            Err(MockError::Throttled),
+            Err(MockError::Throttled),
+        ]);
+        let (res, retries) = policy().retry(is_throttled, || transport.send(), |_| {}).await;
+        assert_eq!(res, Err(MockError::Throttled));
+        assert_eq!(retries, 2);
+        assert_eq!(transport.calls(), 3);
+    }
+
+    #[tokio::test]
+    async fn test_non_retryable_fails_immediately() {
+        let transport = MockTransport::new(vec![Err(MockError::Validation), Ok("unreachable")]);
+        let (res, retries) = policy().retry(is_throttled, || transport.send(), |_| {}).await;
+        assert_eq!(res, Err(MockError::Validation));
+        assert_eq!(retries, 0);
+        assert_eq!(transport.calls(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_gives_up_after_max_total_wait() {
+        let policy = ThrottleRetryPolicy {
+            max_total_wait: Duration::ZERO,
+            ..policy()
+        };
+        let transport = MockTransport::new(vec![Err(MockError::Throttled), Ok("unreachable")]);
+        let (res, retries) = policy.retry(is_throttled, || transport.send(), |_| {}).await;
+        assert_eq!(res, Err(MockError::Throttled));
+        assert_eq!(retries, 0);
+    }
+
+    #[test]
+    fn test_is_retryable() {
+        assert!(ThrottleRetryPolicy::is_retryable(&ApiClientError::QuotaBreach {
+            message: "quota has reached its limit",
+            status_code: Some(429),
+        }));
+        assert!(!ThrottleRetryPolicy::is_retryable(
+            &ApiClientError::MonthlyLimitReached { status_code: Some(429) }
+        ));
+        assert!(!ThrottleRetryPolicy::is_retryable(
+            &ApiClientError::ContextWindowOverflow { status_code: Some(400) }
+        ));
+    }
+}
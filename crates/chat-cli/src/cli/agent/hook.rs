@@ -32,6 +32,30 @@ impl Display for HookTrigger {
     }
 }
 
+/// What happens when a hook fails or times out
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, Eq, PartialEq, JsonSchema, Hash)]
+#[serde(rename_all = "camelCase")]
+pub enum HookFailurePolicy {
+    /// Print the error the first time the hook fails in a session and continue without its output
+    #[default]
+    Warn,
+    /// Stop the session from starting for agentSpawn hooks, or the prompt from being sent for
+    /// userPromptSubmit hooks
+    Block,
+    /// Continue without its output and without printing anything
+    Ignore,
+}
+
+impl Display for HookFailurePolicy {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            HookFailurePolicy::Warn => write!(f, "warn"),
+            HookFailurePolicy::Block => write!(f, "block"),
+            HookFailurePolicy::Ignore => write!(f, "ignore"),
+        }
+    }
+}
+
 #[derive(Debug, Clone, Deserialize, Eq, PartialEq, Hash)]
 pub enum Source {
     Agent,
@@ -49,8 +73,9 @@ pub struct Hook {
     /// The command to run when the hook is triggered
     pub command: String,
 
-    /// Max time the hook can run before it throws a timeout error
-    #[serde(default = "Hook::default_timeout_ms")]
+    /// Max time the hook can run before it is killed, along with the processes it started, and
+    /// fails with a timeout error
+    #[serde(default = "Hook::default_timeout_ms", alias = "timeoutMs")]
     pub timeout_ms: u64,
 
     /// Max output size of the hook before it is truncated
@@ -58,9 +83,20 @@ pub struct Hook {
     pub max_output_size: usize,
 
     /// How long the hook output is cached before it will be executed again
-    #[serde(default = "Hook::default_cache_ttl_seconds")]
+    #[serde(default = "Hook::default_cache_ttl_seconds", alias = "cacheTtlSeconds")]
     pub cache_ttl_seconds: u64,
 
+    /// Whether the output of the hook is added to the context. When false, the hook is only run
+    /// for its side effects
+    #[serde(default = "Hook::default_inject_output", alias = "injectOutput")]
+    pub inject_output: bool,
+
+    /// What happens when the hook fails or times out: "warn" prints the error and continues,
+    /// "block" stops the session from starting (agentSpawn) or the prompt from being sent
+    /// (userPromptSubmit), and "ignore" continues silently
+    #[serde(default, alias = "onFailure")]
+    pub on_failure: HookFailurePolicy,
+
     #[schemars(skip)]
     #[serde(default, skip_serializing)]
     pub source: Source,
@@ -73,6 +109,8 @@ impl Hook {
             timeout_ms: Self::default_timeout_ms(),
             max_output_size: Self::default_max_output_size(),
             cache_ttl_seconds: Self::default_cache_ttl_seconds(),
+            inject_output: Self::default_inject_output(),
+            on_failure: HookFailurePolicy::default(),
             source,
         }
     }
@@ -88,4 +126,37 @@ impl Hook {
     fn default_cache_ttl_seconds() -> u64 {
         DEFAULT_CACHE_TTL_SECONDS
     }
+
+    fn default_inject_output() -> bool {
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    #[test]
+    fn test_deser_failure_policy() {
+        let hook = serde_json::from_value::<Hook>(json!({ "command": "git status" })).unwrap();
+        assert_eq!(hook.timeout_ms, DEFAULT_TIMEOUT_MS);
+        assert_eq!(hook.on_failure, HookFailurePolicy::Warn);
+
+        let hook = serde_json::from_value::<Hook>(json!({
+            "command": "sleep 60",
+            "timeoutMs": 500,
+            "onFailure": "block"
+        }))
+        .unwrap();
+        assert_eq!(hook.timeout_ms, 500);
+        assert_eq!(hook.on_failure, HookFailurePolicy::Block);
+
+        let hook = serde_json::from_value::<Hook>(json!({ "command": "true", "on_failure": "ignore" })).unwrap();
+        assert_eq!(hook.on_failure, HookFailurePolicy::Ignore);
+        assert_eq!(serde_json::to_value(&hook).unwrap()["on_failure"], "ignore");
+
+        assert!(serde_json::from_value::<Hook>(json!({ "command": "true", "on_failure": "retry" })).is_err());
+    }
 }
@@ -17,6 +17,33 @@ pub struct McpServerConfig {
     pub mcp_servers: HashMap<String, CustomToolConfig>,
 }
 
+/// Drops tools that more than one MCP server exposes with the same name and input schema, so
+/// that the model sees a single instance of them.
+#[derive(Clone, Serialize, Deserialize, Debug, Default, Eq, PartialEq, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct DedupeMcpTools {
+    /// Whether duplicate tools are dropped
+    #[serde(default)]
+    pub enabled: bool,
+    /// Servers whose instance of a duplicate tool is kept, most preferred first. Otherwise the
+    /// instance of the server that loaded first is kept
+    #[serde(default)]
+    pub prefer: Vec<String>,
+}
+
+impl DedupeMcpTools {
+    /// Whether the instance of a tool from `server_name` should replace the one already loaded
+    /// from `existing_server_name`.
+    pub fn prefers(&self, server_name: &str, existing_server_name: &str) -> bool {
+        let rank = |name: &str| self.prefer.iter().position(|preferred| preferred == name);
+        match (rank(server_name), rank(existing_server_name)) {
+            (Some(rank), Some(existing_rank)) => rank < existing_rank,
+            (Some(_), None) => true,
+            (None, _) => false,
+        }
+    }
+}
+
 impl McpServerConfig {
     pub async fn load_from_file(os: &Os, path: impl AsRef<Path>) -> eyre::Result<Self> {
         let contents = os.fs.read(path.as_ref()).await?;
@@ -0,0 +1,353 @@
+use std::borrow::Borrow;
+use std::collections::HashMap;
+use std::hash::Hash;
+
+use clap::ValueEnum;
+use serde_json::Value;
+use thiserror::Error;
+
+use super::Agent;
+use super::migrations::CURRENT_VERSION;
+
+/// How [Agent::merge] resolves a field that is set to different values in the two agents.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, ValueEnum)]
+pub enum MergePolicy {
+    /// Keep the value of the override agent
+    #[default]
+    PreferOverride,
+    /// Keep the value of the base agent
+    PreferBase,
+    /// Concatenate lists, such as hooks and arrays in tool settings. Other conflicts keep the value
+    /// of the override agent
+    Append,
+    /// Fail on any conflict
+    Error,
+}
+
+/// Fields that are set to different values in both agents, reported by [MergePolicy::Error].
+#[derive(Debug, Error)]
+#[error("The agents conflict on {}", .0.join(", "))]
+pub struct MergeConflictError(pub Vec<String>);
+
+impl Agent {
+    /// Combines two agents into one. `tools`, `allowedTools` and `resources` are always unioned,
+    /// and `toolsSettings` entries are merged key by key. Everything else that both agents set to
+    /// different values, including MCP servers with the same name, is resolved by `policy`.
+    ///
+    /// The merged agent is named after the override agent, or after the base agent with
+    /// [MergePolicy::PreferBase]. Agents should be merged in their "cold" state, as written in
+    /// their config.
+    pub fn merge(base: &Agent, r#override: &Agent, policy: MergePolicy) -> Result<Agent, MergeConflictError> {
+        let mut merger = Merger {
+            policy,
+            conflicts: Vec::new(),
+        };
+
+        let name = match policy {
+            MergePolicy::PreferBase => base.name.clone(),
+            _ => r#override.name.clone(),
+        };
+        let mut hooks = base.hooks.clone();
+        for (trigger, override_hooks) in &r#override.hooks {
+            let merged = match base.hooks.get(trigger) {
+                Some(base_hooks) if policy == MergePolicy::Append => union(base_hooks, override_hooks),
+                Some(base_hooks) => merger.pick(&format!("hooks.{trigger}"), base_hooks, override_hooks),
+                None => override_hooks.clone(),
+            };
+            hooks.insert(*trigger, merged);
+        }
+        let mut tools_settings = base.tools_settings.clone();
+        for (target, override_settings) in &r#override.tools_settings {
+            let merged = match base.tools_settings.get(target) {
+                Some(base_settings) => {
+                    merger.merge_json(&format!("toolsSettings.{}", **target), base_settings, override_settings)
+                },
+                None => override_settings.clone(),
+            };
+            tools_settings.insert(target.clone(), merged);
+        }
+
+        let merged = Agent {
+            schema: merger.pick("$schema", &base.schema, &r#override.schema),
+            name,
+            version: CURRENT_VERSION,
+            description: merger.pick_option("description", &base.description, &r#override.description),
+            prompt: merger.pick_option("prompt", &base.prompt, &r#override.prompt),
+            model: merger.pick_option("model", &base.model, &r#override.model),
+            prompt_variables: merger.merge_map("promptVariables", &base.prompt_variables, &r#override.prompt_variables),
+            mcp_servers: {
+                let mut mcp_servers = base.mcp_servers.clone();
+                mcp_servers.mcp_servers = merger.merge_map(
+                    "mcpServers",
+                    &base.mcp_servers.mcp_servers,
+                    &r#override.mcp_servers.mcp_servers,
+                );
+                mcp_servers
+            },
+            tools: union(&base.tools, &r#override.tools),
+            tool_aliases: merger.merge_map("toolAliases", &base.tool_aliases, &r#override.tool_aliases),
+            allowed_tools: base.allowed_tools.union(&r#override.allowed_tools).cloned().collect(),
+            resources: union(&base.resources, &r#override.resources),
+            max_resource_files: merger.pick_option(
+                "maxResourceFiles",
+                &base.max_resource_files,
+                &r#override.max_resource_files,
+            ),
+            max_resource_bytes: merger.pick_option(
+                "maxResourceBytes",
+                &base.max_resource_bytes,
+                &r#override.max_resource_bytes,
+            ),
+            hooks,
+            tools_settings,
+            use_legacy_mcp_json: merger.pick(
+                "useLegacyMcpJson",
+                &base.use_legacy_mcp_json,
+                &r#override.use_legacy_mcp_json,
+            ),
+            delegation_enabled: merger.pick(
+                "delegationEnabled",
+                &base.delegation_enabled,
+                &r#override.delegation_enabled,
+            ),
+            sanitize_tool_output: merger.pick(
+                "sanitizeToolOutput",
+                &base.sanitize_tool_output,
+                &r#override.sanitize_tool_output,
+            ),
+            dedupe_mcp_tools: merger.pick("dedupeMcpTools", &base.dedupe_mcp_tools, &r#override.dedupe_mcp_tools),
+            path: None,
+            raw_resources: None,
+        };
+
+        if merger.conflicts.is_empty() {
+            Ok(merged)
+        } else {
+            Err(MergeConflictError(merger.conflicts))
+        }
+    }
+}
+
+struct Merger {
+    policy: MergePolicy,
+    /// Fields found in conflict with [MergePolicy::Error]
+    conflicts: Vec<String>,
+}
+
+impl Merger {
+    /// Resolves a field that both agents set.
+    fn pick<T: PartialEq + Clone>(&mut self, field: &str, base: &T, r#override: &T) -> T {
+        if base == r#override {
+            return base.clone();
+        }
+        match self.policy {
+            MergePolicy::PreferBase => base.clone(),
+            MergePolicy::PreferOverride | MergePolicy::Append => r#override.clone(),
+            MergePolicy::Error => {
+                self.conflicts.push(field.to_string());
+                r#override.clone()
+            },
+        }
+    }
+
+    fn pick_option<T: PartialEq + Clone>(
+        &mut self,
+        field: &str,
+        base: &Option<T>,
+        r#override: &Option<T>,
+    ) -> Option<T> {
+        match (base, r#override) {
+            (Some(base), Some(r#override)) => Some(self.pick(field, base, r#override)),
+            _ => r#override.clone().or_else(|| base.clone()),
+        }
+    }
+
+    fn merge_map<K, V>(&mut self, field: &str, base: &HashMap<K, V>, r#override: &HashMap<K, V>) -> HashMap<K, V>
+    where
+        K: Eq + Hash + Clone + Borrow<str>,
+        V: PartialEq + Clone,
+    {
+        let mut merged = base.clone();
+        for (key, override_value) in r#override {
+            let value = match base.get(key) {
+                Some(base_value) => self.pick(&format!("{field}.{}", key.borrow()), base_value, override_value),
+                None => override_value.clone(),
+            };
+            merged.insert(key.clone(), value);
+        }
+        merged
+    }
+
+    /// Deep merges JSON objects. Arrays are concatenated with [MergePolicy::Append], any other
+    /// values that differ are resolved like fields.
+    fn merge_json(&mut self, field: &str, base: &Value, r#override: &Value) -> Value {
+        match (base, r#override) {
+            (Value::Object(base_object), Value::Object(override_object)) => {
+                let mut merged = base_object.clone();
+                for (key, override_value) in override_object {
+                    let value = match base_object.get(key) {
+                        Some(base_value) => self.merge_json(&format!("{field}.{key}"), base_value, override_value),
+                        None => override_value.clone(),
+                    };
+                    merged.insert(key.clone(), value);
+                }
+                Value::Object(merged)
+            },
+            (Value::Array(base_array), Value::Array(override_array)) if self.policy == MergePolicy::Append => {
+                Value::Array(union(base_array, override_array))
+            },
+            _ => self.pick(field, base, r#override),
+        }
+    }
+}
+
+/// The items of `base` followed by those of `other` that are not in `base`.
+fn union<T: PartialEq + Clone>(base: &[T], other: &[T]) -> Vec<T> {
+    let mut merged = base.to_vec();
+    for item in other {
+        if !merged.contains(item) {
+            merged.push(item.clone());
+        }
+    }
+    merged
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+    use crate::cli::agent::hook::HookTrigger;
+
+    fn agent(value: Value) -> Agent {
+        serde_json::from_value(value).unwrap()
+    }
+
+    fn base_agent() -> Agent {
+        agent(json!({
+            "name": "base",
+            "description": "Base agent",
+            "prompt": "Be brief",
+            "mcpServers": {
+                "git": { "command": "git-mcp" },
+                "fetch": { "command": "fetch-mcp" }
+            },
+            "tools": ["fs_read", "@git"],
+            "allowedTools": ["fs_read"],
+            "resources": ["file://README.md"],
+            "hooks": {
+                "agentSpawn": [{ "command": "git status" }]
+            },
+            "toolsSettings": {
+                "fs_write": { "allowedPaths": ["src/**"], "deniedExtensions": ["env"] }
+            }
+        }))
+    }
+
+    fn override_agent() -> Agent {
+        agent(json!({
+            "name": "override",
+            "prompt": "Be thorough",
+            "model": "claude-sonnet-4",
+            "mcpServers": {
+                "git": { "command": "other-git-mcp" },
+                "docs": { "command": "docs-mcp" }
+            },
+            "tools": ["@git", "fs_write"],
+            "allowedTools": ["fs_write"],
+            "resources": ["file://README.md", "file://docs/**/*.md"],
+            "hooks": {
+                "agentSpawn": [{ "command": "git log -1" }]
+            },
+            "toolsSettings": {
+                "fs_write": { "allowedPaths": ["docs/**"] },
+                "fs_read": { "allowedPaths": ["~/notes"] }
+            }
+        }))
+    }
+
+    #[test]
+    fn test_merge_prefer_override() {
+        let merged = Agent::merge(&base_agent(), &override_agent(), MergePolicy::PreferOverride).unwrap();
+
+        assert_eq!(merged.name, "override");
+        // Fields only set in one agent are kept
+        assert_eq!(merged.description.as_deref(), Some("Base agent"));
+        assert_eq!(merged.prompt.as_deref(), Some("Be thorough"));
+        assert_eq!(merged.model.as_deref(), Some("claude-sonnet-4"));
+        assert_eq!(merged.tools, vec!["fs_read", "@git", "fs_write"]);
+        assert_eq!(merged.allowed_tools.len(), 2);
+        assert_eq!(merged.resources.len(), 2);
+
+        let servers = &merged.mcp_servers.mcp_servers;
+        assert_eq!(servers.len(), 3);
+        assert_eq!(servers["git"].command, "other-git-mcp");
+        assert_eq!(servers["fetch"].command, "fetch-mcp");
+
+        assert_eq!(merged.hooks[&HookTrigger::AgentSpawn].len(), 1);
+        assert_eq!(merged.hooks[&HookTrigger::AgentSpawn][0].command, "git log -1");
+
+        assert_eq!(
+            merged.tools_settings["fs_write"],
+            json!({ "allowedPaths": ["docs/**"], "deniedExtensions": ["env"] })
+        );
+        assert_eq!(merged.tools_settings["fs_read"], json!({ "allowedPaths": ["~/notes"] }));
+    }
+
+    #[test]
+    fn test_merge_prefer_base() {
+        let merged = Agent::merge(&base_agent(), &override_agent(), MergePolicy::PreferBase).unwrap();
+
+        assert_eq!(merged.name, "base");
+        assert_eq!(merged.prompt.as_deref(), Some("Be brief"));
+        assert_eq!(merged.mcp_servers.mcp_servers["git"].command, "git-mcp");
+        assert_eq!(merged.mcp_servers.mcp_servers["docs"].command, "docs-mcp");
+        assert_eq!(merged.hooks[&HookTrigger::AgentSpawn][0].command, "git status");
+        assert_eq!(
+            merged.tools_settings["fs_write"],
+            json!({ "allowedPaths": ["src/**"], "deniedExtensions": ["env"] })
+        );
+        // Collections are unioned whatever the policy
+        assert_eq!(merged.tools, vec!["fs_read", "@git", "fs_write"]);
+    }
+
+    #[test]
+    fn test_merge_append() {
+        let merged = Agent::merge(&base_agent(), &override_agent(), MergePolicy::Append).unwrap();
+
+        let commands = merged.hooks[&HookTrigger::AgentSpawn]
+            .iter()
+            .map(|hook| hook.command.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(commands, vec!["git status", "git log -1"]);
+        assert_eq!(
+            merged.tools_settings["fs_write"]["allowedPaths"],
+            json!(["src/**", "docs/**"])
+        );
+        // Values that cannot be appended keep those of the override agent
+        assert_eq!(merged.prompt.as_deref(), Some("Be thorough"));
+        assert_eq!(merged.mcp_servers.mcp_servers["git"].command, "other-git-mcp");
+    }
+
+    #[test]
+    fn test_merge_error() {
+        let err = Agent::merge(&base_agent(), &override_agent(), MergePolicy::Error).unwrap_err();
+        let mut conflicts = err.0;
+        conflicts.sort();
+        assert_eq!(conflicts, vec![
+            "hooks.agentSpawn",
+            "mcpServers.git",
+            "prompt",
+            "toolsSettings.fs_write.allowedPaths",
+        ]);
+
+        // Agents that only add to each other merge without conflicts
+        let mut r#override = override_agent();
+        r#override.prompt = None;
+        r#override.hooks.clear();
+        r#override.mcp_servers.mcp_servers.remove("git");
+        r#override.tools_settings.remove("fs_write");
+        let merged = Agent::merge(&base_agent(), &r#override, MergePolicy::Error).unwrap();
+        assert_eq!(merged.mcp_servers.mcp_servers.len(), 3);
+    }
+}
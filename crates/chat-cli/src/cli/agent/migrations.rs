@@ -0,0 +1,114 @@
+//! Upgrades agent configs written for an older version of the [super::Agent] format.
+//!
+//! Every breaking change to the format bumps [CURRENT_VERSION] and registers a [Migration] in
+//! [MIGRATIONS] that rewrites a config of the previous version into the new shape. Configs are
+//! migrated as raw JSON, before they are deserialized, so that a migration can rename or
+//! restructure fields the current [super::Agent] no longer accepts.
+
+use serde_json::Value;
+
+/// Rewrites a config of one version into the shape of the next version.
+pub type Migration = fn(Value) -> Value;
+
+/// Migrations in the order they are applied. The migration at index `i` upgrades a config from
+/// version `i + 1` to version `i + 2`.
+pub const MIGRATIONS: &[Migration] = &[];
+
+/// Version of the agent config format this build reads and writes.
+pub const CURRENT_VERSION: u32 = MIGRATIONS.len() as u32 + 1;
+
+/// Version of configs that do not have a `version` field, which were all written before the
+/// field was introduced.
+pub const fn default_version() -> u32 {
+    1
+}
+
+/// Returns the version a config was written for.
+pub fn config_version(value: &Value) -> u32 {
+    value
+        .get("version")
+        .and_then(Value::as_u64)
+        .and_then(|version| u32::try_from(version).ok())
+        .unwrap_or(default_version())
+}
+
+/// Upgrades `value` to [CURRENT_VERSION]. Fails with the version of the config if it was written
+/// for a newer version of the format than this build supports.
+pub fn migrate(value: Value) -> Result<Value, u32> {
+    migrate_with(value, MIGRATIONS)
+}
+
+/// Like [migrate], with `migrations` in place of [MIGRATIONS].
+pub fn migrate_with(mut value: Value, migrations: &[Migration]) -> Result<Value, u32> {
+    let current_version = migrations.len() as u32 + 1;
+    let version = config_version(&value).max(1);
+    if version > current_version {
+        return Err(version);
+    }
+    if version == current_version {
+        return Ok(value);
+    }
+
+    for migration in &migrations[version as usize - 1..] {
+        value = migration(value);
+    }
+    if let Some(map) = value.as_object_mut() {
+        map.insert("version".to_string(), current_version.into());
+    }
+    Ok(value)
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    fn rename_prompt(mut value: Value) -> Value {
+        if let Some(prompt) = value.as_object_mut().and_then(|map| map.remove("systemPrompt")) {
+            value["prompt"] = prompt;
+        }
+        value
+    }
+
+    fn add_description(mut value: Value) -> Value {
+        value["description"] = json!("migrated");
+        value
+    }
+
+    #[test]
+    fn test_migrate_with() {
+        let migrations: &[Migration] = &[rename_prompt, add_description];
+        let v1 = json!({ "name": "agent", "systemPrompt": "be brief" });
+
+        assert_eq!(
+            migrate_with(v1.clone(), migrations).unwrap(),
+            json!({ "name": "agent", "prompt": "be brief", "description": "migrated", "version": 3 })
+        );
+
+        // Only the migrations after the version of the config are applied
+        let v2 = json!({ "name": "agent", "systemPrompt": "be brief", "version": 2 });
+        assert_eq!(
+            migrate_with(v2, migrations).unwrap(),
+            json!({ "name": "agent", "systemPrompt": "be brief", "description": "migrated", "version": 3 })
+        );
+
+        // Configs of the current version are left as they are
+        let v3 = json!({ "name": "agent", "version": 3 });
+        assert_eq!(migrate_with(v3.clone(), migrations).unwrap(), v3);
+
+        // Configs of a newer version cannot be read
+        assert_eq!(
+            migrate_with(json!({ "name": "agent", "version": 4 }), migrations),
+            Err(4)
+        );
+        assert_eq!(migrate_with(json!({ "name": "agent", "version": 2 }), &[]), Err(2));
+    }
+
+    #[test]
+    fn test_migrate_current_version() {
+        let config = json!({ "name": "agent", "version": CURRENT_VERSION });
+        assert_eq!(migrate(config.clone()).unwrap(), config);
+        assert_eq!(config_version(&json!({ "name": "agent" })), 1);
+    }
+}
@@ -1,7 +1,11 @@
+mod bundle;
 pub mod hook;
 mod legacy;
 mod mcp_config;
+mod merge;
+mod migrations;
 mod root_command_args;
+mod template;
 mod wrapper_types;
 
 use std::borrow::Borrow;
@@ -12,12 +16,14 @@ use std::collections::{
 use std::ffi::OsStr;
 use std::io::{
     self,
+    IsTerminal,
     Write,
 };
 use std::path::{
     Path,
     PathBuf,
 };
+use std::sync::LazyLock;
 
 use crossterm::style::{
     Color,
@@ -29,7 +35,11 @@ use crossterm::{
     style,
 };
 use eyre::bail;
-pub use mcp_config::McpServerConfig;
+pub use mcp_config::{
+    DedupeMcpTools,
+    McpServerConfig,
+};
+use regex::Regex;
 pub use root_command_args::*;
 use schemars::{
     JsonSchema,
@@ -39,6 +49,7 @@ use serde::{
     Deserialize,
     Serialize,
 };
+pub use template::AgentTemplate;
 use thiserror::Error;
 use tokio::fs::ReadDir;
 use tracing::{
@@ -52,13 +63,18 @@ pub use wrapper_types::{
     ToolSettingTarget,
     alias_schema,
     tool_settings_schema,
+    tools_settings_root_schema,
 };
 
+use super::chat::cli::suggestions::levenshtein;
 use super::chat::tools::{
     DEFAULT_APPROVE,
     NATIVE_TOOLS,
     ToolOrigin,
+    plain_output,
 };
+use super::chat::util::sanitize::SanitizeLevel;
+use super::chat::util::ui::glyph;
 use crate::cli::agent::hook::{
     Hook,
     HookTrigger,
@@ -73,6 +89,16 @@ use crate::util::{
 
 pub const DEFAULT_AGENT_NAME: &str = "q_cli_default";
 
+/// Name given to the agent read with `q chat --agent-from-stdin`.
+pub const STDIN_AGENT_NAME: &str = "stdin";
+
+/// Appended to every agent's prompt when tools are disabled with `q chat --no-tools`.
+const NO_TOOLS_PROMPT: &str = "Tools are disabled for this session. No tools are available, so answer \
+                               using only the conversation and do not attempt to call any tools.";
+
+/// Agent names further than this from a name that was not found are not suggested.
+const MAX_AGENT_SUGGESTION_DISTANCE: usize = 2;
+
 #[derive(Debug, Error)]
 pub enum AgentConfigError {
     #[error("Json supplied at {} is invalid: {}", path.display(), error)]
@@ -90,6 +116,11 @@ pub enum AgentConfigError {
     Io(#[from] std::io::Error),
     #[error("Failed to parse legacy mcp config: {0}")]
     BadLegacyMcpConfig(#[from] eyre::Report),
+    #[error(
+        "Agent config at {} is version {version}, but this version of Q CLI only supports up to version {}. Update Q CLI to use it",
+        path.display(), migrations::CURRENT_VERSION
+    )]
+    UnsupportedVersion { version: u32, path: PathBuf },
 }
 
 /// An [Agent] is a declarative way of configuring a given instance of q chat. Currently, it is
@@ -124,6 +155,10 @@ pub struct Agent {
     pub schema: String,
     /// Name of the agent
     pub name: String,
+    /// Version of the agent config format. Configs written for an older version are upgraded
+    /// when they are loaded
+    #[serde(default = "migrations::default_version")]
+    pub version: u32,
     /// This field is not model facing and is mostly here for users to discern between agents
     #[serde(default)]
     pub description: Option<String>,
@@ -131,6 +166,14 @@ pub struct Agent {
     /// agent. This should be seen as the same category of context as a system prompt.
     #[serde(default)]
     pub prompt: Option<String>,
+    /// Model used while the agent is active, by name or id. If the model is not available, the
+    /// default model is used instead
+    #[serde(default)]
+    pub model: Option<String>,
+    /// Default values of the `{{variable}}` placeholders in the prompt. Variables without a
+    /// default are asked for when the chat session starts, unless given with `--var name=value`
+    #[serde(default)]
+    pub prompt_variables: HashMap<String, String>,
     /// Configuration for Model Context Protocol (MCP) servers
     #[serde(default)]
     pub mcp_servers: McpServerConfig,
@@ -145,9 +188,18 @@ pub struct Agent {
     /// List of tools the agent is explicitly allowed to use
     #[serde(default)]
     pub allowed_tools: HashSet<String>,
-    /// Files to include in the agent's context
+    /// Files to include in the agent's context. Prefix a resource with "!" (e.g.
+    /// "!file://**/*.env") to exclude the files matched by the resources before it. Environment
+    /// variables written as "${VAR}" are expanded
     #[serde(default)]
     pub resources: Vec<ResourcePath>,
+    /// Maximum number of files the resources may add to the context. Defaults to 50
+    #[serde(default)]
+    pub max_resource_files: Option<usize>,
+    /// Maximum total size in bytes of the files the resources may add to the context. Defaults to
+    /// 1 MiB
+    #[serde(default)]
+    pub max_resource_bytes: Option<usize>,
     /// Commands to run when a chat session is created
     #[serde(default)]
     pub hooks: HashMap<HookTrigger, Vec<Hook>>,
@@ -161,8 +213,23 @@ pub struct Agent {
     /// you configure in the mcpServers field in this config
     #[serde(default)]
     pub use_legacy_mcp_json: bool,
+    /// Whether the agent may hand sub-tasks to other agents with the agent_delegate tool
+    #[serde(default)]
+    pub delegation_enabled: bool,
+    /// How tool output is sanitized before it is sent to the model. "basic" strips ANSI escape
+    /// sequences and zero-width characters, "strict" additionally quotes output that contains
+    /// instruction overrides or fake role markers
+    #[serde(default)]
+    pub sanitize_tool_output: SanitizeLevel,
+    /// Drops tools that more than one MCP server exposes with the same name and input schema.
+    /// Set "enabled" to turn it on, and list servers in "prefer" to choose whose instance is kept
+    #[serde(default)]
+    pub dedupe_mcp_tools: DedupeMcpTools,
     #[serde(skip)]
     pub path: Option<PathBuf>,
+    /// The resources as written in the config, before environment variables were expanded
+    #[serde(skip)]
+    pub raw_resources: Option<Vec<ResourcePath>>,
 }
 
 impl Default for Agent {
@@ -170,8 +237,11 @@ impl Default for Agent {
         Self {
             schema: default_schema(),
             name: DEFAULT_AGENT_NAME.to_string(),
+            version: migrations::CURRENT_VERSION,
             description: Some("Default agent".to_string()),
             prompt: Default::default(),
+            model: Default::default(),
+            prompt_variables: Default::default(),
             mcp_servers: Default::default(),
             tools: vec!["*".to_string()],
             tool_aliases: Default::default(),
@@ -185,10 +255,16 @@ impl Default for Agent {
                 .into_iter()
                 .map(Into::into)
                 .collect::<Vec<_>>(),
+            max_resource_files: Default::default(),
+            max_resource_bytes: Default::default(),
             hooks: Default::default(),
             tools_settings: Default::default(),
             use_legacy_mcp_json: true,
+            delegation_enabled: false,
+            sanitize_tool_output: Default::default(),
+            dedupe_mcp_tools: Default::default(),
             path: None,
+            raw_resources: None,
         }
     }
 }
@@ -198,18 +274,29 @@ impl Agent {
     /// Practically this means reverting some fields back to their original values as they were
     /// written in the config.
     fn freeze(&mut self) {
-        let Self { mcp_servers, .. } = self;
+        let Self {
+            mcp_servers,
+            resources,
+            raw_resources,
+            ..
+        } = self;
 
         mcp_servers
             .mcp_servers
             .retain(|_name, config| !config.is_from_legacy_mcp_json);
+
+        if let Some(raw_resources) = raw_resources.take() {
+            *resources = raw_resources;
+        }
     }
 
     /// This function mutates the agent to a state that is usable for runtime.
     /// Practically this means to convert some of the fields value to their usable counterpart.
-    /// For example, converting the mcp array to actual mcp config and populate the agent file path.
+    /// For example, converting the mcp array to actual mcp config, expanding environment variables
+    /// in resources and populating the agent file path.
     fn thaw(
         &mut self,
+        os: &Os,
         path: &Path,
         legacy_mcp_config: Option<&McpServerConfig>,
         output: &mut impl Write,
@@ -218,6 +305,15 @@ impl Agent {
 
         self.path = Some(path.to_path_buf());
 
+        let expanded = self
+            .resources
+            .iter()
+            .map(|resource| expand_resource_env_vars(os, resource, output))
+            .collect::<Vec<_>>();
+        if expanded != self.resources {
+            self.raw_resources = Some(std::mem::replace(&mut self.resources, expanded));
+        }
+
         if let (true, Some(legacy_mcp_config)) = (self.use_legacy_mcp_json, legacy_mcp_config) {
             for (name, legacy_server) in &legacy_mcp_config.mcp_servers {
                 if mcp_servers.mcp_servers.contains_key(name) {
@@ -276,11 +372,31 @@ impl Agent {
         Ok(serde_json::to_string_pretty(&agent_clone)?)
     }
 
-    /// Retrieves an agent by name. It does so via first seeking the given agent under local dir,
-    /// and falling back to global dir if it does not exist in local.
+    /// Serializes the agent as it is used at runtime. Unlike [Self::to_str_pretty], the agent is
+    /// not frozen, so servers merged in from the legacy mcp.json are kept and resources are shown
+    /// with their environment variables expanded. The path the agent was loaded from is added as
+    /// `path`.
+    pub fn to_str_pretty_resolved(&self) -> eyre::Result<String> {
+        let mut value = serde_json::to_value(self)?;
+        if let (Some(map), Some(path)) = (value.as_object_mut(), &self.path) {
+            map.insert("path".to_string(), serde_json::json!(path));
+        }
+        Ok(serde_json::to_string_pretty(&value)?)
+    }
+
+    /// Retrieves an agent by name. It does so via first seeking the given agent under the
+    /// directories from `--agent-dir` and `QCLI_AGENT_PATH`, then under local dir, and falling
+    /// back to global dir if it does not exist in either.
     pub async fn get_agent_by_name(os: &Os, agent_name: &str) -> eyre::Result<(Agent, PathBuf)> {
         let config_path: Result<PathBuf, PathBuf> = 'config: {
-            // local first, and then fall back to looking at global
+            for dir in directories::chat_extra_agent_dirs(os) {
+                let config_path = dir.join(format!("{agent_name}.json"));
+                if os.fs.exists(&config_path) {
+                    break 'config Ok(config_path);
+                }
+            }
+
+            // local next, and then fall back to looking at global
             let local_config_dir = directories::chat_local_agent_dir(os)?.join(format!("{agent_name}.json"));
             if os.fs.exists(&local_config_dir) {
                 break 'config Ok(local_config_dir);
@@ -297,20 +413,45 @@ impl Agent {
         match config_path {
             Ok(config_path) => {
                 let content = os.fs.read(&config_path).await?;
-                let mut agent = serde_json::from_slice::<Agent>(&content)?;
+                let mut agent = Agent::parse(&content, &config_path)?;
                 let legacy_mcp_config = if agent.use_legacy_mcp_json {
                     load_legacy_mcp_config(os).await.unwrap_or(None)
                 } else {
                     None
                 };
                 let mut stderr = std::io::stderr();
-                agent.thaw(&config_path, legacy_mcp_config.as_ref(), &mut stderr)?;
+                agent.thaw(os, &config_path, legacy_mcp_config.as_ref(), &mut stderr)?;
                 Ok((agent, config_path))
             },
             _ => bail!("Agent {agent_name} does not exist"),
         }
     }
 
+    /// Deserializes the agent config in `content`, read from `path`. Configs written for an older
+    /// version of the format are migrated to [migrations::CURRENT_VERSION] first.
+    pub fn parse(content: &[u8], path: &Path) -> Result<Agent, AgentConfigError> {
+        Self::parse_with(content, path, migrations::MIGRATIONS)
+    }
+
+    /// Like [Agent::parse], with `steps` in place of [migrations::MIGRATIONS].
+    fn parse_with(content: &[u8], path: &Path, steps: &[migrations::Migration]) -> Result<Agent, AgentConfigError> {
+        let invalid_json = |error| AgentConfigError::InvalidJson {
+            error,
+            path: path.to_path_buf(),
+        };
+        let value = serde_json::from_slice::<serde_json::Value>(content).map_err(invalid_json)?;
+        if migrations::config_version(&value) == steps.len() as u32 + 1 {
+            // Deserializing the content directly gives errors with line numbers
+            return serde_json::from_slice::<Agent>(content).map_err(invalid_json);
+        }
+
+        let value = migrations::migrate_with(value, steps).map_err(|version| AgentConfigError::UnsupportedVersion {
+            version,
+            path: path.to_path_buf(),
+        })?;
+        serde_json::from_value::<Agent>(value).map_err(invalid_json)
+    }
+
     pub async fn load(
         os: &Os,
         agent_path: impl AsRef<Path>,
@@ -319,10 +460,7 @@ impl Agent {
         output: &mut impl Write,
     ) -> Result<Agent, AgentConfigError> {
         let content = os.fs.read(&agent_path).await?;
-        let mut agent = serde_json::from_slice::<Agent>(&content).map_err(|e| AgentConfigError::InvalidJson {
-            error: e,
-            path: agent_path.as_ref().to_path_buf(),
-        })?;
+        let mut agent = Agent::parse(&content, agent_path.as_ref())?;
 
         if mcp_enabled {
             if agent.use_legacy_mcp_json && legacy_mcp_config.is_none() {
@@ -331,12 +469,54 @@ impl Agent {
                     legacy_mcp_config.replace(config);
                 }
             }
-            agent.thaw(agent_path.as_ref(), legacy_mcp_config.as_ref(), output)?;
+            agent.thaw(os, agent_path.as_ref(), legacy_mcp_config.as_ref(), output)?;
         } else {
             agent.clear_mcp_configs();
             // Thaw the agent with empty MCP config to finalize normalization.
-            agent.thaw(agent_path.as_ref(), None, output)?;
+            agent.thaw(os, agent_path.as_ref(), None, output)?;
+        }
+        Ok(agent)
+    }
+
+    /// Loads an agent from a config that is not backed by a file, such as one piped through
+    /// stdin. The config is validated against the agent schema before it is deserialized. The
+    /// agent is given the name `name`, whatever the config says, and has no [Agent::path].
+    pub async fn load_ephemeral(
+        os: &Os,
+        name: &str,
+        content: &[u8],
+        mcp_enabled: bool,
+        output: &mut impl Write,
+    ) -> Result<Agent, AgentConfigError> {
+        let invalid_json = |error| AgentConfigError::InvalidJson {
+            error,
+            path: PathBuf::from(name),
+        };
+        let value = serde_json::from_slice::<serde_json::Value>(content).map_err(invalid_json)?;
+        let mut value = migrations::migrate(value).map_err(|version| AgentConfigError::UnsupportedVersion {
+            version,
+            path: PathBuf::from(name),
+        })?;
+        if let Some(map) = value.as_object_mut() {
+            map.insert("name".to_string(), serde_json::json!(name));
+        }
+        if let Ok(schema) = serde_json::to_value(schema_for!(Agent)) {
+            jsonschema::validate(&schema, &value).map_err(|e| Box::new(e.to_owned()))?;
+        }
+        let mut agent = serde_json::from_value::<Agent>(value).map_err(invalid_json)?;
+
+        if mcp_enabled {
+            let legacy_mcp_config = if agent.use_legacy_mcp_json {
+                load_legacy_mcp_config(os).await.unwrap_or(None)
+            } else {
+                None
+            };
+            agent.thaw(os, Path::new(name), legacy_mcp_config.as_ref(), output)?;
+        } else {
+            agent.clear_mcp_configs();
+            agent.thaw(os, Path::new(name), None, output)?;
         }
+        agent.path = None;
         Ok(agent)
     }
 
@@ -362,6 +542,38 @@ impl Agent {
         self.tools_settings
             .retain(|target, _| !is_mcp_tool_ref(&target.to_string()));
     }
+
+    /// The directory of the config file the agent was loaded from. Path patterns in the tool
+    /// settings that start with `./` are relative to it.
+    pub fn config_dir(&self) -> Option<&Path> {
+        self.path.as_deref().and_then(Path::parent)
+    }
+
+    /// Names of the `{{variable}}` placeholders in the prompt, in the order they first appear.
+    pub fn prompt_variable_names(&self) -> Vec<String> {
+        let mut names = Vec::new();
+        for caps in PROMPT_VARIABLE.captures_iter(self.prompt.as_deref().unwrap_or_default()) {
+            let name = caps[1].to_string();
+            if !names.contains(&name) {
+                names.push(name);
+            }
+        }
+        names
+    }
+
+    /// Returns the prompt with its `{{variable}}` placeholders replaced by `values`, falling back
+    /// to the defaults in `promptVariables`. Placeholders without any value are left as they are.
+    pub fn expanded_prompt(&self, values: &HashMap<String, String>) -> Option<String> {
+        let prompt = self.prompt.as_ref()?;
+        let expanded = PROMPT_VARIABLE.replace_all(prompt, |caps: &regex::Captures<'_>| {
+            values
+                .get(&caps[1])
+                .or(self.prompt_variables.get(&caps[1]))
+                .cloned()
+                .unwrap_or_else(|| caps[0].to_string())
+        });
+        Some(expanded.into_owned())
+    }
 }
 
 /// Result of evaluating tool permissions, indicating whether a tool should be allowed,
@@ -384,9 +596,31 @@ pub struct Agents {
     /// Agent name.
     pub active_idx: String,
     pub trust_all_tools: bool,
+    /// Set by `q chat --no-tools`. See [Agents::disable_tools].
+    pub tools_disabled: bool,
 }
 
 impl Agents {
+    /// Removes tool access from every loaded agent for the rest of the session, so that swapping
+    /// agents does not bring tools back. MCP servers are dropped as well since none of their tools
+    /// could be used, and each agent's prompt tells the model that no tools are available.
+    pub fn disable_tools(&mut self) {
+        if self.tools_disabled {
+            return;
+        }
+        self.tools_disabled = true;
+        self.agents.values_mut().for_each(remove_tools);
+    }
+
+    /// Adds an agent loaded after startup under `name`, replacing a loaded agent with the same
+    /// name. Tools are removed from it if they were disabled for the session.
+    pub fn insert(&mut self, name: String, mut agent: Agent) {
+        if self.tools_disabled {
+            remove_tools(&mut agent);
+        }
+        self.agents.insert(name, agent);
+    }
+
     /// This function assumes the relevant transformation to the tool names have been done:
     /// - model tool name -> host tool name
     /// - custom tool namespacing
@@ -425,21 +659,25 @@ impl Agents {
 
     /// This function does a number of things in the following order:
     /// 1. Migrates old profiles if applicable
-    /// 2. Loads local agents
-    /// 3. Loads global agents
-    /// 4. Resolve agent conflicts and merge the two sets of agents
-    /// 5. Validates the active agent config and surfaces error to output accordingly
+    /// 2. Loads agents from the directories given by `--agent-dir` and `QCLI_AGENT_PATH`
+    /// 3. Loads local agents
+    /// 4. Loads global agents
+    /// 5. Resolve agent conflicts and merge the sets of agents, the first one found winning
+    /// 6. Validates the active agent config and surfaces error to output accordingly
     ///
     /// # Arguments
     /// * `os` - Operating system interface for file system operations and database access
     /// * `agent_name` - Optional specific agent name to activate; if None, falls back to default
     ///   agent selection
     /// * `skip_migration` - If true, skips migration of old profiles to new format
+    /// * `interactive` - If true, asks whether to use a similarly named agent when `agent_name` is
+    ///   not found
     /// * `output` - Writer for outputting warnings, errors, and status messages during loading
     pub async fn load(
         os: &mut Os,
         agent_name: Option<&str>,
         skip_migration: bool,
+        interactive: bool,
         output: &mut impl Write,
         mcp_enabled: bool,
     ) -> (Self, AgentsLoadMetadata) {
@@ -448,7 +686,7 @@ impl Agents {
                 output,
                 style::SetForegroundColor(Color::Yellow),
                 style::Print("\n"),
-                style::Print("⚠️  WARNING: "),
+                style::Print(glyph(plain_output(os), "⚠️  WARNING: ", "WARNING: ")),
                 style::SetForegroundColor(Color::Reset),
                 style::Print("MCP functionality has been disabled by your administrator.\n\n"),
             );
@@ -481,6 +719,46 @@ impl Agents {
 
         let mut global_mcp_config = None::<McpServerConfig>;
 
+        let mut extra_agents = Vec::<Agent>::new();
+        for dir in directories::chat_extra_agent_dirs(os) {
+            let files = match os.fs.read_dir(&dir).await {
+                Ok(files) => files,
+                Err(e) => {
+                    let _ = queue!(
+                        output,
+                        style::SetForegroundColor(Color::Yellow),
+                        style::Print("WARNING: "),
+                        style::ResetColor,
+                        style::Print(format!("Skipping agent directory {}: {e}\n", dir.display())),
+                    );
+                    continue;
+                },
+            };
+
+            let results = load_agents_from_entries(files, os, &mut global_mcp_config, mcp_enabled, output).await;
+            for result in results {
+                match result {
+                    Ok(agent) => match extra_agents.iter().find(|a| a.name == agent.name) {
+                        Some(first) => {
+                            let _ = queue_agent_conflict(output, &agent.name, &version_from_dir(first));
+                        },
+                        None => extra_agents.push(agent),
+                    },
+                    Err(e) => {
+                        load_metadata.load_failed_count += 1;
+                        let _ = queue!(
+                            output,
+                            style::SetForegroundColor(Color::Red),
+                            style::Print("Error: "),
+                            style::ResetColor,
+                            style::Print(e),
+                            style::Print("\n"),
+                        );
+                    },
+                }
+            }
+        }
+
         let mut local_agents = 'local: {
             // We could be launching from the home dir, in which case the global and local agents
             // are the same set of agents. If that is the case, we simply skip this.
@@ -609,30 +887,37 @@ impl Agents {
             };
         }
 
+        // If there is a naming conflict for agents, we retain the instance from the extra
+        // directories first, and the local instance second
+        let extra_dirs = extra_agents
+            .iter()
+            .map(|a| (a.name.clone(), version_from_dir(a)))
+            .collect::<HashMap<String, String>>();
+        local_agents.retain(|a| match extra_dirs.get(&a.name) {
+            Some(version) => {
+                let _ = queue_agent_conflict(output, &a.name, version);
+                false
+            },
+            None => true,
+        });
+
         let local_names = local_agents.iter().map(|a| a.name.as_str()).collect::<HashSet<&str>>();
         global_agents.retain(|a| {
-            // If there is a naming conflict for agents, we would retain the local instance
             let name = a.name.as_str();
-            if local_names.contains(name) {
-                let _ = queue!(
-                    output,
-                    style::SetForegroundColor(style::Color::Yellow),
-                    style::Print("WARNING: "),
-                    style::ResetColor,
-                    style::Print("Agent conflict for "),
-                    style::SetForegroundColor(style::Color::Green),
-                    style::Print(name),
-                    style::ResetColor,
-                    style::Print(". Using workspace version.\n")
-                );
+            if let Some(version) = extra_dirs.get(name) {
+                let _ = queue_agent_conflict(output, name, version);
+                false
+            } else if local_names.contains(name) {
+                let _ = queue_agent_conflict(output, name, "workspace version");
                 false
             } else {
                 true
             }
         });
 
-        local_agents.append(&mut global_agents);
-        let mut all_agents = local_agents;
+        let mut all_agents = extra_agents;
+        all_agents.append(&mut local_agents);
+        all_agents.append(&mut global_agents);
 
         // Assume agent in the following order of priority:
         // 1. The agent name specified by the start command via --agent (this is the agent_name that's
@@ -644,16 +929,35 @@ impl Agents {
                 if all_agents.iter().any(|a| a.name.as_str() == name) {
                     break 'active_idx name.to_string();
                 }
-                let _ = queue!(
+
+                let suggestions = similar_agent_names(name, all_agents.iter().map(|a| a.name.as_str()));
+                let did_you_mean = match suggestions.as_slice() {
+                    [] => String::new(),
+                    names => {
+                        let quoted = names.iter().map(|n| format!("'{n}'")).collect::<Vec<_>>();
+                        format!(" Did you mean {}?", quoted.join(" or "))
+                    },
+                };
+                let _ = execute!(
                     output,
                     style::SetForegroundColor(Color::Red),
                     style::Print("Error"),
                     style::SetForegroundColor(Color::Yellow),
-                    style::Print(format!(
-                        ": no agent with name {} found. Falling back to user specified default",
-                        name
-                    )),
-                    style::Print("\n"),
+                    style::Print(format!(": no agent with name {name} found.{did_you_mean}\n")),
+                    style::SetForegroundColor(Color::Reset)
+                );
+
+                // Only offer to switch when the typo is unambiguous.
+                if let [suggestion] = suggestions.as_slice() {
+                    if interactive && std::io::stdin().is_terminal() && confirm_agent_suggestion(suggestion) {
+                        break 'active_idx suggestion.to_string();
+                    }
+                }
+
+                let _ = queue!(
+                    output,
+                    style::SetForegroundColor(Color::Yellow),
+                    style::Print("Falling back to user specified default\n"),
                     style::SetForegroundColor(Color::Reset)
                 );
             }
@@ -755,6 +1059,12 @@ impl Agents {
         }
 
         load_metadata.launched_agent = active_idx.clone();
+        load_metadata.load_count = agents.len() as u32;
+        load_metadata.agents = {
+            let mut loaded = agents.values().map(LoadedAgentMetadata::from).collect::<Vec<_>>();
+            loaded.sort_by(|a, b| a.name.cmp(&b.name));
+            loaded
+        };
         (
             Self {
                 agents,
@@ -765,6 +1075,13 @@ impl Agents {
         )
     }
 
+    /// Adds an agent that is not backed by a file, e.g. one from [Agent::load_ephemeral], and
+    /// makes it the active agent. A loaded agent with the same name is replaced.
+    pub fn activate_ephemeral(&mut self, agent: Agent) {
+        self.active_idx = agent.name.clone();
+        self.insert(agent.name.clone(), agent);
+    }
+
     /// Returns a label to describe the permission status for a given tool.
     pub fn display_label(&self, tool_name: &str, origin: &ToolOrigin) -> String {
         use crate::util::pattern_matching::matches_any_pattern;
@@ -826,13 +1143,86 @@ impl Agents {
 }
 
 /// Metadata from the executed [Agents::load] operation.
-#[derive(Debug, Clone, Default)]
+#[derive(Debug, Clone, Default, Serialize)]
 pub struct AgentsLoadMetadata {
     pub migration_performed: bool,
     pub migrated_count: u32,
     pub load_count: u32,
     pub load_failed_count: u32,
     pub launched_agent: String,
+    /// Summary of each agent that loaded successfully, sorted by name.
+    pub agents: Vec<LoadedAgentMetadata>,
+}
+
+/// Summary of a single agent loaded by [Agents::load].
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct LoadedAgentMetadata {
+    pub name: String,
+    pub path: Option<PathBuf>,
+    pub mcp_server_count: usize,
+    pub tool_count: usize,
+    pub resource_count: usize,
+}
+
+impl From<&Agent> for LoadedAgentMetadata {
+    fn from(agent: &Agent) -> Self {
+        Self {
+            name: agent.name.clone(),
+            path: agent.path.clone(),
+            mcp_server_count: agent.mcp_servers.mcp_servers.len(),
+            tool_count: agent.tools.len(),
+            resource_count: agent.resources.len(),
+        }
+    }
+}
+
+/// Describes an agent by the directory it was loaded from, for [queue_agent_conflict].
+fn version_from_dir(agent: &Agent) -> String {
+    let dir = agent.path.as_deref().and_then(Path::parent).unwrap_or(Path::new(""));
+    format!("version from {}", dir.display())
+}
+
+/// Warns that several agents are named `name`, and that `version` of it is used.
+fn queue_agent_conflict(output: &mut impl Write, name: &str, version: &str) -> io::Result<()> {
+    queue!(
+        output,
+        style::SetForegroundColor(style::Color::Yellow),
+        style::Print("WARNING: "),
+        style::ResetColor,
+        style::Print("Agent conflict for "),
+        style::SetForegroundColor(style::Color::Green),
+        style::Print(name),
+        style::ResetColor,
+        style::Print(format!(". Using {version}.\n"))
+    )
+}
+
+/// Returns the names in `names` closest to the agent `name` that was not found, if any are close
+/// enough to be a typo of it. Several names are returned when they are equally close.
+fn similar_agent_names<'a>(name: &str, names: impl Iterator<Item = &'a str>) -> Vec<&'a str> {
+    let mut closest = Vec::new();
+    let mut closest_distance = MAX_AGENT_SUGGESTION_DISTANCE;
+    for candidate in names {
+        let distance = levenshtein(name, candidate);
+        if distance < closest_distance {
+            closest.clear();
+            closest_distance = distance;
+        }
+        if distance == closest_distance && !closest.contains(&candidate) {
+            closest.push(candidate);
+        }
+    }
+    closest.sort_unstable();
+    closest
+}
+
+/// Asks whether to launch `suggestion` in place of an agent that was not found.
+fn confirm_agent_suggestion(suggestion: &str) -> bool {
+    dialoguer::Confirm::with_theme(&util::dialoguer_theme())
+        .with_prompt(format!("Use agent '{suggestion}' instead?"))
+        .default(true)
+        .interact_on_opt(&dialoguer::console::Term::stderr())
+        .is_ok_and(|confirmed| confirmed == Some(true))
 }
 
 async fn load_agents_from_entries(
@@ -916,6 +1306,40 @@ pub fn queue_permission_override_warning(
     )?)
 }
 
+/// Environment variables in an agent resource, written as `${VAR}` or `${env:VAR}`.
+static RESOURCE_ENV_VAR: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"\$\{(?:env:)?([^}]+)\}").expect("env var pattern should be valid"));
+
+/// Expands environment variables written as `${VAR}` or `${env:VAR}` in an agent resource.
+/// Variables that are not set are left as they are and reported to `output`.
+fn expand_resource_env_vars(os: &Os, resource: &ResourcePath, output: &mut impl Write) -> ResourcePath {
+    RESOURCE_ENV_VAR
+        .replace_all(resource, |caps: &regex::Captures<'_>| {
+            let var_name = &caps[1];
+            os.env.get(var_name).unwrap_or_else(|_| {
+                let _ = queue!(
+                    output,
+                    style::SetForegroundColor(Color::Yellow),
+                    style::Print("WARNING: "),
+                    style::ResetColor,
+                    style::Print("Environment variable "),
+                    style::SetForegroundColor(Color::Green),
+                    style::Print(var_name),
+                    style::ResetColor,
+                    style::Print(format!(" used in resource '{}' is not set.\n", resource.as_str())),
+                );
+                caps[0].to_string()
+            })
+        })
+        .to_string()
+        .into()
+}
+
+/// A `{{variable}}` placeholder in an agent prompt, capturing the variable name.
+static PROMPT_VARIABLE: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"\{\{\s*([a-zA-Z_][a-zA-Z0-9_]*)\s*\}\}").expect("prompt variable pattern should be valid")
+});
+
 fn default_schema() -> String {
     "https://raw.githubusercontent.com/aws/amazon-q-developer-cli/refs/heads/main/schemas/agent-v1.json".into()
 }
@@ -945,6 +1369,17 @@ fn validate_agent_name(name: &str) -> eyre::Result<()> {
     Ok(())
 }
 
+/// See [Agents::disable_tools]
+fn remove_tools(agent: &mut Agent) {
+    agent.tools.clear();
+    agent.allowed_tools.clear();
+    agent.mcp_servers.mcp_servers.clear();
+    agent.prompt = Some(match agent.prompt.take() {
+        Some(prompt) => format!("{prompt}\n\n{NO_TOOLS_PROMPT}"),
+        None => NO_TOOLS_PROMPT.to_string(),
+    });
+}
+
 #[cfg(test)]
 mod tests {
     use serde_json::json;
@@ -986,6 +1421,309 @@ mod tests {
         assert!(agent.mcp_servers.mcp_servers.contains_key("fetch"));
         assert!(agent.mcp_servers.mcp_servers.contains_key("git"));
         assert!(agent.tool_aliases.contains_key("@gits/some_tool"));
+        assert_eq!(agent.model, None);
+
+        let agent =
+            serde_json::from_value::<Agent>(json!({ "name": "some_agent", "model": "claude-sonnet-4" })).unwrap();
+        assert_eq!(agent.model.as_deref(), Some("claude-sonnet-4"));
+    }
+
+    #[tokio::test]
+    async fn test_thaw_expands_resource_env_vars() {
+        let os = Os::new().await.unwrap();
+        unsafe {
+            os.env.set_var("PROJECT_ROOT", "/work/project");
+        }
+
+        let mut agent = Agent {
+            resources: vec![
+                "file://${PROJECT_ROOT}/docs/**/*.md".into(),
+                "!file://${env:PROJECT_ROOT}/docs/drafts".into(),
+                "file://${NOT_SET_FOR_TEST}/README.md".into(),
+            ],
+            ..Default::default()
+        };
+        let mut output = Vec::new();
+        agent.thaw(&os, Path::new("agent.json"), None, &mut output).unwrap();
+
+        assert_eq!(agent.resources, vec![
+            ResourcePath::from("file:///work/project/docs/**/*.md"),
+            ResourcePath::from("!file:///work/project/docs/drafts"),
+            ResourcePath::from("file://${NOT_SET_FOR_TEST}/README.md"),
+        ]);
+        assert!(String::from_utf8_lossy(&output).contains("NOT_SET_FOR_TEST"));
+
+        // The resources are written back as they were in the config
+        let frozen = serde_json::from_str::<Agent>(&agent.to_str_pretty().unwrap()).unwrap();
+        assert_eq!(
+            frozen.resources[0],
+            ResourcePath::from("file://${PROJECT_ROOT}/docs/**/*.md")
+        );
+    }
+
+    #[tokio::test]
+    async fn test_load_ephemeral() {
+        let os = Os::new().await.unwrap();
+        let content = br#"{ "name": "ignored", "prompt": "You review diffs", "tools": ["fs_read"] }"#;
+        let agent = Agent::load_ephemeral(&os, "stdin", content, true, &mut Vec::new())
+            .await
+            .unwrap();
+        assert_eq!(agent.name, "stdin");
+        assert_eq!(agent.prompt.as_deref(), Some("You review diffs"));
+        assert_eq!(agent.path, None);
+
+        let mut agents = Agents::default();
+        agents.activate_ephemeral(agent);
+        assert_eq!(agents.get_active().map(|a| a.name.as_str()), Some("stdin"));
+
+        // The name is optional, everything else must match the schema
+        assert!(
+            Agent::load_ephemeral(&os, "stdin", br#"{ "tools": "fs_read" }"#, true, &mut Vec::new())
+                .await
+                .is_err_and(|e| matches!(e, AgentConfigError::SchemaMismatch { .. }))
+        );
+        assert!(
+            Agent::load_ephemeral(&os, "stdin", b"not json", true, &mut Vec::new())
+                .await
+                .is_err_and(|e| matches!(e, AgentConfigError::InvalidJson { .. }))
+        );
+    }
+
+    #[test]
+    fn test_tools_settings_schema() {
+        let schema = serde_json::to_value(tools_settings_root_schema()).unwrap();
+        let valid = json!({
+            "fs_write": { "allowedPaths": ["~/projects"], "restrictToWorkspace": false },
+            "execute_bash": {
+                "allowedCommands": ["git status"],
+                "showFsDiff": true,
+                "networkPolicy": "audit",
+                "allowedEnvVars": ["PATH", "AWS_*"],
+            },
+            "fs_read": { "maxResultTokens": 8000, "maxLinesPerRead": { "**/*.log": 500 } },
+            "@git/git_status": { "anything": 1 },
+        });
+        assert!(jsonschema::validate(&schema, &valid).is_ok());
+        for invalid in [
+            json!({ "fs_read": { "allowedPaths": "~/projects" } }),
+            json!({ "use_aws": { "deniedServices": [1] } }),
+            json!({ "fs_write": { "restrictToWorkspace": "yes" } }),
+            json!({ "execute_bash": { "networkPolicy": "none" } }),
+            json!({ "execute_bash": { "sanitizeEnv": "true" } }),
+            json!({ "fs_read": { "maxResultTokens": "8000" } }),
+            json!({ "fs_read": { "maxLinesPerRead": { "**/*.log": "500" } } }),
+        ] {
+            assert!(jsonschema::validate(&schema, &invalid).is_err(), "{invalid}");
+        }
+
+        // The agent schema checks the settings of native tools the same way
+        let agent_schema = serde_json::to_value(schema_for!(Agent)).unwrap();
+        let agent = |tools_settings: serde_json::Value| json!({ "name": "agent", "toolsSettings": tools_settings });
+        assert!(jsonschema::validate(&agent_schema, &agent(valid)).is_ok());
+        let invalid = json!({ "fs_read": { "allowedPaths": "~/projects" } });
+        assert!(jsonschema::validate(&agent_schema, &agent(invalid)).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_extra_agent_dirs() {
+        let mut os = Os::new().await.unwrap();
+        os.agent_dirs = vec![PathBuf::from("/ci/agents")];
+        let paths = std::env::join_paths(["/missing", "/more/agents"]).unwrap();
+        unsafe {
+            os.env.set_var("QCLI_AGENT_PATH", paths);
+        }
+        assert_eq!(
+            directories::chat_agent_path_env_var(&os),
+            std::env::join_paths(["/ci/agents", "/missing", "/more/agents"]).ok()
+        );
+
+        let global_dir = directories::chat_global_agent_path(&os).unwrap();
+        let global_dir = global_dir.to_str().unwrap();
+        for (dir, name, description) in [
+            ("/ci/agents", "ci", "first"),
+            ("/more/agents", "ci", "second"),
+            ("/more/agents", "other", "more"),
+            (global_dir, "ci", "global"),
+        ] {
+            os.fs.create_dir_all(dir).await.unwrap();
+            let content = json!({ "name": name, "description": description }).to_string();
+            os.fs.write(format!("{dir}/{name}.json"), content).await.unwrap();
+        }
+
+        let (agent, path) = Agent::get_agent_by_name(&os, "ci").await.unwrap();
+        assert_eq!(path, PathBuf::from("/ci/agents/ci.json"));
+        assert_eq!(agent.description.as_deref(), Some("first"));
+        let (_, path) = Agent::get_agent_by_name(&os, "other").await.unwrap();
+        assert_eq!(path, PathBuf::from("/more/agents/other.json"));
+
+        let mut output = Vec::new();
+        let (agents, _) = Agents::load(&mut os, Some("ci"), true, false, &mut output, true).await;
+        assert_eq!(agents.get_active().unwrap().description.as_deref(), Some("first"));
+        assert!(agents.agents.contains_key("other"));
+
+        let output = String::from_utf8_lossy(&output);
+        assert!(output.contains("Skipping agent directory /missing"), "{output}");
+        assert_eq!(output.matches("Agent conflict for").count(), 2, "{output}");
+        assert!(output.contains("Using version from /ci/agents"), "{output}");
+    }
+
+    #[test]
+    fn test_prompt_variables() {
+        let agent = serde_json::from_value::<Agent>(json!({
+            "name": "reviewer",
+            "prompt": "Review {{language}} code for {{ team }}. Prefer idiomatic {{language}}, {{unset}} and {{not a var}}",
+            "promptVariables": { "team": "platform" }
+        }))
+        .unwrap();
+        assert_eq!(agent.prompt_variable_names(), vec!["language", "team", "unset"]);
+
+        let values = HashMap::from([("language".to_string(), "Rust".to_string())]);
+        assert_eq!(
+            agent.expanded_prompt(&values).as_deref(),
+            Some("Review Rust code for platform. Prefer idiomatic Rust, {{unset}} and {{not a var}}")
+        );
+
+        let values = HashMap::from([("team".to_string(), "infra".to_string())]);
+        assert!(agent.expanded_prompt(&values).unwrap().contains("for infra."));
+        assert_eq!(Agent::default().expanded_prompt(&values), None);
+    }
+
+    #[test]
+    fn test_similar_agent_names() {
+        let names = ["developer", "devops", "reviewer", "ci", "cd"];
+        assert_eq!(similar_agent_names("dveloper", names.into_iter()), vec!["developer"]);
+        assert_eq!(similar_agent_names("revewer", names.into_iter()), vec!["reviewer"]);
+        // Equally close names are all suggested
+        assert_eq!(similar_agent_names("cx", names.into_iter()), vec!["cd", "ci"]);
+        // Closer names win over names that are further away
+        assert_eq!(similar_agent_names("devop", names.into_iter()), vec!["devops"]);
+        assert!(similar_agent_names("security", names.into_iter()).is_empty());
+    }
+
+    #[test]
+    fn test_parse_migrates_v1_agent() {
+        // A version 2 of the format that renamed `systemPrompt` to `prompt`
+        fn rename_system_prompt(mut value: serde_json::Value) -> serde_json::Value {
+            if let Some(prompt) = value.as_object_mut().and_then(|map| map.remove("systemPrompt")) {
+                value["prompt"] = prompt;
+            }
+            value
+        }
+        let steps: &[migrations::Migration] = &[rename_system_prompt];
+        let path = Path::new("/agents/reviewer.json");
+
+        let v1 = json!({
+            "name": "reviewer",
+            "systemPrompt": "You review diffs",
+            "tools": ["fs_read"],
+        });
+        let agent = Agent::parse_with(v1.to_string().as_bytes(), path, steps).unwrap();
+        assert_eq!(agent.version, 2);
+        assert_eq!(agent.prompt.as_deref(), Some("You review diffs"));
+        assert_eq!(agent.tools, vec!["fs_read".to_string()]);
+
+        // Configs of the current version are not migrated
+        let v2 = json!({ "name": "reviewer", "systemPrompt": "You review diffs", "version": 2 });
+        assert!(
+            Agent::parse_with(v2.to_string().as_bytes(), path, steps)
+                .is_err_and(|e| matches!(e, AgentConfigError::InvalidJson { .. }))
+        );
+        let v2 = json!({ "name": "reviewer", "prompt": "You review diffs", "version": 2 });
+        let agent = Agent::parse_with(v2.to_string().as_bytes(), path, steps).unwrap();
+        assert_eq!(agent.prompt.as_deref(), Some("You review diffs"));
+    }
+
+    #[tokio::test]
+    async fn test_load_migrates_v1_agent() {
+        let os = Os::new().await.unwrap();
+        os.fs.create_dir_all("/agents").await.unwrap();
+        let v1 = json!({
+            "name": "reviewer",
+            "prompt": "You review diffs",
+            "tools": ["fs_read"],
+            "allowedTools": ["fs_read"],
+            "toolsSettings": { "fs_read": { "allowedPaths": ["~/src"] } }
+        });
+        os.fs.write("/agents/reviewer.json", v1.to_string()).await.unwrap();
+
+        let agent = Agent::load(&os, "/agents/reviewer.json", &mut None, false, &mut Vec::new())
+            .await
+            .unwrap();
+        assert_eq!(agent.version, migrations::CURRENT_VERSION);
+        assert_eq!(agent.name, "reviewer");
+        assert_eq!(agent.prompt.as_deref(), Some("You review diffs"));
+        assert_eq!(agent.tools, vec!["fs_read".to_string()]);
+        assert!(agent.allowed_tools.contains("fs_read"));
+        assert_eq!(
+            agent.tools_settings.get("fs_read"),
+            Some(&json!({ "allowedPaths": ["~/src"] }))
+        );
+
+        // Configs of a newer version than this build supports are rejected
+        let future = json!({ "name": "reviewer", "version": migrations::CURRENT_VERSION + 1 });
+        os.fs.write("/agents/future.json", future.to_string()).await.unwrap();
+        assert!(
+            Agent::load(&os, "/agents/future.json", &mut None, false, &mut Vec::new())
+                .await
+                .is_err_and(|e| matches!(e, AgentConfigError::UnsupportedVersion { .. }))
+        );
+    }
+
+    #[tokio::test]
+    async fn test_load_suggests_similar_agent() {
+        let mut os = Os::new().await.unwrap();
+        let local_dir = directories::chat_local_agent_dir(&os).unwrap();
+        os.fs.create_dir_all(&local_dir).await.unwrap();
+        let content = json!({ "name": "developer" }).to_string();
+        os.fs.write(local_dir.join("developer.json"), content).await.unwrap();
+
+        let mut output = Vec::new();
+        let (agents, _) = Agents::load(&mut os, Some("dveloper"), true, false, &mut output, true).await;
+        assert_eq!(agents.active_idx, DEFAULT_AGENT_NAME);
+
+        let output = String::from_utf8_lossy(&output);
+        assert!(
+            output.contains("no agent with name dveloper found. Did you mean 'developer'?"),
+            "{output}"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_to_str_pretty_resolved() {
+        let os = Os::new().await.unwrap();
+        let legacy_mcp_config =
+            serde_json::from_value::<McpServerConfig>(json!({ "legacy": { "command": "legacy-mcp" } })).unwrap();
+        let mut agent = serde_json::from_str::<Agent>(INPUT).unwrap();
+        agent.use_legacy_mcp_json = true;
+        agent
+            .thaw(
+                &os,
+                Path::new("/agents/some_agent.json"),
+                Some(&legacy_mcp_config),
+                &mut Vec::new(),
+            )
+            .unwrap();
+
+        let resolved = serde_json::from_str::<serde_json::Value>(&agent.to_str_pretty_resolved().unwrap()).unwrap();
+        assert_eq!(resolved["path"], "/agents/some_agent.json");
+        assert_eq!(resolved["mcpServers"]["legacy"]["command"], "legacy-mcp");
+        assert_eq!(resolved["mcpServers"]["fetch"]["command"], "fetch3.1");
+
+        // The cold form leaves out both
+        let frozen = serde_json::from_str::<serde_json::Value>(&agent.to_str_pretty().unwrap()).unwrap();
+        assert!(frozen.get("path").is_none());
+        assert!(frozen["mcpServers"].get("legacy").is_none());
+    }
+
+    #[test]
+    fn test_loaded_agent_metadata() {
+        let agent = serde_json::from_str::<Agent>(INPUT).expect("Deserializtion failed");
+        let metadata = LoadedAgentMetadata::from(&agent);
+        assert_eq!(metadata.name, "some_agent");
+        assert_eq!(metadata.path, None);
+        assert_eq!(metadata.mcp_server_count, 2);
+        assert_eq!(metadata.tool_count, 1);
+        assert_eq!(metadata.resource_count, 1);
     }
 
     #[test]
@@ -1141,6 +1879,36 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_disable_tools() {
+        let mut agents = Agents::default();
+        let agent = serde_json::from_str::<Agent>(INPUT).expect("Deserializtion failed");
+        agents.agents.insert(agent.name.clone(), agent);
+        agents.agents.insert("default".to_string(), Agent::default());
+
+        agents.disable_tools();
+        agents.disable_tools();
+        // Agents loaded later in the session, e.g. with /agent create, have no tools either
+        agents.insert(
+            "late".to_string(),
+            serde_json::from_str::<Agent>(INPUT).expect("Deserializtion failed"),
+        );
+
+        assert!(agents.tools_disabled);
+        assert_eq!(agents.agents.len(), 3);
+        for agent in agents.agents.values() {
+            assert!(agent.tools.is_empty());
+            assert!(agent.allowed_tools.is_empty());
+            assert!(agent.mcp_servers.mcp_servers.is_empty());
+            assert!(
+                agent
+                    .prompt
+                    .as_ref()
+                    .is_some_and(|p| p.ends_with(NO_TOOLS_PROMPT) && p.matches(NO_TOOLS_PROMPT).count() == 1)
+            );
+        }
+    }
+
     #[test]
     fn test_display_label_trust_all_tools() {
         let agents = Agents {
@@ -1205,17 +1973,26 @@ mod tests {
         let agent = Agent {
             schema: "test".to_string(),
             name: "test-agent".to_string(),
+            version: 1,
             description: None,
             prompt: None,
+            model: None,
+            prompt_variables: Default::default(),
             mcp_servers: Default::default(),
             tools: Vec::new(),
             tool_aliases: Default::default(),
             allowed_tools,
             tools_settings: Default::default(),
             resources: Vec::new(),
+            max_resource_files: None,
+            max_resource_bytes: None,
             hooks: Default::default(),
             use_legacy_mcp_json: false,
+            delegation_enabled: false,
+            sanitize_tool_output: Default::default(),
+            dedupe_mcp_tools: Default::default(),
             path: None,
+            raw_resources: None,
         };
 
         agents.agents.insert("test-agent".to_string(), agent);
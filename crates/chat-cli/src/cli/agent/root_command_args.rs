@@ -1,10 +1,14 @@
-use std::io::Write;
+use std::io::{
+    IsTerminal,
+    Write,
+};
 use std::path::PathBuf;
 use std::process::ExitCode;
 
 use clap::{
     Args,
     Subcommand,
+    ValueEnum,
 };
 use crossterm::style::Color;
 use crossterm::{
@@ -17,12 +21,18 @@ use eyre::{
 };
 use schemars::schema_for;
 
+use super::bundle::AgentBundle;
+use super::merge::MergePolicy;
 use super::{
     Agent,
+    AgentTemplate,
     Agents,
     McpServerConfig,
     legacy,
+    tools_settings_root_schema,
 };
+use crate::cli::chat::tools::plain_output;
+use crate::cli::chat::util::ui::glyph;
 use crate::database::settings::Setting;
 use crate::os::Os;
 use crate::util::directories;
@@ -45,6 +55,24 @@ pub enum AgentSubcommands {
         /// The name of an agent that shall be used as the starting point for the agent creation
         #[arg(long, short)]
         from: Option<String>,
+        /// A built-in template to use as the starting point for the agent creation
+        #[arg(long, short, value_enum, conflicts_with = "from")]
+        template: Option<AgentTemplate>,
+    },
+    /// Print the config of an agent as pretty JSON
+    Show {
+        /// Name of the agent to show
+        name: String,
+        /// Show the agent as q chat uses it: with the servers from the legacy mcp.json merged in,
+        /// environment variables in resources expanded, and the path it was loaded from
+        #[arg(long)]
+        resolved: bool,
+    },
+    /// Print the JSON schema of agent configs, e.g. for editor autocompletion and validation
+    Schema {
+        /// Which schema to print
+        #[arg(value_enum, default_value_t)]
+        kind: SchemaKind,
     },
     /// Validate a config with the given path
     Validate {
@@ -63,6 +91,56 @@ pub enum AgentSubcommands {
         #[arg(long, short)]
         name: String,
     },
+    /// Package an agent, the files its resources resolve to and its MCP servers into a zip bundle
+    Export {
+        /// Name of the agent to export
+        name: String,
+        /// Path of the bundle to write
+        #[arg(long, short)]
+        out: PathBuf,
+    },
+    /// Install an agent from a bundle created with `q agent export`. The MCP servers, hooks,
+    /// allowed tools and tool settings of the agent are only kept once they are trusted
+    Import {
+        /// Path of the bundle to import
+        path: PathBuf,
+        /// Name to give the imported agent. Defaults to the name it was exported with
+        #[arg(long, short)]
+        name: Option<String>,
+        /// Install the agent in the global agent directory. This is the default
+        #[arg(long, conflicts_with = "local")]
+        global: bool,
+        /// Install the agent in the agent directory of the current workspace
+        #[arg(long)]
+        local: bool,
+        /// Replace an existing agent with the same name
+        #[arg(long)]
+        force: bool,
+    },
+    /// Combine two agents into one. Tools, allowed tools and resources are unioned, tool settings
+    /// are merged key by key and other conflicting fields are resolved by the policy
+    Merge {
+        /// Name of the agent to start from
+        base: String,
+        /// Name of the agent whose config is merged into the base agent
+        #[arg(value_name = "OVERRIDE")]
+        override_agent: String,
+        /// How to resolve fields that both agents set to different values
+        #[arg(long, value_enum, default_value_t)]
+        policy: MergePolicy,
+        /// Path to write the merged agent to. The agent is printed if not provided
+        #[arg(long, short)]
+        output: Option<PathBuf>,
+    },
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, ValueEnum)]
+pub enum SchemaKind {
+    /// The whole agent config
+    #[default]
+    Agent,
+    /// The `toolsSettings` field of an agent config, describing the settings of the native tools
+    ToolsSettings,
 }
 
 #[derive(Debug, Clone, PartialEq, Eq, Default, Args)]
@@ -83,7 +161,7 @@ impl AgentArgs {
         };
         match self.cmd {
             Some(AgentSubcommands::List) | None => {
-                let agents = Agents::load(os, None, true, &mut stderr, mcp_enabled).await.0;
+                let agents = Agents::load(os, None, true, false, &mut stderr, mcp_enabled).await.0;
                 let agent_with_path =
                     agents
                         .agents
@@ -107,9 +185,15 @@ impl AgentArgs {
 
                 writeln!(stderr, "{}", output_str)?;
             },
-            Some(AgentSubcommands::Create { name, directory, from }) => {
-                let mut agents = Agents::load(os, None, true, &mut stderr, mcp_enabled).await.0;
-                let path_with_file_name = create_agent(os, &mut agents, name.clone(), directory, from).await?;
+            Some(AgentSubcommands::Create {
+                name,
+                directory,
+                from,
+                template,
+            }) => {
+                let mut agents = Agents::load(os, None, true, false, &mut stderr, mcp_enabled).await.0;
+                let path_with_file_name =
+                    create_agent(os, &mut agents, name.clone(), directory, from, template).await?;
                 let editor_cmd = std::env::var("EDITOR").unwrap_or_else(|_| "vi".to_string());
                 let mut cmd = std::process::Command::new(editor_cmd);
 
@@ -124,7 +208,7 @@ impl AgentArgs {
                         path_with_file_name.display()
                     );
                 };
-                if let Err(e) = serde_json::from_slice::<Agent>(&content) {
+                if let Err(e) = Agent::parse(&content, &path_with_file_name) {
                     bail!(
                         "Post write validation failed for agent '{name}' at path: {}. Malformed config detected: {e}",
                         path_with_file_name.display()
@@ -133,11 +217,29 @@ impl AgentArgs {
 
                 writeln!(
                     stderr,
-                    "\n📁 Created agent {} '{}'\n",
+                    "\n{}Created agent {} '{}'\n",
+                    glyph(plain_output(os), "📁 ", ""),
                     name,
                     path_with_file_name.display()
                 )?;
             },
+            Some(AgentSubcommands::Show { name, resolved }) => {
+                let mut agents = Agents::load(os, None, true, false, &mut stderr, mcp_enabled).await.0;
+                let agent = agents.switch(&name)?;
+                let content = if resolved {
+                    agent.to_str_pretty_resolved()?
+                } else {
+                    agent.to_str_pretty()?
+                };
+                writeln!(std::io::stdout(), "{content}")?;
+            },
+            Some(AgentSubcommands::Schema { kind }) => {
+                let schema = match kind {
+                    SchemaKind::Agent => schema_for!(Agent),
+                    SchemaKind::ToolsSettings => tools_settings_root_schema(),
+                };
+                writeln!(std::io::stdout(), "{}", serde_json::to_string_pretty(&schema)?)?;
+            },
             Some(AgentSubcommands::Validate { path }) => {
                 let mut global_mcp_config = None::<McpServerConfig>;
                 let agent = Agent::load(os, path.as_str(), &mut global_mcp_config, mcp_enabled, &mut stderr).await;
@@ -229,7 +331,7 @@ impl AgentArgs {
                         let _ = queue!(
                             stderr,
                             style::SetForegroundColor(Color::Green),
-                            style::Print("✓ Success: "),
+                            style::Print(glyph(plain_output(os), "✓ Success: ", "Success: ")),
                             style::ResetColor,
                             style::Print(format!(
                                 "Profile migration successful. Migrated {} agent(s)\n",
@@ -258,7 +360,7 @@ impl AgentArgs {
                 }
             },
             Some(AgentSubcommands::SetDefault { name }) => {
-                let mut agents = Agents::load(os, None, true, &mut stderr, mcp_enabled).await.0;
+                let mut agents = Agents::load(os, None, true, false, &mut stderr, mcp_enabled).await.0;
                 match agents.switch(&name) {
                     Ok(agent) => {
                         os.database
@@ -269,7 +371,8 @@ impl AgentArgs {
                         let _ = queue!(
                             stderr,
                             style::SetForegroundColor(Color::Green),
-                            style::Print("✓ Default agent set to '"),
+                            style::Print(glyph(plain_output(os), "✓ ", "")),
+                            style::Print("Default agent set to '"),
                             style::Print(&agent.name),
                             style::Print("'. This will take effect the next time q chat is launched.\n"),
                             style::ResetColor,
@@ -286,18 +389,137 @@ impl AgentArgs {
                     },
                 }
             },
+            Some(AgentSubcommands::Export { name, out }) => {
+                let mut agents = Agents::load(os, None, true, false, &mut stderr, mcp_enabled).await.0;
+                let agent = agents.switch(&name)?;
+                let bundle = AgentBundle::from_agent(os, agent).await?;
+                os.fs.write(&out, bundle.to_zip()?).await?;
+
+                writeln!(
+                    stderr,
+                    "\n{}Exported agent '{}' with {} resource file(s) to {}\n",
+                    glyph(plain_output(os), "📦 ", ""),
+                    name,
+                    bundle.manifest.resources.len(),
+                    out.display()
+                )?;
+            },
+            Some(AgentSubcommands::Import {
+                path,
+                name,
+                local,
+                force,
+                ..
+            }) => {
+                let Ok(content) = os.fs.read(&path).await else {
+                    bail!("Unable to read agent bundle at {}", path.display());
+                };
+                let bundle = AgentBundle::from_zip(&content)?;
+                let name = name.unwrap_or_else(|| bundle.manifest.agent_name.clone());
+
+                let agents = Agents::load(os, None, true, false, &mut stderr, mcp_enabled).await.0;
+                if !force && agents.agents.contains_key(&name) {
+                    bail!(
+                        "An agent named '{name}' already exists. Use --name to import it under another name, or --force to replace it"
+                    );
+                }
+
+                let trust_summary = bundle.trust_summary();
+                let trusted = confirm_bundle_trust(&trust_summary, &mut stderr)?;
+                let agent_dir = if local {
+                    directories::chat_local_agent_dir(os)?
+                } else {
+                    directories::chat_global_agent_path(os)?
+                };
+                let config_path = bundle.install(os, &agent_dir, &name, force, trusted).await?;
+
+                writeln!(
+                    stderr,
+                    "\n{}Imported agent {} '{}'\n",
+                    glyph(plain_output(os), "📁 ", ""),
+                    name,
+                    config_path.display()
+                )?;
+                if !trusted {
+                    writeln!(
+                        stderr,
+                        "The MCP servers of the agent were imported disabled, and its hooks, allowed tools, tool settings and resources outside of the bundle were left out. Set \"disabled\" to false in its config to enable the servers.\n"
+                    )?;
+                }
+            },
+            Some(AgentSubcommands::Merge {
+                base,
+                override_agent,
+                policy,
+                output,
+            }) => {
+                let mut agents = Agents::load(os, None, true, false, &mut stderr, mcp_enabled).await.0;
+                let mut base = agents.switch(&base)?.clone();
+                let mut override_agent = agents.switch(&override_agent)?.clone();
+                // Merge the configs as they are written, not as they were resolved at load time
+                base.freeze();
+                override_agent.freeze();
+
+                let merged = Agent::merge(&base, &override_agent, policy)?;
+                let content = merged.to_str_pretty()?;
+                match output {
+                    Some(output) => {
+                        os.fs.write(&output, content).await?;
+                        writeln!(
+                            stderr,
+                            "\n{}Merged agents '{}' and '{}' into {}\n",
+                            glyph(plain_output(os), "🔀 ", ""),
+                            base.name,
+                            override_agent.name,
+                            output.display()
+                        )?;
+                    },
+                    None => writeln!(std::io::stdout(), "{content}")?,
+                }
+            },
         }
 
         Ok(ExitCode::SUCCESS)
     }
 }
 
+/// Lists what an imported agent gets to do on the user's machine and asks whether it should be
+/// trusted. Nothing is trusted when there is no terminal to ask in.
+fn confirm_bundle_trust(items: &[String], output: &mut impl Write) -> Result<bool> {
+    if items.is_empty() {
+        return Ok(true);
+    }
+    if !std::io::stdin().is_terminal() || !std::io::stderr().is_terminal() {
+        return Ok(false);
+    }
+
+    queue!(
+        output,
+        style::Print("\nThe agent comes with the following commands, permissions and files:\n"),
+        style::SetForegroundColor(Color::DarkGrey),
+    )?;
+    for item in items {
+        queue!(output, style::Print(format!("  {item}\n")))?;
+    }
+    queue!(
+        output,
+        style::ResetColor,
+        style::Print("Do you trust them and want to keep them in the imported agent? [y/N]: "),
+    )?;
+    output.flush()?;
+
+    let mut input = String::new();
+    std::io::stdin().read_line(&mut input)?;
+    Ok(matches!(input.trim().to_lowercase().as_str(), "y" | "yes"))
+}
+
 pub async fn create_agent(
     os: &mut Os,
     agents: &mut Agents,
     name: String,
     path: Option<String>,
     from: Option<String>,
+    template: Option<AgentTemplate>,
 ) -> Result<PathBuf> {
     let path = if let Some(path) = path {
         let mut path = PathBuf::from(path);
@@ -328,6 +550,8 @@ pub async fn create_agent(
         let mut agent_to_copy = agents.switch(from.as_str())?.clone();
         agent_to_copy.name = name.clone();
         agent_to_copy
+    } else if let Some(template) = template {
+        Agent::new_from_template(&name, template)
     } else {
         Agent {
             name: name.clone(),
@@ -371,7 +595,8 @@ mod tests {
                 cmd: Some(AgentSubcommands::Create {
                     name: "some_agent".to_string(),
                     directory: None,
-                    from: Some("some_old_agent".to_string())
+                    from: Some("some_old_agent".to_string()),
+                    template: None,
                 })
             })
         );
@@ -381,7 +606,109 @@ mod tests {
                 cmd: Some(AgentSubcommands::Create {
                     name: "some_agent".to_string(),
                     directory: None,
-                    from: Some("some_old_agent".to_string())
+                    from: Some("some_old_agent".to_string()),
+                    template: None,
+                })
+            })
+        );
+    }
+
+    #[test]
+    fn test_agent_subcommand_create_from_template() {
+        assert_parse!(
+            ["agent", "create", "--name", "reviewer", "--template", "code-review"],
+            RootSubcommand::Agent(AgentArgs {
+                cmd: Some(AgentSubcommands::Create {
+                    name: "reviewer".to_string(),
+                    directory: None,
+                    from: None,
+                    template: Some(AgentTemplate::CodeReview),
+                })
+            })
+        );
+    }
+
+    #[test]
+    fn test_agent_subcommand_schema() {
+        assert_parse!(
+            ["agent", "schema"],
+            RootSubcommand::Agent(AgentArgs {
+                cmd: Some(AgentSubcommands::Schema {
+                    kind: SchemaKind::Agent
+                })
+            })
+        );
+        assert_parse!(
+            ["agent", "schema", "tools-settings"],
+            RootSubcommand::Agent(AgentArgs {
+                cmd: Some(AgentSubcommands::Schema {
+                    kind: SchemaKind::ToolsSettings
+                })
+            })
+        );
+    }
+
+    #[test]
+    fn test_agent_subcommand_export() {
+        assert_parse!(
+            ["agent", "export", "reviewer", "--out", "reviewer.zip"],
+            RootSubcommand::Agent(AgentArgs {
+                cmd: Some(AgentSubcommands::Export {
+                    name: "reviewer".to_string(),
+                    out: PathBuf::from("reviewer.zip"),
+                })
+            })
+        );
+    }
+
+    #[test]
+    fn test_agent_subcommand_import() {
+        assert_parse!(
+            ["agent", "import", "bundle.zip", "-n", "copy", "--local", "--force"],
+            RootSubcommand::Agent(AgentArgs {
+                cmd: Some(AgentSubcommands::Import {
+                    path: PathBuf::from("bundle.zip"),
+                    name: Some("copy".to_string()),
+                    global: false,
+                    local: true,
+                    force: true,
+                })
+            })
+        );
+        assert!(
+            <crate::cli::Cli as clap::Parser>::try_parse_from([
+                crate::util::CHAT_BINARY_NAME,
+                "agent",
+                "import",
+                "reviewer.zip",
+                "--global",
+                "--local"
+            ])
+            .is_err()
+        );
+    }
+
+    #[test]
+    fn test_agent_subcommand_merge() {
+        assert_parse!(
+            ["agent", "merge", "base", "reviewer"],
+            RootSubcommand::Agent(AgentArgs {
+                cmd: Some(AgentSubcommands::Merge {
+                    base: "base".to_string(),
+                    override_agent: "reviewer".to_string(),
+                    policy: MergePolicy::PreferOverride,
+                    output: None,
+                })
+            })
+        );
+        assert_parse!(
+            ["agent", "merge", "dev", "ops", "--policy", "error", "-o", "m.json"],
+            RootSubcommand::Agent(AgentArgs {
+                cmd: Some(AgentSubcommands::Merge {
+                    base: "dev".to_string(),
+                    override_agent: "ops".to_string(),
+                    policy: MergePolicy::Error,
+                    output: Some(PathBuf::from("m.json")),
                 })
             })
         );
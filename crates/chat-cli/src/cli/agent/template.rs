@@ -0,0 +1,60 @@
+use clap::ValueEnum;
+
+use super::Agent;
+
+/// Starting points for agents tailored to common workflows. Each template pre-populates the
+/// prompt, tools, allowed tools and tool settings, and can be customized after creation.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum AgentTemplate {
+    /// Reviews code changes with read-only access to the repository
+    CodeReview,
+    /// Explores datasets and writes analysis scripts and reports
+    DataAnalysis,
+    /// Investigates infrastructure, deployments and pipelines
+    DevOps,
+    /// Writes and maintains documentation
+    Documentation,
+}
+
+impl AgentTemplate {
+    fn content(&self) -> &'static str {
+        match self {
+            AgentTemplate::CodeReview => include_str!("templates/code_review.json"),
+            AgentTemplate::DataAnalysis => include_str!("templates/data_analysis.json"),
+            AgentTemplate::DevOps => include_str!("templates/dev_ops.json"),
+            AgentTemplate::Documentation => include_str!("templates/documentation.json"),
+        }
+    }
+}
+
+impl Agent {
+    /// Creates an agent named `name` from one of the templates compiled into the binary.
+    pub fn new_from_template(name: &str, template: AgentTemplate) -> Agent {
+        let mut agent = serde_json::from_str::<Agent>(template.content()).expect("agent templates are valid agents");
+        agent.name = name.to_string();
+        agent
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_new_from_template() {
+        for template in AgentTemplate::value_variants() {
+            let agent = Agent::new_from_template("my-agent", *template);
+            assert_eq!(agent.name, "my-agent");
+            assert!(agent.prompt.as_ref().is_some_and(|p| !p.is_empty()));
+            assert!(!agent.tools.is_empty());
+            // Every allowed or configured tool should also be available to the agent
+            for tool in agent
+                .allowed_tools
+                .iter()
+                .chain(agent.tools_settings.keys().map(|k| &k.0))
+            {
+                assert!(agent.tools.contains(tool), "{template:?}: {tool} is not in tools");
+            }
+        }
+    }
+}
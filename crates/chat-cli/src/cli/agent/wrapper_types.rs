@@ -1,6 +1,7 @@
 use std::borrow::Borrow;
 use std::ops::Deref;
 
+use schemars::generate::SchemaSettings;
 use schemars::{
     JsonSchema,
     Schema,
@@ -12,6 +13,11 @@ use serde::{
     Serialize,
 };
 
+use crate::cli::chat::tools::execute::ExecuteSettings;
+use crate::cli::chat::tools::fs_read::FsReadSettings;
+use crate::cli::chat::tools::fs_write::FsWriteSettings;
+use crate::cli::chat::tools::use_aws::UseAwsSettings;
+
 /// Subject of the tool name change. For tools in mcp servers, you would need to prefix them with
 /// their server names
 #[derive(Debug, Clone, Serialize, Deserialize, Eq, Hash, PartialEq, JsonSchema)]
@@ -76,8 +82,21 @@ pub fn tool_settings_schema(generator: &mut SchemaGenerator) -> Schema {
         .and_then(|v| v.as_str())
         .unwrap_or("The name of the tool to be configured");
 
+    // Keys that are not described, such as those of MCP tools, are still allowed
+    let native_tool_settings = [
+        ("execute_bash", generator.subschema_for::<ExecuteSettings>()),
+        ("execute_cmd", generator.subschema_for::<ExecuteSettings>()),
+        ("fs_read", generator.subschema_for::<FsReadSettings>()),
+        ("fs_write", generator.subschema_for::<FsWriteSettings>()),
+        ("use_aws", generator.subschema_for::<UseAwsSettings>()),
+    ]
+    .into_iter()
+    .map(|(tool_name, schema)| (tool_name.to_string(), schema.to_value()))
+    .collect::<serde_json::Map<_, _>>();
+
     json_schema!({
         "type": "object",
+        "properties": native_tool_settings,
         "additionalProperties": {
             "type": "object",
             "description": "Settings for tools. Refer to our documentations to see how to configure them"
@@ -89,10 +108,24 @@ pub fn tool_settings_schema(generator: &mut SchemaGenerator) -> Schema {
     })
 }
 
+/// Returns a standalone schema for the `toolsSettings` of an agent, with the settings of the native
+/// tools inlined rather than referenced.
+pub fn tools_settings_root_schema() -> Schema {
+    let mut generator = SchemaSettings::default()
+        .with(|settings| settings.inline_subschemas = true)
+        .into_generator();
+    let mut schema = tool_settings_schema(&mut generator);
+    if let Some(meta_schema) = &generator.settings().meta_schema {
+        schema.insert("$schema".to_string(), meta_schema.to_string().into());
+    }
+    schema.insert("title".to_string(), "ToolsSettings".into());
+    schema
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize, Eq, Hash, PartialEq, JsonSchema)]
 pub struct ResourcePath(
-    // You can extend this list via "|". e.g. r"^(file://|database://)"
-    #[schemars(regex(pattern = r"^(file://)"))]
+    // You can extend this list via "|". e.g. r"^!?(file://|database://)"
+    #[schemars(regex(pattern = r"^!?(file://)"))]
     String,
 );
 
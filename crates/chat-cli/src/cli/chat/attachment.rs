@@ -0,0 +1,216 @@
+use std::path::Path;
+
+use eyre::{
+    Result,
+    bail,
+};
+use serde::Serialize;
+use tokio::io::AsyncReadExt;
+use tokio::sync::OnceCell;
+
+use super::token_counter::TokenCounter;
+use crate::os::Os;
+
+/// Maximum number of bytes of a single attachment that are sent to the model. Anything past it
+/// is dropped and replaced by a truncation notice.
+pub const MAX_ATTACHMENT_BYTES: usize = 256 * 1024;
+
+/// Name used as the source of an attachment read from stdin.
+const STDIN_SOURCE: &str = "stdin";
+
+/// `--file` argument that attaches what is piped through stdin.
+pub const STDIN_FILE_ARG: &str = "-";
+
+/// Content attached to the first prompt of a non-interactive session, either piped through stdin
+/// or passed with `--file`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Attachment {
+    /// `stdin`, or the path of the attached file
+    pub source: String,
+    /// Content sent to the model, at most [MAX_ATTACHMENT_BYTES] long
+    pub content: String,
+    /// Size of the original content in bytes
+    pub bytes: usize,
+    pub truncated: bool,
+}
+
+impl Attachment {
+    /// Creates an attachment from raw bytes, rejecting content that is not text.
+    pub fn new(source: impl Into<String>, bytes: Vec<u8>) -> Result<Self> {
+        let source = source.into();
+        if bytes.contains(&0) {
+            bail!("{source} contains binary data, only text can be attached to the prompt");
+        }
+        let Ok(mut content) = String::from_utf8(bytes) else {
+            bail!("{source} is not valid UTF-8, only text can be attached to the prompt");
+        };
+
+        let size = content.len();
+        let truncated = size > MAX_ATTACHMENT_BYTES;
+        if truncated {
+            let mut end = MAX_ATTACHMENT_BYTES;
+            while !content.is_char_boundary(end) {
+                end -= 1;
+            }
+            content.truncate(end);
+        }
+
+        Ok(Self {
+            source,
+            content,
+            bytes: size,
+            truncated,
+        })
+    }
+
+    /// Reads everything piped through stdin. Returns [None] when stdin is a terminal or nothing
+    /// but whitespace was piped.
+    pub async fn from_stdin() -> Result<Option<Self>> {
+        let Some(buffer) = piped_stdin().await? else {
+            return Ok(None);
+        };
+        if buffer.iter().all(u8::is_ascii_whitespace) {
+            return Ok(None);
+        }
+        Self::new(STDIN_SOURCE, buffer.to_vec()).map(Some)
+    }
+
+    pub async fn from_file(os: &Os, path: &Path) -> Result<Self> {
+        let source = path.to_string_lossy().to_string();
+        match os.fs.read(path).await {
+            Ok(bytes) => Self::new(source, bytes),
+            Err(err) => bail!("Failed to read {source}: {err}"),
+        }
+    }
+
+    /// The attachment as a delimited block that is appended to the prompt.
+    fn to_context_block(&self) -> String {
+        let mut block = format!("--- ATTACHMENT BEGIN: {} ---\n{}", self.source, self.content);
+        if !block.ends_with('\n') {
+            block.push('\n');
+        }
+        if self.truncated {
+            block.push_str(&format!(
+                "[Truncated: only the first {} of {} bytes are included]\n",
+                self.content.len(),
+                self.bytes
+            ));
+        }
+        block.push_str(&format!("--- ATTACHMENT END: {} ---\n", self.source));
+        block
+    }
+}
+
+/// Appends `attachments` to `prompt`, each in its own delimited block.
+pub fn prompt_with_attachments(prompt: &str, attachments: &[Attachment]) -> String {
+    let mut input = prompt.trim().to_string();
+    for attachment in attachments {
+        input.push_str("\n\n");
+        input.push_str(&attachment.to_context_block());
+    }
+    input
+}
+
+/// An [Attachment] as reported with `--output json`.
+#[derive(Debug, Serialize)]
+pub struct AttachmentRecord {
+    pub source: String,
+    /// Size of the original content in bytes
+    pub bytes: usize,
+    pub truncated: bool,
+    /// Estimated number of tokens the attachment adds to the prompt
+    pub tokens: usize,
+}
+
+impl From<&Attachment> for AttachmentRecord {
+    fn from(attachment: &Attachment) -> Self {
+        Self {
+            source: attachment.source.clone(),
+            bytes: attachment.bytes,
+            truncated: attachment.truncated,
+            tokens: TokenCounter::count_tokens(&attachment.to_context_block()),
+        }
+    }
+}
+
+/// Returns everything piped through stdin, or [None] when stdin is a terminal.
+///
+/// Stdin can only be read once, so its content is kept for the rest of the process. This lets
+/// `fs_read` read it with the `-` path after it was attached to the first prompt.
+pub async fn piped_stdin() -> Result<Option<&'static [u8]>> {
+    use std::io::IsTerminal;
+
+    static PIPED_STDIN: OnceCell<Vec<u8>> = OnceCell::const_new();
+    if let Some(buffer) = PIPED_STDIN.get() {
+        return Ok(Some(buffer));
+    }
+
+    if std::io::stdin().is_terminal() {
+        return Ok(None);
+    }
+    let buffer = PIPED_STDIN
+        .get_or_try_init(|| async {
+            let mut buffer = Vec::new();
+            tokio::io::stdin().read_to_end(&mut buffer).await.map(|_| buffer)
+        })
+        .await;
+    match buffer {
+        Ok(buffer) => Ok(Some(buffer)),
+        Err(err) => bail!("Error reading from stdin: {err}"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_attachment_rejects_binary() {
+        assert!(Attachment::new("stdin", vec![0x89, b'P', b'N', b'G', 0, 0]).is_err());
+        assert!(Attachment::new("stdin", vec![0xff, 0xfe, b'a']).is_err());
+        assert!(Attachment::new("stdin", b"diff --git a/x b/x\n".to_vec()).is_ok());
+    }
+
+    #[test]
+    fn test_attachment_truncation() {
+        // Multi-byte characters so that the cap does not fall on a char boundary
+        let content = format!("a{}", "é".repeat(MAX_ATTACHMENT_BYTES));
+        let attachment = Attachment::new("big.txt", content.into_bytes()).unwrap();
+        assert!(attachment.truncated);
+        assert_eq!(attachment.bytes, MAX_ATTACHMENT_BYTES * 2 + 1);
+        assert_eq!(attachment.content.len(), MAX_ATTACHMENT_BYTES - 1);
+        assert!(attachment.to_context_block().contains(&format!(
+            "[Truncated: only the first {} of {} bytes are included]",
+            MAX_ATTACHMENT_BYTES - 1,
+            MAX_ATTACHMENT_BYTES * 2 + 1
+        )));
+
+        let attachment = Attachment::new("small.txt", b"hello".to_vec()).unwrap();
+        assert!(!attachment.truncated);
+        assert_eq!(attachment.content, "hello");
+    }
+
+    #[test]
+    fn test_prompt_with_attachments() {
+        let attachments = vec![
+            Attachment::new("stdin", b"+ added line\n".to_vec()).unwrap(),
+            Attachment::new("/notes.md", b"notes".to_vec()).unwrap(),
+        ];
+        assert_eq!(
+            prompt_with_attachments("review this diff\n", &attachments),
+            "review this diff\n\n\
+            --- ATTACHMENT BEGIN: stdin ---\n+ added line\n--- ATTACHMENT END: stdin ---\n\n\n\
+            --- ATTACHMENT BEGIN: /notes.md ---\nnotes\n--- ATTACHMENT END: /notes.md ---\n"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_attachment_from_file() {
+        let os = Os::new().await.unwrap();
+        os.fs.write("/notes.md", "notes").await.unwrap();
+        let attachment = Attachment::from_file(&os, Path::new("/notes.md")).await.unwrap();
+        assert_eq!(attachment.source, "/notes.md");
+        assert_eq!(attachment.content, "notes");
+        assert!(Attachment::from_file(&os, Path::new("/missing.md")).await.is_err());
+    }
+}
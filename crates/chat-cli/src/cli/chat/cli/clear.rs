@@ -17,16 +17,24 @@ use crate::cli::chat::{
 
 #[deny(missing_docs)]
 #[derive(Debug, PartialEq, Args)]
-pub struct ClearArgs;
+pub struct ClearArgs {
+    /// Only clear the conversation history, keeping the already loaded context from hooks and
+    /// agent resources
+    #[arg(long)]
+    keep_context: bool,
+}
 
 impl ClearArgs {
     pub async fn execute(self, session: &mut ChatSession) -> Result<ChatState, ChatError> {
+        let prompt = if self.keep_context {
+            "\nAre you sure? This will erase the conversation history for the current session. Loaded context will be kept. "
+        } else {
+            "\nAre you sure? This will erase the conversation history and context from hooks for the current session. "
+        };
         execute!(
             session.stderr,
             style::SetForegroundColor(Color::DarkGrey),
-            style::Print(
-                "\nAre you sure? This will erase the conversation history and context from hooks for the current session. "
-            ),
+            style::Print(prompt),
             style::Print("["),
             style::SetForegroundColor(Color::Green),
             style::Print("y"),
@@ -48,7 +56,9 @@ impl ClearArgs {
 
         if ["y", "Y"].contains(&user_input.as_str()) {
             session.conversation.clear(true);
-            if let Some(cm) = session.conversation.context_manager.as_mut() {
+            // Keeping the hook cache means agent spawn hooks aren't re-run on the next request,
+            // so their output is reused rather than regenerated.
+            if let (false, Some(cm)) = (self.keep_context, session.conversation.context_manager.as_mut()) {
                 cm.hook_executor.cache.clear();
             }
             execute!(
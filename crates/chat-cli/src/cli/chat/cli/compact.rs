@@ -1,7 +1,13 @@
 use clap::Args;
+use crossterm::execute;
+use crossterm::style::{
+    self,
+    Color,
+};
 
 use crate::cli::chat::consts::MAX_USER_MESSAGE_SIZE;
 use crate::cli::chat::message::UserMessageContent;
+use crate::cli::chat::token_counter::TokenCount;
 use crate::cli::chat::{
     ChatError,
     ChatSession,
@@ -28,7 +34,13 @@ How it works
 • Clears the conversation history to free up space
 • The assistant will reference the summary context in future responses
 
-Compaction will be automatically performed whenever the context window overflows.
+Options
+• --preserve <topic> keeps full detail about a topic in the summary (can be repeated)
+• --min-tokens <N> skips compaction if the context is smaller than N tokens
+
+Compaction will be automatically performed whenever the context window overflows, and before
+sending a message once the conversation reaches 90% of the context window.
+To change that threshold, run: `q settings chat.autoCompactThreshold <percent>` (0 to only compact on overflow)
 To disable this behavior, run: `q settings chat.disableAutoCompaction true`"
 )]
 pub struct CompactArgs {
@@ -46,16 +58,36 @@ pub struct CompactArgs {
     /// truncate_large_messages to be set.
     #[arg(long, requires = "truncate_large_messages")]
     max_message_length: Option<usize>,
+    /// A topic that the summary should retain in full detail. Can be specified multiple times.
+    #[arg(long, value_name = "TOPIC")]
+    preserve: Vec<String>,
+    /// Refuse to compact if the current context is smaller than this many tokens.
+    #[arg(long, value_name = "N")]
+    min_tokens: Option<usize>,
 }
 
 impl CompactArgs {
     pub async fn execute(self, os: &mut Os, session: &mut ChatSession) -> Result<ChatState, ChatError> {
+        if let Some(min_tokens) = self.min_tokens {
+            let token_count: TokenCount = session.conversation.calculate_char_count(os).await?.into();
+            if token_count.value() < min_tokens {
+                execute!(
+                    session.stderr,
+                    style::SetForegroundColor(Color::Yellow),
+                    style::Print(format!(
+                        "\nSkipping compaction: the context is ~{} tokens, below the minimum of {}.\n\n",
+                        token_count, min_tokens
+                    )),
+                    style::SetForegroundColor(Color::Reset)
+                )?;
+                return Ok(ChatState::PromptUser {
+                    skip_printing_tools: true,
+                });
+            }
+        }
+
         let default = CompactStrategy::default();
-        let prompt = if self.prompt.is_empty() {
-            None
-        } else {
-            Some(self.prompt.join(" "))
-        };
+        let prompt = self.summary_prompt();
 
         // Compact interrupts the current conversation so this will always result in a new user
         // turn.
@@ -71,6 +103,26 @@ impl CompactArgs {
             })
             .await
     }
+
+    /// The custom instructions for the summary, combining the free-form prompt with any topics
+    /// to preserve.
+    fn summary_prompt(&self) -> Option<String> {
+        let mut instructions = Vec::new();
+        if !self.prompt.is_empty() {
+            instructions.push(self.prompt.join(" "));
+        }
+        instructions.extend(
+            self.preserve
+                .iter()
+                .map(|topic| format!("ensure the summary retains full detail about: {}", topic)),
+        );
+
+        if instructions.is_empty() {
+            None
+        } else {
+            Some(instructions.join("\n"))
+        }
+    }
 }
 
 /// Parameters for performing the history compaction request.
@@ -93,3 +145,49 @@ impl Default for CompactStrategy {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use clap::Parser;
+
+    use super::*;
+
+    #[derive(Parser)]
+    struct TestCli {
+        #[command(flatten)]
+        args: CompactArgs,
+    }
+
+    fn parse(args: &[&str]) -> CompactArgs {
+        TestCli::try_parse_from(std::iter::once("compact").chain(args.iter().copied()))
+            .unwrap()
+            .args
+    }
+
+    #[test]
+    fn test_summary_prompt() {
+        assert_eq!(parse(&[]).summary_prompt(), None);
+        assert_eq!(
+            parse(&["focus", "on", "tests"]).summary_prompt(),
+            Some("focus on tests".to_string())
+        );
+        assert_eq!(
+            parse(&["--preserve", "auth flow", "--preserve", "db schema"]).summary_prompt(),
+            Some(
+                "ensure the summary retains full detail about: auth flow\n\
+                 ensure the summary retains full detail about: db schema"
+                    .to_string()
+            )
+        );
+        assert_eq!(
+            parse(&["be brief", "--preserve", "auth flow"]).summary_prompt(),
+            Some("be brief\nensure the summary retains full detail about: auth flow".to_string())
+        );
+    }
+
+    #[test]
+    fn test_min_tokens() {
+        assert_eq!(parse(&["--min-tokens", "5000"]).min_tokens, Some(5000));
+        assert_eq!(parse(&[]).min_tokens, None);
+    }
+}
@@ -14,9 +14,12 @@ use crate::cli::chat::consts::AGENT_FORMAT_HOOKS_DOC_URL;
 use crate::cli::chat::context::{
     ContextFilePath,
     calc_max_context_files_size,
+    exclusion_matcher,
 };
 use crate::cli::chat::token_counter::TokenCounter;
+use crate::cli::chat::tools::plain_output;
 use crate::cli::chat::util::drop_matched_context_files;
+use crate::cli::chat::util::ui::glyph;
 use crate::cli::chat::{
     ChatError,
     ChatSession,
@@ -46,14 +49,22 @@ pub enum ContextSubcommand {
         #[arg(long)]
         expand: bool,
     },
-    /// Add context rules (filenames or glob patterns)
+    /// Add context rules (filenames or glob patterns) or MCP resources (@server:resource-uri)
     Add {
         /// Include even if matched files exceed size limits
         #[arg(short, long)]
         force: bool,
+        /// Watch the added files and reload them when they change (at most 10 paths per session)
+        #[arg(short, long)]
+        watch: bool,
         #[arg(required = true)]
         paths: Vec<String>,
     },
+    /// List the resources offered by connected MCP servers
+    Resources {
+        /// Only list resources from this server
+        server: Option<String>,
+    },
     /// Remove specified rules
     #[command(alias = "rm")]
     Remove {
@@ -96,7 +107,11 @@ impl ContextSubcommand {
                     session.stderr,
                     style::SetAttribute(Attribute::Bold),
                     style::SetForegroundColor(Color::Magenta),
-                    style::Print(format!("👤 Agent ({}):\n", context_manager.current_profile)),
+                    style::Print(format!(
+                        "{}Agent ({}):\n",
+                        glyph(plain_output(os), "👤 ", ""),
+                        context_manager.current_profile
+                    )),
                     style::SetAttribute(Attribute::Reset),
                 )?;
 
@@ -110,6 +125,22 @@ impl ContextSubcommand {
                 } else {
                     for path in &agent_owned_list {
                         execute!(session.stderr, style::Print(format!("    {} ", path.get_path_as_str())))?;
+                        if let Some(pattern) = path.exclusion() {
+                            // Exclusions apply to the files matched by the resources before them
+                            if let Ok(matcher) = exclusion_matcher(os, pattern) {
+                                let before = profile_context_files.len();
+                                profile_context_files.retain(|(path, _, _)| !matcher.is_match(path));
+                                let excluded = before - profile_context_files.len();
+                                execute!(
+                                    session.stderr,
+                                    style::SetForegroundColor(Color::DarkGrey),
+                                    style::Print(format!("({excluded} excluded)")),
+                                    style::SetForegroundColor(Color::Reset)
+                                )?;
+                            }
+                            execute!(session.stderr, style::Print("\n"))?;
+                            continue;
+                        }
                         if let Ok(context_files) = context_manager
                             .get_context_files_by_path(os, path.get_path_as_str())
                             .await
@@ -136,11 +167,11 @@ impl ContextSubcommand {
                     session.stderr,
                     style::SetAttribute(Attribute::Bold),
                     style::SetForegroundColor(Color::Magenta),
-                    style::Print("💬 Session (temporary):\n"),
+                    style::Print(format!("{}Session (temporary):\n", glyph(plain_output(os), "💬 ", ""))),
                     style::SetAttribute(Attribute::Reset),
                 )?;
 
-                if session_owned_list.is_empty() {
+                if session_owned_list.is_empty() && context_manager.mcp_resources.is_empty() {
                     execute!(
                         session.stderr,
                         style::SetForegroundColor(Color::DarkGrey),
@@ -169,6 +200,16 @@ impl ContextSubcommand {
                         }
                         execute!(session.stderr, style::Print("\n"))?;
                     }
+                    for (reference, content) in &context_manager.mcp_resources {
+                        execute!(
+                            session.stderr,
+                            style::Print(format!("    {} ", reference)),
+                            style::SetForegroundColor(Color::Green),
+                            style::Print("(mcp resource)\n"),
+                            style::SetForegroundColor(Color::Reset)
+                        )?;
+                        profile_context_files.insert((reference.clone(), content.clone(), true));
+                    }
                     execute!(session.stderr, style::Print("\n"))?;
                 }
 
@@ -200,7 +241,11 @@ impl ContextSubcommand {
 
                     for (filename, content, is_temporary) in &profile_context_files {
                         let est_tokens = TokenCounter::count_tokens(content);
-                        let icon = if *is_temporary { "💬" } else { "👤" };
+                        let icon = if *is_temporary {
+                            glyph(plain_output(os), "💬", "[session]")
+                        } else {
+                            glyph(plain_output(os), "👤", "[agent]")
+                        };
                         execute!(
                             session.stderr,
                             style::Print(format!("{} {} ", icon, filename)),
@@ -231,9 +276,40 @@ impl ContextSubcommand {
 
                     execute!(
                         session.stderr,
-                        style::Print(format!("\nTotal: ~{} tokens\n\n", total_tokens))
+                        style::Print(format!("\nTotal: ~{} tokens\n", total_tokens))
                     )?;
 
+                    // Files added in session are never skipped but still count toward the totals
+                    if let Ok((files_in_use, skipped)) = context_manager.resolve_context_files(os).await {
+                        let total_bytes = files_in_use.iter().map(|(_, content)| content.len()).sum::<usize>();
+                        execute!(
+                            session.stderr,
+                            style::SetForegroundColor(Color::DarkGrey),
+                            style::Print(format!(
+                                "Resource limits: {}/{} files, {}/{} KiB\n\n",
+                                files_in_use.len(),
+                                context_manager.max_resource_files,
+                                total_bytes.div_ceil(1024),
+                                context_manager.max_resource_bytes / 1024
+                            )),
+                            style::SetForegroundColor(Color::Reset)
+                        )?;
+                        if let Some((top_glob, matches)) = skipped.top_glob {
+                            execute!(
+                                session.stderr,
+                                style::SetForegroundColor(Color::DarkYellow),
+                                style::Print(format!(
+                                    "{} agent resource file{} not included because the resource limits were reached. '{}' matched the most files ({}). Consider narrowing it or raising maxResourceFiles / maxResourceBytes in the agent config.\n\n",
+                                    skipped.count,
+                                    if skipped.count == 1 { " is" } else { "s are" },
+                                    top_glob,
+                                    matches
+                                )),
+                                style::SetForegroundColor(Color::Reset)
+                            )?;
+                        }
+                    }
+
                     if let Some(dropped_files) = dropped_files {
                         if !dropped_files.is_empty() {
                             execute!(
@@ -294,27 +370,137 @@ impl ContextSubcommand {
                     }
                 }
             },
-            Self::Add { force, paths } => match context_manager.add_paths(os, paths.clone(), force).await {
-                Ok(_) => {
+            Self::Add { force, watch, paths } => {
+                let (resources, paths) = paths
+                    .into_iter()
+                    .partition::<Vec<_>, _>(|p| parse_resource_reference(p).is_some());
+
+                for reference in resources {
+                    let Some((server_name, uri)) = parse_resource_reference(&reference) else {
+                        continue;
+                    };
+                    let result = match session.conversation.tool_manager.read_resource(server_name, uri).await {
+                        Ok(content) => context_manager.add_mcp_resource(reference.clone(), content),
+                        Err(e) => Err(eyre::eyre!(e)),
+                    };
+                    match result {
+                        Ok(_) => {
+                            execute!(
+                                session.stderr,
+                                style::SetForegroundColor(Color::Green),
+                                style::Print(format!("\nAdded resource {} to context.\n", reference)),
+                                style::SetForegroundColor(Color::Reset)
+                            )?;
+                        },
+                        Err(e) => {
+                            execute!(
+                                session.stderr,
+                                style::SetForegroundColor(Color::Red),
+                                style::Print(format!("\nError adding {}: {}\n", reference, e)),
+                                style::SetForegroundColor(Color::Reset)
+                            )?;
+                        },
+                    }
+                }
+
+                if !paths.is_empty() {
+                    match context_manager.add_paths(os, paths.clone(), force).await {
+                        Ok(_) => {
+                            execute!(
+                                session.stderr,
+                                style::SetForegroundColor(Color::Green),
+                                style::Print(format!("\nAdded {} path(s) to context.\n", paths.len())),
+                                style::SetForegroundColor(Color::Reset)
+                            )?;
+                            if watch {
+                                for path in paths {
+                                    if let Err(e) = session
+                                        .context_watcher
+                                        .watch(os, path.clone(), &context_manager.watched_files)
+                                        .await
+                                    {
+                                        execute!(
+                                            session.stderr,
+                                            style::SetForegroundColor(Color::Red),
+                                            style::Print(format!("Not watching {}: {}\n", path, e)),
+                                            style::SetForegroundColor(Color::Reset)
+                                        )?;
+                                    }
+                                }
+                            }
+                        },
+                        Err(e) => {
+                            execute!(
+                                session.stderr,
+                                style::SetForegroundColor(Color::Red),
+                                style::Print(format!("\nError: {}\n", e)),
+                                style::SetForegroundColor(Color::Reset)
+                            )?;
+                        },
+                    }
+                }
+
+                execute!(
+                    session.stderr,
+                    style::SetForegroundColor(Color::Green),
+                    style::Print("Note: Context modifications via slash command is temporary.\n\n"),
+                    style::SetForegroundColor(Color::Reset)
+                )?;
+            },
+            Self::Resources { server } => {
+                let resources = session
+                    .conversation
+                    .tool_manager
+                    .list_resources(server.as_deref())
+                    .await;
+                if resources.iter().all(|(_, resources)| resources.is_empty()) {
                     execute!(
                         session.stderr,
-                        style::SetForegroundColor(Color::Green),
-                        style::Print(format!("\nAdded {} path(s) to context.\n", paths.len())),
-                        style::Print("Note: Context modifications via slash command is temporary.\n\n"),
+                        style::SetForegroundColor(Color::DarkGrey),
+                        style::Print(match &server {
+                            Some(server) => format!("\nNo resources offered by {}.\n\n", server),
+                            None => "\nNo resources offered by connected servers.\n\n".to_string(),
+                        }),
                         style::SetForegroundColor(Color::Reset)
                     )?;
-                },
-                Err(e) => {
+                } else {
+                    for (server_name, resources) in resources.iter().filter(|(_, r)| !r.is_empty()) {
+                        execute!(
+                            session.stderr,
+                            style::Print("\n"),
+                            style::SetAttribute(Attribute::Bold),
+                            style::SetForegroundColor(Color::Magenta),
+                            style::Print(format!("{}:\n", server_name)),
+                            style::SetAttribute(Attribute::Reset),
+                            style::SetForegroundColor(Color::Reset),
+                        )?;
+                        for resource in resources {
+                            execute!(
+                                session.stderr,
+                                style::Print(format!("    @{}:{} ", server_name, resource.uri)),
+                                style::SetForegroundColor(Color::DarkGrey),
+                                style::Print(match &resource.mime_type {
+                                    Some(mime_type) => format!("{} ({})", resource.name, mime_type),
+                                    None => resource.name.clone(),
+                                }),
+                                style::SetForegroundColor(Color::Reset),
+                                style::Print("\n"),
+                            )?;
+                        }
+                    }
                     execute!(
                         session.stderr,
-                        style::SetForegroundColor(Color::Red),
-                        style::Print(format!("\nError: {}\n\n", e)),
+                        style::SetForegroundColor(Color::DarkGrey),
+                        style::Print("\nUse /context add @server:resource-uri to add a resource to context.\n\n"),
                         style::SetForegroundColor(Color::Reset)
                     )?;
-                },
+                }
             },
             Self::Remove { paths } => match context_manager.remove_paths(paths.clone()) {
                 Ok(_) => {
+                    for path in &paths {
+                        session.context_watcher.unwatch(path);
+                    }
                     execute!(
                         session.stderr,
                         style::SetForegroundColor(Color::Green),
@@ -334,6 +520,7 @@ impl ContextSubcommand {
             },
             Self::Clear => {
                 context_manager.clear();
+                session.context_watcher.clear();
                 execute!(
                     session.stderr,
                     style::SetForegroundColor(Color::Green),
@@ -366,9 +553,33 @@ impl ContextSubcommand {
         match self {
             ContextSubcommand::Show { .. } => "show",
             ContextSubcommand::Add { .. } => "add",
+            ContextSubcommand::Resources { .. } => "resources",
             ContextSubcommand::Remove { .. } => "remove",
             ContextSubcommand::Clear => "clear",
             ContextSubcommand::Hooks => "hooks",
         }
     }
 }
+
+/// Splits a reference of the form `@server:resource-uri` into its server name and uri.
+fn parse_resource_reference(reference: &str) -> Option<(&str, &str)> {
+    let (server_name, uri) = reference.strip_prefix('@')?.split_once(':')?;
+    (!server_name.is_empty() && !uri.is_empty()).then_some((server_name, uri))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_resource_reference() {
+        assert_eq!(
+            parse_resource_reference("@docs:file:///readme.md"),
+            Some(("docs", "file:///readme.md"))
+        );
+        assert_eq!(parse_resource_reference("@docs:"), None);
+        assert_eq!(parse_resource_reference("@:file:///readme.md"), None);
+        assert_eq!(parse_resource_reference("docs:file:///readme.md"), None);
+        assert_eq!(parse_resource_reference("src/**/*.rs"), None);
+    }
+}
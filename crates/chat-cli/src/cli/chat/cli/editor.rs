@@ -9,10 +9,13 @@ use uuid::Uuid;
 
 use crate::cli::chat::{
     ChatError,
+    ChatErrorKind,
     ChatSession,
     ChatState,
 };
 
+const EDITOR_HINT: &str = "Set the EDITOR environment variable to the command of an installed editor";
+
 #[deny(missing_docs)]
 #[derive(Debug, PartialEq, Args)]
 pub struct EditorArgs {
@@ -33,7 +36,10 @@ impl EditorArgs {
                 execute!(
                     session.stderr,
                     style::SetForegroundColor(Color::Red),
-                    style::Print(format!("\nError opening editor: {}\n\n", err)),
+                    style::Print(format!("\nError opening editor: {}\n", err)),
+                    style::SetForegroundColor(Color::DarkGrey),
+                    style::Print(err.hint().map(|hint| format!("{hint}\n")).unwrap_or_default()),
+                    style::Print("\n"),
                     style::SetForegroundColor(Color::Reset)
                 )?;
 
@@ -84,27 +90,35 @@ impl EditorArgs {
 
 /// Opens the user's preferred editor to compose a prompt
 pub fn open_editor(initial_text: Option<String>) -> Result<String, ChatError> {
-    // Create a temporary file with a unique name
-    let temp_dir = std::env::temp_dir();
     let file_name = format!("q_prompt_{}.md", Uuid::new_v4());
-    let temp_file_path = temp_dir.join(file_name);
+    let content = edit_in_editor(&file_name, &initial_text.unwrap_or_default())?;
+    Ok(content.trim().to_string())
+}
+
+/// Opens the user's preferred editor on a temporary file named `file_name` that is pre-populated
+/// with `initial_content`, returning the content of the file once the editor exits.
+pub fn edit_in_editor(file_name: &str, initial_content: &str) -> Result<String, ChatError> {
+    let temp_file_path = std::env::temp_dir().join(file_name);
 
     // Get the editor from environment variable or use a default
     let editor_cmd = std::env::var("EDITOR").unwrap_or_else(|_| "vi".to_string());
 
     // Parse the editor command to handle arguments
-    let mut parts =
-        shlex::split(&editor_cmd).ok_or_else(|| ChatError::Custom("Failed to parse EDITOR command".into()))?;
+    let mut parts = shlex::split(&editor_cmd)
+        .ok_or_else(|| ChatError::with_hint(ChatErrorKind::Internal, "Failed to parse EDITOR command", EDITOR_HINT))?;
 
     if parts.is_empty() {
-        return Err(ChatError::Custom("EDITOR environment variable is empty".into()));
+        return Err(ChatError::with_hint(
+            ChatErrorKind::Internal,
+            "EDITOR environment variable is empty",
+            EDITOR_HINT,
+        ));
     }
 
     let editor_bin = parts.remove(0);
 
-    // Write initial content to the file if provided
-    let initial_content = initial_text.unwrap_or_default();
-    std::fs::write(&temp_file_path, &initial_content)
+    // Write initial content to the file
+    std::fs::write(&temp_file_path, initial_content)
         .map_err(|e| ChatError::Custom(format!("Failed to create temporary file: {}", e).into()))?;
 
     // Open the editor with the parsed command and arguments
@@ -117,9 +131,10 @@ pub fn open_editor(initial_text: Option<String>) -> Result<String, ChatError> {
     let status = cmd
         .arg(&temp_file_path)
         .status()
-        .map_err(|e| ChatError::Custom(format!("Failed to open editor: {}", e).into()))?;
+        .map_err(|e| ChatError::with_hint(ChatErrorKind::Io, format!("Failed to open editor: {}", e), EDITOR_HINT))?;
 
     if !status.success() {
+        let _ = std::fs::remove_file(&temp_file_path);
         return Err(ChatError::Custom("Editor exited with non-zero status".into()));
     }
 
@@ -130,5 +145,5 @@ pub fn open_editor(initial_text: Option<String>) -> Result<String, ChatError> {
     // Clean up the temporary file
     let _ = std::fs::remove_file(&temp_file_path);
 
-    Ok(content.trim().to_string())
+    Ok(content)
 }
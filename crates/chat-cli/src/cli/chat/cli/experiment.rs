@@ -13,6 +13,8 @@ use crossterm::{
 use dialoguer::Select;
 
 use crate::cli::chat::conversation::format_tool_spec;
+use crate::cli::chat::tools::plain_output;
+use crate::cli::chat::util::ui::glyph;
 use crate::cli::chat::{
     ChatError,
     ChatSession,
@@ -90,7 +92,8 @@ async fn select_experiment(os: &mut Os, session: &mut ChatSession) -> Result<Opt
     queue!(
         session.stderr,
         style::SetForegroundColor(Color::Yellow),
-        style::Print("⚠ Experimental features may be changed or removed at any time\n\n"),
+        style::Print(glyph(plain_output(os), "⚠ ", "Warning: ")),
+        style::Print("Experimental features may be changed or removed at any time\n\n"),
         style::ResetColor,
     )?;
 
@@ -1,4 +1,8 @@
-use std::collections::HashMap;
+use std::collections::{
+    HashMap,
+    HashSet,
+};
+use std::ffi::OsStr;
 use std::io::Write;
 use std::process::Stdio;
 use std::time::{
@@ -28,22 +32,35 @@ use futures::stream::{
     FuturesUnordered,
     StreamExt,
 };
-use spinners::{
-    Spinner,
-    Spinners,
-};
+use spinners::Spinners;
 
 use crate::cli::agent::hook::{
     Hook,
+    HookFailurePolicy,
     HookTrigger,
 };
 use crate::cli::chat::consts::AGENT_FORMAT_HOOKS_DOC_URL;
-use crate::cli::chat::util::truncate_safe;
+use crate::cli::chat::util::ui::{
+    glyph,
+    start_spinner,
+};
+use crate::cli::chat::util::{
+    truncate_safe,
+    truncate_safe_start,
+};
 use crate::cli::chat::{
     ChatError,
     ChatSession,
     ChatState,
 };
+use crate::util::env_var::QCLI_AGENT_PATH;
+use crate::util::process::{
+    Pid,
+    terminate_process_tree,
+};
+
+/// Number of bytes of stderr shown when a hook fails
+const HOOK_STDERR_SNIPPET_LENGTH: usize = 512;
 
 #[derive(Debug, Clone)]
 pub struct CachedHook {
@@ -51,30 +68,62 @@ pub struct CachedHook {
     expiry: Option<Instant>,
 }
 
+/// The last time a hook ran
+#[derive(Debug, Clone)]
+pub struct HookRun {
+    pub finished: Instant,
+    pub duration: Duration,
+    /// Size of the output in bytes, or why the hook failed
+    pub result: Result<usize, String>,
+}
+
 /// Maps a hook name to a [`CachedHook`]
 #[derive(Debug, Clone, Default)]
 pub struct HookExecutor {
     pub cache: HashMap<(HookTrigger, Hook), CachedHook>,
+    /// The last run of each hook, whether it succeeded or not
+    runs: HashMap<(HookTrigger, Hook), HookRun>,
+    /// Hooks that have already printed a failure warning this session
+    warned: HashSet<(HookTrigger, Hook)>,
 }
 
 impl HookExecutor {
     pub fn new() -> Self {
-        Self { cache: HashMap::new() }
+        Self {
+            cache: HashMap::new(),
+            runs: HashMap::new(),
+            warned: HashSet::new(),
+        }
+    }
+
+    /// Returns the last time the hook ran, if it ran in this session.
+    pub fn last_run(&self, trigger: HookTrigger, hook: &Hook) -> Option<&HookRun> {
+        self.runs.get(&(trigger, hook.clone()))
     }
 
     /// Run and cache [`Hook`]s. Any hooks that are already cached will be returned without
-    /// executing. Hooks that fail to execute will not be returned. Returned hook order is
-    /// undefined.
+    /// executing. Hooks that fail to execute will not be returned and are handled according to
+    /// their [`HookFailurePolicy`]: a warning is printed only the first time a given hook fails in
+    /// the session, and a failed blocking hook fails the whole run with
+    /// [`ChatError::HookBlocked`] once every hook has finished. Returned hook order is undefined.
     ///
     /// If `updates` is `Some`, progress on hook execution will be written to it.
     /// Errors encountered with write operations to `updates` are ignored.
     ///
+    /// Each of `prompt_variables` is set as a `PROMPT_VAR_<name>` environment variable, and
+    /// `agent_path`, if given, as `QCLI_AGENT_PATH`.
+    ///
+    /// No spinner is shown and the summary uses ASCII markers when `plain` is set.
+    ///
     /// Note: [`HookTrigger::AgentSpawn`] hooks never leave the cache.
     pub async fn run_hooks(
         &mut self,
         hooks: HashMap<HookTrigger, Vec<Hook>>,
         output: &mut impl Write,
         prompt: Option<&str>,
+        prompt_variables: &HashMap<String, String>,
+        agent_path: Option<&OsStr>,
+        plain: bool,
     ) -> Result<Vec<((HookTrigger, Hook), String)>, ChatError> {
         let mut cached = vec![];
         let mut futures = FuturesUnordered::new();
@@ -86,7 +135,7 @@ impl HookExecutor {
                 cached.push((hook.clone(), cache.clone()));
                 continue;
             }
-            futures.push(self.run_hook(hook, prompt));
+            futures.push(self.run_hook(hook, prompt, prompt_variables, agent_path));
         }
 
         let mut complete = 0;
@@ -101,11 +150,12 @@ impl HookExecutor {
         };
 
         if total != 0 {
-            spinner = Some(Spinner::new(Spinners::Dots12, spinner_text(complete, total)));
+            spinner = start_spinner(plain, Spinners::Dots12, spinner_text(complete, total));
         }
 
         // Process results as they complete
         let mut results = vec![];
+        let mut blocked = None;
         let start_time = Instant::now();
         while let Some((hook, result, duration)) = futures.next().await {
             // If output is enabled, handle that first
@@ -121,20 +171,41 @@ impl HookExecutor {
                 )?;
             }
 
+            self.runs.insert(hook.clone(), HookRun {
+                finished: Instant::now(),
+                duration,
+                result: match &result {
+                    Ok(output) => Ok(output.len()),
+                    Err(err) => Err(err.to_string()),
+                },
+            });
+
             if let Err(err) = &result {
-                queue!(
-                    output,
-                    style::SetForegroundColor(style::Color::Red),
-                    style::Print("✗ "),
-                    style::SetForegroundColor(style::Color::Blue),
-                    style::Print(&hook.1.command),
-                    style::ResetColor,
-                    style::Print(" failed after "),
-                    style::SetForegroundColor(style::Color::Yellow),
-                    style::Print(format!("{:.2} s", duration.as_secs_f32())),
-                    style::ResetColor,
-                    style::Print(format!(": {}\n", err)),
-                )?;
+                match hook.1.on_failure {
+                    HookFailurePolicy::Warn => {
+                        if self.warned.insert(hook.clone()) {
+                            queue!(
+                                output,
+                                style::SetForegroundColor(style::Color::DarkGrey),
+                                style::Print(format!(
+                                    "Warning: hook '{}' failed after {:.2} s and its output was not added to context: {}\n",
+                                    &hook.1.command,
+                                    duration.as_secs_f32(),
+                                    err
+                                )),
+                                style::ResetColor,
+                            )?;
+                        }
+                    },
+                    HookFailurePolicy::Block => {
+                        blocked.get_or_insert(ChatError::HookBlocked {
+                            trigger: hook.0,
+                            command: hook.1.command.clone(),
+                            error: err.to_string(),
+                        });
+                    },
+                    HookFailurePolicy::Ignore => (),
+                }
             }
 
             // Process results regardless of output enabled
@@ -147,9 +218,9 @@ impl HookExecutor {
             // The futures set size decreases each time we process one
             if futures.is_empty() {
                 let symbol = if total == complete {
-                    "✓".to_string().green()
+                    glyph(plain, "✓", "[OK]").green()
                 } else {
-                    "✗".to_string().red()
+                    glyph(plain, "✗", "[FAIL]").red()
                 };
 
                 queue!(
@@ -161,7 +232,7 @@ impl HookExecutor {
                     style::ResetColor,
                 )?;
             } else {
-                spinner = Some(Spinner::new(Spinners::Dots, spinner_text(complete, total)));
+                spinner = start_spinner(plain, Spinners::Dots, spinner_text(complete, total));
             }
         }
         drop(futures);
@@ -177,6 +248,10 @@ impl HookExecutor {
             });
         }
 
+        if let Some(err) = blocked {
+            return Err(err);
+        }
+
         results.append(&mut cached);
 
         Ok(results)
@@ -186,6 +261,8 @@ impl HookExecutor {
         &self,
         hook: (HookTrigger, Hook),
         prompt: Option<&str>,
+        prompt_variables: &HashMap<String, String>,
+        agent_path: Option<&OsStr>,
     ) -> ((HookTrigger, Hook), Result<String>, Duration) {
         let start_time = Instant::now();
 
@@ -211,6 +288,12 @@ impl HookExecutor {
             .stdout(Stdio::piped())
             .stderr(Stdio::piped());
 
+        // The hook leads its own process group so that everything it started can be killed when
+        // it times out
+        #[cfg(unix)]
+        cmd.process_group(0);
+        cmd.kill_on_drop(true);
+
         let timeout = Duration::from_millis(hook.1.timeout_ms);
 
         // Set USER_PROMPT environment variable if provided
@@ -219,11 +302,27 @@ impl HookExecutor {
             let sanitized_prompt = sanitize_user_prompt(prompt);
             cmd.env("USER_PROMPT", sanitized_prompt);
         }
+        for (name, value) in prompt_variables {
+            cmd.env(format!("PROMPT_VAR_{name}"), value);
+        }
+        if let Some(agent_path) = agent_path {
+            cmd.env(QCLI_AGENT_PATH, agent_path);
+        }
 
-        let command_future = cmd.output();
+        let child = match cmd.spawn() {
+            Ok(child) => child,
+            Err(err) => {
+                return (
+                    hook,
+                    Err(eyre!("failed to execute command: {}", err)),
+                    start_time.elapsed(),
+                );
+            },
+        };
+        let pid = child.id().map(Pid::from_u32);
 
         // Run with timeout
-        let result = match tokio::time::timeout(timeout, command_future).await {
+        let result = match tokio::time::timeout(timeout, child.wait_with_output()).await {
             Ok(Ok(result)) => {
                 if result.status.success() {
                     let stdout = result.stdout.to_str_lossy();
@@ -238,11 +337,25 @@ impl HookExecutor {
                     );
                     Ok(stdout)
                 } else {
-                    Err(eyre!("command returned non-zero exit code: {}", result.status))
+                    let stderr = result.stderr.to_str_lossy();
+                    let stderr = stderr.trim();
+                    match stderr.is_empty() {
+                        true => Err(eyre!("command returned non-zero exit code: {}", result.status)),
+                        false => Err(eyre!(
+                            "command returned non-zero exit code: {}: {}",
+                            result.status,
+                            truncate_safe_start(stderr, HOOK_STDERR_SNIPPET_LENGTH)
+                        )),
+                    }
                 }
             },
             Ok(Err(err)) => Err(eyre!("failed to execute command: {}", err)),
-            Err(_) => Err(eyre!("command timed out after {} ms", timeout.as_millis())),
+            Err(_) => {
+                if let Some(pid) = pid {
+                    let _ = terminate_process_tree(pid);
+                }
+                Err(eyre!("command timed out after {} ms", timeout.as_millis()))
+            },
         };
 
         (hook, result, start_time.elapsed())
@@ -284,7 +397,10 @@ Refer to the documentation for how to configure hooks with your agent: https://g
 Notes:
 • Hooks are executed in parallel
 • 'conversation_start' hooks run on the first user prompt and are attached once to the conversation history sent to Amazon Q
-• 'per_prompt' hooks run on each user prompt and are attached to the prompt, but are not stored in conversation history"
+• 'per_prompt' hooks run on each user prompt and are attached to the prompt, but are not stored in conversation history
+• 'per_prompt' hook output is cached for the hook's cache_ttl_seconds, so rapid follow-up prompts reuse the last output
+• Hooks with inject_output set to false are run for their side effects only
+• Hooks that time out are killed along with the processes they started. on_failure decides whether a failed hook prints a warning, is ignored, or blocks the session (agent_spawn) or the prompt (per_prompt)"
 )]
 pub struct HooksArgs;
 
@@ -304,6 +420,37 @@ impl HooksArgs {
                 false => {
                     for hook in hooks {
                         writeln!(&mut out, "  - {}", hook.command)?;
+
+                        let ttl = match trigger {
+                            HookTrigger::AgentSpawn => "session".to_string(),
+                            HookTrigger::UserPromptSubmit => format!("{}s", hook.cache_ttl_seconds),
+                        };
+                        let last_run = match context_manager.hook_executor.last_run(*trigger, hook) {
+                            Some(run) => {
+                                let ago = run.finished.elapsed().as_secs();
+                                let took = run.duration.as_secs_f32();
+                                match &run.result {
+                                    Ok(size) => format!("last run {ago}s ago in {took:.2}s, output {size} bytes"),
+                                    Err(err) => format!("last run {ago}s ago, failed after {took:.2}s: {err}"),
+                                }
+                            },
+                            None => "not run yet".to_string(),
+                        };
+                        let inject = if hook.inject_output {
+                            ""
+                        } else {
+                            ", output not injected"
+                        };
+                        writeln!(
+                            &mut out,
+                            "    {}",
+                            format!(
+                                "ttl {ttl}, timeout {}ms, on failure {}{inject}",
+                                hook.timeout_ms, hook.on_failure
+                            )
+                            .dark_grey()
+                        )?;
+                        writeln!(&mut out, "    {}", last_run.dark_grey())?;
                     }
                 },
             }
@@ -329,3 +476,68 @@ impl HooksArgs {
         })
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cli::agent::hook::Source;
+
+    fn agent_spawn_hook(command: &str, timeout_ms: u64, on_failure: HookFailurePolicy) -> (HookTrigger, Hook) {
+        let mut hook = Hook::new(command.to_string(), Source::Agent);
+        hook.timeout_ms = timeout_ms;
+        hook.on_failure = on_failure;
+        (HookTrigger::AgentSpawn, hook)
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_run_hooks_timeout_and_failure_policy() {
+        async fn run(
+            executor: &mut HookExecutor,
+            (trigger, hook): &(HookTrigger, Hook),
+        ) -> (
+            Result<Vec<((HookTrigger, Hook), String)>, ChatError>,
+            String,
+            Option<HookRun>,
+        ) {
+            let mut output = Vec::new();
+            let hooks = HashMap::from([(*trigger, vec![hook.clone()])]);
+            let result = executor
+                .run_hooks(hooks, &mut output, None, &HashMap::new(), None, true)
+                .await;
+            let last_run = executor.last_run(*trigger, hook).cloned();
+            (result, String::from_utf8_lossy(&output).into_owned(), last_run)
+        }
+
+        let mut executor = HookExecutor::new();
+
+        // The hung hook and the sleep it started are killed once the timeout is reached
+        let start = Instant::now();
+        let hung = agent_spawn_hook("sleep 30 & sleep 30", 200, HookFailurePolicy::Warn);
+        let (result, output, last_run) = run(&mut executor, &hung).await;
+        assert!(result.unwrap().is_empty());
+        assert!(start.elapsed() < Duration::from_secs(10));
+        assert!(output.contains("timed out after 200 ms"), "{output}");
+        assert!(last_run.unwrap().result.is_err_and(|err| err.contains("timed out")));
+
+        let failing = agent_spawn_hook("echo oops >&2; exit 3", 5_000, HookFailurePolicy::Ignore);
+        let (result, output, last_run) = run(&mut executor, &failing).await;
+        assert!(result.unwrap().is_empty());
+        assert!(!output.contains("Warning"), "{output}");
+        assert!(last_run.unwrap().result.is_err_and(|err| err.contains("oops")));
+
+        let blocking = agent_spawn_hook("echo oops >&2; exit 3", 5_000, HookFailurePolicy::Block);
+        match run(&mut executor, &blocking).await.0 {
+            Err(ChatError::HookBlocked { trigger, error, .. }) => {
+                assert_eq!(trigger, HookTrigger::AgentSpawn);
+                assert!(error.contains("oops"), "{error}");
+            },
+            other => panic!("expected the hook to block, found {other:?}"),
+        }
+
+        let working = agent_spawn_hook("echo ok", 5_000, HookFailurePolicy::Block);
+        let (result, _, last_run) = run(&mut executor, &working).await;
+        assert_eq!(result.unwrap()[0].1, "ok\n");
+        assert_eq!(last_run.unwrap().result, Ok(3));
+    }
+}
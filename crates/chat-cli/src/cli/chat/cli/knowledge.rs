@@ -1,3 +1,4 @@
+use std::collections::HashMap;
 use std::io::Write;
 
 use clap::Subcommand;
@@ -7,12 +8,25 @@ use crossterm::style::{
     Color,
 };
 use eyre::Result;
+use semantic_search_client::types::IndexSince;
 use semantic_search_client::{
     OperationStatus,
     SystemStatus,
 };
 
-use crate::cli::chat::tools::sanitize_path_tool_arg;
+use crate::cli::chat::tools::knowledge::{
+    Knowledge,
+    KnowledgeAdd,
+    KnowledgeCancel,
+    KnowledgeClear,
+    KnowledgeRemove,
+    KnowledgeUpdate,
+};
+use crate::cli::chat::tools::{
+    plain_output,
+    sanitize_path_tool_arg,
+};
+use crate::cli::chat::util::ui::glyph;
 use crate::cli::chat::{
     ChatError,
     ChatSession,
@@ -20,6 +34,7 @@ use crate::cli::chat::{
 };
 use crate::database::settings::Setting;
 use crate::os::Os;
+use crate::util::knowledge_backend::KnowledgeBackend;
 use crate::util::knowledge_store::KnowledgeStore;
 
 /// Knowledge base management commands
@@ -44,7 +59,13 @@ pub enum KnowledgeSubcommand {
     #[command(alias = "rm")]
     Remove { path: String },
     /// Update a file or directory in knowledge base
-    Update { path: String },
+    Update {
+        path: String,
+        /// Only re-index files changed since a time (RFC 3339 or YYYY-MM-DD) or, in a git
+        /// repository, a git ref
+        #[arg(long)]
+        since: Option<IndexSince>,
+    },
     /// Remove all knowledge base entries
     Clear,
     /// Show background operation status
@@ -67,7 +88,7 @@ enum OperationResult {
 impl KnowledgeSubcommand {
     pub async fn execute(self, os: &Os, session: &mut ChatSession) -> Result<ChatState, ChatError> {
         if !Self::is_feature_enabled(os) {
-            Self::write_feature_disabled_message(session)?;
+            Self::write_feature_disabled_message(session, plain_output(os))?;
             return Ok(Self::default_chat_state());
         }
 
@@ -85,13 +106,14 @@ impl KnowledgeSubcommand {
             .unwrap_or(false)
     }
 
-    fn write_feature_disabled_message(session: &mut ChatSession) -> Result<(), std::io::Error> {
+    fn write_feature_disabled_message(session: &mut ChatSession, plain: bool) -> Result<(), std::io::Error> {
         queue!(
             session.stderr,
             style::SetForegroundColor(Color::Red),
             style::Print("\nKnowledge tool is disabled. Enable it with: q settings chat.enableKnowledge true\n"),
             style::SetForegroundColor(Color::Yellow),
-            style::Print("💡 Your knowledge base data is preserved and will be available when re-enabled.\n\n"),
+            style::Print(glyph(plain, "💡 ", "")),
+            style::Print("Your knowledge base data is preserved and will be available when re-enabled.\n\n"),
             style::SetForegroundColor(Color::Reset)
         )
     }
@@ -108,6 +130,10 @@ impl KnowledgeSubcommand {
     }
 
     async fn execute_operation(&self, os: &Os, session: &mut ChatSession) -> OperationResult {
+        if KnowledgeBackend::from_settings(os) == KnowledgeBackend::Sqlite {
+            return self.execute_sqlite_operation(os, session).await;
+        }
+
         match self {
             KnowledgeSubcommand::Show => {
                 match Self::handle_show(os, session).await {
@@ -122,7 +148,7 @@ impl KnowledgeSubcommand {
                 index_type,
             } => Self::handle_add(os, session, path, include, exclude, index_type).await,
             KnowledgeSubcommand::Remove { path } => Self::handle_remove(os, session, path).await,
-            KnowledgeSubcommand::Update { path } => Self::handle_update(os, session, path).await,
+            KnowledgeSubcommand::Update { path, since } => Self::handle_update(os, session, path, since.as_ref()).await,
             KnowledgeSubcommand::Clear => Self::handle_clear(os, session).await,
             KnowledgeSubcommand::Status => Self::handle_status(os, session).await,
             KnowledgeSubcommand::Cancel { operation_id } => {
@@ -131,6 +157,55 @@ impl KnowledgeSubcommand {
         }
     }
 
+    /// Runs the command against the sqlite backend the same way the knowledge tool does. Content
+    /// is indexed immediately and as a whole, so index options and `--since` do not apply.
+    async fn execute_sqlite_operation(&self, os: &Os, session: &mut ChatSession) -> OperationResult {
+        let knowledge = match self {
+            KnowledgeSubcommand::Show => Knowledge::Show,
+            KnowledgeSubcommand::Add { path, .. } => match Self::validate_and_sanitize_path(os, path) {
+                Ok(sanitized_path) => Knowledge::Add(KnowledgeAdd {
+                    name: path.clone(),
+                    value: sanitized_path,
+                }),
+                Err(e) => return OperationResult::Error(format!("Invalid path: {}", e)),
+            },
+            KnowledgeSubcommand::Remove { path } => Knowledge::Remove(KnowledgeRemove {
+                name: String::new(),
+                context_id: String::new(),
+                path: path.clone(),
+            }),
+            KnowledgeSubcommand::Update { path, .. } => Knowledge::Update(KnowledgeUpdate {
+                path: path.clone(),
+                context_id: String::new(),
+                // Entries added with /knowledge are named after the path they were added with
+                name: path.clone(),
+            }),
+            KnowledgeSubcommand::Clear => {
+                if let Err(result) = Self::confirm_clear(session, plain_output(os)) {
+                    return result;
+                }
+                Knowledge::Clear(KnowledgeClear { confirm: true })
+            },
+            KnowledgeSubcommand::Status => Knowledge::Status,
+            KnowledgeSubcommand::Cancel { operation_id } => Knowledge::Cancel(KnowledgeCancel {
+                operation_id: operation_id.clone().unwrap_or_else(|| "all".to_string()),
+            }),
+        };
+
+        let backend = KnowledgeBackend::Sqlite.implementation();
+        if let Err(e) = backend.validate(os, &knowledge).await {
+            return OperationResult::Error(e.to_string());
+        }
+        let agent = Self::get_agent(session);
+        match backend
+            .invoke(os, &knowledge, agent, session.conversation.conversation_id())
+            .await
+        {
+            Ok(message) => OperationResult::Info(message),
+            Err(e) => OperationResult::Error(format!("Failed to access knowledge base: {}", e)),
+        }
+    }
+
     async fn handle_show(os: &Os, session: &mut ChatSession) -> Result<(), std::io::Error> {
         let agent_name = Self::get_agent(session).map(|a| a.name.clone());
 
@@ -140,7 +215,7 @@ impl KnowledgeSubcommand {
                 session.stderr,
                 style::SetAttribute(crossterm::style::Attribute::Bold),
                 style::SetForegroundColor(Color::Magenta),
-                style::Print(format!("👤 Agent ({}):\n", agent)),
+                style::Print(format!("{}Agent ({}):\n", glyph(plain_output(os), "👤 ", ""), agent)),
                 style::SetAttribute(crossterm::style::Attribute::Reset),
             )?;
 
@@ -148,6 +223,11 @@ impl KnowledgeSubcommand {
                 Ok(store) => {
                     let store = store.lock().await;
                     let contexts = store.get_all().await.unwrap_or_default();
+                    let memory_usage = store
+                        .get_status_data()
+                        .await
+                        .map(|status| status.context_memory)
+                        .unwrap_or_default();
 
                     if contexts.is_empty() {
                         queue!(
@@ -157,7 +237,13 @@ impl KnowledgeSubcommand {
                             style::SetForegroundColor(Color::Reset)
                         )?;
                     } else {
-                        Self::format_knowledge_entries_with_indent(session, &contexts, "    ")?;
+                        Self::format_knowledge_entries_with_indent(
+                            session,
+                            &contexts,
+                            &memory_usage,
+                            "    ",
+                            plain_output(os),
+                        )?;
                     }
                 },
                 Err(_) => {
@@ -177,13 +263,22 @@ impl KnowledgeSubcommand {
     fn format_knowledge_entries_with_indent(
         session: &mut ChatSession,
         contexts: &[semantic_search_client::KnowledgeContext],
+        memory_usage: &HashMap<String, usize>,
         indent: &str,
+        plain: bool,
     ) -> Result<(), std::io::Error> {
         for ctx in contexts {
+            // Contexts are only loaded into memory once they are searched
+            let memory = match memory_usage.get(&ctx.id) {
+                Some(&bytes) if bytes >= 1024 * 1024 => format!("{:.1} MB in memory", bytes as f64 / (1024.0 * 1024.0)),
+                Some(&bytes) => format!("{:.1} KB in memory", bytes as f64 / 1024.0),
+                None => "not loaded".to_string(),
+            };
+
             // Main entry line with name and ID
             queue!(
                 session.stderr,
-                style::Print(format!("{}📂 ", indent)),
+                style::Print(format!("{}{}", indent, glyph(plain, "📂 ", ""))),
                 style::SetAttribute(style::Attribute::Bold),
                 style::SetForegroundColor(Color::Grey),
                 style::Print(&ctx.name),
@@ -217,6 +312,8 @@ impl KnowledgeSubcommand {
                 style::Print(" • "),
                 style::SetForegroundColor(Color::DarkGrey),
                 style::Print(format!("{}", ctx.updated_at.format("%m/%d %H:%M"))),
+                style::Print(" • "),
+                style::Print(memory),
                 style::SetForegroundColor(Color::Reset),
                 style::Print("\n\n")
             )?;
@@ -321,7 +418,7 @@ impl KnowledgeSubcommand {
     }
 
     /// Handle update operation
-    async fn handle_update(os: &Os, session: &ChatSession, path: &str) -> OperationResult {
+    async fn handle_update(os: &Os, session: &ChatSession, path: &str, since: Option<&IndexSince>) -> OperationResult {
         match Self::validate_and_sanitize_path(os, path) {
             Ok(sanitized_path) => {
                 let agent = Self::get_agent(session);
@@ -333,7 +430,11 @@ impl KnowledgeSubcommand {
                 };
                 let mut store = async_knowledge_store.lock().await;
 
-                match store.update_by_path(&sanitized_path).await {
+                let result = match since {
+                    Some(since) => store.update_by_path_since(&sanitized_path, since.clone()).await,
+                    None => store.update_by_path(&sanitized_path).await,
+                };
+                match result {
                     Ok(message) => OperationResult::Info(message),
                     Err(e) => OperationResult::Error(format!("Failed to update: {}", e)),
                 }
@@ -344,23 +445,8 @@ impl KnowledgeSubcommand {
 
     /// Handle clear operation
     async fn handle_clear(os: &Os, session: &mut ChatSession) -> OperationResult {
-        // Require confirmation
-        queue!(
-            session.stderr,
-            style::Print("⚠️  This action will remove all knowledge base entries.\n"),
-            style::Print("Clear the knowledge base? (y/N): ")
-        )
-        .unwrap();
-        session.stderr.flush().unwrap();
-
-        let mut input = String::new();
-        if std::io::stdin().read_line(&mut input).is_err() {
-            return OperationResult::Error("Failed to read input".to_string());
-        }
-
-        let input = input.trim().to_lowercase();
-        if input != "y" && input != "yes" {
-            return OperationResult::Info("Clear operation cancelled".to_string());
+        if let Err(result) = Self::confirm_clear(session, plain_output(os)) {
+            return result;
         }
 
         let agent = Self::get_agent(session);
@@ -373,13 +459,20 @@ impl KnowledgeSubcommand {
         // First, cancel any pending operations
         queue!(
             session.stderr,
-            style::Print("🛑 Cancelling any pending operations...\n")
+            style::Print(format!(
+                "{}Cancelling any pending operations...\n",
+                glyph(plain_output(os), "🛑 ", "")
+            ))
         )
         .unwrap();
         if let Err(e) = store.cancel_operation(None).await {
             queue!(
                 session.stderr,
-                style::Print(&format!("⚠️  Warning: Failed to cancel operations: {}\n", e))
+                style::Print(&format!(
+                    "{}Warning: Failed to cancel operations: {}\n",
+                    glyph(plain_output(os), "⚠️  ", ""),
+                    e
+                ))
             )
             .unwrap();
         }
@@ -387,7 +480,10 @@ impl KnowledgeSubcommand {
         // Now perform immediate synchronous clear
         queue!(
             session.stderr,
-            style::Print("🗑️  Clearing all knowledge base entries...\n")
+            style::Print(format!(
+                "{}Clearing all knowledge base entries...\n",
+                glyph(plain_output(os), "🗑️  ", "")
+            ))
         )
         .unwrap();
         match store.clear_immediate().await {
@@ -396,6 +492,30 @@ impl KnowledgeSubcommand {
         }
     }
 
+    /// Asks the user to confirm clearing the knowledge base, returning the result to show when
+    /// they do not
+    fn confirm_clear(session: &mut ChatSession, plain: bool) -> Result<(), OperationResult> {
+        queue!(
+            session.stderr,
+            style::Print(glyph(plain, "⚠️  ", "Warning: ")),
+            style::Print("This action will remove all knowledge base entries.\n"),
+            style::Print("Clear the knowledge base? (y/N): ")
+        )
+        .unwrap();
+        session.stderr.flush().unwrap();
+
+        let mut input = String::new();
+        if std::io::stdin().read_line(&mut input).is_err() {
+            return Err(OperationResult::Error("Failed to read input".to_string()));
+        }
+
+        let input = input.trim().to_lowercase();
+        if input != "y" && input != "yes" {
+            return Err(OperationResult::Info("Clear operation cancelled".to_string()));
+        }
+        Ok(())
+    }
+
     /// Handle status operation
     async fn handle_status(os: &Os, session: &ChatSession) -> OperationResult {
         let agent = Self::get_agent(session);
@@ -407,7 +527,7 @@ impl KnowledgeSubcommand {
 
         match store.get_status_data().await {
             Ok(status_data) => {
-                let formatted_status = Self::format_status_display(&status_data);
+                let formatted_status = Self::format_status_display(&status_data, plain_output(os));
                 OperationResult::Info(formatted_status)
             },
             Err(e) => OperationResult::Error(format!("Failed to get status: {}", e)),
@@ -415,28 +535,34 @@ impl KnowledgeSubcommand {
     }
 
     /// Format status data for display (UI rendering responsibility)
-    fn format_status_display(status: &SystemStatus) -> String {
+    fn format_status_display(status: &SystemStatus, plain: bool) -> String {
         let mut status_lines = Vec::new();
 
         // Show knowledge base summary
         status_lines.push(format!(
-            "📚 Total knowledge base entries: {} ({} persistent, {} volatile)",
-            status.total_contexts, status.persistent_contexts, status.volatile_contexts
+            "{}Total knowledge base entries: {} ({} persistent, {} volatile)",
+            glyph(plain, "📚 ", ""),
+            status.total_contexts,
+            status.persistent_contexts,
+            status.volatile_contexts
         ));
 
         if status.operations.is_empty() {
-            status_lines.push("✅ No active operations".to_string());
+            status_lines.push(format!("{}No active operations", glyph(plain, "✅ ", "")));
             return status_lines.join("\n");
         }
 
-        status_lines.push("📊 Active Operations:".to_string());
+        status_lines.push(format!("{}Active Operations:", glyph(plain, "📊 ", "")));
         status_lines.push(format!(
-            "  📈 Queue Status: {} active, {} waiting (max {} concurrent)",
-            status.active_count, status.waiting_count, status.max_concurrent
+            "  {}Queue Status: {} active, {} waiting (max {} concurrent)",
+            glyph(plain, "📈 ", ""),
+            status.active_count,
+            status.waiting_count,
+            status.max_concurrent
         ));
 
         for op in &status.operations {
-            let formatted_operation = Self::format_operation_display(op);
+            let formatted_operation = Self::format_operation_display(op, plain);
             status_lines.push(formatted_operation);
         }
 
@@ -444,19 +570,22 @@ impl KnowledgeSubcommand {
     }
 
     /// Format a single operation for display
-    fn format_operation_display(op: &OperationStatus) -> String {
+    fn format_operation_display(op: &OperationStatus, plain: bool) -> String {
         let elapsed = op.started_at.elapsed().unwrap_or_default();
 
         let (status_icon, status_info) = if op.is_cancelled {
-            ("🛑", "Cancelled".to_string())
+            (glyph(plain, "🛑", "[cancelled]"), "Cancelled".to_string())
         } else if op.is_failed {
-            ("❌", op.message.clone())
+            (glyph(plain, "❌", "[failed]"), op.message.clone())
         } else if op.is_waiting {
-            ("⏳", op.message.clone())
+            (glyph(plain, "⏳", "[waiting]"), op.message.clone())
         } else if Self::should_show_progress_bar(op.current, op.total) {
-            ("🔄", Self::create_progress_bar(op.current, op.total, &op.message))
+            (
+                glyph(plain, "🔄", "[running]"),
+                Self::create_progress_bar(op.current, op.total, &op.message),
+            )
         } else {
-            ("🔄", op.message.clone())
+            (glyph(plain, "🔄", "[running]"), op.message.clone())
         };
 
         let operation_desc = op.operation_type.display_name();
@@ -715,4 +844,19 @@ mod tests {
             panic!("Expected Add subcommand");
         }
     }
+
+    #[test]
+    fn test_update_since_parsing() {
+        let cli = TestCli::try_parse_from(["test", "update", "/some/path", "--since", "main"]).unwrap();
+        assert_eq!(cli.knowledge, KnowledgeSubcommand::Update {
+            path: "/some/path".to_string(),
+            since: Some(IndexSince::GitRef("main".to_string())),
+        });
+
+        let cli = TestCli::try_parse_from(["test", "update", "/some/path"]).unwrap();
+        assert_eq!(cli.knowledge, KnowledgeSubcommand::Update {
+            path: "/some/path".to_string(),
+            since: None,
+        });
+    }
 }
@@ -1,6 +1,10 @@
 use std::io::Write;
+use std::path::Path;
 
-use clap::Args;
+use clap::{
+    Args,
+    Subcommand,
+};
 use crossterm::queue;
 use crossterm::style::{
     self,
@@ -8,24 +12,30 @@ use crossterm::style::{
 };
 
 use crate::cli::chat::tool_manager::LoadingRecord;
+use crate::cli::chat::tools::plain_output;
+use crate::cli::chat::util::ui::glyph;
 use crate::cli::chat::{
     ChatError,
     ChatSession,
     ChatState,
 };
+use crate::os::Os;
 
 #[deny(missing_docs)]
 #[derive(Debug, PartialEq, Args)]
-pub struct McpArgs;
+pub struct McpArgs {
+    #[command(subcommand)]
+    subcommand: Option<McpSubcommand>,
+}
 
 impl McpArgs {
-    pub async fn execute(self, session: &mut ChatSession) -> Result<ChatState, ChatError> {
+    pub async fn execute(self, os: &Os, session: &mut ChatSession) -> Result<ChatState, ChatError> {
         if !session.conversation.mcp_enabled {
             queue!(
                 session.stderr,
                 style::SetForegroundColor(Color::Yellow),
                 style::Print("\n"),
-                style::Print("⚠️  WARNING: "),
+                style::Print(glyph(plain_output(os), "⚠️  WARNING: ", "WARNING: ")),
                 style::SetForegroundColor(Color::Reset),
                 style::Print("MCP functionality has been disabled by your administrator.\n\n"),
             )?;
@@ -35,6 +45,19 @@ impl McpArgs {
             });
         }
 
+        // The server list is shown after enabling or disabling a server as well, which doubles as
+        // the confirmation of the change
+        if let Some(subcommand) = self.subcommand {
+            let show_servers = !matches!(subcommand, McpSubcommand::Log);
+            subcommand.execute(os, session).await?;
+            if !show_servers {
+                session.stderr.flush()?;
+                return Ok(ChatState::PromptUser {
+                    skip_printing_tools: true,
+                });
+            }
+        }
+
         let terminal_width = session.terminal_width();
         let still_loading = session
             .conversation
@@ -46,7 +69,8 @@ impl McpArgs {
             .collect::<Vec<_>>()
             .join("");
 
-        for (server_name, msg) in session.conversation.tool_manager.mcp_load_record.lock().await.iter() {
+        let tool_manager = &session.conversation.tool_manager;
+        for (server_name, msg) in tool_manager.mcp_load_record.lock().await.iter() {
             let msg = msg
                 .iter()
                 .map(|record| match record {
@@ -57,9 +81,19 @@ impl McpArgs {
                 .collect::<Vec<_>>()
                 .join("\n--- tools refreshed ---\n");
 
+            let url = tool_manager
+                .clients
+                .get(server_name)
+                .and_then(|client| client.get_masked_url())
+                .map(|url| format!(" ({url})"))
+                .unwrap_or_default();
+
             queue!(
                 session.stderr,
                 style::Print(server_name),
+                style::SetForegroundColor(Color::DarkGrey),
+                style::Print(url),
+                style::SetForegroundColor(Color::Reset),
                 style::Print("\n"),
                 style::Print(format!("{}\n", "▔".repeat(terminal_width))),
                 style::Print(msg),
@@ -67,6 +101,24 @@ impl McpArgs {
             )?;
         }
 
+        let disabled = tool_manager
+            .disabled_servers()
+            .iter()
+            .map(|name| format!(" - {name}\n"))
+            .collect::<Vec<_>>()
+            .join("");
+        if !disabled.is_empty() {
+            queue!(
+                session.stderr,
+                style::Print("Disabled:\n"),
+                style::Print(format!("{}\n", "▔".repeat(terminal_width))),
+                style::SetForegroundColor(Color::DarkGrey),
+                style::Print(disabled),
+                style::SetForegroundColor(Color::Reset),
+                style::Print("\n")
+            )?;
+        }
+
         if !still_loading.is_empty() {
             queue!(
                 session.stderr,
@@ -83,4 +135,161 @@ impl McpArgs {
             skip_printing_tools: true,
         })
     }
+
+    pub fn subcommand_name(&self) -> Option<&'static str> {
+        self.subcommand.as_ref().map(|s| s.name())
+    }
+}
+
+#[deny(missing_docs)]
+#[derive(Debug, PartialEq, Subcommand)]
+pub enum McpSubcommand {
+    /// Start a disabled server and save it as enabled in the agent config
+    Enable {
+        /// Name of the server as configured in the agent
+        name: String,
+    },
+    /// Shut down a server and save it as disabled in the agent config
+    Disable {
+        /// Name of the server as configured in the agent
+        name: String,
+    },
+    /// Write how the servers of this session loaded to a file in the log directory and print its
+    /// path
+    Log,
+}
+
+impl McpSubcommand {
+    async fn execute(self, os: &Os, session: &mut ChatSession) -> Result<(), ChatError> {
+        let (server_name, disabled) = match self {
+            Self::Enable { name } => (name, false),
+            Self::Disable { name } => (name, true),
+            Self::Log => return write_load_record(os, session).await,
+        };
+
+        let Some(agent) = session.conversation.agents.get_active_mut() else {
+            return Err(ChatError::Custom("No active agent".into()));
+        };
+        let Some(config) = agent.mcp_servers.mcp_servers.get(&server_name).cloned() else {
+            queue!(
+                session.stderr,
+                style::SetForegroundColor(Color::Red),
+                style::Print(format!(
+                    "\nNo mcp server named '{server_name}' is configured in agent {}\n\n",
+                    agent.name
+                )),
+                style::SetForegroundColor(Color::Reset),
+            )?;
+            return Ok(());
+        };
+        if config.disabled == disabled {
+            queue!(
+                session.stderr,
+                style::Print(format!(
+                    "\nMcp server '{server_name}' is already {}\n\n",
+                    if disabled { "disabled" } else { "enabled" }
+                )),
+            )?;
+            return Ok(());
+        }
+
+        let tool_manager = &mut session.conversation.tool_manager;
+        if disabled {
+            tool_manager.disable_server(&server_name).await;
+        } else if let Err(e) = tool_manager.enable_server(os, &server_name, config.clone()) {
+            queue!(
+                session.stderr,
+                style::SetForegroundColor(Color::Red),
+                style::Print(format!("\nFailed to start mcp server '{server_name}': {e}\n\n")),
+                style::SetForegroundColor(Color::Reset),
+            )?;
+            return Ok(());
+        }
+
+        let agent_path = agent.path.clone();
+        if let Some(config) = agent.mcp_servers.mcp_servers.get_mut(&server_name) {
+            config.disabled = disabled;
+        }
+        if let Some(config) = tool_manager
+            .agent
+            .lock()
+            .await
+            .mcp_servers
+            .mcp_servers
+            .get_mut(&server_name)
+        {
+            config.disabled = disabled;
+        }
+
+        queue!(
+            session.stderr,
+            style::SetForegroundColor(Color::Green),
+            style::Print(format!(
+                "\n{}{} mcp server '{server_name}'",
+                glyph(plain_output(os), "✓ ", ""),
+                if disabled { "Disabled" } else { "Enabled" }
+            )),
+            style::SetForegroundColor(Color::Reset),
+        )?;
+        // Servers from the legacy mcp.json are not part of the agent config, so there is nothing to
+        // save them to
+        match agent_path.filter(|_| !config.is_from_legacy_mcp_json) {
+            Some(path) => match save_server_disabled(os, &path, &server_name, disabled).await {
+                Ok(()) => queue!(session.stderr, style::Print(format!(" in {}\n\n", path.display())))?,
+                Err(e) => queue!(
+                    session.stderr,
+                    style::SetForegroundColor(Color::Yellow),
+                    style::Print(format!(
+                        " for this session only, failed to save the agent config: {e}\n\n"
+                    )),
+                    style::SetForegroundColor(Color::Reset),
+                )?,
+            },
+            None => queue!(session.stderr, style::Print(" for this session\n\n"))?,
+        }
+
+        Ok(())
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Enable { .. } => "enable",
+            Self::Disable { .. } => "disable",
+            Self::Log => "log",
+        }
+    }
+}
+
+/// Writes the load record of the servers, which is otherwise only written once the servers are
+/// first loaded, and prints the path of the file.
+async fn write_load_record(os: &Os, session: &mut ChatSession) -> Result<(), ChatError> {
+    match session.conversation.tool_manager.persist_load_record(os).await {
+        Ok(path) => queue!(
+            session.stderr,
+            style::Print(format!("\nMcp server load record written to {}\n\n", path.display())),
+        )?,
+        Err(e) => queue!(
+            session.stderr,
+            style::SetForegroundColor(Color::Red),
+            style::Print(format!("\nFailed to write the mcp server load record: {e}\n\n")),
+            style::SetForegroundColor(Color::Reset),
+        )?,
+    }
+    Ok(())
+}
+
+/// Updates the `disabled` field of a server in the agent config at `path`. Only that field of the
+/// raw config is changed, so that the rest of the file, and any changes made to the agent during
+/// the session, are left as they are.
+async fn save_server_disabled(os: &Os, path: &Path, server_name: &str, disabled: bool) -> eyre::Result<()> {
+    let content = os.fs.read(path).await?;
+    let mut config = serde_json::from_slice::<serde_json::Value>(&content)?;
+    let server = config
+        .get_mut("mcpServers")
+        .and_then(|servers| servers.get_mut(server_name))
+        .and_then(serde_json::Value::as_object_mut)
+        .ok_or(eyre::eyre!("server {server_name} is not in {}", path.display()))?;
+    server.insert("disabled".to_string(), disabled.into());
+    os.fs.write(path, serde_json::to_string_pretty(&config)?).await?;
+    Ok(())
 }
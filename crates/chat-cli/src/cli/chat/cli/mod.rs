@@ -7,10 +7,13 @@ pub mod hooks;
 pub mod knowledge;
 pub mod mcp;
 pub mod model;
+pub mod pause;
 pub mod persist;
 pub mod profile;
 pub mod prompts;
+pub mod sessions;
 pub mod subscribe;
+pub mod suggestions;
 pub mod tangent;
 pub mod todos;
 pub mod tools;
@@ -26,9 +29,11 @@ use hooks::HooksArgs;
 use knowledge::KnowledgeSubcommand;
 use mcp::McpArgs;
 use model::ModelArgs;
+use pause::PauseArgs;
 use persist::PersistSubcommand;
 use profile::AgentSubcommand;
 use prompts::PromptsArgs;
+use sessions::SessionsArgs;
 use tangent::TangentArgs;
 use todos::TodoSubcommand;
 use tools::ToolsArgs;
@@ -81,7 +86,7 @@ pub enum SlashCommand {
     Hooks(HooksArgs),
     /// Show current session's context window usage
     Usage(UsageArgs),
-    /// See mcp server loaded
+    /// See mcp server loaded, or enable and disable servers
     Mcp(McpArgs),
     /// Select a model for the current conversation session
     Model(ModelArgs),
@@ -95,6 +100,11 @@ pub enum SlashCommand {
     Tangent(TangentArgs),
     #[command(flatten)]
     Persist(PersistSubcommand),
+    /// Save the session, including its agent and MCP servers, and quit. Resume it later with q
+    /// chat --resume-session <SESSION_ID>
+    Pause(PauseArgs),
+    /// Browse the saved conversations of this workspace and load one
+    Sessions(SessionsArgs),
     // #[command(flatten)]
     // Root(RootSubcommand),
     /// View, manage, and resume to-do lists
@@ -135,7 +145,7 @@ impl SlashCommand {
             Self::Knowledge(subcommand) => subcommand.execute(os, session).await,
             Self::PromptEditor(args) => args.execute(session).await,
             Self::Compact(args) => args.execute(os, session).await,
-            Self::Tools(args) => args.execute(session).await,
+            Self::Tools(args) => args.execute(os, session).await,
             Self::Issue(args) => {
                 if let Err(err) = args.execute(os).await {
                     return Err(ChatError::Custom(err.to_string().into()));
@@ -148,12 +158,14 @@ impl SlashCommand {
             Self::Prompts(args) => args.execute(session).await,
             Self::Hooks(args) => args.execute(session).await,
             Self::Usage(args) => args.execute(os, session).await,
-            Self::Mcp(args) => args.execute(session).await,
+            Self::Mcp(args) => args.execute(os, session).await,
             Self::Model(args) => args.execute(os, session).await,
             Self::Experiment(args) => args.execute(os, session).await,
             Self::Subscribe(args) => args.execute(os, session).await,
             Self::Tangent(args) => args.execute(os, session).await,
             Self::Persist(subcommand) => subcommand.execute(os, session).await,
+            Self::Pause(args) => args.execute(os, session).await,
+            Self::Sessions(args) => args.execute(os, session).await,
             // Self::Root(subcommand) => {
             //     if let Err(err) = subcommand.execute(os, database, telemetry).await {
             //         return Err(ChatError::Custom(err.to_string().into()));
@@ -191,6 +203,8 @@ impl SlashCommand {
                 PersistSubcommand::Save { .. } => "save",
                 PersistSubcommand::Load { .. } => "load",
             },
+            Self::Pause(_) => "pause",
+            Self::Sessions(_) => "sessions",
             Self::Todos(_) => "todos",
         }
     }
@@ -202,6 +216,7 @@ impl SlashCommand {
             SlashCommand::Knowledge(sub) => Some(sub.name()),
             SlashCommand::Tools(arg) => arg.subcommand_name(),
             SlashCommand::Prompts(arg) => arg.subcommand_name(),
+            SlashCommand::Mcp(arg) => arg.subcommand_name(),
             _ => None,
         }
     }
@@ -1,5 +1,11 @@
-use amzn_codewhisperer_client::types::Model;
-use clap::Args;
+use amzn_codewhisperer_client::types::{
+    InputType,
+    Model,
+};
+use clap::{
+    Args,
+    Subcommand,
+};
 use crossterm::style::{
     self,
     Color,
@@ -14,12 +20,17 @@ use serde::{
     Serialize,
 };
 
-use crate::api_client::Endpoint;
+use crate::api_client::model_capabilities::model_capabilities;
+use crate::api_client::{
+    ApiClientError,
+    Endpoint,
+};
 use crate::cli::chat::{
     ChatError,
     ChatSession,
     ChatState,
 };
+use crate::database::settings::Setting;
 use crate::os::Os;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -32,6 +43,12 @@ pub struct ModelInfo {
     /// Size of the model's context window, in tokens
     #[serde(default = "default_context_window")]
     pub context_window_tokens: usize,
+    /// Maximum number of tokens the model generates in a response, if known
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_output_tokens: Option<usize>,
+    /// Whether the model accepts images as input
+    #[serde(default)]
+    pub supports_images: bool,
 }
 
 impl ModelInfo {
@@ -44,6 +61,11 @@ impl ModelInfo {
             model_id: model.model_id().to_string(),
             model_name: model.model_name().map(|s| s.to_string()),
             context_window_tokens,
+            max_output_tokens: model
+                .token_limits()
+                .and_then(|limits| limits.max_output_tokens())
+                .map(|tokens| tokens as usize),
+            supports_images: model.supported_input_types().contains(&InputType::Image),
         }
     }
 
@@ -53,6 +75,8 @@ impl ModelInfo {
             model_id,
             model_name: None,
             context_window_tokens: 200_000,
+            max_output_tokens: None,
+            supports_images: false,
         }
     }
 
@@ -62,14 +86,110 @@ impl ModelInfo {
 }
 #[deny(missing_docs)]
 #[derive(Debug, PartialEq, Args)]
-pub struct ModelArgs;
+#[command(args_conflicts_with_subcommands = true)]
+pub struct ModelArgs {
+    #[command(subcommand)]
+    subcommand: Option<ModelSubcommand>,
+    /// Name or id of the model to use, or a part of it that matches a single model
+    model: Option<String>,
+}
+
+#[deny(missing_docs)]
+#[derive(Debug, PartialEq, Subcommand)]
+pub enum ModelSubcommand {
+    /// List the available models
+    List {
+        /// Show the context window, output limit, and capabilities of each model
+        #[arg(long, short)]
+        details: bool,
+    },
+}
 
 impl ModelArgs {
     pub async fn execute(self, os: &Os, session: &mut ChatSession) -> Result<ChatState, ChatError> {
-        Ok(select_model(os, session).await?.unwrap_or(ChatState::PromptUser {
-            skip_printing_tools: false,
-        }))
+        match (self.subcommand, self.model) {
+            (Some(ModelSubcommand::List { details }), _) => list_models(os, session, details).await?,
+            (None, Some(query)) => switch_model(os, session, &query).await?,
+            (None, None) => {
+                return Ok(select_model(os, session).await?.unwrap_or(ChatState::PromptUser {
+                    skip_printing_tools: false,
+                }));
+            },
+        }
+
+        Ok(ChatState::PromptUser {
+            skip_printing_tools: true,
+        })
+    }
+}
+
+async fn list_models(os: &Os, session: &mut ChatSession, details: bool) -> Result<(), ChatError> {
+    let (models, default_model) = get_available_models(os).await?;
+    let active_model_id = session.conversation.model_info.as_ref().map(|m| m.model_id.as_str());
+
+    let output = if details {
+        format_model_table(&models, active_model_id, &default_model.model_id)
+    } else {
+        models
+            .iter()
+            .map(|model| format!("  {}\n", model_label(model, active_model_id, &default_model.model_id)))
+            .collect()
+    };
+
+    execute!(
+        session.stderr,
+        style::Print("\n"),
+        style::Print(output),
+        style::Print("\n")
+    )?;
+    Ok(())
+}
+
+/// Switches to the model matching `query`, see [match_models].
+async fn switch_model(os: &Os, session: &mut ChatSession, query: &str) -> Result<(), ChatError> {
+    let (models, _default_model) = get_available_models(os).await?;
+    let names = |models: &[&ModelInfo]| {
+        models
+            .iter()
+            .map(|model| model.display_name())
+            .collect::<Vec<_>>()
+            .join(", ")
+    };
+
+    match match_models(&models, query).as_slice() {
+        [model] => {
+            session.conversation.model_info = Some((*model).clone());
+            session.conversation.session_model_info = Some((*model).clone());
+            execute!(
+                session.stderr,
+                style::Print(format!("\n Using {}\n\n", model.display_name())),
+            )?;
+        },
+        [] => {
+            execute!(
+                session.stderr,
+                style::SetForegroundColor(Color::Red),
+                style::Print(format!(
+                    "\nNo model matches '{query}'. Available models: {}\n\n",
+                    names(&models.iter().collect::<Vec<_>>())
+                )),
+                style::ResetColor,
+            )?;
+        },
+        matches => {
+            execute!(
+                session.stderr,
+                style::SetForegroundColor(Color::Yellow),
+                style::Print(format!(
+                    "\n'{query}' matches more than one model: {}. Use a longer name to pick one.\n\n",
+                    names(matches)
+                )),
+                style::ResetColor,
+            )?;
+        },
     }
+
+    Ok(())
 }
 
 pub async fn select_model(os: &Os, session: &mut ChatSession) -> Result<Option<ChatState>, ChatError> {
@@ -125,6 +245,7 @@ pub async fn select_model(os: &Os, session: &mut ChatSession) -> Result<Option<C
     if let Some(index) = selection {
         let selected = models[index].clone();
         session.conversation.model_info = Some(selected.clone());
+        session.conversation.session_model_info = Some(selected.clone());
         let display_name = selected.display_name();
 
         queue!(
@@ -155,17 +276,8 @@ pub async fn get_model_info(model_id: &str, os: &Os) -> Result<ModelInfo, ChatEr
 
 /// Get available models with caching support
 pub async fn get_available_models(os: &Os) -> Result<(Vec<ModelInfo>, ModelInfo), ChatError> {
-    let endpoint = Endpoint::configured_value(&os.database);
-    let region = endpoint.region().as_ref();
-
-    match os.client.get_available_models(region).await {
-        Ok(api_res) => {
-            let models: Vec<ModelInfo> = api_res.models.iter().map(ModelInfo::from_api_model).collect();
-            let default_model = ModelInfo::from_api_model(&api_res.default_model);
-
-            tracing::debug!("Successfully fetched {} models from API", models.len());
-            Ok((models, default_model))
-        },
+    match fetch_available_models(os).await {
+        Ok(available) => Ok(available),
         // In case of API throttling or other errors, fall back to hardcoded models
         Err(e) => {
             tracing::error!("Failed to fetch models from API: {}, using fallback list", e);
@@ -178,6 +290,30 @@ pub async fn get_available_models(os: &Os) -> Result<(Vec<ModelInfo>, ModelInfo)
     }
 }
 
+/// The model used when neither `--model` nor the active agent picks one: the model of the
+/// `chat.defaultModel` setting if it is available, or else the default model of the service.
+pub fn default_model(os: &Os, models: &[ModelInfo], service_default: &ModelInfo) -> ModelInfo {
+    os.database
+        .settings
+        .get_string(Setting::ChatDefaultModel)
+        .and_then(|saved| find_model(models, &saved).cloned())
+        .unwrap_or_else(|| service_default.clone())
+}
+
+/// Fetches the available models and the default one from the API, without falling back to the
+/// hardcoded models when that fails.
+pub async fn fetch_available_models(os: &Os) -> Result<(Vec<ModelInfo>, ModelInfo), ApiClientError> {
+    let endpoint = Endpoint::configured_value(&os.database);
+    let region = endpoint.region().as_ref();
+
+    let api_res = os.client.get_available_models(region).await?;
+    let models: Vec<ModelInfo> = api_res.models.iter().map(ModelInfo::from_api_model).collect();
+    let default_model = ModelInfo::from_api_model(&api_res.default_model);
+
+    tracing::debug!("Successfully fetched {} models from API", models.len());
+    Ok((models, default_model))
+}
+
 /// Returns the context window length in tokens for the given model_id.
 /// Uses cached model data when available
 pub fn context_window_tokens(model_info: Option<&ModelInfo>) -> usize {
@@ -194,11 +330,15 @@ fn get_fallback_models() -> Vec<ModelInfo> {
             model_name: Some("claude-sonnet-4".to_string()),
             model_id: "claude-sonnet-4".to_string(),
             context_window_tokens: 200_000,
+            max_output_tokens: None,
+            supports_images: false,
         },
         ModelInfo {
             model_name: Some("claude-3.7-sonnet".to_string()),
             model_id: "claude-3.7-sonnet".to_string(),
             context_window_tokens: 200_000,
+            max_output_tokens: None,
+            supports_images: false,
         },
     ]
 }
@@ -211,6 +351,87 @@ pub fn normalize_model_name(name: &str) -> &str {
     }
 }
 
+/// Returns the models matching `query`: the model whose name or id is `query` if there is one,
+/// otherwise every model whose name or id contains it. Matching is case-insensitive.
+pub fn match_models<'a>(models: &'a [ModelInfo], query: &str) -> Vec<&'a ModelInfo> {
+    if let Some(model) = find_model(models, query) {
+        return vec![model];
+    }
+
+    let query = normalize_model_name(query).to_lowercase();
+    models
+        .iter()
+        .filter(|m| {
+            m.model_id.to_lowercase().contains(&query)
+                || m.model_name
+                    .as_deref()
+                    .is_some_and(|n| n.to_lowercase().contains(&query))
+        })
+        .collect()
+}
+
+fn model_label(model: &ModelInfo, active_model_id: Option<&str>, default_model_id: &str) -> String {
+    let mut label = model.display_name().to_string();
+    if Some(model.model_id.as_str()) == active_model_id {
+        label.push_str(" (active)");
+    }
+    if model.model_id == default_model_id {
+        label.push_str(" (default)");
+    }
+    label
+}
+
+fn format_tokens(tokens: usize) -> String {
+    match tokens {
+        t if t >= 1_000_000 && t % 1_000_000 == 0 => format!("{}M", t / 1_000_000),
+        t if t >= 1000 && t % 1000 == 0 => format!("{}k", t / 1000),
+        t => t.to_string(),
+    }
+}
+
+/// Formats `models` as a table of their ids, limits, and capabilities.
+fn format_model_table(models: &[ModelInfo], active_model_id: Option<&str>, default_model_id: &str) -> String {
+    let yes_no = |supported: Option<bool>| match supported {
+        Some(true) => "yes",
+        Some(false) => "no",
+        None => "unknown",
+    };
+
+    let mut rows = vec![["Model", "Id", "Context", "Output", "Images", "Thinking", "Tool use"].map(String::from)];
+    for model in models {
+        let capabilities =
+            model_capabilities(&model.model_id).or_else(|| model.model_name.as_deref().and_then(model_capabilities));
+        rows.push([
+            model_label(model, active_model_id, default_model_id),
+            model.model_id.clone(),
+            format_tokens(model.context_window_tokens),
+            model.max_output_tokens.map_or("unknown".to_string(), format_tokens),
+            yes_no(Some(model.supports_images)).to_string(),
+            yes_no(capabilities.map(|c| c.thinking)).to_string(),
+            yes_no(capabilities.map(|c| c.tool_use)).to_string(),
+        ]);
+    }
+
+    let mut widths = [0; 7];
+    for row in &rows {
+        for (width, cell) in widths.iter_mut().zip(row) {
+            *width = (*width).max(cell.chars().count());
+        }
+    }
+
+    let mut table = String::new();
+    for row in rows {
+        let line = row
+            .iter()
+            .zip(widths)
+            .map(|(cell, width)| format!("{cell:<width$}"))
+            .collect::<Vec<_>>()
+            .join("  ");
+        table.push_str(&format!("  {}\n", line.trim_end()));
+    }
+    table
+}
+
 pub fn find_model<'a>(models: &'a [ModelInfo], name: &str) -> Option<&'a ModelInfo> {
     let normalized = normalize_model_name(name);
     models.iter().find(|m| {
@@ -220,3 +441,83 @@ pub fn find_model<'a>(models: &'a [ModelInfo], name: &str) -> Option<&'a ModelIn
             || m.model_id.eq_ignore_ascii_case(normalized)
     })
 }
+
+#[cfg(test)]
+mod tests {
+    use clap::Parser;
+
+    use super::*;
+
+    #[derive(Parser)]
+    #[command(name = "model")]
+    struct TestCli {
+        #[command(flatten)]
+        model: ModelArgs,
+    }
+
+    fn model(model_id: &str, model_name: &str) -> ModelInfo {
+        ModelInfo {
+            model_name: Some(model_name.to_string()),
+            model_id: model_id.to_string(),
+            context_window_tokens: 200_000,
+            max_output_tokens: Some(64_000),
+            supports_images: true,
+        }
+    }
+
+    #[test]
+    fn test_model_args_parsing() {
+        let args = TestCli::try_parse_from(["model"]).unwrap().model;
+        assert_eq!(args, ModelArgs {
+            subcommand: None,
+            model: None
+        });
+
+        let args = TestCli::try_parse_from(["model", "list", "--details"]).unwrap().model;
+        assert_eq!(args.subcommand, Some(ModelSubcommand::List { details: true }));
+
+        let args = TestCli::try_parse_from(["model", "sonnet"]).unwrap().model;
+        assert_eq!(args.model.as_deref(), Some("sonnet"));
+    }
+
+    #[test]
+    fn test_match_models() {
+        let models = vec![
+            model("CLAUDE_SONNET_4_20250514_V1_0", "claude-sonnet-4"),
+            model("CLAUDE_3_7_SONNET_20250219_V1_0", "claude-3.7-sonnet"),
+            model("CLAUDE_SONNET_4_5_20250929_V1_0", "claude-sonnet-4.5"),
+        ];
+        let ids = |query: &str| {
+            match_models(&models, query)
+                .into_iter()
+                .map(|m| m.model_id.as_str())
+                .collect::<Vec<_>>()
+        };
+
+        // An exact name wins over partial matches
+        assert_eq!(ids("claude-sonnet-4"), vec!["CLAUDE_SONNET_4_20250514_V1_0"]);
+        assert_eq!(ids("claude-4-sonnet"), vec!["CLAUDE_SONNET_4_20250514_V1_0"]);
+        assert_eq!(ids("3.7"), vec!["CLAUDE_3_7_SONNET_20250219_V1_0"]);
+        assert_eq!(ids("SONNET-4.5"), vec!["CLAUDE_SONNET_4_5_20250929_V1_0"]);
+        assert_eq!(ids("sonnet").len(), 3);
+        assert!(ids("opus").is_empty());
+    }
+
+    #[test]
+    fn test_format_model_table() {
+        let mut unknown = model("NEW_MODEL_V1", "new-model");
+        unknown.context_window_tokens = 128_500;
+        unknown.max_output_tokens = None;
+        unknown.supports_images = false;
+        let models = vec![model("CLAUDE_SONNET_4_20250514_V1_0", "claude-sonnet-4"), unknown];
+
+        let table = format_model_table(&models, Some("NEW_MODEL_V1"), "CLAUDE_SONNET_4_20250514_V1_0");
+        let lines = table.lines().collect::<Vec<_>>();
+        assert_eq!(lines.len(), 3);
+        assert!(lines[0].starts_with("  Model "));
+        assert!(lines[1].starts_with("  claude-sonnet-4 (default)  CLAUDE_SONNET_4_20250514_V1_0  200k"));
+        assert!(lines[1].ends_with("64k      yes     yes       yes"));
+        assert!(lines[2].starts_with("  new-model (active)"));
+        assert!(lines[2].ends_with("128500   unknown  no      unknown   unknown"));
+    }
+}
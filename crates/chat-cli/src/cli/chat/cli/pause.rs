@@ -0,0 +1,50 @@
+use clap::Args;
+use crossterm::execute;
+use crossterm::style::{
+    self,
+    Color,
+};
+
+use crate::cli::chat::tools::plain_output;
+use crate::cli::chat::util::ui::glyph;
+use crate::cli::chat::{
+    ChatError,
+    ChatSession,
+    ChatState,
+};
+use crate::os::Os;
+
+#[derive(Debug, PartialEq, Args)]
+pub struct PauseArgs;
+
+impl PauseArgs {
+    pub async fn execute(self, os: &Os, session: &mut ChatSession) -> Result<ChatState, ChatError> {
+        match session.serialize_pause(os).await {
+            Ok(session_id) => {
+                execute!(
+                    session.stderr,
+                    style::SetForegroundColor(Color::Green),
+                    style::Print(format!(
+                        "\n{}Paused the session. Resume it with ",
+                        glyph(plain_output(os), "✔ ", "")
+                    )),
+                    style::SetForegroundColor(Color::Cyan),
+                    style::Print(format!("q chat --resume-session {session_id}\n\n")),
+                    style::SetForegroundColor(Color::Reset)
+                )?;
+                Ok(ChatState::Exit)
+            },
+            Err(err) => {
+                execute!(
+                    session.stderr,
+                    style::SetForegroundColor(Color::Red),
+                    style::Print(format!("\nFailed to pause the session: {err}\n\n")),
+                    style::SetForegroundColor(Color::Reset)
+                )?;
+                Ok(ChatState::PromptUser {
+                    skip_printing_tools: true,
+                })
+            },
+        }
+    }
+}
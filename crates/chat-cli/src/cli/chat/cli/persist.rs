@@ -1,3 +1,7 @@
+use std::path::Path;
+use std::sync::Arc;
+use std::time::Duration;
+
 use clap::Subcommand;
 use crossterm::execute;
 use crossterm::style::{
@@ -5,14 +9,35 @@ use crossterm::style::{
     Attribute,
     Color,
 };
+use time::OffsetDateTime;
+use tokio::sync::Mutex;
+use tracing::warn;
 
 use crate::cli::ConversationState;
+use crate::cli::chat::cli::model::{
+    fetch_available_models,
+    find_model,
+};
+use crate::cli::chat::conversation::TITLE_MAX_WORDS;
+use crate::cli::chat::parser::{
+    ResponseEvent,
+    SendMessageStream,
+};
+use crate::cli::chat::tools::plain_output;
+use crate::cli::chat::util::ui::glyph;
 use crate::cli::chat::{
     ChatError,
     ChatSession,
     ChatState,
 };
+use crate::database::SavedConversation;
 use crate::os::Os;
+use crate::telemetry::core::MessageMetaTag;
+use crate::util::directories;
+
+/// How long to wait for the model to generate the title of a conversation before falling back to
+/// [ConversationState::fallback_title].
+const TITLE_REQUEST_TIMEOUT: Duration = Duration::from_secs(10);
 
 #[deny(missing_docs)]
 #[derive(Debug, PartialEq, Subcommand)]
@@ -51,7 +76,6 @@ impl PersistSubcommand {
 
         match self {
             Self::Save { path, force } => {
-                let contents = tri!(serde_json::to_string_pretty(&session.conversation), "export to", &path);
                 if os.fs.exists(&path) && !force {
                     execute!(
                         session.stderr,
@@ -66,12 +90,17 @@ impl PersistSubcommand {
                         skip_printing_tools: true,
                     });
                 }
-                tri!(os.fs.write(&path, contents).await, "export to", &path);
+                let saved = tri!(save_conversation(os, session, &path).await, "export to", &path);
 
                 execute!(
                     session.stderr,
                     style::SetForegroundColor(Color::Green),
-                    style::Print(format!("\n✔ Exported conversation state to {}\n\n", &path)),
+                    style::Print(format!(
+                        "\n{}Exported conversation \"{}\" to {}\n\n",
+                        glyph(plain_output(os), "✔ ", ""),
+                        saved.title,
+                        &path
+                    )),
                     style::SetAttribute(Attribute::Reset)
                 )?;
             },
@@ -96,7 +125,35 @@ impl PersistSubcommand {
                 let mut new_state: ConversationState = tri!(serde_json::from_str(&contents), "import from", &path);
                 std::mem::swap(&mut new_state.tool_manager, &mut session.conversation.tool_manager);
                 std::mem::swap(&mut new_state.mcp_enabled, &mut session.conversation.mcp_enabled);
-                std::mem::swap(&mut new_state.model_info, &mut session.conversation.model_info);
+                // Restore the model the conversation was saved with, unless it is no longer available.
+                // It is kept as is when the available models can't be fetched.
+                match new_state.model_info.take() {
+                    Some(saved) => {
+                        new_state.model_info = match fetch_available_models(os).await {
+                            Ok((models, default_model)) => match find_model(&models, &saved.model_id) {
+                                Some(model) => Some(model.clone()),
+                                None => {
+                                    execute!(
+                                        session.stderr,
+                                        style::SetForegroundColor(Color::Yellow),
+                                        style::Print(format!(
+                                            "\nModel {} of the saved conversation is no longer available. Using {} instead.\n",
+                                            saved.display_name(),
+                                            default_model.display_name()
+                                        )),
+                                        style::SetAttribute(Attribute::Reset)
+                                    )?;
+                                    Some(default_model)
+                                },
+                            },
+                            Err(e) => {
+                                tracing::warn!("Failed to fetch models, keeping the saved model: {}", e);
+                                Some(saved)
+                            },
+                        };
+                    },
+                    None => std::mem::swap(&mut new_state.model_info, &mut session.conversation.model_info),
+                }
                 std::mem::swap(
                     &mut new_state.context_manager,
                     &mut session.conversation.context_manager,
@@ -107,7 +164,11 @@ impl PersistSubcommand {
                 execute!(
                     session.stderr,
                     style::SetForegroundColor(Color::Green),
-                    style::Print(format!("\n✔ Imported conversation state from {}\n\n", &path)),
+                    style::Print(format!(
+                        "\n{}Imported conversation state from {}\n\n",
+                        glyph(plain_output(os), "✔ ", ""),
+                        &path
+                    )),
                     style::SetAttribute(Attribute::Reset)
                 )?;
             },
@@ -118,3 +179,197 @@ impl PersistSubcommand {
         })
     }
 }
+
+/// Saves the conversation of `session` to `path` and records it for `/sessions`. The title of the
+/// conversation is generated the first time it is saved.
+pub async fn save_conversation(
+    os: &Os,
+    session: &mut ChatSession,
+    path: impl AsRef<Path>,
+) -> eyre::Result<SavedConversation> {
+    let title = match &session.conversation.title {
+        Some(title) => title.clone(),
+        None => generate_title(os, &session.conversation).await,
+    };
+    session.conversation.title = Some(title.clone());
+
+    let workspace = os.env.current_dir()?;
+    let path = workspace.join(path);
+    if let Some(parent) = path.parent() {
+        os.fs.create_dir_all(parent).await?;
+    }
+    os.fs
+        .write(&path, serde_json::to_string_pretty(&session.conversation)?)
+        .await?;
+
+    let saved = SavedConversation {
+        path,
+        title,
+        workspace,
+        saved_at: OffsetDateTime::now_utc(),
+        message_count: session.conversation.history().len(),
+        agent: Some(session.conversation.agents.active_idx.clone()).filter(|name| !name.is_empty()),
+    };
+    if let Err(err) = os.database.set_saved_conversation(&saved) {
+        warn!(?err, "Failed to record the saved conversation");
+    }
+    Ok(saved)
+}
+
+/// Saves the conversation to the saved conversations directory when chat exits, if
+/// `chat.saveOnExit` is enabled.
+pub async fn save_on_exit(os: &Os, session: &mut ChatSession) -> Result<(), ChatError> {
+    let path = match directories::chat_saved_conversations_dir(os) {
+        Ok(dir) => dir.join(format!("{}.json", session.conversation.conversation_id())),
+        Err(err) => return Err(ChatError::Custom(err.to_string().into())),
+    };
+    match save_conversation(os, session, &path).await {
+        Ok(saved) => execute!(
+            session.stderr,
+            style::SetForegroundColor(Color::Green),
+            style::Print(format!(
+                "{}Saved conversation \"{}\"\n",
+                glyph(plain_output(os), "✔ ", ""),
+                saved.title
+            )),
+            style::SetAttribute(Attribute::Reset)
+        )?,
+        Err(err) => execute!(
+            session.stderr,
+            style::SetForegroundColor(Color::Red),
+            style::Print(format!("Failed to save the conversation: {err}\n")),
+            style::SetAttribute(Attribute::Reset)
+        )?,
+    }
+    Ok(())
+}
+
+/// Asks the model for a title of the conversation. Falls back to
+/// [ConversationState::fallback_title] if the request fails or times out, e.g. when offline.
+async fn generate_title(os: &Os, conversation: &ConversationState) -> String {
+    let Some(request) = conversation.create_title_request() else {
+        return conversation.fallback_title();
+    };
+
+    let response = async {
+        let mut stream = SendMessageStream::send_message(
+            &os.client,
+            request,
+            Arc::new(Mutex::new(None)),
+            Some(vec![MessageMetaTag::GenerateTitle]),
+        )
+        .await
+        .map_err(|err| warn!(?err, "Failed to generate a conversation title"))
+        .ok()?;
+        loop {
+            match stream.recv().await? {
+                Ok(ResponseEvent::EndStream { message, .. }) => return Some(message.content().to_string()),
+                Ok(_) => (),
+                Err(err) => {
+                    warn!(?err, "Failed to generate a conversation title");
+                    return None;
+                },
+            }
+        }
+    };
+
+    match tokio::time::timeout(TITLE_REQUEST_TIMEOUT, response).await {
+        Ok(Some(response)) => clean_title(&response).unwrap_or_else(|| conversation.fallback_title()),
+        _ => conversation.fallback_title(),
+    }
+}
+
+/// Extracts a title of at most [TITLE_MAX_WORDS] words from the response of the model, which may
+/// be wrapped in quotes or markdown.
+fn clean_title(response: &str) -> Option<String> {
+    let line = response.lines().map(str::trim).find(|line| !line.is_empty())?;
+    let title = line
+        .trim_start_matches('#')
+        .trim_matches(|c: char| c.is_whitespace() || matches!(c, '"' | '\'' | '`' | '*'))
+        .split_whitespace()
+        .take(TITLE_MAX_WORDS)
+        .collect::<Vec<_>>()
+        .join(" ");
+    let title = title.trim_end_matches(['.', '!', ':']);
+    (!title.is_empty()).then(|| title.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use super::*;
+    use crate::cli::agent::{
+        Agent,
+        Agents,
+    };
+    use crate::cli::chat::input_source::InputSource;
+    use crate::cli::chat::tool_manager::ToolManager;
+    use crate::cli::chat::tools::ToolSpec;
+
+    #[test]
+    fn test_clean_title() {
+        let cases = [
+            ("Debugging the release build", Some("Debugging the release build")),
+            (
+                "\"Fixing flaky integration tests.\"\n",
+                Some("Fixing flaky integration tests"),
+            ),
+            ("\n## **Rust borrow checker help**", Some("Rust borrow checker help")),
+            (
+                "Setting up a new project with many words",
+                Some("Setting up a new project"),
+            ),
+            ("  \n \"\" ", None),
+        ];
+        for (response, expected) in cases {
+            assert_eq!(clean_title(response).as_deref(), expected, "response: {response:?}");
+        }
+    }
+
+    #[tokio::test]
+    async fn test_save_conversation() {
+        let mut os = Os::new().await.unwrap();
+        os.client
+            .set_mock_output(serde_json::json!([["Hello!"], ["\"Greeting the assistant\""]]));
+
+        let mut agents = Agents::default();
+        agents.activate_ephemeral(Agent::default());
+        let mut session = ChatSession::new(
+            &mut os,
+            std::io::stdout(),
+            std::io::stderr(),
+            "fake_conv_id",
+            agents,
+            None,
+            InputSource::new_mock(vec!["hi there".to_string(), "/quit".to_string()]),
+            false,
+            || Some(80),
+            ToolManager::default(),
+            None,
+            serde_json::from_str::<HashMap<String, ToolSpec>>(include_str!("../tools/tool_index.json")).unwrap(),
+            true,
+            false,
+        )
+        .await
+        .unwrap();
+        session.spawn(&mut os).await.unwrap();
+
+        let saved = save_conversation(&os, &mut session, "conversation.json").await.unwrap();
+        assert_eq!(saved.title, "Greeting the assistant");
+        assert_eq!(saved.message_count, 1);
+        assert_eq!(saved.workspace, os.env.current_dir().unwrap());
+        assert_eq!(os.database.get_saved_conversations().unwrap(), vec![saved.clone()]);
+
+        let contents = os.fs.read_to_string(&saved.path).await.unwrap();
+        let conversation = serde_json::from_str::<ConversationState>(&contents).unwrap();
+        assert_eq!(conversation.title.as_deref(), Some("Greeting the assistant"));
+
+        // The title is kept on later saves, and falls back to the first prompt without a model
+        let saved = save_conversation(&os, &mut session, "conversation.json").await.unwrap();
+        assert_eq!(saved.title, "Greeting the assistant");
+        session.conversation.title = None;
+        let saved = save_conversation(&os, &mut session, "conversation.json").await.unwrap();
+        assert_eq!(saved.title, "hi there");
+    }
+}
@@ -30,11 +30,15 @@ use syntect::util::{
 
 use crate::cli::agent::{
     Agent,
+    AgentTemplate,
     Agents,
+    AgentsLoadMetadata,
     McpServerConfig,
     create_agent,
 };
 use crate::cli::chat::conversation::McpServerInfo;
+use crate::cli::chat::tools::plain_output;
+use crate::cli::chat::util::ui::glyph;
 use crate::cli::chat::{
     ChatError,
     ChatSession,
@@ -75,6 +79,9 @@ pub enum AgentSubcommand {
         /// The name of an agent that shall be used as the starting point for the agent creation
         #[arg(long, short)]
         from: Option<String>,
+        /// A built-in template to use as the starting point for the agent creation
+        #[arg(long, short, value_enum, conflicts_with = "from")]
+        template: Option<AgentTemplate>,
     },
     /// Generate an agent configuration using AI
     Generate {},
@@ -94,12 +101,19 @@ pub enum AgentSubcommand {
     /// Swap to a new agent at runtime
     #[command(alias = "switch")]
     Swap { name: Option<String> },
+    /// Show what happened while loading agents at startup
+    #[command(name = "status")]
+    Metrics {
+        /// Print the load metadata as JSON
+        #[arg(long)]
+        json: bool,
+    },
 }
 
 fn prompt_mcp_server_selection(servers: &[McpServerInfo]) -> eyre::Result<Option<Vec<&McpServerInfo>>> {
     let items: Vec<String> = servers
         .iter()
-        .map(|server| format!("{} ({})", server.name, server.config.command))
+        .map(|server| format!("{} ({})", server.name, server.config.display_target()))
         .collect();
 
     let selections = match MultiSelect::new()
@@ -171,11 +185,46 @@ impl AgentSubcommand {
                 highlight_json(&mut session.stderr, pretty.as_str())
                     .map_err(|e| ChatError::Custom(format!("Error printing agent schema: {e}").into()))?;
             },
-            Self::Create { name, directory, from } => {
-                let mut agents = Agents::load(os, None, true, &mut session.stderr, session.conversation.mcp_enabled)
-                    .await
-                    .0;
-                let path_with_file_name = create_agent(os, &mut agents, name.clone(), directory, from)
+            Self::Metrics { json } => {
+                let Some(metadata) = session.agents_load_metadata.as_ref() else {
+                    execute!(
+                        session.stderr,
+                        style::SetForegroundColor(Color::Yellow),
+                        style::Print("No agent load information is available for this session\n"),
+                        style::SetForegroundColor(Color::Reset),
+                    )?;
+                    return Ok(ChatState::PromptUser {
+                        skip_printing_tools: true,
+                    });
+                };
+
+                if json {
+                    let pretty = serde_json::to_string_pretty(metadata).map_err(|e| {
+                        ChatError::Custom(format!("Failed to convert agent load metadata to string: {e}").into())
+                    })?;
+                    highlight_json(&mut session.stderr, pretty.as_str())
+                        .map_err(|e| ChatError::Custom(format!("Error printing agent load metadata: {e}").into()))?;
+                } else {
+                    print_load_metadata(&mut session.stderr, metadata)?;
+                }
+            },
+            Self::Create {
+                name,
+                directory,
+                from,
+                template,
+            } => {
+                let mut agents = Agents::load(
+                    os,
+                    None,
+                    true,
+                    false,
+                    &mut session.stderr,
+                    session.conversation.mcp_enabled,
+                )
+                .await
+                .0;
+                let path_with_file_name = create_agent(os, &mut agents, name.clone(), directory, from, template)
                     .await
                     .map_err(|e| ChatError::Custom(Cow::Owned(e.to_string())))?;
                 let editor_cmd = std::env::var("EDITOR").unwrap_or_else(|_| "vi".to_string());
@@ -196,7 +245,7 @@ impl AgentSubcommand {
                 .await;
                 match new_agent {
                     Ok(agent) => {
-                        session.conversation.agents.agents.insert(agent.name.clone(), agent);
+                        session.conversation.agents.insert(agent.name.clone(), agent);
                     },
                     Err(e) => {
                         execute!(
@@ -359,7 +408,8 @@ impl AgentSubcommand {
                     execute!(
                         session.stderr,
                         style::SetForegroundColor(Color::Green),
-                        style::Print("✓ Default agent set to '"),
+                        style::Print(glyph(plain_output(os), "✓ ", "")),
+                        style::Print("Default agent set to '"),
                         style::Print(&agent.name),
                         style::Print("'. This will take effect the next time q chat is launched.\n"),
                         style::ResetColor,
@@ -435,10 +485,58 @@ impl AgentSubcommand {
             Self::Schema => "schema",
             Self::SetDefault { .. } => "set_default",
             Self::Swap { .. } => "swap",
+            Self::Metrics { .. } => "status",
         }
     }
 }
 
+fn print_load_metadata(output: &mut impl Write, metadata: &AgentsLoadMetadata) -> Result<(), ChatError> {
+    let yes_no = |b: bool| if b { "yes" } else { "no" };
+    queue!(
+        output,
+        style::SetAttribute(Attribute::Bold),
+        style::Print("Agent load status\n"),
+        style::SetAttribute(Attribute::Reset),
+        style::Print(format!("  Launched agent:      {}\n", metadata.launched_agent)),
+        style::Print(format!(
+            "  Migration performed: {} ({} migrated)\n",
+            yes_no(metadata.migration_performed),
+            metadata.migrated_count
+        )),
+        style::Print(format!("  Failed to load:      {}\n", metadata.load_failed_count)),
+        style::Print("\n"),
+        style::SetAttribute(Attribute::Bold),
+        style::Print(format!("Loaded agents ({})\n", metadata.agents.len())),
+        style::SetAttribute(Attribute::Reset),
+    )?;
+
+    for agent in &metadata.agents {
+        let path = agent
+            .path
+            .as_ref()
+            .map_or("(built-in)".to_string(), |p| p.display().to_string());
+        queue!(
+            output,
+            style::SetForegroundColor(if agent.name == metadata.launched_agent {
+                Color::Green
+            } else {
+                Color::Reset
+            }),
+            style::Print(format!("  {}\n", agent.name)),
+            style::SetForegroundColor(Color::DarkGrey),
+            style::Print(format!("    Path: {path}\n")),
+            style::Print(format!(
+                "    MCP servers: {}  Tools: {}  Resources: {}\n",
+                agent.mcp_server_count, agent.tool_count, agent.resource_count
+            )),
+            style::SetForegroundColor(Color::Reset),
+        )?;
+    }
+
+    execute!(output, style::Print("\n"))?;
+    Ok(())
+}
+
 fn highlight_json(output: &mut impl Write, json_str: &str) -> eyre::Result<()> {
     let ps = SyntaxSet::load_defaults_newlines();
     let ts = ThemeSet::load_defaults();
@@ -464,11 +562,14 @@ pub async fn get_all_available_mcp_servers(os: &mut Os) -> Result<Vec<McpServerI
 
     // 1. Load from agent configurations (highest priority)
     let mut null_writer = NullWriter;
-    let (agents, _) = Agents::load(os, None, true, &mut null_writer, true).await;
+    let (agents, _) = Agents::load(os, None, true, false, &mut null_writer, true).await;
 
     for (_, agent) in agents.agents {
         for (server_name, server_config) in agent.mcp_servers.mcp_servers {
-            if !servers.values().any(|s| s.config.command == server_config.command) {
+            if !servers
+                .values()
+                .any(|s| s.config.command == server_config.command && s.config.url == server_config.url)
+            {
                 servers.insert(server_name.clone(), McpServerInfo {
                     name: server_name,
                     config: server_config,
@@ -481,7 +582,10 @@ pub async fn get_all_available_mcp_servers(os: &mut Os) -> Result<Vec<McpServerI
     if let Ok(workspace_path) = directories::chat_legacy_workspace_mcp_config(os) {
         if let Ok(workspace_config) = McpServerConfig::load_from_file(os, workspace_path).await {
             for (server_name, server_config) in workspace_config.mcp_servers {
-                if !servers.values().any(|s| s.config.command == server_config.command) {
+                if !servers
+                    .values()
+                    .any(|s| s.config.command == server_config.command && s.config.url == server_config.url)
+                {
                     servers.insert(server_name.clone(), McpServerInfo {
                         name: server_name,
                         config: server_config,
@@ -495,7 +599,10 @@ pub async fn get_all_available_mcp_servers(os: &mut Os) -> Result<Vec<McpServerI
     if let Ok(global_path) = directories::chat_legacy_global_mcp_config(os) {
         if let Ok(global_config) = McpServerConfig::load_from_file(os, global_path).await {
             for (server_name, server_config) in global_config.mcp_servers {
-                if !servers.values().any(|s| s.config.command == server_config.command) {
+                if !servers
+                    .values()
+                    .any(|s| s.config.command == server_config.command && s.config.url == server_config.url)
+                {
                     servers.insert(server_name.clone(), McpServerInfo {
                         name: server_name,
                         config: server_config,
@@ -16,6 +16,7 @@ use crossterm::{
     execute,
     queue,
 };
+use dialoguer::Select;
 use thiserror::Error;
 use unicode_width::UnicodeWidthStr;
 
@@ -32,14 +33,10 @@ use crate::mcp_client::PromptGetResult;
 pub enum GetPromptError {
     #[error("Prompt with name {0} does not exist")]
     PromptNotFound(String),
-    #[error("Prompt {0} is offered by more than one server. Use one of the following {1}")]
-    AmbiguousPrompt(String, String),
     #[error("Missing client")]
     MissingClient,
     #[error("Missing prompt name")]
     MissingPromptName,
-    #[error("Missing prompt bundle")]
-    MissingPromptInfo,
     #[error(transparent)]
     General(#[from] eyre::Report),
     #[error("Incorrect response type received")]
@@ -57,7 +54,11 @@ These templates are provided by the mcp servers you have installed and configure
 To actually retrieve a prompt, directly start with the following command (without prepending /prompt get):
   <em>@<<prompt name>> [arg]</em>                             <black!>Retrieve prompt specified</black!>
 Or if you prefer the long way:
-  <em>/prompts get <<prompt name>> [arg]</em>                 <black!>Retrieve prompt specified</black!>"
+  <em>/prompts get <<prompt name>> [arg]</em>                 <black!>Retrieve prompt specified</black!>
+  <em>/prompts use <<server>>/<<prompt name>> [arg]</em>       <black!>Retrieve prompt from a specific server</black!>
+
+Missing required arguments are asked for one by one. To see what a prompt expands to without sending it:
+  <em>/prompts preview <<prompt name>> [arg]</em>             <black!>Show the rendered prompt messages</black!>"
 })]
 pub struct PromptsArgs {
     #[command(subcommand)]
@@ -72,22 +73,46 @@ impl PromptsArgs {
         };
 
         if let Some(subcommand) = self.subcommand {
-            if matches!(subcommand, PromptsSubcommand::Get { .. }) {
+            if matches!(
+                subcommand,
+                PromptsSubcommand::Get { .. } | PromptsSubcommand::Preview { .. }
+            ) {
                 return subcommand.execute(session).await;
             }
         }
 
         let terminal_width = session.terminal_width();
         let prompts = session.conversation.tool_manager.list_prompts().await?;
-        let mut longest_name = "";
-        let arg_pos = {
-            let optimal_case = UnicodeWidthStr::width(longest_name) + terminal_width / 4;
-            if optimal_case > terminal_width {
-                terminal_width / 3
-            } else {
-                optimal_case
-            }
-        };
+        let search_word = search_word.as_deref().unwrap_or("");
+        let mut entries = prompts
+            .iter()
+            .filter(|(prompt_name, _)| prompt_name.contains(search_word))
+            .flat_map(|(_, bundles)| bundles)
+            .map(|bundle| (bundle, source_badge(bundle)))
+            .collect::<Vec<_>>();
+        entries.sort_by(|(a, _), (b, _)| {
+            a.prompt_get
+                .name
+                .cmp(&b.prompt_get.name)
+                .then_with(|| a.server_name.cmp(&b.server_name))
+        });
+
+        // Arguments are aligned in a column after the longest "- <name> <badge>"
+        fn entry_width(bundle: &PromptBundle, badge: &str) -> usize {
+            UnicodeWidthStr::width("- ")
+                + UnicodeWidthStr::width(bundle.prompt_get.name.as_str())
+                + 1
+                + UnicodeWidthStr::width(badge)
+        }
+        let arg_pos = entries
+            .iter()
+            .map(|(bundle, badge)| entry_width(bundle, badge))
+            .max()
+            .unwrap_or_default()
+            .max(UnicodeWidthStr::width("Prompt"))
+            .saturating_add(2)
+            .min(terminal_width / 2);
+
         // Add usage guidance at the top
         queue!(
             session.stderr,
@@ -101,6 +126,12 @@ impl PromptsArgs {
             style::Print("'@<prompt name> [...args]'"),
             style::SetForegroundColor(Color::Reset),
             style::SetAttribute(Attribute::Reset),
+            style::Print(" or "),
+            style::SetAttribute(Attribute::Bold),
+            style::SetForegroundColor(Color::Green),
+            style::Print("'/prompts use <server>/<prompt name> [...args]'"),
+            style::SetForegroundColor(Color::Reset),
+            style::SetAttribute(Attribute::Reset),
             style::Print("\n\n"),
         )?;
         queue!(
@@ -112,7 +143,7 @@ impl PromptsArgs {
             style::Print({
                 let name_width = UnicodeWidthStr::width("Prompt");
                 let padding = arg_pos.saturating_sub(name_width);
-                " ".repeat(padding)
+                " ".repeat(padding.max(1))
             }),
             style::SetAttribute(Attribute::Bold),
             style::Print("Arguments (* = required)"),
@@ -120,78 +151,46 @@ impl PromptsArgs {
             style::Print("\n"),
             style::Print(format!("{}\n", "▔".repeat(terminal_width))),
         )?;
-        let mut prompts_by_server: Vec<_> = prompts
-            .iter()
-            .fold(
-                HashMap::<&String, Vec<&PromptBundle>>::new(),
-                |mut acc, (prompt_name, bundles)| {
-                    if prompt_name.contains(search_word.as_deref().unwrap_or("")) {
-                        if prompt_name.len() > longest_name.len() {
-                            longest_name = prompt_name.as_str();
-                        }
-                        for bundle in bundles {
-                            acc.entry(&bundle.server_name)
-                                .and_modify(|b| b.push(bundle))
-                                .or_insert(vec![bundle]);
-                        }
-                    }
-                    acc
-                },
-            )
-            .into_iter()
-            .collect();
-        prompts_by_server.sort_by_key(|(server_name, _)| server_name.as_str());
-
-        for (i, (server_name, bundles)) in prompts_by_server.iter_mut().enumerate() {
-            bundles.sort_by_key(|bundle| &bundle.prompt_get.name);
-
-            if i > 0 {
-                queue!(session.stderr, style::Print("\n"))?;
-            }
+
+        if entries.is_empty() {
             queue!(
                 session.stderr,
-                style::SetAttribute(Attribute::Bold),
-                style::Print(server_name),
-                style::Print(" (MCP):"),
-                style::SetAttribute(Attribute::Reset),
-                style::Print("\n"),
+                style::SetForegroundColor(Color::DarkGrey),
+                style::Print("No prompts found.\n"),
+                style::SetForegroundColor(Color::Reset),
+            )?;
+        }
+
+        for (bundle, badge) in entries {
+            queue!(
+                session.stderr,
+                style::Print("- "),
+                style::Print(&bundle.prompt_get.name),
+                style::Print(" "),
+                style::SetForegroundColor(Color::DarkGrey),
+                style::Print(&badge),
+                style::SetForegroundColor(Color::Reset),
             )?;
-            for bundle in bundles {
+
+            let args = bundle.prompt_get.arguments.as_deref().unwrap_or_default();
+            if args.is_empty() {
+                queue!(session.stderr, style::Print("\n"))?;
+                continue;
+            }
+
+            let padding = arg_pos.saturating_sub(entry_width(bundle, &badge));
+            queue!(session.stderr, style::Print(" ".repeat(padding.max(1))))?;
+            for (i, arg) in args.iter().enumerate() {
                 queue!(
                     session.stderr,
-                    style::Print("- "),
-                    style::Print(&bundle.prompt_get.name),
-                    style::Print({
-                        if bundle
-                            .prompt_get
-                            .arguments
-                            .as_ref()
-                            .is_some_and(|args| !args.is_empty())
-                        {
-                            let name_width = UnicodeWidthStr::width(bundle.prompt_get.name.as_str());
-                            let padding = arg_pos
-                                .saturating_sub(name_width)
-                                .saturating_sub(UnicodeWidthStr::width("- "));
-                            " ".repeat(padding.max(1))
-                        } else {
-                            "\n".to_owned()
-                        }
-                    })
+                    style::SetForegroundColor(Color::DarkGrey),
+                    style::Print(match arg.required {
+                        Some(true) => format!("{}*", arg.name),
+                        _ => arg.name.clone(),
+                    }),
+                    style::SetForegroundColor(Color::Reset),
+                    style::Print(if i < args.len() - 1 { ", " } else { "\n" }),
                 )?;
-                if let Some(args) = bundle.prompt_get.arguments.as_ref() {
-                    for (i, arg) in args.iter().enumerate() {
-                        queue!(
-                            session.stderr,
-                            style::SetForegroundColor(Color::DarkGrey),
-                            style::Print(match arg.required {
-                                Some(true) => format!("{}*", arg.name),
-                                _ => arg.name.clone(),
-                            }),
-                            style::SetForegroundColor(Color::Reset),
-                            style::Print(if i < args.len() - 1 { ", " } else { "\n" }),
-                        )?;
-                    }
-                }
             }
         }
 
@@ -210,69 +209,55 @@ impl PromptsArgs {
 pub enum PromptsSubcommand {
     /// List available prompts from a tool or show all available prompt
     List { search_word: Option<String> },
+    /// Retrieve a prompt and send it to the model as your next message
+    #[command(visible_alias = "use")]
     Get {
         #[arg(long, hide = true)]
         orig_input: Option<String>,
         name: String,
         arguments: Option<Vec<String>>,
     },
+    /// Show the messages a prompt renders to without sending them to the model
+    Preview {
+        /// Name of the prompt, optionally as @server/name
+        name: String,
+        /// Positional arguments for the prompt
+        arguments: Option<Vec<String>>,
+    },
 }
 
 impl PromptsSubcommand {
     pub async fn execute(self, session: &mut ChatSession) -> Result<ChatState, ChatError> {
-        let PromptsSubcommand::Get {
-            orig_input,
-            name,
-            arguments,
-        } = self
-        else {
-            unreachable!("List has already been parsed out at this point");
+        let (orig_input, name, arguments, preview) = match self {
+            PromptsSubcommand::Get {
+                orig_input,
+                name,
+                arguments,
+            } => (orig_input, name, arguments, false),
+            PromptsSubcommand::Preview { name, arguments } => (None, name, arguments, true),
+            PromptsSubcommand::List { .. } => unreachable!("List has already been parsed out at this point"),
         };
 
-        let prompts = match session.conversation.tool_manager.get_prompt(name, arguments).await {
-            Ok(resp) => resp,
-            Err(e) => {
-                match e {
-                    GetPromptError::AmbiguousPrompt(prompt_name, alt_msg) => {
-                        queue!(
-                            session.stderr,
-                            style::Print("\n"),
-                            style::SetForegroundColor(Color::Yellow),
-                            style::Print("Prompt "),
-                            style::SetForegroundColor(Color::Cyan),
-                            style::Print(prompt_name),
-                            style::SetForegroundColor(Color::Yellow),
-                            style::Print(" is ambiguous. Use one of the following "),
-                            style::SetForegroundColor(Color::Cyan),
-                            style::Print(alt_msg),
-                            style::SetForegroundColor(Color::Reset),
-                        )?;
-                    },
-                    GetPromptError::PromptNotFound(prompt_name) => {
-                        queue!(
-                            session.stderr,
-                            style::Print("\n"),
-                            style::SetForegroundColor(Color::Yellow),
-                            style::Print("Prompt "),
-                            style::SetForegroundColor(Color::Cyan),
-                            style::Print(prompt_name),
-                            style::SetForegroundColor(Color::Yellow),
-                            style::Print(" not found. Use "),
-                            style::SetForegroundColor(Color::Cyan),
-                            style::Print("/prompts list"),
-                            style::SetForegroundColor(Color::Yellow),
-                            style::Print(" to see available prompts.\n"),
-                            style::SetForegroundColor(Color::Reset),
-                        )?;
-                    },
-                    _ => return Err(ChatError::Custom(e.to_string().into())),
-                }
-                execute!(session.stderr, style::Print("\n"))?;
-                return Ok(ChatState::PromptUser {
-                    skip_printing_tools: true,
-                });
-            },
+        let Some(bundle) = select_prompt(session, &name).await? else {
+            execute!(session.stderr, style::Print("\n"))?;
+            return Ok(ChatState::PromptUser {
+                skip_printing_tools: true,
+            });
         };
+        let Some(arguments) = collect_arguments(session, &bundle, arguments)? else {
+            execute!(session.stderr, style::Print("\n"))?;
+            return Ok(ChatState::PromptUser {
+                skip_printing_tools: true,
+            });
+        };
+        let arguments = (!arguments.is_empty()).then_some(arguments);
+
+        let prompts = session
+            .conversation
+            .tool_manager
+            .get_prompt(&bundle, arguments)
+            .await
+            .map_err(|e| ChatError::Custom(e.to_string().into()))?;
         if let Some(err) = prompts.error {
             // If we are running into error we should just display the error
             // and abort.
@@ -295,11 +280,15 @@ impl PromptsSubcommand {
                 .ok_or(ChatError::Custom("Result field missing from prompt/get request".into()))?;
             let prompts = serde_json::from_value::<PromptGetResult>(prompts)
                 .map_err(|e| ChatError::Custom(format!("Failed to deserialize prompt/get result: {:?}", e).into()))?;
-            session.pending_prompts.clear();
-            session.pending_prompts.append(&mut VecDeque::from(prompts.messages));
-            return Ok(ChatState::HandleInput {
-                input: orig_input.unwrap_or_default(),
-            });
+            if preview {
+                print_preview(session, &bundle, &prompts)?;
+            } else {
+                session.pending_prompts.clear();
+                session.pending_prompts.append(&mut VecDeque::from(prompts.messages));
+                return Ok(ChatState::HandleInput {
+                    input: orig_input.unwrap_or_default(),
+                });
+            }
         }
 
         execute!(session.stderr, style::Print("\n"))?;
@@ -313,6 +302,203 @@ impl PromptsSubcommand {
         match self {
             PromptsSubcommand::List { .. } => "list",
             PromptsSubcommand::Get { .. } => "get",
+            PromptsSubcommand::Preview { .. } => "preview",
         }
     }
 }
+
+/// Label shown next to a prompt in `/prompts list` naming where the prompt comes from.
+fn source_badge(bundle: &PromptBundle) -> String {
+    format!("[mcp: {}]", bundle.server_name)
+}
+
+/// Resolves `name` to a single prompt. If the prompt is offered by more than one server and no
+/// server was specified, the user picks one from a list.
+///
+/// Returns [None] if no prompt was found or selected, in which case the reason has already been
+/// displayed.
+async fn select_prompt(session: &mut ChatSession, name: &str) -> Result<Option<PromptBundle>, ChatError> {
+    let mut bundles = match session.conversation.tool_manager.find_prompts(name).await {
+        Ok(bundles) => bundles,
+        Err(GetPromptError::PromptNotFound(prompt_name)) => {
+            queue!(
+                session.stderr,
+                style::Print("\n"),
+                style::SetForegroundColor(Color::Yellow),
+                style::Print("Prompt "),
+                style::SetForegroundColor(Color::Cyan),
+                style::Print(prompt_name),
+                style::SetForegroundColor(Color::Yellow),
+                style::Print(" not found. Use "),
+                style::SetForegroundColor(Color::Cyan),
+                style::Print("/prompts list"),
+                style::SetForegroundColor(Color::Yellow),
+                style::Print(" to see available prompts.\n"),
+                style::SetForegroundColor(Color::Reset),
+            )?;
+            return Ok(None);
+        },
+        Err(e) => return Err(ChatError::Custom(e.to_string().into())),
+    };
+    if bundles.len() == 1 {
+        return Ok(bundles.pop());
+    }
+
+    bundles.sort_by(|a, b| a.server_name.cmp(&b.server_name));
+    let options = bundles
+        .iter()
+        .map(|bundle| format!("@{}/{}", bundle.server_name, bundle.prompt_get.name))
+        .collect::<Vec<_>>();
+
+    if !session.interactive {
+        queue!(
+            session.stderr,
+            style::Print("\n"),
+            style::SetForegroundColor(Color::Yellow),
+            style::Print("Prompt "),
+            style::SetForegroundColor(Color::Cyan),
+            style::Print(name),
+            style::SetForegroundColor(Color::Yellow),
+            style::Print(" is ambiguous. Use one of the following "),
+            style::SetForegroundColor(Color::Cyan),
+            style::Print(options.iter().fold("\n".to_string(), |mut acc, option| {
+                acc.push_str(&format!("- {option}\n"));
+                acc
+            })),
+            style::SetForegroundColor(Color::Reset),
+        )?;
+        return Ok(None);
+    }
+
+    let selection = match Select::with_theme(&crate::util::dialoguer_theme())
+        .with_prompt(format!("Prompt {name} is offered by more than one server"))
+        .items(&options)
+        .default(0)
+        .interact_on_opt(&dialoguer::console::Term::stdout())
+    {
+        Ok(selection) => selection,
+        // Ctrl‑C -> Err(Interrupted)
+        Err(dialoguer::Error::IO(ref e)) if e.kind() == std::io::ErrorKind::Interrupted => None,
+        Err(e) => return Err(ChatError::Custom(format!("Failed to get prompt selection: {e}").into())),
+    };
+
+    Ok(selection.map(|i| bundles.swap_remove(i)))
+}
+
+/// Maps the positional `arguments` onto the arguments declared by the prompt, in order. In
+/// interactive sessions, required arguments that were not provided are asked for by name, showing
+/// the argument's description and default value if the server advertises them.
+///
+/// Returns [None] if the user cancelled.
+fn collect_arguments(
+    session: &mut ChatSession,
+    bundle: &PromptBundle,
+    arguments: Option<Vec<String>>,
+) -> Result<Option<HashMap<String, String>>, ChatError> {
+    let schema = bundle.prompt_get.arguments.as_deref().unwrap_or_default();
+    let mut values = arguments.unwrap_or_default().into_iter();
+    let mut collected = HashMap::new();
+
+    for arg in schema {
+        if let Some(value) = values.next() {
+            collected.insert(arg.name.clone(), value);
+            continue;
+        }
+        if arg.required != Some(true) || !session.interactive {
+            continue;
+        }
+
+        if let Some(description) = &arg.description {
+            execute!(
+                session.stderr,
+                style::SetForegroundColor(Color::DarkGrey),
+                style::Print(format!("{description}\n")),
+                style::SetForegroundColor(Color::Reset),
+            )?;
+        }
+        let theme = crate::util::dialoguer_theme();
+        let mut input = dialoguer::Input::<String>::with_theme(&theme).with_prompt(&arg.name);
+        if let Some(default) = &arg.default {
+            input = input.default(default.clone());
+        }
+        match input.interact_text() {
+            Ok(value) => {
+                collected.insert(arg.name.clone(), value);
+            },
+            Err(dialoguer::Error::IO(ref e)) if e.kind() == std::io::ErrorKind::Interrupted => return Ok(None),
+            Err(e) => return Err(ChatError::Custom(format!("Failed to read prompt argument: {e}").into())),
+        }
+    }
+
+    Ok(Some(collected))
+}
+
+fn print_preview(session: &mut ChatSession, bundle: &PromptBundle, prompt: &PromptGetResult) -> Result<(), ChatError> {
+    queue!(
+        session.stderr,
+        style::Print("\n"),
+        style::SetAttribute(Attribute::Bold),
+        style::Print(format!("@{}/{}", bundle.server_name, bundle.prompt_get.name)),
+        style::SetAttribute(Attribute::Reset),
+        style::Print("\n"),
+    )?;
+    if let Some(description) = &prompt.description {
+        queue!(
+            session.stderr,
+            style::SetForegroundColor(Color::DarkGrey),
+            style::Print(format!("{description}\n")),
+            style::SetForegroundColor(Color::Reset),
+        )?;
+    }
+    for message in &prompt.messages {
+        queue!(
+            session.stderr,
+            style::Print("\n"),
+            style::SetForegroundColor(Color::Cyan),
+            style::Print(format!("{}:\n", message.role)),
+            style::SetForegroundColor(Color::Reset),
+            style::Print(format!("{}\n", message.content)),
+        )?;
+    }
+    queue!(
+        session.stderr,
+        style::Print("\n"),
+        style::SetForegroundColor(Color::DarkGrey),
+        style::Print("This is a preview, nothing was sent to the model. Use "),
+        style::SetForegroundColor(Color::Green),
+        style::Print("/prompts get"),
+        style::SetForegroundColor(Color::DarkGrey),
+        style::Print(" to submit the prompt.\n"),
+        style::SetForegroundColor(Color::Reset),
+    )?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use clap::Parser;
+
+    use super::*;
+
+    #[derive(Parser)]
+    struct TestCli {
+        #[command(flatten)]
+        args: PromptsArgs,
+    }
+
+    #[test]
+    fn test_prompts_use_alias() {
+        let args = TestCli::try_parse_from(["prompts", "use", "git/commit-message", "staged", "short"])
+            .unwrap()
+            .args;
+        assert_eq!(
+            args.subcommand,
+            Some(PromptsSubcommand::Get {
+                orig_input: None,
+                name: "git/commit-message".to_string(),
+                arguments: Some(vec!["staged".to_string(), "short".to_string()]),
+            })
+        );
+        assert_eq!(args.subcommand_name(), Some("get"));
+    }
+}
@@ -0,0 +1,171 @@
+use clap::Args;
+use crossterm::style::{
+    self,
+    Color,
+};
+use crossterm::{
+    execute,
+    queue,
+};
+use dialoguer::Select;
+
+use crate::cli::chat::cli::persist::PersistSubcommand;
+use crate::cli::chat::{
+    ChatError,
+    ChatSession,
+    ChatState,
+};
+use crate::database::SavedConversation;
+use crate::os::Os;
+
+#[deny(missing_docs)]
+#[derive(Debug, PartialEq, Args)]
+pub struct SessionsArgs {
+    /// Include conversations saved in other workspaces
+    #[arg(long)]
+    all: bool,
+}
+
+impl SessionsArgs {
+    pub async fn execute(self, os: &Os, session: &mut ChatSession) -> Result<ChatState, ChatError> {
+        let saved = saved_conversations(os, self.all)?;
+        if saved.is_empty() {
+            execute!(
+                session.stderr,
+                style::SetForegroundColor(Color::Yellow),
+                style::Print(match self.all {
+                    true => "\nNo saved conversations. Save one with /save <path>\n\n",
+                    false => "\nNo conversations saved in this workspace. List all of them with /sessions --all\n\n",
+                }),
+                style::SetForegroundColor(Color::Reset)
+            )?;
+            return Ok(ChatState::PromptUser {
+                skip_printing_tools: true,
+            });
+        }
+
+        let labels = saved
+            .iter()
+            .map(|saved| session_label(saved, self.all))
+            .collect::<Vec<_>>();
+        if !session.interactive {
+            queue!(session.stderr, style::Print("\n"))?;
+            for label in &labels {
+                queue!(session.stderr, style::Print(format!("{label}\n")))?;
+            }
+            execute!(session.stderr, style::Print("\n"))?;
+            return Ok(ChatState::PromptUser {
+                skip_printing_tools: true,
+            });
+        }
+
+        let selection = match Select::with_theme(&crate::util::dialoguer_theme())
+            .with_prompt("Select a conversation to load")
+            .items(&labels)
+            .default(0)
+            .interact_on_opt(&dialoguer::console::Term::stdout())
+        {
+            Ok(selection) => selection,
+            // Ctrl‑C -> Err(Interrupted)
+            Err(dialoguer::Error::IO(ref e)) if e.kind() == std::io::ErrorKind::Interrupted => None,
+            Err(e) => return Err(ChatError::Custom(format!("Failed to select: {e}").into())),
+        };
+
+        match selection {
+            Some(index) => {
+                PersistSubcommand::Load {
+                    path: saved[index].path.to_string_lossy().into_owned(),
+                }
+                .execute(os, session)
+                .await
+            },
+            None => Ok(ChatState::PromptUser {
+                skip_printing_tools: true,
+            }),
+        }
+    }
+}
+
+/// Returns the conversations saved in the current workspace, or in every workspace with `all`,
+/// newest first. Conversations whose file was deleted since are forgotten.
+fn saved_conversations(os: &Os, all: bool) -> Result<Vec<SavedConversation>, ChatError> {
+    let workspace = os.env.current_dir()?;
+    let mut saved = os
+        .database
+        .get_saved_conversations()
+        .map_err(|err| ChatError::Custom(format!("Failed to read the saved conversations: {err}").into()))?;
+
+    saved.retain(|saved| {
+        let exists = os.fs.exists(&saved.path);
+        if !exists {
+            let _ = os.database.delete_saved_conversation(&saved.path);
+        }
+        exists && (all || saved.workspace == workspace)
+    });
+    saved.sort_by(|a, b| b.saved_at.cmp(&a.saved_at));
+    Ok(saved)
+}
+
+fn session_label(saved: &SavedConversation, show_workspace: bool) -> String {
+    let saved_at: chrono::DateTime<chrono::Local> = std::time::SystemTime::from(saved.saved_at).into();
+    let mut label = format!(
+        "{}  {}  {} messages",
+        saved.title,
+        saved_at.format("%Y-%m-%d %H:%M"),
+        saved.message_count
+    );
+    if let Some(agent) = &saved.agent {
+        label.push_str(&format!("  {agent}"));
+    }
+    if show_workspace {
+        label.push_str(&format!("  {}", saved.workspace.display()));
+    }
+    label
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use time::OffsetDateTime;
+
+    use super::*;
+
+    #[tokio::test]
+    async fn test_saved_conversations() {
+        let os = Os::new().await.unwrap();
+        let workspace = os.env.current_dir().unwrap();
+        let conversation = |name: &str, workspace: PathBuf, saved_at: i64| SavedConversation {
+            path: workspace.join(format!("{name}.json")),
+            title: name.to_string(),
+            workspace,
+            saved_at: OffsetDateTime::from_unix_timestamp(saved_at).unwrap(),
+            message_count: 2,
+            agent: None,
+        };
+        let saved = [
+            conversation("older", workspace.clone(), 1_000),
+            conversation("newer", workspace.clone(), 2_000),
+            conversation("elsewhere", PathBuf::from("/elsewhere"), 3_000),
+            conversation("deleted", workspace.clone(), 4_000),
+        ];
+        for saved in &saved {
+            if saved.title != "deleted" {
+                os.fs.create_dir_all(&saved.workspace).await.unwrap();
+                os.fs.write(&saved.path, "{}").await.unwrap();
+            }
+            os.database.set_saved_conversation(saved).unwrap();
+        }
+
+        let titles = |all| {
+            saved_conversations(&os, all)
+                .unwrap()
+                .into_iter()
+                .map(|saved| saved.title)
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(titles(false), vec!["newer", "older"]);
+        assert_eq!(titles(true), vec!["elsewhere", "newer", "older"]);
+        assert_eq!(os.database.get_saved_conversations().unwrap().len(), 3);
+    }
+}
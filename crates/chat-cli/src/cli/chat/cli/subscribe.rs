@@ -11,6 +11,7 @@ use crossterm::{
 };
 
 use crate::auth::builder_id::is_idc_user;
+use crate::cli::chat::tools::plain_output;
 use crate::cli::chat::{
     ActualSubscriptionStatus,
     ChatError,
@@ -168,11 +169,16 @@ async fn upgrade_to_pro(os: &mut Os, session: &mut ChatSession) -> Result<(), Ch
     // Create a subscription token and open the webpage
     let r = os.client.create_subscription_token().await?;
 
-    let url = with_spinner(&mut session.stderr, "Preparing to upgrade...", || async move {
-        r.encoded_verification_url()
-            .map(|s| s.to_string())
-            .ok_or(ChatError::Custom("Missing verification URL".into()))
-    })
+    let url = with_spinner(
+        plain_output(os),
+        &mut session.stderr,
+        "Preparing to upgrade...",
+        || async move {
+            r.encoded_verification_url()
+                .map(|s| s.to_string())
+                .ok_or(ChatError::Custom("Missing verification URL".into()))
+        },
+    )
     .await?;
 
     if is_remote() || crate::util::open::open_url_async(&url).await.is_err() {
@@ -0,0 +1,189 @@
+use clap::CommandFactory;
+
+use super::SlashCommand;
+
+/// Maximum number of suggestions shown for an unknown command.
+const MAX_SUGGESTIONS: usize = 3;
+/// Candidates further than this from the typed token are not suggested.
+const MAX_SUGGESTION_DISTANCE: usize = 2;
+
+/// "Did you mean" suggestions for a slash command that clap failed to recognize.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CommandSuggestion {
+    /// The unrecognized command as typed, e.g. `/contxt` or `/context shw`
+    pub unknown: String,
+    /// Index into the user's arguments of the token that was not recognized
+    pub token_index: usize,
+    /// Candidate replacements for the unrecognized token with their edit distance, closest first
+    pub candidates: Vec<(String, usize)>,
+}
+
+impl CommandSuggestion {
+    /// Computes suggestions for `args`, the user's input split into arguments without the leading
+    /// slash. Returns [None] if every command token is recognized or nothing is close enough.
+    pub fn from_args(args: &[String]) -> Option<Self> {
+        let root = SlashCommand::command();
+        let first = args.first()?;
+
+        let (token_index, parent) = match root.find_subcommand(first) {
+            None => (0, &root),
+            Some(command) => {
+                let second = args.get(1)?;
+                if !command.has_subcommands() || second.starts_with('-') || command.find_subcommand(second).is_some() {
+                    return None;
+                }
+                (1, command)
+            },
+        };
+
+        let token = args[token_index].as_str();
+        let mut candidates = parent
+            .get_subcommands()
+            .filter(|c| c.get_name() != "help")
+            .map(|c| (c.get_name().to_string(), levenshtein(token, c.get_name())))
+            .filter(|(_, distance)| *distance <= MAX_SUGGESTION_DISTANCE)
+            .collect::<Vec<_>>();
+        if candidates.is_empty() {
+            return None;
+        }
+        candidates.sort_by(|a, b| a.1.cmp(&b.1).then_with(|| a.0.cmp(&b.0)));
+        candidates.truncate(MAX_SUGGESTIONS);
+
+        Some(Self {
+            unknown: format!("/{}", args[..=token_index].join(" ")),
+            token_index,
+            candidates,
+        })
+    }
+
+    /// The suggested commands, formatted the way a user would type them
+    pub fn commands(&self, args: &[String]) -> Vec<String> {
+        let prefix = args[..self.token_index].join(" ");
+        self.candidates
+            .iter()
+            .map(|(name, _)| {
+                if prefix.is_empty() {
+                    format!("/{name}")
+                } else {
+                    format!("/{prefix} {name}")
+                }
+            })
+            .collect()
+    }
+
+    /// Returns the corrected input if there is a single candidate one edit away, which is safe
+    /// enough to offer running directly.
+    pub fn corrected_input(&self, args: &[String]) -> Option<String> {
+        match self.candidates.as_slice() {
+            [(name, 1)] => {
+                let mut corrected = args.to_vec();
+                corrected[self.token_index] = name.clone();
+                let joined =
+                    shlex::try_join(corrected.iter().map(|s| s.as_str())).unwrap_or_else(|_| corrected.join(" "));
+                Some(format!("/{joined}"))
+            },
+            _ => None,
+        }
+    }
+
+    /// Message shown in place of clap's error
+    pub fn message(&self, args: &[String]) -> String {
+        let quoted = self.commands(args).iter().map(|c| format!("'{c}'")).collect::<Vec<_>>();
+        let options = match quoted.as_slice() {
+            [] => String::new(),
+            [only] => only.clone(),
+            [rest @ .., last] => format!("{}, or {}", rest.join(", "), last),
+        };
+        format!("Unknown command '{}'. Did you mean {}?", self.unknown, options)
+    }
+}
+
+/// Edit distance between two strings, counted in characters.
+pub fn levenshtein(a: &str, b: &str) -> usize {
+    let b_chars = b.chars().collect::<Vec<_>>();
+    let mut previous = (0..=b_chars.len()).collect::<Vec<_>>();
+    let mut current = vec![0; b_chars.len() + 1];
+
+    for (i, a_char) in a.chars().enumerate() {
+        current[0] = i + 1;
+        for (j, b_char) in b_chars.iter().enumerate() {
+            let substitution = previous[j] + usize::from(a_char != *b_char);
+            current[j + 1] = substitution.min(previous[j + 1] + 1).min(current[j] + 1);
+        }
+        std::mem::swap(&mut previous, &mut current);
+    }
+
+    previous[b_chars.len()]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args(input: &str) -> Vec<String> {
+        shlex::split(input).unwrap()
+    }
+
+    #[test]
+    fn test_levenshtein() {
+        assert_eq!(levenshtein("", ""), 0);
+        assert_eq!(levenshtein("context", "context"), 0);
+        assert_eq!(levenshtein("contxt", "context"), 1);
+        assert_eq!(levenshtein("kitten", "sitting"), 3);
+        assert_eq!(levenshtein("", "abc"), 3);
+    }
+
+    #[test]
+    fn test_suggests_top_level_command() {
+        let args = args("contxt show");
+        let suggestion = CommandSuggestion::from_args(&args).unwrap();
+        assert_eq!(suggestion.unknown, "/contxt");
+        assert_eq!(suggestion.commands(&args)[0], "/context");
+        assert_eq!(
+            suggestion.message(&args),
+            "Unknown command '/contxt'. Did you mean '/context'?"
+        );
+        assert_eq!(suggestion.corrected_input(&args).as_deref(), Some("/context show"));
+    }
+
+    #[test]
+    fn test_suggests_subcommand() {
+        let args = args("context shw");
+        let suggestion = CommandSuggestion::from_args(&args).unwrap();
+        assert_eq!(suggestion.unknown, "/context shw");
+        assert_eq!(suggestion.commands(&args), vec!["/context show".to_string()]);
+        assert_eq!(suggestion.corrected_input(&args).as_deref(), Some("/context show"));
+    }
+
+    #[test]
+    fn test_ambiguous_suggestions_are_not_run() {
+        // "tool" is one edit from "tools" and two from "todos"
+        let args = args("tool");
+        let suggestion = CommandSuggestion::from_args(&args).unwrap();
+        assert!(suggestion.candidates.len() > 1);
+        assert!(suggestion.candidates.len() <= MAX_SUGGESTIONS);
+        assert_eq!(suggestion.candidates[0], ("tools".to_string(), 1));
+        assert_eq!(suggestion.corrected_input(&args), None);
+    }
+
+    #[test]
+    fn test_no_suggestions() {
+        assert_eq!(CommandSuggestion::from_args(&args("context show")), None);
+        assert_eq!(CommandSuggestion::from_args(&args("xyzzyplugh")), None);
+        assert_eq!(CommandSuggestion::from_args(&args("context --help")), None);
+        assert_eq!(CommandSuggestion::from_args(&[]), None);
+    }
+
+    #[test]
+    fn test_message_lists_multiple_suggestions() {
+        let suggestion = CommandSuggestion {
+            unknown: "/x".to_string(),
+            token_index: 0,
+            candidates: vec![("a".to_string(), 1), ("b".to_string(), 1), ("c".to_string(), 2)],
+        };
+        assert_eq!(
+            suggestion.message(&args("x")),
+            "Unknown command '/x'. Did you mean '/a', '/b', or '/c'?"
+        );
+    }
+}
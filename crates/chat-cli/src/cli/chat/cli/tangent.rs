@@ -14,7 +14,14 @@ use crate::database::settings::Setting;
 use crate::os::Os;
 
 #[derive(Debug, PartialEq, Args)]
-pub struct TangentArgs;
+pub struct TangentArgs {
+    /// Add a summary of the tangent to the main conversation when leaving tangent mode
+    #[arg(long, visible_alias = "summarize-on-exit", conflicts_with = "discard")]
+    pub merge_on_exit: bool,
+    /// Discard the tangent when leaving tangent mode, even if a summary was requested
+    #[arg(long)]
+    pub discard: bool,
+}
 
 impl TangentArgs {
     pub async fn execute(self, os: &Os, session: &mut ChatSession) -> Result<ChatState, ChatError> {
@@ -36,37 +43,22 @@ impl TangentArgs {
             });
         }
         if session.conversation.is_in_tangent_mode() {
-            // Get duration before exiting tangent mode
-            let duration_seconds = session.conversation.get_tangent_duration_seconds().unwrap_or(0);
-
-            session.conversation.exit_tangent_mode();
-
-            // Send telemetry for tangent mode session
-            if let Err(err) = os
-                .telemetry
-                .send_tangent_mode_session(
-                    &os.database,
-                    session.conversation.conversation_id().to_string(),
-                    crate::telemetry::TelemetryResult::Succeeded,
-                    crate::telemetry::core::TangentModeSessionArgs { duration_seconds },
-                )
-                .await
-            {
-                tracing::warn!(?err, "Failed to send tangent mode session telemetry");
+            let merge = !self.discard
+                && (self.merge_on_exit
+                    || session.conversation.tangent_merge_on_exit()
+                    || os
+                        .database
+                        .settings
+                        .get_bool(Setting::ChatTangentMergeSummary)
+                        .unwrap_or(false));
+            if merge {
+                // Summarizing requires a model request, which manages ctrl+c on its own
+                return Ok(ChatState::MergeTangent);
             }
-
-            execute!(
-                session.stderr,
-                style::SetForegroundColor(Color::DarkGrey),
-                style::Print("Restored conversation from checkpoint ("),
-                style::SetForegroundColor(Color::Yellow),
-                style::Print("↯"),
-                style::SetForegroundColor(Color::DarkGrey),
-                style::Print("). - Returned to main conversation.\n"),
-                style::SetForegroundColor(Color::Reset)
-            )?;
+            exit_tangent_mode(os, session, None).await?;
         } else {
             session.conversation.enter_tangent_mode();
+            session.conversation.set_tangent_merge_on_exit(self.merge_on_exit);
 
             // Get the configured tangent mode key for display
             let tangent_key_char = match os
@@ -106,8 +98,52 @@ impl TangentArgs {
     }
 }
 
+/// Restores the main conversation, adding `summary` to it as a context note if given.
+pub async fn exit_tangent_mode(os: &Os, session: &mut ChatSession, summary: Option<String>) -> Result<(), ChatError> {
+    // Get duration before exiting tangent mode
+    let duration_seconds = session.conversation.get_tangent_duration_seconds().unwrap_or(0);
+
+    match &summary {
+        Some(summary) => session.conversation.exit_tangent_mode_with_summary(summary),
+        None => session.conversation.exit_tangent_mode(),
+    }
+
+    // Send telemetry for tangent mode session
+    if let Err(err) = os
+        .telemetry
+        .send_tangent_mode_session(
+            &os.database,
+            session.conversation.conversation_id().to_string(),
+            crate::telemetry::TelemetryResult::Succeeded,
+            crate::telemetry::core::TangentModeSessionArgs { duration_seconds },
+        )
+        .await
+    {
+        tracing::warn!(?err, "Failed to send tangent mode session telemetry");
+    }
+
+    execute!(
+        session.stderr,
+        style::SetForegroundColor(Color::DarkGrey),
+        style::Print("Restored conversation from checkpoint ("),
+        style::SetForegroundColor(Color::Yellow),
+        style::Print("↯"),
+        style::SetForegroundColor(Color::DarkGrey),
+        style::Print(match summary {
+            Some(_) => "). - Returned to main conversation with a summary of the tangent.\n",
+            None => "). - Returned to main conversation.\n",
+        }),
+        style::SetForegroundColor(Color::Reset)
+    )?;
+
+    Ok(())
+}
+
 #[cfg(test)]
 mod tests {
+    use clap::Parser;
+
+    use super::TangentArgs;
     use crate::cli::agent::Agents;
     use crate::cli::chat::conversation::ConversationState;
     use crate::cli::chat::tool_manager::ToolManager;
@@ -144,4 +180,23 @@ mod tests {
         assert!(!conversation.is_in_tangent_mode());
         assert!(conversation.get_tangent_duration_seconds().is_none());
     }
+
+    #[derive(Parser)]
+    struct TestCli {
+        #[command(flatten)]
+        args: TangentArgs,
+    }
+
+    #[test]
+    fn test_tangent_args_parsing() {
+        let args = TestCli::try_parse_from(["tangent"]).unwrap().args;
+        assert!(!args.merge_on_exit && !args.discard);
+
+        for flag in ["--merge-on-exit", "--summarize-on-exit"] {
+            let args = TestCli::try_parse_from(["tangent", flag]).unwrap().args;
+            assert!(args.merge_on_exit, "{flag}");
+        }
+
+        assert!(TestCli::try_parse_from(["tangent", "--summarize-on-exit", "--discard"]).is_err());
+    }
 }
@@ -7,12 +7,14 @@ use crossterm::style::{
 use dialoguer::Select;
 use eyre::Result;
 
+use crate::cli::chat::tools::plain_output;
 use crate::cli::chat::tools::todo::{
     TodoList,
     TodoListState,
     delete_todo,
     get_all_todos,
 };
+use crate::cli::chat::util::ui::glyph;
 use crate::cli::chat::{
     ChatError,
     ChatSession,
@@ -45,17 +47,23 @@ pub struct TodoDisplayEntry {
     pub num_tasks: usize,
     pub description: String,
     pub id: String,
+    pub plain: bool,
 }
 
 impl std::fmt::Display for TodoDisplayEntry {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         if self.num_completed == self.num_tasks {
-            write!(f, "{} {}", "✓".green().bold(), self.description.clone(),)
+            write!(
+                f,
+                "{} {}",
+                glyph(self.plain, "✓", "[done]").green().bold(),
+                self.description.clone(),
+            )
         } else {
             write!(
                 f,
                 "{} {} ({}/{})",
-                "✗".red().bold(),
+                glyph(self.plain, "✗", "[todo]").red().bold(),
                 self.description.clone(),
                 self.num_completed,
                 self.num_tasks
@@ -102,7 +110,9 @@ impl TodoSubcommand {
                 if cleared_one {
                     execute!(
                         session.stderr,
-                        style::Print("✔ Cleared finished to-do lists!\n".green())
+                        style::Print(
+                            format!("{}Cleared finished to-do lists!\n", glyph(plain_output(os), "✔ ", "")).green()
+                        )
                     )?;
                 } else {
                     execute!(session.stderr, style::Print("No finished to-do lists to clear!\n"))?;
@@ -124,7 +134,7 @@ impl TodoSubcommand {
                                 session.stderr,
                                 style::Print(format!(
                                     "{} {}",
-                                    "⟳ Resuming:".magenta(),
+                                    format!("{}Resuming:", glyph(plain_output(os), "⟳ ", "")).magenta(),
                                     entries[index].description.clone()
                                 ))
                             )?;
@@ -170,7 +180,12 @@ impl TodoSubcommand {
                                 .await
                                 .map_err(|_e| ChatError::Custom("Could not delete all to-do lists".into()))?;
                         }
-                        execute!(session.stderr, style::Print("✔ Deleted all to-do lists!\n".green()),)?;
+                        execute!(
+                            session.stderr,
+                            style::Print(
+                                format!("{}Deleted all to-do lists!\n", glyph(plain_output(os), "✔ ", "")).green()
+                            ),
+                        )?;
                     } else if let Some(index) = fuzzy_select_todos(&entries, "Select a to-do list to delete:") {
                         if index < entries.len() {
                             delete_todo(os, &entries[index].id).await.map_err(|e| {
@@ -178,7 +193,9 @@ impl TodoSubcommand {
                             })?;
                             execute!(
                                 session.stderr,
-                                style::Print("✔ Deleted to-do list: ".green()),
+                                style::Print(
+                                    format!("{}Deleted to-do list: ", glyph(plain_output(os), "✔ ", "")).green()
+                                ),
                                 style::Print(format!("{}\n", entries[index].description.clone().dark_grey()))
                             )?;
                         }
@@ -195,6 +212,7 @@ impl TodoSubcommand {
     /// Convert all to-do list state entries to displayable entries
     async fn get_descriptions_and_statuses(os: &Os) -> Result<Vec<TodoDisplayEntry>> {
         let mut out = Vec::new();
+        let plain = plain_output(os);
         let (todos, _) = get_all_todos(os).await?;
         for todo in todos.iter() {
             out.push(TodoDisplayEntry {
@@ -202,6 +220,7 @@ impl TodoSubcommand {
                 num_tasks: todo.tasks.len(),
                 description: todo.description.clone(),
                 id: todo.id.clone(),
+                plain,
             });
         }
         Ok(out)
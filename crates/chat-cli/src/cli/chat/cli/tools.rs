@@ -26,13 +26,20 @@ use crate::cli::chat::consts::{
     AGENT_FORMAT_TOOLS_DOC_URL,
     DUMMY_TOOL_NAME,
 };
-use crate::cli::chat::tools::ToolOrigin;
+use crate::cli::chat::tool_manager::ToolInfo;
+use crate::cli::chat::tools::{
+    ToolOrigin,
+    plain_output,
+};
+use crate::cli::chat::util::tool_display::DisplayOutput;
+use crate::cli::chat::util::ui::glyph;
 use crate::cli::chat::{
     ChatError,
     ChatSession,
     ChatState,
     TRUST_ALL_TEXT,
 };
+use crate::os::Os;
 use crate::util::consts::MCP_SERVER_TOOL_DELIMITER;
 
 #[deny(missing_docs)]
@@ -43,7 +50,7 @@ pub struct ToolsArgs {
 }
 
 impl ToolsArgs {
-    pub async fn execute(self, session: &mut ChatSession) -> Result<ChatState, ChatError> {
+    pub async fn execute(self, os: &Os, session: &mut ChatSession) -> Result<ChatState, ChatError> {
         if let Some(subcommand) = self.subcommand {
             return subcommand.execute(session).await;
         }
@@ -175,7 +182,7 @@ impl ToolsArgs {
                 session.stderr,
                 style::SetForegroundColor(Color::Yellow),
                 style::Print("\n"),
-                style::Print("⚠️  WARNING: "),
+                style::Print(glyph(plain_output(os), "⚠️  WARNING: ", "WARNING: ")),
                 style::SetForegroundColor(Color::Reset),
                 style::Print("MCP functionality has been disabled by your administrator.\n\n"),
             )?;
@@ -200,12 +207,14 @@ Refer to the documentation for how to configure tools with your agent: https://g
 pub enum ToolsSubcommand {
     /// Show the input schema for all available tools
     Schema,
-    /// Trust a specific tool or tools for the session
+    /// Trust a specific tool or tools for the session. Use @server to trust every tool of an MCP
+    /// server
     Trust {
         #[arg(required = true)]
         tool_names: Vec<String>,
     },
-    /// Revert a tool or tools to per-request confirmation
+    /// Revert a tool or tools to per-request confirmation. Use @server to revert a server trusted
+    /// with @server
     Untrust {
         #[arg(required = true)]
         tool_names: Vec<String>,
@@ -214,6 +223,12 @@ pub enum ToolsSubcommand {
     TrustAll,
     /// Reset all tools to default permission levels
     Reset,
+    /// Set how much of a tool's output is shown in the terminal for the session
+    Output {
+        tool_name: String,
+        #[arg(value_enum)]
+        mode: DisplayOutput,
+    },
 }
 
 impl ToolsSubcommand {
@@ -244,6 +259,15 @@ impl ToolsSubcommand {
             })
             .unwrap_or_default();
 
+        // Servers can be referenced as @server before their tools have finished loading
+        let mut server_names = existing_custom_tools
+            .iter()
+            .map(|info| info.server_name.clone())
+            .collect::<HashSet<_>>();
+        if let Some(agent) = session.conversation.agents.get_active() {
+            server_names.extend(agent.mcp_servers.mcp_servers.keys().cloned());
+        }
+
         match self {
             Self::Schema => {
                 let schema_json = serde_json::to_string_pretty(&session.conversation.tool_manager.schema)
@@ -251,10 +275,8 @@ impl ToolsSubcommand {
                 queue!(session.stderr, style::Print(schema_json), style::Print("\n"))?;
             },
             Self::Trust { tool_names } => {
-                let (valid_tools, invalid_tools): (Vec<String>, Vec<String>) =
-                    tool_names.into_iter().partition(|tool_name| {
-                        existing_custom_tools.contains(tool_name) || native_tool_names.contains(tool_name)
-                    });
+                let (tools_to_trust, invalid_tools) =
+                    resolve_tool_names(tool_names, &native_tool_names, &existing_custom_tools, &server_names);
 
                 if !invalid_tools.is_empty() {
                     queue!(
@@ -269,20 +291,7 @@ impl ToolsSubcommand {
                         style::SetForegroundColor(Color::Reset),
                     )?;
                 }
-                if !valid_tools.is_empty() {
-                    let tools_to_trust = valid_tools
-                        .into_iter()
-                        .filter_map(|tool_name| {
-                            if native_tool_names.contains(&tool_name) {
-                                Some(tool_name)
-                            } else {
-                                existing_custom_tools
-                                    .get(&tool_name)
-                                    .map(|info| format!("@{}{MCP_SERVER_TOOL_DELIMITER}{tool_name}", info.server_name))
-                            }
-                        })
-                        .collect::<Vec<_>>();
-
+                if !tools_to_trust.is_empty() {
                     queue!(
                         session.stderr,
                         style::SetForegroundColor(Color::Green),
@@ -312,10 +321,8 @@ impl ToolsSubcommand {
                 }
             },
             Self::Untrust { tool_names } => {
-                let (valid_tools, invalid_tools): (Vec<String>, Vec<String>) =
-                    tool_names.into_iter().partition(|tool_name| {
-                        existing_custom_tools.contains(tool_name) || native_tool_names.contains(tool_name)
-                    });
+                let (tools_to_untrust, invalid_tools) =
+                    resolve_tool_names(tool_names, &native_tool_names, &existing_custom_tools, &server_names);
 
                 if !invalid_tools.is_empty() {
                     queue!(
@@ -330,20 +337,7 @@ impl ToolsSubcommand {
                         style::SetForegroundColor(Color::Reset),
                     )?;
                 }
-                if !valid_tools.is_empty() {
-                    let tools_to_untrust = valid_tools
-                        .into_iter()
-                        .filter_map(|tool_name| {
-                            if native_tool_names.contains(&tool_name) {
-                                Some(tool_name)
-                            } else {
-                                existing_custom_tools
-                                    .get(&tool_name)
-                                    .map(|info| format!("@{}{MCP_SERVER_TOOL_DELIMITER}{tool_name}", info.server_name))
-                            }
-                        })
-                        .collect::<Vec<_>>();
-
+                if !tools_to_untrust.is_empty() {
                     session.conversation.agents.untrust_tools(&tools_to_untrust);
 
                     queue!(
@@ -370,7 +364,7 @@ impl ToolsSubcommand {
                 if let Some(path) = active_agent_path {
                     let result = async {
                         let content = tokio::fs::read(&path).await?;
-                        let orig_agent = serde_json::from_slice::<Agent>(&content)?;
+                        let orig_agent = Agent::parse(&content, &path)?;
                         // since all we're doing here is swapping the tool list, it's okay if we
                         // don't thaw it here
                         Ok::<Agent, Box<dyn std::error::Error>>(orig_agent)
@@ -400,6 +394,46 @@ impl ToolsSubcommand {
                     style::SetForegroundColor(Color::Reset),
                 )?;
             },
+            Self::Output { tool_name, mode } => {
+                let settings_name = if native_tool_names.contains(&tool_name) {
+                    Some(tool_name.clone())
+                } else {
+                    existing_custom_tools
+                        .get(&tool_name)
+                        .map(|info| format!("@{}{MCP_SERVER_TOOL_DELIMITER}{tool_name}", info.server_name))
+                };
+
+                match settings_name {
+                    Some(settings_name) => {
+                        queue!(
+                            session.stderr,
+                            style::SetForegroundColor(Color::Green),
+                            style::Print(format!(
+                                "\nOutput of '{}' is now {} in the terminal.",
+                                settings_name,
+                                match mode {
+                                    DisplayOutput::Full => "shown in full",
+                                    DisplayOutput::Summary => "summarized",
+                                    DisplayOutput::Silent => "hidden",
+                                }
+                            )),
+                            style::SetForegroundColor(Color::Reset),
+                        )?;
+                        session.tool_display_overrides.insert(settings_name, mode);
+                    },
+                    None => {
+                        queue!(
+                            session.stderr,
+                            style::SetForegroundColor(Color::Red),
+                            style::Print(format!(
+                                "\nCannot set the output of '{}', it does not exist.",
+                                tool_name
+                            )),
+                            style::SetForegroundColor(Color::Reset),
+                        )?;
+                    },
+                }
+            },
         };
 
         session.stderr.flush()?;
@@ -416,6 +450,57 @@ impl ToolsSubcommand {
             ToolsSubcommand::Untrust { .. } => "untrust",
             ToolsSubcommand::TrustAll => "trust-all",
             ToolsSubcommand::Reset => "reset",
+            ToolsSubcommand::Output { .. } => "output",
+        }
+    }
+}
+
+/// Maps the tool names given to `/tools trust` and `/tools untrust` to the names used in
+/// `allowedTools`, returning them along with the names that don't match any tool. `@server` is kept
+/// as is and stands for every tool of that MCP server.
+fn resolve_tool_names(
+    tool_names: Vec<String>,
+    native_tool_names: &[String],
+    custom_tools: &HashSet<ToolInfo>,
+    server_names: &HashSet<String>,
+) -> (Vec<String>, Vec<String>) {
+    let mut resolved = Vec::new();
+    let mut invalid = Vec::new();
+    for tool_name in tool_names {
+        if native_tool_names.contains(&tool_name) {
+            resolved.push(tool_name);
+        } else if let Some(info) = custom_tools.get(&tool_name) {
+            resolved.push(format!("@{}{MCP_SERVER_TOOL_DELIMITER}{tool_name}", info.server_name));
+        } else if tool_name
+            .strip_prefix('@')
+            .is_some_and(|server| server_names.contains(server))
+        {
+            resolved.push(tool_name);
+        } else {
+            invalid.push(tool_name);
         }
     }
+    (resolved, invalid)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_resolve_tool_names() {
+        let custom_tools = HashSet::from([ToolInfo {
+            server_name: "git".to_string(),
+            host_tool_name: "git_status".to_string(),
+        }]);
+        let server_names = HashSet::from(["git".to_string(), "fetch".to_string()]);
+        let tool_names = ["fs_read", "git_status", "@git", "@fetch", "@unknown", "git"]
+            .map(String::from)
+            .to_vec();
+
+        let (resolved, invalid) =
+            resolve_tool_names(tool_names, &["fs_read".to_string()], &custom_tools, &server_names);
+        assert_eq!(resolved, vec!["fs_read", "@git/git_status", "@git", "@fetch"]);
+        assert_eq!(invalid, vec!["@unknown", "git"]);
+    }
 }
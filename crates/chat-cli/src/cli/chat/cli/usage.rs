@@ -10,10 +10,9 @@ use crossterm::{
 };
 
 use super::model::context_window_tokens;
-use crate::cli::chat::token_counter::{
-    CharCount,
-    TokenCount,
-};
+use crate::cli::chat::token_counter::TokenCount;
+use crate::cli::chat::tools::plain_output;
+use crate::cli::chat::util::ui::glyph;
 use crate::cli::chat::{
     ChatError,
     ChatSession,
@@ -22,10 +21,18 @@ use crate::cli::chat::{
 use crate::os::Os;
 #[deny(missing_docs)]
 #[derive(Debug, PartialEq, Args)]
-pub struct UsageArgs;
+pub struct UsageArgs {
+    /// Show per-turn latency metrics (time to first token and output tokens per second)
+    #[arg(long)]
+    perf: bool,
+}
 
 impl UsageArgs {
     pub async fn execute(self, os: &Os, session: &mut ChatSession) -> Result<ChatState, ChatError> {
+        if self.perf {
+            return print_perf_metrics(session);
+        }
+
         let state = session
             .conversation
             .backend_conversation_state(os, true, &mut session.stderr)
@@ -45,16 +52,10 @@ impl UsageArgs {
         }
 
         let data = state.calculate_conversation_size();
-        let tool_specs_json: String = state
-            .tools
-            .values()
-            .filter_map(|s| serde_json::to_string(s).ok())
-            .collect::<Vec<String>>()
-            .join("");
         let context_token_count: TokenCount = data.context_messages.into();
         let assistant_token_count: TokenCount = data.assistant_messages.into();
         let user_token_count: TokenCount = data.user_messages.into();
-        let tools_char_count: CharCount = tool_specs_json.len().into(); // usize → CharCount
+        let tools_char_count = state.tools_char_count();
         let tools_token_count: TokenCount = tools_char_count.into(); // CharCount → TokenCount
         let total_token_used: TokenCount =
             (data.context_messages + data.user_messages + data.assistant_messages + tools_char_count).into();
@@ -184,10 +185,23 @@ impl UsageArgs {
             )),
         )?;
 
+        let retries = session.throttle_retry_count();
+        if retries > 0 {
+            queue!(
+                session.stderr,
+                style::SetForegroundColor(Color::DarkYellow),
+                style::Print(format!(
+                    "Requests retried after throttling or server errors this session: {}\n",
+                    retries
+                )),
+                style::SetForegroundColor(Color::Reset),
+            )?;
+        }
+
         queue!(
             session.stderr,
             style::SetAttribute(Attribute::Bold),
-            style::Print("\n💡 Pro Tips:\n"),
+            style::Print(format!("\n{}Pro Tips:\n", glyph(plain_output(os), "💡 ", ""))),
             style::SetAttribute(Attribute::Reset),
             style::SetForegroundColor(Color::DarkGrey),
             style::Print("Run "),
@@ -213,3 +227,64 @@ impl UsageArgs {
         })
     }
 }
+
+/// Prints time-to-first-token and output token throughput for the recorded user turns.
+///
+/// A high time to first token with normal throughput usually points at network or queueing
+/// latency, whereas low throughput points at the model itself.
+fn print_perf_metrics(session: &mut ChatSession) -> Result<ChatState, ChatError> {
+    let turns = session.turn_perf_metrics();
+    if turns.is_empty() {
+        execute!(
+            session.stderr,
+            style::SetForegroundColor(Color::DarkGrey),
+            style::Print(
+                "\nNo latency metrics recorded yet. Metrics are only collected when telemetry is enabled.\n\n"
+            ),
+            style::SetForegroundColor(Color::Reset),
+        )?;
+        return Ok(ChatState::PromptUser {
+            skip_printing_tools: true,
+        });
+    }
+
+    let mut rows = Vec::new();
+    for (i, turn) in turns.iter().enumerate() {
+        for (j, md) in turn.iter().enumerate() {
+            rows.push((
+                format!("{}.{}", i + 1, j + 1),
+                md.time_to_first_chunk
+                    .map_or("-".to_string(), |d| format!("{:.0} ms", d.as_secs_f64() * 1000.0)),
+                md.output_tokens_per_second()
+                    .map_or("-".to_string(), |v| format!("{:.1} tok/s", v)),
+                format!("{:.1} s", md.total_duration().as_secs_f64()),
+            ));
+        }
+    }
+
+    queue!(
+        session.stderr,
+        style::SetAttribute(Attribute::Bold),
+        style::Print(format!(
+            "\n{:<8}{:<16}{:<16}{}\n",
+            "Turn", "First token", "Throughput", "Total"
+        )),
+        style::SetAttribute(Attribute::Reset),
+    )?;
+    for (turn, ttft, throughput, total) in rows {
+        queue!(
+            session.stderr,
+            style::Print(format!("{:<8}{:<16}{:<16}{}\n", turn, ttft, throughput, total)),
+        )?;
+    }
+    execute!(
+        session.stderr,
+        style::SetForegroundColor(Color::DarkGrey),
+        style::Print("\nTurns with multiple requests (e.g. tool use follow ups) are listed as <turn>.<request>.\n\n"),
+        style::SetForegroundColor(Color::Reset),
+    )?;
+
+    Ok(ChatState::PromptUser {
+        skip_printing_tools: true,
+    })
+}
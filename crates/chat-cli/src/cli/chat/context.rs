@@ -1,12 +1,20 @@
 use std::collections::HashMap;
 use std::io::Write;
 use std::path::Path;
+use std::sync::{
+    Arc,
+    Mutex,
+};
 
 use eyre::{
     Result,
     eyre,
 };
 use glob::glob;
+use globset::{
+    GlobSet,
+    GlobSetBuilder,
+};
 use serde::{
     Deserialize,
     Deserializer,
@@ -24,7 +32,18 @@ use crate::cli::agent::hook::{
 use crate::cli::chat::ChatError;
 use crate::cli::chat::cli::hooks::HookExecutor;
 use crate::cli::chat::cli::model::ModelInfo;
+use crate::cli::chat::tools::plain_output;
 use crate::os::Os;
+use crate::util::directories;
+
+/// Default maximum number of files an agent's resources may add to the context.
+pub const DEFAULT_MAX_RESOURCE_FILES: usize = 50;
+/// Default maximum total size in bytes of the files an agent's resources may add to the context.
+pub const DEFAULT_MAX_RESOURCE_BYTES: usize = 1024 * 1024;
+/// Maximum number of MCP resources that can be added to the context with `/context add`.
+pub const MAX_MCP_RESOURCES: usize = 20;
+/// Maximum total size in bytes of the MCP resources added to the context with `/context add`.
+pub const MAX_MCP_RESOURCE_BYTES: usize = 1024 * 1024;
 
 #[derive(Debug, Clone)]
 pub enum ContextFilePath {
@@ -62,6 +81,15 @@ impl ContextFilePath {
             Self::Agent(path) | Self::Session(path) => path.as_str(),
         }
     }
+
+    /// Returns the pattern of an agent resource that excludes files, i.e. one written as
+    /// `!file://pattern` in the agent config.
+    pub fn exclusion(&self) -> Option<&str> {
+        match self {
+            Self::Agent(path) => path.strip_prefix('!'),
+            Self::Session(_) => None,
+        }
+    }
 }
 
 impl PartialEq for ContextFilePath {
@@ -89,16 +117,33 @@ impl PartialEq<ContextFilePath> for String {
     }
 }
 
+/// Files matched by each watched context path, shared between the [ContextManager] and the
+/// context watcher.
+pub type WatchedFiles = Arc<Mutex<HashMap<String, Vec<(String, String)>>>>;
+
 /// Manager for context files and profiles.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ContextManager {
     max_context_files_size: usize,
+    /// Maximum number of files that the agent's resources may add to the context.
+    #[serde(default = "default_max_resource_files")]
+    pub max_resource_files: usize,
+    /// Maximum total size in bytes of the files that the agent's resources may add to the context.
+    #[serde(default = "default_max_resource_bytes")]
+    pub max_resource_bytes: usize,
     /// Name of the current active profile.
     pub current_profile: String,
     /// List of file paths or glob patterns to include in the context.
     pub paths: Vec<ContextFilePath>,
     /// Map of Hook Name to [`Hook`]. The hook name serves as the hook's ID.
     pub hooks: HashMap<HookTrigger, Vec<Hook>>,
+    /// MCP resources added via /context add, as pairs of `@server:uri` and the content read.
+    #[serde(default)]
+    pub mcp_resources: Vec<(String, String)>,
+    /// Files matched by the paths watched with `/context add --watch`, kept up to date by the
+    /// context watcher so that watched paths don't have to be read again for every message.
+    #[serde(skip)]
+    pub watched_files: WatchedFiles,
     #[serde(skip)]
     pub hook_executor: HookExecutor,
 }
@@ -108,15 +153,25 @@ impl ContextManager {
         let paths = agent
             .resources
             .iter()
-            .filter(|resource| resource.starts_with("file://"))
-            .map(|s| ContextFilePath::Agent(s.trim_start_matches("file://").to_string()))
+            .filter_map(|resource| match resource.strip_prefix('!') {
+                Some(negated) => negated
+                    .strip_prefix("file://")
+                    .map(|path| ContextFilePath::Agent(format!("!{path}"))),
+                None => resource
+                    .strip_prefix("file://")
+                    .map(|path| ContextFilePath::Agent(path.to_string())),
+            })
             .collect::<Vec<_>>();
 
         Ok(Self {
             max_context_files_size,
+            max_resource_files: agent.max_resource_files.unwrap_or(DEFAULT_MAX_RESOURCE_FILES),
+            max_resource_bytes: agent.max_resource_bytes.unwrap_or(DEFAULT_MAX_RESOURCE_BYTES),
             current_profile: agent.name.clone(),
             paths,
             hooks: agent.hooks.clone(),
+            mcp_resources: Vec::new(),
+            watched_files: WatchedFiles::default(),
             hook_executor: HookExecutor::new(),
         })
     }
@@ -158,6 +213,31 @@ impl ContextManager {
         Ok(())
     }
 
+    /// Add the content of an MCP resource to the session context. MCP resources have their own
+    /// limits, [MAX_MCP_RESOURCES] and [MAX_MCP_RESOURCE_BYTES], separate from the agent's.
+    ///
+    /// # Arguments
+    /// * `reference` - The resource as it was referred to, i.e. `@server:uri`
+    /// * `content` - The text content read from the server
+    pub fn add_mcp_resource(&mut self, reference: String, content: String) -> Result<()> {
+        if self.mcp_resources.iter().any(|(r, _)| *r == reference) {
+            return Err(eyre!("Resource '{}' already exists.", reference));
+        }
+        if self.mcp_resources.len() >= MAX_MCP_RESOURCES {
+            return Err(eyre!("At most {} MCP resources can be added.", MAX_MCP_RESOURCES));
+        }
+        let bytes = self.mcp_resources.iter().map(|(_, c)| c.len()).sum::<usize>() + content.len();
+        if bytes > MAX_MCP_RESOURCE_BYTES {
+            return Err(eyre!(
+                "Adding '{}' would exceed the {} KB limit for MCP resources.",
+                reference,
+                MAX_MCP_RESOURCE_BYTES / 1024
+            ));
+        }
+        self.mcp_resources.push((reference, content));
+        Ok(())
+    }
+
     /// Remove paths from the context configuration.
     ///
     /// # Arguments
@@ -167,11 +247,17 @@ impl ContextManager {
     /// A Result indicating success or an error
     pub fn remove_paths(&mut self, paths: Vec<String>) -> Result<()> {
         // Remove each path if it exists
-        let old_path_num = self.paths.len();
+        let old_path_num = self.paths.len() + self.mcp_resources.len();
         self.paths
             .retain(|p| !paths.iter().any(|path| path.as_str() == p.get_path_as_str()));
-
-        if old_path_num == self.paths.len() {
+        self.mcp_resources
+            .retain(|(reference, _)| !paths.iter().any(|path| path == reference));
+        self.watched_files
+            .lock()
+            .expect("watched files lock poisoned")
+            .retain(|watched, _| !paths.contains(watched));
+
+        if old_path_num == self.paths.len() + self.mcp_resources.len() {
             return Err(eyre!("None of the specified paths were found in the context"));
         }
 
@@ -181,6 +267,8 @@ impl ContextManager {
     /// Clear all paths from the context configuration.
     pub fn clear(&mut self) {
         self.paths.clear();
+        self.mcp_resources.clear();
+        self.watched_files.lock().expect("watched files lock poisoned").clear();
     }
 
     /// Get all context files (global + profile-specific).
@@ -188,21 +276,29 @@ impl ContextManager {
     /// This method:
     /// 1. Processes all paths in the global and profile configurations
     /// 2. Expands glob patterns to include matching files
-    /// 3. Reads the content of each file
-    /// 4. Returns a vector of (filename, content) pairs
+    /// 3. Reads the content of each file, skipping agent resources past the resource limits
+    /// 4. Appends the content of any MCP resources that have been added
+    /// 5. Returns a vector of (filename, content) pairs
     ///
     ///
     /// # Returns
     /// A Result containing a vector of (filename, content) pairs or an error
     pub async fn get_context_files(&self, os: &Os) -> Result<Vec<(String, String)>> {
+        Ok(self.resolve_context_files(os).await?.0)
+    }
+
+    /// Same as [Self::get_context_files], but also returns the agent resources that were skipped
+    /// because of the resource limits.
+    pub async fn resolve_context_files(&self, os: &Os) -> Result<(Vec<(String, String)>, SkippedResources)> {
         let mut context_files = Vec::new();
 
-        self.collect_context_files(os, &self.paths, &mut context_files).await?;
+        let skipped = self.collect_context_files(os, &self.paths, &mut context_files).await?;
+        context_files.extend(self.mcp_resources.iter().cloned());
 
         context_files.sort_by(|a, b| a.0.cmp(&b.0));
         context_files.dedup_by(|a, b| a.0 == b.0);
 
-        Ok(context_files)
+        Ok((context_files, skipped))
     }
 
     pub async fn get_context_files_by_path(&self, os: &Os, path: &str) -> Result<Vec<(String, String)>> {
@@ -212,32 +308,98 @@ impl ContextManager {
     }
 
     /// Collects context files and optionally drops files if the total size exceeds the limit.
-    /// Returns (files_to_use, dropped_files)
+    /// Returns (files_to_use, dropped_files, skipped_resources)
     pub async fn collect_context_files_with_limit(
         &self,
         os: &Os,
-    ) -> Result<(Vec<(String, String)>, Vec<(String, String)>)> {
-        let mut files = self.get_context_files(os).await?;
+    ) -> Result<(Vec<(String, String)>, Vec<(String, String)>, SkippedResources)> {
+        let (mut files, skipped) = self.resolve_context_files(os).await?;
 
         let dropped_files = drop_matched_context_files(&mut files, self.max_context_files_size).unwrap_or_default();
 
         // remove dropped files from files
         files.retain(|file| !dropped_files.iter().any(|dropped| dropped.0 == file.0));
 
-        Ok((files, dropped_files))
+        Ok((files, dropped_files, skipped))
     }
 
+    /// Expands `paths` into `context_files`. Files brought in by the agent's resources stop being
+    /// added once [Self::max_resource_files] or [Self::max_resource_bytes] is reached, whereas
+    /// files added in session are always accepted.
+    ///
+    /// Agent resources are processed in order, and an exclusion (e.g. `!**/*.env`) removes the
+    /// files matched by the resources before it. Like in a gitignore file, a later resource can
+    /// include the files again.
     async fn collect_context_files(
         &self,
         os: &Os,
         paths: &[ContextFilePath],
         context_files: &mut Vec<(String, String)>,
-    ) -> Result<()> {
+    ) -> Result<SkippedResources> {
+        let mut skipped = SkippedResources::default();
+        let mut resources = Vec::<(String, String)>::new();
+
         for path in paths {
+            if let Some(pattern) = path.exclusion() {
+                let matcher = exclusion_matcher(os, pattern)?;
+                resources.retain(|(filename, _)| !matcher.is_match(filename));
+                continue;
+            }
+
+            if let ContextFilePath::Session(rule) = path {
+                let watched = self
+                    .watched_files
+                    .lock()
+                    .expect("watched files lock poisoned")
+                    .get(rule)
+                    .cloned();
+                if let Some(watched) = watched {
+                    context_files.extend(watched);
+                    continue;
+                }
+            }
+
+            let mut matched = Vec::new();
             // Use is_validation=false to handle non-matching globs gracefully
-            process_path(os, path.get_path_as_str(), context_files, false).await?;
+            process_path(os, path.get_path_as_str(), &mut matched, false).await?;
+
+            if let ContextFilePath::Session(_) = path {
+                context_files.extend(matched);
+                continue;
+            }
+
+            let match_count = matched.len();
+            if skipped.top_glob.as_ref().is_none_or(|(_, count)| match_count > *count) {
+                skipped.top_glob = Some((path.get_path_as_str().to_string(), match_count));
+            }
+
+            for (filename, content) in matched {
+                if !resources.iter().any(|(f, _)| *f == filename) {
+                    resources.push((filename, content));
+                }
+            }
         }
-        Ok(())
+
+        let mut resource_files = 0;
+        let mut resource_bytes = 0;
+        for (filename, content) in resources {
+            if context_files.iter().any(|(f, _)| *f == filename) {
+                continue;
+            }
+            if resource_files >= self.max_resource_files || resource_bytes + content.len() > self.max_resource_bytes {
+                skipped.count += 1;
+                continue;
+            }
+            resource_files += 1;
+            resource_bytes += content.len();
+            context_files.push((filename, content));
+        }
+
+        if skipped.count == 0 {
+            skipped.top_glob = None;
+        }
+
+        Ok(skipped)
     }
 
     /// Run all the currently enabled hooks from both the global and profile contexts.
@@ -248,13 +410,43 @@ impl ContextManager {
         trigger: HookTrigger,
         output: &mut impl Write,
         prompt: Option<&str>,
+        prompt_variables: &HashMap<String, String>,
+        os: &Os,
     ) -> Result<Vec<((HookTrigger, Hook), String)>, ChatError> {
         let mut hooks = self.hooks.clone();
         hooks.retain(|t, _| *t == trigger);
-        self.hook_executor.run_hooks(hooks, output, prompt).await
+        let agent_path = directories::chat_agent_path_env_var(os);
+        self.hook_executor
+            .run_hooks(
+                hooks,
+                output,
+                prompt,
+                prompt_variables,
+                agent_path.as_deref(),
+                plain_output(os),
+            )
+            .await
     }
 }
 
+/// Agent resources that were left out of the context because a resource limit was reached.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SkippedResources {
+    /// Number of files that were not added
+    pub count: usize,
+    /// The resource that matched the most files along with its number of matches, if any files
+    /// were skipped
+    pub top_glob: Option<(String, usize)>,
+}
+
+fn default_max_resource_files() -> usize {
+    DEFAULT_MAX_RESOURCE_FILES
+}
+
+fn default_max_resource_bytes() -> usize {
+    DEFAULT_MAX_RESOURCE_BYTES
+}
+
 /// Calculates the maximum context files size to use for the given model id.
 pub fn calc_max_context_files_size(model: Option<&ModelInfo>) -> usize {
     // Sets the max as 75% of the context window
@@ -277,32 +469,13 @@ pub fn calc_max_context_files_size(model: Option<&ModelInfo>) -> usize {
 ///
 /// # Returns
 /// A Result indicating success or an error
-async fn process_path(
+pub(super) async fn process_path(
     os: &Os,
     path: &str,
     context_files: &mut Vec<(String, String)>,
     is_validation: bool,
 ) -> Result<()> {
-    // Expand ~ to home directory
-    let expanded_path = if path.starts_with('~') {
-        if let Some(home_dir) = os.env.home() {
-            home_dir.join(&path[2..]).to_string_lossy().to_string()
-        } else {
-            return Err(eyre!("Could not determine home directory"));
-        }
-    } else {
-        path.to_string()
-    };
-
-    // Handle absolute, relative paths, and glob patterns
-    let full_path = if expanded_path.starts_with('/') {
-        expanded_path
-    } else {
-        os.env.current_dir()?.join(&expanded_path).to_string_lossy().to_string()
-    };
-
-    // Required in chroot testing scenarios so that we can use `Path::exists`.
-    let full_path = os.fs.chroot_path_str(full_path);
+    let full_path = expand_path(os, path)?;
 
     // Check if the path contains glob patterns
     if full_path.contains('*') || full_path.contains('?') || full_path.contains('[') {
@@ -357,6 +530,41 @@ async fn process_path(
     Ok(())
 }
 
+/// Expands `~` to the home directory and makes `path` absolute, relative to the current
+/// directory.
+pub(super) fn expand_path(os: &Os, path: &str) -> Result<String> {
+    // Expand ~ to home directory
+    let expanded_path = if path.starts_with('~') {
+        if let Some(home_dir) = os.env.home() {
+            home_dir.join(&path[2..]).to_string_lossy().to_string()
+        } else {
+            return Err(eyre!("Could not determine home directory"));
+        }
+    } else {
+        path.to_string()
+    };
+
+    // Handle absolute, relative paths, and glob patterns
+    let full_path = if expanded_path.starts_with('/') {
+        expanded_path
+    } else {
+        os.env.current_dir()?.join(&expanded_path).to_string_lossy().to_string()
+    };
+
+    // Required in chroot testing scenarios so that we can use `Path::exists`.
+    let full_path = os.fs.chroot_path_str(full_path);
+
+    Ok(full_path)
+}
+
+/// Builds a matcher for the files excluded by an agent resource such as `!**/*.env`. Excluding a
+/// directory also excludes everything under it.
+pub fn exclusion_matcher(os: &Os, pattern: &str) -> Result<GlobSet> {
+    let mut builder = GlobSetBuilder::new();
+    directories::add_gitignore_globs(&mut builder, &expand_path(os, pattern)?)?;
+    Ok(builder.build()?)
+}
+
 /// Add a file to the context collection.
 ///
 /// This method:
@@ -391,7 +599,7 @@ mod tests {
         os.fs.write("test/to-drop.md", "long content that exceed limit").await?;
         manager.add_paths(&os, vec!["test/*.md".to_string()], false).await?;
 
-        let (used, dropped) = manager.collect_context_files_with_limit(&os).await.unwrap();
+        let (used, dropped, _) = manager.collect_context_files_with_limit(&os).await.unwrap();
 
         assert!(used.len() + dropped.len() == 2);
         assert!(used.len() == 1);
@@ -399,6 +607,93 @@ mod tests {
         Ok(())
     }
 
+    #[tokio::test]
+    async fn test_resource_limits() -> Result<()> {
+        let os = Os::new().await.unwrap();
+        let mut manager = create_test_context_manager(None).expect("Failed to create test context manager");
+        manager.max_resource_files = 2;
+
+        os.fs.create_dir_all("rules").await?;
+        for i in 0..4 {
+            os.fs.write(format!("rules/r{i}.md"), "rule").await?;
+        }
+        os.fs.write("extra.md", "extra").await?;
+        manager.paths.push(ContextFilePath::Agent("rules/*.md".to_string()));
+        manager.add_paths(&os, vec!["extra.md".to_string()], false).await?;
+
+        let (files, skipped) = manager.resolve_context_files(&os).await?;
+        assert_eq!(files.len(), 3, "session files should be accepted past the limit");
+        assert!(files.iter().any(|(f, _)| f.ends_with("extra.md")));
+        assert_eq!(skipped.count, 2);
+        assert_eq!(skipped.top_glob, Some(("rules/*.md".to_string(), 4)));
+
+        manager.max_resource_files = usize::MAX;
+        manager.max_resource_bytes = "rule".len() * 3;
+        let (files, skipped) = manager.resolve_context_files(&os).await?;
+        assert_eq!(files.len(), 4);
+        assert_eq!(skipped.count, 1);
+
+        manager.max_resource_bytes = DEFAULT_MAX_RESOURCE_BYTES;
+        let (_, skipped) = manager.resolve_context_files(&os).await?;
+        assert_eq!(skipped, SkippedResources::default());
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_resource_exclusions() -> Result<()> {
+        let os = Os::new().await.unwrap();
+        let mut manager = create_test_context_manager(None).expect("Failed to create test context manager");
+
+        os.fs.create_dir_all("docs/private").await?;
+        os.fs.write("docs/a.md", "a").await?;
+        os.fs.write("docs/b.md", "b").await?;
+        os.fs.write("docs/secret.env", "KEY=value").await?;
+        os.fs.write("docs/private/c.md", "c").await?;
+
+        async fn resolve(os: &Os, manager: &mut ContextManager, paths: &[&str]) -> Result<Vec<String>> {
+            manager.paths = paths.iter().map(|p| ContextFilePath::Agent(p.to_string())).collect();
+            let files = manager.get_context_files(os).await?;
+            Ok(files
+                .into_iter()
+                .map(|(f, _)| f.rsplit_once("docs/").unwrap().1.to_string())
+                .collect())
+        }
+
+        assert_eq!(resolve(&os, &mut manager, &["docs/*", "!**/*.env"]).await?, vec![
+            "a.md", "b.md"
+        ]);
+        // Excluding a directory excludes everything under it
+        assert_eq!(
+            resolve(&os, &mut manager, &["docs/**/*.md", "!docs/private"]).await?,
+            vec!["a.md", "b.md"]
+        );
+        // Later resources take precedence over earlier ones
+        assert_eq!(
+            resolve(&os, &mut manager, &["docs/*", "!docs/*", "docs/a.md"]).await?,
+            vec!["a.md"]
+        );
+        assert_eq!(resolve(&os, &mut manager, &["!docs/a.md", "docs/*.md"]).await?, vec![
+            "a.md", "b.md"
+        ]);
+        assert_eq!(
+            resolve(&os, &mut manager, &["docs/*.md", "!docs/*.md", "!docs/b.md"]).await?,
+            Vec::<String>::new()
+        );
+
+        // Excluded files do not count toward the resource limits
+        manager.max_resource_files = 2;
+        manager.paths = ["docs/**/*.md", "!docs/a.md"]
+            .iter()
+            .map(|p| ContextFilePath::Agent(p.to_string()))
+            .collect();
+        let (files, skipped) = manager.resolve_context_files(&os).await?;
+        assert_eq!(files.len(), 2);
+        assert_eq!(skipped.count, 0);
+
+        Ok(())
+    }
+
     #[tokio::test]
     async fn test_path_ops() -> Result<()> {
         let os = Os::new().await.unwrap();
@@ -432,6 +727,53 @@ mod tests {
         Ok(())
     }
 
+    #[tokio::test]
+    async fn test_mcp_resource_ops() -> Result<()> {
+        let os = Os::new().await.unwrap();
+        let mut manager = create_test_context_manager(None).expect("Failed to create test context manager");
+
+        manager.add_mcp_resource("@docs:file:///readme.md".to_string(), "readme".to_string())?;
+        assert!(
+            manager
+                .add_mcp_resource("@docs:file:///readme.md".to_string(), "readme".to_string())
+                .is_err(),
+            "adding the same resource twice should fail"
+        );
+
+        let files = manager.get_context_files(&os).await?;
+        assert_eq!(files, vec![(
+            "@docs:file:///readme.md".to_string(),
+            "readme".to_string()
+        )]);
+
+        manager.remove_paths(vec!["@docs:file:///readme.md".to_string()])?;
+        assert!(manager.get_context_files(&os).await?.is_empty());
+
+        // MCP resources have their own limits, independent of the agent's resource limits
+        manager.max_resource_files = 0;
+        manager.max_resource_bytes = 0;
+        for i in 0..MAX_MCP_RESOURCES {
+            manager.add_mcp_resource(format!("@docs:file:///{i}.md"), "doc".to_string())?;
+        }
+        assert_eq!(manager.get_context_files(&os).await?.len(), MAX_MCP_RESOURCES);
+        assert!(
+            manager
+                .add_mcp_resource("@docs:file:///more.md".to_string(), "doc".to_string())
+                .is_err()
+        );
+        manager.clear();
+        assert!(
+            manager
+                .add_mcp_resource(
+                    "@docs:file:///big.md".to_string(),
+                    "a".repeat(MAX_MCP_RESOURCE_BYTES + 1)
+                )
+                .is_err()
+        );
+
+        Ok(())
+    }
+
     #[test]
     fn test_calc_max_context_files_size() {
         assert_eq!(
@@ -439,6 +781,8 @@ mod tests {
                 model_id: "CLAUDE_SONNET_4_20250514_V1_0".to_string(),
                 model_name: Some("Claude".to_string()),
                 context_window_tokens: 200_000,
+                max_output_tokens: None,
+                supports_images: false,
             })),
             150_000
         );
@@ -447,6 +791,8 @@ mod tests {
                 model_id: "OPENAI_GPT_OSS_120B_1_0".to_string(),
                 model_name: Some("GPT".to_string()),
                 context_window_tokens: 128_000,
+                max_output_tokens: None,
+                supports_images: false,
             })),
             96_000
         );
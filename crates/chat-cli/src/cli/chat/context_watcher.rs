@@ -0,0 +1,306 @@
+use std::path::{
+    Path,
+    PathBuf,
+};
+use std::sync::{
+    Arc,
+    Mutex,
+};
+use std::time::Duration;
+
+use eyre::{
+    Result,
+    bail,
+};
+use notify::{
+    Event,
+    RecursiveMode,
+    Watcher,
+};
+use tokio::task::JoinHandle;
+
+use super::context::{
+    WatchedFiles,
+    expand_path,
+    process_path,
+};
+use crate::os::Os;
+
+/// Maximum number of context rules that can be watched at once, so that a session cannot exhaust
+/// the file handles of the system.
+pub const MAX_WATCHED_PATHS: usize = 10;
+
+/// How long to wait for file system events to settle before reloading a rule. Editors often
+/// save a file in several steps, e.g. by writing a temporary file and renaming it.
+const DEBOUNCE: Duration = Duration::from_millis(200);
+
+/// Watches context rules added with `/context add --watch` for changes.
+///
+/// Each rule gets a file system watcher on the closest existing directory of its files. When
+/// an event arrives, the files matched by the rule are read again and stored in the
+/// [WatchedFiles] of the context manager, which uses them for the following messages.
+#[derive(Debug, Default)]
+pub struct ContextWatcher {
+    /// Watched context rules and the tasks owning their watchers, in the order they were added
+    watched: Vec<(String, JoinHandle<()>)>,
+    /// Rules whose files changed since [Self::take_refreshed] was last called
+    refreshed: Arc<Mutex<Vec<String>>>,
+}
+
+impl ContextWatcher {
+    /// Starts watching the files matched by the context rule `path`, keeping their contents in
+    /// `watched_files` until the rule is removed from it.
+    pub async fn watch(&mut self, os: &Os, path: String, watched_files: &WatchedFiles) -> Result<()> {
+        if self.is_watched(&path) {
+            bail!("'{}' is already being watched", path);
+        }
+        if self.watched.len() >= MAX_WATCHED_PATHS {
+            bail!("At most {} context paths can be watched at once", MAX_WATCHED_PATHS);
+        }
+
+        let (tx, mut rx) = tokio::sync::mpsc::unbounded_channel();
+        let mut watcher = notify::recommended_watcher(move |event: notify::Result<Event>| {
+            if event.is_ok_and(|event| !event.kind.is_access()) {
+                let _ = tx.send(());
+            }
+        })?;
+        let (root, mode) = watch_target(&expand_path(os, &path)?);
+        watcher.watch(&root, mode)?;
+
+        let mut last = read_rule(os, &path).await;
+        watched_files
+            .lock()
+            .expect("watched files lock poisoned")
+            .insert(path.clone(), last.clone());
+
+        let os = os.clone();
+        let watched_files = Arc::clone(watched_files);
+        let refreshed = Arc::clone(&self.refreshed);
+        let rule = path.clone();
+        let handle = tokio::spawn(async move {
+            // The watcher stops once dropped, which happens when the task is aborted
+            let _watcher = watcher;
+            while rx.recv().await.is_some() {
+                tokio::time::sleep(DEBOUNCE).await;
+                while rx.try_recv().is_ok() {}
+
+                let files = read_rule(&os, &rule).await;
+                if files == last {
+                    continue;
+                }
+                last = files.clone();
+
+                // A rule removed from the context while it was being read must not come back
+                match watched_files
+                    .lock()
+                    .expect("watched files lock poisoned")
+                    .get_mut(&rule)
+                {
+                    Some(watched) => *watched = files,
+                    None => continue,
+                }
+                let mut refreshed = refreshed.lock().expect("refreshed lock poisoned");
+                if !refreshed.contains(&rule) {
+                    refreshed.push(rule.clone());
+                }
+            }
+        });
+        self.watched.push((path, handle));
+
+        Ok(())
+    }
+
+    /// Stops watching `path`, returning whether it was being watched.
+    pub fn unwatch(&mut self, path: &str) -> bool {
+        let Some(index) = self.watched.iter().position(|(p, _)| p == path) else {
+            return false;
+        };
+        let (_, handle) = self.watched.remove(index);
+        handle.abort();
+        self.refreshed
+            .lock()
+            .expect("refreshed lock poisoned")
+            .retain(|p| p != path);
+        true
+    }
+
+    /// Stops watching every context rule.
+    pub fn clear(&mut self) {
+        for (_, handle) in self.watched.drain(..) {
+            handle.abort();
+        }
+        self.refreshed.lock().expect("refreshed lock poisoned").clear();
+    }
+
+    pub fn is_watched(&self, path: &str) -> bool {
+        self.watched.iter().any(|(p, _)| p == path)
+    }
+
+    /// Returns the context rules whose files were reloaded since the last call.
+    pub fn take_refreshed(&self) -> Vec<String> {
+        std::mem::take(&mut *self.refreshed.lock().expect("refreshed lock poisoned"))
+    }
+}
+
+impl Drop for ContextWatcher {
+    fn drop(&mut self) {
+        self.clear();
+    }
+}
+
+/// Reads the files matched by `path`, sorted by name. A rule that no longer matches any file
+/// reads as empty.
+async fn read_rule(os: &Os, path: &str) -> Vec<(String, String)> {
+    let mut files = Vec::new();
+    if process_path(os, path, &mut files, false).await.is_err() {
+        files.clear();
+    }
+    files.sort();
+    files
+}
+
+/// The directory to watch for the expanded context rule `full_path`, and whether it has to be
+/// watched recursively.
+///
+/// Globs are watched from the directory before their first wildcard. A single file is watched
+/// through its directory so that it is noticed when replaced or recreated. If the directory
+/// doesn't exist yet, its closest existing ancestor is watched recursively instead.
+fn watch_target(full_path: &str) -> (PathBuf, RecursiveMode) {
+    let mut base = PathBuf::new();
+    let mut pattern = Vec::new();
+    for component in Path::new(full_path).components() {
+        let name = component.as_os_str().to_string_lossy();
+        if pattern.is_empty() && !name.contains(['*', '?', '[']) {
+            base.push(component);
+        } else {
+            pattern.push(name.into_owned());
+        }
+    }
+
+    let mut mode = if pattern.len() > 1 || pattern.iter().any(|name| name.contains("**")) {
+        RecursiveMode::Recursive
+    } else {
+        RecursiveMode::NonRecursive
+    };
+    if pattern.is_empty() && !base.is_dir() {
+        base.pop();
+    }
+    while !base.exists() && base.pop() {
+        mode = RecursiveMode::Recursive;
+    }
+
+    (base, mode)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_read_rule() {
+        let os = Os::new().await.unwrap();
+        os.fs.create_dir_all("watched").await.unwrap();
+        os.fs.write("watched/b.md", "b").await.unwrap();
+        os.fs.write("watched/a.md", "a").await.unwrap();
+
+        let files = read_rule(&os, "watched/*.md").await;
+        assert_eq!(files.len(), 2);
+        assert!(files[0].0.ends_with("a.md"));
+        assert_eq!(files[0].1, "a");
+
+        os.fs.write("watched/a.md", "changed").await.unwrap();
+        assert_ne!(read_rule(&os, "watched/*.md").await, files);
+
+        assert!(read_rule(&os, "missing.md").await.is_empty());
+        assert!(read_rule(&os, "missing/*.md").await.is_empty());
+    }
+
+    #[test]
+    fn test_watch_target() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path();
+        std::fs::create_dir_all(root.join("docs/nested")).unwrap();
+        std::fs::write(root.join("docs/a.md"), "a").unwrap();
+        let target = |path: &str| watch_target(&root.join(path).to_string_lossy());
+
+        assert_eq!(target("docs/a.md"), (root.join("docs"), RecursiveMode::NonRecursive));
+        assert_eq!(target("docs/new.md"), (root.join("docs"), RecursiveMode::NonRecursive));
+        assert_eq!(target("docs"), (root.join("docs"), RecursiveMode::NonRecursive));
+        assert_eq!(target("docs/*.md"), (root.join("docs"), RecursiveMode::NonRecursive));
+        assert_eq!(target("docs/**/*.md"), (root.join("docs"), RecursiveMode::Recursive));
+        assert_eq!(target("docs/*/a.md"), (root.join("docs"), RecursiveMode::Recursive));
+        assert_eq!(
+            target("missing/dir/a.md"),
+            (root.to_path_buf(), RecursiveMode::Recursive)
+        );
+    }
+
+    #[tokio::test]
+    async fn test_watch_reloads_changed_files() {
+        let os = Os::new().await.unwrap();
+        os.fs.create_dir_all("watched").await.unwrap();
+        os.fs.write("watched/a.md", "a").await.unwrap();
+
+        let watched_files = WatchedFiles::default();
+        let mut watcher = ContextWatcher::default();
+        watcher
+            .watch(&os, "watched/*.md".to_string(), &watched_files)
+            .await
+            .unwrap();
+        assert_eq!(watched_files.lock().unwrap()["watched/*.md"][0].1, "a");
+        os.fs.write("watched/a.md", "changed").await.unwrap();
+
+        let mut refreshed = Vec::new();
+        for _ in 0..50 {
+            refreshed = watcher.take_refreshed();
+            if !refreshed.is_empty() {
+                break;
+            }
+            tokio::time::sleep(Duration::from_millis(100)).await;
+        }
+        assert_eq!(refreshed, vec!["watched/*.md".to_string()]);
+        assert_eq!(watched_files.lock().unwrap()["watched/*.md"][0].1, "changed");
+
+        // Once removed from the context, the rule is no longer reloaded
+        watched_files.lock().unwrap().clear();
+        os.fs.write("watched/a.md", "changed again").await.unwrap();
+        tokio::time::sleep(Duration::from_secs(1)).await;
+        assert!(watched_files.lock().unwrap().is_empty());
+        assert!(watcher.take_refreshed().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_watch_limit() {
+        let os = Os::new().await.unwrap();
+        let watched_files = WatchedFiles::default();
+        let mut watcher = ContextWatcher::default();
+
+        for i in 0..MAX_WATCHED_PATHS {
+            watcher.watch(&os, format!("rule{i}.md"), &watched_files).await.unwrap();
+        }
+        assert!(
+            watcher
+                .watch(&os, "one-too-many.md".to_string(), &watched_files)
+                .await
+                .is_err()
+        );
+        assert!(
+            watcher
+                .watch(&os, "rule0.md".to_string(), &watched_files)
+                .await
+                .is_err()
+        );
+
+        assert!(watcher.unwatch("rule0.md"));
+        assert!(!watcher.unwatch("rule0.md"));
+        assert!(!watcher.is_watched("rule0.md"));
+        watcher
+            .watch(&os, "one-too-many.md".to_string(), &watched_files)
+            .await
+            .unwrap();
+
+        watcher.clear();
+        assert!(!watcher.is_watched("rule1.md"));
+        assert!(watcher.take_refreshed().is_empty());
+    }
+}
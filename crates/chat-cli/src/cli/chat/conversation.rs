@@ -30,6 +30,7 @@ use super::consts::{
 };
 use super::context::{
     ContextManager,
+    SkippedResources,
     calc_max_context_files_size,
 };
 use super::line_tracker::FileLineTracker;
@@ -51,7 +52,11 @@ use super::tools::{
     ToolOrigin,
     ToolSpec,
 };
-use super::util::serde_value_to_document;
+use super::util::tokens::truncate_to_tokens;
+use super::util::{
+    serde_value_to_document,
+    truncate_safe,
+};
 use crate::api_client::model::{
     ChatMessage,
     ConversationState as FigConversationState,
@@ -69,6 +74,9 @@ use crate::cli::agent::hook::{
 use crate::cli::chat::ChatError;
 use crate::cli::chat::cli::model::{
     ModelInfo,
+    default_model,
+    find_model,
+    get_available_models,
     get_model_info,
 };
 use crate::cli::chat::tools::custom_tool::CustomToolConfig;
@@ -78,6 +86,21 @@ use crate::os::Os;
 pub const CONTEXT_ENTRY_START_HEADER: &str = "--- CONTEXT ENTRY BEGIN ---\n";
 pub const CONTEXT_ENTRY_END_HEADER: &str = "--- CONTEXT ENTRY END ---\n\n";
 
+/// Token budget for the summary of a tangent that is merged back into the main conversation.
+pub const TANGENT_SUMMARY_MAX_TOKENS: usize = 500;
+
+/// Maximum length of each message of a tangent sent for summarization, in bytes.
+const TANGENT_SUMMARY_MAX_MESSAGE_LENGTH: usize = 25_000;
+
+/// Number of user prompts sent to the model to generate a title of the conversation.
+const TITLE_REQUEST_MAX_PROMPTS: usize = 3;
+
+/// Maximum length of each user prompt sent to generate a title, in bytes.
+const TITLE_REQUEST_MAX_PROMPT_LENGTH: usize = 2_000;
+
+/// Maximum number of words in a conversation title.
+pub const TITLE_MAX_WORDS: usize = 5;
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct HistoryEntry {
     user: UserMessage,
@@ -127,6 +150,11 @@ pub struct ConversationState {
     /// Model explicitly selected by the user in this conversation state via `/model`.
     #[serde(default, skip_serializing_if = "Option::is_none")]
     pub model_info: Option<ModelInfo>,
+    /// Model used while the active agent doesn't set one: the model the session started with, or
+    /// the last one chosen with `/model`. Not known yet when the session started with the model
+    /// of its agent, in which case the default model is looked up once needed.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub session_model_info: Option<ModelInfo>,
     /// Used to track agent vs user updates to file modifications.
     ///
     /// Maps from a file path to [FileLineTracker]
@@ -137,6 +165,17 @@ pub struct ConversationState {
     /// Tangent mode checkpoint - stores main conversation when in tangent mode
     #[serde(default, skip_serializing_if = "Option::is_none")]
     tangent_state: Option<ConversationCheckpoint>,
+    /// Short title of the conversation, generated the first time it is saved
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub title: Option<String>,
+    /// Values of the `{{variable}}` placeholders in the agent prompt, collected when the session
+    /// started. Also passed to hooks as `PROMPT_VAR_<name>` environment variables.
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub prompt_variables: HashMap<String, String>,
+    /// Conversations of the agents that delegated a task with the agent_delegate tool, innermost
+    /// last. See [Self::enter_delegation].
+    #[serde(skip)]
+    delegation_states: Vec<ConversationCheckpoint>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -152,6 +191,9 @@ struct ConversationCheckpoint {
     /// Timestamp when tangent mode was entered (milliseconds since epoch)
     #[serde(default = "time::OffsetDateTime::now_utc")]
     tangent_start_time: time::OffsetDateTime,
+    /// Whether a summary of the tangent should be added to the main conversation on exit
+    #[serde(default)]
+    merge_on_exit: bool,
 }
 
 impl ConversationState {
@@ -181,6 +223,10 @@ impl ConversationState {
         } else {
             None
         };
+        let session_model = match (&model, agents.get_active().and_then(|agent| agent.model.as_deref())) {
+            (Some(info), Some(requested)) if find_model(std::slice::from_ref(info), requested).is_some() => None,
+            _ => model.clone(),
+        };
 
         Self {
             conversation_id: conversation_id.to_string(),
@@ -196,9 +242,13 @@ impl ConversationState {
             agents,
             model: None,
             model_info: model,
+            session_model_info: session_model,
             file_line_tracker: HashMap::new(),
             mcp_enabled,
             tangent_state: None,
+            title: None,
+            prompt_variables: HashMap::new(),
+            delegation_states: Vec::new(),
         }
     }
 
@@ -232,6 +282,7 @@ impl ConversationState {
             main_transcript: self.transcript.clone(),
             main_latest_summary: self.latest_summary.clone(),
             tangent_start_time: time::OffsetDateTime::now_utc(),
+            merge_on_exit: false,
         }
     }
 
@@ -266,6 +317,109 @@ impl ConversationState {
         }
     }
 
+    /// Starts an empty conversation for a task delegated to another agent. The current
+    /// conversation is kept aside until the matching [Self::exit_delegation].
+    pub fn enter_delegation(&mut self) {
+        self.delegation_states.push(self.create_checkpoint());
+        self.history.clear();
+        self.next_message = None;
+        self.transcript.clear();
+        self.latest_summary = None;
+        self.valid_history_range = (0, 0);
+    }
+
+    /// Discards the conversation of a delegated task and restores the one it was delegated from.
+    pub fn exit_delegation(&mut self) {
+        if let Some(checkpoint) = self.delegation_states.pop() {
+            self.restore_from_checkpoint(checkpoint);
+        }
+    }
+
+    /// Whether a summary of the current tangent was requested with `/tangent --merge-on-exit`
+    pub fn tangent_merge_on_exit(&self) -> bool {
+        self.tangent_state
+            .as_ref()
+            .is_some_and(|checkpoint| checkpoint.merge_on_exit)
+    }
+
+    pub fn set_tangent_merge_on_exit(&mut self, merge_on_exit: bool) {
+        if let Some(checkpoint) = &mut self.tangent_state {
+            checkpoint.merge_on_exit = merge_on_exit;
+        }
+    }
+
+    /// Returns a request for a summary of the messages exchanged since entering tangent mode, or
+    /// [None] if not in tangent mode or nothing was exchanged.
+    ///
+    /// This does not modify the conversation, so the request can be abandoned at any point. The
+    /// summary is added with [ConversationState::exit_tangent_mode_with_summary].
+    pub fn create_tangent_summary_request(&self) -> Option<FigConversationState> {
+        let main_len = self.tangent_state.as_ref()?.main_history.len();
+        let mut history = self.history.iter().skip(main_len).cloned().collect::<VecDeque<_>>();
+        if history.is_empty() {
+            return None;
+        }
+        for HistoryEntry { user, .. } in &mut history {
+            user.truncate_safe(TANGENT_SUMMARY_MAX_MESSAGE_LENGTH);
+        }
+
+        let summary_content = format!(
+            "[SYSTEM NOTE: This is an automated summarization request, not from the user]\n\n\
+            The messages above are a side conversation (a tangent) that is about to be discarded. \
+            Summarize what was learned in it so that it can be carried back into the main conversation.\n\n\
+            FORMAT REQUIREMENTS: Use at most {TANGENT_SUMMARY_MAX_TOKENS} tokens in a few bullet points. \
+            Include conclusions, decisions, and technical facts such as file names, commands, and code. \
+            DO NOT respond conversationally. DO NOT address the user directly."
+        );
+        let mut summary_message = Some(UserMessage::new_prompt(summary_content.clone(), None));
+
+        // Only send the dummy tool spec in order to prevent the model from ever attempting a tool
+        // use.
+        let mut tools = self.tools.clone();
+        tools.retain(|k, v| match k {
+            ToolOrigin::Native => {
+                v.retain(|tool| match tool {
+                    Tool::ToolSpecification(tool_spec) => tool_spec.name == DUMMY_TOOL_NAME,
+                });
+                true
+            },
+            ToolOrigin::McpServer(_) => false,
+        });
+
+        enforce_conversation_invariants(&mut history, &mut summary_message, &tools);
+
+        Some(FigConversationState {
+            conversation_id: Some(self.conversation_id.clone()),
+            user_input_message: summary_message
+                .unwrap_or(UserMessage::new_prompt(summary_content, None)) // should not happen
+                .into_user_input_message(self.model_info.as_ref().map(|m| m.model_id.clone()), &tools),
+            history: Some(flatten_history(history.iter())),
+        })
+    }
+
+    /// Exits tangent mode and adds `summary` to the main conversation as a context note.
+    pub fn exit_tangent_mode_with_summary(&mut self, summary: &str) {
+        self.exit_tangent_mode();
+
+        let mut summary = summary.to_string();
+        truncate_to_tokens(&mut summary, TANGENT_SUMMARY_MAX_TOKENS);
+        let note = format!(
+            "{CONTEXT_ENTRY_START_HEADER}This is a summary of a side conversation (a tangent) that took place \
+            after the messages above. It is added for context and is not a request from the user.\n\n\
+            TANGENT SUMMARY:\n{summary}\n{CONTEXT_ENTRY_END_HEADER}"
+        );
+        self.append_transcript(format!("Tangent summary:\n{summary}"));
+        self.history.push_back(HistoryEntry {
+            user: UserMessage::new_prompt(note, None),
+            assistant: AssistantMessage::new_response(
+                None,
+                "Noted, I will take the summary of the tangent into account.".to_string(),
+            ),
+            request_metadata: None,
+        });
+        self.valid_history_range = (0, self.history.len());
+    }
+
     /// Appends a collection prompts into history and returns the last message in the collection.
     /// It asserts that the collection ends with a prompt that assumes the role of user.
     pub fn append_prompts(&mut self, mut prompts: VecDeque<Prompt>) -> Option<String> {
@@ -454,6 +608,21 @@ impl ConversationState {
             )
             .ok();
         }
+        if let Some((top_glob, matches)) = &context.skipped_resources.top_glob {
+            execute!(
+                stderr,
+                style::SetForegroundColor(Color::DarkYellow),
+                style::Print(format!(
+                    "\n{} agent resource file{} skipped due to the resource limits. '{}' matched the most files ({}).\n",
+                    context.skipped_resources.count,
+                    if context.skipped_resources.count == 1 { " was" } else { "s were" },
+                    top_glob,
+                    matches
+                )),
+                style::SetForegroundColor(style::Color::Reset)
+            )
+            .ok();
+        }
 
         Ok(context
             .into_fig_conversation_state()
@@ -504,12 +673,20 @@ impl ConversationState {
         let mut agent_spawn_context = None;
         if let Some(cm) = self.context_manager.as_mut() {
             let user_prompt = self.next_message.as_ref().and_then(|m| m.prompt());
-            let agent_spawn = cm.run_hooks(HookTrigger::AgentSpawn, output, user_prompt).await?;
+            let agent_spawn = cm
+                .run_hooks(HookTrigger::AgentSpawn, output, user_prompt, &self.prompt_variables, os)
+                .await?;
             agent_spawn_context = format_hook_context(&agent_spawn, HookTrigger::AgentSpawn);
 
             if let (true, Some(next_message)) = (run_perprompt_hooks, self.next_message.as_mut()) {
                 let per_prompt = cm
-                    .run_hooks(HookTrigger::UserPromptSubmit, output, next_message.prompt())
+                    .run_hooks(
+                        HookTrigger::UserPromptSubmit,
+                        output,
+                        next_message.prompt(),
+                        &self.prompt_variables,
+                        os,
+                    )
                     .await?;
                 if let Some(ctx) = format_hook_context(&per_prompt, HookTrigger::UserPromptSubmit) {
                     next_message.additional_context = ctx;
@@ -517,7 +694,8 @@ impl ConversationState {
             }
         }
 
-        let (context_messages, dropped_context_files) = self.context_messages(os, agent_spawn_context).await;
+        let (context_messages, dropped_context_files, skipped_resources) =
+            self.context_messages(os, agent_spawn_context).await;
 
         Ok(BackendConversationState {
             conversation_id: self.conversation_id.as_str(),
@@ -527,6 +705,7 @@ impl ConversationState {
                 .range(self.valid_history_range.0..self.valid_history_range.1),
             context_messages,
             dropped_context_files,
+            skipped_resources,
             tools: &self.tools,
             model_id: self.model_info.as_ref().map(|m| m.model_id.as_str()),
         })
@@ -650,6 +829,15 @@ impl ConversationState {
         self.latest_summary = Some((summary, request_metadata));
     }
 
+    /// Drops the oldest half of the history to make room in the context window, without asking
+    /// the model for a summary of it. The latest summary is kept. Returns the number of
+    /// user/assistant message pairs that were dropped.
+    pub fn drop_oldest_turns(&mut self) -> usize {
+        let count = self.history.len().div_ceil(2);
+        self.history.drain(..count);
+        count
+    }
+
     pub async fn create_agent_generation_request(
         &mut self,
         agent_name: &str,
@@ -695,6 +883,65 @@ Return only the JSON configuration, no additional text.",
         })
     }
 
+    /// Creates a standalone request asking the model for a title of the conversation, based on its
+    /// first user prompts. Returns [None] if the user has not sent a prompt yet.
+    pub fn create_title_request(&self) -> Option<FigConversationState> {
+        let prompts = self
+            .history
+            .iter()
+            .filter_map(|entry| entry.user.prompt())
+            .take(TITLE_REQUEST_MAX_PROMPTS)
+            .map(|prompt| format!("- {}", truncate_safe(prompt.trim(), TITLE_REQUEST_MAX_PROMPT_LENGTH)))
+            .collect::<Vec<_>>();
+        if prompts.is_empty() {
+            return None;
+        }
+
+        let title_content = format!(
+            "[SYSTEM NOTE: This is an automated title generation request, not from the user]\n\n\
+            Write a title of at most {TITLE_MAX_WORDS} words for a conversation that started with the \
+            following messages. Return only the title, with no quotes, punctuation at the end, or \
+            additional text.\n\n{}",
+            prompts.join("\n")
+        );
+
+        // Only send the dummy tool spec to prevent the model from attempting tool use
+        let mut tools = self.tools.clone();
+        tools.retain(|k, v| match k {
+            ToolOrigin::Native => {
+                v.retain(|tool| match tool {
+                    Tool::ToolSpecification(tool_spec) => tool_spec.name == DUMMY_TOOL_NAME,
+                });
+                true
+            },
+            ToolOrigin::McpServer(_) => false,
+        });
+
+        Some(FigConversationState {
+            conversation_id: Some(self.conversation_id.clone()),
+            user_input_message: UserMessage::new_prompt(title_content, None)
+                .into_user_input_message(self.model_info.as_ref().map(|m| m.model_id.clone()), &tools),
+            history: Some(Vec::new()),
+        })
+    }
+
+    /// A title made of the first words of the first user prompt, used when the model could not
+    /// be asked for one.
+    pub fn fallback_title(&self) -> String {
+        let words = self
+            .history
+            .iter()
+            .find_map(|entry| entry.user.prompt())
+            .unwrap_or_default()
+            .split_whitespace()
+            .take(TITLE_MAX_WORDS)
+            .collect::<Vec<_>>();
+        match words.is_empty() {
+            true => "Untitled conversation".to_string(),
+            false => words.join(" "),
+        }
+    }
+
     pub fn current_profile(&self) -> Option<&str> {
         if let Some(cm) = self.context_manager.as_ref() {
             Some(cm.current_profile.as_str())
@@ -704,7 +951,8 @@ Return only the JSON configuration, no additional text.",
     }
 
     /// Returns pairs of user and assistant messages to include as context in the message history
-    /// including both summaries and context files if available, and the dropped context files.
+    /// including both summaries and context files if available, the dropped context files, and the
+    /// agent resources skipped due to the resource limits.
     ///
     /// TODO:
     /// - Either add support for multiple context messages if the context is too large to fit inside
@@ -715,9 +963,10 @@ Return only the JSON configuration, no additional text.",
         &mut self,
         os: &Os,
         additional_context: Option<String>,
-    ) -> (Option<Vec<HistoryEntry>>, Vec<(String, String)>) {
+    ) -> (Option<Vec<HistoryEntry>>, Vec<(String, String)>, SkippedResources) {
         let mut context_content = String::new();
         let mut dropped_context_files = Vec::new();
+        let mut skipped_resources = SkippedResources::default();
         if let Some((summary, _)) = &self.latest_summary {
             context_content.push_str(CONTEXT_ENTRY_START_HEADER);
             context_content.push_str("This summary contains ALL relevant information from our previous conversation including tool uses, results, code analysis, and file operations. YOU MUST reference this information when answering questions and explicitly acknowledge specific details from the summary when they're relevant to the current question.\n\n");
@@ -730,10 +979,11 @@ Return only the JSON configuration, no additional text.",
         // Add context files if available
         if let Some(context_manager) = self.context_manager.as_mut() {
             match context_manager.collect_context_files_with_limit(os).await {
-                Ok((files_to_use, files_dropped)) => {
+                Ok((files_to_use, files_dropped, skipped)) => {
                     if !files_dropped.is_empty() {
                         dropped_context_files.extend(files_dropped);
                     }
+                    skipped_resources = skipped;
 
                     if !files_to_use.is_empty() {
                         context_content.push_str(CONTEXT_ENTRY_START_HEADER);
@@ -753,7 +1003,11 @@ Return only the JSON configuration, no additional text.",
             context_content.push_str(&context);
         }
 
-        if let Some(agent_prompt) = self.agents.get_active().and_then(|a| a.prompt.as_ref()) {
+        if let Some(agent_prompt) = self
+            .agents
+            .get_active()
+            .and_then(|a| a.expanded_prompt(&self.prompt_variables))
+        {
             context_content.push_str(&format!("Follow this instruction: {}", agent_prompt));
         }
 
@@ -768,9 +1022,10 @@ Return only the JSON configuration, no additional text.",
                     request_metadata: None,
                 }]),
                 dropped_context_files,
+                skipped_resources,
             )
         } else {
-            (None, dropped_context_files)
+            (None, dropped_context_files, skipped_resources)
         }
     }
 
@@ -799,6 +1054,24 @@ Return only the JSON configuration, no additional text.",
         })
     }
 
+    /// Estimated context window usage in percent, counted the same way as `/usage`: the
+    /// conversation history, context messages, and tool specifications.
+    pub async fn context_usage_percent(&mut self, os: &Os) -> Result<usize, ChatError> {
+        let state = self.backend_conversation_state(os, false, &mut vec![]).await?;
+        let used_chars = state.char_count() + state.tools_char_count();
+        let max_chars = TokenCounter::token_to_chars(context_window_tokens(self.model_info.as_ref()));
+
+        Ok(*used_chars * 100 / max_chars.max(1))
+    }
+
+    /// Whether the estimated conversation size has reached `percent` of the model's context window
+    pub async fn exceeds_context_threshold(&mut self, os: &Os, percent: usize) -> Result<bool, ChatError> {
+        let total_chars = self.calculate_char_count(os).await?;
+        let max_chars = TokenCounter::token_to_chars(context_window_tokens(self.model_info.as_ref()));
+
+        Ok(*total_chars >= max_chars.saturating_mul(percent) / 100)
+    }
+
     pub fn append_user_transcript(&mut self, message: &str) {
         self.append_transcript(format!("> {}", message.replace("\n", "> \n")));
     }
@@ -827,6 +1100,36 @@ Return only the JSON configuration, no additional text.",
         agent_name: &str,
     ) -> Result<(), ChatError> {
         let agent = self.agents.switch(agent_name).map_err(ChatError::AgentSwapError)?;
+        let agent_model = match &agent.model {
+            Some(requested) => {
+                let (models, _) = get_available_models(os).await?;
+                let model = find_model(&models, requested).cloned();
+                if model.is_none() {
+                    execute!(
+                        output,
+                        style::SetForegroundColor(Color::Yellow),
+                        style::Print(format!(
+                            "Model '{requested}' of agent {agent_name} is not available, using the default model instead\n"
+                        )),
+                        style::SetForegroundColor(Color::Reset),
+                    )?;
+                }
+                model
+            },
+            None => None,
+        };
+        // An agent without a model of its own goes back to the model of the session
+        let model = match (agent_model, &self.session_model_info) {
+            (Some(model), _) => model,
+            (None, Some(session_model)) => session_model.clone(),
+            (None, None) => {
+                let (models, service_default) = get_available_models(os).await?;
+                let model = default_model(os, &models, &service_default);
+                self.session_model_info = Some(model.clone());
+                model
+            },
+        };
+        self.model_info = Some(model);
         self.context_manager.replace({
             ContextManager::from_agent(agent, calc_max_context_files_size(self.model_info.as_ref()))
                 .map_err(|e| ChatError::Custom(format!("Context manager has failed to instantiate: {e}").into()))?
@@ -876,6 +1179,7 @@ pub struct BackendConversationStateImpl<'a, T, U> {
     pub history: T,
     pub context_messages: U,
     pub dropped_context_files: Vec<(String, String)>,
+    pub skipped_resources: SkippedResources,
     pub tools: &'a HashMap<ToolOrigin, Vec<Tool>>,
     pub model_id: Option<&'a str>,
 }
@@ -926,6 +1230,16 @@ impl BackendConversationStateImpl<'_, std::collections::vec_deque::Iter<'_, Hist
             assistant_messages: assistant_chars.into(),
         }
     }
+
+    /// The number of characters used by the tool specifications sent with each request.
+    pub fn tools_char_count(&self) -> CharCount {
+        self.tools
+            .values()
+            .filter_map(|s| serde_json::to_string(s).ok())
+            .map(|s| s.len())
+            .sum::<usize>()
+            .into()
+    }
 }
 
 /// Reflects a detailed accounting of the context window utilization for a given conversation.
@@ -972,7 +1286,11 @@ impl From<InputSchema> for ToolInputSchema {
 /// [Option::Some] if `hook_results` is not empty and at least one hook has content. Otherwise,
 /// [Option::None]
 fn format_hook_context(hook_results: &[((HookTrigger, Hook), String)], trigger: HookTrigger) -> Option<String> {
-    if hook_results.iter().all(|(_, content)| content.is_empty()) {
+    let hook_results = hook_results
+        .iter()
+        .filter(|((h_trigger, hook), content)| *h_trigger == trigger && hook.inject_output && !content.is_empty())
+        .collect::<Vec<_>>();
+    if hook_results.is_empty() {
         return None;
     }
 
@@ -985,8 +1303,8 @@ fn format_hook_context(hook_results: &[((HookTrigger, Hook), String)], trigger:
     }
     context_content.push_str("\n\n");
 
-    for (_, output) in hook_results.iter().filter(|((h_trigger, _), _)| *h_trigger == trigger) {
-        context_content.push_str(&format!("{output}\n\n"));
+    for ((_, hook), output) in hook_results {
+        context_content.push_str(&format!("--- Output of hook `{}` ---\n{output}\n\n", hook.command));
     }
     context_content.push_str(CONTEXT_ENTRY_END_HEADER);
     Some(context_content)
@@ -1278,6 +1596,143 @@ mod tests {
         }
     }
 
+    #[tokio::test]
+    async fn test_swap_agent_model() {
+        let mut os = Os::new().await.unwrap();
+        let agent = |name: &str, model: Option<&str>| Agent {
+            name: name.to_string(),
+            model: model.map(str::to_string),
+            ..Default::default()
+        };
+        let mut agents = Agents::default();
+        agents.insert("plain".to_string(), agent("plain", None));
+        agents.insert("missing".to_string(), agent("missing", Some("no-such-model")));
+        agents.activate_ephemeral(agent("pinned", Some("model-1")));
+        let mut output = vec![];
+
+        let mut tool_manager = ToolManager::default();
+        let mut conversation = ConversationState::new(
+            "fake_conv_id",
+            agents,
+            tool_manager.load_tools(&mut os, &mut output).await.unwrap(),
+            tool_manager,
+            Some("model-1".to_string()),
+            &os,
+            false,
+        )
+        .await;
+        let model_id = |conversation: &ConversationState| conversation.model_info.as_ref().unwrap().model_id.clone();
+
+        // The session started with the model of its agent, so an agent without a model gets the
+        // default model
+        assert!(conversation.session_model_info.is_none());
+        conversation.swap_agent(&mut os, &mut output, "plain").await.unwrap();
+        assert_eq!(model_id(&conversation), "model-1");
+        assert_eq!(conversation.session_model_info.as_ref().unwrap().model_id, "model-1");
+
+        // As if picked with /model
+        conversation.model_info = Some(ModelInfo::from_id("picked".to_string()));
+        conversation.session_model_info = conversation.model_info.clone();
+        conversation.swap_agent(&mut os, &mut output, "pinned").await.unwrap();
+        assert_eq!(model_id(&conversation), "model-1");
+        conversation.swap_agent(&mut os, &mut output, "plain").await.unwrap();
+        assert_eq!(model_id(&conversation), "picked");
+
+        conversation.swap_agent(&mut os, &mut output, "pinned").await.unwrap();
+        conversation.swap_agent(&mut os, &mut output, "missing").await.unwrap();
+        assert_eq!(model_id(&conversation), "picked");
+        assert!(
+            String::from_utf8_lossy(&output)
+                .contains("Model 'no-such-model' of agent missing is not available, using the default model instead")
+        );
+    }
+
+    #[tokio::test]
+    async fn test_drop_oldest_turns() {
+        let mut os = Os::new().await.unwrap();
+        let mut tool_manager = ToolManager::default();
+        let mut conversation = ConversationState::new(
+            "fake_conv_id",
+            Agents::default(),
+            tool_manager.load_tools(&mut os, &mut vec![]).await.unwrap(),
+            tool_manager,
+            None,
+            &os,
+            false,
+        )
+        .await;
+
+        for i in 0..5 {
+            conversation.set_next_user_message(i.to_string()).await;
+            conversation
+                .as_sendable_conversation_state(&os, &mut vec![], false)
+                .await
+                .unwrap();
+            conversation.push_assistant_message(&mut os, AssistantMessage::new_response(None, i.to_string()), None);
+        }
+
+        conversation.set_next_user_message("next".to_string()).await;
+        assert_eq!(conversation.drop_oldest_turns(), 3);
+        assert_eq!(conversation.history().len(), 2);
+        let s = conversation
+            .as_sendable_conversation_state(&os, &mut vec![], false)
+            .await
+            .unwrap();
+        assert_conversation_state_invariants(s, 0);
+        assert_eq!(conversation.history().front().unwrap().user.prompt(), Some("3"));
+    }
+
+    #[tokio::test]
+    async fn test_exceeds_context_threshold() {
+        let mut os = Os::new().await.unwrap();
+        let mut tool_manager = ToolManager::default();
+        let mut conversation = ConversationState::new(
+            "fake_conv_id",
+            Agents::default(),
+            tool_manager.load_tools(&mut os, &mut vec![]).await.unwrap(),
+            tool_manager,
+            None,
+            &os,
+            false,
+        )
+        .await;
+
+        conversation.set_next_user_message("hello".to_string()).await;
+        assert!(conversation.exceeds_context_threshold(&os, 0).await.unwrap());
+        assert!(!conversation.exceeds_context_threshold(&os, 90).await.unwrap());
+
+        // A message larger than the whole context window exceeds any threshold
+        let max_chars = TokenCounter::token_to_chars(context_window_tokens(conversation.model_info.as_ref()));
+        conversation.reset_next_user_message();
+        conversation.set_next_user_message("a".repeat(max_chars)).await;
+        assert!(conversation.exceeds_context_threshold(&os, 100).await.unwrap());
+    }
+
+    #[tokio::test]
+    async fn test_context_usage_percent() {
+        let mut os = Os::new().await.unwrap();
+        let mut tool_manager = ToolManager::default();
+        let mut conversation = ConversationState::new(
+            "fake_conv_id",
+            Agents::default(),
+            tool_manager.load_tools(&mut os, &mut vec![]).await.unwrap(),
+            tool_manager,
+            None,
+            &os,
+            false,
+        )
+        .await;
+
+        conversation.set_next_user_message("hello".to_string()).await;
+        assert!(conversation.context_usage_percent(&os).await.unwrap() < 50);
+
+        // Tool specifications count towards the usage on top of the messages
+        let max_chars = TokenCounter::token_to_chars(context_window_tokens(conversation.model_info.as_ref()));
+        conversation.reset_next_user_message();
+        conversation.set_next_user_message("a".repeat(max_chars)).await;
+        assert!(conversation.context_usage_percent(&os).await.unwrap() > 100);
+    }
+
     #[tokio::test]
     async fn test_conversation_state_history_handling_with_tool_results() {
         let mut os = Os::new().await.unwrap();
@@ -1492,6 +1947,69 @@ mod tests {
         assert!(!conversation.is_in_tangent_mode());
     }
 
+    #[tokio::test]
+    async fn test_tangent_mode_with_summary() {
+        let mut os = Os::new().await.unwrap();
+        let agents = Agents::default();
+        let mut tool_manager = ToolManager::default();
+        let mut conversation = ConversationState::new(
+            "fake_conv_id",
+            agents,
+            tool_manager.load_tools(&mut os, &mut vec![]).await.unwrap(),
+            tool_manager,
+            None,
+            &os,
+            false, // mcp_enabled
+        )
+        .await;
+
+        conversation
+            .set_next_user_message("main conversation".to_string())
+            .await;
+        conversation.push_assistant_message(
+            &mut os,
+            AssistantMessage::new_response(None, "main response".to_string()),
+            None,
+        );
+        let main_history_len = conversation.history.len();
+
+        // Nothing to summarize outside of tangent mode or before anything was exchanged
+        assert!(conversation.create_tangent_summary_request().is_none());
+        conversation.enter_tangent_mode();
+        conversation.set_tangent_merge_on_exit(true);
+        assert!(conversation.tangent_merge_on_exit());
+        assert!(conversation.create_tangent_summary_request().is_none());
+
+        conversation
+            .set_next_user_message("tangent conversation".to_string())
+            .await;
+        conversation.push_assistant_message(
+            &mut os,
+            AssistantMessage::new_response(None, "tangent response".to_string()),
+            None,
+        );
+
+        let request = conversation.create_tangent_summary_request().unwrap();
+        // Only the messages of the tangent are summarized
+        assert_eq!(request.history.as_ref().unwrap().len(), 2);
+        // Creating the request leaves the conversation untouched
+        assert_eq!(conversation.history.len(), main_history_len + 1);
+        assert!(conversation.is_in_tangent_mode());
+
+        conversation.exit_tangent_mode_with_summary("- the tangent was about testing");
+        assert!(!conversation.is_in_tangent_mode());
+        assert_eq!(conversation.history.len(), main_history_len + 1);
+        let summary_prompt = conversation.history.back().unwrap().user.prompt().unwrap();
+        assert!(summary_prompt.contains("TANGENT SUMMARY"));
+        assert!(summary_prompt.contains("the tangent was about testing"));
+        assert!(
+            !conversation
+                .history
+                .iter()
+                .any(|e| e.user.prompt().is_some_and(|p| p.contains("tangent conversation")))
+        );
+    }
+
     #[tokio::test]
     async fn test_tangent_mode_duration() {
         let mut os = Os::new().await.unwrap();
@@ -1527,4 +2045,82 @@ mod tests {
         // No duration when not in tangent mode
         assert!(conversation.get_tangent_duration_seconds().is_none());
     }
+
+    #[tokio::test]
+    async fn test_delegation() {
+        let mut os = Os::new().await.unwrap();
+        let agents = Agents::default();
+        let mut tool_manager = ToolManager::default();
+        let mut conversation = ConversationState::new(
+            "fake_conv_id",
+            agents,
+            tool_manager.load_tools(&mut os, &mut vec![]).await.unwrap(),
+            tool_manager,
+            None,
+            &os,
+            false, // mcp_enabled
+        )
+        .await;
+
+        conversation
+            .set_next_user_message("main conversation".to_string())
+            .await;
+        conversation.push_assistant_message(
+            &mut os,
+            AssistantMessage::new_response(None, "main response".to_string()),
+            None,
+        );
+
+        // The delegated task starts from an empty conversation
+        conversation.enter_delegation();
+        assert!(conversation.history.is_empty());
+        assert!(conversation.transcript.is_empty());
+        conversation.set_next_user_message("delegated task".to_string()).await;
+        conversation.push_assistant_message(
+            &mut os,
+            AssistantMessage::new_response(None, "delegated response".to_string()),
+            None,
+        );
+
+        // Delegations nest
+        conversation.enter_delegation();
+        assert!(conversation.history.is_empty());
+        conversation.exit_delegation();
+        assert_eq!(conversation.history.len(), 1);
+        assert_eq!(conversation.history[0].assistant.content(), "delegated response");
+
+        conversation.exit_delegation();
+        assert_eq!(conversation.history.len(), 1);
+        assert_eq!(conversation.history[0].assistant.content(), "main response");
+        assert!(conversation.next_message.is_none());
+
+        // Exiting without a delegation leaves the conversation alone
+        conversation.exit_delegation();
+        assert_eq!(conversation.history.len(), 1);
+    }
+
+    #[test]
+    fn test_format_hook_context() {
+        use crate::cli::agent::hook::Source;
+
+        let injected = Hook::new("git status --short".to_string(), Source::Agent);
+        let mut silent = Hook::new("touch marker".to_string(), Source::Agent);
+        silent.inject_output = false;
+
+        let results = vec![
+            ((HookTrigger::UserPromptSubmit, injected), " M src/main.rs".to_string()),
+            (
+                (HookTrigger::UserPromptSubmit, silent.clone()),
+                "side effect".to_string(),
+            ),
+        ];
+        let ctx = format_hook_context(&results, HookTrigger::UserPromptSubmit).unwrap();
+        assert!(ctx.contains("--- Output of hook `git status --short` ---\n M src/main.rs"));
+        assert!(!ctx.contains("side effect"));
+
+        // Nothing to inject for a different trigger or when only silent hooks produced output
+        assert!(format_hook_context(&results, HookTrigger::AgentSpawn).is_none());
+        let results = vec![((HookTrigger::UserPromptSubmit, silent), "side effect".to_string())];
+        assert!(format_hook_context(&results, HookTrigger::UserPromptSubmit).is_none());
+    }
 }
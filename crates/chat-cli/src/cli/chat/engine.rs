@@ -0,0 +1,285 @@
+//! Runs q chat without a terminal, for embedding it in other applications.
+//!
+//! [ChatEngine] drives the same [ChatSession] state machine as `q chat`. Instead of printing
+//! to the terminal, everything that happens during a turn is reported to an [EventSink] as a
+//! [ChatEvent], and tool approvals are requested from a [ToolApprover].
+
+use async_trait::async_trait;
+use eyre::Result;
+use tracing::warn;
+
+use super::input_source::InputSource;
+use super::tool_manager::ToolManagerBuilder;
+use super::{
+    ChatError,
+    ChatErrorKind,
+    ChatSession,
+    ChatState,
+};
+use crate::cli::agent::{
+    Agent,
+    Agents,
+};
+use crate::os::Os;
+use crate::util::NullWriter;
+
+/// Something that happened during a turn, reported to the [EventSink] of a [ChatEngine].
+#[derive(Debug, Clone, PartialEq)]
+pub enum ChatEvent {
+    /// Part of the response of the assistant, as it is streamed
+    AssistantDelta(String),
+    /// The model asked to use a tool. Reported before the tool is approved
+    ToolRequested(ToolRequest),
+    /// A requested tool was executed
+    ToolResult {
+        id: String,
+        name: String,
+        success: bool,
+        /// Text output of the tool, or the error it failed with
+        output: String,
+    },
+    /// The turn failed. The engine can still be sent further messages
+    Error { kind: ChatErrorKind, message: String },
+}
+
+/// A tool use requested by the model.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ToolRequest {
+    pub id: String,
+    pub name: String,
+    /// The input the model passed to the tool
+    pub args: serde_json::Value,
+}
+
+/// Receives the [ChatEvent]s of a [ChatEngine].
+pub trait EventSink: Send {
+    fn emit(&mut self, event: ChatEvent);
+}
+
+impl<F: FnMut(ChatEvent) + Send> EventSink for F {
+    fn emit(&mut self, event: ChatEvent) {
+        self(event);
+    }
+}
+
+/// Answer to a [ToolApprover], matching the choices offered at the q chat prompt.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ToolDecision {
+    /// Execute the tool once
+    Allow,
+    /// Execute the tool, and trust it for the rest of the session
+    Trust,
+    /// Do not execute the tool. The model is asked to clarify the expected action instead
+    Deny,
+}
+
+/// Decides on the tool uses that the agent of a [ChatEngine] does not allow on its own.
+#[async_trait]
+pub trait ToolApprover: Send {
+    async fn approve(&mut self, request: &ToolRequest) -> ToolDecision;
+}
+
+/// A chat session that is driven one turn at a time, see the [module docs](self).
+pub struct ChatEngine {
+    os: Os,
+    session: ChatSession,
+    approver: Box<dyn ToolApprover>,
+}
+
+impl ChatEngine {
+    /// Starts a new conversation with `agent`, loading its MCP servers.
+    pub async fn new(
+        mut os: Os,
+        agent: Agent,
+        events: Box<dyn EventSink>,
+        approver: Box<dyn ToolApprover>,
+    ) -> Result<Self> {
+        let conversation_id = uuid::Uuid::new_v4().to_string();
+        let mcp_enabled = match os.client.is_mcp_enabled().await {
+            Ok(enabled) => enabled,
+            Err(err) => {
+                warn!(?err, "Failed to check MCP configuration, defaulting to enabled");
+                true
+            },
+        };
+
+        let mut agents = Agents::default();
+        agents.activate_ephemeral(agent);
+        let mut tool_manager = ToolManagerBuilder::default()
+            .conversation_id(&conversation_id)
+            .agent(agents.get_active().cloned().unwrap_or_default())
+            .build(&mut os, Box::new(NullWriter), false)
+            .await?;
+        let tool_config = tool_manager.load_tools(&mut os, &mut NullWriter).await?;
+
+        let session = ChatSession::new(
+            &mut os,
+            std::io::stdout(),
+            std::io::stderr(),
+            &conversation_id,
+            agents,
+            None,
+            InputSource::new_mock(Vec::new()),
+            false,
+            || None,
+            tool_manager,
+            None,
+            tool_config,
+            false,
+            mcp_enabled,
+        )
+        .await?
+        .with_event_sink(events);
+
+        Ok(Self { os, session, approver })
+    }
+
+    /// Sends `user_message` and drives the conversation until the model has responded to it,
+    /// executing the tools it asks for along the way. As at the q chat prompt, messages starting
+    /// with `/` run slash commands.
+    ///
+    /// Errors that end the turn are reported as [ChatEvent::Error]. An error is only returned if
+    /// the session itself can no longer continue.
+    pub async fn send(&mut self, user_message: impl Into<String>) -> Result<(), ChatError> {
+        self.session.inner = Some(ChatState::HandleInput {
+            input: user_message.into(),
+        });
+
+        loop {
+            match self.session.inner {
+                Some(ChatState::Exit) => return Ok(()),
+                Some(ChatState::PromptUser { .. }) => {
+                    let Some(request) = self.session.pending_tool_request() else {
+                        return Ok(());
+                    };
+                    let input = match self.approver.approve(&request).await {
+                        ToolDecision::Allow => "y",
+                        ToolDecision::Trust => "t",
+                        ToolDecision::Deny => "n",
+                    };
+                    self.session.inner = Some(ChatState::HandleInput {
+                        input: input.to_string(),
+                    });
+                },
+                _ => self.session.next(&mut self.os).await?,
+            }
+        }
+    }
+
+    /// Id of the conversation, as stored in the q chat history.
+    pub fn conversation_id(&self) -> &str {
+        self.session.conversation.conversation_id()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::{
+        Arc,
+        Mutex,
+    };
+
+    use super::*;
+
+    struct Approver(ToolDecision);
+
+    #[async_trait]
+    impl ToolApprover for Approver {
+        async fn approve(&mut self, _request: &ToolRequest) -> ToolDecision {
+            self.0
+        }
+    }
+
+    async fn engine(os: Os, decision: ToolDecision) -> (ChatEngine, Arc<Mutex<Vec<ChatEvent>>>) {
+        let events = Arc::new(Mutex::new(Vec::new()));
+        let sink = {
+            let events = Arc::clone(&events);
+            move |event: ChatEvent| events.lock().unwrap().push(event)
+        };
+        let engine = ChatEngine::new(os, Agent::default(), Box::new(sink), Box::new(Approver(decision)))
+            .await
+            .unwrap();
+        (engine, events)
+    }
+
+    #[tokio::test]
+    async fn test_engine_turn() {
+        let mut os = Os::new().await.unwrap();
+        os.client.set_mock_output(serde_json::json!([
+            [
+                "Creating the file",
+                {
+                    "tool_use_id": "1",
+                    "name": "fs_write",
+                    "args": {
+                        "command": "create",
+                        "file_text": "Hello, world!",
+                        "path": "/file.txt",
+                    }
+                }
+            ],
+            [
+                "Done!",
+            ],
+        ]));
+
+        let (mut engine, events) = engine(os, ToolDecision::Allow).await;
+        engine.send("create a file").await.unwrap();
+        assert_eq!(
+            engine.os.fs.read_to_string("/file.txt").await.unwrap(),
+            "Hello, world!\n"
+        );
+
+        let events = events.lock().unwrap();
+        let text = events
+            .iter()
+            .filter_map(|event| match event {
+                ChatEvent::AssistantDelta(text) => Some(text.as_str()),
+                _ => None,
+            })
+            .collect::<String>();
+        assert!(text.contains("Creating the file"));
+        assert!(text.contains("Done!"));
+        assert!(events.iter().any(
+            |event| matches!(event, ChatEvent::ToolRequested(request) if request.id == "1" && request.name == "fs_write")
+        ));
+        assert!(
+            events
+                .iter()
+                .any(|event| matches!(event, ChatEvent::ToolResult { id, success, .. } if id == "1" && *success))
+        );
+    }
+
+    #[tokio::test]
+    async fn test_engine_denied_tool() {
+        let mut os = Os::new().await.unwrap();
+        os.client.set_mock_output(serde_json::json!([
+            [
+                "Creating the file",
+                {
+                    "tool_use_id": "1",
+                    "name": "fs_write",
+                    "args": {
+                        "command": "create",
+                        "file_text": "Hello, world!",
+                        "path": "/file.txt",
+                    }
+                }
+            ],
+            [
+                "Where should the file go?",
+            ],
+        ]));
+
+        let (mut engine, events) = engine(os, ToolDecision::Deny).await;
+        engine.send("create a file").await.unwrap();
+        assert!(!engine.os.fs.exists("/file.txt"));
+        assert!(
+            !events
+                .lock()
+                .unwrap()
+                .iter()
+                .any(|event| matches!(event, ChatEvent::ToolResult { .. }))
+        );
+    }
+}
@@ -0,0 +1,276 @@
+//! Structured trace of a chat session, written with `q chat --event-log <PATH>`.
+//!
+//! Each [Event] is written to the log as a single line of JSON, wrapped in an [EventRecord],
+//! and flushed right away so that the file stays usable if the session crashes. Conversation
+//! text, such as prompts, responses and tool arguments, is only logged when the session is
+//! started with `--event-log-include-text`.
+
+use std::fs::{
+    File,
+    OpenOptions,
+};
+use std::io::Write;
+use std::path::Path;
+
+use chrono::{
+    DateTime,
+    Utc,
+};
+use serde::{
+    Deserialize,
+    Serialize,
+};
+use tracing::warn;
+
+/// A single line of the event log.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct EventRecord {
+    pub timestamp: DateTime<Utc>,
+    #[serde(flatten)]
+    pub event: Event,
+}
+
+/// Something that happened during a chat session. Serialized with its name in the `event` field,
+/// e.g. `{"event":"tool_completed",...}`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "event", rename_all = "snake_case")]
+pub enum Event {
+    SessionStart {
+        /// Name of the active agent
+        agent: Option<String>,
+        /// Id of the model, if one was selected
+        model: Option<String>,
+        /// Names of the MCP servers that were configured for the session
+        mcp_servers: Vec<String>,
+    },
+    /// A message from the user was sent to the model.
+    PromptSubmitted {
+        /// Length of the message in characters
+        length: usize,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        text: Option<String>,
+    },
+    /// The model finished responding.
+    AssistantMessage {
+        /// Length of the response text in characters
+        length: usize,
+        stop_reason: StopReason,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        text: Option<String>,
+    },
+    ToolRequested {
+        id: String,
+        name: String,
+        /// The input the model passed to the tool
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        args: Option<serde_json::Value>,
+    },
+    ToolApproved {
+        id: String,
+        name: String,
+        /// Whether the tool was allowed without asking the user
+        trusted: bool,
+    },
+    ToolDenied {
+        id: String,
+        name: String,
+        reason: DenyReason,
+    },
+    /// A tool finished executing, successfully or not.
+    ToolCompleted {
+        id: String,
+        name: String,
+        success: bool,
+        duration_ms: u64,
+        /// Size of the tool output, or of the error it failed with, in bytes
+        result_size: usize,
+    },
+    /// The conversation history was shortened to fit the context window.
+    Compaction {
+        strategy: CompactionStrategy,
+        /// Number of messages in the history before it was shortened
+        messages: usize,
+    },
+    Error {
+        /// One of the [super::ChatErrorKind] names, e.g. `ToolApprovalRequired`
+        kind: String,
+        message: String,
+    },
+    SessionEnd {
+        /// Number of requests sent to the model
+        model_turns: u32,
+        /// Number of tools executed
+        tool_invocations: u32,
+        /// Estimated number of tokens sent to the model
+        input_tokens: usize,
+        /// Estimated number of tokens received from the model
+        output_tokens: usize,
+    },
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum StopReason {
+    /// The response is complete and it is the user's turn
+    EndTurn,
+    /// The model is waiting for the result of the tools it requested
+    ToolUse,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DenyReason {
+    /// The user rejected the tool use when asked for approval
+    User,
+    /// The arguments of the tool matched a rule on the denied list of the agent
+    DeniedList,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CompactionStrategy {
+    /// The history was replaced with a summary written by the model
+    Summary,
+    /// The oldest messages were dropped after the context window overflowed
+    DropOldest,
+}
+
+impl Event {
+    /// Removes the conversation text carried by the event.
+    fn redact_text(&mut self) {
+        match self {
+            Event::PromptSubmitted { text, .. } | Event::AssistantMessage { text, .. } => *text = None,
+            Event::ToolRequested { args, .. } => *args = None,
+            _ => (),
+        }
+    }
+}
+
+/// Writes the [Event]s of a session to a file as newline-delimited JSON.
+#[derive(Debug)]
+pub struct EventLog {
+    file: File,
+    include_text: bool,
+}
+
+impl EventLog {
+    /// Opens the log at `path`. Events are appended if the file already exists.
+    pub fn open(path: &Path, include_text: bool) -> std::io::Result<Self> {
+        let file = OpenOptions::new().create(true).append(true).open(path)?;
+        Ok(Self { file, include_text })
+    }
+
+    /// Writes `event` and flushes it to the file. Failing to write is logged rather than
+    /// returned, so that a broken log does not end the session.
+    pub fn write(&mut self, mut event: Event) {
+        if !self.include_text {
+            event.redact_text();
+        }
+        let record = EventRecord {
+            timestamp: Utc::now(),
+            event,
+        };
+        let result = serde_json::to_string(&record)
+            .map_err(std::io::Error::from)
+            .and_then(|line| writeln!(self.file, "{line}"))
+            .and_then(|_| self.file.flush());
+        if let Err(err) = result {
+            warn!(?err, "failed to write to the event log");
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn read_records(path: &Path) -> Vec<EventRecord> {
+        std::fs::read_to_string(path)
+            .unwrap()
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect()
+    }
+
+    #[test]
+    fn test_event_log_excludes_text_by_default() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("events.ndjson");
+
+        let mut log = EventLog::open(&path, false).unwrap();
+        log.write(Event::PromptSubmitted {
+            length: 5,
+            text: Some("hello".to_string()),
+        });
+        log.write(Event::ToolRequested {
+            id: "1".to_string(),
+            name: "fs_read".to_string(),
+            args: Some(serde_json::json!({ "path": "/tmp" })),
+        });
+
+        // Every event is flushed as soon as it is written.
+        let records = read_records(&path);
+        assert_eq!(records[0].event, Event::PromptSubmitted { length: 5, text: None });
+        assert_eq!(records[1].event, Event::ToolRequested {
+            id: "1".to_string(),
+            name: "fs_read".to_string(),
+            args: None,
+        });
+        assert!(!std::fs::read_to_string(&path).unwrap().contains("hello"));
+    }
+
+    #[test]
+    fn test_event_log_include_text() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("events.ndjson");
+
+        let mut log = EventLog::open(&path, true).unwrap();
+        log.write(Event::AssistantMessage {
+            length: 2,
+            stop_reason: StopReason::EndTurn,
+            text: Some("hi".to_string()),
+        });
+        drop(log);
+
+        // Reopening the log appends to it.
+        let mut log = EventLog::open(&path, true).unwrap();
+        log.write(Event::Compaction {
+            strategy: CompactionStrategy::Summary,
+            messages: 4,
+        });
+
+        let records = read_records(&path);
+        assert_eq!(records.len(), 2);
+        assert_eq!(records[0].event, Event::AssistantMessage {
+            length: 2,
+            stop_reason: StopReason::EndTurn,
+            text: Some("hi".to_string()),
+        });
+    }
+
+    #[test]
+    fn test_event_serialization() {
+        let record = EventRecord {
+            timestamp: DateTime::from_timestamp(0, 0).unwrap(),
+            event: Event::ToolCompleted {
+                id: "1".to_string(),
+                name: "execute_bash".to_string(),
+                success: true,
+                duration_ms: 12,
+                result_size: 34,
+            },
+        };
+        assert_eq!(
+            serde_json::to_value(&record).unwrap(),
+            serde_json::json!({
+                "timestamp": "1970-01-01T00:00:00Z",
+                "event": "tool_completed",
+                "id": "1",
+                "name": "execute_bash",
+                "success": true,
+                "duration_ms": 12,
+                "result_size": 34,
+            })
+        );
+    }
+}
@@ -0,0 +1,130 @@
+use clap::ValueEnum;
+use serde::Serialize;
+
+use super::ChatErrorKind;
+use super::attachment::AttachmentRecord;
+use super::session_stats::SessionStats;
+
+/// Format of what a non-interactive chat session writes to stdout.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, ValueEnum)]
+pub enum OutputFormat {
+    /// The assistant response and tool output as they would be shown in a terminal
+    #[default]
+    Plain,
+    /// A single JSON object describing the turn, written once the session ends
+    Json,
+}
+
+/// Result of a single non-interactive turn, written to stdout with `--output json`.
+#[derive(Debug, Default, Serialize)]
+pub struct TurnOutput {
+    /// Final message from the assistant, if the turn completed
+    pub response: Option<String>,
+    /// Tools executed during the turn, in order
+    pub tools_used: Vec<ToolUseRecord>,
+    /// Content attached to the prompt from stdin or with `--file`
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub attachments: Vec<AttachmentRecord>,
+    pub usage: Usage,
+    /// Why the turn did not complete
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<TurnError>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ToolUseRecord {
+    pub id: String,
+    pub name: String,
+    pub success: bool,
+    /// Text output of the tool, or the error it failed with
+    pub result: String,
+}
+
+#[derive(Debug, Default, Serialize)]
+pub struct Usage {
+    /// Number of requests sent to the model
+    pub model_turns: u32,
+    /// Estimated number of tokens sent to the model
+    pub input_tokens: usize,
+    /// Estimated number of tokens received from the model
+    pub output_tokens: usize,
+}
+
+impl From<&SessionStats> for Usage {
+    fn from(stats: &SessionStats) -> Self {
+        Self {
+            model_turns: stats.model_turns,
+            input_tokens: stats.input_tokens,
+            output_tokens: stats.output_tokens,
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct TurnError {
+    /// One of the [ChatErrorKind] names, e.g. `ToolApprovalRequired`
+    pub kind: String,
+    pub message: String,
+    /// Process exit code
+    pub exit_code: u8,
+}
+
+impl TurnError {
+    pub fn new(kind: ChatErrorKind, message: String) -> Self {
+        Self {
+            kind: kind.as_ref().to_string(),
+            message,
+            exit_code: kind.exit_code(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_turn_output_serialization() {
+        let output = TurnOutput {
+            response: Some("Done".to_string()),
+            tools_used: vec![ToolUseRecord {
+                id: "tooluse_1".to_string(),
+                name: "fs_read".to_string(),
+                success: true,
+                result: "contents".to_string(),
+            }],
+            attachments: vec![AttachmentRecord {
+                source: "stdin".to_string(),
+                bytes: 2048,
+                truncated: false,
+                tokens: 510,
+            }],
+            usage: Usage {
+                model_turns: 2,
+                input_tokens: 100,
+                output_tokens: 20,
+            },
+            error: None,
+        };
+        let value = serde_json::to_value(&output).unwrap();
+        assert_eq!(value["response"], "Done");
+        assert_eq!(value["tools_used"][0]["name"], "fs_read");
+        assert_eq!(value["attachments"][0]["source"], "stdin");
+        assert_eq!(value["attachments"][0]["tokens"], 510);
+        assert_eq!(value["usage"]["model_turns"], 2);
+        assert!(value.get("error").is_none());
+
+        let output = TurnOutput {
+            error: Some(TurnError::new(
+                ChatErrorKind::ToolApprovalRequired,
+                "approval required".to_string(),
+            )),
+            ..Default::default()
+        };
+        let value = serde_json::to_value(&output).unwrap();
+        assert_eq!(value["response"], serde_json::Value::Null);
+        assert!(value.get("attachments").is_none());
+        assert_eq!(value["error"]["kind"], "ToolApprovalRequired");
+        assert_eq!(value["error"]["exit_code"], 4);
+    }
+}
@@ -26,6 +26,7 @@ use super::conversation::{
 use super::tools::{
     InvokeOutput,
     OutputKind,
+    ToolErrorKind,
     ToolOrigin,
 };
 use super::util::{
@@ -133,9 +134,10 @@ impl UserMessage {
                 tool_use_results: tool_use_ids
                     .map(|id| ToolUseResult {
                         tool_use_id: id.to_string(),
-                        content: vec![ToolUseResultBlock::Text(
-                            "Tool use was cancelled by the user".to_string(),
-                        )],
+                        content: vec![
+                            ToolUseResultBlock::Text("Tool use was cancelled by the user".to_string()),
+                            ToolUseResultBlock::Json(ToolErrorKind::Cancelled.to_json()),
+                        ],
                         status: ToolResultStatus::Error,
                     })
                     .collect(),
@@ -1,26 +1,36 @@
+mod attachment;
 pub mod cli;
 mod consts;
 pub mod context;
+mod context_watcher;
 mod conversation;
+pub mod engine;
 mod error_formatter;
+pub mod event_log;
 mod input_source;
+mod json_output;
 mod message;
 mod parse;
 use std::path::MAIN_SEPARATOR;
 mod line_tracker;
 mod parser;
+mod pause;
 mod prompt;
 mod prompt_parser;
+mod read_cache;
 mod server_messenger;
+mod session_stats;
 #[cfg(unix)]
 mod skim_integration;
 mod token_counter;
+mod tool_loop;
 pub mod tool_manager;
 pub mod tools;
 pub mod util;
 use std::borrow::Cow;
 use std::collections::{
     HashMap,
+    HashSet,
     VecDeque,
 };
 use std::io::{
@@ -28,6 +38,7 @@ use std::io::{
     Read,
     Write,
 };
+use std::path::PathBuf;
 use std::process::ExitCode;
 use std::sync::Arc;
 use std::time::{
@@ -36,6 +47,13 @@ use std::time::{
 };
 
 use amzn_codewhisperer_client::types::SubscriptionStatus;
+use attachment::{
+    Attachment,
+    AttachmentRecord,
+    MAX_ATTACHMENT_BYTES,
+    STDIN_FILE_ARG,
+    prompt_with_attachments,
+};
 use clap::{
     Args,
     CommandFactory,
@@ -43,9 +61,11 @@ use clap::{
 };
 use cli::compact::CompactStrategy;
 use cli::model::{
+    default_model,
     get_available_models,
     select_model,
 };
+use context_watcher::ContextWatcher;
 pub use conversation::ConversationState;
 use conversation::TokenWarningLevel;
 use crossterm::style::{
@@ -60,6 +80,18 @@ use crossterm::{
     style,
     terminal,
 };
+use engine::{
+    ChatEvent,
+    EventSink,
+    ToolRequest,
+};
+use event_log::{
+    CompactionStrategy,
+    DenyReason,
+    Event,
+    EventLog,
+    StopReason,
+};
 use eyre::{
     Report,
     Result,
@@ -67,6 +99,13 @@ use eyre::{
     eyre,
 };
 use input_source::InputSource;
+pub use json_output::OutputFormat;
+use json_output::{
+    ToolUseRecord,
+    TurnError,
+    TurnOutput,
+    Usage,
+};
 use message::{
     AssistantMessage,
     AssistantToolUse,
@@ -82,32 +121,46 @@ use parser::{
     RequestMetadata,
     SendMessageStream,
 };
+use pause::PausedSession;
+use read_cache::FileReadCache;
 use regex::Regex;
+use session_stats::SessionStats;
 use spinners::{
     Spinner,
     Spinners,
 };
 use thiserror::Error;
 use time::OffsetDateTime;
-use token_counter::TokenCounter;
+use token_counter::{
+    CharCount,
+    CharCounter,
+    TokenCount,
+    TokenCounter,
+};
 use tokio::signal::ctrl_c;
 use tokio::sync::{
     Mutex,
     broadcast,
 };
+use tool_loop::ToolLoopDetector;
 use tool_manager::{
     PromptQuery,
     PromptQueryResult,
     ToolManager,
     ToolManagerBuilder,
 };
+use tools::agent_delegate::AgentDelegate;
+use tools::fs_write::FsWrite;
 use tools::gh_issue::GhIssueContext;
 use tools::{
     NATIVE_TOOLS,
     OutputKind,
     QueuedTool,
+    TOOL_ERROR_KIND_KEY,
     Tool,
+    ToolErrorKind,
     ToolSpec,
+    plain_output,
 };
 use tracing::{
     debug,
@@ -117,7 +170,15 @@ use tracing::{
     warn,
 };
 use util::images::RichImageBlock;
-use util::ui::draw_box;
+use util::tool_display::{
+    DisplayOutput,
+    DisplayWriter,
+};
+use util::ui::{
+    draw_box,
+    glyph,
+    start_spinner,
+};
 use util::{
     animate_output,
     play_notification_bell,
@@ -131,6 +192,7 @@ use super::agent::{
     PermissionEvalResult,
 };
 use crate::api_client::model::ToolResultStatus;
+use crate::api_client::retry_policy::ThrottleRetryPolicy;
 use crate::api_client::{
     self,
     ApiClientError,
@@ -138,7 +200,14 @@ use crate::api_client::{
 use crate::auth::AuthError;
 use crate::auth::builder_id::is_idc_user;
 use crate::cli::TodoListState;
-use crate::cli::agent::Agents;
+use crate::cli::agent::hook::HookTrigger;
+use crate::cli::agent::{
+    Agent,
+    Agents,
+    AgentsLoadMetadata,
+    LoadedAgentMetadata,
+    STDIN_AGENT_NAME,
+};
 use crate::cli::chat::cli::SlashCommand;
 use crate::cli::chat::cli::editor::open_editor;
 use crate::cli::chat::cli::model::find_model;
@@ -146,7 +215,12 @@ use crate::cli::chat::cli::prompts::{
     GetPromptError,
     PromptsSubcommand,
 };
+use crate::cli::chat::cli::suggestions::CommandSuggestion;
 use crate::cli::chat::message::UserMessage;
+use crate::cli::chat::util::sanitize::{
+    findings_notice,
+    sanitize_tool_output,
+};
 use crate::cli::chat::util::sanitize_unicode_tags;
 use crate::database::settings::Setting;
 use crate::mcp_client::Prompt;
@@ -163,9 +237,11 @@ use crate::telemetry::{
     ReasonCode,
     TelemetryResult,
     get_error_reason,
+    is_telemetry_enabled,
 };
 use crate::util::{
     MCP_SERVER_TOOL_DELIMITER,
+    NullWriter,
     directories,
 };
 
@@ -195,9 +271,16 @@ pub struct ChatArgs {
     /// Resumes the previous conversation from this directory.
     #[arg(short, long)]
     pub resume: bool,
+    /// Resumes the session paused with /pause that has this id
+    #[arg(long, value_name = "SESSION_ID", conflicts_with = "resume")]
+    pub resume_session: Option<String>,
     /// Context profile to use
     #[arg(long = "agent", alias = "profile")]
     pub agent: Option<String>,
+    /// Read an agent config as JSON from stdin and use it for this session without saving it.
+    /// Only available with --no-interactive
+    #[arg(long, conflicts_with = "agent")]
+    pub agent_from_stdin: bool,
     /// Current model to use
     #[arg(long = "model")]
     pub model: Option<String>,
@@ -208,37 +291,107 @@ pub struct ChatArgs {
     /// '--trust-tools=fs_read,fs_write', trust no tools: '--trust-tools='
     #[arg(long, value_delimiter = ',', value_name = "TOOL_NAMES")]
     pub trust_tools: Option<Vec<String>>,
+    /// Start the chat with every tool disabled, including read-only ones. The model can only
+    /// respond with text.
+    #[arg(long, conflicts_with_all = ["trust_all_tools", "trust_tools"])]
+    pub no_tools: bool,
     /// Whether the command should run without expecting user input
     #[arg(long, alias = "non-interactive")]
     pub no_interactive: bool,
+    /// Run a single prompt without expecting user input and exit. Equivalent to passing the
+    /// prompt as INPUT together with --no-interactive
+    #[arg(long, conflicts_with = "input")]
+    pub prompt: Option<String>,
+    /// Format of what is written to stdout. With json, a single object containing the response,
+    /// the tools used and usage is printed once the turn is complete. Implies --no-interactive
+    #[arg(long, value_enum, default_value_t)]
+    pub output: OutputFormat,
+    /// Attach the contents of a file to the prompt, or what is piped through stdin with `-`. Can be
+    /// repeated. Only available with --no-interactive, where piped text is the prompt when no
+    /// question is given
+    #[arg(long = "file", value_name = "PATH")]
+    pub files: Vec<PathBuf>,
+    /// Value of a `{{variable}}` placeholder in the agent prompt. Can be repeated. Variables
+    /// without a value or a default in the agent are asked for when the session starts
+    #[arg(long = "var", value_name = "NAME=VALUE", value_parser = parse_prompt_variable)]
+    pub vars: Vec<(String, String)>,
+    /// Write a trace of the session to this file as newline-delimited JSON, one event per line.
+    /// Prompts, responses and tool arguments are left out unless --event-log-include-text is passed
+    #[arg(long, value_name = "PATH")]
+    pub event_log: Option<PathBuf>,
+    /// Include prompts, responses and tool arguments in the --event-log
+    #[arg(long, requires = "event_log")]
+    pub event_log_include_text: bool,
     /// The first question to ask
     pub input: Option<String>,
 }
 
 impl ChatArgs {
     pub async fn execute(mut self, os: &mut Os) -> Result<ExitCode> {
-        let mut input = self.input;
+        if self.prompt.is_some() || self.output == OutputFormat::Json {
+            self.no_interactive = true;
+        }
+        let mut input = self.prompt.or(self.input);
 
-        if self.no_interactive && input.is_none() {
-            if !std::io::stdin().is_terminal() {
-                let mut buffer = String::new();
-                match std::io::stdin().read_to_string(&mut buffer) {
-                    Ok(_) => {
-                        if !buffer.trim().is_empty() {
-                            input = Some(buffer.trim().to_string());
-                        }
-                    },
-                    Err(e) => {
-                        eprintln!("Error reading from stdin: {}", e);
-                    },
+        let mut stdin_agent_config = None;
+        if self.agent_from_stdin {
+            if !self.no_interactive {
+                bail!("--agent-from-stdin can only be used when running in non-interactive mode");
+            }
+            if std::io::stdin().is_terminal() {
+                bail!("--agent-from-stdin expects an agent config to be piped through stdin");
+            }
+            let mut buffer = Vec::new();
+            std::io::stdin().read_to_end(&mut buffer)?;
+            stdin_agent_config = Some(buffer);
+        }
+
+        let mut attachments = Vec::new();
+        if self.no_interactive {
+            // Stdin is only read when it is needed, as the prompt or with `--file -`, since it may be
+            // left open by whatever started the command and never be closed.
+            let attach_stdin = self.files.iter().any(|path| path.as_os_str() == STDIN_FILE_ARG);
+            let stdin = match stdin_agent_config {
+                Some(_) if attach_stdin => bail!("--file - can't be used with --agent-from-stdin"),
+                Some(_) => None,
+                None if input.is_none() || attach_stdin => Attachment::from_stdin().await?,
+                None => None,
+            };
+            if let Some(stdin) = stdin {
+                // Piped stdin is the prompt itself unless one was given as an argument
+                if input.is_some() {
+                    attachments.push(stdin);
+                } else if stdin.truncated {
+                    bail!(
+                        "The prompt piped through stdin is over {MAX_ATTACHMENT_BYTES} bytes. Pass a question as \
+                         an argument with --file - to attach it to the prompt instead"
+                    );
+                } else {
+                    input = Some(stdin.content.trim().to_string());
                 }
             }
 
             if input.is_none() {
                 bail!("Input must be supplied when running in non-interactive mode");
             }
+        } else if !self.files.is_empty() {
+            bail!("--file can only be used when running in non-interactive mode");
+        }
+        for path in self.files.iter().filter(|path| path.as_os_str() != STDIN_FILE_ARG) {
+            attachments.push(Attachment::from_file(os, path).await?);
+        }
+        if let Some(prompt) = input.as_mut().filter(|_| !attachments.is_empty()) {
+            *prompt = prompt_with_attachments(prompt, &attachments);
         }
 
+        let event_log = match &self.event_log {
+            Some(path) => Some(
+                EventLog::open(path, self.event_log_include_text)
+                    .map_err(|err| eyre!("Failed to open the event log {}: {err}", path.display()))?,
+            ),
+            None => None,
+        };
+
         let stdout = std::io::stdout();
         let mut stderr = std::io::stderr();
 
@@ -272,11 +425,42 @@ impl ChatArgs {
             },
         };
 
-        let agents = {
+        let paused = match &self.resume_session {
+            Some(session_id) => Some(PausedSession::load(os, session_id).await?),
+            None => None,
+        };
+
+        let (agents, agents_load_metadata) = {
             let skip_migration = self.no_interactive;
-            let (mut agents, md) =
-                Agents::load(os, self.agent.as_deref(), skip_migration, &mut stderr, mcp_enabled).await;
+            // A paused session is resumed with the agent it was paused with, unless another one is given
+            let agent = self
+                .agent
+                .as_deref()
+                .or(paused.as_ref().map(|paused| paused.active_agent.as_str()));
+            let (mut agents, mut md) = Agents::load(
+                os,
+                agent,
+                skip_migration,
+                !self.no_interactive,
+                &mut stderr,
+                mcp_enabled,
+            )
+            .await;
+            if let Some(config) = &stdin_agent_config {
+                let agent = Agent::load_ephemeral(os, STDIN_AGENT_NAME, config, mcp_enabled, &mut stderr)
+                    .await
+                    .map_err(|err| eyre!("Failed to load the agent from stdin: {err}"))?;
+                md.load_count += 1;
+                md.launched_agent = agent.name.clone();
+                md.agents.retain(|a| a.name != agent.name);
+                md.agents.push(LoadedAgentMetadata::from(&agent));
+                md.agents.sort_by(|a, b| a.name.cmp(&b.name));
+                agents.activate_ephemeral(agent);
+            }
             agents.trust_all_tools = self.trust_all_tools;
+            if self.no_tools {
+                agents.disable_tools();
+            }
 
             os.telemetry
                 .send_agent_config_init(&os.database, conversation_id.clone(), AgentConfigInitArgs {
@@ -284,7 +468,7 @@ impl ChatArgs {
                     agents_loaded_failed_count: md.load_failed_count as i64,
                     legacy_profile_migration_executed: md.migration_performed,
                     legacy_profile_migrated_count: md.migrated_count as i64,
-                    launched_agent: md.launched_agent,
+                    launched_agent: md.launched_agent.clone(),
                 })
                 .await
                 .map_err(|err| error!(?err, "failed to send agent config init telemetry"))
@@ -335,12 +519,16 @@ impl ChatArgs {
                 }
             }
 
-            agents
+            (agents, md)
         };
 
         // If modelId is specified, verify it exists before starting the chat
-        // Otherwise, CLI will use a default model when starting chat
+        // Otherwise, CLI will use the model of the agent or a default model when starting chat
         let (models, default_model_opt) = get_available_models(os).await?;
+        let default_model_id = || Some(default_model(os, &models, &default_model_opt).model_id);
+        let agent_model = agents
+            .get_active()
+            .and_then(|agent| Some((agent.name.as_str(), agent.model.as_deref()?)));
         let model_id: Option<String> = if let Some(requested) = self.model.as_ref() {
             if let Some(m) = find_model(&models, requested) {
                 Some(m.model_id.clone())
@@ -352,12 +540,29 @@ impl ChatArgs {
                     .join(", ");
                 bail!("Model '{}' does not exist. Available models: {}", requested, available);
             }
-        } else if let Some(saved) = os.database.settings.get_string(Setting::ChatDefaultModel) {
-            find_model(&models, &saved)
-                .map(|m| m.model_id.clone())
-                .or(Some(default_model_opt.model_id.clone()))
+        } else if let Some((agent_name, requested)) = agent_model {
+            match find_model(&models, requested) {
+                Some(m) => Some(m.model_id.clone()),
+                None => {
+                    execute!(
+                        stderr,
+                        style::SetForegroundColor(Color::Yellow),
+                        style::Print("WARNING: "),
+                        style::SetForegroundColor(Color::Reset),
+                        style::Print(format!(
+                            "Model '{requested}' of agent {agent_name} is not available, using the default model instead\n"
+                        )),
+                    )?;
+                    default_model_id()
+                },
+            }
         } else {
-            Some(default_model_opt.model_id.clone())
+            default_model_id()
+        };
+
+        let prompt_variables = match agents.get_active() {
+            Some(agent) => collect_prompt_variables(agent, self.vars, !self.no_interactive)?,
+            None => HashMap::from_iter(self.vars),
         };
 
         let (prompt_request_sender, prompt_request_receiver) = tokio::sync::broadcast::channel::<PromptQuery>(5);
@@ -374,7 +579,7 @@ impl ChatArgs {
             .await?;
         let tool_config = tool_manager.load_tools(os, &mut stderr).await?;
 
-        ChatSession::new(
+        let mut session = ChatSession::new(
             os,
             stdout,
             stderr,
@@ -391,10 +596,67 @@ impl ChatArgs {
             mcp_enabled,
         )
         .await?
-        .spawn(os)
-        .await
-        .map(|_| ExitCode::SUCCESS)
+        .with_agents_load_metadata(agents_load_metadata)
+        .with_prompt_variables(prompt_variables)
+        .with_output_format(self.output)
+        .with_attachments(&attachments)
+        .with_event_log(event_log);
+        if let Some(paused) = paused {
+            session.restore_pause(os, paused).await?;
+        }
+
+        session.log_session_start();
+        let result = session.spawn(os).await;
+        session.log_session_end();
+        if let Some(output) = session.take_turn_output(result.as_ref().err()) {
+            writeln!(std::io::stdout(), "{}", serde_json::to_string_pretty(&output)?)?;
+        }
+        result?;
+        Ok(session.exit_code())
+    }
+}
+
+/// Parses a `--var` argument written as `NAME=VALUE`.
+fn parse_prompt_variable(arg: &str) -> Result<(String, String), String> {
+    match arg.split_once('=') {
+        Some((name, value)) if !name.is_empty() => Ok((name.to_string(), value.to_string())),
+        _ => Err(format!("expected NAME=VALUE, found '{arg}'")),
+    }
+}
+
+/// Collects the values of the `{{variable}}` placeholders in the prompt of `agent`. Values given
+/// with `--var` take precedence over the defaults of the agent, and the variables that have
+/// neither are asked for, or fail the session when it isn't interactive.
+fn collect_prompt_variables(
+    agent: &Agent,
+    vars: Vec<(String, String)>,
+    interactive: bool,
+) -> Result<HashMap<String, String>> {
+    let mut values = HashMap::from_iter(vars);
+    let missing = agent
+        .prompt_variable_names()
+        .into_iter()
+        .filter(|name| !values.contains_key(name) && !agent.prompt_variables.contains_key(name))
+        .collect::<Vec<_>>();
+    if missing.is_empty() {
+        return Ok(values);
+    }
+    if !interactive {
+        bail!(
+            "The prompt of agent {} needs a value for {}. Pass it with --var NAME=VALUE or set a default in promptVariables",
+            agent.name,
+            missing.join(", ")
+        );
+    }
+
+    let theme = crate::util::dialoguer_theme();
+    for name in missing {
+        let value = dialoguer::Input::<String>::with_theme(&theme)
+            .with_prompt(format!("Value of {{{{{name}}}}} for agent {}", agent.name))
+            .interact_text()?;
+        values.insert(name, value);
     }
+    Ok(values)
 }
 
 const WELCOME_TEXT: &str = color_print::cstr! {"<cyan!>
@@ -455,10 +717,32 @@ const SMALL_SCREEN_POPULAR_SHORTCUTS: &str = color_print::cstr! {"<black!><green
 </black!>"};
 
 const RESPONSE_TIMEOUT_CONTENT: &str = "Response timed out - message took too long to generate";
+
+/// Maximum number of completed user turns retained for `/usage --perf`.
+const MAX_TURN_PERF_HISTORY: usize = 20;
+
+/// Default percentage of the context window at which history is compacted before sending a message.
+const DEFAULT_AUTO_COMPACT_THRESHOLD: usize = 90;
+
+/// Default percentage of the context window at which running /compact is suggested.
+const DEFAULT_CONTEXT_WARNING_THRESHOLD: usize = 80;
+
+/// Default number of identical tool calls in a row after which the user is asked whether to
+/// continue.
+const DEFAULT_TOOL_LOOP_THRESHOLD: usize = 5;
+
+/// Default number of model turns a delegated agent may take before its task is returned.
+const DEFAULT_DELEGATION_MAX_TURNS: usize = 10;
+
+/// Maximum number of tasks delegated with the agent_delegate tool that can be in progress at once.
+const MAX_DELEGATION_DEPTH: usize = 3;
+
 const TRUST_ALL_TEXT: &str = color_print::cstr! {"<green!>All tools are now trusted (<red!>!</red!>). Amazon Q will execute tools <bold>without</bold> asking for confirmation.\
 \nAgents can sometimes do unexpected things so understand the risks.</green!>
 \nLearn more at https://docs.aws.amazon.com/amazonq/latest/qdeveloper-ug/command-line-chat-security.html#command-line-chat-trustall-safety"};
 
+const NO_TOOLS_TEXT: &str = color_print::cstr! {"<yellow!>Tools are off for this session. Amazon Q will respond with text only and cannot read files or run commands.</yellow!>"};
+
 const TOOL_BULLET: &str = " ● ";
 const CONTINUATION_LINE: &str = " ⋮ ";
 const PURPOSE_ARROW: &str = " ↳ ";
@@ -504,9 +788,141 @@ pub enum ChatError {
     CompactHistoryFailure,
     #[error("Failed to swap to agent: {0}")]
     AgentSwapError(eyre::Report),
+    #[error("The {trigger} hook '{command}' failed: {error}")]
+    HookBlocked {
+        trigger: HookTrigger,
+        command: String,
+        error: String,
+    },
+    /// A failure with a known cause, carrying a short message for the user and optionally a hint
+    /// on how to resolve it.
+    #[error("{message}")]
+    Detailed {
+        kind: ChatErrorKind,
+        message: Cow<'static, str>,
+        hint: Option<Cow<'static, str>>,
+    },
+}
+
+/// Machine readable classification of a [ChatError].
+///
+/// The kind decides the remediation hint shown to the user, the exit code used in
+/// non-interactive mode, and the reason recorded in telemetry.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, strum::AsRefStr)]
+pub enum ChatErrorKind {
+    /// Unexpected failure with no more specific classification.
+    Internal,
+    /// Missing, invalid, or expired credentials.
+    Auth,
+    /// The backend failed to handle the request.
+    Service,
+    /// Request or usage limits were reached.
+    RateLimited,
+    /// The conversation no longer fits within the model's context window.
+    ContextOverflow,
+    /// The requested agent doesn't exist or couldn't be loaded.
+    Agent,
+    /// An MCP server failed to start, respond, or serve a prompt.
+    McpServer,
+    /// A tool required approval but the session couldn't ask for it.
+    ToolApprovalRequired,
+    /// Reading from or writing to the terminal or file system failed.
+    Io,
+    /// The user interrupted the operation.
+    Interrupted,
+}
+
+impl ChatErrorKind {
+    /// Exit code used when a non-interactive session fails with this kind of error. Keep in sync
+    /// with [CHAT_EXIT_CODES_HELP].
+    pub fn exit_code(self) -> u8 {
+        match self {
+            Self::Internal | Self::Io => 1,
+            Self::Auth => 3,
+            Self::ToolApprovalRequired => 4,
+            Self::RateLimited => 5,
+            Self::ContextOverflow => 6,
+            Self::Agent => 7,
+            Self::McpServer => 8,
+            Self::Service => 9,
+            Self::Interrupted => 130,
+        }
+    }
+
+    fn from_api_client_error(err: &ApiClientError) -> Self {
+        match err {
+            ApiClientError::AuthError(_) | ApiClientError::Credentials(_) => Self::Auth,
+            ApiClientError::QuotaBreach { .. } | ApiClientError::MonthlyLimitReached { .. } => Self::RateLimited,
+            ApiClientError::ContextWindowOverflow { .. } => Self::ContextOverflow,
+            _ => Self::Service,
+        }
+    }
 }
 
+pub const CHAT_EXIT_CODES_HELP: &str = color_print::cstr! {"<em>Exit codes (with --no-interactive):</em>
+  0    Success
+  1    Unexpected or I/O error
+  3    Authentication failed or credentials expired
+  4    A tool required approval
+  5    Request or usage limit reached
+  6    Conversation exceeded the context window
+  7    Agent could not be found or loaded
+  8    MCP server failure
+  9    Amazon Q service error
+  130  Interrupted
+
+<em>Scripting:</em>
+  <em>q chat --prompt "..." --output json</em> runs a single turn, auto-approving only tools the agent trusts, and
+  prints a JSON object with <em>response</em>, <em>tools_used</em>, <em>usage</em> and, on failure, <em>error</em> to stdout."};
+
 impl ChatError {
+    pub fn with_hint(
+        kind: ChatErrorKind,
+        message: impl Into<Cow<'static, str>>,
+        hint: impl Into<Cow<'static, str>>,
+    ) -> Self {
+        Self::Detailed {
+            kind,
+            message: message.into(),
+            hint: Some(hint.into()),
+        }
+    }
+
+    pub fn kind(&self) -> ChatErrorKind {
+        match self {
+            ChatError::Client(e) => ChatErrorKind::from_api_client_error(e),
+            ChatError::Auth(_) => ChatErrorKind::Auth,
+            ChatError::SendMessage(e) => ChatErrorKind::from_api_client_error(&e.source),
+            ChatError::ResponseStream(_) => ChatErrorKind::Service,
+            ChatError::Std(_) => ChatErrorKind::Io,
+            ChatError::Readline(_) => ChatErrorKind::Io,
+            ChatError::Custom(_) => ChatErrorKind::Internal,
+            ChatError::Interrupted { .. } => ChatErrorKind::Interrupted,
+            ChatError::GetPromptError(_) => ChatErrorKind::McpServer,
+            ChatError::NonInteractiveToolApproval => ChatErrorKind::ToolApprovalRequired,
+            ChatError::CompactHistoryFailure => ChatErrorKind::ContextOverflow,
+            ChatError::AgentSwapError(_) => ChatErrorKind::Agent,
+            ChatError::HookBlocked { .. } => ChatErrorKind::Agent,
+            ChatError::Detailed { kind, .. } => *kind,
+        }
+    }
+
+    /// A suggestion on how the user can resolve the error, if there is one.
+    pub fn hint(&self) -> Option<Cow<'static, str>> {
+        match self {
+            ChatError::Detailed { hint, .. } => hint.clone(),
+            ChatError::AgentSwapError(_) => Some("Run /agent list to see the available agents".into()),
+            ChatError::HookBlocked { .. } => {
+                Some("Fix the hook, or set its on_failure to \"warn\" to continue without its output".into())
+            },
+            ChatError::GetPromptError(GetPromptError::PromptNotFound(_)) => {
+                Some("Run /prompts list to see the available prompts".into())
+            },
+            _ if self.kind() == ChatErrorKind::Auth => Some("Run `q login` to refresh your credentials".into()),
+            _ => None,
+        }
+    }
+
     fn status_code(&self) -> Option<u16> {
         match self {
             ChatError::Client(e) => e.status_code(),
@@ -521,6 +937,8 @@ impl ChatError {
             ChatError::NonInteractiveToolApproval => None,
             ChatError::CompactHistoryFailure => None,
             ChatError::AgentSwapError(_) => None,
+            ChatError::HookBlocked { .. } => None,
+            ChatError::Detailed { .. } => None,
         }
     }
 }
@@ -540,6 +958,8 @@ impl ReasonCode for ChatError {
             ChatError::NonInteractiveToolApproval => "NonInteractiveToolApproval".to_string(),
             ChatError::CompactHistoryFailure => "CompactHistoryFailure".to_string(),
             ChatError::AgentSwapError(_) => "AgentSwapError".to_string(),
+            ChatError::HookBlocked { .. } => "HookBlocked".to_string(),
+            ChatError::Detailed { kind, .. } => kind.as_ref().to_string(),
         }
     }
 }
@@ -564,9 +984,9 @@ impl From<parser::RecvError> for ChatError {
 
 pub struct ChatSession {
     /// For output read by humans and machine
-    pub stdout: std::io::Stdout,
+    pub stdout: Box<dyn Write + Send>,
     /// For display output, only read by humans
-    pub stderr: std::io::Stderr,
+    pub stderr: Box<dyn Write + Send>,
     initial_input: Option<String>,
     /// Whether we're starting a new conversation or continuing an old one.
     existing_conversation: bool,
@@ -587,6 +1007,18 @@ pub struct ChatSession {
     tool_turn_start_time: Option<Instant>,
     /// [RequestMetadata] about the ongoing operation.
     user_turn_request_metadata: Vec<RequestMetadata>,
+    /// [RequestMetadata] of previously completed user turns, used for `/usage --perf`. Only
+    /// recorded if the user has opted in to telemetry.
+    turn_perf_history: VecDeque<Vec<RequestMetadata>>,
+    /// Whether per-turn latency metrics should be retained in [Self::turn_perf_history].
+    record_turn_perf: bool,
+    /// Number of times a throttled or failed request was retried this session, shown in `/usage`.
+    throttle_retry_count: u32,
+    /// Whether the request being sent is a retry after the previous attempt overflowed the context
+    /// window. Such a request is only retried once.
+    retried_context_overflow: bool,
+    /// Turn, tool use, and token counts printed when the session exits.
+    session_stats: SessionStats,
     /// Telemetry events to be sent as part of the conversation. The HashMap key is tool_use_id.
     tool_use_telemetry_events: HashMap<String, ToolUseEventBuilder>,
     /// State used to keep track of tool use relation
@@ -595,6 +1027,39 @@ pub struct ChatSession {
     failed_request_ids: Vec<String>,
     /// Pending prompts to be sent
     pending_prompts: VecDeque<Prompt>,
+    /// Tool uses of the current turn, keyed by `(tool_name, tool_use_id)`, whose deny rules the
+    /// user has chosen to override. Overrides only ever apply to the single invocation they were
+    /// granted for, and are cleared whenever the model requests new tool uses.
+    denied_tool_overrides: HashSet<(String, String)>,
+    /// Files read by `fs_read` this session, used to warn before editing files changed externally.
+    file_reads: FileReadCache,
+    /// Tracks repeated identical tool calls, reset whenever the user sends a message.
+    tool_loop: ToolLoopDetector,
+    /// Context rules added with `/context add --watch`.
+    context_watcher: ContextWatcher,
+    /// How much tool output to show, set with `/tools output`. Keyed by the tool's name in
+    /// `toolsSettings` and taking precedence over the agent's `displayOutput` settings.
+    tool_display_overrides: HashMap<String, DisplayOutput>,
+    /// Estimated context window usage in percent, shown in the prompt. Updated once per turn so
+    /// that rendering the prompt does not need to measure the conversation.
+    context_usage: Option<usize>,
+    /// Whether /compact has been suggested since the context usage last crossed the threshold.
+    context_warning_shown: bool,
+    /// Whether history has been compacted since the conversation last crossed
+    /// `chat.autoCompactThreshold`.
+    auto_compacted: bool,
+    /// Tasks delegated with the agent_delegate tool that are in progress, innermost last.
+    delegations: Vec<Delegation>,
+    /// What happened while loading agents at startup, shown by `/agent status`.
+    agents_load_metadata: Option<AgentsLoadMetadata>,
+    /// Kind of the error that ended a non-interactive session, used to pick the exit code.
+    failure_kind: Option<ChatErrorKind>,
+    /// Collected instead of printing to stdout when running with [OutputFormat::Json].
+    turn_output: Option<TurnOutput>,
+    /// Receives what happens during a turn when the session is driven by a [engine::ChatEngine].
+    event_sink: Box<dyn EventSink>,
+    /// Trace of the session written with `--event-log`.
+    event_log: Option<EventLog>,
     interactive: bool,
     inner: Option<ChatState>,
     ctrlc_rx: broadcast::Receiver<()>,
@@ -689,8 +1154,8 @@ impl ChatSession {
         });
 
         Ok(Self {
-            stdout,
-            stderr,
+            stdout: Box::new(stdout),
+            stderr: Box::new(stderr),
             initial_input: input,
             existing_conversation,
             input_source,
@@ -699,12 +1164,31 @@ impl ChatSession {
             conversation,
             tool_uses: vec![],
             user_turn_request_metadata: vec![],
+            turn_perf_history: VecDeque::new(),
+            record_turn_perf: is_telemetry_enabled(&os.env, &os.database),
+            throttle_retry_count: 0,
+            retried_context_overflow: false,
+            session_stats: SessionStats::default(),
             pending_tool_index: None,
             tool_turn_start_time: None,
             tool_use_telemetry_events: HashMap::new(),
             tool_use_status: ToolUseStatus::Idle,
             failed_request_ids: Vec::new(),
             pending_prompts: VecDeque::new(),
+            denied_tool_overrides: HashSet::new(),
+            file_reads: FileReadCache::default(),
+            tool_loop: ToolLoopDetector::default(),
+            context_watcher: ContextWatcher::default(),
+            tool_display_overrides: HashMap::new(),
+            context_usage: None,
+            context_warning_shown: false,
+            auto_compacted: false,
+            delegations: Vec::new(),
+            agents_load_metadata: None,
+            failure_kind: None,
+            turn_output: None,
+            event_sink: Box::new(|_: ChatEvent| {}),
+            event_log: None,
             interactive,
             inner: Some(ChatState::default()),
             ctrlc_rx,
@@ -745,6 +1229,10 @@ impl ChatSession {
                 // compact_history manages ctrl+c handling
                 self.compact_history(os, prompt, show_summary, strategy).await
             },
+            ChatState::MergeTangent => {
+                // merge_tangent manages ctrl+c handling
+                self.merge_tangent(os).await
+            },
             ChatState::ExecuteTools => {
                 let tool_uses_clone = self.tool_uses.clone();
                 tokio::select! {
@@ -795,9 +1283,26 @@ impl ChatSession {
 
         // We encountered an error. Handle it.
         error!(?err, "An error occurred processing the current state");
-        let (reason, reason_desc) = get_error_reason(&err);
-        self.send_error_telemetry(os, reason, Some(reason_desc), err.status_code())
-            .await;
+        let kind = err.kind();
+        let hint = err.hint();
+        let message = err.to_string();
+        self.event_sink.emit(ChatEvent::Error {
+            kind,
+            message: message.clone(),
+        });
+        self.log_event(Event::Error {
+            kind: kind.as_ref().to_string(),
+            message: message.clone(),
+        });
+        // Record the kind rather than the error message, which may contain user data such as
+        // file paths.
+        self.send_error_telemetry(
+            os,
+            err.reason_code(),
+            Some(kind.as_ref().to_string()),
+            err.status_code(),
+        )
+        .await;
 
         if self.spinner.is_some() {
             drop(self.spinner.take());
@@ -808,7 +1313,21 @@ impl ChatSession {
             )?;
         }
 
+        // A blocking agentSpawn hook failed, the session can't start without it
+        let exit = matches!(err, ChatError::HookBlocked {
+            trigger: HookTrigger::AgentSpawn,
+            ..
+        });
+
         let (context, report, display_err_message) = match err {
+            ChatError::HookBlocked { trigger, .. } => (
+                match trigger {
+                    HookTrigger::AgentSpawn => "The session was not started",
+                    HookTrigger::UserPromptSubmit => "The prompt was not sent",
+                },
+                Report::from(err),
+                true,
+            ),
             ChatError::Interrupted { tool_uses: ref inter } => {
                 execute!(self.stderr, style::Print("\n\n"))?;
 
@@ -858,14 +1377,45 @@ impl ChatSession {
             },
             ChatError::SendMessage(err) => match err.source {
                 // Errors from attempting to send too large of a conversation history. In
-                // this case, attempt to automatically compact the history for the user.
+                // this case, attempt to automatically make room in the context window and retry
+                // the request once. If auto compaction is disabled, the oldest turns are dropped
+                // instead of summarized.
                 ApiClientError::ContextWindowOverflow { .. } => {
-                    if os
+                    let retried = std::mem::take(&mut self.retried_context_overflow);
+                    let auto_compaction = !os
                         .database
                         .settings
                         .get_bool(Setting::ChatDisableAutoCompaction)
-                        .unwrap_or(false)
-                    {
+                        .unwrap_or(false);
+                    let dropped = match (retried, auto_compaction) {
+                        (false, false) if self.conversation.next_user_message().is_some() => {
+                            self.conversation.drop_oldest_turns()
+                        },
+                        _ => 0,
+                    };
+                    if dropped > 0 {
+                        self.retried_context_overflow = true;
+                        self.log_event(Event::Compaction {
+                            strategy: CompactionStrategy::DropOldest,
+                            messages: self.conversation.history().len() + dropped,
+                        });
+                        execute!(
+                            self.stdout,
+                            style::SetForegroundColor(Color::Yellow),
+                            style::Print(format!(
+                                "The context window has overflowed, dropping the {dropped} oldest turns from the history and retrying..."
+                            )),
+                            style::SetAttribute(Attribute::Reset),
+                            style::Print("\n\n"),
+                        )?;
+                        self.inner = Some(ChatState::HandleResponseStream(
+                            self.conversation
+                                .as_sendable_conversation_state(os, &mut self.stderr, false)
+                                .await?,
+                        ));
+
+                        return Ok(());
+                    } else if retried || !auto_compaction {
                         execute!(
                             self.stderr,
                             style::SetForegroundColor(Color::Red),
@@ -877,6 +1427,7 @@ impl ChatSession {
                         )?;
                         ("The conversation history has overflowed", eyre!(err), false)
                     } else {
+                        self.retried_context_overflow = true;
                         self.inner = Some(ChatState::CompactHistory {
                             prompt: None,
                             show_summary: false,
@@ -912,7 +1463,10 @@ impl ChatSession {
                         self.stderr,
                         style::SetAttribute(Attribute::Bold),
                         style::SetForegroundColor(Color::Red),
-                        style::Print(" ⚠️  Amazon Q rate limit reached:\n"),
+                        style::Print(format!(
+                            " {}Amazon Q rate limit reached:\n",
+                            glyph(plain_output(os), "⚠️  ", "")
+                        )),
                         style::Print(format!("    {}\n\n", err.clone())),
                         style::SetAttribute(Attribute::Reset),
                         style::SetForegroundColor(Color::Reset),
@@ -1013,6 +1567,10 @@ impl ChatSession {
                         )?;
                     }
 
+                    if !self.interactive {
+                        self.record_failure(kind, message);
+                    }
+                    self.abort_delegations(os).await?;
                     self.inner = Some(ChatState::PromptUser {
                         skip_printing_tools: false,
                     });
@@ -1047,21 +1605,38 @@ impl ChatSession {
             queue!(self.stderr, style::Print(&text),)?;
             self.conversation.append_transcript(text);
 
-            execute!(
+            queue!(
                 self.stderr,
                 style::SetAttribute(Attribute::Reset),
                 style::SetForegroundColor(Color::Reset),
             )?;
+            if let Some(hint) = &hint {
+                queue!(
+                    self.stderr,
+                    style::SetForegroundColor(Color::DarkGrey),
+                    style::Print(format!("{hint}\n")),
+                    style::SetForegroundColor(Color::Reset),
+                )?;
+            }
+            self.stderr.flush()?;
         }
 
+        if !self.interactive {
+            self.record_failure(kind, message);
+        }
+
+        self.abort_delegations(os).await?;
         self.conversation.enforce_conversation_invariants();
         self.conversation.reset_next_user_message();
         self.pending_tool_index = None;
         self.tool_turn_start_time = None;
         self.reset_user_turn();
 
-        self.inner = Some(ChatState::PromptUser {
-            skip_printing_tools: false,
+        self.inner = Some(match exit {
+            true => ChatState::Exit,
+            false => ChatState::PromptUser {
+                skip_printing_tools: false,
+            },
         });
 
         Ok(())
@@ -1085,6 +1660,21 @@ impl Drop for ChatSession {
     }
 }
 
+/// A task handed to another agent with the agent_delegate tool, see
+/// [ChatSession::start_delegation].
+#[derive(Debug)]
+struct Delegation {
+    /// Agent that delegated the task, restored once the task is done
+    parent_agent: String,
+    /// The agent_delegate tool use that the final response of the agent is returned for
+    tool_use_id: String,
+    /// Results of the tools used alongside agent_delegate, returned together with its result
+    tool_results: Vec<ToolUseResult>,
+    images: Vec<RichImageBlock>,
+    /// Model turns the agent has taken so far
+    turns: usize,
+}
+
 /// The chat execution state.
 ///
 /// Intended to provide more robust handling around state transitions while dealing with, e.g.,
@@ -1115,6 +1705,8 @@ pub enum ChatState {
         /// Parameters for how to perform the compaction request.
         strategy: CompactStrategy,
     },
+    /// Leave tangent mode, adding a summary of the tangent to the main conversation.
+    MergeTangent,
     /// Retry the current request if we encounter a model overloaded error.
     RetryModelOverload,
     /// Exit the chat.
@@ -1138,10 +1730,53 @@ impl ChatSession {
         request_metadata_lock: Arc<Mutex<Option<RequestMetadata>>>,
         message_meta_tags: Option<Vec<MessageMetaTag>>,
     ) -> Result<SendMessageStream, ChatError> {
-        match SendMessageStream::send_message(&os.client, conversation_state, request_metadata_lock, message_meta_tags)
-            .await
-        {
-            Ok(res) => Ok(res),
+        let retry_policy = ThrottleRetryPolicy::from_database(&os.database);
+        let interactive = self.interactive;
+        let plain = plain_output(os);
+        let spinner = &mut self.spinner;
+        let stderr = &mut self.stderr;
+        let (result, retries) = retry_policy
+            .retry(
+                |err: &parser::SendMessageError| ThrottleRetryPolicy::is_retryable(&err.source),
+                || {
+                    SendMessageStream::send_message(
+                        &os.client,
+                        conversation_state.clone(),
+                        request_metadata_lock.clone(),
+                        message_meta_tags.clone(),
+                    )
+                },
+                |status| {
+                    let msg = format!(
+                        "Throttled, retrying in {}s (attempt {}/{})",
+                        status.delay.as_secs_f64().ceil(),
+                        status.attempt,
+                        status.max_attempts
+                    );
+                    if interactive {
+                        if let Some(mut spinner) = spinner.take() {
+                            spinner.stop();
+                        }
+                        let _ = execute!(
+                            stderr,
+                            cursor::MoveToColumn(0),
+                            terminal::Clear(terminal::ClearType::CurrentLine)
+                        );
+                        *spinner = start_spinner(plain, Spinners::Dots, msg);
+                    } else {
+                        let _ = execute!(stderr, style::Print(format!("{msg}\n")));
+                    }
+                },
+            )
+            .await;
+        self.throttle_retry_count += retries;
+
+        match result {
+            Ok(res) => {
+                self.session_stats.model_turns += 1;
+                self.session_stats.input_tokens += TokenCount::from(conversation_state.char_count()).value();
+                Ok(res)
+            },
             Err(err) => {
                 let (reason, reason_desc) = get_error_reason(&err);
                 self.send_chat_telemetry(
@@ -1150,7 +1785,7 @@ impl ChatSession {
                     Some(reason),
                     Some(reason_desc),
                     err.status_code(),
-                    true, // We never retry failed requests, so this always ends the current turn.
+                    true, // Any retries have been exhausted by now, so this always ends the current turn.
                 )
                 .await;
                 Err(err.into())
@@ -1158,6 +1793,142 @@ impl ChatSession {
         }
     }
 
+    /// Records the result of the [Agents::load] call that produced this session's agents.
+    pub fn with_agents_load_metadata(mut self, metadata: AgentsLoadMetadata) -> Self {
+        self.agents_load_metadata = Some(metadata);
+        self
+    }
+
+    /// Values of the `{{variable}}` placeholders in the agent prompt, see
+    /// [ConversationState::prompt_variables]. Values restored from a resumed conversation are kept
+    /// unless given again.
+    pub fn with_prompt_variables(mut self, values: HashMap<String, String>) -> Self {
+        self.conversation.prompt_variables.extend(values);
+        self
+    }
+
+    /// With [OutputFormat::Json], everything the session would print to stdout is discarded and
+    /// a [TurnOutput] is collected instead, see [Self::take_turn_output].
+    pub fn with_output_format(mut self, format: OutputFormat) -> Self {
+        if format == OutputFormat::Json {
+            self.stdout = Box::new(NullWriter);
+            self.turn_output = Some(TurnOutput::default());
+        }
+        self
+    }
+
+    /// Reports what happens during a turn to `sink` instead of printing it. Used by
+    /// [engine::ChatEngine], which drives the session without a terminal.
+    pub fn with_event_sink(mut self, sink: Box<dyn EventSink>) -> Self {
+        self.stdout = Box::new(NullWriter);
+        self.stderr = Box::new(NullWriter);
+        self.event_sink = sink;
+        self
+    }
+
+    /// Writes a trace of the session to `event_log`.
+    pub fn with_event_log(mut self, event_log: Option<EventLog>) -> Self {
+        self.event_log = event_log;
+        self
+    }
+
+    /// Reports `attachments` in the [TurnOutput], if one is collected.
+    pub fn with_attachments(mut self, attachments: &[Attachment]) -> Self {
+        if let Some(output) = self.turn_output.as_mut() {
+            output.attachments = attachments.iter().map(AttachmentRecord::from).collect();
+        }
+        self
+    }
+
+    /// Exit code for the process once the session has finished. Non-interactive sessions that
+    /// ended with an error report its [ChatErrorKind::exit_code].
+    fn exit_code(&self) -> ExitCode {
+        match self.failure_kind {
+            Some(kind) => ExitCode::from(kind.exit_code()),
+            None => ExitCode::SUCCESS,
+        }
+    }
+
+    /// Records the error that ended a non-interactive session.
+    fn record_failure(&mut self, kind: ChatErrorKind, message: String) {
+        self.failure_kind = Some(kind);
+        if let Some(output) = self.turn_output.as_mut() {
+            output.error = Some(TurnError::new(kind, message));
+        }
+    }
+
+    /// Returns the [TurnOutput] collected when running with [OutputFormat::Json]. `err` is the
+    /// error the session was ended by, if it was not already handled by [Self::next].
+    fn take_turn_output(&mut self, err: Option<&Report>) -> Option<TurnOutput> {
+        let mut output = self.turn_output.take()?;
+        output.usage = Usage::from(&self.session_stats);
+        if let (None, Some(err)) = (&output.error, err) {
+            let kind = err
+                .downcast_ref::<ChatError>()
+                .map_or(ChatErrorKind::Internal, ChatError::kind);
+            output.error = Some(TurnError::new(kind, err.to_string()));
+        }
+        Some(output)
+    }
+
+    fn log_event(&mut self, event: Event) {
+        if let Some(event_log) = self.event_log.as_mut() {
+            event_log.write(event);
+        }
+    }
+
+    fn log_session_start(&mut self) {
+        let clients = &self.conversation.tool_manager.clients;
+        let mut mcp_servers = clients.keys().cloned().collect::<Vec<_>>();
+        mcp_servers.sort();
+        self.log_event(Event::SessionStart {
+            agent: self.conversation.agents.get_active().map(|agent| agent.name.clone()),
+            model: self.conversation.model_info.as_ref().map(|info| info.model_id.clone()),
+            mcp_servers,
+        });
+    }
+
+    /// Logs that the tool use at `index` was approved. `trusted` is whether it was allowed without
+    /// asking the user.
+    fn log_tool_approved(&mut self, index: usize, trusted: bool) {
+        let tool = &self.tool_uses[index];
+        let event = Event::ToolApproved {
+            id: tool.id.clone(),
+            name: tool.name.clone(),
+            trusted,
+        };
+        self.log_event(event);
+    }
+
+    fn log_tool_denied(&mut self, index: usize, reason: DenyReason) {
+        let tool = &self.tool_uses[index];
+        let event = Event::ToolDenied {
+            id: tool.id.clone(),
+            name: tool.name.clone(),
+            reason,
+        };
+        self.log_event(event);
+    }
+
+    fn log_session_end(&mut self) {
+        self.log_event(Event::SessionEnd {
+            model_turns: self.session_stats.model_turns,
+            tool_invocations: self.session_stats.tool_invocations.values().sum(),
+            input_tokens: self.session_stats.input_tokens,
+            output_tokens: self.session_stats.output_tokens,
+        });
+    }
+
+    /// The tool use waiting for the user to approve it, if any.
+    fn pending_tool_request(&self) -> Option<ToolRequest> {
+        let tool = self.tool_uses.get(self.pending_tool_index?)?;
+        Some(ToolRequest {
+            id: tool.id.clone(),
+            name: tool.name.clone(),
+            args: tool.args.clone(),
+        })
+    }
+
     async fn spawn(&mut self, os: &mut Os) -> Result<()> {
         let is_small_screen = self.terminal_width() < GREETING_BREAK_POINT;
         if os
@@ -1181,7 +1952,7 @@ impl ChatSession {
                 // If the screen is small, print the tip in a single line
                 execute!(
                     self.stderr,
-                    style::Print("💡 ".to_string()),
+                    style::Print(glyph(plain_output(os), "💡 ", "Tip: ")),
                     style::Print(tip),
                     style::Print("\n")
                 )?;
@@ -1222,6 +1993,16 @@ impl ChatSession {
             )?;
         }
 
+        if self.conversation.agents.tools_disabled {
+            queue!(
+                self.stderr,
+                style::Print(format!(
+                    "{}{NO_TOOLS_TEXT}\n\n",
+                    if !is_small_screen { "\n" } else { "" }
+                ))
+            )?;
+        }
+
         if let Some(agent) = self.conversation.agents.get_active() {
             agent.print_overridden_permissions(&mut self.stderr)?;
         }
@@ -1235,7 +2016,11 @@ impl ChatSession {
                 execute!(
                     self.stderr,
                     style::SetForegroundColor(Color::Cyan),
-                    style::Print(format!("🤖 You are chatting with {}\n", display_name)),
+                    style::Print(format!(
+                        "{}You are chatting with {}\n",
+                        glyph(plain_output(os), "🤖 ", ""),
+                        display_name
+                    )),
                     style::SetForegroundColor(Color::Reset),
                     style::Print("\n")
                 )?;
@@ -1249,6 +2034,26 @@ impl ChatSession {
         while !matches!(self.inner, Some(ChatState::Exit)) {
             self.next(os).await?;
         }
+        self.context_watcher.clear();
+        self.conversation.tool_manager.shutdown().await;
+
+        if self.interactive
+            && !self.conversation.history().is_empty()
+            && os.database.settings.get_bool(Setting::ChatSaveOnExit).unwrap_or(false)
+        {
+            cli::persist::save_on_exit(os, self).await?;
+        }
+
+        // Only shown for interactive sessions so that scripted output is left untouched.
+        if self.interactive
+            && os
+                .database
+                .settings
+                .get_bool(Setting::ChatShowSessionSummary)
+                .unwrap_or(true)
+        {
+            self.session_stats.print(&mut self.stderr)?;
+        }
 
         Ok(())
     }
@@ -1340,7 +2145,7 @@ impl ChatSession {
 
         if self.interactive {
             execute!(self.stderr, cursor::Hide, style::Print("\n"))?;
-            self.spinner = Some(Spinner::new(Spinners::Dots, "Creating summary...".to_string()));
+            self.spinner = start_spinner(plain_output(os), Spinners::Dots, "Creating summary...".to_string());
         }
 
         let mut response = match self
@@ -1467,6 +2272,10 @@ impl ChatSession {
             )?;
         }
 
+        self.log_event(Event::Compaction {
+            strategy: CompactionStrategy::Summary,
+            messages: self.conversation.history().len(),
+        });
         self.conversation
             .replace_history_with_summary(summary.clone(), strategy, request_metadata);
 
@@ -1482,7 +2291,8 @@ impl ChatSession {
             execute!(
                 self.stderr,
                 style::SetForegroundColor(Color::Green),
-                style::Print("✔ Conversation history has been compacted successfully!\n\n"),
+                style::Print(glyph(plain_output(os), "✔ ", "")),
+                style::Print("Conversation history has been compacted successfully!\n\n"),
                 style::SetForegroundColor(Color::DarkGrey)
             )?;
 
@@ -1547,14 +2357,171 @@ impl ChatSession {
         }
     }
 
-    /// Generates a custom agent configuration (system prompt and tool config) based on user input.
-    /// Uses an LLM to create the agent specifications from the provided name and description.
-    async fn generate_agent_config(
+    /// Leaves tangent mode, first asking the model for a summary of the tangent and adding it to
+    /// the main conversation.
+    ///
+    /// Pressing ctrl+c while the summary is generated skips it and restores the main conversation
+    /// unchanged. If the request fails, the session stays in tangent mode.
+    async fn merge_tangent(&mut self, os: &mut Os) -> Result<ChatState, ChatError> {
+        let summary = match self.conversation.create_tangent_summary_request() {
+            Some(summary_state) => {
+                // Same pattern as compact_history for handling ctrl+c interruption
+                let request_metadata: Arc<Mutex<Option<RequestMetadata>>> = Arc::new(Mutex::new(None));
+                let request_metadata_clone = Arc::clone(&request_metadata);
+                let mut ctrl_c_stream = self.ctrlc_rx.resubscribe();
+
+                tokio::select! {
+                    res = self.summarize_tangent_impl(os, summary_state, request_metadata_clone) => Some(res?),
+                    Ok(_) = ctrl_c_stream.recv() => {
+                        debug!(?request_metadata, "ctrlc received in tangent summary");
+                        // Wait for handle_response to finish handling the ctrlc.
+                        tokio::time::sleep(Duration::from_millis(5)).await;
+                        if let Some(request_metadata) = request_metadata.lock().await.take() {
+                            self.user_turn_request_metadata.push(request_metadata);
+                        }
+                        self.send_chat_telemetry(os, TelemetryResult::Cancelled, None, None, None, true)
+                            .await;
+
+                        if self.spinner.is_some() {
+                            drop(self.spinner.take());
+                        }
+                        execute!(
+                            self.stderr,
+                            terminal::Clear(terminal::ClearType::CurrentLine),
+                            cursor::MoveToColumn(0),
+                            cursor::Show,
+                            style::SetForegroundColor(Color::Yellow),
+                            style::Print("Skipped the tangent summary.\n"),
+                            style::SetForegroundColor(Color::Reset)
+                        )?;
+                        None
+                    }
+                }
+            },
+            None => None,
+        };
+
+        cli::tangent::exit_tangent_mode(os, self, summary).await?;
+
+        Ok(ChatState::PromptUser {
+            skip_printing_tools: false,
+        })
+    }
+
+    async fn summarize_tangent_impl(
         &mut self,
         os: &mut Os,
-        agent_name: &str,
-        agent_description: &str,
-        selected_servers: &str,
+        summary_state: api_client::model::ConversationState,
+        request_metadata_lock: Arc<Mutex<Option<RequestMetadata>>>,
+    ) -> Result<String, ChatError> {
+        let input_tokens = TokenCount::from(summary_state.char_count()).value();
+
+        if self.interactive {
+            execute!(self.stderr, cursor::Hide, style::Print("\n"))?;
+            self.spinner = start_spinner(plain_output(os), Spinners::Dots, "Summarizing tangent...".to_string());
+        }
+
+        let response = self
+            .send_message(
+                os,
+                summary_state,
+                request_metadata_lock,
+                Some(vec![MessageMetaTag::TangentMode]),
+            )
+            .await;
+        let mut response = match response {
+            Ok(res) => res,
+            Err(err) => {
+                if self.interactive {
+                    self.spinner.take();
+                    execute!(
+                        self.stderr,
+                        terminal::Clear(terminal::ClearType::CurrentLine),
+                        cursor::MoveToColumn(0),
+                        cursor::Show,
+                        style::SetAttribute(Attribute::Reset)
+                    )?;
+                }
+                return Err(err);
+            },
+        };
+
+        let (summary, request_metadata) = loop {
+            match response.recv().await {
+                Some(Ok(parser::ResponseEvent::EndStream {
+                    message,
+                    request_metadata,
+                })) => {
+                    self.user_turn_request_metadata.push(request_metadata.clone());
+                    break (message.content().to_string(), request_metadata);
+                },
+                Some(Ok(_)) => (),
+                Some(Err(err)) => {
+                    if let Some(request_id) = &err.request_metadata.request_id {
+                        self.failed_request_ids.push(request_id.clone());
+                    };
+
+                    self.user_turn_request_metadata.push(err.request_metadata.clone());
+
+                    let (reason, reason_desc) = get_error_reason(&err);
+                    self.send_chat_telemetry(
+                        os,
+                        TelemetryResult::Failed,
+                        Some(reason),
+                        Some(reason_desc),
+                        err.status_code(),
+                        true,
+                    )
+                    .await;
+
+                    return Err(err.into());
+                },
+                None => {
+                    error!("response stream receiver closed before receiving a stop event");
+                    return Err(ChatError::Custom("Stream failed during tangent summary".into()));
+                },
+            }
+        };
+
+        let output_tokens = TokenCount::from(CharCount::from(request_metadata.response_size)).value();
+        self.session_stats.output_tokens += output_tokens;
+
+        if self.spinner.is_some() {
+            drop(self.spinner.take());
+            queue!(
+                self.stderr,
+                terminal::Clear(terminal::ClearType::CurrentLine),
+                cursor::MoveToColumn(0),
+                cursor::Show
+            )?;
+        }
+
+        self.send_chat_telemetry(os, TelemetryResult::Succeeded, None, None, None, true)
+            .await;
+
+        execute!(
+            self.stderr,
+            style::SetForegroundColor(Color::DarkGrey),
+            style::Print(format!(
+                "Summarized the tangent with ~{} tokens ({} in, {} out).\n",
+                input_tokens + output_tokens,
+                input_tokens,
+                output_tokens
+            )),
+            style::SetForegroundColor(Color::Reset)
+        )?;
+
+        Ok(summary)
+    }
+
+    /// Generates a custom agent configuration (system prompt and tool config) based on user input.
+    /// Uses an LLM to create the agent specifications from the provided name and description.
+    async fn generate_agent_config(
+        &mut self,
+        os: &mut Os,
+        agent_name: &str,
+        agent_description: &str,
+        selected_servers: &str,
         schema: &str,
         is_global: bool,
     ) -> Result<ChatState, ChatError> {
@@ -1635,10 +2602,11 @@ impl ChatSession {
 
         if self.interactive {
             execute!(self.stderr, cursor::Hide, style::Print("\n"))?;
-            self.spinner = Some(Spinner::new(
+            self.spinner = start_spinner(
+                plain_output(os),
                 Spinners::Dots,
                 format!("Generating agent config for '{}'...", agent_name),
-            ));
+            );
         }
 
         let mut response = match self
@@ -1720,7 +2688,8 @@ impl ChatSession {
                 execute!(
                     self.stderr,
                     style::SetForegroundColor(Color::Red),
-                    style::Print("✗ The LLM did not generate a valid agent configuration. Please try again.\n\n"),
+                    style::Print(glyph(plain_output(os), "✗ ", "Error: ")),
+                    style::Print("The LLM did not generate a valid agent configuration. Please try again.\n\n"),
                     style::SetForegroundColor(Color::Reset)
                 )?;
                 return Ok(ChatState::PromptUser {
@@ -1741,7 +2710,8 @@ impl ChatSession {
                 execute!(
                     self.stderr,
                     style::SetForegroundColor(Color::Red),
-                    style::Print(format!("✗ Invalid edited configuration: {}\n\n", err)),
+                    style::Print(glyph(plain_output(os), "✗ ", "Error: ")),
+                    style::Print(format!("Invalid edited configuration: {}\n\n", err)),
                     style::SetForegroundColor(Color::Reset)
                 )?;
                 return Ok(ChatState::PromptUser {
@@ -1755,7 +2725,8 @@ impl ChatSession {
             execute!(
                 self.stderr,
                 style::SetForegroundColor(Color::Red),
-                style::Print(format!("✗ Failed to save agent config: {}\n\n", err)),
+                style::Print(glyph(plain_output(os), "✗ ", "Error: ")),
+                style::Print(format!("Failed to save agent config: {}\n\n", err)),
                 style::SetForegroundColor(Color::Reset)
             )?;
             return Err(err);
@@ -1765,7 +2736,8 @@ impl ChatSession {
             self.stderr,
             style::SetForegroundColor(Color::Green),
             style::Print(format!(
-                "✓ Agent '{}' has been created and saved successfully!\n",
+                "{}Agent '{}' has been created and saved successfully!\n",
+                glyph(plain_output(os), "✓ ", ""),
                 agent_name
             )),
             style::SetForegroundColor(Color::Reset)
@@ -1779,6 +2751,7 @@ impl ChatSession {
     /// Read input from the user.
     async fn prompt_user(&mut self, os: &Os, skip_printing_tools: bool) -> Result<ChatState, ChatError> {
         execute!(self.stderr, cursor::Show)?;
+        self.retried_context_overflow = false;
 
         // Check token usage and display warnings if needed
         if self.pending_tool_index.is_none() {
@@ -1786,18 +2759,45 @@ impl ChatSession {
             if let Err(err) = self.display_char_warnings(os).await {
                 warn!("Failed to display character limit warnings: {}", err);
             }
+            if let Err(err) = self.update_context_gauge(os).await {
+                warn!("Failed to update the context usage gauge: {}", err);
+            }
+        }
+
+        for path in self.context_watcher.take_refreshed() {
+            queue!(
+                self.stderr,
+                style::SetForegroundColor(Color::DarkGrey),
+                style::Print(format!("[context refreshed: {}]\n", path)),
+                style::SetForegroundColor(Color::Reset),
+            )?;
         }
 
         let show_tool_use_confirmation_dialog = !skip_printing_tools && self.pending_tool_index.is_some();
         if show_tool_use_confirmation_dialog {
-            execute!(
+            let editable = self.pending_tool_is_editable();
+            queue!(
                 self.stderr,
                 style::SetForegroundColor(Color::DarkGrey),
                 style::Print("\nAllow this action? Use '"),
                 style::SetForegroundColor(Color::Green),
                 style::Print("t"),
                 style::SetForegroundColor(Color::DarkGrey),
-                style::Print("' to trust (always allow) this tool for the session. ["),
+                style::Print("' to trust (always allow) this tool for the session"),
+            )?;
+            if editable {
+                queue!(
+                    self.stderr,
+                    style::Print(", or '"),
+                    style::SetForegroundColor(Color::Green),
+                    style::Print("e"),
+                    style::SetForegroundColor(Color::DarkGrey),
+                    style::Print("' to edit the change before accepting it"),
+                )?;
+            }
+            queue!(
+                self.stderr,
+                style::Print(". ["),
                 style::SetForegroundColor(Color::Green),
                 style::Print("y"),
                 style::SetForegroundColor(Color::DarkGrey),
@@ -1809,6 +2809,18 @@ impl ChatSession {
                 style::SetForegroundColor(Color::Green),
                 style::Print("t"),
                 style::SetForegroundColor(Color::DarkGrey),
+            )?;
+            if editable {
+                queue!(
+                    self.stderr,
+                    style::Print("/"),
+                    style::SetForegroundColor(Color::Green),
+                    style::Print("e"),
+                    style::SetForegroundColor(Color::DarkGrey),
+                )?;
+            }
+            execute!(
+                self.stderr,
                 style::Print("]:\n\n"),
                 style::SetForegroundColor(Color::Reset),
             )?;
@@ -1891,6 +2903,7 @@ impl ChatSession {
                                 // from manually running /compact, without impacting behavior of
                                 // other slash commands.
                                 || matches!(chat_state, ChatState::CompactHistory { .. })
+                                || matches!(chat_state, ChatState::MergeTangent)
                             {
                                 return Ok(chat_state);
                             }
@@ -1917,6 +2930,30 @@ impl ChatSession {
                     writeln!(self.stderr)?;
                 },
                 Err(err) => {
+                    // Offer close matches for misspelled commands rather than clap's generic error.
+                    if err.kind() == clap::error::ErrorKind::InvalidSubcommand {
+                        if let Some(suggestion) = CommandSuggestion::from_args(&orig_args) {
+                            execute!(
+                                self.stderr,
+                                style::SetForegroundColor(Color::Red),
+                                style::Print(suggestion.message(&orig_args)),
+                                style::SetForegroundColor(Color::Reset),
+                                style::Print("\n"),
+                            )?;
+
+                            if let Some(corrected) = suggestion.corrected_input(&orig_args) {
+                                if self.prompt_run_suggested_command(&corrected)? {
+                                    return Ok(ChatState::HandleInput { input: corrected });
+                                }
+                            }
+
+                            writeln!(self.stderr)?;
+                            return Ok(ChatState::PromptUser {
+                                skip_printing_tools: false,
+                            });
+                        }
+                    }
+
                     // Replace the dummy name with a slash. Also have to check for an ansi sequence
                     // for invalid slash commands (e.g. on a "/doesntexist" input).
                     let ansi_output = err
@@ -2031,9 +3068,18 @@ impl ChatSession {
                         }
                     }
                     tool_use.accepted = true;
+                    self.log_tool_approved(index, false);
 
                     return Ok(ChatState::ExecuteTools);
                 }
+
+                if ["e", "E"].contains(&input) && self.pending_tool_is_editable() {
+                    if self.edit_pending_tool(os, index)? {
+                        return Ok(ChatState::ExecuteTools);
+                    }
+                    // Closing the editor without changes rejects the tool use, same as "n".
+                    user_input = "n".to_string();
+                }
             } else if !self.pending_prompts.is_empty() {
                 let prompts = self.pending_prompts.drain(0..).collect();
                 user_input = self
@@ -2045,6 +3091,14 @@ impl ChatSession {
             // Otherwise continue with normal chat on 'n' or other responses
             self.tool_use_status = ToolUseStatus::Idle;
 
+            if let Some(index) = self.pending_tool_index {
+                self.log_tool_denied(index, DenyReason::User);
+            }
+            self.log_event(Event::PromptSubmitted {
+                length: user_input.chars().count(),
+                text: Some(user_input.clone()),
+            });
+
             if self.pending_tool_index.is_some() {
                 // If the user just enters "n", replace the message we send to the model with
                 // something more substantial.
@@ -2061,8 +3115,27 @@ impl ChatSession {
                 self.conversation.set_next_user_message(user_input).await;
             }
 
+            self.tool_loop.reset();
             self.reset_user_turn();
 
+            if let Some(threshold) = self.auto_compact_threshold(os).await {
+                execute!(
+                    self.stderr,
+                    style::SetForegroundColor(Color::Yellow),
+                    style::Print(format!(
+                        "Conversation reached {threshold}% of the context window, summarizing the history...\n\n"
+                    )),
+                    style::SetForegroundColor(Color::Reset),
+                )?;
+
+                // The next user message is already set, so it is sent once compaction succeeds.
+                return Ok(ChatState::CompactHistory {
+                    prompt: None,
+                    show_summary: false,
+                    strategy: CompactStrategy::default(),
+                });
+            }
+
             let conv_state = self
                 .conversation
                 .as_sendable_conversation_state(os, &mut self.stderr, true)
@@ -2074,7 +3147,7 @@ impl ChatSession {
             queue!(self.stderr, cursor::Hide)?;
 
             if self.interactive {
-                self.spinner = Some(Spinner::new(Spinners::Dots, "Thinking...".to_owned()));
+                self.spinner = start_spinner(plain_output(os), Spinners::Dots, "Thinking...".to_owned());
             }
 
             Ok(ChatState::HandleResponseStream(conv_state))
@@ -2112,21 +3185,67 @@ impl ChatSession {
             }
 
             let mut denied_match_set = None::<Vec<String>>;
+            let is_overridden = self
+                .denied_tool_overrides
+                .contains(&(tool.name.clone(), tool.id.clone()));
             let allowed =
-                self.conversation
-                    .agents
-                    .get_active()
-                    .is_some_and(|a| match tool.tool.requires_acceptance(os, a) {
-                        PermissionEvalResult::Allow => true,
-                        PermissionEvalResult::Ask => false,
-                        PermissionEvalResult::Deny(matches) => {
-                            denied_match_set.replace(matches);
-                            false
-                        },
+                is_overridden
+                    || self.conversation.agents.get_active().is_some_and(|a| {
+                        match tool.tool.requires_acceptance(os, a) {
+                            PermissionEvalResult::Allow => true,
+                            PermissionEvalResult::Ask => false,
+                            PermissionEvalResult::Deny(matches) => {
+                                denied_match_set.replace(matches);
+                                false
+                            },
+                        }
                     })
                     || self.conversation.agents.trust_all_tools;
 
+            // Replacing text in a file that changed since the model last read it always needs
+            // confirmation, so that edits made in the user's editor are not silently clobbered.
+            let external_modification = match &tool.tool {
+                Tool::FsWrite(fs_write @ FsWrite::StrReplace { .. }) => {
+                    self.file_reads.external_modification(os, &fs_write.path(os)).await
+                },
+                Tool::FsWrite(FsWrite::Transaction { operations, .. }) => {
+                    let mut modification = None;
+                    for operation in operations.iter().filter(|op| matches!(op, FsWrite::StrReplace { .. })) {
+                        modification = self.file_reads.external_modification(os, &operation.path(os)).await;
+                        if modification.is_some() {
+                            break;
+                        }
+                    }
+                    modification
+                },
+                _ => None,
+            };
+            let outside_workspace = matches!(&tool.tool, Tool::FsWrite(fs_write) if fs_write.is_outside_workspace(os));
+
+            // Pause runaway agentic loops where the model keeps making the exact same call.
+            let loop_threshold = os
+                .database
+                .settings
+                .get_int_or(Setting::ChatToolLoopThreshold, DEFAULT_TOOL_LOOP_THRESHOLD);
+            let is_looping = loop_threshold > 0 && tool.consecutive_calls >= loop_threshold;
+            let consecutive_calls = tool.consecutive_calls;
+
+            let allowed = allowed && external_modification.is_none() && !is_looping;
+
             if let Some(match_set) = denied_match_set {
+                if self.interactive {
+                    let tool_name = tool.name.clone();
+                    let tool_use_id = tool.id.clone();
+                    if self.prompt_denied_tool_override(&tool_name, &match_set)? {
+                        // Evaluate the tool again with its deny rules lifted, so that the other
+                        // checks still apply to it.
+                        self.denied_tool_overrides.insert((tool_name, tool_use_id));
+                        return Ok(ChatState::ExecuteTools);
+                    }
+                }
+
+                self.log_tool_denied(i, DenyReason::DeniedList);
+                let tool = &self.tool_uses[i];
                 let formatted_set = match_set.into_iter().fold(String::new(), |mut acc, rule| {
                     acc.push_str(&format!("\n  - {rule}"));
                     acc
@@ -2165,6 +3284,42 @@ impl ChatSession {
             // TODO: Control flow is hacky here because of borrow rules
             let _ = tool;
             self.print_tool_description(os, i, allowed).await?;
+            if let Some(modification) = external_modification {
+                let modified: chrono::DateTime<chrono::Local> = modification.modified.into();
+                execute!(
+                    self.stderr,
+                    style::SetForegroundColor(Color::Yellow),
+                    style::Print(format!(
+                        "\nThis file was modified outside of this chat at {} ({} changed lines since it was last read).\n",
+                        modified.format("%Y-%m-%d %H:%M:%S"),
+                        modification.changed_lines
+                    )),
+                    style::SetForegroundColor(Color::Reset),
+                )?;
+            }
+            if outside_workspace && !allowed {
+                execute!(
+                    self.stderr,
+                    style::SetForegroundColor(Color::Yellow),
+                    style::Print(format!(
+                        "\nThis path is outside workspace root {}.\n",
+                        os.env.current_dir().unwrap_or_default().display()
+                    )),
+                    style::SetForegroundColor(Color::Reset),
+                )?;
+            }
+            if is_looping {
+                execute!(
+                    self.stderr,
+                    style::SetForegroundColor(Color::Yellow),
+                    style::Print(format!(
+                        "\nThis tool has been called {consecutive_calls} times in a row with the same arguments. Continue?\n"
+                    )),
+                    style::SetForegroundColor(Color::Reset),
+                )?;
+                // Count again from scratch if the user lets the model carry on.
+                self.tool_loop.reset();
+            }
             let tool = &mut self.tool_uses[i];
 
             if allowed {
@@ -2172,6 +3327,7 @@ impl ChatSession {
                 self.tool_use_telemetry_events
                     .entry(tool.id.clone())
                     .and_modify(|ev| ev.is_trusted = true);
+                self.log_tool_approved(i, !is_overridden);
                 continue;
             }
 
@@ -2186,7 +3342,32 @@ impl ChatSession {
         let mut tool_results = vec![];
         let mut image_blocks: Vec<RichImageBlock> = Vec::new();
 
+        // Delegating hands the conversation over to another agent, so it is only started once the
+        // other tools have run.
+        let delegation = self.tool_uses.iter().find_map(|tool| match &tool.tool {
+            Tool::AgentDelegate(delegate) => Some((tool.id.clone(), delegate.clone())),
+            _ => None,
+        });
+
         for tool in &self.tool_uses {
+            if let Tool::AgentDelegate(_) = &tool.tool {
+                if delegation.as_ref().is_some_and(|(id, _)| *id != tool.id) {
+                    tool_results.push(ToolUseResult {
+                        tool_use_id: tool.id.clone(),
+                        content: vec![
+                            ToolUseResultBlock::Text(
+                                "Only one task can be delegated at a time, delegate this task once the other one is done"
+                                    .to_string(),
+                            ),
+                            ToolUseResultBlock::Json(ToolErrorKind::InvalidInput.to_json()),
+                        ],
+                        status: ToolResultStatus::Error,
+                    });
+                }
+                continue;
+            }
+
+            self.session_stats.record_tool_invocation(&tool.name);
             let tool_start = std::time::Instant::now();
             let mut tool_telemetry = self.tool_use_telemetry_events.entry(tool.id.clone());
             tool_telemetry = tool_telemetry.and_modify(|ev| {
@@ -2205,15 +3386,23 @@ impl ChatSession {
                 }
             }
 
+            let settings_name = tool.tool.settings_name();
+            let display_output = match self.tool_display_overrides.get(&settings_name) {
+                Some(display_output) => *display_output,
+                None => DisplayOutput::from_settings(self.conversation.agents.get_active(), &settings_name),
+            };
+            let mut tool_stdout = DisplayWriter::new(&mut self.stdout, display_output);
             let invoke_result = tool
                 .tool
                 .invoke(
                     os,
-                    &mut self.stdout,
+                    &mut tool_stdout,
                     &mut self.conversation.file_line_tracker,
                     self.conversation.agents.get_active(),
+                    self.conversation.conversation_id(),
                 )
                 .await;
+            tool_stdout.finish()?;
 
             if self.spinner.is_some() {
                 queue!(
@@ -2237,11 +3426,35 @@ impl ChatSession {
                     // legacy fields previously implemented for only MCP tools
                     ev.custom_tool_call_latency = Some(tool_time.as_secs() as usize);
                     ev.input_token_size = Some(ct.get_input_token_size());
+                    ev.delegation_path = ct.delegation_path();
                 });
             }
+            let duration_ms = u64::try_from(tool_time.as_millis()).unwrap_or(u64::MAX);
             let tool_time = format!("{}.{}", tool_time.as_secs(), tool_time.subsec_millis());
             match invoke_result {
-                Ok(result) => {
+                Ok(mut result) => {
+                    match &tool.tool {
+                        Tool::FsRead(fs_read) => {
+                            for path in fs_read.read_paths(os) {
+                                self.file_reads.record_read(os, &path).await;
+                            }
+                        },
+                        Tool::FsWrite(fs_write) => {
+                            for path in fs_write.paths(os) {
+                                self.file_reads.record_write(os, &path).await;
+                            }
+                        },
+                        _ => {},
+                    }
+
+                    let sanitize_level = self
+                        .conversation
+                        .agents
+                        .get_active()
+                        .map(|agent| agent.sanitize_tool_output)
+                        .unwrap_or_default();
+                    let sanitize_findings = sanitize_tool_output(&mut result.output, sanitize_level);
+
                     match result.output {
                         OutputKind::Text(ref text) => {
                             debug!("Output is Text: {}", text);
@@ -2269,6 +3482,14 @@ impl ChatSession {
                         style::SetForegroundColor(Color::Reset),
                         style::Print("\n\n"),
                     )?;
+                    if !sanitize_findings.is_empty() {
+                        execute!(
+                            self.stderr,
+                            style::SetForegroundColor(Color::DarkGrey),
+                            style::Print(format!("{}\n\n", findings_notice(&sanitize_findings))),
+                            style::SetForegroundColor(Color::Reset),
+                        )?;
+                    }
 
                     tool_telemetry = tool_telemetry.and_modify(|ev| ev.is_success = Some(true));
                     if let Tool::Custom(_) = &tool.tool {
@@ -2278,33 +3499,67 @@ impl ChatSession {
 
                     // Send telemetry for agent contribution
                     if let Tool::FsWrite(w) = &tool.tool {
-                        let sanitized_path_str = w.path(os).to_string_lossy().to_string();
-                        let conversation_id = self.conversation.conversation_id().to_string();
-                        let message_id = self.conversation.message_id().map(|s| s.to_string());
-                        if let Some(tracker) = self.conversation.file_line_tracker.get_mut(&sanitized_path_str) {
-                            let lines_by_agent = tracker.lines_by_agent();
-                            let lines_by_user = tracker.lines_by_user();
-
-                            os.telemetry
-                                .send_agent_contribution_metric(
-                                    &os.database,
-                                    conversation_id,
-                                    message_id,
-                                    Some(tool.id.clone()),   // Already a String
-                                    Some(tool.name.clone()), // Already a String
-                                    Some(lines_by_agent),
-                                    Some(lines_by_user),
-                                )
-                                .await
-                                .ok();
-
-                            tracker.prev_fswrite_lines = tracker.after_fswrite_lines;
+                        for path in w.paths(os) {
+                            let sanitized_path_str = path.to_string_lossy().to_string();
+                            let conversation_id = self.conversation.conversation_id().to_string();
+                            let message_id = self.conversation.message_id().map(|s| s.to_string());
+                            if let Some(tracker) = self.conversation.file_line_tracker.get_mut(&sanitized_path_str) {
+                                let lines_by_agent = tracker.lines_by_agent();
+                                let lines_by_user = tracker.lines_by_user();
+
+                                os.telemetry
+                                    .send_agent_contribution_metric(
+                                        &os.database,
+                                        conversation_id,
+                                        message_id,
+                                        Some(tool.id.clone()),   // Already a String
+                                        Some(tool.name.clone()), // Already a String
+                                        Some(lines_by_agent),
+                                        Some(lines_by_user),
+                                    )
+                                    .await
+                                    .ok();
+
+                                tracker.prev_fswrite_lines = tracker.after_fswrite_lines;
+                            }
                         }
                     }
 
+                    let output = result.as_str().into_owned();
+                    self.event_sink.emit(ChatEvent::ToolResult {
+                        id: tool.id.clone(),
+                        name: tool.name.clone(),
+                        success: true,
+                        output: output.clone(),
+                    });
+                    if let Some(event_log) = self.event_log.as_mut() {
+                        event_log.write(Event::ToolCompleted {
+                            id: tool.id.clone(),
+                            name: tool.name.clone(),
+                            success: true,
+                            duration_ms,
+                            result_size: output.len(),
+                        });
+                    }
+                    if let Some(turn_output) = self.turn_output.as_mut() {
+                        turn_output.tools_used.push(ToolUseRecord {
+                            id: tool.id.clone(),
+                            name: tool.name.clone(),
+                            success: true,
+                            result: output,
+                        });
+                    }
+
+                    let mut content = vec![result.into()];
+                    if let (true, Tool::FsWrite(fs_write)) = (tool.user_edited, &tool.tool) {
+                        content.push(ToolUseResultBlock::Text(format!(
+                            "Note: the user modified the proposed change before it was applied. The content that was written instead:\n{}",
+                            fs_write.proposed_content()
+                        )));
+                    }
                     tool_results.push(ToolUseResult {
                         tool_use_id: tool.id.clone(),
-                        content: vec![result.into()],
+                        content,
                         status: ToolResultStatus::Success,
                     });
                 },
@@ -2328,12 +3583,35 @@ impl ChatSession {
                         ev.is_success = Some(false);
                         ev.reason_desc = Some(err.to_string());
                     });
+                    self.event_sink.emit(ChatEvent::ToolResult {
+                        id: tool.id.clone(),
+                        name: tool.name.clone(),
+                        success: false,
+                        output: err.to_string(),
+                    });
+                    if let Some(event_log) = self.event_log.as_mut() {
+                        event_log.write(Event::ToolCompleted {
+                            id: tool.id.clone(),
+                            name: tool.name.clone(),
+                            success: false,
+                            duration_ms,
+                            result_size: err.to_string().len(),
+                        });
+                    }
+                    if let Some(output) = self.turn_output.as_mut() {
+                        output.tools_used.push(ToolUseRecord {
+                            id: tool.id.clone(),
+                            name: tool.name.clone(),
+                            success: false,
+                            result: err.to_string(),
+                        });
+                    }
                     tool_results.push(ToolUseResult {
                         tool_use_id: tool.id.clone(),
-                        content: vec![ToolUseResultBlock::Text(format!(
-                            "An error occurred processing the tool: \n{}",
-                            &err
-                        ))],
+                        content: vec![
+                            ToolUseResultBlock::Text(format!("An error occurred processing the tool: \n{}", &err)),
+                            ToolUseResultBlock::Json(tool.tool.error_kind(&err).to_json()),
+                        ],
                         status: ToolResultStatus::Error,
                     });
                     if let ToolUseStatus::Idle = self.tool_use_status {
@@ -2347,6 +3625,32 @@ impl ChatSession {
             }
         }
 
+        if let Some((tool_use_id, delegate)) = delegation {
+            match self.check_delegation(os, &delegate).await {
+                Ok(()) => {
+                    return self
+                        .start_delegation(os, tool_use_id, delegate, tool_results, image_blocks)
+                        .await;
+                },
+                Err((err, kind)) => {
+                    execute!(
+                        self.stderr,
+                        style::SetForegroundColor(Color::Red),
+                        style::Print(format!("Failed to delegate the task: {err}\n\n")),
+                        style::SetForegroundColor(Color::Reset),
+                    )?;
+                    tool_results.push(ToolUseResult {
+                        tool_use_id,
+                        content: vec![
+                            ToolUseResultBlock::Text(format!("Failed to delegate the task: {err}")),
+                            ToolUseResultBlock::Json(kind.to_json()),
+                        ],
+                        status: ToolResultStatus::Error,
+                    });
+                },
+            }
+        }
+
         if !image_blocks.is_empty() {
             let images = image_blocks.into_iter().map(|(block, _)| block).collect();
             self.conversation.add_tool_results_with_images(tool_results, images);
@@ -2363,7 +3667,7 @@ impl ChatSession {
         execute!(self.stderr, cursor::Hide)?;
         execute!(self.stderr, style::Print("\n"), style::SetAttribute(Attribute::Reset))?;
         if self.interactive {
-            self.spinner = Some(Spinner::new(Spinners::Dots, "Thinking...".to_string()));
+            self.spinner = start_spinner(plain_output(os), Spinners::Dots, "Thinking...".to_string());
         }
 
         self.send_chat_telemetry(os, TelemetryResult::Succeeded, None, None, None, false)
@@ -2376,6 +3680,184 @@ impl ChatSession {
         ));
     }
 
+    /// Checks that the task of `delegate` can be delegated, loading the agent it names if it was
+    /// not loaded at startup.
+    async fn check_delegation(&mut self, os: &Os, delegate: &AgentDelegate) -> Result<(), (String, ToolErrorKind)> {
+        if self.delegations.len() >= MAX_DELEGATION_DEPTH {
+            return Err((
+                format!("tasks can only be delegated {MAX_DELEGATION_DEPTH} levels deep"),
+                ToolErrorKind::InvalidInput,
+            ));
+        }
+
+        let agents = &mut self.conversation.agents;
+        if !agents.agents.contains_key(&delegate.agent) {
+            match Agent::get_agent_by_name(os, &delegate.agent).await {
+                Ok((agent, _)) => {
+                    agents.insert(delegate.agent.clone(), agent);
+                },
+                Err(err) => {
+                    warn!(?err, "failed to load agent {}", delegate.agent);
+                    let mut names = agents.agents.keys().map(String::as_str).collect::<Vec<_>>();
+                    names.sort();
+                    return Err((
+                        format!(
+                            "no agent named {} was found. Available agents: {}",
+                            delegate.agent,
+                            names.join(", ")
+                        ),
+                        ToolErrorKind::NotFound,
+                    ));
+                },
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Hands the task of `delegate` to the agent it names. The current agent and conversation are
+    /// kept aside while that agent works on the task in a conversation of its own, and are
+    /// restored by [Self::finish_delegation] once it responds without using any tools.
+    ///
+    /// `tool_results` and `images` are the results of the other tools used alongside the
+    /// delegation, which are returned together with its result.
+    async fn start_delegation(
+        &mut self,
+        os: &mut Os,
+        tool_use_id: String,
+        delegate: AgentDelegate,
+        tool_results: Vec<ToolUseResult>,
+        images: Vec<RichImageBlock>,
+    ) -> Result<ChatState, ChatError> {
+        self.tool_use_telemetry_events
+            .entry(tool_use_id.clone())
+            .and_modify(|ev| ev.is_accepted = true);
+        self.send_chat_telemetry(os, TelemetryResult::Succeeded, None, None, None, false)
+            .await;
+        self.send_tool_use_telemetry(os).await;
+
+        self.delegations.push(Delegation {
+            parent_agent: self.conversation.agents.active_idx.clone(),
+            tool_use_id,
+            tool_results,
+            images,
+            turns: 0,
+        });
+        self.tool_uses.clear();
+        self.pending_tool_index = None;
+        self.tool_loop.reset();
+
+        self.conversation.enter_delegation();
+        self.conversation
+            .swap_agent(os, &mut self.stderr, &delegate.agent)
+            .await?;
+        execute!(
+            self.stderr,
+            style::SetForegroundColor(Color::DarkGrey),
+            style::Print("Agent "),
+            style::SetForegroundColor(Color::Green),
+            style::Print(&delegate.agent),
+            style::SetForegroundColor(Color::DarkGrey),
+            style::Print(" is working on the delegated task\n\n"),
+            style::SetForegroundColor(Color::Reset),
+        )?;
+
+        self.conversation.set_next_user_message(delegate.task).await;
+        let conv_state = self
+            .conversation
+            .as_sendable_conversation_state(os, &mut self.stderr, true)
+            .await?;
+        // The cursor is only hidden once nothing can fail anymore, so that an error never leaves it
+        // hidden.
+        if self.interactive {
+            execute!(self.stderr, cursor::Hide)?;
+            self.spinner = start_spinner(plain_output(os), Spinners::Dots, "Thinking...".to_owned());
+        }
+
+        Ok(ChatState::HandleResponseStream(conv_state))
+    }
+
+    /// Restores the agent and conversation that the innermost delegated task came from, and
+    /// returns `response` as the result of its agent_delegate tool use.
+    async fn finish_delegation(&mut self, os: &mut Os, response: String) -> Result<ChatState, ChatError> {
+        let Some(delegation) = self.delegations.pop() else {
+            return Ok(ChatState::PromptUser {
+                skip_printing_tools: false,
+            });
+        };
+        let agent_name = self.conversation.agents.active_idx.clone();
+        self.tool_uses.clear();
+        self.pending_tool_index = None;
+        self.tool_loop.reset();
+
+        self.conversation.exit_delegation();
+        self.conversation
+            .swap_agent(os, &mut self.stderr, &delegation.parent_agent)
+            .await?;
+        execute!(
+            self.stderr,
+            style::SetForegroundColor(Color::DarkGrey),
+            style::Print(format!(
+                "\nAgent {agent_name} finished the delegated task after {} turns, back to agent ",
+                delegation.turns
+            )),
+            style::SetForegroundColor(Color::Green),
+            style::Print(&delegation.parent_agent),
+            style::SetForegroundColor(Color::Reset),
+            style::Print("\n\n"),
+        )?;
+
+        let mut tool_results = delegation.tool_results;
+        tool_results.push(ToolUseResult {
+            tool_use_id: delegation.tool_use_id,
+            content: vec![ToolUseResultBlock::Text(response)],
+            status: ToolResultStatus::Success,
+        });
+        if delegation.images.is_empty() {
+            self.conversation.add_tool_results(tool_results);
+        } else {
+            let images = delegation.images.into_iter().map(|(block, _)| block).collect();
+            self.conversation.add_tool_results_with_images(tool_results, images);
+        }
+
+        let conv_state = self
+            .conversation
+            .as_sendable_conversation_state(os, &mut self.stderr, false)
+            .await?;
+        if self.interactive {
+            execute!(self.stderr, cursor::Hide)?;
+            self.spinner = start_spinner(plain_output(os), Spinners::Dots, "Thinking...".to_owned());
+        }
+
+        Ok(ChatState::HandleResponseStream(conv_state))
+    }
+
+    /// Abandons every delegated task in progress, restoring the agent and conversation that the
+    /// outermost task was delegated from.
+    async fn abort_delegations(&mut self, os: &mut Os) -> Result<(), ChatError> {
+        let delegations = std::mem::take(&mut self.delegations);
+        let Some(outermost) = delegations.first() else {
+            return Ok(());
+        };
+        for _ in &delegations {
+            self.conversation.exit_delegation();
+        }
+        self.conversation
+            .swap_agent(os, &mut self.stderr, &outermost.parent_agent)
+            .await?;
+        execute!(
+            self.stderr,
+            style::SetForegroundColor(Color::Yellow),
+            style::Print("The delegated task was abandoned, back to agent "),
+            style::SetForegroundColor(Color::Green),
+            style::Print(&outermost.parent_agent),
+            style::SetForegroundColor(Color::Reset),
+            style::Print("\n\n"),
+        )?;
+
+        Ok(())
+    }
+
     /// Sends a [crate::api_client::ApiClient::send_message] request to the backend and consumes
     /// the response stream.
     ///
@@ -2427,6 +3909,23 @@ impl ChatSession {
                             buf.push('\n');
                             tool_name_being_recvd = Some(name);
                         },
+                        parser::ResponseEvent::ToolUseProgress { name, bytes, lines } => {
+                            if self.interactive {
+                                if let Some(mut spinner) = self.spinner.take() {
+                                    spinner.stop();
+                                }
+                                queue!(
+                                    self.stderr,
+                                    cursor::MoveToColumn(0),
+                                    terminal::Clear(terminal::ClearType::CurrentLine)
+                                )?;
+                                self.spinner = start_spinner(
+                                    plain_output(os),
+                                    Spinners::Dots,
+                                    format!("Receiving {name}: {bytes} bytes, {lines} lines"),
+                                );
+                            }
+                        },
                         parser::ResponseEvent::AssistantText(text) => {
                             // Add Q response prefix before the first assistant text.
                             if !response_prefix_printed && !text.trim().is_empty() {
@@ -2438,6 +3937,7 @@ impl ChatSession {
                                 )?;
                                 response_prefix_printed = true;
                             }
+                            self.event_sink.emit(ChatEvent::AssistantDelta(text.clone()));
                             buf.push_str(&text);
                         },
                         parser::ResponseEvent::ToolUse(tool_use) => {
@@ -2462,8 +3962,19 @@ impl ChatSession {
                             if message.content() == RESPONSE_TIMEOUT_CONTENT {
                                 error!(?request_id, ?message, "Encountered an unexpected model response");
                             }
+                            self.session_stats.output_tokens +=
+                                TokenCount::from(CharCount::from(rm.response_size)).value();
+                            self.log_event(Event::AssistantMessage {
+                                length: message.content().chars().count(),
+                                stop_reason: match message.tool_uses() {
+                                    Some(_) => StopReason::ToolUse,
+                                    None => StopReason::EndTurn,
+                                },
+                                text: Some(message.content().to_string()),
+                            });
                             self.conversation.push_assistant_message(os, message, Some(rm.clone()));
                             self.user_turn_request_metadata.push(rm);
+                            self.retried_context_overflow = false;
                             ended = true;
                         },
                     }
@@ -2498,7 +4009,8 @@ impl ChatSession {
                             );
 
                             execute!(self.stderr, cursor::Hide)?;
-                            self.spinner = Some(Spinner::new(Spinners::Dots, "Dividing up the work...".to_string()));
+                            self.spinner =
+                                start_spinner(plain_output(os), Spinners::Dots, "Dividing up the work...".to_string());
 
                             // For stream timeouts, we'll tell the model to try and split its response into
                             // smaller chunks.
@@ -2544,9 +4056,12 @@ impl ChatSession {
                                 .push_assistant_message(os, *message, Some(recv_error.request_metadata));
                             let tool_results = vec![ToolUseResult {
                                     tool_use_id,
-                                    content: vec![ToolUseResultBlock::Text(
-                                        "The generated tool was too large, try again but this time split up the work between multiple tool uses".to_string(),
-                                    )],
+                                    content: vec![
+                                        ToolUseResultBlock::Text(
+                                            "The generated tool was too large, try again but this time split up the work between multiple tool uses".to_string(),
+                                        ),
+                                        ToolUseResultBlock::Json(ToolErrorKind::InvalidInput.to_json()),
+                                    ],
                                     status: ToolResultStatus::Error,
                                 }];
                             self.conversation.add_tool_results(tool_results);
@@ -2616,11 +4131,12 @@ impl ChatSession {
                 tokio::time::sleep(Duration::from_millis(8)).await;
             }
 
-            // Set spinner after showing all of the assistant text content so far.
-            if tool_name_being_recvd.is_some() {
+            // Set spinner after showing all of the assistant text content so far, unless it
+            // already shows the progress of the tool use.
+            if tool_name_being_recvd.is_some() && self.spinner.is_none() {
                 queue!(self.stderr, cursor::Hide)?;
                 if self.interactive {
-                    self.spinner = Some(Spinner::new(Spinners::Dots, "Thinking...".to_string()));
+                    self.spinner = start_spinner(plain_output(os), Spinners::Dots, "Thinking...".to_string());
                 }
             }
 
@@ -2654,9 +4170,29 @@ impl ChatSession {
             }
         }
 
+        if let Some(delegation) = self.delegations.last_mut() {
+            delegation.turns += 1;
+            let max_turns = os
+                .database
+                .settings
+                .get_int_or(Setting::ChatDelegationMaxTurns, DEFAULT_DELEGATION_MAX_TURNS);
+            if tool_uses.is_empty() {
+                return self.finish_delegation(os, buf.trim().to_string()).await;
+            } else if delegation.turns >= max_turns {
+                let response = format!(
+                    "{}\n\n[The agent was stopped after {max_turns} turns before it finished the task]",
+                    buf.trim()
+                );
+                return self.finish_delegation(os, response).await;
+            }
+        }
+
         if !tool_uses.is_empty() {
             Ok(ChatState::ValidateTools { tool_uses })
         } else {
+            if let Some(output) = self.turn_output.as_mut() {
+                output.response = Some(buf.trim().to_string());
+            }
             self.tool_uses.clear();
             self.pending_tool_index = None;
             self.tool_turn_start_time = None;
@@ -2675,10 +4211,17 @@ impl ChatSession {
         debug!(?tool_uses, "Validating tool uses");
         let mut queued_tools: Vec<QueuedTool> = Vec::new();
         let mut tool_results: Vec<ToolUseResult> = Vec::new();
+        let delegation_enabled = self
+            .conversation
+            .agents
+            .get_active()
+            .is_some_and(|agent| agent.delegation_enabled);
 
         for tool_use in tool_uses {
             let tool_use_id = tool_use.id.clone();
             let tool_use_name = tool_use.name.clone();
+            let tool_use_args = tool_use.args.clone();
+            let consecutive_calls = self.tool_loop.record(&tool_use.name, &tool_use.args);
             let mut tool_telemetry = ToolUseEventBuilder::new(
                 conv_id.clone(),
                 tool_use.id.clone(),
@@ -2688,6 +4231,19 @@ impl ChatSession {
             .set_tool_name(tool_use.name.clone())
             .utterance_id(self.conversation.message_id().map(|s| s.to_string()));
             match self.conversation.tool_manager.get_tool_from_tool_use(tool_use) {
+                // The tool is not offered to agents without delegation, but the model can still
+                // name it
+                Ok(Tool::AgentDelegate(_)) if !delegation_enabled => {
+                    tool_telemetry.is_valid = Some(false);
+                    tool_results.push(ToolUseResult {
+                        tool_use_id: tool_use_id.clone(),
+                        content: vec![
+                            ToolUseResultBlock::Text(format!("No tool with \"{tool_use_name}\" is found")),
+                            ToolUseResultBlock::Json(ToolErrorKind::UnknownTool.to_json()),
+                        ],
+                        status: ToolResultStatus::Error,
+                    });
+                },
                 Ok(mut tool) => {
                     // Apply non-Q-generated context to tools
                     self.contextualize_tool(&mut tool);
@@ -2695,20 +4251,38 @@ impl ChatSession {
                     match tool.validate(os).await {
                         Ok(()) => {
                             tool_telemetry.is_valid = Some(true);
+                            self.event_sink.emit(ChatEvent::ToolRequested(ToolRequest {
+                                id: tool_use_id.clone(),
+                                name: tool_use_name.clone(),
+                                args: tool_use_args.clone(),
+                            }));
+                            self.log_event(Event::ToolRequested {
+                                id: tool_use_id.clone(),
+                                name: tool_use_name.clone(),
+                                args: Some(tool_use_args.clone()),
+                            });
                             queued_tools.push(QueuedTool {
                                 id: tool_use_id.clone(),
                                 name: tool_use_name,
+                                args: tool_use_args,
                                 tool,
                                 accepted: false,
+                                user_edited: false,
+                                consecutive_calls,
                             });
                         },
                         Err(err) => {
                             tool_telemetry.is_valid = Some(false);
                             tool_results.push(ToolUseResult {
                                 tool_use_id: tool_use_id.clone(),
-                                content: vec![ToolUseResultBlock::Text(format!(
-                                    "Failed to validate tool parameters: {err}"
-                                ))],
+                                content: vec![
+                                    ToolUseResultBlock::Text(format!("Failed to validate tool parameters: {err}")),
+                                    ToolUseResultBlock::Json(
+                                        ToolErrorKind::from_report(&err)
+                                            .unwrap_or(ToolErrorKind::InvalidInput)
+                                            .to_json(),
+                                    ),
+                                ],
                                 status: ToolResultStatus::Error,
                             });
                         },
@@ -2735,6 +4309,8 @@ impl ChatSession {
                 for block in &tool_result.content {
                     let content: Option<Cow<'_, str>> = match block {
                         ToolUseResultBlock::Text(t) => Some(t.as_str().into()),
+                        // The error kind is meant for the model, the text already describes the error
+                        ToolUseResultBlock::Json(d) if d.get(TOOL_ERROR_KIND_KEY).is_some() => None,
                         ToolUseResultBlock::Json(d) => serde_json::to_string(d)
                             .map_err(|err| error!(?err, "failed to serialize tool result content"))
                             .map(Into::into)
@@ -2773,6 +4349,7 @@ impl ChatSession {
 
         self.tool_uses = queued_tools;
         self.pending_tool_index = Some(0);
+        self.denied_tool_overrides.clear();
         self.tool_turn_start_time = Some(Instant::now());
         Ok(ChatState::ExecuteTools)
     }
@@ -2795,7 +4372,7 @@ impl ChatSession {
         }
 
         if self.interactive {
-            self.spinner = Some(Spinner::new(Spinners::Dots, "Thinking...".to_owned()));
+            self.spinner = start_spinner(plain_output(os), Spinners::Dots, "Thinking...".to_owned());
         }
 
         Ok(ChatState::HandleResponseStream(
@@ -2836,7 +4413,8 @@ impl ChatSession {
             self.stdout,
             style::SetForegroundColor(Color::Magenta),
             style::Print(format!(
-                "🛠️  Using tool: {}{}",
+                "{}Using tool: {}{}",
+                glyph(plain_output(os), "🛠️  ", ""),
                 tool_use.tool.display_name(),
                 if trusted { " (trusted)".dark_green() } else { "".reset() }
             )),
@@ -2870,6 +4448,61 @@ impl ChatSession {
         Ok(())
     }
 
+    /// Asks the user whether a tool use that was denied by the active agent's rules should be
+    /// allowed to run anyway. Returns `true` only if the user explicitly answers `y`.
+    fn prompt_denied_tool_override(&mut self, tool_name: &str, rules: &[String]) -> Result<bool, ChatError> {
+        let formatted_rules = rules.iter().map(|r| format!("`{r}`")).collect::<Vec<_>>().join(", ");
+        queue!(
+            self.stderr,
+            style::SetForegroundColor(Color::Red),
+            style::Print("\nTool "),
+            style::SetForegroundColor(Color::Yellow),
+            style::Print(format!("`{tool_name}`")),
+            style::SetForegroundColor(Color::Red),
+            style::Print(format!(" was denied because of rule {formatted_rules}. ")),
+        )?;
+        self.confirm("Override for this invocation?")
+    }
+
+    /// Asks whether to run a corrected slash command in place of one that was not recognized.
+    fn prompt_run_suggested_command(&mut self, command: &str) -> Result<bool, ChatError> {
+        queue!(
+            self.stderr,
+            style::SetForegroundColor(Color::DarkGrey),
+            style::Print("Run "),
+            style::SetForegroundColor(Color::Green),
+            style::Print(format!("'{command}' ")),
+        )?;
+        self.confirm("instead?")
+    }
+
+    /// Prints `prompt` followed by a `[y/N]` choice, after anything already queued on stderr, and
+    /// reads the answer. Returns `true` only if the user explicitly answers `y`.
+    fn confirm(&mut self, prompt: &str) -> Result<bool, ChatError> {
+        execute!(
+            self.stderr,
+            style::SetForegroundColor(Color::DarkGrey),
+            style::Print(format!("{prompt} [")),
+            style::SetForegroundColor(Color::Green),
+            style::Print("y"),
+            style::SetForegroundColor(Color::DarkGrey),
+            style::Print("/"),
+            style::SetForegroundColor(Color::Green),
+            style::Print("N"),
+            style::SetForegroundColor(Color::DarkGrey),
+            style::Print("]:\n\n"),
+            style::SetForegroundColor(Color::Reset),
+            cursor::Show,
+        )?;
+
+        // Setting `exit_on_single_ctrl_c` for better ux: exit the confirmation dialog rather than the CLI
+        let user_input = self
+            .read_user_input("> ".yellow().to_string().as_str(), true)
+            .unwrap_or_default();
+
+        Ok(["y", "Y"].contains(&user_input.trim()))
+    }
+
     /// Helper function to read user input with a prompt and Ctrl+C handling
     fn read_user_input(&mut self, prompt: &str, exit_on_single_ctrl_c: bool) -> Option<String> {
         let mut ctrl_c = false;
@@ -2906,7 +4539,7 @@ impl ChatSession {
         let profile = self.conversation.current_profile().map(|s| s.to_string());
         let all_trusted = self.all_tools_trusted();
         let tangent_mode = self.conversation.is_in_tangent_mode();
-        prompt::generate_prompt(profile.as_deref(), all_trusted, tangent_mode)
+        prompt::generate_prompt(profile.as_deref(), all_trusted, tangent_mode, self.context_usage)
     }
 
     async fn send_tool_use_telemetry(&mut self, os: &Os) {
@@ -2929,6 +4562,77 @@ impl ChatSession {
         self.conversation.agents.trust_all_tools
     }
 
+    /// Returns the configured auto-compaction threshold if the conversation has grown past it.
+    ///
+    /// Returns [None] when auto-compaction is disabled, the threshold is 0, or there is no history
+    /// to compact yet. History is only compacted once per crossing of the threshold, so that a
+    /// conversation still past it after compaction is not summarized again on every turn.
+    async fn auto_compact_threshold(&mut self, os: &Os) -> Option<usize> {
+        let settings = &os.database.settings;
+        if settings.get_bool(Setting::ChatDisableAutoCompaction).unwrap_or(false) {
+            return None;
+        }
+
+        let threshold = settings.get_int_or(Setting::ChatAutoCompactThreshold, DEFAULT_AUTO_COMPACT_THRESHOLD);
+        if threshold == 0 || self.conversation.history().is_empty() {
+            return None;
+        }
+
+        match self.conversation.exceeds_context_threshold(os, threshold).await {
+            Ok(true) if self.auto_compacted => None,
+            Ok(true) => {
+                self.auto_compacted = true;
+                Some(threshold)
+            },
+            Ok(false) => {
+                self.auto_compacted = false;
+                None
+            },
+            Err(err) => {
+                warn!(?err, "Failed to estimate the conversation size");
+                None
+            },
+        }
+    }
+
+    /// Refreshes the context usage shown in the prompt, and suggests running /compact once when
+    /// usage crosses `chat.contextWarningThreshold`.
+    ///
+    /// The gauge is only shown in interactive sessions attached to a terminal.
+    async fn update_context_gauge(&mut self, os: &Os) -> Result<(), ChatError> {
+        let settings = &os.database.settings;
+        if !self.interactive
+            || !settings.get_bool(Setting::ChatShowContextGauge).unwrap_or(true)
+            || !std::io::stdin().is_terminal()
+            || !std::io::stderr().is_terminal()
+        {
+            self.context_usage = None;
+            return Ok(());
+        }
+
+        let threshold = settings.get_int_or(Setting::ChatContextWarningThreshold, DEFAULT_CONTEXT_WARNING_THRESHOLD);
+        let percent = self.conversation.context_usage_percent(os).await?;
+        self.context_usage = Some(percent);
+
+        if threshold == 0 || percent < threshold {
+            self.context_warning_shown = false;
+        } else if !self.context_warning_shown {
+            self.context_warning_shown = true;
+            execute!(
+                self.stderr,
+                style::SetForegroundColor(Color::Yellow),
+                style::Print(format!("\nThe context window is {percent}% full. Run ")),
+                style::SetForegroundColor(Color::Green),
+                style::Print("/compact"),
+                style::SetForegroundColor(Color::Yellow),
+                style::Print(" to summarize it and free up space.\n"),
+                style::SetForegroundColor(Color::Reset)
+            )?;
+        }
+
+        Ok(())
+    }
+
     /// Display character limit warnings based on current conversation size
     async fn display_char_warnings(&mut self, os: &Os) -> Result<(), ChatError> {
         let warning_level = self.conversation.get_token_warning_level(os).await?;
@@ -2940,7 +4644,10 @@ impl ChatSession {
                     self.stderr,
                     style::SetForegroundColor(Color::Yellow),
                     style::SetAttribute(Attribute::Bold),
-                    style::Print("\n⚠️ This conversation is getting lengthy.\n"),
+                    style::Print(format!(
+                        "\n{}This conversation is getting lengthy.\n",
+                        glyph(plain_output(os), "⚠️ ", "Warning: ")
+                    )),
                     style::SetAttribute(Attribute::Reset),
                     style::Print(
                         "To ensure continued smooth operation, please use /compact to summarize the conversation.\n\n"
@@ -2956,13 +4663,94 @@ impl ChatSession {
         Ok(())
     }
 
+    /// Whether the tool use awaiting approval can be edited by the user before it is accepted.
+    fn pending_tool_is_editable(&self) -> bool {
+        self.pending_tool_index
+            .and_then(|index| self.tool_uses.get(index))
+            .is_some_and(|tool_use| match &tool_use.tool {
+                Tool::FsWrite(fs_write) => !matches!(fs_write, FsWrite::Delete { .. } | FsWrite::Transaction { .. }),
+                _ => false,
+            })
+    }
+
+    /// Opens the content proposed by the pending fs_write tool use in the user's editor, and
+    /// accepts the edited version in its place.
+    ///
+    /// Returns false if the editor was closed without any changes, or failed to open.
+    fn edit_pending_tool(&mut self, os: &Os, index: usize) -> Result<bool, ChatError> {
+        let Tool::FsWrite(fs_write) = &self.tool_uses[index].tool else {
+            return Ok(false);
+        };
+
+        let proposed = fs_write.proposed_content();
+        // Keep the extension of the file being written for syntax highlighting
+        let file_name = match fs_write.path(os).extension() {
+            Some(ext) => format!("q_fs_write_{}.{}", uuid::Uuid::new_v4(), ext.to_string_lossy()),
+            None => format!("q_fs_write_{}", uuid::Uuid::new_v4()),
+        };
+        let edited = match cli::editor::edit_in_editor(&file_name, &proposed) {
+            Ok(edited) => edited,
+            Err(err) => {
+                execute!(
+                    self.stderr,
+                    style::SetForegroundColor(Color::Red),
+                    style::Print(format!("Failed to edit the change: {}\n", err)),
+                    style::SetForegroundColor(Color::Reset)
+                )?;
+                return Ok(false);
+            },
+        };
+
+        if edited == proposed {
+            execute!(
+                self.stderr,
+                style::SetForegroundColor(Color::DarkGrey),
+                style::Print("No changes were saved, rejecting the change.\n\n"),
+                style::SetForegroundColor(Color::Reset)
+            )?;
+            return Ok(false);
+        }
+
+        let edited_tool = Tool::FsWrite(fs_write.with_content(edited));
+        let tool_use = &mut self.tool_uses[index];
+        tool_use.tool = edited_tool;
+        tool_use.user_edited = true;
+        tool_use.accepted = true;
+        self.log_tool_approved(index, false);
+        Ok(true)
+    }
+
     /// Resets state associated with the active user turn.
     ///
     /// This should *always* be called whenever a new user prompt is sent to the backend. Note
     /// that includes tool use rejections.
     fn reset_user_turn(&mut self) {
         info!(?self.user_turn_request_metadata, "Resetting the current user turn");
-        self.user_turn_request_metadata.clear();
+        let request_metadata = std::mem::take(&mut self.user_turn_request_metadata);
+        if self.record_turn_perf && !request_metadata.is_empty() {
+            if self.turn_perf_history.len() >= MAX_TURN_PERF_HISTORY {
+                self.turn_perf_history.pop_front();
+            }
+            self.turn_perf_history.push_back(request_metadata);
+        }
+    }
+
+    /// Number of requests retried due to throttling or server errors this session.
+    pub fn throttle_retry_count(&self) -> u32 {
+        self.throttle_retry_count
+    }
+
+    /// Returns the [RequestMetadata] for each recorded user turn, oldest first, including the
+    /// most recent turn if it has not been reset yet.
+    pub fn turn_perf_metrics(&self) -> Vec<&[RequestMetadata]> {
+        if !self.record_turn_perf {
+            return Vec::new();
+        }
+        let mut turns = self.turn_perf_history.iter().map(Vec::as_slice).collect::<Vec<_>>();
+        if !self.user_turn_request_metadata.is_empty() {
+            turns.push(self.user_turn_request_metadata.as_slice());
+        }
+        turns
     }
 
     /// Sends an "codewhispererterminal_addChatMessage" telemetry event.
@@ -3004,6 +4792,7 @@ impl ChatSession {
                     .map(|d| d.as_secs_f64() * 1000.0)
                     .collect::<Vec<_>>()
             }),
+            output_tokens_per_second: md.and_then(|md| md.output_tokens_per_second()),
             chat_conversation_type: md.and_then(|md| md.chat_conversation_type),
             tool_use_id: self.conversation.latest_tool_use_ids(),
             tool_name: self.conversation.latest_tool_use_names(),
@@ -3044,6 +4833,7 @@ impl ChatSession {
                         .iter()
                         .map(|md| md.time_to_first_chunk.map(|d| d.as_secs_f64() * 1000.0))
                         .collect::<_>(),
+                    output_tokens_per_second: mds.iter().map(|md| md.output_tokens_per_second()).collect::<_>(),
                     chat_conversation_type: md.and_then(|md| md.chat_conversation_type),
                     assistant_response_length: mds.iter().map(|md| md.response_size as i64).sum(),
                     message_meta_tags: mds.last().map(|md| md.message_meta_tags.clone()).unwrap_or_default(),
@@ -3191,19 +4981,25 @@ async fn get_subscription_status_with_spinner(
     os: &mut Os,
     output: &mut impl Write,
 ) -> Result<ActualSubscriptionStatus> {
-    return with_spinner(output, "Checking subscription status...", || async {
+    let plain = plain_output(os);
+    return with_spinner(plain, output, "Checking subscription status...", || async {
         get_subscription_status(os).await
     })
     .await;
 }
 
-pub async fn with_spinner<T, E, F, Fut>(output: &mut impl std::io::Write, spinner_text: &str, f: F) -> Result<T, E>
+pub async fn with_spinner<T, E, F, Fut>(
+    plain: bool,
+    output: &mut impl std::io::Write,
+    spinner_text: &str,
+    f: F,
+) -> Result<T, E>
 where
     F: FnOnce() -> Fut,
     Fut: std::future::Future<Output = Result<T, E>>,
 {
     queue!(output, cursor::Hide,).ok();
-    let spinner = Some(Spinner::new(Spinners::Dots, spinner_text.to_owned()));
+    let spinner = start_spinner(plain, Spinners::Dots, spinner_text.to_owned());
 
     let result = f().await;
 
@@ -3243,7 +5039,10 @@ mod tests {
     use std::path::PathBuf;
 
     use super::*;
-    use crate::cli::agent::Agent;
+    use crate::cli::agent::{
+        Agent,
+        ToolSettingTarget,
+    };
 
     async fn get_test_agents(os: &Os) -> Agents {
         const AGENT_PATH: &str = "/persona/TestAgent.json";
@@ -3327,6 +5126,113 @@ mod tests {
         assert_eq!(os.fs.read_to_string("/file.txt").await.unwrap(), "Hello, world!\n");
     }
 
+    #[tokio::test]
+    async fn test_auto_compact_threshold_once_per_crossing() {
+        let mut os = Os::new().await.unwrap();
+        os.database
+            .settings
+            .set(Setting::ChatAutoCompactThreshold, 1)
+            .await
+            .unwrap();
+
+        let agents = get_test_agents(&os).await;
+        let tool_config = serde_json::from_str::<HashMap<String, ToolSpec>>(include_str!("tools/tool_index.json"))
+            .expect("Tools failed to load");
+        let mut session = ChatSession::new(
+            &mut os,
+            std::io::stdout(),
+            std::io::stderr(),
+            "fake_conv_id",
+            agents,
+            None,
+            InputSource::new_mock(vec![]),
+            false,
+            || Some(80),
+            ToolManager::default(),
+            None,
+            tool_config,
+            true,
+            false,
+        )
+        .await
+        .unwrap();
+
+        // Fill 2% of the context window
+        let context_window =
+            crate::cli::chat::cli::model::context_window_tokens(session.conversation.model_info.as_ref());
+        let message = "a".repeat(TokenCounter::token_to_chars(context_window) / 50);
+        session.conversation.set_next_user_message(message).await;
+        session.conversation.push_assistant_message(
+            &mut os,
+            AssistantMessage::new_response(None, "ok".to_string()),
+            None,
+        );
+
+        assert_eq!(session.auto_compact_threshold(&os).await, Some(1));
+        // Still past the threshold, e.g. because the summary is large, but it was already compacted
+        assert_eq!(session.auto_compact_threshold(&os).await, None);
+
+        // Dropping back below the threshold allows compacting again on the next crossing
+        os.database
+            .settings
+            .set(Setting::ChatAutoCompactThreshold, 99)
+            .await
+            .unwrap();
+        assert_eq!(session.auto_compact_threshold(&os).await, None);
+        os.database
+            .settings
+            .set(Setting::ChatAutoCompactThreshold, 1)
+            .await
+            .unwrap();
+        assert_eq!(session.auto_compact_threshold(&os).await, Some(1));
+    }
+
+    #[tokio::test]
+    async fn test_context_overflow_retry() {
+        let mut os = Os::new().await.unwrap();
+        os.database
+            .settings
+            .set(Setting::ChatDisableAutoCompaction, true)
+            .await
+            .unwrap();
+        let responses = serde_json::json!(["ContextWindowOverflow", "ContextWindowOverflow", ["Hello!"]]);
+        os.client.set_mock_output(responses);
+
+        let tool_config = serde_json::from_str::<HashMap<String, ToolSpec>>(include_str!("tools/tool_index.json"))
+            .expect("Tools failed to load");
+        let mut session = ChatSession::new(
+            &mut os,
+            std::io::stdout(),
+            std::io::stderr(),
+            "fake_conv_id",
+            get_test_agents(&os).await,
+            None,
+            InputSource::new_mock(vec!["hi".to_string(), "hi again".to_string(), "exit".to_string()]),
+            false,
+            || Some(80),
+            ToolManager::default(),
+            None,
+            tool_config,
+            true,
+            false,
+        )
+        .await
+        .unwrap();
+        for i in 0..4 {
+            session.conversation.set_next_user_message(i.to_string()).await;
+            session.conversation.push_assistant_message(
+                &mut os,
+                AssistantMessage::new_response(None, i.to_string()),
+                None,
+            );
+        }
+        session.spawn(&mut os).await.unwrap();
+
+        // The first overflow drops the 2 oldest turns and retries once. The retry overflows too,
+        // which is reported instead of dropping more turns, and the next prompt is sent as usual.
+        assert_eq!(session.conversation.history().len(), 3);
+    }
+
     #[tokio::test]
     async fn test_flow_tool_permissions() {
         let mut os = Os::new().await.unwrap();
@@ -3474,6 +5380,72 @@ mod tests {
         // assert!(!ctx.fs.exists("/file6.txt"));
     }
 
+    #[tokio::test]
+    async fn test_flow_denied_tool_override() {
+        let mut os = Os::new().await.unwrap();
+        let create = |path: &str| {
+            serde_json::json!([
+                "Ok",
+                {
+                    "tool_use_id": "1",
+                    "name": "fs_write",
+                    "args": {
+                        "command": "create",
+                        "file_text": "Hello, world!",
+                        "path": path,
+                    }
+                }
+            ])
+        };
+        os.client.set_mock_output(serde_json::json!([
+            create("/denied/file1.txt"),
+            ["Done"],
+            create("/denied/file2.txt"),
+            ["Ok, I won't make it."],
+        ]));
+
+        let mut agents = get_test_agents(&os).await;
+        agents.get_active_mut().unwrap().tools_settings.insert(
+            ToolSettingTarget("fs_write".to_string()),
+            serde_json::json!({ "deniedPaths": ["/denied"] }),
+        );
+        let tool_config = serde_json::from_str::<HashMap<String, ToolSpec>>(include_str!("tools/tool_index.json"))
+            .expect("Tools failed to load");
+        ChatSession::new(
+            &mut os,
+            std::io::stdout(),
+            std::io::stderr(),
+            "fake_conv_id",
+            agents,
+            None,
+            InputSource::new_mock(vec![
+                "create a file".to_string(),
+                "y".to_string(), // override the deny rule, which runs the tool without asking again
+                "create another file".to_string(),
+                "n".to_string(), // the override does not carry over to the next tool use
+                "exit".to_string(),
+            ]),
+            false,
+            || Some(80),
+            ToolManager::default(),
+            None,
+            tool_config,
+            true,
+            false,
+        )
+        .await
+        .unwrap()
+        .spawn(&mut os)
+        .await
+        .unwrap();
+
+        assert_eq!(
+            os.fs.read_to_string("/denied/file1.txt").await.unwrap(),
+            "Hello, world!\n"
+        );
+        assert!(!os.fs.exists("/denied/file2.txt"));
+    }
+
     #[tokio::test]
     async fn test_flow_multiple_tools() {
         // let _ = tracing_subscriber::fmt::try_init();
@@ -3642,6 +5614,67 @@ mod tests {
         assert!(!os.fs.exists("/file2.txt"));
     }
 
+    #[tokio::test]
+    async fn test_pause_and_resume() {
+        let mut os = Os::new().await.unwrap();
+        os.client.set_mock_output(serde_json::json!([["Hello!"]]));
+
+        let tool_config = serde_json::from_str::<HashMap<String, ToolSpec>>(include_str!("tools/tool_index.json"))
+            .expect("Tools failed to load");
+        let mut session = ChatSession::new(
+            &mut os,
+            std::io::stdout(),
+            std::io::stderr(),
+            "fake_conv_id",
+            get_test_agents(&os).await,
+            None,
+            InputSource::new_mock(vec!["hi".to_string(), "/pause".to_string()]),
+            false,
+            || Some(80),
+            ToolManager::default(),
+            None,
+            tool_config.clone(),
+            true,
+            false,
+        )
+        .await
+        .unwrap();
+        session.spawn(&mut os).await.unwrap();
+
+        let session_id = session.serialize_pause(&os).await.unwrap();
+        let paused = PausedSession::load(&os, &session_id).await.unwrap();
+        assert_eq!(paused.session_id, session_id);
+        assert_eq!(paused.active_agent, session.conversation.agents.active_idx);
+        assert_eq!(paused.conversation.history().len(), 1);
+
+        let mut resumed = ChatSession::new(
+            &mut os,
+            std::io::stdout(),
+            std::io::stderr(),
+            "new_conv_id",
+            get_test_agents(&os).await,
+            None,
+            InputSource::new_mock(vec![]),
+            false,
+            || Some(80),
+            ToolManager::default(),
+            None,
+            tool_config,
+            true,
+            false,
+        )
+        .await
+        .unwrap();
+        resumed.restore_pause(&os, paused).await.unwrap();
+        assert_eq!(resumed.conversation.conversation_id(), "fake_conv_id");
+        assert_eq!(resumed.conversation.tool_manager.conversation_id, "fake_conv_id");
+        assert_eq!(resumed.conversation.history().len(), 1);
+        assert!(resumed.existing_conversation);
+
+        assert!(PausedSession::load(&os, "20250101-120000-00000000").await.is_err());
+        assert!(PausedSession::load(&os, "../../settings").await.is_err());
+    }
+
     #[test]
     fn test_editor_content_processing() {
         // Since we no longer have template replacement, this test is simplified
@@ -3706,6 +5739,59 @@ mod tests {
             assert_eq!(actual, *expected, "expected {} for input {}", expected, input);
         }
     }
+
+    #[test]
+    fn test_collect_prompt_variables() {
+        let agent = Agent {
+            prompt: Some("Review {{language}} code owned by {{team}}".to_string()),
+            prompt_variables: HashMap::from([("team".to_string(), "platform".to_string())]),
+            ..Default::default()
+        };
+
+        let vars = vec![("language".to_string(), "Rust".to_string())];
+        let values = collect_prompt_variables(&agent, vars, false).unwrap();
+        assert_eq!(values, HashMap::from([("language".to_string(), "Rust".to_string())]));
+        assert_eq!(
+            agent.expanded_prompt(&values).as_deref(),
+            Some("Review Rust code owned by platform")
+        );
+
+        let err = collect_prompt_variables(&agent, vec![], false).unwrap_err();
+        assert!(err.to_string().contains("language"));
+
+        assert_eq!(parse_prompt_variable("a=b=c"), Ok(("a".to_string(), "b=c".to_string())));
+        assert!(parse_prompt_variable("=b").is_err());
+    }
+
+    #[test]
+    fn test_chat_error_kind_and_hint() {
+        let err = ChatError::Auth(crate::auth::AuthError::NoToken);
+        assert_eq!(err.kind(), ChatErrorKind::Auth);
+        assert_eq!(err.kind().exit_code(), 3);
+        assert!(err.hint().is_some_and(|hint| hint.contains("q login")));
+
+        let err = ChatError::Custom("something went wrong".into());
+        assert_eq!(err.kind(), ChatErrorKind::Internal);
+        assert!(err.hint().is_none());
+
+        let err = ChatError::with_hint(ChatErrorKind::McpServer, "server failed", "try again");
+        assert_eq!(err.to_string(), "server failed");
+        assert_eq!(err.reason_code(), "McpServer");
+        assert_eq!(err.hint().as_deref(), Some("try again"));
+        assert_eq!(ChatError::NonInteractiveToolApproval.kind().exit_code(), 4);
+
+        let err = ChatError::HookBlocked {
+            trigger: HookTrigger::AgentSpawn,
+            command: "./check-vpn.sh".to_string(),
+            error: "command timed out after 500 ms".to_string(),
+        };
+        assert_eq!(
+            err.to_string(),
+            "The agentSpawn hook './check-vpn.sh' failed: command timed out after 500 ms"
+        );
+        assert_eq!(err.kind(), ChatErrorKind::Agent);
+        assert!(err.hint().is_some_and(|hint| hint.contains("on_failure")));
+    }
 }
 
 // Helper method to save the agent config to file
@@ -29,6 +29,10 @@ use super::message::{
     AssistantMessage,
     AssistantToolUse,
 };
+use super::token_counter::{
+    CharCount,
+    TokenCount,
+};
 use crate::api_client::model::{
     ChatResponseStream,
     ConversationState,
@@ -44,6 +48,9 @@ use crate::telemetry::core::{
     MessageMetaTag,
 };
 
+/// Minimum time between two [ResponseEvent::ToolUseProgress] events of the same tool use.
+const TOOL_USE_PROGRESS_INTERVAL: Duration = Duration::from_millis(200);
+
 /// Error from sending a SendMessage request.
 #[derive(Debug, Error)]
 pub struct SendMessageError {
@@ -460,6 +467,7 @@ impl ResponseParser {
     async fn parse_tool_use(&mut self, id: String, name: String) -> Result<AssistantToolUse, RecvError> {
         let mut tool_string = String::new();
         let start = Instant::now();
+        let mut last_progress = start;
         while let Some(ChatResponseStream::ToolUseEvent { .. }) = self.peek().await? {
             if let Some(ChatResponseStream::ToolUseEvent { input, stop, .. }) = self.next().await? {
                 if let Some(i) = input {
@@ -469,6 +477,16 @@ impl ResponseParser {
                     break;
                 }
             }
+            if last_progress.elapsed() >= TOOL_USE_PROGRESS_INTERVAL {
+                last_progress = Instant::now();
+                // Progress is only informative, so it is dropped rather than waited on when the
+                // channel is full.
+                let _ = self.event_tx.try_send(Ok(ResponseEvent::ToolUseProgress {
+                    name: name.clone(),
+                    bytes: tool_string.len(),
+                    lines: count_escaped_lines(&tool_string),
+                }));
+            }
         }
 
         let args = match serde_json::from_str(&tool_string) {
@@ -625,6 +643,15 @@ pub enum ResponseEvent {
     AssistantText(String),
     /// Notification that a tool use is being received.
     ToolUseStart { name: String },
+    /// Periodic notification of how much of the arguments of the tool use being received have
+    /// arrived so far.
+    ToolUseProgress {
+        name: String,
+        /// Size of the JSON arguments received so far, in bytes
+        bytes: usize,
+        /// Number of lines of the string values in the arguments received so far
+        lines: usize,
+    },
     /// A tool use requested by the assistant. This should be displayed to the user as it is
     /// received.
     ToolUse(AssistantToolUse),
@@ -668,6 +695,35 @@ pub struct RequestMetadata {
     pub message_meta_tags: Vec<MessageMetaTag>,
 }
 
+impl RequestMetadata {
+    /// Total time from sending the request until the stream completed or ended in an error.
+    pub fn total_duration(&self) -> Duration {
+        Duration::from_millis(
+            self.stream_end_timestamp_ms
+                .saturating_sub(self.request_start_timestamp_ms),
+        )
+    }
+
+    /// Estimated number of output tokens received per second, measured from the first chunk
+    /// until the end of the stream.
+    ///
+    /// Returns [None] if no chunks were received or the stream ended immediately after the first
+    /// chunk.
+    pub fn output_tokens_per_second(&self) -> Option<f64> {
+        let streaming_duration = self.total_duration().checked_sub(self.time_to_first_chunk?)?;
+        if streaming_duration.is_zero() {
+            return None;
+        }
+        let output_tokens: TokenCount = CharCount::from(self.response_size).into();
+        Some(output_tokens.value() as f64 / streaming_duration.as_secs_f64())
+    }
+}
+
+/// Counts the lines of the partial JSON `args`, where newlines within strings are still escaped.
+fn count_escaped_lines(args: &str) -> usize {
+    args.matches("\\n").count() + 1
+}
+
 fn system_time_to_unix_ms(time: SystemTime) -> u64 {
     (time
         .duration_since(UNIX_EPOCH)
@@ -680,6 +736,33 @@ fn system_time_to_unix_ms(time: SystemTime) -> u64 {
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_request_metadata_output_tokens_per_second() {
+        let md = RequestMetadata {
+            request_start_timestamp_ms: 1_000,
+            stream_end_timestamp_ms: 3_000,
+            time_to_first_chunk: Some(Duration::from_millis(1_000)),
+            response_size: 400,
+            ..Default::default()
+        };
+        // 400 chars ~= 100 tokens streamed over 1 second.
+        assert_eq!(md.output_tokens_per_second(), Some(100.0));
+
+        let md = RequestMetadata {
+            time_to_first_chunk: None,
+            ..md
+        };
+        assert_eq!(md.output_tokens_per_second(), None);
+    }
+
+    #[test]
+    fn test_count_escaped_lines() {
+        assert_eq!(count_escaped_lines(r#"{"path": "/a.rs", "file_text": "fn main"#), 1);
+        let args = serde_json::json!({ "file_text": "fn main() {\n    println!();\n}\n" }).to_string();
+        assert_eq!(count_escaped_lines(&args[..args.len() - 5]), 3);
+        assert_eq!(count_escaped_lines(&args), 4);
+    }
+
     #[tokio::test]
     async fn test_response_parser_ignores_licensed_code() {
         // let _ = tracing_subscriber::fmt::try_init();
@@ -0,0 +1,255 @@
+//! Pausing a chat session to disk so that it can be picked up later with
+//! `q chat --resume-session <session id>`.
+//!
+//! Unlike `/save`, a paused session also records the active agent and the MCP servers that were
+//! loaded along with their tool specs. The servers themselves are reconnected on resume.
+
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+
+use crossterm::style::{
+    self,
+    Color,
+};
+use crossterm::{
+    execute,
+    queue,
+};
+use eyre::{
+    Result,
+    bail,
+    eyre,
+};
+use serde::{
+    Deserialize,
+    Serialize,
+};
+use sha2::{
+    Digest,
+    Sha256,
+};
+use time::OffsetDateTime;
+use time::macros::format_description;
+
+use super::cli::model::get_available_models;
+use super::conversation::ConversationState;
+use super::tools::{
+    ToolOrigin,
+    ToolSpec,
+    plain_output,
+};
+use super::util::ui::glyph;
+use super::{
+    ChatError,
+    ChatSession,
+};
+use crate::os::Os;
+use crate::util::directories;
+
+/// Number of hex characters of the conversation hash kept in a session id.
+const SESSION_HASH_LENGTH: usize = 8;
+
+/// A chat session as written by [ChatSession::serialize_pause].
+#[derive(Debug, Serialize, Deserialize)]
+pub struct PausedSession {
+    pub session_id: String,
+    #[serde(with = "time::serde::rfc3339")]
+    pub paused_at: OffsetDateTime,
+    /// Name of the agent that was active, see [crate::cli::agent::Agents::active_idx]
+    pub active_agent: String,
+    /// Tool specs of each MCP server that was loaded, by server name
+    pub mcp_servers: BTreeMap<String, Vec<ToolSpec>>,
+    pub conversation: ConversationState,
+}
+
+impl PausedSession {
+    /// Path the session with the given id is paused to.
+    pub fn path(os: &Os, session_id: &str) -> Result<PathBuf> {
+        if !is_valid_session_id(session_id) {
+            bail!("'{session_id}' is not a valid session id");
+        }
+        Ok(directories::chat_paused_sessions_dir(os)?.join(format!("{session_id}.json")))
+    }
+
+    pub async fn load(os: &Os, session_id: &str) -> Result<Self> {
+        let path = Self::path(os, session_id)?;
+        if !os.fs.exists(&path) {
+            return Err(eyre!("No paused session with id {session_id}"));
+        }
+        let contents = os.fs.read_to_string(&path).await?;
+        serde_json::from_str(&contents).map_err(|err| eyre!("Paused session {session_id} is corrupted: {err}"))
+    }
+}
+
+/// Builds a session id from the time the session was paused and a truncated hash of the
+/// serialized conversation, e.g. `20250101-120000-3fa2c1d8`.
+fn session_id(paused_at: OffsetDateTime, conversation: &[u8]) -> String {
+    let timestamp = paused_at
+        .format(format_description!("[year][month][day]-[hour][minute][second]"))
+        .unwrap_or_default();
+    let hash = Sha256::digest(conversation)
+        .iter()
+        .map(|byte| format!("{byte:02x}"))
+        .collect::<String>();
+    format!("{timestamp}-{}", &hash[..SESSION_HASH_LENGTH])
+}
+
+/// Whether `session_id` has the shape of the ids built by [session_id], so that a session id
+/// given on the command line cannot point outside of the paused sessions directory.
+fn is_valid_session_id(session_id: &str) -> bool {
+    match session_id.split('-').collect::<Vec<_>>().as_slice() {
+        [date, time, hash] => {
+            date.len() == 8
+                && time.len() == 6
+                && hash.len() == SESSION_HASH_LENGTH
+                && date.bytes().chain(time.bytes()).all(|b| b.is_ascii_digit())
+                && hash.bytes().all(|b| b.is_ascii_hexdigit())
+        },
+        _ => false,
+    }
+}
+
+impl ChatSession {
+    /// Writes the conversation, the active agent and the tools of the loaded MCP servers to the
+    /// paused sessions directory. Returns the id to pass to `q chat --resume-session`.
+    pub async fn serialize_pause(&self, os: &Os) -> Result<String> {
+        let tool_manager = &self.conversation.tool_manager;
+        let mut mcp_servers = tool_manager
+            .clients
+            .keys()
+            .map(|name| (name.clone(), Vec::new()))
+            .collect::<BTreeMap<_, _>>();
+        for spec in tool_manager.schema.values() {
+            if let ToolOrigin::McpServer(server) = &spec.tool_origin {
+                mcp_servers.entry(server.clone()).or_default().push(spec.clone());
+            }
+        }
+        for specs in mcp_servers.values_mut() {
+            specs.sort_by(|a, b| a.name.cmp(&b.name));
+        }
+
+        let conversation = serde_json::to_vec(&self.conversation)?;
+        let paused_at = OffsetDateTime::now_utc();
+        let session_id = session_id(paused_at, &conversation);
+        let paused = PausedSession {
+            session_id: session_id.clone(),
+            paused_at,
+            active_agent: self.conversation.agents.active_idx.clone(),
+            mcp_servers,
+            conversation: self.conversation.clone(),
+        };
+
+        let path = PausedSession::path(os, &session_id)?;
+        if let Some(parent) = path.parent() {
+            os.fs.create_dir_all(parent).await?;
+        }
+        os.fs.write(&path, serde_json::to_string_pretty(&paused)?).await?;
+        Ok(session_id)
+    }
+
+    /// Replaces the conversation of this session with the one of `paused`. The tool manager,
+    /// agents and context of this session are kept, as the servers and agents were loaded again
+    /// when the session started. Servers and tools that are missing since the pause are reported.
+    pub async fn restore_pause(&mut self, os: &Os, paused: PausedSession) -> Result<(), ChatError> {
+        let PausedSession {
+            session_id,
+            paused_at,
+            mcp_servers,
+            conversation: mut restored,
+            ..
+        } = paused;
+
+        std::mem::swap(&mut restored.tool_manager, &mut self.conversation.tool_manager);
+        std::mem::swap(&mut restored.agents, &mut self.conversation.agents);
+        std::mem::swap(&mut restored.context_manager, &mut self.conversation.context_manager);
+        std::mem::swap(&mut restored.mcp_enabled, &mut self.conversation.mcp_enabled);
+        restored.tool_manager.conversation_id = restored.conversation_id().to_string();
+        if let Some(saved) = restored.model_info.take() {
+            let (models, _) = get_available_models(os).await?;
+            restored.model_info = models.into_iter().find(|m| m.model_id == saved.model_id);
+            if restored.model_info.is_none() {
+                queue!(
+                    self.stderr,
+                    style::SetForegroundColor(Color::Yellow),
+                    style::Print(format!(
+                        "Model {} of the paused session is no longer available.\n",
+                        saved.display_name()
+                    )),
+                    style::ResetColor,
+                )?;
+            }
+        }
+        if restored.model_info.is_none() {
+            restored.model_info = self.conversation.model_info.take();
+        }
+
+        restored.update_state(true).await;
+        for (server, specs) in &mcp_servers {
+            let tool_manager = &restored.tool_manager;
+            if !tool_manager.clients.contains_key(server) {
+                queue!(
+                    self.stderr,
+                    style::SetForegroundColor(Color::Yellow),
+                    style::Print(format!("MCP server {server} of the paused session is not loaded.\n")),
+                    style::ResetColor,
+                )?;
+                continue;
+            }
+            let missing = specs
+                .iter()
+                .filter(|spec| !tool_manager.schema.contains_key(&spec.name))
+                .map(|spec| spec.name.as_str())
+                .collect::<Vec<_>>();
+            if !missing.is_empty() {
+                queue!(
+                    self.stderr,
+                    style::SetForegroundColor(Color::Yellow),
+                    style::Print(format!(
+                        "Tools of MCP server {server} that are no longer available: {}\n",
+                        missing.join(", ")
+                    )),
+                    style::ResetColor,
+                )?;
+            }
+        }
+
+        self.conversation = restored;
+        self.existing_conversation = true;
+        if self.initial_input.is_none() {
+            self.initial_input = Some("In a few words, summarize our conversation so far.".to_owned());
+        }
+        execute!(
+            self.stderr,
+            style::SetForegroundColor(Color::Green),
+            style::Print(format!(
+                "{}Resumed session {session_id}, paused on {} UTC\n",
+                glyph(plain_output(os), "✔ ", ""),
+                paused_at
+                    .format(format_description!("[year]-[month]-[day] [hour]:[minute]"))
+                    .unwrap_or_default()
+            )),
+            style::ResetColor,
+        )?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_session_id() {
+        let paused_at = OffsetDateTime::from_unix_timestamp(1_700_000_000).unwrap();
+        let id = session_id(paused_at, b"conversation");
+        assert!(id.starts_with("20231114-221320-"));
+        assert_eq!(id.len(), "20231114-221320-".len() + SESSION_HASH_LENGTH);
+        assert_ne!(id, session_id(paused_at, b"another conversation"));
+
+        assert!(is_valid_session_id(&id));
+        assert!(!is_valid_session_id("../../../.ssh/id_rsa"));
+        assert!(!is_valid_session_id("20231114-221320-../../x"));
+        assert!(!is_valid_session_id("20231114-221320-3fa2c1d8-extra"));
+        assert!(!is_valid_session_id(""));
+    }
+}
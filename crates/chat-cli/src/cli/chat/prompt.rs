@@ -56,8 +56,14 @@ pub const COMMANDS: &[&str] = &[
     "/tools untrust",
     "/tools trust-all",
     "/tools reset",
+    "/tools output",
     "/mcp",
+    "/mcp enable",
+    "/mcp disable",
+    "/mcp log",
     "/model",
+    "/model list",
+    "/model list --details",
     "/experiment",
     "/agent",
     "/agent help",
@@ -68,12 +74,14 @@ pub const COMMANDS: &[&str] = &[
     "/agent set",
     "/agent schema",
     "/agent generate",
+    "/agent status",
     "/prompts",
     "/context",
     "/context help",
     "/context show",
     "/context show --expand",
     "/context add",
+    "/context resources",
     "/context rm",
     "/context clear",
     "/hooks",
@@ -87,8 +95,12 @@ pub const COMMANDS: &[&str] = &[
     "/compact",
     "/compact help",
     "/usage",
+    "/usage --perf",
     "/save",
     "/load",
+    "/pause",
+    "/sessions",
+    "/sessions --all",
     "/subscribe",
     "/todos",
     "/todos resume",
@@ -97,6 +109,11 @@ pub const COMMANDS: &[&str] = &[
     "/todos delete",
 ];
 
+/// Context usage in percent from which the prompt gauge is shown in yellow.
+const GAUGE_YELLOW_PERCENT: usize = 50;
+/// Context usage in percent from which the prompt gauge is shown in red.
+const GAUGE_RED_PERCENT: usize = 80;
+
 pub type PromptQuerySender = tokio::sync::broadcast::Sender<PromptQuery>;
 pub type PromptQueryResponseReceiver = tokio::sync::broadcast::Receiver<PromptQueryResult>;
 
@@ -396,6 +413,17 @@ impl Highlighter for ChatHelper {
         if let Some(components) = parse_prompt_components(prompt) {
             let mut result = String::new();
 
+            // Add context usage gauge if present (green, yellow or red depending on usage)
+            if let Some(percent) = components.context_usage {
+                let gauge = format!("[{percent}%] ");
+                let gauge = match percent {
+                    p if p >= GAUGE_RED_PERCENT => gauge.red(),
+                    p if p >= GAUGE_YELLOW_PERCENT => gauge.yellow(),
+                    _ => gauge.green(),
+                };
+                result.push_str(&gauge.to_string());
+            }
+
             // Add profile part if present (cyan)
             if let Some(profile) = components.profile {
                 result.push_str(&format!("[{}] ", profile).cyan().to_string());
@@ -670,6 +698,31 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_highlight_prompt_context_gauge() {
+        let (prompt_request_sender, _) = tokio::sync::broadcast::channel::<PromptQuery>(1);
+        let (_, prompt_response_receiver) = tokio::sync::broadcast::channel::<PromptQueryResult>(1);
+        let helper = ChatHelper {
+            completer: ChatCompleter::new(prompt_request_sender, prompt_response_receiver),
+            hinter: ChatHinter::new(true),
+            validator: MultiLineValidator,
+        };
+
+        // Test gauge colors - green below 50%, yellow below 80%, red from 80%
+        let highlighted = helper.highlight_prompt("[12%] > ", true);
+        assert_eq!(highlighted, format!("{}{}", "[12%] ".green(), "> ".magenta()));
+        let highlighted = helper.highlight_prompt("[62%] [dev] > ", true);
+        assert_eq!(
+            highlighted,
+            format!("{}{}{}", "[62%] ".yellow(), "[dev] ".cyan(), "> ".magenta())
+        );
+        let highlighted = helper.highlight_prompt("[80%] !> ", true);
+        assert_eq!(
+            highlighted,
+            format!("{}{}{}", "[80%] ".red(), "!".red(), "> ".magenta())
+        );
+    }
+
     #[test]
     fn test_chat_hinter_command_hint() {
         let hinter = ChatHinter::new(true);
@@ -3,6 +3,8 @@ use crate::cli::agent::DEFAULT_AGENT_NAME;
 /// Components extracted from a prompt string
 #[derive(Debug, PartialEq)]
 pub struct PromptComponents {
+    /// Estimated context window usage in percent
+    pub context_usage: Option<usize>,
     pub profile: Option<String>,
     pub warning: bool,
     pub tangent_mode: bool,
@@ -11,12 +13,22 @@ pub struct PromptComponents {
 /// Parse prompt components from a plain text prompt
 pub fn parse_prompt_components(prompt: &str) -> Option<PromptComponents> {
     // Expected format: "[agent] !> " or "> " or "!> " or "[agent] ↯ > " or "↯ > " or "[agent] ↯ !> "
-    // etc.
+    // etc., optionally starting with the context usage gauge, e.g. "[62%] [agent] > "
+    let mut context_usage = None;
     let mut profile = None;
     let mut warning = false;
     let mut tangent_mode = false;
     let mut remaining = prompt.trim();
 
+    // Check for the context usage gauge [N%] first. Agent names cannot contain '%', so this never
+    // mistakes an agent for the gauge.
+    if let Some((gauge, rest)) = remaining.strip_prefix('[').and_then(|r| r.split_once("%]")) {
+        if let Ok(percent) = gauge.parse::<usize>() {
+            context_usage = Some(percent);
+            remaining = rest.trim_start();
+        }
+    }
+
     // Check for agent pattern [agent] first
     if let Some(start) = remaining.find('[') {
         if let Some(end) = remaining.find(']') {
@@ -43,6 +55,7 @@ pub fn parse_prompt_components(prompt: &str) -> Option<PromptComponents> {
     // Should end with "> " for both normal and tangent mode
     if remaining.trim_end() == ">" {
         Some(PromptComponents {
+            context_usage,
             profile,
             warning,
             tangent_mode,
@@ -52,18 +65,24 @@ pub fn parse_prompt_components(prompt: &str) -> Option<PromptComponents> {
     }
 }
 
-pub fn generate_prompt(current_profile: Option<&str>, warning: bool, tangent_mode: bool) -> String {
+pub fn generate_prompt(
+    current_profile: Option<&str>,
+    warning: bool,
+    tangent_mode: bool,
+    context_usage: Option<usize>,
+) -> String {
     // Generate plain text prompt that will be colored by highlight_prompt
     let warning_symbol = if warning { "!" } else { "" };
+    let gauge_part = context_usage.map(|p| format!("[{p}%] ")).unwrap_or_default();
     let profile_part = current_profile
         .filter(|&p| p != DEFAULT_AGENT_NAME)
         .map(|p| format!("[{p}] "))
         .unwrap_or_default();
 
     if tangent_mode {
-        format!("{profile_part}↯ {warning_symbol}> ")
+        format!("{gauge_part}{profile_part}↯ {warning_symbol}> ")
     } else {
-        format!("{profile_part}{warning_symbol}> ")
+        format!("{gauge_part}{profile_part}{warning_symbol}> ")
     }
 }
 
@@ -74,26 +93,49 @@ mod tests {
     #[test]
     fn test_generate_prompt() {
         // Test default prompt (no profile)
-        assert_eq!(generate_prompt(None, false, false), "> ");
+        assert_eq!(generate_prompt(None, false, false, None), "> ");
         // Test default prompt with warning
-        assert_eq!(generate_prompt(None, true, false), "!> ");
+        assert_eq!(generate_prompt(None, true, false, None), "!> ");
         // Test tangent mode
-        assert_eq!(generate_prompt(None, false, true), "↯ > ");
+        assert_eq!(generate_prompt(None, false, true, None), "↯ > ");
         // Test tangent mode with warning
-        assert_eq!(generate_prompt(None, true, true), "↯ !> ");
+        assert_eq!(generate_prompt(None, true, true, None), "↯ !> ");
         // Test default profile (should be same as no profile)
-        assert_eq!(generate_prompt(Some(DEFAULT_AGENT_NAME), false, false), "> ");
+        assert_eq!(generate_prompt(Some(DEFAULT_AGENT_NAME), false, false, None), "> ");
         // Test custom profile
-        assert_eq!(generate_prompt(Some("test-profile"), false, false), "[test-profile] > ");
+        assert_eq!(
+            generate_prompt(Some("test-profile"), false, false, None),
+            "[test-profile] > "
+        );
         // Test custom profile with tangent mode
         assert_eq!(
-            generate_prompt(Some("test-profile"), false, true),
+            generate_prompt(Some("test-profile"), false, true, None),
             "[test-profile] ↯ > "
         );
         // Test another custom profile with warning
-        assert_eq!(generate_prompt(Some("dev"), true, false), "[dev] !> ");
+        assert_eq!(generate_prompt(Some("dev"), true, false, None), "[dev] !> ");
         // Test custom profile with warning and tangent mode
-        assert_eq!(generate_prompt(Some("dev"), true, true), "[dev] ↯ !> ");
+        assert_eq!(generate_prompt(Some("dev"), true, true, None), "[dev] ↯ !> ");
+    }
+
+    #[test]
+    fn test_context_gauge() {
+        assert_eq!(generate_prompt(None, false, false, Some(62)), "[62%] > ");
+        assert_eq!(generate_prompt(Some("dev"), true, true, Some(5)), "[5%] [dev] ↯ !> ");
+
+        let components = parse_prompt_components("[62%] > ").unwrap();
+        assert_eq!(components.context_usage, Some(62));
+        assert!(components.profile.is_none());
+
+        let components = parse_prompt_components("[100%] [dev] ↯ !> ").unwrap();
+        assert_eq!(components.context_usage, Some(100));
+        assert_eq!(components.profile.as_deref(), Some("dev"));
+        assert!(components.warning);
+        assert!(components.tangent_mode);
+
+        let components = parse_prompt_components("[dev] > ").unwrap();
+        assert_eq!(components.context_usage, None);
+        assert_eq!(components.profile.as_deref(), Some("dev"));
     }
 
     #[test]
@@ -0,0 +1,143 @@
+use std::collections::HashMap;
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{
+    Hash,
+    Hasher,
+};
+use std::path::{
+    Path,
+    PathBuf,
+};
+use std::time::SystemTime;
+
+use similar::{
+    Algorithm,
+    ChangeTag,
+    capture_diff_slices,
+};
+
+use crate::os::Os;
+
+/// A file as it was when `fs_read` last returned it to the model. Only a hash of each line is
+/// kept, which is enough to count the lines changed since.
+#[derive(Debug, Clone)]
+struct ReadSnapshot {
+    modified: SystemTime,
+    line_hashes: Vec<u64>,
+}
+
+/// A change made to a file by something other than the agent since the agent last read it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ExternalModification {
+    /// When the file was last modified
+    pub modified: SystemTime,
+    /// Number of lines added or removed compared to the content the agent last read
+    pub changed_lines: usize,
+}
+
+/// Tracks the files read with `fs_read` during a session, so that `fs_write` edits can detect
+/// changes made to them outside of the chat in the meantime.
+#[derive(Debug, Default)]
+pub struct FileReadCache {
+    files: HashMap<PathBuf, ReadSnapshot>,
+}
+
+impl FileReadCache {
+    /// Records the current state of `path`. Files that cannot be read as text are forgotten.
+    pub async fn record_read(&mut self, os: &Os, path: &Path) {
+        match Self::snapshot(os, path).await {
+            Some(snapshot) => {
+                self.files.insert(path.to_path_buf(), snapshot);
+            },
+            None => {
+                self.files.remove(path);
+            },
+        }
+    }
+
+    /// Refreshes the state of `path` after the agent wrote to it, so that its own edits are not
+    /// reported as external modifications.
+    pub async fn record_write(&mut self, os: &Os, path: &Path) {
+        if self.files.contains_key(path) {
+            self.record_read(os, path).await;
+        }
+    }
+
+    /// Returns how `path` changed since it was last recorded, if it was modified externally.
+    pub async fn external_modification(&self, os: &Os, path: &Path) -> Option<ExternalModification> {
+        let previous = self.files.get(path)?;
+        let current = Self::snapshot(os, path).await?;
+        if current.modified == previous.modified {
+            return None;
+        }
+
+        let changed_lines = capture_diff_slices(Algorithm::Myers, &previous.line_hashes, &current.line_hashes)
+            .iter()
+            .flat_map(|op| op.iter_changes(&previous.line_hashes, &current.line_hashes))
+            .filter(|change| change.tag() != ChangeTag::Equal)
+            .count();
+
+        (changed_lines > 0).then_some(ExternalModification {
+            modified: current.modified,
+            changed_lines,
+        })
+    }
+
+    async fn snapshot(os: &Os, path: &Path) -> Option<ReadSnapshot> {
+        let modified = os.fs.metadata(path).await.ok()?.modified().ok()?;
+        let content = os.fs.read_to_string(path).await.ok()?;
+        let line_hashes = content
+            .lines()
+            .map(|line| {
+                let mut hasher = DefaultHasher::new();
+                line.hash(&mut hasher);
+                hasher.finish()
+            })
+            .collect();
+        Some(ReadSnapshot { modified, line_hashes })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::*;
+
+    #[tokio::test]
+    async fn test_external_modification() {
+        let os = Os::new().await.unwrap();
+        let path = PathBuf::from("/notes.txt");
+        os.fs.write(&path, "one\ntwo\nthree\n").await.unwrap();
+
+        let mut cache = FileReadCache::default();
+        assert!(cache.external_modification(&os, &path).await.is_none());
+
+        cache.record_read(&os, &path).await;
+        assert!(cache.external_modification(&os, &path).await.is_none());
+
+        // Make sure the modification time moves forward
+        tokio::time::sleep(Duration::from_millis(10)).await;
+        os.fs.write(&path, "one\n2\nthree\nfour\n").await.unwrap();
+        let modification = cache.external_modification(&os, &path).await.unwrap();
+        assert_eq!(modification.changed_lines, 3);
+
+        // Writes made by the agent are not reported
+        cache.record_write(&os, &path).await;
+        assert!(cache.external_modification(&os, &path).await.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_unchanged_content_is_not_reported() {
+        let os = Os::new().await.unwrap();
+        let path = PathBuf::from("/notes.txt");
+        os.fs.write(&path, "one\n").await.unwrap();
+
+        let mut cache = FileReadCache::default();
+        cache.record_read(&os, &path).await;
+
+        tokio::time::sleep(Duration::from_millis(10)).await;
+        os.fs.write(&path, "one\n").await.unwrap();
+        assert!(cache.external_modification(&os, &path).await.is_none());
+    }
+}
@@ -152,6 +152,10 @@ impl Messenger for ServerMessenger {
         });
     }
 
+    fn set_pid(&mut self, pid: Option<u32>) {
+        self.pid = pid;
+    }
+
     fn duplicate(&self) -> Box<dyn Messenger> {
         Box::new(self.clone())
     }
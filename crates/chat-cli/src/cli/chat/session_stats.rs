@@ -0,0 +1,129 @@
+use std::collections::BTreeMap;
+use std::io::Write;
+use std::time::{
+    Duration,
+    Instant,
+};
+
+use crossterm::execute;
+use crossterm::style::{
+    self,
+    Attribute,
+};
+
+/// Counters accumulated over the lifetime of a [super::ChatSession], printed as a summary when
+/// the session exits.
+#[derive(Debug, Clone)]
+pub struct SessionStats {
+    start: Instant,
+    /// Number of requests sent to the model
+    pub model_turns: u32,
+    /// Number of tool invocations, keyed by tool name
+    pub tool_invocations: BTreeMap<String, u32>,
+    /// Estimated number of tokens sent to the model
+    pub input_tokens: usize,
+    /// Estimated number of tokens received from the model
+    pub output_tokens: usize,
+}
+
+impl Default for SessionStats {
+    fn default() -> Self {
+        Self {
+            start: Instant::now(),
+            model_turns: 0,
+            tool_invocations: BTreeMap::new(),
+            input_tokens: 0,
+            output_tokens: 0,
+        }
+    }
+}
+
+impl SessionStats {
+    pub fn record_tool_invocation(&mut self, tool_name: &str) {
+        *self.tool_invocations.entry(tool_name.to_string()).or_default() += 1;
+    }
+
+    /// Prints the summary in a dim color. Nothing is printed if the model was never called.
+    pub fn print(&self, output: &mut impl Write) -> Result<(), std::io::Error> {
+        if self.model_turns == 0 {
+            return Ok(());
+        }
+        execute!(
+            output,
+            style::SetAttribute(Attribute::Dim),
+            style::Print(self.format(self.start.elapsed())),
+            style::SetAttribute(Attribute::Reset),
+        )
+    }
+
+    fn format(&self, duration: Duration) -> String {
+        let total_tools = self.tool_invocations.values().sum::<u32>();
+        let mut summary = format!(
+            "\nSession: {} turn{}, {} tool use{}",
+            self.model_turns,
+            if self.model_turns == 1 { "" } else { "s" },
+            total_tools,
+            if total_tools == 1 { "" } else { "s" },
+        );
+        if total_tools > 0 {
+            let breakdown = self
+                .tool_invocations
+                .iter()
+                .map(|(name, count)| format!("{name}: {count}"))
+                .collect::<Vec<_>>()
+                .join(", ");
+            summary.push_str(&format!(" ({breakdown})"));
+        }
+        summary.push_str(&format!(
+            "\n~{} tokens ({} in, {} out) in {}\n",
+            self.input_tokens + self.output_tokens,
+            self.input_tokens,
+            self.output_tokens,
+            format_duration(duration)
+        ));
+        summary
+    }
+}
+
+fn format_duration(duration: Duration) -> String {
+    let secs = duration.as_secs();
+    match (secs / 3600, secs % 3600 / 60, secs % 60) {
+        (0, 0, s) => format!("{s}s"),
+        (0, m, s) => format!("{m}m {s}s"),
+        (h, m, _) => format!("{h}h {m}m"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_format() {
+        let mut stats = SessionStats {
+            model_turns: 3,
+            input_tokens: 1200,
+            output_tokens: 300,
+            ..Default::default()
+        };
+        assert_eq!(
+            stats.format(Duration::from_secs(42)),
+            "\nSession: 3 turns, 0 tool uses\n~1500 tokens (1200 in, 300 out) in 42s\n"
+        );
+
+        stats.record_tool_invocation("fs_read");
+        stats.record_tool_invocation("fs_read");
+        stats.record_tool_invocation("execute_bash");
+        assert_eq!(
+            stats.format(Duration::from_secs(3725)),
+            "\nSession: 3 turns, 3 tool uses (execute_bash: 1, fs_read: 2)\n~1500 tokens (1200 in, 300 out) in 1h 2m\n"
+        );
+    }
+
+    #[test]
+    fn test_format_duration() {
+        assert_eq!(format_duration(Duration::from_secs(5)), "5s");
+        assert_eq!(format_duration(Duration::from_secs(65)), "1m 5s");
+        assert_eq!(format_duration(Duration::from_secs(7260)), "2h 1m");
+    }
+}
@@ -7,6 +7,13 @@ use super::message::{
     UserMessage,
     UserMessageContent,
 };
+use super::util::document_to_serde_value;
+use crate::api_client::model::{
+    ChatMessage,
+    ConversationState as FigConversationState,
+    ToolResultContentBlock,
+    UserInputMessage,
+};
 use crate::cli::chat::conversation::{
     BackendConversationState,
     ConversationSize,
@@ -167,6 +174,47 @@ impl CharCounter for &[ToolUseResult] {
     }
 }
 
+/// Counts the characters of a request as it is sent to the backend, including the full history.
+impl CharCounter for FigConversationState {
+    fn char_count(&self) -> CharCount {
+        let user_message_chars = |msg: &UserInputMessage| {
+            let tool_result_chars = msg
+                .user_input_message_context
+                .iter()
+                .flat_map(|ctx| ctx.tool_results.iter().flatten())
+                .flat_map(|result| &result.content)
+                .fold(0, |acc, block| {
+                    acc + match block {
+                        ToolResultContentBlock::Json(doc) => {
+                            calculate_value_char_count(&document_to_serde_value(doc.clone()))
+                        },
+                        ToolResultContentBlock::Text(s) => s.len(),
+                    }
+                });
+            msg.content.len() + tool_result_chars
+        };
+
+        let history_chars = self.history.iter().flatten().fold(0, |acc, msg| {
+            acc + match msg {
+                ChatMessage::UserInputMessage(msg) => user_message_chars(msg),
+                ChatMessage::AssistantResponseMessage(msg) => {
+                    msg.content.len()
+                        + msg
+                            .tool_uses
+                            .iter()
+                            .flatten()
+                            .map(|tool_use| {
+                                calculate_value_char_count(&document_to_serde_value(tool_use.input.clone().into()))
+                            })
+                            .sum::<usize>()
+                },
+            }
+        });
+
+        (history_chars + user_message_chars(&self.user_input_message)).into()
+    }
+}
+
 fn calculate_value_char_count(document: &serde_json::Value) -> usize {
     match document {
         serde_json::Value::Null => 1,
@@ -248,4 +296,30 @@ mod tests {
             0
         );
     }
+
+    #[test]
+    fn test_request_char_count() {
+        use crate::api_client::model::AssistantResponseMessage;
+
+        let user_message = |content: &str| UserInputMessage {
+            content: content.to_string(),
+            user_input_message_context: None,
+            user_intent: None,
+            images: None,
+            model_id: None,
+        };
+        let state = FigConversationState {
+            conversation_id: None,
+            user_input_message: user_message("hello"),
+            history: Some(vec![
+                ChatMessage::UserInputMessage(user_message("hi")),
+                ChatMessage::AssistantResponseMessage(AssistantResponseMessage {
+                    message_id: None,
+                    content: "how can I help".to_string(),
+                    tool_uses: None,
+                }),
+            ]),
+        };
+        assert_eq!(*state.char_count(), "hello".len() + "hi".len() + "how can I help".len());
+    }
 }
@@ -0,0 +1,62 @@
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{
+    Hash,
+    Hasher,
+};
+
+/// Detects the model calling the same tool with identical arguments over and over again.
+#[derive(Debug, Default)]
+pub struct ToolLoopDetector {
+    /// Hash of the tool name and arguments of the most recent call
+    last_call: Option<u64>,
+    /// Number of times in a row [Self::last_call] was made
+    repeats: usize,
+}
+
+impl ToolLoopDetector {
+    /// Records a tool call, returning how many times in a row this exact call has now been made.
+    pub fn record(&mut self, name: &str, args: &serde_json::Value) -> usize {
+        let mut hasher = DefaultHasher::new();
+        name.hash(&mut hasher);
+        args.to_string().hash(&mut hasher);
+        let call = hasher.finish();
+
+        if self.last_call == Some(call) {
+            self.repeats += 1;
+        } else {
+            self.last_call = Some(call);
+            self.repeats = 1;
+        }
+        self.repeats
+    }
+
+    /// Forgets the current streak of repeated calls.
+    pub fn reset(&mut self) {
+        *self = Self::default();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    #[test]
+    fn test_tool_loop_detector() {
+        let mut detector = ToolLoopDetector::default();
+        let args = json!({ "command": "ls" });
+
+        assert_eq!(detector.record("execute_bash", &args), 1);
+        assert_eq!(detector.record("execute_bash", &args), 2);
+        assert_eq!(detector.record("execute_bash", &args), 3);
+
+        // Different arguments or a different tool start a new streak
+        assert_eq!(detector.record("execute_bash", &json!({ "command": "pwd" })), 1);
+        assert_eq!(detector.record("fs_read", &json!({ "command": "pwd" })), 1);
+        assert_eq!(detector.record("fs_read", &json!({ "command": "pwd" })), 2);
+
+        detector.reset();
+        assert_eq!(detector.record("fs_read", &json!({ "command": "pwd" })), 1);
+    }
+}
@@ -1,8 +1,10 @@
 use std::borrow::Borrow;
+use std::cmp::Reverse;
 use std::collections::{
     HashMap,
     HashSet,
 };
+use std::fmt::Display;
 use std::future::Future;
 use std::hash::{
     DefaultHasher,
@@ -38,6 +40,10 @@ use futures::{
     stream,
 };
 use regex::Regex;
+use serde::de::DeserializeOwned;
+use strip_ansi_escapes::strip_str;
+use time::OffsetDateTime;
+use time::format_description::well_known::Rfc3339;
 use tokio::signal::ctrl_c;
 use tokio::sync::{
     Mutex,
@@ -59,6 +65,7 @@ use crate::api_client::model::{
 };
 use crate::cli::agent::{
     Agent,
+    DedupeMcpTools,
     McpServerConfig,
 };
 use crate::cli::chat::cli::prompts::GetPromptError;
@@ -68,6 +75,10 @@ use crate::cli::chat::server_messenger::{
     ServerMessengerBuilder,
     UpdateEventMessage,
 };
+use crate::cli::chat::tools::agent_delegate::{
+    AGENT_DELEGATE_TOOL_NAME,
+    AgentDelegate,
+};
 use crate::cli::chat::tools::custom_tool::{
     CustomTool,
     CustomToolClient,
@@ -78,13 +89,25 @@ use crate::cli::chat::tools::fs_write::FsWrite;
 use crate::cli::chat::tools::gh_issue::GhIssue;
 use crate::cli::chat::tools::introspect::Introspect;
 use crate::cli::chat::tools::knowledge::Knowledge;
+use crate::cli::chat::tools::parse_error::ToolParseError;
 use crate::cli::chat::tools::thinking::Thinking;
 use crate::cli::chat::tools::todo::TodoList;
 use crate::cli::chat::tools::use_aws::UseAws;
 use crate::cli::chat::tools::{
     Tool,
+    ToolErrorKind,
     ToolOrigin,
     ToolSpec,
+    plain_output,
+};
+use crate::cli::chat::util::serde_value_to_document;
+use crate::cli::chat::util::ui::{
+    truncate_with_ellipsis,
+    wrap_width,
+};
+use crate::cli::chat::{
+    ChatError,
+    ChatErrorKind,
 };
 use crate::database::Database;
 use crate::database::settings::Setting;
@@ -92,17 +115,27 @@ use crate::mcp_client::{
     JsonRpcResponse,
     Messenger,
     PromptGet,
+    ResourceInfo,
+    ResourceReadContents,
+    ResourceReadResult,
 };
 use crate::os::Os;
 use crate::telemetry::TelemetryThread;
 use crate::util::MCP_SERVER_TOOL_DELIMITER;
-use crate::util::directories::home_dir;
+use crate::util::directories::{
+    home_dir,
+    logs_dir,
+};
 
 const NAMESPACE_DELIMITER: &str = "___";
 // This applies for both mcp server and tool name since in the end the tool name as seen by the
 // model is just {server_name}{NAMESPACE_DELIMITER}{tool_name}
 const VALID_TOOL_NAME: &str = "^[a-zA-Z][a-zA-Z0-9_]*$";
 const SPINNER_CHARS: [char; 10] = ['⠋', '⠙', '⠹', '⠸', '⠼', '⠴', '⠦', '⠧', '⠇', '⠏'];
+const PLAIN_SPINNER_CHARS: [char; 4] = ['|', '/', '-', '\\'];
+/// How long each MCP server is given to exit on its own when the session ends before it is
+/// terminated.
+const MCP_SHUTDOWN_TIMEOUT: Duration = Duration::from_secs(2);
 
 pub fn workspace_mcp_config_path(os: &Os) -> eyre::Result<PathBuf> {
     Ok(os.env.current_dir()?.join(".amazonq").join("mcp.json"))
@@ -160,6 +193,7 @@ pub struct ToolManagerBuilder {
     has_new_stuff: Arc<AtomicBool>,
     mcp_load_record: Arc<Mutex<HashMap<String, Vec<LoadingRecord>>>>,
     new_tool_specs: NewToolSpecs,
+    resources: ServerResources,
     is_first_launch: bool,
     agent: Option<Arc<Mutex<Agent>>>,
 }
@@ -176,6 +210,7 @@ impl Default for ToolManagerBuilder {
             has_new_stuff: Default::default(),
             mcp_load_record: Default::default(),
             new_tool_specs: Default::default(),
+            resources: Default::default(),
             is_first_launch: true,
             agent: Default::default(),
         }
@@ -196,6 +231,7 @@ impl From<&mut ToolManager> for ToolManagerBuilder {
             has_new_stuff: value.has_new_stuff.clone(),
             mcp_load_record: value.mcp_load_record.clone(),
             new_tool_specs: value.new_tool_specs.clone(),
+            resources: value.resources.clone(),
             // if we are getting a builder from an instantiated tool manager this field would be
             // false
             is_first_launch: false,
@@ -258,6 +294,7 @@ impl ToolManagerBuilder {
         };
         debug_assert!(self.conversation_id.is_some());
         let conversation_id = self.conversation_id.ok_or(eyre::eyre!("Missing conversation id"))?;
+        let plain = plain_output(os);
 
         // Separate enabled and disabled servers
         let (enabled_servers, disabled_servers): (Vec<_>, Vec<_>) = mcp_servers
@@ -270,33 +307,35 @@ impl ToolManagerBuilder {
             .map(|(server_name, _)| server_name.clone())
             .collect();
 
+        let required_servers = enabled_servers
+            .iter()
+            .filter(|(_, server_config)| server_config.required)
+            .map(|(server_name, _)| server_name.clone())
+            .collect::<Vec<_>>();
+
+        // Remote servers are shown alongside their (masked) url while loading
+        let remote_server_urls = enabled_servers
+            .iter()
+            .filter(|(_, server_config)| server_config.url.is_some())
+            .map(|(server_name, server_config)| (server_name.clone(), server_config.display_target()))
+            .collect::<HashMap<_, _>>();
+
         let pre_initialized = enabled_servers
             .into_iter()
             .filter_map(|(server_name, server_config)| {
                 if server_name == "builtin" {
-                    let _ = queue!(
-                        output,
-                        style::SetForegroundColor(style::Color::Red),
-                        style::Print("✗ Invalid server name "),
-                        style::SetForegroundColor(style::Color::Blue),
-                        style::Print(&server_name),
-                        style::ResetColor,
-                        style::Print(". Server name cannot contain reserved word "),
-                        style::SetForegroundColor(style::Color::Yellow),
-                        style::Print("builtin"),
-                        style::ResetColor,
-                        style::Print(" (it is used to denote native tools)\n")
-                    );
+                    let _ = queue_reserved_name_message(&server_name, plain, &mut output);
                     None
                 } else {
+                    let priority = server_config.priority.unwrap_or_default();
                     let custom_tool_client = CustomToolClient::from_config(server_name.clone(), server_config, os);
-                    Some((server_name, custom_tool_client))
+                    Some((server_name, priority, custom_tool_client))
                 }
             })
-            .collect::<Vec<(String, _)>>();
+            .collect::<Vec<(String, i32, _)>>();
 
         let mut loading_servers = HashMap::<String, Instant>::new();
-        for (server_name, _) in &pre_initialized {
+        for (server_name, _, _) in &pre_initialized {
             let init_time = std::time::Instant::now();
             loading_servers.insert(server_name.clone(), init_time);
         }
@@ -305,11 +344,20 @@ impl ToolManagerBuilder {
         // Spawn a task for displaying the mcp loading statuses.
         // This is only necessary when we are in interactive mode AND there are servers to load.
         // Otherwise we do not need to be spawning this.
-        let (loading_display_task, loading_status_sender) =
-            spawn_display_task(interactive, total, disabled_servers, output);
+        let (loading_display_task, loading_status_sender) = spawn_display_task(
+            interactive,
+            plain,
+            wrap_width(os),
+            total,
+            disabled_servers,
+            remote_server_urls,
+            output,
+        );
 
         let mut clients = HashMap::<String, Arc<CustomToolClient>>::new();
+        let mut init_priorities = HashMap::<String, i32>::new();
         let new_tool_specs = self.new_tool_specs;
+        let resources = self.resources;
         let has_new_stuff = self.has_new_stuff;
         let pending = Arc::new(RwLock::new(HashSet::<String>::new()));
         let notify = Arc::new(Notify::new());
@@ -332,6 +380,7 @@ impl ToolManagerBuilder {
             let telemetry = os.telemetry.clone();
             let loading_status_sender = loading_status_sender.clone();
             let new_tool_specs = new_tool_specs.clone();
+            let resources = resources.clone();
             let conv_id = conversation_id.clone();
             let pending = pending.clone();
             let regex = Regex::new(VALID_TOOL_NAME)?;
@@ -351,27 +400,32 @@ impl ToolManagerBuilder {
                 telemetry,
                 loading_status_sender,
                 new_tool_specs,
+                resources,
                 total,
                 conv_id,
+                plain,
             );
         }
 
         debug_assert!(messenger_builder.is_some());
         let messenger_builder = messenger_builder.unwrap();
-        for (mut name, init_res) in pre_initialized {
-            let mut messenger = messenger_builder.build_with_name(name.clone());
+        for (mut name, mut priority, init_res) in pre_initialized {
+            let messenger = messenger_builder.build_with_name(name.clone());
             match init_res {
                 Ok(mut client) => {
-                    let pid = client.get_pid();
-                    messenger.pid = pid;
+                    // The process of stdio servers is only spawned once they are initialized, which
+                    // also gives the messenger the id of the process
                     client.assign_messenger(Box::new(messenger));
                     let mut client = Arc::new(client);
                     while let Some(collided_client) = clients.insert(name.clone(), client) {
                         // to avoid server name collision we are going to circumvent this by
                         // appending the name with 1
+                        let collided_priority = init_priorities.insert(name.clone(), priority).unwrap_or_default();
                         name.push('1');
                         client = collided_client;
+                        priority = collided_priority;
                     }
+                    init_priorities.insert(name, priority);
                 },
                 Err(e) => {
                     error!("Error initializing mcp client for server {}: {:?}", name, &e);
@@ -396,16 +450,19 @@ impl ToolManagerBuilder {
         Ok(ToolManager {
             conversation_id,
             clients,
+            init_priorities,
             pending_clients: pending,
             notify: Some(notify),
             loading_status_sender,
             loading_display_task,
             new_tool_specs,
+            resources,
             has_new_stuff,
             is_interactive: interactive,
             mcp_load_record: load_record,
             agent,
             disabled_servers: disabled_servers_display,
+            required_servers,
             prompts_sender_receiver_pair: {
                 if let (Some(sender), Some(receiver)) = (self.prompt_query_sender, self.prompt_query_result_receiver) {
                     Some((sender, receiver))
@@ -431,6 +488,41 @@ pub struct PromptBundle {
     pub prompt_get: PromptGet,
 }
 
+#[derive(Debug, thiserror::Error)]
+pub enum ResourceError {
+    #[error("No server named {0} is connected")]
+    ServerNotFound(String),
+    #[error("Server returned an error: {0}")]
+    Server(String),
+    #[error("Server returned no contents for {0}")]
+    EmptyResult(String),
+    #[error("{uri} has binary content ({mime_type}) and cannot be added to context")]
+    Binary { uri: String, mime_type: String },
+    #[error(transparent)]
+    General(#[from] eyre::Report),
+}
+
+/// Inlines the textual contents of a resources/read result. Binary contents are rejected since
+/// only text can be added to context.
+fn resource_read_result_to_text(uri: &str, result: ResourceReadResult) -> Result<String, ResourceError> {
+    if result.contents.is_empty() {
+        return Err(ResourceError::EmptyResult(uri.to_string()));
+    }
+    let mut texts = Vec::with_capacity(result.contents.len());
+    for contents in result.contents {
+        match contents {
+            ResourceReadContents::Text { text, .. } => texts.push(text),
+            ResourceReadContents::Blob { uri, mime_type, .. } => {
+                return Err(ResourceError::Binary {
+                    uri,
+                    mime_type: mime_type.unwrap_or_else(|| "unknown type".to_string()),
+                });
+            },
+        }
+    }
+    Ok(texts.join("\n"))
+}
+
 #[derive(Clone, Debug)]
 pub enum PromptQuery {
     List,
@@ -490,6 +582,9 @@ type ServerName = String;
 /// tool name).
 type NewToolSpecs = Arc<Mutex<HashMap<ServerName, (HashMap<ModelToolName, ToolInfo>, Vec<ToolSpec>)>>>;
 
+/// Resources advertised by each server, as of their latest resources/list result.
+type ServerResources = Arc<Mutex<HashMap<ServerName, Vec<ResourceInfo>>>>;
+
 /// A pair of channels used for prompt list communication between the tool manager and chat helper.
 /// The sender broadcasts a list of available prompt names, while the receiver listens for
 /// search queries to filter the prompt list.
@@ -511,6 +606,10 @@ pub struct ToolManager {
     /// These clients are used to communicate with MCP servers.
     pub clients: HashMap<String, Arc<CustomToolClient>>,
 
+    /// The configured priority of each client in [Self::clients]. Clients with a higher priority
+    /// are initialized first.
+    init_priorities: HashMap<String, i32>,
+
     /// A list of client names that are still in the process of being initialized
     pub pending_clients: Arc<RwLock<HashSet<String>>>,
 
@@ -528,6 +627,10 @@ pub struct ToolManager {
     /// from server initialization processes.
     new_tool_specs: NewToolSpecs,
 
+    /// A cache of resources advertised by connected servers. This is kept up to date by the
+    /// orchestrator task as servers are loaded and as they notify us of resource list changes.
+    resources: ServerResources,
+
     /// A notifier to understand if the initial loading has completed.
     /// This is only used for initial loading and is discarded after.
     notify: Option<Arc<Notify>>,
@@ -545,6 +648,10 @@ pub struct ToolManager {
     /// to ensure tool names comply with naming requirements.
     pub tn_map: HashMap<ModelToolName, ToolInfo>,
 
+    /// The reverse of [Self::tn_map], from the server and the tool name it exposed to the name
+    /// the model knows the tool by. Rebuilt whenever [Self::tn_map] changes.
+    host_to_model_map: HashMap<(ServerName, HostToolName), ModelToolName>,
+
     /// A cache of tool's input schema for all of the available tools.
     /// This is mainly used to show the user what the tools look like from the perspective of the
     /// model.
@@ -562,6 +669,9 @@ pub struct ToolManager {
     /// List of disabled MCP server names for display purposes
     disabled_servers: Vec<String>,
 
+    /// List of MCP server names that must load for the session to start
+    required_servers: Vec<String>,
+
     /// A builder for mcp clients to communicate with the orchestrator task
     /// We need to store this for when we switch agent - we need to be spawning messengers that are
     /// already listened to by the orchestrator task
@@ -582,11 +692,14 @@ impl Clone for ToolManager {
             clients: self.clients.clone(),
             has_new_stuff: self.has_new_stuff.clone(),
             new_tool_specs: self.new_tool_specs.clone(),
+            resources: self.resources.clone(),
             tn_map: self.tn_map.clone(),
+            host_to_model_map: self.host_to_model_map.clone(),
             schema: self.schema.clone(),
             is_interactive: self.is_interactive,
             mcp_load_record: self.mcp_load_record.clone(),
             disabled_servers: self.disabled_servers.clone(),
+            required_servers: self.required_servers.clone(),
             ..Default::default()
         }
     }
@@ -610,6 +723,7 @@ impl ToolManager {
         drop(agent_lock);
 
         self.mcp_load_record.lock().await.clear();
+        self.resources.lock().await.clear();
 
         let builder = ToolManagerBuilder::from(&mut *self);
         let mut new_tool_manager = builder.build(os, Box::new(std::io::sink()), true).await?;
@@ -622,6 +736,14 @@ impl ToolManager {
         Ok(())
     }
 
+    /// Shuts down every connected MCP server, giving them [MCP_SHUTDOWN_TIMEOUT] to exit on their
+    /// own, e.g. to flush their state, before they are terminated. Servers are shut down
+    /// concurrently.
+    pub async fn shutdown(&mut self) {
+        let clients = std::mem::take(&mut self.clients);
+        future::join_all(clients.values().map(|client| client.shutdown(MCP_SHUTDOWN_TIMEOUT))).await;
+    }
+
     pub async fn load_tools(
         &mut self,
         os: &mut Os,
@@ -630,7 +752,8 @@ impl ToolManager {
         let tx = self.loading_status_sender.take();
         let notify = self.notify.take();
         self.schema = {
-            let tool_list = &self.agent.lock().await.tools;
+            let agent = self.agent.lock().await;
+            let tool_list = &agent.tools;
             let is_allow_all = tool_list.len() == 1 && tool_list.first().is_some_and(|n| n == "*");
             let is_allow_native = tool_list.iter().any(|t| t.as_str() == "@builtin");
             let mut tool_specs =
@@ -653,6 +776,9 @@ impl ToolManager {
             if !crate::cli::chat::tools::todo::TodoList::is_enabled(os) {
                 tool_specs.remove("todo_list");
             }
+            if !agent.delegation_enabled {
+                tool_specs.remove(AGENT_DELEGATE_TOOL_NAME);
+            }
 
             #[cfg(windows)]
             {
@@ -684,40 +810,54 @@ impl ToolManager {
 
             tool_specs
         };
-        let load_tools = self
+        let init_concurrency = os
+            .database
+            .settings
+            .get_int(Setting::McpInitConcurrency)
+            .map_or(20_usize, |n| n.max(1) as usize);
+        let init_timeout = Duration::from_millis(if self.is_interactive {
+            os.database
+                .settings
+                .get_int(Setting::McpInitTimeout)
+                .map_or(5000_u64, |s| s as u64)
+        } else {
+            // if it is non-interactive we will want to use the "mcp.noInteractiveTimeout"
+            os.database
+                .settings
+                .get_int(Setting::McpNoInteractiveTimeout)
+                .map_or(30_000_u64, |s| s as u64)
+        });
+        let inits = self
             .clients
-            .values()
-            .map(|c| {
+            .iter()
+            .map(|(name, c)| {
                 let clone = Arc::clone(c);
-                async move { clone.init().await }
+                let priority = self.init_priorities.get(name).copied().unwrap_or_default();
+                (priority, async move {
+                    if let Err(e) = clone.init().await {
+                        error!(
+                            "Error initializing mcp client for server {}: {:?}",
+                            clone.get_server_name(),
+                            e
+                        );
+                    }
+                })
             })
             .collect::<Vec<_>>();
-        let initial_poll = stream::iter(load_tools)
-            .map(|async_closure| tokio::spawn(async_closure))
-            .buffer_unordered(20);
-        tokio::spawn(async move {
-            initial_poll.collect::<Vec<_>>().await;
-        });
+        let initial_poll = tokio::spawn(init_by_priority(inits, init_concurrency, init_timeout));
         // We need to cast it to erase the type otherwise the compiler will default to static
         // dispatch, which would result in an error of inconsistent match arm return type.
         let timeout_fut: Pin<Box<dyn Future<Output = ()>>> = if self.clients.is_empty() || !self.is_first_launch {
             // If there is no server loaded, we want to resolve immediately
             Box::pin(future::ready(()))
-        } else if self.is_interactive {
-            let init_timeout = os
-                .database
-                .settings
-                .get_int(Setting::McpInitTimeout)
-                .map_or(5000_u64, |s| s as u64);
-            Box::pin(tokio::time::sleep(std::time::Duration::from_millis(init_timeout)))
         } else {
-            // if it is non-interactive we will want to use the "mcp.noInteractiveTimeout"
-            let init_timeout = os
-                .database
-                .settings
-                .get_int(Setting::McpNoInteractiveTimeout)
-                .map_or(30_000_u64, |s| s as u64);
-            Box::pin(tokio::time::sleep(std::time::Duration::from_millis(init_timeout)))
+            // Each server is given the timeout from when it starts initializing, so servers
+            // waiting for their turn are not cut short
+            Box::pin(async move {
+                if let Ok(Some(last_start)) = initial_poll.await {
+                    tokio::time::sleep_until((last_start + init_timeout).into()).await;
+                }
+            })
         };
         let server_loading_fut: Pin<Box<dyn Future<Output = ()>>> = if let Some(notify) = notify {
             Box::pin(async move { notify.notified().await })
@@ -781,36 +921,83 @@ impl ToolManager {
             )?;
         }
         stderr.flush()?;
+        if !self.clients.is_empty() {
+            if let Err(err) = self.persist_load_record(os).await {
+                warn!(?err, "Failed to write the mcp server load record");
+            }
+        }
+        // Required servers are only enforced on the initial load since subsequent loads (e.g.
+        // swapping agents) do not wait on the servers
+        if self.is_first_launch {
+            let loading_required_servers = self.loading_required_servers().await;
+            if !loading_required_servers.is_empty() {
+                // Required servers are given their own timeout rather than being cut short by
+                // the one of the other servers
+                let required_timeout = Duration::from_millis(
+                    os.database
+                        .settings
+                        .get_int(Setting::McpRequiredInitTimeout)
+                        .map_or(60_000_u64, |s| s as u64),
+                );
+                queue!(
+                    stderr,
+                    style::Print(format!(
+                        "Waiting for required mcp server(s) to load: {}\n",
+                        loading_required_servers.join(", ")
+                    ))
+                )?;
+                stderr.flush()?;
+                tokio::select! {
+                    _ = tokio::time::timeout(required_timeout, self.wait_for_required_servers()) => {},
+                    _ = ctrl_c() => {
+                        return Err(eyre::eyre!("User interrupted loading of the required mcp servers. Ending."));
+                    }
+                }
+            }
+            let failed_required_servers = self.failed_required_servers().await;
+            if !failed_required_servers.is_empty() {
+                return Err(ChatError::with_hint(
+                    ChatErrorKind::McpServer,
+                    format!(
+                        "Required mcp server(s) failed to load: {}. See $TMPDIR/qlog/chat.log for more details",
+                        failed_required_servers.join(", ")
+                    ),
+                    "Check the configuration of the server and that its command can be run",
+                )
+                .into());
+            }
+            let loading_required_servers = self.loading_required_servers().await;
+            if !loading_required_servers.is_empty() {
+                return Err(ChatError::with_hint(
+                    ChatErrorKind::McpServer,
+                    format!(
+                        "Required mcp server(s) did not finish loading: {}. See $TMPDIR/qlog/chat.log for more details",
+                        loading_required_servers.join(", ")
+                    ),
+                    "If the server is slow to start, increase its load timeout with `q settings mcp.requiredInitTimeout`",
+                )
+                .into());
+            }
+        }
         self.update().await;
         Ok(self.schema.clone())
     }
 
     pub fn get_tool_from_tool_use(&self, value: AssistantToolUse) -> Result<Tool, ToolResult> {
-        let map_err = |parse_error| ToolResult {
-            tool_use_id: value.id.clone(),
-            content: vec![ToolResultContentBlock::Text(format!(
-                "Failed to validate tool parameters: {parse_error}. The model has either suggested tool parameters which are incompatible with the existing tools, or has suggested one or more tool that does not exist in the list of known tools."
-            ))],
-            status: ToolResultStatus::Error,
-        };
-
         Ok(match value.name.as_str() {
-            "fs_read" => Tool::FsRead(serde_json::from_value::<FsRead>(value.args).map_err(map_err)?),
-            "fs_write" => Tool::FsWrite(serde_json::from_value::<FsWrite>(value.args).map_err(map_err)?),
+            "fs_read" => Tool::FsRead(self.parse_tool_args::<FsRead>(&value)?),
+            "fs_write" => Tool::FsWrite(self.parse_tool_args::<FsWrite>(&value)?),
             #[cfg(windows)]
-            "execute_cmd" => {
-                Tool::ExecuteCommand(serde_json::from_value::<ExecuteCommand>(value.args).map_err(map_err)?)
-            },
+            "execute_cmd" => Tool::ExecuteCommand(self.parse_tool_args::<ExecuteCommand>(&value)?),
             #[cfg(not(windows))]
-            "execute_bash" => {
-                Tool::ExecuteCommand(serde_json::from_value::<ExecuteCommand>(value.args).map_err(map_err)?)
-            },
-            "use_aws" => Tool::UseAws(serde_json::from_value::<UseAws>(value.args).map_err(map_err)?),
-            "report_issue" => Tool::GhIssue(serde_json::from_value::<GhIssue>(value.args).map_err(map_err)?),
-            "introspect" => Tool::Introspect(serde_json::from_value::<Introspect>(value.args).map_err(map_err)?),
-            "thinking" => Tool::Thinking(serde_json::from_value::<Thinking>(value.args).map_err(map_err)?),
-            "knowledge" => Tool::Knowledge(serde_json::from_value::<Knowledge>(value.args).map_err(map_err)?),
-            "todo_list" => Tool::Todo(serde_json::from_value::<TodoList>(value.args).map_err(map_err)?),
+            "execute_bash" => Tool::ExecuteCommand(self.parse_tool_args::<ExecuteCommand>(&value)?),
+            "use_aws" => Tool::UseAws(self.parse_tool_args::<UseAws>(&value)?),
+            "report_issue" => Tool::GhIssue(self.parse_tool_args::<GhIssue>(&value)?),
+            "introspect" => Tool::Introspect(self.parse_tool_args::<Introspect>(&value)?),
+            "thinking" => Tool::Thinking(self.parse_tool_args::<Thinking>(&value)?),
+            "knowledge" => Tool::Knowledge(self.parse_tool_args::<Knowledge>(&value)?),
+            "todo_list" => Tool::Todo(self.parse_tool_args::<TodoList>(&value)?),
+            AGENT_DELEGATE_TOOL_NAME => Tool::AgentDelegate(self.parse_tool_args::<AgentDelegate>(&value)?),
             // Note that this name is namespaced with server_name{DELIMITER}tool_name
             name => {
                 // Note: tn_map also has tools that underwent no transformation. In otherwords, if
@@ -824,9 +1011,12 @@ impl ToolManager {
                         // No match, we throw an error
                         Err(ToolResult {
                             tool_use_id: value.id.clone(),
-                            content: vec![ToolResultContentBlock::Text(format!(
-                                "No tool with \"{name}\" is found"
-                            ))],
+                            content: vec![
+                                ToolResultContentBlock::Text(format!("No tool with \"{name}\" is found")),
+                                ToolResultContentBlock::Json(serde_value_to_document(
+                                    ToolErrorKind::UnknownTool.to_json(),
+                                )),
+                            ],
                             status: ToolResultStatus::Error,
                         })
                     },
@@ -834,9 +1024,12 @@ impl ToolManager {
                 let Some(client) = self.clients.get(server_name) else {
                     return Err(ToolResult {
                         tool_use_id: value.id,
-                        content: vec![ToolResultContentBlock::Text(format!(
-                            "The tool, \"{server_name}\" is not supported by the client"
-                        ))],
+                        content: vec![
+                            ToolResultContentBlock::Text(format!(
+                                "The tool, \"{server_name}\" is not supported by the client"
+                            )),
+                            ToolResultContentBlock::Json(serde_value_to_document(ToolErrorKind::Unavailable.to_json())),
+                        ],
                         status: ToolResultStatus::Error,
                     });
                 };
@@ -854,12 +1047,42 @@ impl ToolManager {
                     client: client.clone(),
                     method: "tools/call".to_owned(),
                     params: Some(params),
+                    delegate_clients: self.clients.clone(),
+                    delegation_path: Default::default(),
                 };
                 Tool::Custom(custom_tool)
             },
         })
     }
 
+    /// Parses the arguments of a native tool use. If they are rejected, the result tells the model
+    /// which field does not match the tool's schema and how to correct it, see [ToolParseError].
+    fn parse_tool_args<T: DeserializeOwned>(&self, value: &AssistantToolUse) -> Result<T, ToolResult> {
+        T::deserialize(&value.args).map_err(|err| {
+            let parse_error = self
+                .schema
+                .get(&value.name)
+                .and_then(|spec| ToolParseError::from_schema(&spec.input_schema, &value.args));
+            let text = match parse_error {
+                Some(parse_error) => format!(
+                    "Failed to validate tool parameters: {err}. Retry the tool use with the suggested correction applied:\n{}",
+                    parse_error.to_json()
+                ),
+                None => format!(
+                    "Failed to validate tool parameters: {err}. The model has either suggested tool parameters which are incompatible with the existing tools, or has suggested one or more tool that does not exist in the list of known tools."
+                ),
+            };
+            ToolResult {
+                tool_use_id: value.id.clone(),
+                content: vec![
+                    ToolResultContentBlock::Text(text),
+                    ToolResultContentBlock::Json(serde_value_to_document(ToolErrorKind::InvalidInput.to_json())),
+                ],
+                status: ToolResultStatus::Error,
+            }
+        })
+    }
+
     /// Updates tool managers various states with new information
     pub async fn update(&mut self) {
         // A hashmap of <tool name, tool spec>
@@ -875,12 +1098,26 @@ impl ToolManager {
             )
         };
 
+        let dedupe = self.agent.lock().await.dedupe_mcp_tools.clone();
         let mut updated_servers = HashSet::<ToolOrigin>::new();
         let mut conflicts = HashMap::<ServerName, String>::new();
-        for (server_name, (tool_name_map, specs)) in new_tools {
+        let mut duplicates = HashMap::<ServerName, String>::new();
+        for (server_name, (mut tool_name_map, specs)) in new_tools {
             // First we evict the tools that were already in the tn_map
             self.tn_map.retain(|_, tool_info| tool_info.server_name != server_name);
 
+            // Tools that another server already provides are dropped before checking for conflicts
+            if dedupe.enabled {
+                self.dedupe_tools(
+                    &dedupe,
+                    &server_name,
+                    &mut tool_name_map,
+                    &specs,
+                    &mut tool_specs,
+                    &mut duplicates,
+                );
+            }
+
             // And update them with the new tools queried
             // valid: tools that do not have conflicts in naming
             let (valid, invalid) = tool_name_map
@@ -909,6 +1146,7 @@ impl ToolManager {
 
             self.tn_map.extend(valid);
         }
+        self.update_host_to_model_map();
 
         // Update schema
         // As we are writing over the ensemble of tools in a given server, we will need to first
@@ -918,10 +1156,18 @@ impl ToolManager {
         self.schema.extend(tool_specs);
 
         // if block here to avoid repeatedly asking for loc
-        if !conflicts.is_empty() {
+        if !conflicts.is_empty() || !duplicates.is_empty() {
             let mut record_lock = self.mcp_load_record.lock().await;
-            for (server_name, msg) in conflicts {
-                let record = LoadingRecord::Err(msg);
+            let conflicts = conflicts
+                .into_iter()
+                .map(|(server_name, msg)| (server_name, LoadingRecord::Err(msg)));
+            let duplicates = duplicates.into_iter().map(|(server_name, list)| {
+                let msg = format!(
+                    "The following tools are not loaded because another server provides an identical tool:\n{list}"
+                );
+                (server_name, LoadingRecord::Warn(msg))
+            });
+            for (server_name, record) in conflicts.chain(duplicates) {
                 record_lock
                     .entry(server_name)
                     .and_modify(|v| v.push(record.clone()))
@@ -930,6 +1176,76 @@ impl ToolManager {
         }
     }
 
+    /// Returns the name the model knows the tool `host_name` of `server` by, which differs from
+    /// `host_name` when the tool was aliased or its name had to be sanitized.
+    pub fn get_model_name(&self, server: &str, host_name: &str) -> Option<&str> {
+        self.host_to_model_map
+            .get(&(server.to_string(), host_name.to_string()))
+            .map(String::as_str)
+    }
+
+    fn update_host_to_model_map(&mut self) {
+        self.host_to_model_map = self
+            .tn_map
+            .iter()
+            .map(|(model_tool_name, info)| {
+                (
+                    (info.server_name.clone(), info.host_tool_name.clone()),
+                    model_tool_name.clone(),
+                )
+            })
+            .collect();
+    }
+
+    /// Drops the tools of `server_name` that another server already provides with the same host
+    /// tool name and input schema. If `server_name` is preferred by `dedupe`, the other server's
+    /// instance is dropped instead. Dropped tools are listed in `duplicates` by server.
+    ///
+    /// `tool_specs` holds the tools of the servers updated before `server_name` in this batch,
+    /// which are not in [Self::schema] yet.
+    fn dedupe_tools(
+        &mut self,
+        dedupe: &DedupeMcpTools,
+        server_name: &str,
+        tool_name_map: &mut HashMap<ModelToolName, ToolInfo>,
+        specs: &[ToolSpec],
+        tool_specs: &mut HashMap<ModelToolName, ToolSpec>,
+        duplicates: &mut HashMap<ServerName, String>,
+    ) {
+        for spec in specs {
+            let Some(tool_info) = tool_name_map.get(&spec.name) else {
+                continue;
+            };
+            let existing = self.tn_map.iter().find_map(|(model_tool_name, existing_info)| {
+                let existing_spec = tool_specs
+                    .get(model_tool_name)
+                    .or_else(|| self.schema.get(model_tool_name))?;
+                (existing_info.host_tool_name == tool_info.host_tool_name
+                    && existing_spec.input_schema.0 == spec.input_schema.0)
+                    .then(|| (model_tool_name.clone(), existing_info.server_name.clone()))
+            });
+            let Some((existing_tool_name, existing_server_name)) = existing else {
+                continue;
+            };
+
+            if dedupe.prefers(server_name, &existing_server_name) {
+                self.tn_map.remove(&existing_tool_name);
+                self.schema.remove(&existing_tool_name);
+                tool_specs.remove(&existing_tool_name);
+                duplicates
+                    .entry(existing_server_name)
+                    .or_default()
+                    .push_str(&format!(" - {existing_tool_name} (kept from {server_name})\n"));
+            } else {
+                tool_name_map.remove(&spec.name);
+                duplicates
+                    .entry(server_name.to_string())
+                    .or_default()
+                    .push_str(&format!(" - {} (kept from {existing_server_name})\n", spec.name));
+            }
+        }
+    }
+
     pub async fn list_prompts(&self) -> Result<HashMap<String, Vec<PromptBundle>>, GetPromptError> {
         if let Some((query_sender, query_result_receiver)) = &self.prompts_sender_receiver_pair {
             let mut new_receiver = query_result_receiver.resubscribe();
@@ -950,117 +1266,243 @@ impl ToolManager {
         }
     }
 
+    /// Finds the prompts matching `name`, which is either a prompt name or `server/prompt`
+    /// (optionally prefixed with `@`). More than one bundle is returned when the prompt is offered
+    /// by several servers and no server was specified.
+    pub async fn find_prompts(&self, name: &str) -> Result<Vec<PromptBundle>, GetPromptError> {
+        let name = name.strip_prefix('@').unwrap_or(name);
+        let (server_name, prompt_name) = match name.split_once('/') {
+            None => (None, name),
+            Some((server_name, prompt_name)) => (Some(server_name), prompt_name),
+        };
+        if prompt_name.is_empty() {
+            return Err(GetPromptError::MissingPromptName);
+        }
+
+        let prompts = self.list_prompts().await?;
+        let bundles = prompts
+            .get(prompt_name)
+            .into_iter()
+            .flatten()
+            .filter(|bundle| server_name.is_none_or(|sn| sn == bundle.server_name))
+            .cloned()
+            .collect::<Vec<_>>();
+
+        if bundles.is_empty() {
+            return Err(GetPromptError::PromptNotFound(prompt_name.to_string()));
+        }
+        Ok(bundles)
+    }
+
+    /// Retrieves the prompt described by `bundle` from its server via prompts/get.
     pub async fn get_prompt(
         &self,
-        name: String,
-        arguments: Option<Vec<String>>,
+        bundle: &PromptBundle,
+        arguments: Option<HashMap<String, String>>,
     ) -> Result<JsonRpcResponse, GetPromptError> {
-        let (server_name, prompt_name) = match name.split_once('/') {
-            None => (None::<String>, Some(name.clone())),
-            Some((server_name, prompt_name)) => (Some(server_name.to_string()), Some(prompt_name.to_string())),
+        let client = self
+            .clients
+            .get(&bundle.server_name)
+            .ok_or(GetPromptError::MissingClient)?;
+        let params = {
+            let mut params = serde_json::Map::new();
+            params.insert(
+                "name".to_string(),
+                serde_json::Value::String(bundle.prompt_get.name.clone()),
+            );
+            if let Some(args) = arguments {
+                params.insert("arguments".to_string(), serde_json::json!(args));
+            }
+            Some(serde_json::Value::Object(params))
         };
-        let prompt_name = prompt_name.ok_or(GetPromptError::MissingPromptName)?;
+        let resp = client.request("prompts/get", params).await?;
+        Ok(resp)
+    }
 
-        if let Some((query_sender, query_result_receiver)) = &self.prompts_sender_receiver_pair {
-            query_sender
-                .send(PromptQuery::List)
-                .map_err(|e| GetPromptError::General(eyre::eyre!(e)))?;
-            let prompts = query_result_receiver
-                .resubscribe()
-                .recv()
-                .await
-                .map_err(|e| GetPromptError::General(eyre::eyre!(e)))?;
-            let PromptQueryResult::List(prompts) = prompts else {
-                return Err(GetPromptError::IncorrectResponseType);
-            };
+    /// Returns the cached resources advertised by each server, optionally limited to a single
+    /// server. Servers are sorted by name.
+    pub async fn list_resources(&self, server_name: Option<&str>) -> Vec<(String, Vec<ResourceInfo>)> {
+        let mut resources = self
+            .resources
+            .lock()
+            .await
+            .iter()
+            .filter(|(name, _)| server_name.is_none_or(|sn| sn == name.as_str()))
+            .map(|(name, resources)| (name.clone(), resources.clone()))
+            .collect::<Vec<_>>();
+        resources.sort_by(|a, b| a.0.cmp(&b.0));
+        resources
+    }
 
-            match (prompts.get(&prompt_name), server_name.as_ref()) {
-                // If we have more than one eligible clients but no server name specified
-                (Some(bundles), None) if bundles.len() > 1 => {
-                    Err(GetPromptError::AmbiguousPrompt(prompt_name.clone(), {
-                        bundles.iter().fold("\n".to_string(), |mut acc, b| {
-                            acc.push_str(&format!("- @{}/{}\n", b.server_name, prompt_name));
-                            acc
-                        })
-                    }))
-                },
-                // Normal case where we have enough info to proceed
-                // Note that if bundle exists, it should never be empty
-                (Some(bundles), sn) => {
-                    let bundle = if bundles.len() > 1 {
-                        let Some(sn) = sn else {
-                            return Err(GetPromptError::AmbiguousPrompt(prompt_name.clone(), {
-                                bundles.iter().fold("\n".to_string(), |mut acc, b| {
-                                    acc.push_str(&format!("- @{}/{}\n", b.server_name, prompt_name));
-                                    acc
-                                })
-                            }));
-                        };
-                        let bundle = bundles.iter().find(|b| b.server_name == *sn);
-                        match bundle {
-                            Some(bundle) => bundle,
-                            None => {
-                                return Err(GetPromptError::AmbiguousPrompt(prompt_name.clone(), {
-                                    bundles.iter().fold("\n".to_string(), |mut acc, b| {
-                                        acc.push_str(&format!("- @{}/{}\n", b.server_name, prompt_name));
-                                        acc
-                                    })
-                                }));
-                            },
-                        }
-                    } else {
-                        bundles.first().ok_or(GetPromptError::MissingPromptInfo)?
-                    };
+    /// Reads a resource from the given server via resources/read, returning its text content.
+    pub async fn read_resource(&self, server_name: &str, uri: &str) -> Result<String, ResourceError> {
+        let client = self
+            .clients
+            .get(server_name)
+            .ok_or(ResourceError::ServerNotFound(server_name.to_string()))?;
+        let resp = client
+            .request("resources/read", Some(serde_json::json!({ "uri": uri })))
+            .await?;
+        if let Some(error) = resp.error {
+            return Err(ResourceError::Server(error.message));
+        }
+        let result = resp.result.ok_or(ResourceError::EmptyResult(uri.to_string()))?;
+        let result = serde_json::from_value::<ResourceReadResult>(result).map_err(|e| eyre::eyre!(e))?;
+        resource_read_result_to_text(uri, result)
+    }
 
-                    let server_name = &bundle.server_name;
-                    let client = self.clients.get(server_name).ok_or(GetPromptError::MissingClient)?;
-                    let PromptBundle { prompt_get, .. } = bundle;
-                    let args = if let (Some(schema), Some(value)) = (&prompt_get.arguments, &arguments) {
-                        let params = schema.iter().zip(value.iter()).fold(
-                            HashMap::<String, String>::new(),
-                            |mut acc, (prompt_get_arg, value)| {
-                                acc.insert(prompt_get_arg.name.clone(), value.clone());
-                                acc
-                            },
-                        );
-                        Some(serde_json::json!(params))
-                    } else {
-                        None
-                    };
-                    let params = {
-                        let mut params = serde_json::Map::new();
-                        params.insert("name".to_string(), serde_json::Value::String(prompt_name));
-                        if let Some(args) = args {
-                            params.insert("arguments".to_string(), args);
-                        }
-                        Some(serde_json::Value::Object(params))
-                    };
-                    let resp = client.request("prompts/get", params).await?;
-                    Ok(resp)
-                },
-                (None, _) => Err(GetPromptError::PromptNotFound(prompt_name)),
-            }
-        } else {
-            Err(GetPromptError::MissingChannel)
+    /// Returns the required servers that failed to load, i.e. the ones that have only reported
+    /// errors.
+    async fn failed_required_servers(&self) -> Vec<String> {
+        let load_record = self.mcp_load_record.lock().await;
+        self.required_servers
+            .iter()
+            .filter(|server_name| {
+                load_record
+                    .get(*server_name)
+                    .is_some_and(|records| records.iter().all(|r| matches!(r, LoadingRecord::Err(_))))
+            })
+            .cloned()
+            .collect()
+    }
+
+    /// Returns the required servers that have not reported anything yet, i.e. the ones that are
+    /// still loading or waiting for their turn to.
+    async fn loading_required_servers(&self) -> Vec<String> {
+        let load_record = self.mcp_load_record.lock().await;
+        self.required_servers
+            .iter()
+            .filter(|server_name| load_record.get(*server_name).is_none_or(|records| records.is_empty()))
+            .cloned()
+            .collect()
+    }
+
+    /// Resolves once every required server has either loaded or failed to.
+    async fn wait_for_required_servers(&self) {
+        while !self.loading_required_servers().await.is_empty() {
+            tokio::time::sleep(Duration::from_millis(100)).await;
         }
     }
 
+    /// Path of the file the load record of the servers is written to, one per conversation.
+    pub fn load_record_path(&self) -> eyre::Result<PathBuf> {
+        Ok(logs_dir()?.join(format!("mcp-load-{}.log", self.conversation_id)))
+    }
+
+    /// Writes [Self::mcp_load_record] without ANSI escapes to [Self::load_record_path], so that
+    /// servers failing to load can be looked into once the session is over.
+    pub async fn persist_load_record(&self, os: &Os) -> eyre::Result<PathBuf> {
+        let path = self.load_record_path()?;
+        let content = format_load_record(&self.conversation_id, &*self.mcp_load_record.lock().await);
+        if let Some(parent) = path.parent() {
+            os.fs.create_dir_all(parent).await?;
+        }
+        os.fs.write(&path, content).await?;
+        Ok(path)
+    }
+
     pub async fn pending_clients(&self) -> Vec<String> {
         self.pending_clients.read().await.iter().cloned().collect::<Vec<_>>()
     }
+
+    /// Names of the servers that are configured but not loaded
+    pub fn disabled_servers(&self) -> &[String] {
+        &self.disabled_servers
+    }
+
+    /// Shuts down the client of `server_name` and removes the tools it offered.
+    ///
+    /// Dropping the client terminates the server process, after which the orchestrator task
+    /// clears the prompts and resources of the server.
+    pub async fn disable_server(&mut self, server_name: &str) {
+        self.clients.remove(server_name);
+        self.pending_clients.write().await.remove(server_name);
+        self.new_tool_specs.lock().await.remove(server_name);
+        self.mcp_load_record.lock().await.remove(server_name);
+
+        let tool_origin = ToolOrigin::McpServer(server_name.to_string());
+        self.tn_map.retain(|_, tool_info| tool_info.server_name != server_name);
+        self.schema.retain(|_, spec| spec.tool_origin != tool_origin);
+        self.update_host_to_model_map();
+        if !self.disabled_servers.iter().any(|name| name == server_name) {
+            self.disabled_servers.push(server_name.to_string());
+        }
+        self.has_new_stuff.store(true, Ordering::Release);
+    }
+
+    /// Spawns a client for `server_name` and initializes it in the background. Its tools are
+    /// picked up by the orchestrator task once the server has listed them.
+    pub fn enable_server(&mut self, os: &Os, server_name: &str, config: CustomToolConfig) -> eyre::Result<()> {
+        let messenger_builder = self
+            .messenger_builder
+            .as_ref()
+            .ok_or(eyre::eyre!("Tool manager is not listening for mcp server updates"))?;
+        let mut client = CustomToolClient::from_config(server_name.to_string(), config, os)?;
+        let messenger = messenger_builder.build_with_name(server_name.to_string());
+        client.assign_messenger(Box::new(messenger));
+
+        let client = Arc::new(client);
+        self.clients.insert(server_name.to_string(), client.clone());
+        self.disabled_servers.retain(|name| name != server_name);
+        tokio::spawn(async move {
+            if let Err(e) = client.init().await {
+                error!(
+                    "Error initializing mcp client for server {}: {:?}",
+                    client.get_server_name(),
+                    e
+                );
+            }
+        });
+
+        Ok(())
+    }
+}
+
+/// Initializes servers in order of priority, highest first, with at most `concurrency` of them
+/// initializing at once. Once a server has been initializing for `timeout` it is no longer waited
+/// on and the next server is started, while it keeps initializing in the background.
+///
+/// Returns when the last server started initializing, once every server has either finished or
+/// timed out.
+async fn init_by_priority<F>(mut inits: Vec<(i32, F)>, concurrency: usize, timeout: Duration) -> Option<Instant>
+where
+    F: Future<Output = ()> + Send + 'static,
+{
+    inits.sort_by_key(|(priority, _)| Reverse(*priority));
+    // The stream is only polled for the next server when one of the servers in flight completes
+    stream::iter(inits)
+        .map(|(_, init)| async move {
+            let started = Instant::now();
+            let _ = tokio::time::timeout(timeout, tokio::spawn(init)).await;
+            started
+        })
+        .buffer_unordered(concurrency)
+        .collect::<Vec<_>>()
+        .await
+        .into_iter()
+        .max()
 }
 
 type DisplayTaskJoinHandle = JoinHandle<Result<(), eyre::Report>>;
 type LoadingStatusSender = tokio::sync::mpsc::Sender<LoadingMsg>;
 
+/// Width of the longest status line about a server while loading, without the server name.
+const STATUS_LINE_WIDTH: usize = "[FAIL]  has failed to load after 00.00 s".len();
+
+/// Server names are not truncated to fewer columns than this, however narrow the terminal is.
+const MIN_SERVER_NAME_WIDTH: usize = 16;
+
 /// This function spawns a background task whose sole responsibility is to listen for incoming
 /// server loading status and display them to the output.
 /// It returns a join handle to the task as well as a sender with which loading status is to be
 /// reported.
 fn spawn_display_task(
     interactive: bool,
+    plain: bool,
+    width: Option<usize>,
     total: usize,
     disabled_servers: Vec<(String, CustomToolConfig)>,
+    remote_server_urls: HashMap<String, String>,
     mut output: Box<dyn Write + Send + Sync + 'static>,
 ) -> (Option<DisplayTaskJoinHandle>, Option<LoadingStatusSender>) {
     if interactive && (total > 0 || !disabled_servers.is_empty()) {
@@ -1070,20 +1512,35 @@ fn spawn_display_task(
                 let mut spinner_logo_idx: usize = 0;
                 let mut complete: usize = 0;
                 let mut failed: usize = 0;
+                // Long names are truncated so that status lines do not wrap in narrow terminals.
+                let display_name = |name: String| {
+                    let name = match remote_server_urls.get(&name) {
+                        Some(url) => format!("{name} ({url})"),
+                        None => name,
+                    };
+                    match width {
+                        Some(width) => {
+                            let max_width = width.saturating_sub(STATUS_LINE_WIDTH).max(MIN_SERVER_NAME_WIDTH);
+                            truncate_with_ellipsis(&name, max_width).into_owned()
+                        },
+                        None => name,
+                    }
+                };
 
                 // Show disabled servers immediately
                 for (server_name, _) in &disabled_servers {
-                    queue_disabled_message(server_name, &mut output)?;
+                    queue_disabled_message(server_name, plain, &mut output)?;
                 }
 
                 if total > 0 {
-                    queue_init_message(spinner_logo_idx, complete, failed, total, &mut output)?;
+                    queue_init_message(spinner_logo_idx, complete, failed, total, plain, &mut output)?;
                 }
 
                 loop {
                     match tokio::time::timeout(Duration::from_millis(50), rx.recv()).await {
                         Ok(Some(recv_result)) => match recv_result {
                             LoadingMsg::Done { name, time } => {
+                                let name = display_name(name);
                                 complete += 1;
                                 execute!(
                                     output,
@@ -1091,10 +1548,11 @@ fn spawn_display_task(
                                     cursor::MoveUp(1),
                                     terminal::Clear(terminal::ClearType::CurrentLine),
                                 )?;
-                                queue_success_message(&name, &time, &mut output)?;
-                                queue_init_message(spinner_logo_idx, complete, failed, total, &mut output)?;
+                                queue_success_message(&name, &time, plain, &mut output)?;
+                                queue_init_message(spinner_logo_idx, complete, failed, total, plain, &mut output)?;
                             },
                             LoadingMsg::Error { name, msg, time } => {
+                                let name = display_name(name);
                                 failed += 1;
                                 execute!(
                                     output,
@@ -1102,10 +1560,11 @@ fn spawn_display_task(
                                     cursor::MoveUp(1),
                                     terminal::Clear(terminal::ClearType::CurrentLine),
                                 )?;
-                                queue_failure_message(&name, &msg, time.as_str(), &mut output)?;
-                                queue_init_message(spinner_logo_idx, complete, failed, total, &mut output)?;
+                                queue_failure_message(&name, &msg, time.as_str(), plain, &mut output)?;
+                                queue_init_message(spinner_logo_idx, complete, failed, total, plain, &mut output)?;
                             },
                             LoadingMsg::Warn { name, msg, time } => {
+                                let name = display_name(name);
                                 complete += 1;
                                 execute!(
                                     output,
@@ -1114,8 +1573,8 @@ fn spawn_display_task(
                                     terminal::Clear(terminal::ClearType::CurrentLine),
                                 )?;
                                 let msg = eyre::eyre!(msg.to_string());
-                                queue_warn_message(&name, &msg, time.as_str(), &mut output)?;
-                                queue_init_message(spinner_logo_idx, complete, failed, total, &mut output)?;
+                                queue_warn_message(&name, &msg, time.as_str(), plain, &mut output)?;
+                                queue_init_message(spinner_logo_idx, complete, failed, total, plain, &mut output)?;
                             },
                             LoadingMsg::Terminate { still_loading } => {
                                 if !still_loading.is_empty() && total > 0 {
@@ -1130,7 +1589,7 @@ fn spawn_display_task(
                                         acc
                                     });
                                     let msg = eyre::eyre!(msg);
-                                    queue_incomplete_load_message(complete, total, &msg, &mut output)?;
+                                    queue_incomplete_load_message(complete, total, &msg, plain, &mut output)?;
                                 } else if total > 0 {
                                     // Clear the loading line if we have enabled servers
                                     execute!(
@@ -1145,13 +1604,13 @@ fn spawn_display_task(
                             },
                         },
                         Err(_e) => {
-                            spinner_logo_idx = (spinner_logo_idx + 1) % SPINNER_CHARS.len();
+                            spinner_logo_idx = spinner_logo_idx.wrapping_add(1);
                             execute!(
                                 output,
                                 cursor::SavePosition,
                                 cursor::MoveToColumn(0),
                                 cursor::MoveUp(1),
-                                style::Print(SPINNER_CHARS[spinner_logo_idx]),
+                                style::Print(spinner_char(spinner_logo_idx, plain)),
                                 cursor::RestorePosition
                             )?;
                         },
@@ -1168,6 +1627,50 @@ fn spawn_display_task(
     }
 }
 
+/// Replaces the prompts offered by `server_name` with `prompt_list`. A prompts list result declares
+/// everything that is available (and not the diff), so the prompts the server no longer lists are
+/// removed. Prompts of the same name from other servers are kept alongside in the same entry.
+fn update_server_prompts(
+    prompts: &mut HashMap<String, Vec<PromptBundle>>,
+    server_name: &str,
+    prompt_list: Vec<serde_json::Value>,
+) {
+    for bundles in prompts.values_mut() {
+        bundles.retain(|bundle| bundle.server_name != server_name);
+    }
+    prompts.retain(|_, bundles| !bundles.is_empty());
+
+    for result in prompt_list {
+        let Ok(prompt_get) = serde_json::from_value::<PromptGet>(result) else {
+            error!("Failed to deserialize prompt get from server {server_name}");
+            continue;
+        };
+        prompts.entry(prompt_get.name.clone()).or_default().push(PromptBundle {
+            server_name: server_name.to_string(),
+            prompt_get,
+        });
+    }
+}
+
+/// Returns the names of the prompts containing `search_word` that are offered for completion.
+/// Prompts offered by more than one server are listed once per server as `server/prompt`.
+fn search_prompts(prompts: &HashMap<String, Vec<PromptBundle>>, search_word: Option<&str>) -> Vec<String> {
+    prompts
+        .iter()
+        .flat_map(|(prompt_name, bundles)| {
+            if bundles.len() > 1 {
+                bundles
+                    .iter()
+                    .map(|b| format!("{}/{}", b.server_name, prompt_name))
+                    .collect()
+            } else {
+                vec![prompt_name.to_owned()]
+            }
+        })
+        .filter(|n| search_word.is_none_or(|p| n.contains(p)))
+        .collect()
+}
+
 /// This function spawns the orchestrator task that has the following responsibilities:
 /// - Listens for server driven events (see [UpdateEventMessage] for a list of current applicable
 ///   events). These are things such as tool list (because we fetch tools in the background), prompt
@@ -1197,8 +1700,10 @@ fn spawn_orchestrator_task(
     telemetry: TelemetryThread,
     loading_status_sender: Option<LoadingStatusSender>,
     new_tool_specs: NewToolSpecs,
+    resources: ServerResources,
     total: usize,
     conv_id: String,
+    plain: bool,
 ) {
     tokio::spawn(async move {
         use tokio::sync::broadcast::Sender as BroadcastSender;
@@ -1238,27 +1743,7 @@ fn spawn_orchestrator_task(
                     }
                 },
                 PromptQuery::Search(search_word) => {
-                    let filtered_prompts = prompts
-                        .iter()
-                        .flat_map(|(prompt_name, bundles)| {
-                            if bundles.len() > 1 {
-                                bundles
-                                    .iter()
-                                    .map(|b| format!("{}/{}", b.server_name, prompt_name))
-                                    .collect()
-                            } else {
-                                vec![prompt_name.to_owned()]
-                            }
-                        })
-                        .filter(|n| {
-                            if let Some(p) = &search_word {
-                                n.contains(p)
-                            } else {
-                                true
-                            }
-                        })
-                        .collect::<Vec<_>>();
-
+                    let filtered_prompts = search_prompts(prompts, search_word.as_deref());
                     let query_res = PromptQueryResult::Search(filtered_prompts);
                     if let Err(e) = prompt_query_response_sender.send(query_res) {
                         error!("Error sending prompts to chat helper: {:?}", e);
@@ -1288,6 +1773,7 @@ fn spawn_orchestrator_task(
             notify_weak: &std::sync::Weak<Notify>,
             initialized: &mut HashSet<String>,
             prompts: &mut HashMap<String, Vec<PromptBundle>>,
+            resources: &ServerResources,
             total: usize,
         ) {
             record_temp_buf.clear();
@@ -1321,7 +1807,7 @@ fn spawn_orchestrator_task(
                         Err(_) => vec![],
                     };
 
-                    let (tool_filter, alias_list) = {
+                    let (tool_filter, alias_list, alias_patterns) = {
                         let agent_lock = agent.lock().await;
 
                         // We will assume all tools are allowed if the tool list consists of 1
@@ -1352,24 +1838,36 @@ fn spawn_orchestrator_task(
                         };
 
                         let server_prefix = format!("@{server_name}");
-                        let alias_list = agent_lock.tool_aliases.iter().fold(
-                            HashMap::<HostToolName, ModelToolName>::new(),
-                            |mut acc, (full_path, model_tool_name)| {
-                                if full_path.starts_with(&server_prefix) {
-                                    if let Some((_, host_tool_name)) = full_path.split_once(MCP_SERVER_TOOL_DELIMITER) {
-                                        acc.insert(host_tool_name.to_string(), model_tool_name.clone());
-                                    }
-                                }
-                                acc
-                            },
-                        );
+                        let mut alias_list = HashMap::<HostToolName, ModelToolName>::new();
+                        let mut alias_patterns = Vec::<AliasPattern>::new();
+                        for (full_path, model_tool_name) in &agent_lock.tool_aliases {
+                            if !full_path.starts_with(&server_prefix) {
+                                continue;
+                            }
+                            let Some((_, host_tool_name)) = full_path.split_once(MCP_SERVER_TOOL_DELIMITER) else {
+                                continue;
+                            };
+                            if !AliasPattern::is_pattern(host_tool_name) {
+                                alias_list.insert(host_tool_name.to_string(), model_tool_name.clone());
+                                continue;
+                            }
+                            match AliasPattern::new(host_tool_name, model_tool_name) {
+                                Ok(pattern) => alias_patterns.push(pattern),
+                                Err(e) => warn!("Ignoring invalid tool alias pattern {}: {e}", full_path.as_str()),
+                            }
+                        }
+                        // Aliases are stored in a map, sort the patterns so that the first match
+                        // is the same on every load.
+                        alias_patterns.sort_by(|a, b| a.regex.as_str().cmp(b.regex.as_str()));
 
-                        (tool_filter, alias_list)
+                        (tool_filter, alias_list, alias_patterns)
                     };
 
                     match result {
                         Ok(result) => {
-                            if pid.is_none_or(|pid| !is_process_running(pid)) {
+                            // Stdio clients fail to be created without the id of their process,
+                            // so only remote servers, which have no process, come without one
+                            if pid.is_some_and(|pid| !is_process_running(pid)) {
                                 let pid = pid.map_or("unknown".to_string(), |pid| pid.to_string());
                                 info!(
                                     "Received tool list result from {server_name} but its associated process {pid} is no longer running. Ignoring."
@@ -1380,6 +1878,7 @@ fn spawn_orchestrator_task(
                                     &server_name,
                                     &eyre::eyre!("Process associated is no longer running"),
                                     &time_taken,
+                                    plain,
                                     &mut buf_writer,
                                 );
                                 let _ = buf_writer.flush();
@@ -1413,6 +1912,7 @@ fn spawn_orchestrator_task(
                                 &mut specs,
                                 &mut sanitized_mapping,
                                 &alias_list,
+                                &alias_patterns,
                                 regex,
                                 telemetry_clone,
                                 &result_tools,
@@ -1448,11 +1948,20 @@ fn spawn_orchestrator_task(
                             // Maintain a record of the server load:
                             let mut buf_writer = BufWriter::new(&mut *record_temp_buf);
                             if let Err(e) = &process_result {
-                                let _ =
-                                    queue_warn_message(server_name.as_str(), e, time_taken.as_str(), &mut buf_writer);
+                                let _ = queue_warn_message(
+                                    server_name.as_str(),
+                                    e,
+                                    time_taken.as_str(),
+                                    plain,
+                                    &mut buf_writer,
+                                );
                             } else {
-                                let _ =
-                                    queue_success_message(server_name.as_str(), time_taken.as_str(), &mut buf_writer);
+                                let _ = queue_success_message(
+                                    server_name.as_str(),
+                                    time_taken.as_str(),
+                                    plain,
+                                    &mut buf_writer,
+                                );
                             }
                             let _ = buf_writer.flush();
                             drop(buf_writer);
@@ -1476,7 +1985,8 @@ fn spawn_orchestrator_task(
                             error!("Error loading server {server_name}: {:?}", e);
                             // Maintain a record of the server load:
                             let mut buf_writer = BufWriter::new(&mut *record_temp_buf);
-                            let _ = queue_failure_message(server_name.as_str(), &e, &time_taken, &mut buf_writer);
+                            let _ =
+                                queue_failure_message(server_name.as_str(), &e, &time_taken, plain, &mut buf_writer);
                             let _ = buf_writer.flush();
                             drop(buf_writer);
                             let record = String::from_utf8_lossy(record_temp_buf).to_string();
@@ -1519,45 +2029,15 @@ fn spawn_orchestrator_task(
                     result,
                     pid,
                 } => match result {
-                    Ok(prompt_list_result) if pid.is_some() => {
-                        let pid = pid.unwrap();
-                        if !is_process_running(pid) {
+                    Ok(prompt_list_result) => {
+                        // Only remote servers come without a process id, see the tools list result
+                        if let Some(pid) = pid.filter(|pid| !is_process_running(*pid)) {
                             info!(
                                 "Received prompt list result from {server_name} but its associated process {pid} is no longer running. Ignoring."
                             );
                             return;
                         }
-                        // We first need to clear all the PromptGets that are associated with
-                        // this server because PromptsListResult is declaring what is available
-                        // (and not the diff)
-                        prompts
-                            .values_mut()
-                            .for_each(|bundles| bundles.retain(|bundle| bundle.server_name != server_name));
-
-                        // And then we update them with the new comers
-                        for result in prompt_list_result.prompts {
-                            let Ok(prompt_get) = serde_json::from_value::<PromptGet>(result) else {
-                                error!("Failed to deserialize prompt get from server {server_name}");
-                                continue;
-                            };
-                            prompts
-                                .entry(prompt_get.name.clone())
-                                .and_modify(|bundles| {
-                                    bundles.push(PromptBundle {
-                                        server_name: server_name.clone(),
-                                        prompt_get: prompt_get.clone(),
-                                    });
-                                })
-                                .or_insert_with(|| {
-                                    vec![PromptBundle {
-                                        server_name: server_name.clone(),
-                                        prompt_get,
-                                    }]
-                                });
-                        }
-                    },
-                    Ok(_) => {
-                        error!("Received prompt list result without pid from {server_name}. Ignoring.");
+                        update_server_prompts(prompts, &server_name, prompt_list_result.prompts);
                     },
                     Err(e) => {
                         error!("Error fetching prompts from server {server_name}: {:?}", e);
@@ -1578,10 +2058,38 @@ fn spawn_orchestrator_task(
                     },
                 },
                 UpdateEventMessage::ResourcesListResult {
-                    server_name: _,
-                    result: _,
-                    pid: _,
-                } => {},
+                    server_name,
+                    result,
+                    pid,
+                } => match result {
+                    Ok(resource_list_result) => {
+                        // Stdio clients fail to be created without the id of their process,
+                        // so only remote servers, which have no process, come without one
+                        if pid.is_some_and(|pid| !is_process_running(pid)) {
+                            info!(
+                                "Received resource list result from {server_name} but its associated process is no longer running. Ignoring."
+                            );
+                            return;
+                        }
+                        // Like prompts, the list result declares everything that is available
+                        // (and not the diff), so we replace what we have for this server
+                        let server_resources = resource_list_result
+                            .resources
+                            .into_iter()
+                            .filter_map(|v| match serde_json::from_value::<ResourceInfo>(v) {
+                                Ok(resource) => Some(resource),
+                                Err(e) => {
+                                    error!("Failed to deserialize resource from server {server_name}: {:?}", e);
+                                    None
+                                },
+                            })
+                            .collect::<Vec<_>>();
+                        resources.lock().await.insert(server_name, server_resources);
+                    },
+                    Err(e) => {
+                        error!("Error fetching resources from server {server_name}: {:?}", e);
+                    },
+                },
                 UpdateEventMessage::ResourceTemplatesListResult {
                     server_name: _,
                     result: _,
@@ -1592,13 +2100,14 @@ fn spawn_orchestrator_task(
                     loading_servers.insert(server_name, std::time::Instant::now());
                 },
                 UpdateEventMessage::Deinit { server_name, .. } => {
-                    // Only prompts are stored here so we'll just be clearing that
+                    // Only prompts and resources are stored here so we'll just be clearing those
                     // In the future if we are also storing tools, we need to make sure that
                     // the tools are also pruned.
                     for (_prompt_name, bundles) in prompts.iter_mut() {
                         bundles.retain(|bundle| bundle.server_name != server_name);
                     }
                     prompts.retain(|_, bundles| !bundles.is_empty());
+                    resources.lock().await.remove(&server_name);
                     has_new_stuff.store(true, Ordering::Release);
                 },
             }
@@ -1627,6 +2136,7 @@ fn spawn_orchestrator_task(
                             &notify_weak,
                             &mut initialized,
                             &mut prompts,
+                            &resources,
                             total
                         ).await;
                 },
@@ -1640,6 +2150,37 @@ fn spawn_orchestrator_task(
     });
 }
 
+/// A `toolAliases` entry whose tool name is a regex with capture groups, e.g.
+/// `"@github/(.*)": "gh_$1"`. Every tool of the server whose name fully matches is renamed by
+/// expanding the replacement.
+struct AliasPattern {
+    regex: Regex,
+    replacement: String,
+}
+
+impl AliasPattern {
+    /// Only tool names containing a capture group are treated as patterns so that existing exact
+    /// aliases, which may contain characters such as `.`, keep working unchanged.
+    fn is_pattern(host_tool_name: &str) -> bool {
+        host_tool_name.contains('(') && host_tool_name.contains(')')
+    }
+
+    fn new(pattern: &str, replacement: &str) -> Result<Self, regex::Error> {
+        Ok(Self {
+            regex: Regex::new(&format!("^(?:{pattern})$"))?,
+            replacement: replacement.to_string(),
+        })
+    }
+
+    fn rename(&self, host_tool_name: &str) -> Option<ModelToolName> {
+        self.regex.is_match(host_tool_name).then(|| {
+            self.regex
+                .replace(host_tool_name, self.replacement.as_str())
+                .into_owned()
+        })
+    }
+}
+
 #[allow(clippy::too_many_arguments)]
 async fn process_tool_specs(
     database: &Database,
@@ -1648,6 +2189,7 @@ async fn process_tool_specs(
     specs: &mut Vec<ToolSpec>,
     tn_map: &mut HashMap<ModelToolName, ToolInfo>,
     alias_list: &HashMap<HostToolName, ModelToolName>,
+    alias_patterns: &[AliasPattern],
     regex: &Regex,
     telemetry: &TelemetryThread,
     result_tools: &[String],
@@ -1672,17 +2214,22 @@ async fn process_tool_specs(
     };
 
     for spec in specs.iter_mut() {
-        let model_tool_name = alias_list.get(&spec.name).cloned().unwrap_or({
-            if !regex.is_match(&spec.name) {
-                let mut sn = sanitize_name(spec.name.clone(), regex, &mut hasher);
-                while tn_map.contains_key(&sn) {
-                    sn.push('1');
+        // Exact aliases take precedence over patterns
+        let model_tool_name = alias_list
+            .get(&spec.name)
+            .cloned()
+            .or_else(|| alias_patterns.iter().find_map(|pattern| pattern.rename(&spec.name)))
+            .unwrap_or({
+                if !regex.is_match(&spec.name) {
+                    let mut sn = sanitize_name(spec.name.clone(), regex, &mut hasher);
+                    while tn_map.contains_key(&sn) {
+                        sn.push('1');
+                    }
+                    sn
+                } else {
+                    spec.name.clone()
                 }
-                sn
-            } else {
-                spec.name.clone()
-            }
-        });
+            });
         if model_tool_name.len() > 64 {
             out_of_spec_tool_names.push(OutOfSpecName::TooLong(spec.name.clone()));
             continue;
@@ -1794,19 +2341,68 @@ fn is_process_running(pid: u32) -> bool {
     }
 }
 
-fn queue_success_message(name: &str, time_taken: &str, output: &mut impl Write) -> eyre::Result<()> {
-    Ok(queue!(
-        output,
-        style::SetForegroundColor(style::Color::Green),
-        style::Print("✓ "),
-        style::SetForegroundColor(style::Color::Blue),
-        style::Print(name),
-        style::ResetColor,
-        style::Print(" loaded in "),
-        style::SetForegroundColor(style::Color::Yellow),
-        style::Print(format!("{time_taken} s\n")),
-        style::ResetColor,
-    )?)
+/// Markers shown in front of the servers while they load.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum StatusMarker {
+    Success,
+    Failure,
+    Warning,
+    Disabled,
+}
+
+impl StatusMarker {
+    fn symbol(self, plain: bool) -> &'static str {
+        match (self, plain) {
+            (Self::Success, false) => "✓",
+            (Self::Failure, false) => "✗",
+            (Self::Warning, false) => "⚠",
+            (Self::Disabled, false) => "○",
+            (Self::Success, true) => "[OK]",
+            (Self::Failure, true) => "[FAIL]",
+            (Self::Warning, true) => "[WARN]",
+            (Self::Disabled, true) => "[OFF]",
+        }
+    }
+
+    fn color(self) -> style::Color {
+        match self {
+            Self::Success => style::Color::Green,
+            Self::Failure => style::Color::Red,
+            Self::Warning => style::Color::Yellow,
+            Self::Disabled => style::Color::DarkGrey,
+        }
+    }
+}
+
+fn spinner_char(spinner_logo_idx: usize, plain: bool) -> char {
+    let chars: &[char] = if plain { &PLAIN_SPINNER_CHARS } else { &SPINNER_CHARS };
+    chars[spinner_logo_idx % chars.len()]
+}
+
+/// Queues `text` in the given color, or as is when `plain` is set.
+fn queue_colored(output: &mut impl Write, plain: bool, color: style::Color, text: impl Display) -> std::io::Result<()> {
+    if plain {
+        queue!(output, style::Print(text))
+    } else {
+        queue!(
+            output,
+            style::SetForegroundColor(color),
+            style::Print(text),
+            style::ResetColor
+        )
+    }
+}
+
+fn queue_marker(output: &mut impl Write, marker: StatusMarker, plain: bool) -> std::io::Result<()> {
+    queue_colored(output, plain, marker.color(), marker.symbol(plain))
+}
+
+pub fn queue_success_message(name: &str, time_taken: &str, plain: bool, output: &mut impl Write) -> eyre::Result<()> {
+    queue_marker(output, StatusMarker::Success, plain)?;
+    queue_colored(output, plain, style::Color::Blue, format!(" {name}"))?;
+    queue!(output, style::Print(" loaded in "))?;
+    queue_colored(output, plain, style::Color::Yellow, format!("{time_taken} s\n"))?;
+    Ok(())
 }
 
 fn queue_init_message(
@@ -1814,130 +2410,105 @@ fn queue_init_message(
     complete: usize,
     failed: usize,
     total: usize,
+    plain: bool,
     output: &mut impl Write,
 ) -> eyre::Result<()> {
     if total == complete {
-        queue!(
-            output,
-            style::SetForegroundColor(style::Color::Green),
-            style::Print("✓"),
-            style::ResetColor,
-        )?;
+        queue_marker(output, StatusMarker::Success, plain)?;
     } else if total == complete + failed {
-        queue!(
-            output,
-            style::SetForegroundColor(style::Color::Red),
-            style::Print("✗"),
-            style::ResetColor,
-        )?;
+        queue_marker(output, StatusMarker::Failure, plain)?;
     } else {
-        queue!(output, style::Print(SPINNER_CHARS[spinner_logo_idx]))?;
+        queue!(output, style::Print(spinner_char(spinner_logo_idx, plain)))?;
     }
-    queue!(
-        output,
-        style::SetForegroundColor(style::Color::Blue),
-        style::Print(format!(" {}", complete)),
-        style::ResetColor,
-        style::Print(" of "),
-        style::SetForegroundColor(style::Color::Blue),
-        style::Print(format!("{} ", total)),
-        style::ResetColor,
-        style::Print("mcp servers initialized."),
-    )?;
+    queue_servers_initialized(complete, total, plain, output)?;
     if total > complete + failed {
-        queue!(
-            output,
-            style::SetForegroundColor(style::Color::Blue),
-            style::Print(" ctrl-c "),
-            style::ResetColor,
-            style::Print("to start chatting now")
-        )?;
+        queue_colored(output, plain, style::Color::Blue, " ctrl-c ")?;
+        queue!(output, style::Print("to start chatting now"))?;
     }
     Ok(queue!(output, style::Print("\n"))?)
 }
 
-fn queue_failure_message(
+fn queue_servers_initialized(complete: usize, total: usize, plain: bool, output: &mut impl Write) -> eyre::Result<()> {
+    queue_colored(output, plain, style::Color::Blue, format!(" {}", complete))?;
+    queue!(output, style::Print(" of "))?;
+    queue_colored(output, plain, style::Color::Blue, format!("{} ", total))?;
+    Ok(queue!(output, style::Print("mcp servers initialized."))?)
+}
+
+pub fn queue_failure_message(
     name: &str,
     fail_load_msg: &eyre::Report,
     time: &str,
+    plain: bool,
     output: &mut impl Write,
 ) -> eyre::Result<()> {
     use crate::util::CHAT_BINARY_NAME;
+    queue_marker(output, StatusMarker::Failure, plain)?;
+    queue_colored(output, plain, style::Color::Blue, format!(" {name}"))?;
+    queue!(output, style::Print(" has failed to load after"))?;
+    queue_colored(output, plain, style::Color::Yellow, format!(" {time} s"))?;
     Ok(queue!(
         output,
-        style::SetForegroundColor(style::Color::Red),
-        style::Print("✗ "),
-        style::SetForegroundColor(style::Color::Blue),
-        style::Print(name),
-        style::ResetColor,
-        style::Print(" has failed to load after"),
-        style::SetForegroundColor(style::Color::Yellow),
-        style::Print(format!(" {time} s")),
-        style::ResetColor,
         style::Print("\n - "),
         style::Print(fail_load_msg),
         style::Print("\n"),
         style::Print(format!(
             " - run with Q_LOG_LEVEL=trace and see $TMPDIR/{CHAT_BINARY_NAME} for detail\n"
         )),
-        style::ResetColor,
     )?)
 }
 
-fn queue_warn_message(name: &str, msg: &eyre::Report, time: &str, output: &mut impl Write) -> eyre::Result<()> {
+fn queue_warn_message(
+    name: &str,
+    msg: &eyre::Report,
+    time: &str,
+    plain: bool,
+    output: &mut impl Write,
+) -> eyre::Result<()> {
+    queue_marker(output, StatusMarker::Warning, plain)?;
+    queue_colored(output, plain, style::Color::Blue, format!(" {name}"))?;
+    queue!(output, style::Print(" has loaded in"))?;
+    queue_colored(output, plain, style::Color::Yellow, format!(" {time} s"))?;
     Ok(queue!(
         output,
-        style::SetForegroundColor(style::Color::Yellow),
-        style::Print("⚠ "),
-        style::SetForegroundColor(style::Color::Blue),
-        style::Print(name),
-        style::ResetColor,
-        style::Print(" has loaded in"),
-        style::SetForegroundColor(style::Color::Yellow),
-        style::Print(format!(" {time} s")),
-        style::ResetColor,
         style::Print(" with the following warning:\n"),
         style::Print(msg),
-        style::ResetColor,
     )?)
 }
 
-fn queue_disabled_message(name: &str, output: &mut impl Write) -> eyre::Result<()> {
-    Ok(queue!(
+fn queue_disabled_message(name: &str, plain: bool, output: &mut impl Write) -> eyre::Result<()> {
+    queue_marker(output, StatusMarker::Disabled, plain)?;
+    queue_colored(output, plain, style::Color::Blue, format!(" {name}"))?;
+    Ok(queue!(output, style::Print(" is disabled\n"))?)
+}
+
+fn queue_reserved_name_message(name: &str, plain: bool, output: &mut impl Write) -> eyre::Result<()> {
+    queue_colored(
         output,
-        style::SetForegroundColor(style::Color::DarkGrey),
-        style::Print("○ "),
-        style::SetForegroundColor(style::Color::Blue),
-        style::Print(name),
-        style::ResetColor,
-        style::Print(" is disabled\n"),
-        style::ResetColor,
-    )?)
+        plain,
+        style::Color::Red,
+        format!("{} Invalid server name ", StatusMarker::Failure.symbol(plain)),
+    )?;
+    queue_colored(output, plain, style::Color::Blue, name)?;
+    queue!(output, style::Print(". Server name cannot contain reserved word "))?;
+    queue_colored(output, plain, style::Color::Yellow, "builtin")?;
+    Ok(queue!(output, style::Print(" (it is used to denote native tools)\n"))?)
 }
 
 fn queue_incomplete_load_message(
     complete: usize,
     total: usize,
     msg: &eyre::Report,
+    plain: bool,
     output: &mut impl Write,
 ) -> eyre::Result<()> {
+    queue_marker(output, StatusMarker::Warning, plain)?;
+    queue_servers_initialized(complete, total, plain, output)?;
     Ok(queue!(
         output,
-        style::SetForegroundColor(style::Color::Yellow),
-        style::Print("⚠"),
-        style::SetForegroundColor(style::Color::Blue),
-        style::Print(format!(" {}", complete)),
-        style::ResetColor,
-        style::Print(" of "),
-        style::SetForegroundColor(style::Color::Blue),
-        style::Print(format!("{} ", total)),
-        style::ResetColor,
-        style::Print("mcp servers initialized."),
-        style::ResetColor,
         // We expect the message start with a newline
         style::Print(" Servers still loading:"),
         style::Print(msg),
-        style::ResetColor,
     )?)
 }
 
@@ -1949,9 +2520,31 @@ fn queue_prompts_load_error_message(name: &str, msg: &eyre::Report, output: &mut
     )?)
 }
 
+/// Renders the load record of the servers as plain text, with the servers sorted by name.
+fn format_load_record(conversation_id: &str, load_record: &HashMap<String, Vec<LoadingRecord>>) -> String {
+    let written_at = OffsetDateTime::now_utc().format(&Rfc3339).unwrap_or_default();
+    let mut content = format!("MCP server load record of conversation {conversation_id}, written at {written_at}\n");
+
+    let mut servers = load_record.iter().collect::<Vec<_>>();
+    servers.sort_by(|(a, _), (b, _)| a.cmp(b));
+    for (server_name, records) in servers {
+        content.push_str(&format!("\n[{server_name}]\n"));
+        for record in records {
+            let (level, message) = match record {
+                LoadingRecord::Success(message) => ("SUCCESS", message),
+                LoadingRecord::Warn(message) => ("WARN", message),
+                LoadingRecord::Err(message) => ("ERROR", message),
+            };
+            content.push_str(&format!("{level}: {}\n", strip_str(message).trim()));
+        }
+    }
+    content
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::cli::chat::tools::InputSchema;
 
     #[test]
     fn test_sanitize_server_name() {
@@ -1973,4 +2566,413 @@ mod tests {
         let sanitized = sanitize_name(with_delim, &regex, &mut hasher);
         assert_eq!(sanitized, "abc");
     }
+
+    #[test]
+    fn test_alias_pattern() {
+        assert!(!AliasPattern::is_pattern("create_issue"));
+        assert!(!AliasPattern::is_pattern("file.read"));
+        assert!(AliasPattern::is_pattern("github__(.*)"));
+
+        let pattern = AliasPattern::new("github__(.*)", "gh_$1").unwrap();
+        assert_eq!(
+            pattern.rename("github__create_issue").as_deref(),
+            Some("gh_create_issue")
+        );
+        assert_eq!(pattern.rename("gitlab__create_issue"), None);
+
+        // Patterns must match the whole tool name
+        let pattern = AliasPattern::new("(issue)", "ticket").unwrap();
+        assert_eq!(pattern.rename("create_issue"), None);
+
+        assert!(AliasPattern::new("(unclosed", "x").is_err());
+    }
+
+    #[tokio::test]
+    async fn test_failed_required_servers() {
+        let tool_manager = ToolManager {
+            required_servers: vec!["loaded".to_string(), "failed".to_string(), "pending".to_string()],
+            ..Default::default()
+        };
+        {
+            let mut load_record = tool_manager.mcp_load_record.lock().await;
+            load_record.insert("loaded".to_string(), vec![LoadingRecord::Warn("warning".to_string())]);
+            load_record.insert("failed".to_string(), vec![LoadingRecord::Err("error".to_string())]);
+        }
+        assert_eq!(tool_manager.failed_required_servers().await, vec!["failed".to_string()]);
+        assert_eq!(tool_manager.loading_required_servers().await, vec![
+            "pending".to_string()
+        ]);
+
+        // Servers still loading are waited on until they report
+        {
+            let mut load_record = tool_manager.mcp_load_record.lock().await;
+            load_record.insert("pending".to_string(), vec![LoadingRecord::Err("error".to_string())]);
+        }
+        tokio::time::timeout(Duration::from_secs(1), tool_manager.wait_for_required_servers())
+            .await
+            .unwrap();
+        assert_eq!(tool_manager.failed_required_servers().await, vec![
+            "failed".to_string(),
+            "pending".to_string()
+        ]);
+    }
+
+    #[tokio::test]
+    async fn test_persist_load_record() {
+        let os = Os::new().await.unwrap();
+        let tool_manager = ToolManager {
+            conversation_id: "conv_id".to_string(),
+            ..Default::default()
+        };
+        {
+            let mut load_record = tool_manager.mcp_load_record.lock().await;
+            let mut success = Vec::new();
+            queue_success_message("git", "0.42", false, &mut success).unwrap();
+            load_record.insert("git".to_string(), vec![LoadingRecord::Success(
+                String::from_utf8(success).unwrap(),
+            )]);
+            load_record.insert("fetch".to_string(), vec![
+                LoadingRecord::Err("\u{1b}[31mconnection refused\u{1b}[0m".to_string()),
+                LoadingRecord::Warn("tool list changed".to_string()),
+            ]);
+        }
+
+        let path = tool_manager.persist_load_record(&os).await.unwrap();
+        assert_eq!(path, tool_manager.load_record_path().unwrap());
+        assert!(path.ends_with("mcp-load-conv_id.log"));
+
+        let content = os.fs.read_to_string(&path).await.unwrap();
+        assert!(content.starts_with("MCP server load record of conversation conv_id"));
+        assert!(!content.contains('\u{1b}'));
+        let fetch = content.find("[fetch]").unwrap();
+        let git = content.find("[git]").unwrap();
+        assert!(fetch < git);
+        assert!(content.contains("ERROR: connection refused\nWARN: tool list changed\n"));
+        assert!(content.contains("SUCCESS: ✓ git loaded in 0.42 s\n"));
+    }
+
+    #[test]
+    fn test_plain_loading_messages() {
+        let mut output = Vec::new();
+        queue_success_message("git", "0.42", true, &mut output).unwrap();
+        queue_disabled_message("fetch", true, &mut output).unwrap();
+        queue_init_message(0, 1, 0, 2, true, &mut output).unwrap();
+        queue_init_message(5, 1, 1, 2, true, &mut output).unwrap();
+        let output = String::from_utf8(output).unwrap();
+        assert_eq!(
+            output,
+            "[OK] git loaded in 0.42 s\n\
+             [OFF] fetch is disabled\n\
+             | 1 of 2 mcp servers initialized. ctrl-c to start chatting now\n\
+             [FAIL] 1 of 2 mcp servers initialized.\n"
+        );
+        assert!(output.is_ascii());
+
+        let mut output = Vec::new();
+        queue_warn_message("git", &eyre::eyre!("slow"), "1.00", true, &mut output).unwrap();
+        assert_eq!(
+            String::from_utf8(output).unwrap(),
+            "[WARN] git has loaded in 1.00 s with the following warning:\nslow"
+        );
+        assert_eq!(spinner_char(5, true), '/');
+    }
+
+    #[tokio::test]
+    async fn test_disable_server() {
+        let mut tool_manager = ToolManager::default();
+        for (server_name, tool_name) in [("git", "git___status"), ("fetch", "fetch___get")] {
+            tool_manager.tn_map.insert(tool_name.to_string(), ToolInfo {
+                server_name: server_name.to_string(),
+                host_tool_name: tool_name.to_string(),
+            });
+            tool_manager.schema.insert(tool_name.to_string(), ToolSpec {
+                name: tool_name.to_string(),
+                description: String::new(),
+                input_schema: InputSchema(serde_json::json!({})),
+                tool_origin: ToolOrigin::McpServer(server_name.to_string()),
+            });
+        }
+        tool_manager
+            .mcp_load_record
+            .lock()
+            .await
+            .insert("git".to_string(), vec![LoadingRecord::Success("loaded".to_string())]);
+
+        tool_manager.disable_server("git").await;
+        tool_manager.disable_server("git").await;
+        assert_eq!(tool_manager.tn_map.keys().collect::<Vec<_>>(), vec!["fetch___get"]);
+        assert_eq!(tool_manager.schema.keys().collect::<Vec<_>>(), vec!["fetch___get"]);
+        assert!(tool_manager.mcp_load_record.lock().await.is_empty());
+        assert_eq!(tool_manager.disabled_servers(), ["git".to_string()]);
+        assert!(tool_manager.has_new_stuff.load(Ordering::Acquire));
+    }
+
+    #[tokio::test]
+    async fn test_init_by_priority() {
+        use std::sync::atomic::AtomicUsize;
+
+        let started = Arc::new(std::sync::Mutex::new(Vec::new()));
+        let running = Arc::new(AtomicUsize::new(0));
+        let max_running = Arc::new(AtomicUsize::new(0));
+        let inits = || {
+            [(0, "low"), (10, "high"), (-1, "last"), (5, "mid"), (0, "low2")]
+                .into_iter()
+                .map(|(priority, name)| {
+                    let (started, running, max_running) = (started.clone(), running.clone(), max_running.clone());
+                    (priority, async move {
+                        started.lock().unwrap().push(name);
+                        max_running.fetch_max(running.fetch_add(1, Ordering::SeqCst) + 1, Ordering::SeqCst);
+                        tokio::time::sleep(Duration::from_millis(20)).await;
+                        running.fetch_sub(1, Ordering::SeqCst);
+                    })
+                })
+                .collect::<Vec<_>>()
+        };
+
+        init_by_priority(inits(), 1, Duration::from_secs(5)).await;
+        assert_eq!(*started.lock().unwrap(), ["high", "mid", "low", "low2", "last"]);
+        assert_eq!(max_running.load(Ordering::SeqCst), 1);
+
+        init_by_priority(inits(), 2, Duration::from_secs(5)).await;
+        assert_eq!(max_running.load(Ordering::SeqCst), 2);
+
+        // A server that takes too long to initialize does not hold up the servers after it
+        let hung = Box::pin(future::pending::<()>()) as Pin<Box<dyn Future<Output = ()> + Send>>;
+        let quick = Box::pin(async {}) as Pin<Box<dyn Future<Output = ()> + Send>>;
+        let last_start = tokio::time::timeout(
+            Duration::from_secs(5),
+            init_by_priority(vec![(1, hung), (0, quick)], 1, Duration::from_millis(50)),
+        )
+        .await
+        .unwrap();
+        assert!(last_start.is_some());
+    }
+
+    #[tokio::test]
+    async fn test_get_model_name() {
+        let mut tool_manager = ToolManager::default();
+        let (mut tn_map, mut specs) = server_tools("git", &[
+            ("status", serde_json::json!({})),
+            ("diff", serde_json::json!({})),
+        ]);
+        // An aliased tool and one whose host name had to be sanitized
+        for (host_tool_name, model_tool_name) in [("status", "git_status"), ("diff", "git___diff")] {
+            let info = tn_map.remove(host_tool_name).unwrap();
+            tn_map.insert(model_tool_name.to_string(), info);
+            let spec = specs.iter_mut().find(|spec| spec.name == host_tool_name).unwrap();
+            spec.name = model_tool_name.to_string();
+        }
+        tool_manager
+            .new_tool_specs
+            .lock()
+            .await
+            .insert("git".to_string(), (tn_map, specs));
+        tool_manager.update().await;
+
+        for (host_tool_name, model_tool_name) in [("status", "git_status"), ("diff", "git___diff")] {
+            assert_eq!(
+                tool_manager.get_model_name("git", host_tool_name),
+                Some(model_tool_name)
+            );
+            let info = tool_manager.tn_map.get(model_tool_name).unwrap();
+            assert_eq!(
+                (info.server_name.as_str(), info.host_tool_name.as_str()),
+                ("git", host_tool_name)
+            );
+        }
+        assert_eq!(tool_manager.get_model_name("fetch", "status"), None);
+        assert_eq!(tool_manager.get_model_name("git", "git_status"), None);
+
+        tool_manager.disable_server("git").await;
+        assert_eq!(tool_manager.get_model_name("git", "status"), None);
+    }
+
+    #[test]
+    fn test_prompt_list_changed_updates_completions() {
+        let prompt_list = |names: &[&str]| {
+            names
+                .iter()
+                .map(|name| serde_json::json!({ "name": name }))
+                .collect::<Vec<_>>()
+        };
+        let completions = |prompts: &HashMap<String, Vec<PromptBundle>>, word: Option<&str>| {
+            let mut names = search_prompts(prompts, word);
+            names.sort();
+            names
+        };
+
+        let mut prompts = HashMap::<String, Vec<PromptBundle>>::new();
+        update_server_prompts(&mut prompts, "git", prompt_list(&["review", "commit_message"]));
+        update_server_prompts(&mut prompts, "github", prompt_list(&["review"]));
+        assert_eq!(completions(&prompts, None), vec![
+            "commit_message",
+            "git/review",
+            "github/review"
+        ]);
+
+        // The server announces a new list: removed prompts disappear and new ones show up
+        update_server_prompts(&mut prompts, "git", prompt_list(&["review", "rebase"]));
+        assert_eq!(completions(&prompts, None), vec![
+            "git/review",
+            "github/review",
+            "rebase"
+        ]);
+        assert_eq!(completions(&prompts, Some("base")), vec!["rebase"]);
+        assert!(!prompts.contains_key("commit_message"));
+
+        // Once only one server offers a prompt it is no longer prefixed with the server name
+        update_server_prompts(&mut prompts, "github", Vec::new());
+        assert_eq!(completions(&prompts, None), vec!["rebase", "review"]);
+        assert_eq!(prompts["review"].len(), 1);
+        assert_eq!(prompts["review"][0].server_name, "git");
+    }
+
+    fn server_tools(
+        server_name: &str,
+        tools: &[(&str, serde_json::Value)],
+    ) -> (HashMap<ModelToolName, ToolInfo>, Vec<ToolSpec>) {
+        let tn_map = tools
+            .iter()
+            .map(|(tool_name, _)| {
+                (tool_name.to_string(), ToolInfo {
+                    server_name: server_name.to_string(),
+                    host_tool_name: tool_name.to_string(),
+                })
+            })
+            .collect();
+        let specs = tools
+            .iter()
+            .map(|(tool_name, schema)| ToolSpec {
+                name: tool_name.to_string(),
+                description: "description".to_string(),
+                input_schema: InputSchema(schema.clone()),
+                tool_origin: ToolOrigin::McpServer(server_name.to_string()),
+            })
+            .collect();
+        (tn_map, specs)
+    }
+
+    #[tokio::test]
+    async fn test_dedupe_tools() {
+        let status_schema = serde_json::json!({ "type": "object", "properties": { "repo": { "type": "string" } } });
+        let load = |prefer: Vec<String>| {
+            let status_schema = status_schema.clone();
+            async move {
+                let mut tool_manager = ToolManager::default();
+                tool_manager.agent.lock().await.dedupe_mcp_tools = DedupeMcpTools { enabled: true, prefer };
+                for (server_name, diff_schema) in [
+                    ("git", serde_json::json!({})),
+                    ("github", serde_json::json!({ "type": "object" })),
+                ] {
+                    tool_manager.new_tool_specs.lock().await.insert(
+                        server_name.to_string(),
+                        server_tools(server_name, &[("status", status_schema.clone()), ("diff", diff_schema)]),
+                    );
+                    tool_manager.update().await;
+                }
+                tool_manager
+            }
+        };
+        let server_of = |tool_manager: &ToolManager, tool_name: &str| {
+            tool_manager.tn_map.get(tool_name).map(|info| info.server_name.clone())
+        };
+
+        // The instance of the server that loaded first is kept by default
+        let tool_manager = load(Vec::new()).await;
+        assert_eq!(server_of(&tool_manager, "status").as_deref(), Some("git"));
+        assert_eq!(tool_manager.schema.len(), 2);
+        let load_record = tool_manager.mcp_load_record.lock().await;
+        let records = load_record.get("github").unwrap();
+        let [LoadingRecord::Err(conflict), LoadingRecord::Warn(duplicate)] = &records[..] else {
+            panic!("unexpected load record: {records:?}");
+        };
+        // Tools with the same name but a different schema still conflict
+        assert!(conflict.contains(" - diff from github"));
+        assert!(duplicate.contains(" - status (kept from git)"));
+        drop(load_record);
+
+        // A preferred server replaces the instance of a server that loaded before it
+        let tool_manager = load(vec!["github".to_string()]).await;
+        assert_eq!(server_of(&tool_manager, "status").as_deref(), Some("github"));
+        assert_eq!(
+            tool_manager.schema.get("status").map(|spec| spec.tool_origin.clone()),
+            Some(ToolOrigin::McpServer("github".to_string()))
+        );
+        let load_record = tool_manager.mcp_load_record.lock().await;
+        let records = load_record.get("git").unwrap();
+        let [LoadingRecord::Warn(duplicate)] = &records[..] else {
+            panic!("unexpected load record: {records:?}");
+        };
+        assert!(duplicate.contains(" - status (kept from github)"));
+    }
+
+    #[test]
+    fn test_get_tool_from_tool_use_parse_error() {
+        let mut tool_manager = ToolManager::default();
+        let specs = serde_json::from_str::<HashMap<String, ToolSpec>>(include_str!("tools/tool_index.json")).unwrap();
+        tool_manager
+            .schema
+            .insert("fs_read".to_string(), specs["fs_read"].clone());
+
+        let tool_use = AssistantToolUse {
+            id: "tool_use_id".to_string(),
+            name: "fs_read".to_string(),
+            args: serde_json::json!({ "operations": [{ "mode": "line", "path": "/tmp/a.txt" }] }),
+            ..Default::default()
+        };
+        let result = tool_manager.get_tool_from_tool_use(tool_use).unwrap_err();
+        assert_eq!(result.tool_use_id, "tool_use_id");
+        let Some(ToolResultContentBlock::Text(text)) = result.content.first() else {
+            panic!("expected a text block, got {:?}", result.content);
+        };
+        assert!(text.contains(r#""error_type":"out_of_range""#), "{text}");
+        assert!(text.contains(r#""field_path":"operations[0].mode""#), "{text}");
+        assert!(text.contains(r#""mode":"Line""#), "{text}");
+    }
+
+    #[test]
+    fn test_resource_list_deserialization() {
+        let result = serde_json::from_value::<crate::mcp_client::ResourcesListResult>(serde_json::json!({
+            "resources": [
+                { "uri": "file:///readme.md", "name": "readme", "mimeType": "text/markdown" },
+                { "uri": "db://tables", "name": "tables" }
+            ]
+        }))
+        .unwrap();
+        let resources = result
+            .resources
+            .into_iter()
+            .map(|v| serde_json::from_value::<ResourceInfo>(v).unwrap())
+            .collect::<Vec<_>>();
+        assert_eq!(resources[0].mime_type.as_deref(), Some("text/markdown"));
+        assert_eq!(resources[1].uri, "db://tables");
+        assert_eq!(resources[1].mime_type, None);
+    }
+
+    #[test]
+    fn test_resource_read_result_to_text() {
+        let text = serde_json::from_value::<ResourceReadResult>(serde_json::json!({
+            "contents": [
+                { "uri": "file:///a.md", "mimeType": "text/markdown", "text": "a" },
+                { "uri": "file:///b.md", "text": "b" }
+            ]
+        }))
+        .unwrap();
+        assert_eq!(resource_read_result_to_text("file:///", text).unwrap(), "a\nb");
+
+        let binary = serde_json::from_value::<ResourceReadResult>(serde_json::json!({
+            "contents": [{ "uri": "file:///logo.png", "mimeType": "image/png", "blob": "iVBORw0KGgo=" }]
+        }))
+        .unwrap();
+        assert!(matches!(
+            resource_read_result_to_text("file:///logo.png", binary),
+            Err(ResourceError::Binary { mime_type, .. }) if mime_type == "image/png"
+        ));
+
+        let empty = ResourceReadResult { contents: vec![] };
+        assert!(matches!(
+            resource_read_result_to_text("file:///missing", empty),
+            Err(ResourceError::EmptyResult(_))
+        ));
+    }
 }
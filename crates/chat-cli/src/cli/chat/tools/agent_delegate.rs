@@ -0,0 +1,106 @@
+use std::io::Write;
+
+use crossterm::queue;
+use crossterm::style::{
+    self,
+    Color,
+};
+use eyre::{
+    Result,
+    bail,
+};
+use serde::Deserialize;
+
+use crate::cli::agent::{
+    Agent,
+    PermissionEvalResult,
+};
+use crate::cli::chat::CONTINUATION_LINE;
+use crate::util::pattern_matching::matches_any_pattern;
+
+/// Name of the tool as seen by the model.
+pub const AGENT_DELEGATE_TOOL_NAME: &str = "agent_delegate";
+
+/// Hands a sub-task to another agent.
+///
+/// The tool is not invoked like the others. Instead the chat session swaps in the named agent,
+/// runs a separate conversation with it that starts from [Self::task], and returns its final
+/// response as the tool result once it is done. Only offered to agents with
+/// [Agent::delegation_enabled] set.
+#[derive(Debug, Clone, Deserialize)]
+pub struct AgentDelegate {
+    /// Name of the agent to hand the task to
+    pub agent: String,
+    /// Description of the task, sent to the agent as its first prompt
+    pub task: String,
+}
+
+impl AgentDelegate {
+    pub fn queue_description(&self, output: &mut impl Write) -> Result<()> {
+        queue!(
+            output,
+            style::Print("Delegating a task to agent "),
+            style::SetForegroundColor(Color::Green),
+            style::Print(&self.agent),
+            style::ResetColor,
+            style::Print(":\n"),
+        )?;
+        for line in self.task.lines() {
+            queue!(output, style::Print(format!("{CONTINUATION_LINE} {line}\n")))?;
+        }
+        Ok(())
+    }
+
+    pub async fn validate(&mut self) -> Result<()> {
+        if self.agent.trim().is_empty() {
+            bail!("The agent name must not be empty");
+        }
+        if self.task.trim().is_empty() {
+            bail!("The task must not be empty");
+        }
+        Ok(())
+    }
+
+    pub fn eval_perm(&self, agent: &Agent) -> PermissionEvalResult {
+        _ = self;
+
+        if matches_any_pattern(&agent.allowed_tools, AGENT_DELEGATE_TOOL_NAME) {
+            PermissionEvalResult::Allow
+        } else {
+            PermissionEvalResult::Ask
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_agent_delegate_validate() {
+        let mut delegate = serde_json::from_value::<AgentDelegate>(serde_json::json!({
+            "agent": "reviewer",
+            "task": "Review the changes in src/",
+        }))
+        .unwrap();
+        assert!(delegate.validate().await.is_ok());
+
+        delegate.task = "  ".to_string();
+        assert!(delegate.validate().await.is_err());
+
+        assert!(serde_json::from_value::<AgentDelegate>(serde_json::json!({ "agent": "reviewer" })).is_err());
+    }
+
+    #[test]
+    fn test_agent_delegate_eval_perm() {
+        let delegate = AgentDelegate {
+            agent: "reviewer".to_string(),
+            task: "Review".to_string(),
+        };
+        let mut agent = Agent::default();
+        assert!(matches!(delegate.eval_perm(&agent), PermissionEvalResult::Ask));
+
+        agent.allowed_tools.insert(AGENT_DELEGATE_TOOL_NAME.to_string());
+        assert!(matches!(delegate.eval_perm(&agent), PermissionEvalResult::Allow));
+    }
+}
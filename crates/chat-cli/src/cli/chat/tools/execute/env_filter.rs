@@ -0,0 +1,187 @@
+use std::collections::HashMap;
+
+use globset::{
+    GlobBuilder,
+    GlobSet,
+    GlobSetBuilder,
+};
+use tracing::warn;
+
+use super::ExecuteSettings;
+use crate::cli::chat::consts::USER_AGENT_ENV_VAR;
+
+/// Variables passed on when `allowedEnvVars` is not set, enough for most commands to run.
+/// `SYSTEMROOT` and the variables after it are required by cmd.exe and most programs on Windows.
+const DEFAULT_ALLOWED_ENV_VARS: &[&str] = &[
+    "PATH",
+    "HOME",
+    "USER",
+    "LOGNAME",
+    "SHELL",
+    "TERM",
+    "LANG",
+    "LC_*",
+    "TZ",
+    "TMPDIR",
+    "PWD",
+    "SYSTEMROOT",
+    "WINDIR",
+    "COMSPEC",
+    "PATHEXT",
+    "TEMP",
+    "TMP",
+    "USERPROFILE",
+    "APPDATA",
+    "LOCALAPPDATA",
+];
+
+/// Variables removed when `blockedEnvVars` is not set.
+const DEFAULT_BLOCKED_ENV_VARS: &[&str] = &["AWS_SECRET_*", "*TOKEN*", "*PASSWORD*", "*KEY*"];
+
+/// Decides which environment variables a command may see, so that secrets in the environment of
+/// the chat session do not leak to commands run by the model.
+///
+/// Variable names are matched case-insensitively, and blocked patterns take precedence over
+/// allowed ones.
+#[derive(Debug)]
+pub struct EnvFilter {
+    allowed: GlobSet,
+    blocked: GlobSet,
+}
+
+impl EnvFilter {
+    /// Returns the filter configured by `settings`, or [None] if `sanitizeEnv` is not enabled.
+    /// Patterns that are not valid globs are skipped.
+    pub fn from_settings(settings: &ExecuteSettings) -> Option<Self> {
+        if !settings.sanitize_env.unwrap_or(false) {
+            return None;
+        }
+
+        let patterns = |patterns: &Option<Vec<String>>, default: &[&str]| match patterns {
+            Some(patterns) => patterns.clone(),
+            None => default.iter().map(|pattern| pattern.to_string()).collect::<Vec<_>>(),
+        };
+        Some(Self::new(
+            &patterns(&settings.allowed_env_vars, DEFAULT_ALLOWED_ENV_VARS),
+            &patterns(&settings.blocked_env_vars, DEFAULT_BLOCKED_ENV_VARS),
+        ))
+    }
+
+    fn new(allowed: &[String], blocked: &[String]) -> Self {
+        Self {
+            allowed: build_glob_set(allowed),
+            blocked: build_glob_set(blocked),
+        }
+    }
+
+    /// Whether the variable `name` is passed on to commands.
+    pub fn is_allowed(&self, name: &str) -> bool {
+        // Set by Q itself so that AWS CLI calls can be attributed, never a secret.
+        if name == USER_AGENT_ENV_VAR {
+            return true;
+        }
+        self.allowed.is_match(name) && !self.blocked.is_match(name)
+    }
+
+    /// Removes the variables from `env_vars` that are not passed on to commands.
+    pub fn apply(&self, env_vars: &mut HashMap<String, String>) {
+        env_vars.retain(|name, _| self.is_allowed(name));
+    }
+}
+
+fn build_glob_set(patterns: &[String]) -> GlobSet {
+    let mut builder = GlobSetBuilder::new();
+    for pattern in patterns {
+        match GlobBuilder::new(pattern).case_insensitive(true).build() {
+            Ok(glob) => {
+                builder.add(glob);
+            },
+            Err(e) => warn!("Ignoring invalid environment variable pattern {pattern}: {e}"),
+        }
+    }
+    builder.build().unwrap_or_else(|e| {
+        warn!("Failed to build environment variable patterns: {e}");
+        GlobSet::empty()
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cli::agent::{
+        Agent,
+        ToolSettingTarget,
+    };
+
+    fn patterns(patterns: &[&str]) -> Vec<String> {
+        patterns.iter().map(|pattern| pattern.to_string()).collect()
+    }
+
+    fn env_filter(agent: Option<&Agent>) -> Option<EnvFilter> {
+        EnvFilter::from_settings(&ExecuteSettings::from_agent(agent, "execute_bash"))
+    }
+
+    #[test]
+    fn test_env_filter_from_settings() {
+        let mut agent = Agent::default();
+        assert!(env_filter(Some(&agent)).is_none());
+        assert!(env_filter(None).is_none());
+
+        agent.tools_settings.insert(
+            ToolSettingTarget("execute_bash".to_string()),
+            serde_json::json!({ "sanitizeEnv": true }),
+        );
+        let filter = env_filter(Some(&agent)).unwrap();
+        assert!(filter.is_allowed("PATH"));
+        assert!(filter.is_allowed("LC_ALL"));
+        assert!(filter.is_allowed(USER_AGENT_ENV_VAR));
+        assert!(!filter.is_allowed("AWS_SECRET_ACCESS_KEY"));
+        assert!(!filter.is_allowed("EDITOR"));
+
+        agent.tools_settings.insert(
+            ToolSettingTarget("execute_bash".to_string()),
+            serde_json::json!({
+                "sanitizeEnv": true,
+                "allowedEnvVars": ["PATH", "AWS_*", "[invalid"],
+                "blockedEnvVars": [],
+            }),
+        );
+        let filter = env_filter(Some(&agent)).unwrap();
+        assert!(filter.is_allowed("AWS_SECRET_ACCESS_KEY"));
+        assert!(!filter.is_allowed("HOME"));
+
+        // Settings that don't match the schema fall back to the default filter
+        agent.tools_settings.insert(
+            ToolSettingTarget("execute_bash".to_string()),
+            serde_json::json!({ "sanitizeEnv": "false" }),
+        );
+        let filter = env_filter(Some(&agent)).unwrap();
+        assert!(!filter.is_allowed("AWS_SECRET_ACCESS_KEY"));
+    }
+
+    #[test]
+    fn test_env_filter_apply() {
+        let filter = EnvFilter::new(
+            &patterns(&["PATH", "HOME", "GITHUB_*", "MY_*"]),
+            &patterns(DEFAULT_BLOCKED_ENV_VARS),
+        );
+        let mut env_vars: HashMap<String, String> = [
+            ("PATH", "/usr/bin"),
+            ("HOME", "/home/user"),
+            ("GITHUB_TOKEN", "ghp_secret"),
+            ("GITHUB_REPOSITORY", "owner/repo"),
+            ("my_api_key", "secret"),
+            ("My_Db_Password", "hunter2"),
+            ("MY_SETTING", "value"),
+            ("AWS_SECRET_ACCESS_KEY", "secret"),
+        ]
+        .into_iter()
+        .map(|(name, value)| (name.to_string(), value.to_string()))
+        .collect();
+
+        filter.apply(&mut env_vars);
+        let mut names = env_vars.keys().map(String::as_str).collect::<Vec<_>>();
+        names.sort();
+        assert_eq!(names, vec!["GITHUB_REPOSITORY", "HOME", "MY_SETTING", "PATH"]);
+    }
+}
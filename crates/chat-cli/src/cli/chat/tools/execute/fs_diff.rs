@@ -0,0 +1,281 @@
+use std::collections::{
+    BTreeSet,
+    HashMap,
+};
+use std::path::{
+    Path,
+    PathBuf,
+};
+use std::time::SystemTime;
+
+use serde::Serialize;
+use walkdir::WalkDir;
+
+/// Maximum number of files recorded when snapshotting a directory that is not a git repository.
+const MAX_SNAPSHOT_FILES: usize = 10_000;
+
+/// Maximum number of paths reported in each list of [FsChanges].
+const MAX_REPORTED_PATHS: usize = 200;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct FileState {
+    /// Porcelain status code, for snapshots of a git repository
+    status: Option<String>,
+    modified: Option<SystemTime>,
+    len: u64,
+}
+
+/// The files of a directory at a point in time, used to report the side effects of a command.
+///
+/// Inside of a git repository only the files listed by `git status` are recorded, otherwise the
+/// directory is walked up to [MAX_SNAPSHOT_FILES] files.
+#[derive(Debug)]
+pub struct FsSnapshot {
+    root: PathBuf,
+    is_git: bool,
+    truncated: bool,
+    files: HashMap<PathBuf, FileState>,
+}
+
+/// Paths changed between two [FsSnapshot]s, relative to the snapshot root.
+#[derive(Debug, Default, PartialEq, Eq, Serialize)]
+pub struct FsChanges {
+    pub created: Vec<String>,
+    pub modified: Vec<String>,
+    pub deleted: Vec<String>,
+    /// Whether some changes may be missing because there were too many files
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    pub truncated: bool,
+}
+
+impl FsChanges {
+    pub fn is_empty(&self) -> bool {
+        self.created.is_empty() && self.modified.is_empty() && self.deleted.is_empty()
+    }
+}
+
+impl FsSnapshot {
+    pub async fn take(cwd: &Path) -> Self {
+        if let Some(snapshot) = Self::from_git_status(cwd).await {
+            return snapshot;
+        }
+
+        let root = cwd.to_path_buf();
+        tokio::task::spawn_blocking(move || Self::from_walk(root))
+            .await
+            .unwrap_or_else(|_| Self {
+                root: cwd.to_path_buf(),
+                is_git: false,
+                truncated: true,
+                files: HashMap::new(),
+            })
+    }
+
+    async fn from_git_status(cwd: &Path) -> Option<Self> {
+        let toplevel = git(cwd, &["rev-parse", "--show-toplevel"]).await?;
+        let root = PathBuf::from(toplevel.trim());
+        let status = git(&root, &["status", "--porcelain=v1", "-z", "--untracked-files=all"]).await?;
+
+        let files = parse_porcelain(&status)
+            .into_iter()
+            .map(|(status, path)| {
+                let metadata = std::fs::symlink_metadata(root.join(&path)).ok();
+                (path, FileState {
+                    status: Some(status),
+                    modified: metadata.as_ref().and_then(|m| m.modified().ok()),
+                    len: metadata.map_or(0, |m| m.len()),
+                })
+            })
+            .collect();
+
+        Some(Self {
+            root,
+            is_git: true,
+            truncated: false,
+            files,
+        })
+    }
+
+    fn from_walk(root: PathBuf) -> Self {
+        let mut files = HashMap::new();
+        let mut truncated = false;
+        for entry in WalkDir::new(&root).into_iter().filter_map(Result::ok) {
+            if entry.file_type().is_dir() {
+                continue;
+            }
+            if files.len() >= MAX_SNAPSHOT_FILES {
+                truncated = true;
+                break;
+            }
+            let Ok(path) = entry.path().strip_prefix(&root) else {
+                continue;
+            };
+            let metadata = entry.metadata().ok();
+            files.insert(path.to_path_buf(), FileState {
+                status: None,
+                modified: metadata.as_ref().and_then(|m| m.modified().ok()),
+                len: metadata.map_or(0, |m| m.len()),
+            });
+        }
+
+        Self {
+            root,
+            is_git: false,
+            truncated,
+            files,
+        }
+    }
+
+    /// Returns the paths that changed between `before` and this snapshot.
+    pub fn changes_since(&self, before: &FsSnapshot) -> FsChanges {
+        let mut changes = FsChanges {
+            truncated: self.truncated || before.truncated,
+            ..Default::default()
+        };
+
+        let paths = self.files.keys().chain(before.files.keys()).collect::<BTreeSet<_>>();
+        for path in paths {
+            let old = before.files.get(path);
+            let new = self.files.get(path);
+            if old == new {
+                continue;
+            }
+
+            // Files missing from a git snapshot are unmodified tracked files, while files missing
+            // from a walk did not exist.
+            let existed = match old {
+                Some(old) => old.status.as_deref().is_none_or(|status| !status.contains('D')),
+                None => {
+                    before.is_git
+                        && new
+                            .and_then(|new| new.status.as_deref())
+                            .is_none_or(|status| !status.contains('?') && !status.starts_with('A'))
+                },
+            };
+            let exists = match new {
+                Some(new) => new.status.as_deref().is_none_or(|status| !status.contains('D')),
+                None => self.is_git && self.root.join(path).exists(),
+            };
+
+            let list = match (existed, exists) {
+                (false, true) => &mut changes.created,
+                (true, true) => &mut changes.modified,
+                (true, false) => &mut changes.deleted,
+                (false, false) => continue,
+            };
+            if list.len() < MAX_REPORTED_PATHS {
+                list.push(path.to_string_lossy().to_string());
+            } else {
+                changes.truncated = true;
+            }
+        }
+
+        changes
+    }
+}
+
+async fn git(cwd: &Path, args: &[&str]) -> Option<String> {
+    let output = tokio::process::Command::new("git")
+        .arg("-C")
+        .arg(cwd)
+        .args(args)
+        .output()
+        .await
+        .ok()?;
+    output
+        .status
+        .success()
+        .then(|| String::from_utf8_lossy(&output.stdout).to_string())
+}
+
+/// Parses the output of `git status --porcelain=v1 -z` into status codes and paths.
+fn parse_porcelain(output: &str) -> Vec<(String, PathBuf)> {
+    let mut entries = Vec::new();
+    let mut fields = output.split('\0').filter(|field| !field.is_empty());
+    while let Some(field) = fields.next() {
+        let Some((status, path)) = field.split_at_checked(2) else {
+            continue;
+        };
+        // Renames and copies are followed by the original path, which is skipped.
+        if status.starts_with('R') || status.starts_with('C') {
+            fields.next();
+        }
+        entries.push((
+            status.to_string(),
+            PathBuf::from(path.strip_prefix(' ').unwrap_or(path)),
+        ));
+    }
+    entries
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_porcelain() {
+        let output = " M src/main.rs\0?? new file.txt\0R  renamed.rs\0original.rs\0 D gone.rs\0";
+        assert_eq!(parse_porcelain(output), vec![
+            (" M".to_string(), PathBuf::from("src/main.rs")),
+            ("??".to_string(), PathBuf::from("new file.txt")),
+            ("R ".to_string(), PathBuf::from("renamed.rs")),
+            (" D".to_string(), PathBuf::from("gone.rs")),
+        ]);
+    }
+
+    #[tokio::test]
+    async fn test_walk_changes() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::create_dir(dir.path().join("src")).unwrap();
+        std::fs::write(dir.path().join("src/main.rs"), "fn main() {}").unwrap();
+        std::fs::write(dir.path().join("old.txt"), "old").unwrap();
+        std::fs::write(dir.path().join("same.txt"), "same").unwrap();
+
+        let before = FsSnapshot::from_walk(dir.path().to_path_buf());
+        std::fs::write(dir.path().join("src/main.rs"), "fn main() { println!() }").unwrap();
+        std::fs::remove_file(dir.path().join("old.txt")).unwrap();
+        std::fs::write(dir.path().join("new.txt"), "new").unwrap();
+        let after = FsSnapshot::from_walk(dir.path().to_path_buf());
+
+        assert_eq!(after.changes_since(&before), FsChanges {
+            created: vec!["new.txt".to_string()],
+            modified: vec![PathBuf::from("src").join("main.rs").to_string_lossy().to_string()],
+            deleted: vec!["old.txt".to_string()],
+            truncated: false,
+        });
+        assert!(after.changes_since(&after).is_empty());
+    }
+
+    #[test]
+    fn test_git_changes() {
+        let snapshot = |files: &[(&str, &str)]| FsSnapshot {
+            root: PathBuf::from("/does/not/exist"),
+            is_git: true,
+            truncated: false,
+            files: files
+                .iter()
+                .map(|(status, path)| {
+                    (PathBuf::from(path), FileState {
+                        status: Some(status.to_string()),
+                        modified: None,
+                        len: 0,
+                    })
+                })
+                .collect(),
+        };
+
+        let before = snapshot(&[(" M", "edited.rs"), ("??", "scratch.txt")]);
+        let after = snapshot(&[
+            (" M", "edited.rs"),
+            (" M", "tracked.rs"),
+            ("??", "new.txt"),
+            (" D", "removed.rs"),
+        ]);
+        assert_eq!(after.changes_since(&before), FsChanges {
+            created: vec!["new.txt".to_string()],
+            modified: vec!["tracked.rs".to_string()],
+            deleted: vec!["removed.rs".to_string(), "scratch.txt".to_string()],
+            truncated: false,
+        });
+    }
+}
@@ -0,0 +1,182 @@
+use std::collections::BTreeSet;
+
+use schemars::JsonSchema;
+use serde::Deserialize;
+use tracing::warn;
+
+/// Maximum number of connections reported after a command.
+const MAX_REPORTED_CONNECTIONS: usize = 100;
+
+/// What a command is allowed to do on the network, configured with `networkPolicy`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, JsonSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum NetworkPolicy {
+    /// Run the command with network access
+    #[default]
+    Allow,
+    /// Report the network connections the command opened
+    Audit,
+    /// Run the command without network access. Only supported on Linux
+    Block,
+}
+
+/// The network connections of the user's processes at a point in time, used to report the
+/// connections a command opened. Taken with `ss` on Linux and `lsof` on macOS.
+///
+/// Only connections that are still open when the command finishes, including those lingering in
+/// `TIME-WAIT`, can be seen.
+#[derive(Debug)]
+pub struct NetworkSnapshot {
+    connections: BTreeSet<String>,
+}
+
+impl NetworkSnapshot {
+    /// Returns [None] if connections cannot be listed on this platform.
+    pub async fn take() -> Option<Self> {
+        let (program, args, parse): (&str, &[&str], fn(&str, u32) -> BTreeSet<String>) = match std::env::consts::OS {
+            "linux" => ("ss", &["-ntupH"], parse_ss),
+            "macos" => ("lsof", &["-i", "-n", "-P"], parse_lsof),
+            _ => return None,
+        };
+
+        let output = match tokio::process::Command::new(program).args(args).output().await {
+            Ok(output) if output.status.success() => output,
+            Ok(output) => {
+                warn!(status = ?output.status, "{program} failed, network connections are not reported");
+                return None;
+            },
+            Err(err) => {
+                warn!(?err, "Unable to run {program}, network connections are not reported");
+                return None;
+            },
+        };
+
+        Some(Self {
+            connections: parse(&String::from_utf8_lossy(&output.stdout), std::process::id()),
+        })
+    }
+
+    /// Returns the connections opened between `before` and this snapshot.
+    pub fn connections_since(&self, before: &NetworkSnapshot) -> Vec<String> {
+        self.connections
+            .difference(&before.connections)
+            .take(MAX_REPORTED_CONNECTIONS)
+            .cloned()
+            .collect()
+    }
+}
+
+/// Parses the output of `ss -ntupH` into `<protocol> <local> -> <peer> <process>` entries.
+/// Connections of the process `own_pid`, such as the ones to the model, are skipped.
+fn parse_ss(output: &str, own_pid: u32) -> BTreeSet<String> {
+    let own_pid = format!("pid={own_pid},");
+    output
+        .lines()
+        .filter(|line| !line.contains(&own_pid))
+        .filter_map(|line| {
+            let fields = line.split_whitespace().collect::<Vec<_>>();
+            let [protocol, _state, _recv_q, _send_q, local, peer, process @ ..] = fields.as_slice() else {
+                return None;
+            };
+            let connection = format!("{protocol} {local} -> {peer} {}", process.join(" "));
+            Some(connection.trim_end().to_string())
+        })
+        .collect()
+}
+
+/// Parses the output of `lsof -i -n -P` into `<command> <protocol> <connection>` entries.
+/// Connections of the process `own_pid`, such as the ones to the model, are skipped.
+fn parse_lsof(output: &str, own_pid: u32) -> BTreeSet<String> {
+    let own_pid = own_pid.to_string();
+    output
+        .lines()
+        .skip(1)
+        .filter_map(|line| {
+            let fields = line.split_whitespace().collect::<Vec<_>>();
+            let [command, pid, _user, _fd, _type, _device, _size, protocol, name @ ..] = fields.as_slice() else {
+                return None;
+            };
+            (*pid != own_pid).then(|| format!("{command} {protocol} {}", name.join(" ")))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cli::agent::{
+        Agent,
+        ToolSettingTarget,
+    };
+    use crate::cli::chat::tools::execute::ExecuteSettings;
+
+    fn network_policy(agent: Option<&Agent>, tool_name: &str) -> NetworkPolicy {
+        ExecuteSettings::from_agent(agent, tool_name).network_policy()
+    }
+
+    #[test]
+    fn test_network_policy() {
+        let agent = |policy: &str| {
+            let mut agent = Agent::default();
+            agent.tools_settings.insert(
+                ToolSettingTarget("execute_bash".to_string()),
+                serde_json::json!({ "networkPolicy": policy }),
+            );
+            agent
+        };
+
+        assert_eq!(network_policy(None, "execute_bash"), NetworkPolicy::Allow);
+        assert_eq!(
+            network_policy(Some(&agent("audit")), "execute_bash"),
+            NetworkPolicy::Audit
+        );
+        assert_eq!(
+            network_policy(Some(&agent("audit")), "execute_cmd"),
+            NetworkPolicy::Allow
+        );
+        assert_eq!(
+            network_policy(Some(&agent("block")), "execute_bash"),
+            NetworkPolicy::Block
+        );
+        assert_eq!(
+            network_policy(Some(&agent("deny")), "execute_bash"),
+            NetworkPolicy::Block
+        );
+    }
+
+    #[test]
+    fn test_connections_since() {
+        let before = NetworkSnapshot {
+            connections: parse_ss(
+                "tcp   ESTAB 0 0 10.0.0.2:51000 52.94.1.1:443 users:((\"qchat\",pid=42,fd=9))\n\
+                 tcp   ESTAB 0 0 10.0.0.2:52000 140.82.1.1:22 users:((\"ssh\",pid=7,fd=3))\n",
+                42,
+            ),
+        };
+        let after = NetworkSnapshot {
+            connections: parse_ss(
+                "tcp   ESTAB 0 0 10.0.0.2:51000 52.94.1.1:443 users:((\"qchat\",pid=42,fd=9))\n\
+                 tcp   ESTAB 0 0 10.0.0.2:52000 140.82.1.1:22 users:((\"ssh\",pid=7,fd=3))\n\
+                 tcp   TIME-WAIT 0 0 10.0.0.2:53000 93.184.215.14:80\n\
+                 udp   ESTAB 0 0 10.0.0.2:40000 8.8.8.8:53 users:((\"curl\",pid=99,fd=4))\n",
+                42,
+            ),
+        };
+
+        assert_eq!(after.connections_since(&before), vec![
+            "tcp 10.0.0.2:53000 -> 93.184.215.14:80".to_string(),
+            "udp 10.0.0.2:40000 -> 8.8.8.8:53 users:((\"curl\",pid=99,fd=4))".to_string(),
+        ]);
+        assert!(after.connections_since(&after).is_empty());
+    }
+
+    #[test]
+    fn test_parse_lsof() {
+        let output = "COMMAND PID USER FD TYPE DEVICE SIZE/OFF NODE NAME\n\
+                      qchat 42 me 9u IPv4 0x1 0t0 TCP 10.0.0.2:51000->52.94.1.1:443 (ESTABLISHED)\n\
+                      curl 99 me 5u IPv4 0x2 0t0 TCP 10.0.0.2:53000->93.184.215.14:80 (ESTABLISHED)\n";
+        assert_eq!(parse_lsof(output, 42).into_iter().collect::<Vec<_>>(), vec![
+            "curl TCP 10.0.0.2:53000->93.184.215.14:80 (ESTABLISHED)".to_string()
+        ]);
+    }
+}
@@ -5,6 +5,7 @@ use std::process::Stdio;
 use eyre::{
     Context as EyreContext,
     Result,
+    bail,
 };
 use tokio::io::AsyncBufReadExt;
 use tokio::select;
@@ -12,6 +13,7 @@ use tracing::error;
 
 use super::{
     CommandResult,
+    EnvFilter,
     env_vars_with_user_agent,
     format_output,
 };
@@ -21,20 +23,37 @@ use crate::os::Os;
 /// # Arguments
 /// * `command` - The command to run
 /// * `max_result_size` - max size of output streams, truncating if required
+/// * `preserve_ansi_codes` - if set, ANSI escape codes are kept in the output streams
 /// * `updates` - output stream to push informational messages about the progress
+/// * `env_filter` - if set, the command only sees the environment variables the filter allows
+/// * `block_network` - if set, the command is run without network access
 /// # Returns
 /// A [`CommandResult`]
 pub async fn run_command<W: Write>(
     os: &Os,
     command: &str,
     max_result_size: usize,
+    preserve_ansi_codes: bool,
     mut updates: Option<W>,
+    env_filter: Option<&EnvFilter>,
+    block_network: bool,
 ) -> Result<CommandResult> {
+    if block_network {
+        bail!("Blocking network access is only supported on Linux");
+    }
+
     // Set up environment variables with user agent metadata for CloudTrail tracking
-    let env_vars = env_vars_with_user_agent(os);
+    let mut env_vars = env_vars_with_user_agent(os);
+
+    let mut cmd = tokio::process::Command::new("cmd");
+    if let Some(env_filter) = env_filter {
+        // Start from an empty environment so that only the allowed variables are inherited
+        cmd.env_clear();
+        env_filter.apply(&mut env_vars);
+    }
 
     // We need to maintain a handle on stderr and stdout, but pipe it to the terminal as well
-    let mut child = tokio::process::Command::new("cmd")
+    let mut child = cmd
         .arg("/C")
         .arg(command)
         .envs(env_vars)
@@ -114,15 +133,19 @@ pub async fn run_command<W: Write>(
 
     Ok(CommandResult {
         exit_status: exit_status.code(),
-        stdout: format_output(&stdout_final, max_result_size),
-        stderr: format_output(&stderr_final, max_result_size),
+        stdout: format_output(&stdout_final, max_result_size, preserve_ansi_codes),
+        stderr: format_output(&stderr_final, max_result_size, preserve_ansi_codes),
     })
 }
 
 #[cfg(test)]
 mod tests {
     use crate::cli::chat::tools::OutputKind;
-    use crate::cli::chat::tools::execute::ExecuteCommand;
+    use crate::cli::chat::tools::execute::{
+        ExecuteCommand,
+        NetworkPolicy,
+    };
+    use crate::cli::chat::util::tokens::DEFAULT_MAX_RESULT_TOKENS;
     use crate::os::Os;
 
     #[tokio::test]
@@ -136,7 +159,15 @@ mod tests {
         });
         let out = serde_json::from_value::<ExecuteCommand>(v)
             .unwrap()
-            .invoke(&os, &mut stdout)
+            .invoke(
+                &os,
+                &mut stdout,
+                DEFAULT_MAX_RESULT_TOKENS,
+                false,
+                false,
+                None,
+                NetworkPolicy::Allow,
+            )
             .await
             .unwrap();
 
@@ -154,7 +185,15 @@ mod tests {
         });
         let out = serde_json::from_value::<ExecuteCommand>(v)
             .unwrap()
-            .invoke(&os, &mut stdout)
+            .invoke(
+                &os,
+                &mut stdout,
+                DEFAULT_MAX_RESULT_TOKENS,
+                false,
+                false,
+                None,
+                NetworkPolicy::Allow,
+            )
             .await
             .unwrap();
 
@@ -172,7 +211,15 @@ mod tests {
         });
         let out = serde_json::from_value::<ExecuteCommand>(v)
             .unwrap()
-            .invoke(&os, &mut stdout)
+            .invoke(
+                &os,
+                &mut stdout,
+                DEFAULT_MAX_RESULT_TOKENS,
+                false,
+                false,
+                None,
+                NetworkPolicy::Allow,
+            )
             .await
             .unwrap();
         if let OutputKind::Json(json) = out.output {
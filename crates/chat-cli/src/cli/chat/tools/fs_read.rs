@@ -1,6 +1,18 @@
-use std::collections::VecDeque;
+use std::collections::{
+    HashMap,
+    HashSet,
+    VecDeque,
+};
 use std::fs::Metadata;
-use std::io::Write;
+use std::io::{
+    IsTerminal,
+    Read,
+    Write,
+};
+use std::path::{
+    Path,
+    PathBuf,
+};
 
 use crossterm::queue;
 use crossterm::style::{
@@ -11,12 +23,24 @@ use eyre::{
     Result,
     bail,
 };
-use globset::GlobSetBuilder;
+use flate2::read::MultiGzDecoder;
+use globset::{
+    Glob,
+    GlobSet,
+    GlobSetBuilder,
+};
+use schemars::{
+    JsonSchema,
+    Schema,
+    SchemaGenerator,
+    json_schema,
+};
 use serde::{
     Deserialize,
     Serialize,
 };
 use syntect::util::LinesWithEndings;
+use tokio::io::AsyncReadExt;
 use tracing::{
     debug,
     error,
@@ -28,18 +52,32 @@ use super::{
     MAX_TOOL_RESPONSE_SIZE,
     OutputKind,
     format_path,
+    plain_output,
+    queue_unresolved_path_warning,
+    resolve_permission_path,
     sanitize_path_tool_arg,
 };
+use crate::api_client::model::ImageFormat;
 use crate::cli::agent::{
     Agent,
     PermissionEvalResult,
 };
+use crate::cli::chat::attachment::piped_stdin;
+use crate::cli::chat::consts::MAX_NUMBER_OF_IMAGES_PER_REQUEST;
 use crate::cli::chat::tools::display_purpose;
 use crate::cli::chat::util::images::{
+    get_image_block_from_url,
     handle_images_from_paths,
+    image_format_from_bytes,
+    is_image_url,
     is_supported_image_type,
     pre_process,
 };
+use crate::cli::chat::util::tokens::{
+    DEFAULT_MAX_RESULT_TOKENS,
+    truncate_to_tokens,
+};
+use crate::cli::chat::util::ui::fit_path_to_terminal;
 use crate::cli::chat::{
     CONTINUATION_LINE,
     sanitize_unicode_tags,
@@ -64,6 +102,129 @@ pub enum FsReadOperation {
     Image(FsImage),
 }
 
+/// Settings for the fs_read tool in the `toolsSettings` of an agent.
+#[derive(Debug, Deserialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct FsReadSettings {
+    /// Paths that can be read without prompting. Supports gitignore style glob patterns
+    #[serde(default)]
+    allowed_paths: Vec<String>,
+    /// Paths that are denied. Supports gitignore style glob patterns. Deny rules are evaluated
+    /// before allow rules
+    #[serde(default)]
+    denied_paths: Vec<String>,
+    /// Whether to allow reading paths that are not denied without prompting
+    #[serde(default = "default_allow_read_only")]
+    allow_read_only: bool,
+    /// URLs that images can be downloaded from without prompting. Supports glob patterns such as
+    /// `https://example.com/*`
+    #[serde(default)]
+    allowed_urls: Vec<String>,
+    /// Approximate token budget for file contents sent to the model. Defaults to 25000
+    max_result_tokens: Option<u64>,
+    /// Maximum number of lines a single read returns. Either one number for every file, or an
+    /// object from path patterns to numbers
+    #[serde(default)]
+    #[schemars(schema_with = "max_lines_per_read_schema")]
+    max_lines_per_read: Option<MaxLinesPerRead>,
+    /// Whether to return binary files as text, with invalid bytes replaced, instead of a summary
+    read_binary_as_text: Option<bool>,
+}
+
+/// The `maxLinesPerRead` setting of fs_read.
+#[derive(Debug, Deserialize)]
+#[serde(untagged)]
+enum MaxLinesPerRead {
+    /// One limit for every file
+    All(u64),
+    /// Limits for the files matching each path pattern
+    ByPattern(HashMap<String, u64>),
+}
+
+impl Default for FsReadSettings {
+    fn default() -> Self {
+        Self {
+            allowed_paths: Vec::new(),
+            denied_paths: Vec::new(),
+            allow_read_only: default_allow_read_only(),
+            allowed_urls: Vec::new(),
+            max_result_tokens: None,
+            max_lines_per_read: None,
+            read_binary_as_text: None,
+        }
+    }
+}
+
+impl FsReadSettings {
+    /// Returns the settings in the `fs_read` entry of the agent's `toolsSettings`, or the defaults
+    /// if the entry does not match the schema.
+    pub fn from_agent(agent: Option<&Agent>) -> Self {
+        let Some(settings) = agent.and_then(|agent| agent.tools_settings.get("fs_read")) else {
+            return Self::default();
+        };
+        serde_json::from_value(settings.clone()).unwrap_or_else(|err| {
+            warn!(?err, "Invalid tool settings for fs_read, using the defaults");
+            Self::default()
+        })
+    }
+
+    pub fn max_result_tokens(&self) -> usize {
+        self.max_result_tokens
+            .map_or(DEFAULT_MAX_RESULT_TOKENS, |value| value as usize)
+    }
+
+    /// Returns the line cap that `maxLinesPerRead` sets for reads of `path`, or [None] if reads
+    /// are not capped.
+    ///
+    /// The setting is either a number that applies to every file, or an object from path patterns
+    /// to numbers. Patterns behave like those of `allowedPaths`, relative ones being resolved
+    /// against `config_dir`, and when several of them match `path` the smallest limit applies. A
+    /// limit of 0 is ignored.
+    fn max_lines_per_read(&self, os: &Os, config_dir: Option<&Path>, path: &str) -> Option<usize> {
+        let max_lines = match self.max_lines_per_read.as_ref()? {
+            MaxLinesPerRead::All(max_lines) => Some(*max_lines),
+            MaxLinesPerRead::ByPattern(patterns) => {
+                let path = directories::canonicalizes_path(os, path).ok()?;
+                patterns
+                    .iter()
+                    .filter(|(_, max_lines)| **max_lines > 0)
+                    .filter_map(|(pattern, max_lines)| {
+                        let pattern = directories::canonicalizes_path_relative_to(os, pattern, config_dir).ok()?;
+                        let mut builder = GlobSetBuilder::new();
+                        if let Err(e) = directories::add_gitignore_globs(&mut builder, pattern.as_str()) {
+                            warn!("Failed to create glob from path given: {pattern}: {e}. Ignoring.");
+                            return None;
+                        }
+                        builder.build().ok()?.is_match(&path).then_some(*max_lines)
+                    })
+                    .min()
+            },
+        };
+        let max_lines = max_lines.filter(|max_lines| *max_lines > 0)?;
+        Some(max_lines as usize)
+    }
+
+    fn read_binary_as_text(&self) -> bool {
+        self.read_binary_as_text.unwrap_or(false)
+    }
+}
+
+fn default_allow_read_only() -> bool {
+    true
+}
+
+fn max_lines_per_read_schema(_generator: &mut SchemaGenerator) -> Schema {
+    json_schema!({
+        "oneOf": [
+            { "type": "integer", "minimum": 0 },
+            {
+                "type": "object",
+                "additionalProperties": { "type": "integer", "minimum": 0 }
+            }
+        ]
+    })
+}
+
 impl FsRead {
     pub async fn validate(&mut self, os: &Os) -> Result<()> {
         if self.operations.is_empty() {
@@ -76,6 +237,18 @@ impl FsRead {
     }
 
     pub async fn queue_description(&self, os: &Os, updates: &mut impl Write) -> Result<()> {
+        for op in &self.operations {
+            let paths = match op {
+                FsReadOperation::Line(FsLine { path, .. })
+                | FsReadOperation::Directory(FsDirectory { path, .. })
+                | FsReadOperation::Search(FsSearch { path, .. }) => std::slice::from_ref(path),
+                FsReadOperation::Image(fs_image) => fs_image.image_paths.as_slice(),
+            };
+            for path in paths.iter().filter(|path| !is_image_url(path) && *path != STDIN_PATH) {
+                queue_unresolved_path_warning(os, path, updates)?;
+            }
+        }
+
         if self.operations.len() == 1 {
             // Single operation - display without batch prefix
             self.operations[0].queue_description(os, updates).await
@@ -101,40 +274,68 @@ impl FsRead {
         }
     }
 
+    /// Paths of the files whose contents are returned to the model by this tool use.
+    pub fn read_paths(&self, os: &Os) -> Vec<PathBuf> {
+        self.operations
+            .iter()
+            .filter_map(|op| match op {
+                FsReadOperation::Line(fs_line) if !fs_line.is_stdin() => {
+                    Some(sanitize_path_tool_arg(os, &fs_line.path))
+                },
+                _ => None,
+            })
+            .collect()
+    }
+
+    /// Evaluates the permission of the tool use as a whole. A batch is only denied when every
+    /// operation is denied; otherwise the denied operations are skipped by [Self::invoke] and
+    /// reported to the model as errors.
     pub fn eval_perm(&self, os: &Os, agent: &Agent) -> PermissionEvalResult {
-        #[derive(Debug, Deserialize)]
-        #[serde(rename_all = "camelCase")]
-        struct Settings {
-            #[serde(default)]
-            allowed_paths: Vec<String>,
-            #[serde(default)]
-            denied_paths: Vec<String>,
-            #[serde(default = "default_allow_read_only")]
-            allow_read_only: bool,
+        let verdicts = self.eval_operation_perms(os, agent);
+        if !verdicts.is_empty() && verdicts.iter().all(|v| matches!(v, PermissionEvalResult::Deny(_))) {
+            PermissionEvalResult::Deny(
+                verdicts
+                    .into_iter()
+                    .flat_map(|v| match v {
+                        PermissionEvalResult::Deny(rules) => rules,
+                        _ => vec![],
+                    })
+                    .collect(),
+            )
+        } else if verdicts.iter().any(|v| matches!(v, PermissionEvalResult::Ask)) {
+            PermissionEvalResult::Ask
+        } else {
+            PermissionEvalResult::Allow
         }
+    }
 
-        fn default_allow_read_only() -> bool {
-            true
-        }
+    /// Evaluates the permission of each operation separately, in the order of
+    /// [Self::operations].
+    pub fn eval_operation_perms(&self, os: &Os, agent: &Agent) -> Vec<PermissionEvalResult> {
+        let for_all = |verdict: fn() -> PermissionEvalResult| -> Vec<PermissionEvalResult> {
+            self.operations.iter().map(|_| verdict()).collect()
+        };
 
         let is_in_allowlist = matches_any_pattern(&agent.allowed_tools, "fs_read");
         match agent.tools_settings.get("fs_read") {
             Some(settings) => {
-                let Settings {
+                let FsReadSettings {
                     allowed_paths,
                     denied_paths,
                     allow_read_only,
-                } = match serde_json::from_value::<Settings>(settings.clone()) {
+                    allowed_urls,
+                    ..
+                } = match serde_json::from_value::<FsReadSettings>(settings.clone()) {
                     Ok(settings) => settings,
                     Err(e) => {
                         error!("Failed to deserialize tool settings for fs_read: {:?}", e);
-                        return PermissionEvalResult::Ask;
+                        return for_all(|| PermissionEvalResult::Ask);
                     },
                 };
                 let allow_set = {
                     let mut builder = GlobSetBuilder::new();
                     for path in &allowed_paths {
-                        let Ok(path) = directories::canonicalizes_path(os, path) else {
+                        let Ok(path) = directories::canonicalizes_path_relative_to(os, path, agent.config_dir()) else {
                             continue;
                         };
                         if let Err(e) = directories::add_gitignore_globs(&mut builder, path.as_str()) {
@@ -144,11 +345,29 @@ impl FsRead {
                     builder.build()
                 };
 
+                let url_set = {
+                    let mut builder = GlobSetBuilder::new();
+                    for url in &allowed_urls {
+                        match Glob::new(url) {
+                            Ok(glob) => {
+                                builder.add(glob);
+                            },
+                            Err(e) => warn!("Failed to create glob from url given: {url}: {e}. Ignoring."),
+                        }
+                    }
+                    builder.build().unwrap_or_else(|e| {
+                        warn!("fs_read failed to build url set: {:?}", e);
+                        GlobSet::empty()
+                    })
+                };
+
                 let mut sanitized_deny_list = Vec::<&String>::new();
                 let deny_set = {
                     let mut builder = GlobSetBuilder::new();
                     for path in &denied_paths {
-                        let Ok(processed_path) = directories::canonicalizes_path(os, path) else {
+                        let Ok(processed_path) =
+                            directories::canonicalizes_path_relative_to(os, path, agent.config_dir())
+                        else {
                             continue;
                         };
                         match directories::add_gitignore_globs(&mut builder, processed_path.as_str()) {
@@ -166,28 +385,56 @@ impl FsRead {
 
                 match (allow_set, deny_set) {
                     (Ok(allow_set), Ok(deny_set)) => {
-                        let mut deny_list = Vec::<PermissionEvalResult>::new();
-                        let mut ask = false;
+                        let deny = |denied_match_set: Vec<usize>| {
+                            let mut denied_rules = Vec::<String>::new();
+                            for rule in denied_match_set.iter().filter_map(|i| sanitized_deny_list.get(*i)) {
+                                if !denied_rules.contains(rule) {
+                                    denied_rules.push((*rule).clone());
+                                }
+                            }
+                            PermissionEvalResult::Deny(denied_rules)
+                        };
+                        // A symlink inside an allowed directory may lead to a denied one, so the
+                        // resolved target is matched along with the path as given.
+                        let denied_matches = |path: &str| -> (Vec<usize>, Result<(), String>) {
+                            let mut denied_match_set = deny_set.matches(path);
+                            match resolve_permission_path(os, path) {
+                                Ok(resolved) => {
+                                    denied_match_set.extend(deny_set.matches(resolved));
+                                    (denied_match_set, Ok(()))
+                                },
+                                Err(reason) => (denied_match_set, Err(reason)),
+                            }
+                        };
+                        let ask_unresolved = |reason: String| {
+                            warn!("fs_read could not resolve the path to read: {reason}. Falling back to ask");
+                            PermissionEvalResult::Ask
+                        };
 
-                        for op in &self.operations {
-                            match op {
+                        self.operations
+                            .iter()
+                            .map(|op| match op {
+                                // Stdin is not a path on the file system, so only the read-only
+                                // setting applies to it
+                                FsReadOperation::Line(fs_line) if fs_line.is_stdin() => {
+                                    if is_in_allowlist || allow_read_only {
+                                        PermissionEvalResult::Allow
+                                    } else {
+                                        PermissionEvalResult::Ask
+                                    }
+                                },
                                 FsReadOperation::Line(FsLine { path, .. })
                                 | FsReadOperation::Directory(FsDirectory { path, .. })
                                 | FsReadOperation::Search(FsSearch { path, .. }) => {
                                     let Ok(path) = directories::canonicalizes_path(os, path) else {
-                                        ask = true;
-                                        continue;
+                                        return PermissionEvalResult::Ask;
                                     };
-                                    let denied_match_set = deny_set.matches(path.as_ref() as &str);
+                                    let (denied_match_set, resolved) = denied_matches(&path);
                                     if !denied_match_set.is_empty() {
-                                        let deny_res = PermissionEvalResult::Deny({
-                                            denied_match_set
-                                                .iter()
-                                                .filter_map(|i| sanitized_deny_list.get(*i).map(|s| (*s).clone()))
-                                                .collect::<Vec<_>>()
-                                        });
-                                        deny_list.push(deny_res);
-                                        continue;
+                                        return deny(denied_match_set);
+                                    }
+                                    if let Err(reason) = resolved {
+                                        return ask_unresolved(reason);
                                     }
 
                                     // We only want to ask if we are not allowing read only
@@ -196,57 +443,51 @@ impl FsRead {
                                         && !allow_read_only
                                         && !allow_set.is_match(path.as_ref() as &str)
                                     {
-                                        ask = true;
+                                        PermissionEvalResult::Ask
+                                    } else {
+                                        PermissionEvalResult::Allow
                                     }
                                 },
                                 FsReadOperation::Image(fs_image) => {
                                     let paths = &fs_image.image_paths;
-                                    let denied_match_set = paths
-                                        .iter()
-                                        .flat_map(|path| {
-                                            let Ok(path) = directories::canonicalizes_path(os, path) else {
-                                                return vec![];
-                                            };
-                                            deny_set.matches(path.as_ref() as &str)
-                                        })
-                                        .collect::<Vec<_>>();
+                                    let mut denied_match_set = Vec::new();
+                                    let mut unresolved = None;
+                                    for path in paths.iter().filter(|path| !is_image_url(path)) {
+                                        let Ok(path) = directories::canonicalizes_path(os, path) else {
+                                            continue;
+                                        };
+                                        let (matches, resolved) = denied_matches(&path);
+                                        denied_match_set.extend(matches);
+                                        unresolved = unresolved.or(resolved.err());
+                                    }
                                     if !denied_match_set.is_empty() {
-                                        let deny_res = PermissionEvalResult::Deny({
-                                            denied_match_set
-                                                .iter()
-                                                .filter_map(|i| sanitized_deny_list.get(*i).map(|s| (*s).clone()))
-                                                .collect::<Vec<_>>()
-                                        });
-                                        deny_list.push(deny_res);
-                                        continue;
+                                        return deny(denied_match_set);
+                                    }
+                                    if let Some(reason) = unresolved {
+                                        return ask_unresolved(reason);
+                                    }
+
+                                    // Downloads reach out to the network, so neither allowedTools
+                                    // nor allowReadOnly cover them
+                                    if fs_image.urls().any(|url| !url_set.is_match(url.trim())) {
+                                        return PermissionEvalResult::Ask;
                                     }
 
                                     // We only want to ask if we are not allowing read only
                                     // operation
+                                    let mut local_paths = paths.iter().filter(|path| !is_image_url(path)).peekable();
                                     if !is_in_allowlist
                                         && !allow_read_only
-                                        && !paths.iter().any(|path| allow_set.is_match(path))
+                                        && local_paths.peek().is_some()
+                                        && !local_paths.any(|path| allow_set.is_match(path))
                                     {
-                                        ask = true;
+                                        PermissionEvalResult::Ask
+                                    } else {
+                                        PermissionEvalResult::Allow
                                     }
                                 },
-                            }
-                        }
-
-                        if !deny_list.is_empty() {
-                            PermissionEvalResult::Deny({
-                                deny_list.into_iter().fold(Vec::<String>::new(), |mut acc, res| {
-                                    if let PermissionEvalResult::Deny(mut rules) = res {
-                                        acc.append(&mut rules);
-                                    }
-                                    acc
-                                })
                             })
-                        } else if ask {
-                            PermissionEvalResult::Ask
-                        } else {
-                            PermissionEvalResult::Allow
-                        }
+                            .collect()
                     },
                     (allow_res, deny_res) => {
                         if let Err(e) = allow_res {
@@ -256,19 +497,46 @@ impl FsRead {
                             warn!("fs_read failed to build deny set: {:?}", e);
                         }
                         warn!("One or more detailed args failed to parse, falling back to ask");
-                        PermissionEvalResult::Ask
+                        for_all(|| PermissionEvalResult::Ask)
                     },
                 }
             },
-            None if is_in_allowlist => PermissionEvalResult::Allow,
-            _ => PermissionEvalResult::Ask,
+            // Without allowedUrls, every download has to be confirmed
+            None if is_in_allowlist => self
+                .operations
+                .iter()
+                .map(|op| match op {
+                    FsReadOperation::Image(fs_image) if fs_image.urls().next().is_some() => PermissionEvalResult::Ask,
+                    _ => PermissionEvalResult::Allow,
+                })
+                .collect(),
+            _ => for_all(|| PermissionEvalResult::Ask),
         }
     }
 
-    pub async fn invoke(&self, os: &Os, updates: &mut impl Write) -> Result<InvokeOutput> {
+    pub async fn invoke(
+        &self,
+        os: &Os,
+        updates: &mut impl Write,
+        max_result_tokens: usize,
+        agent: Option<&Agent>,
+    ) -> Result<InvokeOutput> {
+        let mut output = self.invoke_operations(os, updates, agent).await?;
+        if let OutputKind::Text(text) | OutputKind::Mixed { text, .. } = &mut output.output {
+            truncate_to_tokens(text, max_result_tokens);
+        }
+        Ok(output)
+    }
+
+    async fn invoke_operations(
+        &self,
+        os: &Os,
+        updates: &mut impl Write,
+        agent: Option<&Agent>,
+    ) -> Result<InvokeOutput> {
         if self.operations.len() == 1 {
             // Single operation - return result directly
-            self.operations[0].invoke(os, updates).await
+            self.operations[0].invoke(os, updates, agent).await
         } else {
             // Multiple operations - combine results
             let mut combined_results = Vec::new();
@@ -277,8 +545,21 @@ impl FsRead {
             let mut success_ops = 0usize;
             let mut failed_ops = 0usize;
 
+            // Operations on denied paths are skipped so that the rest of the batch can still run
+            let verdicts = agent.map(|agent| self.eval_operation_perms(os, agent));
+
             for (i, op) in self.operations.iter().enumerate() {
-                match op.invoke(os, updates).await {
+                if let Some(PermissionEvalResult::Deny(rules)) = verdicts.as_ref().and_then(|v| v.get(i)) {
+                    failed_ops += 1;
+                    combined_results.push(format!(
+                        "=== Operation {} Error ===\nDenied by the deniedPaths of fs_read: {}",
+                        i + 1,
+                        rules.join(", ")
+                    ));
+                    continue;
+                }
+
+                match op.invoke(os, updates, agent).await {
                     Ok(result) => {
                         success_ops += 1;
 
@@ -332,6 +613,7 @@ impl FsRead {
                 updates,
                 false,
                 true,
+                plain_output(os),
             )?;
 
             let combined_text = combined_results.join("\n\n");
@@ -369,18 +651,24 @@ impl FsReadOperation {
     pub async fn queue_description(&self, os: &Os, updates: &mut impl Write) -> Result<()> {
         match self {
             FsReadOperation::Line(fs_line) => fs_line.queue_description(os, updates).await,
-            FsReadOperation::Directory(fs_directory) => fs_directory.queue_description(updates),
-            FsReadOperation::Search(fs_search) => fs_search.queue_description(updates),
+            FsReadOperation::Directory(fs_directory) => fs_directory.queue_description(os, updates),
+            FsReadOperation::Search(fs_search) => fs_search.queue_description(os, updates).await,
             FsReadOperation::Image(fs_image) => fs_image.queue_description(updates),
         }
     }
 
-    pub async fn invoke(&self, os: &Os, updates: &mut impl Write) -> Result<InvokeOutput> {
+    pub async fn invoke(&self, os: &Os, updates: &mut impl Write, agent: Option<&Agent>) -> Result<InvokeOutput> {
         match self {
-            FsReadOperation::Line(fs_line) => fs_line.invoke(os, updates).await,
+            FsReadOperation::Line(fs_line) => {
+                let settings = FsReadSettings::from_agent(agent);
+                let max_lines = settings.max_lines_per_read(os, agent.and_then(Agent::config_dir), &fs_line.path);
+                fs_line
+                    .invoke(os, updates, max_lines, settings.read_binary_as_text())
+                    .await
+            },
             FsReadOperation::Directory(fs_directory) => fs_directory.invoke(os, updates).await,
             FsReadOperation::Search(fs_search) => fs_search.invoke(os, updates).await,
-            FsReadOperation::Image(fs_image) => fs_image.invoke(updates).await,
+            FsReadOperation::Image(fs_image) => fs_image.invoke(os, updates).await,
         }
     }
 }
@@ -392,8 +680,19 @@ pub struct FsImage {
 }
 
 impl FsImage {
+    /// The image paths that are URLs to download.
+    fn urls(&self) -> impl Iterator<Item = &String> {
+        self.image_paths.iter().filter(|path| is_image_url(path))
+    }
+
     pub async fn validate(&mut self, os: &Os) -> Result<()> {
         for path in &self.image_paths {
+            if is_image_url(path) {
+                if let Err(e) = url::Url::parse(path.trim()) {
+                    bail!("'{}' is not a valid URL: {}", path, e);
+                }
+                continue;
+            }
             let path = sanitize_path_tool_arg(os, path);
             if let Some(path) = path.to_str() {
                 let processed_path = pre_process(path);
@@ -411,10 +710,16 @@ impl FsImage {
         Ok(())
     }
 
-    pub async fn invoke(&self, updates: &mut impl Write) -> Result<InvokeOutput> {
-        let pre_processed_paths: Vec<String> = self.image_paths.iter().map(|path| pre_process(path)).collect();
-        let valid_images = handle_images_from_paths(updates, &pre_processed_paths);
-        super::queue_function_result("Successfully read image", updates, false, false)?;
+    pub async fn invoke(&self, os: &Os, updates: &mut impl Write) -> Result<InvokeOutput> {
+        let (urls, paths): (Vec<&String>, Vec<&String>) = self.image_paths.iter().partition(|path| is_image_url(path));
+        let pre_processed_paths: Vec<String> = paths.into_iter().map(|path| pre_process(path)).collect();
+        let mut valid_images = handle_images_from_paths(updates, &pre_processed_paths);
+        let mut seen_urls = HashSet::new();
+        for url in urls.into_iter().filter(|url| seen_urls.insert(*url)) {
+            valid_images.push(get_image_block_from_url(url).await?);
+        }
+        valid_images.truncate(MAX_NUMBER_OF_IMAGES_PER_REQUEST);
+        super::queue_function_result("Successfully read image", updates, false, false, plain_output(os))?;
         Ok(InvokeOutput {
             output: OutputKind::Images(valid_images),
         })
@@ -433,12 +738,33 @@ impl FsImage {
     }
 }
 
+/// Note appended to the lines returned by a read that was cut short by `maxLinesPerRead`.
+fn max_lines_note(max_lines: usize, next_line: usize) -> String {
+    format!(
+        "... truncated: the maxLinesPerRead setting of fs_read limits each read to {max_lines} lines. \
+        Continue from line {next_line} in another read to see more."
+    )
+}
+
+/// Path of [FsLine] that reads what was piped through stdin instead of a file.
+const STDIN_PATH: &str = "-";
+
 /// Read lines from a file.
 #[derive(Debug, Clone, Deserialize)]
 pub struct FsLine {
     pub path: String,
     pub start_line: Option<i32>,
     pub end_line: Option<i32>,
+    /// Disjoint line ranges to read. When non-empty, `start_line` and `end_line` are ignored.
+    #[serde(default)]
+    pub ranges: Vec<LineRange>,
+}
+
+/// An inclusive, 1-based range of lines within a file.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+pub struct LineRange {
+    pub start: usize,
+    pub end: usize,
 }
 
 impl FsLine {
@@ -446,31 +772,69 @@ impl FsLine {
     const DEFAULT_START_LINE: i32 = 1;
 
     pub async fn validate(&mut self, os: &Os) -> Result<()> {
-        let path = sanitize_path_tool_arg(os, &self.path);
-        if !path.exists() {
-            bail!("'{}' does not exist", self.path);
+        if self.is_stdin() {
+            // In an interactive session stdin is where the user types their prompts
+            if std::io::stdin().is_terminal() {
+                bail!("'{STDIN_PATH}' reads what was piped into q chat, but stdin is the user's input in this session");
+            }
+        } else {
+            let path = sanitize_path_tool_arg(os, &self.path);
+            if !path.exists() {
+                bail!("'{}' does not exist", self.path);
+            }
+            let is_file = os.fs.symlink_metadata(&path).await?.is_file();
+            if !is_file {
+                bail!("'{}' is not a file", self.path);
+            }
         }
-        let is_file = os.fs.symlink_metadata(&path).await?.is_file();
-        if !is_file {
-            bail!("'{}' is not a file", self.path);
+        for range in &self.ranges {
+            if range.start == 0 || range.end < range.start {
+                bail!(
+                    "invalid line range {}-{}: ranges are 1-based and the end must not be before the start",
+                    range.start,
+                    range.end
+                );
+            }
         }
         Ok(())
     }
 
     pub async fn queue_description(&self, os: &Os, updates: &mut impl Write) -> Result<()> {
-        let path = sanitize_path_tool_arg(os, &self.path);
-        let file_bytes = os.fs.read(&path).await?;
+        let file_bytes = self.read_bytes(os).await?;
+        let is_compressed = is_gzip(&file_bytes);
+        let file_bytes = decompress_if_gzip(file_bytes)?;
         let file_content = String::from_utf8_lossy(&file_bytes);
         let line_count = file_content.lines().count();
+        let prefix = match (self.is_stdin(), is_compressed) {
+            (true, _) => "Reading from ",
+            (false, true) => "Reading compressed file: ",
+            (false, false) => "Reading file: ",
+        };
         queue!(
             updates,
-            style::Print("Reading file: "),
+            style::Print(prefix),
             style::SetForegroundColor(Color::Green),
-            style::Print(&self.path),
+            style::Print(fit_path_to_terminal(os, prefix, self.display_path())),
             style::ResetColor,
             style::Print(", "),
         )?;
 
+        if !self.ranges.is_empty() {
+            queue!(updates, style::Print("lines "))?;
+            for (i, range) in self.ranges.iter().enumerate() {
+                if i > 0 {
+                    queue!(updates, style::Print(", "))?;
+                }
+                queue!(
+                    updates,
+                    style::SetForegroundColor(Color::Green),
+                    style::Print(format!("{}-{}", range.start, range.end.min(line_count))),
+                    style::ResetColor,
+                )?;
+            }
+            return Ok(());
+        }
+
         let start = convert_negative_index(line_count, self.start_line()) + 1;
         let end = convert_negative_index(line_count, self.end_line()) + 1;
         match (start, end) {
@@ -497,20 +861,41 @@ impl FsLine {
         }
     }
 
-    pub async fn invoke(&self, os: &Os, updates: &mut impl Write) -> Result<InvokeOutput> {
-        let path = sanitize_path_tool_arg(os, &self.path);
+    /// Reads the requested lines, returning at most `max_lines` of them. Binary files are
+    /// summarized instead, unless `binary_as_text` is set.
+    pub async fn invoke(
+        &self,
+        os: &Os,
+        updates: &mut impl Write,
+        max_lines: Option<usize>,
+        binary_as_text: bool,
+    ) -> Result<InvokeOutput> {
+        let path = match self.is_stdin() {
+            true => PathBuf::from(self.display_path()),
+            false => sanitize_path_tool_arg(os, &self.path),
+        };
         debug!(?path, "Reading");
-        let file_bytes = os.fs.read(&path).await?;
+        let file_bytes = decompress_if_gzip(self.read_bytes(os).await?)?;
+        if !binary_as_text {
+            if let Some(mime_type) = binary_mime_type(&file_bytes) {
+                return Self::invoke_binary(&file_bytes, mime_type, &path, updates, plain_output(os));
+            }
+        }
         let file_content = String::from_utf8_lossy(&file_bytes);
         let file_content = sanitize_unicode_tags(&file_content);
         let line_count = file_content.lines().count();
+
+        if !self.ranges.is_empty() {
+            return self.invoke_ranges(&file_content, line_count, &path, updates, max_lines, plain_output(os));
+        }
+
         let (start, end) = (
             convert_negative_index(line_count, self.start_line()),
             convert_negative_index(line_count, self.end_line()),
         );
 
         // safety check to ensure end is always greater than start
-        let end = end.max(start);
+        let mut end = end.max(start);
 
         if start >= line_count {
             bail!(
@@ -521,13 +906,26 @@ impl FsLine {
             );
         }
 
+        // Lines past the end of the file don't count towards the limit
+        end = end.min(line_count - 1);
+        let mut truncation_note = None;
+        if let Some(max_lines) = max_lines.filter(|max_lines| end - start + 1 > *max_lines) {
+            end = start + max_lines - 1;
+            // `end` is 0-based, so the next line to read is `end + 2` in 1-based numbering
+            truncation_note = Some(max_lines_note(max_lines, end + 2));
+        }
+
         // The range should be inclusive on both ends.
-        let file_contents = file_content
+        let mut file_contents = file_content
             .lines()
             .skip(start)
             .take(end - start + 1)
             .collect::<Vec<_>>()
             .join("\n");
+        if let Some(note) = truncation_note {
+            file_contents.push('\n');
+            file_contents.push_str(&note);
+        }
 
         let byte_count = file_contents.len();
         if byte_count > MAX_TOOL_RESPONSE_SIZE {
@@ -546,6 +944,80 @@ time. You tried to read {byte_count} bytes. Try executing with fewer lines speci
             updates,
             false,
             false,
+            plain_output(os),
+        )?;
+
+        Ok(InvokeOutput {
+            output: OutputKind::Text(file_contents),
+        })
+    }
+
+    /// Reads each of [Self::ranges] separately, prefixing every section with a header showing
+    /// the line numbers it covers. At most `max_lines` lines are read across all ranges.
+    fn invoke_ranges(
+        &self,
+        file_content: &str,
+        line_count: usize,
+        path: &Path,
+        updates: &mut impl Write,
+        max_lines: Option<usize>,
+        plain: bool,
+    ) -> Result<InvokeOutput> {
+        let lines = file_content.lines().collect::<Vec<_>>();
+        let mut sections = Vec::with_capacity(self.ranges.len());
+        let mut remaining_lines = max_lines.unwrap_or(usize::MAX);
+        let mut next_line = None;
+        for range in &self.ranges {
+            if remaining_lines == 0 {
+                next_line = Some(range.start);
+                break;
+            }
+            if range.start == 0 || range.start > line_count {
+                bail!(
+                    "line range {}-{} is outside of the file, which has {} lines",
+                    range.start,
+                    range.end,
+                    line_count
+                );
+            }
+            let requested_end = range.end.clamp(range.start, line_count);
+            let end = requested_end.min(range.start.saturating_add(remaining_lines - 1));
+            remaining_lines -= end - range.start + 1;
+            sections.push(format!(
+                "=== Lines {}-{} ===\n{}",
+                range.start,
+                end,
+                lines[range.start - 1..end].join("\n")
+            ));
+            if end < requested_end {
+                next_line = Some(end + 1);
+                break;
+            }
+        }
+        if let (Some(max_lines), Some(next_line)) = (max_lines, next_line) {
+            sections.push(max_lines_note(max_lines, next_line));
+        }
+        let file_contents = sections.join("\n\n");
+
+        let byte_count = file_contents.len();
+        if byte_count > MAX_TOOL_RESPONSE_SIZE {
+            bail!(
+                "This tool only supports reading {MAX_TOOL_RESPONSE_SIZE} bytes at a
+time. You tried to read {byte_count} bytes. Try executing with fewer or smaller ranges specified."
+            );
+        }
+
+        super::queue_function_result(
+            &format!(
+                "Successfully read {} bytes from {} ({} ranges)",
+                byte_count,
+                path.display(),
+                self.ranges.len()
+            ),
+            updates,
+            false,
+            false,
+            plain,
         )?;
 
         Ok(InvokeOutput {
@@ -553,6 +1025,66 @@ time. You tried to read {byte_count} bytes. Try executing with fewer lines speci
         })
     }
 
+    /// Summarizes a binary file rather than returning its content as text, which would only be
+    /// replacement characters to the model.
+    fn invoke_binary(
+        bytes: &[u8],
+        mime_type: &str,
+        path: &Path,
+        updates: &mut impl Write,
+        plain: bool,
+    ) -> Result<InvokeOutput> {
+        let note = match image_format_from_bytes(bytes) {
+            Some(_) => "The file is an image. Read it with the Image mode to see it.",
+            None => "The file is binary, so its content is not returned as text.",
+        };
+
+        super::queue_function_result(
+            &format!(
+                "Read binary file {} ({} bytes, {mime_type})",
+                path.display(),
+                bytes.len()
+            ),
+            updates,
+            false,
+            false,
+            plain,
+        )?;
+
+        Ok(InvokeOutput {
+            output: OutputKind::Json(serde_json::json!({
+                "path": path,
+                "binary": true,
+                "size": bytes.len(),
+                "mimeType": mime_type,
+                "hexdump": hexdump(&bytes[..bytes.len().min(BINARY_HEXDUMP_LENGTH)]),
+                "note": note,
+            })),
+        })
+    }
+
+    fn is_stdin(&self) -> bool {
+        self.path == STDIN_PATH
+    }
+
+    fn display_path(&self) -> &str {
+        match self.is_stdin() {
+            true => "stdin",
+            false => &self.path,
+        }
+    }
+
+    /// Reads the file at [Self::path], or at most [MAX_TOOL_RESPONSE_SIZE] bytes of stdin when
+    /// the path is [STDIN_PATH].
+    async fn read_bytes(&self, os: &Os) -> Result<Vec<u8>> {
+        if self.is_stdin() {
+            let mut bytes = piped_stdin().await?.unwrap_or_default().to_vec();
+            bytes.truncate(MAX_TOOL_RESPONSE_SIZE);
+            return Ok(bytes);
+        }
+        Ok(os.fs.read(sanitize_path_tool_arg(os, &self.path)).await?)
+    }
+
     fn start_line(&self) -> i32 {
         self.start_line.unwrap_or(Self::DEFAULT_START_LINE)
     }
@@ -590,12 +1122,19 @@ impl FsSearch {
         Ok(())
     }
 
-    pub fn queue_description(&self, updates: &mut impl Write) -> Result<()> {
+    pub async fn queue_description(&self, os: &Os, updates: &mut impl Write) -> Result<()> {
+        let path = sanitize_path_tool_arg(os, &self.path);
+        let is_compressed = is_gzip_file(os, &path).await;
+        let prefix = if is_compressed {
+            "Searching compressed file: "
+        } else {
+            "Searching: "
+        };
         queue!(
             updates,
-            style::Print("Searching: "),
+            style::Print(prefix),
             style::SetForegroundColor(Color::Green),
-            style::Print(&self.path),
+            style::Print(fit_path_to_terminal(os, prefix, &self.path)),
             style::ResetColor,
             style::Print(" for pattern: "),
             style::SetForegroundColor(Color::Green),
@@ -609,7 +1148,7 @@ impl FsSearch {
         let file_path = sanitize_path_tool_arg(os, &self.path);
         let pattern = &self.pattern;
 
-        let file_bytes = os.fs.read(&file_path).await?;
+        let file_bytes = decompress_if_gzip(os.fs.read(&file_path).await?)?;
         let file_content = String::from_utf8_lossy(&file_bytes);
         let file_content = sanitize_unicode_tags(&file_content);
         let lines: Vec<&str> = LinesWithEndings::from(&file_content).collect();
@@ -652,6 +1191,7 @@ impl FsSearch {
             updates,
             false,
             false,
+            plain_output(os),
         )?;
 
         Ok(InvokeOutput {
@@ -669,6 +1209,21 @@ impl FsSearch {
 pub struct FsDirectory {
     pub path: String,
     pub depth: Option<usize>,
+    /// Order of the entries within each directory, filesystem order if not given.
+    pub sort_by: Option<DirSortKey>,
+    pub sort_descending: Option<bool>,
+}
+
+/// What the entries of a directory listing are sorted by. Ties are always broken by name.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, strum::Display)]
+#[serde(rename_all = "lowercase")]
+#[strum(serialize_all = "lowercase")]
+pub enum DirSortKey {
+    Name,
+    Size,
+    Modified,
+    /// Directories before files, or files before directories when descending.
+    Type,
 }
 
 impl FsDirectory {
@@ -686,20 +1241,26 @@ impl FsDirectory {
         Ok(())
     }
 
-    pub fn queue_description(&self, updates: &mut impl Write) -> Result<()> {
+    pub fn queue_description(&self, os: &Os, updates: &mut impl Write) -> Result<()> {
         queue!(
             updates,
             style::Print("Reading directory: "),
             style::SetForegroundColor(Color::Green),
-            style::Print(&self.path),
+            style::Print(fit_path_to_terminal(os, "Reading directory: ", &self.path)),
             style::ResetColor,
             style::Print(" "),
         )?;
         let depth = self.depth.unwrap_or_default();
-        Ok(queue!(
-            updates,
-            style::Print(format!("with maximum depth of {}", depth))
-        )?)
+        queue!(updates, style::Print(format!("with maximum depth of {}", depth)))?;
+        if let Some(sort_by) = self.sort_by {
+            let order = if self.sort_descending.unwrap_or_default() {
+                "descending"
+            } else {
+                "ascending"
+            };
+            queue!(updates, style::Print(format!(", sorted by {sort_by} ({order})")))?;
+        }
+        Ok(())
     }
 
     pub async fn invoke(&self, os: &Os, updates: &mut impl Write) -> Result<InvokeOutput> {
@@ -714,11 +1275,18 @@ impl FsDirectory {
                 break;
             }
             let mut read_dir = os.fs.read_dir(path).await?;
-
-            #[cfg(windows)]
+            // The metadata is kept around so sorting does not need to stat the entries again.
+            let mut entries = Vec::new();
             while let Some(ent) = read_dir.next_entry().await? {
                 let md = ent.metadata().await?;
+                entries.push((ent.path(), md));
+            }
+            if let Some(sort_by) = self.sort_by {
+                sort_dir_entries(&mut entries, sort_by, self.sort_descending.unwrap_or_default());
+            }
 
+            #[cfg(windows)]
+            for (ent_path, md) in entries {
                 let modified_timestamp = md.modified()?.duration_since(std::time::UNIX_EPOCH)?.as_secs();
                 let datetime = time::OffsetDateTime::from_unix_timestamp(modified_timestamp as i64).unwrap();
                 let formatted_date = datetime
@@ -730,24 +1298,23 @@ impl FsDirectory {
                 result.push(format!(
                     "{} {} {} {}",
                     format_ftype(&md),
-                    String::from_utf8_lossy(ent.file_name().as_encoded_bytes()),
+                    String::from_utf8_lossy(ent_path.file_name().unwrap_or_default().as_encoded_bytes()),
                     formatted_date,
-                    ent.path().to_string_lossy()
+                    ent_path.to_string_lossy()
                 ));
 
-                if md.is_dir() && md.is_dir() {
-                    dir_queue.push_back((ent.path(), depth + 1));
+                if md.is_dir() {
+                    dir_queue.push_back((ent_path, depth + 1));
                 }
             }
 
             #[cfg(unix)]
-            while let Some(ent) = read_dir.next_entry().await? {
+            for (ent_path, md) in entries {
                 use std::os::unix::fs::{
                     MetadataExt,
                     PermissionsExt,
                 };
 
-                let md = ent.metadata().await?;
                 let formatted_mode = format_mode(md.permissions().mode()).into_iter().collect::<String>();
 
                 let modified_timestamp = md.modified()?.duration_since(std::time::UNIX_EPOCH)?.as_secs();
@@ -769,10 +1336,10 @@ impl FsDirectory {
                     md.gid(),
                     md.size(),
                     formatted_date,
-                    ent.path().to_string_lossy()
+                    ent_path.to_string_lossy()
                 ));
                 if md.is_dir() {
-                    dir_queue.push_back((ent.path(), depth + 1));
+                    dir_queue.push_back((ent_path, depth + 1));
                 }
             }
         }
@@ -795,6 +1362,7 @@ impl FsDirectory {
             updates,
             false,
             false,
+            plain_output(os),
         )?;
 
         Ok(InvokeOutput {
@@ -816,12 +1384,125 @@ fn convert_negative_index(line_count: usize, i: i32) -> usize {
     }
 }
 
+/// Number of leading bytes searched for a NUL byte to tell binary files from text.
+const BINARY_CHECK_LENGTH: usize = 8000;
+
+/// Number of leading bytes of a binary file included in its summary.
+const BINARY_HEXDUMP_LENGTH: usize = 256;
+
+/// Returns the MIME type of `bytes` if they are the content of a binary file, or [None] if they
+/// are text.
+///
+/// Binary files are recognized by their magic number or by a NUL byte near the start. Text in a
+/// legacy encoding such as Latin-1 is not valid UTF-8 either, but is still read as text.
+fn binary_mime_type(bytes: &[u8]) -> Option<&'static str> {
+    if let Some(format) = image_format_from_bytes(bytes) {
+        return Some(match format {
+            ImageFormat::Gif => "image/gif",
+            ImageFormat::Jpeg => "image/jpeg",
+            ImageFormat::Png => "image/png",
+            ImageFormat::Webp => "image/webp",
+        });
+    }
+    match bytes {
+        [0x7f, b'E', b'L', b'F', ..] => Some("application/x-elf"),
+        [b'%', b'P', b'D', b'F', b'-', ..] => Some("application/pdf"),
+        [b'P', b'K', 0x03, 0x04, ..] => Some("application/zip"),
+        [0x00, b'a', b's', b'm', ..] => Some("application/wasm"),
+        _ if bytes[..bytes.len().min(BINARY_CHECK_LENGTH)].contains(&0) => Some("application/octet-stream"),
+        _ => None,
+    }
+}
+
+/// Formats `bytes` like `xxd`, with the offset, up to 16 bytes in hex and those bytes as ASCII on
+/// each line.
+fn hexdump(bytes: &[u8]) -> String {
+    bytes
+        .chunks(16)
+        .enumerate()
+        .map(|(i, chunk)| {
+            let hex = chunk
+                .chunks(2)
+                .map(|pair| pair.iter().map(|b| format!("{b:02x}")).collect::<String>())
+                .collect::<Vec<_>>()
+                .join(" ");
+            let ascii = chunk
+                .iter()
+                .map(|b| match b {
+                    b' ' | b'!'..=b'~' => *b as char,
+                    _ => '.',
+                })
+                .collect::<String>();
+            format!("{:08x}: {hex:<39}  {ascii}", i * 16)
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// Magic bytes at the start of every gzip stream.
+const GZIP_MAGIC: [u8; 2] = [0x1f, 0x8b];
+
+/// Upper bound on the size a gzip compressed file may expand to when read, so that a huge
+/// compressed log can't exhaust memory before the response size checks run.
+const MAX_DECOMPRESSED_SIZE: u64 = 64 * 1024 * 1024;
+
+fn is_gzip(bytes: &[u8]) -> bool {
+    bytes.starts_with(&GZIP_MAGIC)
+}
+
+/// Checks the leading bytes of the file at `path` for the gzip magic number without reading
+/// the whole file.
+async fn is_gzip_file(os: &Os, path: impl AsRef<Path>) -> bool {
+    let Ok(mut file) = os.fs.open(path).await else {
+        return false;
+    };
+    let mut magic = [0u8; GZIP_MAGIC.len()];
+    file.read_exact(&mut magic).await.is_ok() && is_gzip(&magic)
+}
+
+/// Returns the decompressed contents of `bytes` if they are gzip compressed, otherwise returns
+/// them unchanged.
+fn decompress_if_gzip(bytes: Vec<u8>) -> Result<Vec<u8>> {
+    if !is_gzip(&bytes) {
+        return Ok(bytes);
+    }
+
+    let mut decompressed = Vec::new();
+    MultiGzDecoder::new(bytes.as_slice())
+        .take(MAX_DECOMPRESSED_SIZE + 1)
+        .read_to_end(&mut decompressed)
+        .map_err(|e| eyre::eyre!("Failed to decompress gzip file: {e}"))?;
+    if decompressed.len() as u64 > MAX_DECOMPRESSED_SIZE {
+        bail!("Compressed file expands to more than {MAX_DECOMPRESSED_SIZE} bytes and cannot be read");
+    }
+
+    Ok(decompressed)
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 struct SearchMatch {
     line_number: usize,
     context: String,
 }
 
+/// Sorts the entries of a single directory by `sort_by`, breaking ties by name.
+fn sort_dir_entries(entries: &mut [(PathBuf, Metadata)], sort_by: DirSortKey, descending: bool) {
+    entries.sort_by(|(a_path, a_md), (b_path, b_md)| {
+        let ordering = match sort_by {
+            DirSortKey::Name => std::cmp::Ordering::Equal,
+            DirSortKey::Size => a_md.len().cmp(&b_md.len()),
+            DirSortKey::Modified => a_md.modified().ok().cmp(&b_md.modified().ok()),
+            DirSortKey::Type => b_md.is_dir().cmp(&a_md.is_dir()),
+        };
+        let by_name = a_path.file_name().cmp(&b_path.file_name());
+        match (sort_by, descending) {
+            (DirSortKey::Name, true) => by_name.reverse(),
+            (_, true) => ordering.reverse().then(by_name),
+            (_, false) => ordering.then(by_name),
+        }
+    });
+}
+
 fn format_ftype(md: &Metadata) -> char {
     if md.is_symlink() {
         'l'
@@ -868,6 +1549,7 @@ mod tests {
     use crate::cli::chat::util::test::{
         TEST_FILE_CONTENTS,
         TEST_FILE_PATH,
+        TEST_HIDDEN_FILE_PATH,
         setup_test_directory,
     };
 
@@ -941,7 +1623,7 @@ mod tests {
                 });
                 let output = serde_json::from_value::<FsRead>(v)
                     .unwrap()
-                    .invoke(&os, &mut stdout)
+                    .invoke(&os, &mut stdout, DEFAULT_MAX_RESULT_TOKENS, None)
                     .await
                     .unwrap();
 
@@ -962,6 +1644,89 @@ mod tests {
         assert_lines!(2, None::<i32>, lines[1..]);
     }
 
+    #[tokio::test]
+    async fn test_fs_read_truncates_to_token_budget() {
+        let os = setup_test_directory().await;
+        let mut stdout = std::io::stdout();
+
+        let v = serde_json::json!({
+            "operations": [{ "path": TEST_FILE_PATH, "mode": "Line" }]
+        });
+        let output = serde_json::from_value::<FsRead>(v)
+            .unwrap()
+            .invoke(&os, &mut stdout, 10, None)
+            .await
+            .unwrap();
+
+        if let OutputKind::Text(text) = output.output {
+            // Only the first line fits in the budget
+            assert!(text.starts_with("1: Hello world!\n... truncated: original output was"));
+        } else {
+            panic!("expected text output");
+        }
+    }
+
+    #[tokio::test]
+    async fn test_fs_read_line_ranges_invoke() {
+        let os = setup_test_directory().await;
+        let lines = TEST_FILE_CONTENTS.lines().collect::<Vec<_>>();
+        let mut stdout = std::io::stdout();
+
+        let v = serde_json::json!({
+            "operations": [{
+            "path": TEST_FILE_PATH,
+            "mode": "Line",
+            "start_line": 2,
+            "ranges": [{ "start": 1, "end": 1 }, { "start": 3, "end": 100 }]}]
+        });
+        let mut fs_read = serde_json::from_value::<FsRead>(v).unwrap();
+        fs_read.validate(&os).await.unwrap();
+        let output = fs_read
+            .invoke(&os, &mut stdout, DEFAULT_MAX_RESULT_TOKENS, None)
+            .await
+            .unwrap();
+
+        if let OutputKind::Text(text) = output.output {
+            let expected = format!(
+                "=== Lines 1-1 ===\n{}\n\n=== Lines 3-{} ===\n{}",
+                lines[0],
+                lines.len(),
+                lines[2..].join("\n")
+            );
+            assert_eq!(text, expected);
+        } else {
+            panic!("expected text output");
+        }
+
+        let v = serde_json::json!({
+            "operations": [{
+            "path": TEST_FILE_PATH,
+            "mode": "Line",
+            "ranges": [{ "start": 3, "end": 2 }]}]
+        });
+        assert!(
+            serde_json::from_value::<FsRead>(v)
+                .unwrap()
+                .validate(&os)
+                .await
+                .is_err()
+        );
+
+        let v = serde_json::json!({
+            "operations": [{
+            "path": TEST_FILE_PATH,
+            "mode": "Line",
+            "ranges": [{ "start": 100, "end": 200 }]}]
+        });
+        assert!(
+            serde_json::from_value::<FsRead>(v)
+                .unwrap()
+                .invoke(&os, &mut stdout, DEFAULT_MAX_RESULT_TOKENS, None)
+                .await
+                .is_err()
+        );
+    }
+
     #[tokio::test]
     async fn test_fs_read_line_past_eof() {
         let os = setup_test_directory().await;
@@ -975,7 +1740,7 @@ mod tests {
         assert!(
             serde_json::from_value::<FsRead>(v)
                 .unwrap()
-                .invoke(&os, &mut stdout)
+                .invoke(&os, &mut stdout, DEFAULT_MAX_RESULT_TOKENS, None)
                 .await
                 .is_err()
         );
@@ -1008,7 +1773,7 @@ mod tests {
         }]});
         let output = serde_json::from_value::<FsRead>(v)
             .unwrap()
-            .invoke(&os, &mut stdout)
+            .invoke(&os, &mut stdout, DEFAULT_MAX_RESULT_TOKENS, None)
             .await
             .unwrap();
 
@@ -1027,7 +1792,7 @@ mod tests {
         });
         let output = serde_json::from_value::<FsRead>(v)
             .unwrap()
-            .invoke(&os, &mut stdout)
+            .invoke(&os, &mut stdout, DEFAULT_MAX_RESULT_TOKENS, None)
             .await
             .unwrap();
 
@@ -1043,6 +1808,56 @@ mod tests {
         }
     }
 
+    #[tokio::test]
+    async fn test_fs_read_directory_sorted() {
+        async fn list(os: &Os, sort_by: &str, sort_descending: bool) -> Vec<String> {
+            let v = serde_json::json!({
+                "operations": [{
+                "mode": "Directory",
+                "path": "/sorted",
+                "sort_by": sort_by,
+                "sort_descending": sort_descending,
+            }]});
+            let output = serde_json::from_value::<FsRead>(v)
+                .unwrap()
+                .invoke(os, &mut std::io::sink(), DEFAULT_MAX_RESULT_TOKENS, None)
+                .await
+                .unwrap();
+            let OutputKind::Text(text) = output.output else {
+                panic!("expected text output");
+            };
+            text.lines()
+                .map(|line| line.rsplit(['/', '\\']).next().unwrap().to_string())
+                .collect()
+        }
+
+        let os = Os::new().await.unwrap();
+        os.fs.create_dir_all("/sorted/dir").await.unwrap();
+        os.fs.write("/sorted/b.txt", "small").await.unwrap();
+        os.fs.write("/sorted/a.txt", "larger than b").await.unwrap();
+        os.fs.write("/sorted/c.txt", "").await.unwrap();
+        for (name, secs) in [("a.txt", 300), ("b.txt", 100), ("c.txt", 200)] {
+            std::fs::File::options()
+                .write(true)
+                .open(os.fs.chroot_path(format!("/sorted/{name}")))
+                .unwrap()
+                .set_modified(std::time::UNIX_EPOCH + std::time::Duration::from_secs(secs))
+                .unwrap();
+        }
+
+        assert_eq!(list(&os, "name", false).await, ["a.txt", "b.txt", "c.txt", "dir"]);
+        assert_eq!(list(&os, "name", true).await, ["dir", "c.txt", "b.txt", "a.txt"]);
+        assert_eq!(list(&os, "type", false).await, ["dir", "a.txt", "b.txt", "c.txt"]);
+        assert_eq!(list(&os, "type", true).await, ["a.txt", "b.txt", "c.txt", "dir"]);
+
+        // Leave the directory out, its size and modification time depend on the filesystem
+        let files = |names: Vec<String>| names.into_iter().filter(|name| name != "dir").collect::<Vec<_>>();
+        assert_eq!(files(list(&os, "size", false).await), ["c.txt", "b.txt", "a.txt"]);
+        assert_eq!(files(list(&os, "size", true).await), ["a.txt", "b.txt", "c.txt"]);
+        assert_eq!(files(list(&os, "modified", false).await), ["b.txt", "c.txt", "a.txt"]);
+        assert_eq!(files(list(&os, "modified", true).await), ["a.txt", "c.txt", "b.txt"]);
+    }
+
     #[tokio::test]
     async fn test_fs_read_search_invoke() {
         let os = setup_test_directory().await;
@@ -1053,7 +1868,7 @@ mod tests {
                 let v = serde_json::json!($value);
                 let output = serde_json::from_value::<FsRead>(v)
                     .unwrap()
-                    .invoke(&os, &mut stdout)
+                    .invoke(&os, &mut stdout, DEFAULT_MAX_RESULT_TOKENS, None)
                     .await
                     .unwrap();
 
@@ -1100,7 +1915,7 @@ mod tests {
         });
         let output = serde_json::from_value::<FsRead>(v)
             .unwrap()
-            .invoke(&os, &mut stdout)
+            .invoke(&os, &mut stdout, DEFAULT_MAX_RESULT_TOKENS, None)
             .await
             .unwrap();
 
@@ -1116,6 +1931,123 @@ mod tests {
         }
     }
 
+    #[tokio::test]
+    async fn test_fs_read_gzip_file() {
+        use flate2::Compression;
+        use flate2::write::GzEncoder;
+
+        let os = Os::new().await.unwrap();
+        let mut stdout = std::io::stdout();
+
+        let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+        encoder
+            .write_all(b"first line\nerror: second line\nthird line")
+            .unwrap();
+        let gz_file_path = "/app.log.gz";
+        os.fs.write(gz_file_path, encoder.finish().unwrap()).await.unwrap();
+
+        let v = serde_json::json!({
+            "operations": [{
+            "path": gz_file_path,
+            "mode": "Line",
+            "start_line": 2,
+            "end_line": 3}]
+        });
+        let output = serde_json::from_value::<FsRead>(v)
+            .unwrap()
+            .invoke(&os, &mut stdout, DEFAULT_MAX_RESULT_TOKENS, None)
+            .await
+            .unwrap();
+        if let OutputKind::Text(text) = output.output {
+            assert_eq!(text, "error: second line\nthird line");
+        } else {
+            panic!("expected text output");
+        }
+
+        let v = serde_json::json!({
+            "operations": [{
+            "path": gz_file_path,
+            "mode": "Search",
+            "pattern": "error"}]
+        });
+        let output = serde_json::from_value::<FsRead>(v)
+            .unwrap()
+            .invoke(&os, &mut stdout, DEFAULT_MAX_RESULT_TOKENS, None)
+            .await
+            .unwrap();
+        if let OutputKind::Text(text) = output.output {
+            let matches: Vec<SearchMatch> = serde_json::from_str(&text).unwrap();
+            assert_eq!(matches.len(), 1);
+            assert_eq!(matches[0].line_number, 2);
+        } else {
+            panic!("expected text output");
+        }
+
+        assert!(is_gzip_file(&os, gz_file_path).await);
+    }
+
+    #[test]
+    fn test_decompress_if_gzip_passthrough() {
+        let bytes = b"plain text".to_vec();
+        assert_eq!(decompress_if_gzip(bytes.clone()).unwrap(), bytes);
+        assert!(decompress_if_gzip(vec![0x1f, 0x8b, 0x00]).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_fs_read_binary_file_summary() {
+        async fn read(os: &Os, path: &str, agent: Option<&Agent>) -> OutputKind {
+            serde_json::from_value::<FsRead>(serde_json::json!({
+                "operations": [{ "path": path, "mode": "Line" }],
+            }))
+            .unwrap()
+            .invoke(os, &mut std::io::stdout(), DEFAULT_MAX_RESULT_TOKENS, agent)
+            .await
+            .unwrap()
+            .output
+        }
+
+        let os = Os::new().await.unwrap();
+        // Signature and header chunk of a 1x1 PNG
+        let png = b"\x89PNG\r\n\x1a\n\0\0\0\rIHDR\0\0\0\x01\0\0\0\x01\x08\x06\0\0\0\x1f\x15\xc4\x89";
+        os.fs.write("/pixel.png", png).await.unwrap();
+        // Deterministic noise, which includes NUL bytes
+        let noise = (0..1024u32)
+            .map(|i| (i.wrapping_mul(2_654_435_761) >> 13) as u8)
+            .collect::<Vec<_>>();
+        os.fs.write("/noise.bin", &noise).await.unwrap();
+
+        let OutputKind::Json(summary) = read(&os, "/pixel.png", None).await else {
+            panic!("expected a json summary");
+        };
+        assert_eq!(summary["binary"], true);
+        assert_eq!(summary["size"], png.len());
+        assert_eq!(summary["mimeType"], "image/png");
+        assert!(summary["note"].as_str().unwrap().contains("Image mode"));
+        assert!(
+            summary["hexdump"]
+                .as_str()
+                .unwrap()
+                .starts_with("00000000: 8950 4e47 0d0a 1a0a 0000 000d 4948 4452  .PNG........IHDR\n")
+        );
+
+        let OutputKind::Json(summary) = read(&os, "/noise.bin", None).await else {
+            panic!("expected a json summary");
+        };
+        assert_eq!(summary["size"], 1024);
+        assert_eq!(summary["mimeType"], "application/octet-stream");
+        assert!(!summary["note"].as_str().unwrap().contains("Image mode"));
+        assert_eq!(
+            summary["hexdump"].as_str().unwrap().lines().count(),
+            BINARY_HEXDUMP_LENGTH / 16
+        );
+
+        let agent = agent_with_fs_read_settings(serde_json::json!({ "readBinaryAsText": true }));
+        assert!(matches!(
+            read(&os, "/noise.bin", Some(&agent)).await,
+            OutputKind::Text(_)
+        ));
+    }
+
     #[tokio::test]
     async fn test_fs_read_latin1_encoded_file() {
         let os = Os::new().await.unwrap();
@@ -1132,7 +2064,7 @@ mod tests {
         });
         let output = serde_json::from_value::<FsRead>(v)
             .unwrap()
-            .invoke(&os, &mut stdout)
+            .invoke(&os, &mut stdout, DEFAULT_MAX_RESULT_TOKENS, None)
             .await
             .unwrap();
 
@@ -1169,7 +2101,7 @@ mod tests {
         });
         let output = serde_json::from_value::<FsRead>(v)
             .unwrap()
-            .invoke(&os, &mut stdout)
+            .invoke(&os, &mut stdout, DEFAULT_MAX_RESULT_TOKENS, None)
             .await
             .unwrap();
 
@@ -1193,7 +2125,7 @@ mod tests {
         });
         let output = serde_json::from_value::<FsRead>(v)
             .unwrap()
-            .invoke(&os, &mut stdout)
+            .invoke(&os, &mut stdout, DEFAULT_MAX_RESULT_TOKENS, None)
             .await
             .unwrap();
 
@@ -1230,7 +2162,7 @@ mod tests {
         });
         let output = serde_json::from_value::<FsRead>(v)
             .unwrap()
-            .invoke(&os, &mut stdout)
+            .invoke(&os, &mut stdout, DEFAULT_MAX_RESULT_TOKENS, None)
             .await
             .unwrap();
 
@@ -1270,7 +2202,7 @@ mod tests {
         });
         let output = serde_json::from_value::<FsRead>(v)
             .unwrap()
-            .invoke(&os, &mut stdout)
+            .invoke(&os, &mut stdout, DEFAULT_MAX_RESULT_TOKENS, None)
             .await
             .unwrap();
 
@@ -1300,7 +2232,7 @@ mod tests {
         });
         let output = serde_json::from_value::<FsRead>(v)
             .unwrap()
-            .invoke(&os, &mut stdout)
+            .invoke(&os, &mut stdout, DEFAULT_MAX_RESULT_TOKENS, None)
             .await
             .unwrap();
 
@@ -1319,7 +2251,7 @@ mod tests {
         });
         let output = serde_json::from_value::<FsRead>(v)
             .unwrap()
-            .invoke(&os, &mut stdout)
+            .invoke(&os, &mut stdout, DEFAULT_MAX_RESULT_TOKENS, None)
             .await
             .unwrap();
 
@@ -1351,7 +2283,7 @@ mod tests {
 
         let output = serde_json::from_value::<FsRead>(v)
             .unwrap()
-            .invoke(&os, &mut stdout)
+            .invoke(&os, &mut stdout, DEFAULT_MAX_RESULT_TOKENS, None)
             .await
             .unwrap();
         // All text operations should return combined text
@@ -1447,4 +2379,334 @@ mod tests {
                 && deny_list.iter().filter(|p| *p == DENIED_PATH_OR_FILE).collect::<Vec<_>>().len() == 2
         ));
     }
+
+    #[tokio::test]
+    async fn test_eval_perm_symlinks() {
+        const DENIED_PATH: &str = "/denied";
+
+        let agent = Agent {
+            name: "test_agent".to_string(),
+            tools_settings: {
+                let mut map = HashMap::<ToolSettingTarget, serde_json::Value>::new();
+                map.insert(
+                    ToolSettingTarget("fs_read".to_string()),
+                    serde_json::json!({
+                        "allowedPaths": ["/allowed"],
+                        "deniedPaths": [DENIED_PATH]
+                    }),
+                );
+                map
+            },
+            ..Default::default()
+        };
+
+        let os = Os::new().await.unwrap();
+        os.fs.create_dir_all("/allowed").await.unwrap();
+        os.fs.create_dir_all("/denied").await.unwrap();
+        os.fs.write("/allowed/notes.txt", "notes").await.unwrap();
+        os.fs.write("/denied/secret.txt", "secret").await.unwrap();
+        os.fs.symlink("/denied", "/allowed/linked").await.unwrap();
+        os.fs.symlink("/denied/secret.txt", "/allowed/link.txt").await.unwrap();
+        os.fs.symlink("/missing", "/allowed/broken_link").await.unwrap();
+
+        let eval = |operation: serde_json::Value| {
+            serde_json::from_value::<FsRead>(serde_json::json!({ "operations": [operation] }))
+                .unwrap()
+                .eval_perm(&os, &agent)
+        };
+        let denied = PermissionEvalResult::Deny(vec![DENIED_PATH.to_string()]);
+
+        // Reading through a symlinked file or directory is denied
+        assert_eq!(
+            eval(serde_json::json!({ "path": "/allowed/link.txt", "mode": "Line" })),
+            denied
+        );
+        assert_eq!(
+            eval(serde_json::json!({ "path": "/allowed/linked", "mode": "Directory" })),
+            denied
+        );
+        assert_eq!(
+            eval(serde_json::json!({ "path": "/allowed/linked/secret.txt", "mode": "Search", "pattern": "s" })),
+            denied
+        );
+        assert_eq!(
+            eval(serde_json::json!({ "image_paths": ["/allowed/linked/image.png"], "mode": "Image" })),
+            denied
+        );
+
+        // Paths that stay inside the allowed directory are unaffected
+        assert_eq!(
+            eval(serde_json::json!({ "path": "/allowed/notes.txt", "mode": "Line" })),
+            PermissionEvalResult::Allow
+        );
+
+        // The target of a broken symlink is unknown, so permission is asked for
+        assert_eq!(
+            eval(serde_json::json!({ "path": "/allowed/broken_link", "mode": "Line" })),
+            PermissionEvalResult::Ask
+        );
+    }
+
+    #[tokio::test]
+    async fn test_eval_perm_agent_relative_paths() {
+        let mut agent = Agent {
+            name: "test_agent".to_string(),
+            tools_settings: {
+                let mut map = HashMap::<ToolSettingTarget, serde_json::Value>::new();
+                map.insert(
+                    ToolSettingTarget("fs_read".to_string()),
+                    serde_json::json!({
+                        "allowedPaths": ["./../../src/**"],
+                        "deniedPaths": ["./../../src/secrets"]
+                    }),
+                );
+                map
+            },
+            path: Some(PathBuf::from("/repo/.amazonq/agents/test_agent.json")),
+            ..Default::default()
+        };
+
+        let os = Os::new().await.unwrap();
+        let eval = |agent: &Agent, path: &str| {
+            serde_json::from_value::<FsRead>(serde_json::json!({
+                "operations": [{ "path": path, "mode": "Line" }]
+            }))
+            .unwrap()
+            .eval_perm(&os, agent)
+        };
+
+        // Patterns starting with `./` are relative to the directory of the agent config
+        assert_eq!(eval(&agent, "/repo/src/main.rs"), PermissionEvalResult::Allow);
+        assert_eq!(
+            eval(&agent, "/repo/src/secrets/key.pem"),
+            PermissionEvalResult::Deny(vec!["./../../src/secrets".to_string()])
+        );
+        assert_eq!(eval(&agent, "/other/src/main.rs"), PermissionEvalResult::Ask);
+
+        // Moving the agent config moves the paths along with it
+        agent.path = Some(PathBuf::from("/other/.amazonq/agents/test_agent.json"));
+        assert_eq!(eval(&agent, "/repo/src/main.rs"), PermissionEvalResult::Ask);
+        assert_eq!(eval(&agent, "/other/src/main.rs"), PermissionEvalResult::Allow);
+    }
+
+    #[tokio::test]
+    async fn test_batch_skips_denied_operations() {
+        let os = setup_test_directory().await;
+        let agent = Agent {
+            name: "test_agent".to_string(),
+            tools_settings: {
+                let mut map = HashMap::<ToolSettingTarget, serde_json::Value>::new();
+                map.insert(
+                    ToolSettingTarget("fs_read".to_string()),
+                    serde_json::json!({ "deniedPaths": ["/aaaa2"] }),
+                );
+                map
+            },
+            ..Default::default()
+        };
+
+        let batch = serde_json::from_value::<FsRead>(serde_json::json!({
+            "operations": [
+                { "path": TEST_FILE_PATH, "mode": "Line" },
+                { "path": TEST_HIDDEN_FILE_PATH, "mode": "Line" },
+            ],
+        }))
+        .unwrap();
+        assert_eq!(batch.eval_operation_perms(&os, &agent), vec![
+            PermissionEvalResult::Allow,
+            PermissionEvalResult::Deny(vec!["/aaaa2".to_string()]),
+        ]);
+        assert_eq!(batch.eval_perm(&os, &agent), PermissionEvalResult::Allow);
+
+        let output = batch
+            .invoke(&os, &mut std::io::sink(), DEFAULT_MAX_RESULT_TOKENS, Some(&agent))
+            .await
+            .unwrap();
+        let OutputKind::Text(text) = output.output else {
+            panic!("expected text output");
+        };
+        assert!(text.contains("Hello world!"));
+        assert!(text.contains("=== Operation 2 Error ===\nDenied by the deniedPaths of fs_read: /aaaa2"));
+        assert!(!text.contains("this is a hidden file"));
+
+        // A single denied operation still denies the whole tool use
+        let single = serde_json::from_value::<FsRead>(serde_json::json!({
+            "operations": [{ "path": TEST_HIDDEN_FILE_PATH, "mode": "Line" }],
+        }))
+        .unwrap();
+        assert_eq!(
+            single.eval_perm(&os, &agent),
+            PermissionEvalResult::Deny(vec!["/aaaa2".to_string()])
+        );
+    }
+
+    #[tokio::test]
+    async fn test_fs_read_stdin() {
+        let os = Os::new().await.unwrap();
+        let stdin = serde_json::from_value::<FsRead>(serde_json::json!({
+            "operations": [{ "path": "-", "mode": "Line" }],
+        }))
+        .unwrap();
+        assert!(stdin.read_paths(&os).is_empty());
+
+        let agent = agent_with_fs_read_settings(serde_json::json!({ "deniedPaths": ["/"] }));
+        assert_eq!(stdin.eval_perm(&os, &agent), PermissionEvalResult::Allow);
+        let agent = agent_with_fs_read_settings(serde_json::json!({ "allowReadOnly": false }));
+        assert_eq!(stdin.eval_perm(&os, &agent), PermissionEvalResult::Ask);
+    }
+
+    fn agent_with_fs_read_settings(settings: serde_json::Value) -> Agent {
+        Agent {
+            name: "test_agent".to_string(),
+            tools_settings: {
+                let mut map = HashMap::<ToolSettingTarget, serde_json::Value>::new();
+                map.insert(ToolSettingTarget("fs_read".to_string()), settings);
+                map
+            },
+            ..Default::default()
+        }
+    }
+
+    fn max_lines_per_read(os: &Os, agent: Option<&Agent>, path: &str) -> Option<usize> {
+        FsReadSettings::from_agent(agent).max_lines_per_read(os, agent.and_then(Agent::config_dir), path)
+    }
+
+    #[tokio::test]
+    async fn test_max_lines_per_read() {
+        let os = setup_test_directory().await;
+        let lines = TEST_FILE_CONTENTS.lines().collect::<Vec<_>>();
+
+        let agent = agent_with_fs_read_settings(serde_json::json!({ "maxLinesPerRead": 2 }));
+        assert_eq!(max_lines_per_read(&os, Some(&agent), TEST_FILE_PATH), Some(2));
+        assert_eq!(max_lines_per_read(&os, None, TEST_FILE_PATH), None);
+
+        let read = |operation: serde_json::Value| {
+            let fs_read = serde_json::from_value::<FsRead>(serde_json::json!({ "operations": [operation] })).unwrap();
+            let (os, agent) = (&os, &agent);
+            async move {
+                let output = fs_read
+                    .invoke(os, &mut std::io::sink(), DEFAULT_MAX_RESULT_TOKENS, Some(agent))
+                    .await
+                    .unwrap();
+                let OutputKind::Text(text) = output.output else {
+                    panic!("expected text output");
+                };
+                text
+            }
+        };
+
+        // The cap applies regardless of the requested range
+        let text = read(serde_json::json!({ "path": TEST_FILE_PATH, "mode": "Line" })).await;
+        assert_eq!(text, format!("{}\n{}\n{}", lines[0], lines[1], max_lines_note(2, 3)));
+        let text =
+            read(serde_json::json!({ "path": TEST_FILE_PATH, "mode": "Line", "start_line": 2, "end_line": 3 })).await;
+        assert_eq!(text, format!("{}\n{}", lines[1], lines[2]));
+
+        // Lines requested past the end of the file don't count towards the cap
+        let start_line = lines.len() - 1;
+        let text = read(
+            serde_json::json!({ "path": TEST_FILE_PATH, "mode": "Line", "start_line": start_line, "end_line": 100 }),
+        )
+        .await;
+        assert_eq!(text, format!("{}\n{}", lines[start_line - 1], lines[start_line]));
+
+        // Ranges share the cap
+        let text = read(serde_json::json!({
+            "path": TEST_FILE_PATH,
+            "mode": "Line",
+            "ranges": [{ "start": 1, "end": 1 }, { "start": 3, "end": 100 }],
+        }))
+        .await;
+        assert_eq!(
+            text,
+            format!(
+                "=== Lines 1-1 ===\n{}\n\n=== Lines 3-3 ===\n{}\n\n{}",
+                lines[0],
+                lines[2],
+                max_lines_note(2, 4)
+            )
+        );
+    }
+
+    #[tokio::test]
+    async fn test_max_lines_per_read_by_path() {
+        let os = setup_test_directory().await;
+        let agent = agent_with_fs_read_settings(serde_json::json!({
+            "deniedPaths": ["/aaaa1"],
+            "maxLinesPerRead": { "/**": 3, TEST_FILE_PATH: 1, "/aaaa2": 2 },
+        }));
+        // The smallest limit of the matching patterns applies
+        assert_eq!(max_lines_per_read(&os, Some(&agent), TEST_FILE_PATH), Some(1));
+        assert_eq!(max_lines_per_read(&os, Some(&agent), "/aaaa1/bbbb1/cccc1"), Some(3));
+        assert_eq!(max_lines_per_read(&os, Some(&agent), TEST_HIDDEN_FILE_PATH), Some(2));
+
+        // Each operation of a batch is capped separately, and denied operations stay denied
+        let batch = serde_json::from_value::<FsRead>(serde_json::json!({
+            "operations": [
+                { "path": TEST_FILE_PATH, "mode": "Line" },
+                { "path": "/aaaa1/bbbb1/cccc1", "mode": "Line" },
+                { "path": TEST_HIDDEN_FILE_PATH, "mode": "Line" },
+            ],
+        }))
+        .unwrap();
+        let output = batch
+            .invoke(&os, &mut std::io::sink(), DEFAULT_MAX_RESULT_TOKENS, Some(&agent))
+            .await
+            .unwrap();
+        let OutputKind::Text(text) = output.output else {
+            panic!("expected text output");
+        };
+        assert!(text.contains(&format!(
+            "=== Operation 1 Result (Text) ===\n1: Hello world!\n{}",
+            max_lines_note(1, 2)
+        )));
+        assert!(text.contains("=== Operation 2 Error ===\nDenied by the deniedPaths of fs_read: /aaaa1"));
+        assert!(text.contains("=== Operation 3 Result (Text) ===\nthis is a hidden file"));
+    }
+
+    #[tokio::test]
+    async fn test_image_urls() {
+        let os = setup_test_directory().await;
+        let image = |paths: serde_json::Value| {
+            serde_json::from_value::<FsRead>(serde_json::json!({
+                "operations": [{ "image_paths": paths, "mode": "Image" }]
+            }))
+            .unwrap()
+        };
+
+        // URLs are not checked for a supported extension or an existing file
+        let mut fs_read = image(serde_json::json!(["https://example.com/chart?id=1"]));
+        assert!(fs_read.validate(&os).await.is_ok());
+        let mut fs_read = image(serde_json::json!(["https://"]));
+        assert!(fs_read.validate(&os).await.is_err());
+
+        // Denied paths only apply to local files, and downloads are not covered by allowReadOnly
+        let agent = agent_with_fs_read_settings(serde_json::json!({ "deniedPaths": ["/**"] }));
+        let fs_read = image(serde_json::json!(["https://example.com/chart.png"]));
+        assert_eq!(fs_read.eval_perm(&os, &agent), PermissionEvalResult::Ask);
+        let fs_read = image(serde_json::json!(["https://example.com/chart.png", "/img.png"]));
+        assert!(matches!(fs_read.eval_perm(&os, &agent), PermissionEvalResult::Deny(_)));
+
+        // Nor are they covered by allowedTools, only by allowedUrls
+        let mut agent = agent_with_fs_read_settings(serde_json::json!({}));
+        agent.allowed_tools.insert("fs_read".to_string());
+        let fs_read = image(serde_json::json!(["https://example.com/chart.png"]));
+        assert_eq!(fs_read.eval_perm(&os, &agent), PermissionEvalResult::Ask);
+        let mut agent = Agent::default();
+        agent.allowed_tools.insert("fs_read".to_string());
+        assert_eq!(fs_read.eval_perm(&os, &agent), PermissionEvalResult::Ask);
+
+        let agent = agent_with_fs_read_settings(serde_json::json!({ "allowedUrls": ["https://example.com/*"] }));
+        assert_eq!(fs_read.eval_perm(&os, &agent), PermissionEvalResult::Allow);
+        let agent = agent_with_fs_read_settings(serde_json::json!({
+            "allowedUrls": ["https://example.com/*"],
+            "allowReadOnly": false
+        }));
+        assert_eq!(fs_read.eval_perm(&os, &agent), PermissionEvalResult::Allow);
+        let fs_read = image(serde_json::json!([
+            "https://example.com/chart.png",
+            "https://example.org/chart.png"
+        ]));
+        assert_eq!(fs_read.eval_perm(&os, &agent), PermissionEvalResult::Ask);
+    }
 }
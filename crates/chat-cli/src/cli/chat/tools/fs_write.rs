@@ -1,6 +1,7 @@
 use std::collections::HashMap;
 use std::io::Write;
 use std::path::{
+    Component,
     Path,
     PathBuf,
 };
@@ -10,6 +11,7 @@ use crossterm::queue;
 use crossterm::style::{
     self,
     Color,
+    Stylize,
 };
 use eyre::{
     ContextCompat as _,
@@ -18,8 +20,12 @@ use eyre::{
     eyre,
 };
 use globset::GlobSetBuilder;
+use schemars::JsonSchema;
 use serde::Deserialize;
-use similar::DiffableStr;
+use similar::{
+    DiffableStr,
+    InlineChange,
+};
 use syntect::easy::HighlightLines;
 use syntect::highlighting::ThemeSet;
 use syntect::parsing::SyntaxSet;
@@ -34,15 +40,25 @@ use tracing::{
 
 use super::{
     InvokeOutput,
+    OutputKind,
     format_path,
+    plain_output,
+    queue_unresolved_path_warning,
+    resolve_permission_path,
     sanitize_path_tool_arg,
     supports_truecolor,
+    unchroot,
 };
 use crate::cli::agent::{
     Agent,
     PermissionEvalResult,
 };
 use crate::cli::chat::line_tracker::FileLineTracker;
+use crate::cli::chat::util::ui::{
+    wrap_line,
+    wrap_width,
+};
+use crate::database::settings::Setting;
 use crate::os::Os;
 use crate::util::directories;
 use crate::util::pattern_matching::matches_any_pattern;
@@ -50,6 +66,26 @@ use crate::util::pattern_matching::matches_any_pattern;
 static SYNTAX_SET: LazyLock<SyntaxSet> = LazyLock::new(SyntaxSet::load_defaults_newlines);
 static THEME_SET: LazyLock<ThemeSet> = LazyLock::new(ThemeSet::load_defaults);
 
+/// Settings for the fs_write tool in the `toolsSettings` of an agent.
+#[derive(Debug, Deserialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct FsWriteSettings {
+    /// Paths that can be written to without prompting. Supports gitignore style glob patterns
+    #[serde(default)]
+    allowed_paths: Vec<String>,
+    /// Paths that are denied. Supports gitignore style glob patterns. Deny rules are evaluated
+    /// before allow rules
+    #[serde(default)]
+    denied_paths: Vec<String>,
+    /// File extensions that can never be written, with or without the leading dot
+    #[serde(default, alias = "denied_extensions")]
+    denied_extensions: Vec<String>,
+    /// Whether to ask before writing outside the workspace, even when fs_write is allowed
+    #[serde(default)]
+    #[schemars(extend("default" = true))]
+    restrict_to_workspace: Option<bool>,
+}
+
 #[derive(Debug, Clone, Deserialize)]
 #[serde(tag = "command")]
 pub enum FsWrite {
@@ -82,16 +118,59 @@ pub enum FsWrite {
         new_str: String,
         summary: Option<String>,
     },
+    /// Removes a file or directory. Directories that are not empty are only removed if
+    /// `recursive` is set.
+    #[serde(rename = "delete")]
+    Delete {
+        path: String,
+        #[serde(default)]
+        recursive: bool,
+        summary: Option<String>,
+    },
+    /// Applies `operations` in order as a single change. If any of them fails, the files touched
+    /// by the transaction are restored to what they were before it started.
+    #[serde(rename = "transaction")]
+    Transaction {
+        operations: Vec<FsWrite>,
+        summary: Option<String>,
+    },
 }
 
 impl FsWrite {
     pub fn path(&self, os: &Os) -> PathBuf {
-        sanitize_path_tool_arg(os, match self {
+        sanitize_path_tool_arg(os, self.raw_path())
+    }
+
+    /// The path as given by the model. Empty for a transaction, see [Self::paths].
+    fn raw_path(&self) -> &str {
+        match self {
             FsWrite::Create { path, .. } => path.as_str(),
             FsWrite::StrReplace { path, .. } => path.as_str(),
             FsWrite::Insert { path, .. } => path.as_str(),
             FsWrite::Append { path, .. } => path.as_str(),
-        })
+            FsWrite::Delete { path, .. } => path.as_str(),
+            FsWrite::Transaction { .. } => "",
+        }
+    }
+
+    /// Every path written to, which for a transaction is the path of each of its operations.
+    pub fn paths(&self, os: &Os) -> Vec<PathBuf> {
+        match self {
+            FsWrite::Transaction { operations, .. } => operations.iter().map(|op| op.path(os)).collect(),
+            _ => vec![self.path(os)],
+        }
+    }
+
+    /// The name of the command, as given by the model.
+    fn command_name(&self) -> &'static str {
+        match self {
+            FsWrite::Create { .. } => "create",
+            FsWrite::StrReplace { .. } => "str_replace",
+            FsWrite::Insert { .. } => "insert",
+            FsWrite::Append { .. } => "append",
+            FsWrite::Delete { .. } => "delete",
+            FsWrite::Transaction { .. } => "transaction",
+        }
     }
 
     pub async fn invoke(
@@ -100,9 +179,38 @@ impl FsWrite {
         output: &mut impl Write,
         line_tracker: &mut HashMap<String, FileLineTracker>,
     ) -> Result<InvokeOutput> {
+        if let FsWrite::Transaction { operations, .. } = self {
+            return invoke_transaction(os, output, line_tracker, operations).await;
+        }
+
         let cwd = os.env.current_dir()?;
         let path = self.path(os);
 
+        if let FsWrite::Delete { recursive, .. } = self {
+            // Deletions are not tracked as line changes.
+            let metadata = os.fs.symlink_metadata(&path).await?;
+            let invoke_description = if metadata.is_dir() {
+                "Deleting directory: "
+            } else {
+                "Deleting: "
+            };
+            queue!(
+                output,
+                style::Print(invoke_description),
+                style::SetForegroundColor(Color::Red),
+                style::Print(format_path(cwd, &path)),
+                style::ResetColor,
+                style::Print("\n"),
+            )?;
+
+            match (metadata.is_dir(), *recursive) {
+                (true, true) => os.fs.remove_dir_all(&path).await?,
+                (true, false) => os.fs.remove_dir(&path).await?,
+                (false, _) => os.fs.remove_file(&path).await?,
+            }
+            return Ok(Default::default());
+        }
+
         self.update_line_tracker_before_invoke(os, line_tracker).await?;
 
         match self {
@@ -189,6 +297,7 @@ impl FsWrite {
                 file.push_str(new_str);
                 write_to_file(os, &path, file).await?;
             },
+            FsWrite::Delete { .. } | FsWrite::Transaction { .. } => {},
         };
 
         self.update_line_tracker_after_invoke(os, line_tracker).await?;
@@ -293,14 +402,44 @@ impl FsWrite {
                 let lines_added = new_str.lines().count();
                 (lines_added, 0)
             },
+            FsWrite::Delete { .. } | FsWrite::Transaction { .. } => (0, 0),
         };
 
         Ok(result)
     }
 
     pub fn queue_description(&self, os: &Os, output: &mut impl Write) -> Result<()> {
+        if let FsWrite::Transaction { operations, .. } = self {
+            queue!(
+                output,
+                style::Print(format!(
+                    "{} operations, applied together. If any of them fails, none of the changes are kept.\n\n",
+                    operations.len()
+                )),
+            )?;
+            for (i, operation) in operations.iter().enumerate() {
+                queue!(
+                    output,
+                    style::SetAttribute(style::Attribute::Bold),
+                    style::Print(format!(
+                        "{}/{}: {}\n",
+                        i + 1,
+                        operations.len(),
+                        operation.command_name()
+                    )),
+                    style::SetAttribute(style::Attribute::Reset),
+                )?;
+                operation.queue_description(os, output)?;
+                queue!(output, style::Print("\n"))?;
+            }
+            return super::display_purpose(self.get_summary(), output);
+        }
+
         let cwd = os.env.current_dir()?;
+        let highlight = DiffHighlight::from_settings(os);
+        let width = wrap_width(os);
         self.print_relative_path(os, output)?;
+        queue_unresolved_path_warning(os, self.raw_path(), output)?;
         match self {
             FsWrite::Create { path, .. } => {
                 let file_text = self.canonical_create_command_text();
@@ -313,7 +452,7 @@ impl FsWrite {
                     Default::default()
                 };
                 let new = stylize_output_if_able(os, &relative_path, &file_text);
-                print_diff(output, &prev, &new, 1)?;
+                print_diff(output, &prev, &new, 1, highlight, width)?;
 
                 // Display summary as purpose if available after the diff
                 super::display_purpose(self.get_summary(), output)?;
@@ -342,7 +481,7 @@ impl FsWrite {
 
                 let old = stylize_output_if_able(os, &relative_path, &old);
                 let new = stylize_output_if_able(os, &relative_path, &new);
-                print_diff(output, &old, &new, start_line)?;
+                print_diff(output, &old, &new, start_line, highlight, width)?;
 
                 // Display summary as purpose if available after the diff
                 super::display_purpose(self.get_summary(), output)?;
@@ -361,7 +500,7 @@ impl FsWrite {
                 };
                 let old_str = stylize_output_if_able(os, &relative_path, old_str);
                 let new_str = stylize_output_if_able(os, &relative_path, new_str);
-                print_diff(output, &old_str, &new_str, start_line)?;
+                print_diff(output, &old_str, &new_str, start_line, highlight, width)?;
 
                 // Display summary as purpose if available after the diff
                 super::display_purpose(self.get_summary(), output)?;
@@ -373,13 +512,33 @@ impl FsWrite {
                 let relative_path = format_path(cwd, &path);
                 let start_line = os.fs.read_to_string_sync(&path)?.lines().count() + 1;
                 let file = stylize_output_if_able(os, &relative_path, new_str);
-                print_diff(output, &Default::default(), &file, start_line)?;
+                print_diff(output, &Default::default(), &file, start_line, highlight, width)?;
 
                 // Display summary as purpose if available after the diff
                 super::display_purpose(self.get_summary(), output)?;
 
                 Ok(())
             },
+            FsWrite::Delete { recursive, .. } => {
+                let is_dir = os.fs.symlink_metadata_sync(self.path(os)).is_ok_and(|m| m.is_dir());
+                let description = match (is_dir, *recursive) {
+                    (true, true) => "This directory and all of its contents will be deleted.",
+                    (true, false) => "This empty directory will be deleted.",
+                    (false, _) => "This file will be deleted.",
+                };
+                queue!(
+                    output,
+                    style::SetForegroundColor(Color::Red),
+                    style::Print(description),
+                    style::ResetColor,
+                    style::Print("\n"),
+                )?;
+
+                super::display_purpose(self.get_summary(), output)?;
+
+                Ok(())
+            },
+            FsWrite::Transaction { .. } => Ok(()),
         }
     }
 
@@ -404,6 +563,42 @@ impl FsWrite {
                     bail!("Content to append must not be empty")
                 };
             },
+            FsWrite::Delete { path, recursive, .. } => {
+                if path.is_empty() {
+                    bail!("Path must not be empty")
+                };
+                let path = sanitize_path_tool_arg(os, path);
+                let Ok(metadata) = os.fs.symlink_metadata(&path).await else {
+                    bail!("The provided path must exist in order to delete it")
+                };
+                let cwd = os.env.current_dir()?;
+                if resolve_path(os, &cwd, Path::new("")).starts_with(resolve_path(os, &cwd, &path)) {
+                    bail!("The current working directory and its parents cannot be deleted")
+                }
+                if metadata.is_dir() && !*recursive && os.fs.read_dir(&path).await?.next_entry().await?.is_some() {
+                    bail!("The directory is not empty, set `recursive` to true to delete it along with its contents")
+                }
+            },
+            FsWrite::Transaction { operations, .. } => {
+                if operations.is_empty() {
+                    bail!("A transaction must contain at least one operation")
+                }
+                for (i, operation) in operations.iter_mut().enumerate() {
+                    if let FsWrite::Transaction { .. } = operation {
+                        bail!("Transactions cannot be nested")
+                    }
+                    // Only files are snapshotted, so a deleted directory could not be restored.
+                    if let FsWrite::Delete { .. } = operation {
+                        let metadata = os.fs.symlink_metadata(operation.path(os)).await;
+                        if metadata.is_ok_and(|m| m.is_dir()) {
+                            bail!("Directories cannot be deleted as part of a transaction")
+                        }
+                    }
+                    Box::pin(operation.validate(os))
+                        .await
+                        .map_err(|e| eyre!("Operation {} is invalid: {e}", i + 1))?;
+                }
+            },
         }
 
         Ok(())
@@ -416,21 +611,50 @@ impl FsWrite {
             FsWrite::StrReplace { path, .. } => path,
             FsWrite::Insert { path, .. } => path,
             FsWrite::Append { path, .. } => path,
+            FsWrite::Delete { path, .. } => path,
+            FsWrite::Transaction { .. } => return Ok(()),
         };
         // Sanitize the path to handle tilde expansion
         let path = sanitize_path_tool_arg(os, path);
         let relative_path = format_path(cwd, &path);
-        queue!(
-            output,
-            style::Print("Path: "),
-            style::SetForegroundColor(Color::Green),
-            style::Print(&relative_path),
-            style::ResetColor,
-            style::Print("\n\n"),
-        )?;
+        let line = format!("Path: {}", relative_path.green());
+        let line = match wrap_width(os) {
+            Some(width) => wrap_line(&line, width).join("\n"),
+            None => line,
+        };
+        queue!(output, style::Print(line), style::Print("\n\n"))?;
         Ok(())
     }
 
+    /// The content the tool proposes to write, i.e. the whole file for `create`, otherwise
+    /// `new_str`. Empty for `delete`.
+    pub fn proposed_content(&self) -> String {
+        match self {
+            FsWrite::Create { .. } => self.canonical_create_command_text(),
+            FsWrite::StrReplace { new_str, .. } | FsWrite::Insert { new_str, .. } | FsWrite::Append { new_str, .. } => {
+                new_str.clone()
+            },
+            FsWrite::Delete { .. } | FsWrite::Transaction { .. } => String::new(),
+        }
+    }
+
+    /// Returns a copy of this tool use with the content returned by [Self::proposed_content]
+    /// replaced by `content`, used when the user edits the change before accepting it.
+    pub fn with_content(&self, content: String) -> Self {
+        let mut fs_write = self.clone();
+        match &mut fs_write {
+            FsWrite::Create { file_text, new_str, .. } => {
+                *file_text = Some(content);
+                *new_str = None;
+            },
+            FsWrite::StrReplace { new_str, .. } | FsWrite::Insert { new_str, .. } | FsWrite::Append { new_str, .. } => {
+                *new_str = content;
+            },
+            FsWrite::Delete { .. } | FsWrite::Transaction { .. } => {},
+        }
+        fs_write
+    }
+
     /// Returns the text to use for the [FsWrite::Create] command. This is required since we can't
     /// rely on the model always providing `file_text`.
     fn canonical_create_command_text(&self) -> String {
@@ -457,26 +681,46 @@ impl FsWrite {
             FsWrite::StrReplace { summary, .. } => summary.as_ref(),
             FsWrite::Insert { summary, .. } => summary.as_ref(),
             FsWrite::Append { summary, .. } => summary.as_ref(),
+            FsWrite::Delete { summary, .. } => summary.as_ref(),
+            FsWrite::Transaction { summary, .. } => summary.as_ref(),
         }
     }
 
     pub fn eval_perm(&self, os: &Os, agent: &Agent) -> PermissionEvalResult {
-        #[derive(Debug, Deserialize)]
-        #[serde(rename_all = "camelCase")]
-        struct Settings {
-            #[serde(default)]
-            allowed_paths: Vec<String>,
-            #[serde(default)]
-            denied_paths: Vec<String>,
+        // Every operation of a transaction is checked before any of them is applied, so the
+        // transaction is only as permitted as its least permitted operation.
+        if let FsWrite::Transaction { operations, .. } = self {
+            let mut denied_rules = Vec::<String>::new();
+            let mut ask = false;
+            for operation in operations {
+                match operation.eval_perm(os, agent) {
+                    PermissionEvalResult::Allow => {},
+                    PermissionEvalResult::Ask => ask = true,
+                    PermissionEvalResult::Deny(rules) => {
+                        for rule in rules {
+                            if !denied_rules.contains(&rule) {
+                                denied_rules.push(rule);
+                            }
+                        }
+                    },
+                }
+            }
+            return match (denied_rules.is_empty(), ask) {
+                (false, _) => PermissionEvalResult::Deny(denied_rules),
+                (true, true) => PermissionEvalResult::Ask,
+                (true, false) => PermissionEvalResult::Allow,
+            };
         }
 
         let is_in_allowlist = matches_any_pattern(&agent.allowed_tools, "fs_write");
         match agent.tools_settings.get("fs_write") {
             Some(settings) => {
-                let Settings {
+                let FsWriteSettings {
                     allowed_paths,
                     denied_paths,
-                } = match serde_json::from_value::<Settings>(settings.clone()) {
+                    denied_extensions,
+                    restrict_to_workspace,
+                } = match serde_json::from_value::<FsWriteSettings>(settings.clone()) {
                     Ok(settings) => settings,
                     Err(e) => {
                         error!("Failed to deserialize tool settings for fs_write: {:?}", e);
@@ -486,7 +730,7 @@ impl FsWrite {
                 let allow_set = {
                     let mut builder = GlobSetBuilder::new();
                     for path in &allowed_paths {
-                        let Ok(path) = directories::canonicalizes_path(os, path) else {
+                        let Ok(path) = directories::canonicalizes_path_relative_to(os, path, agent.config_dir()) else {
                             continue;
                         };
                         if let Err(e) = directories::add_gitignore_globs(&mut builder, path.as_str()) {
@@ -500,7 +744,9 @@ impl FsWrite {
                 let deny_set = {
                     let mut builder = GlobSetBuilder::new();
                     for path in &denied_paths {
-                        let Ok(processed_path) = directories::canonicalizes_path(os, path) else {
+                        let Ok(processed_path) =
+                            directories::canonicalizes_path_relative_to(os, path, agent.config_dir())
+                        else {
                             continue;
                         };
                         match directories::add_gitignore_globs(&mut builder, processed_path.as_str()) {
@@ -522,23 +768,102 @@ impl FsWrite {
                             Self::Create { path, .. }
                             | Self::Insert { path, .. }
                             | Self::Append { path, .. }
-                            | Self::StrReplace { path, .. } => {
+                            | Self::StrReplace { path, .. }
+                            | Self::Delete { path, .. } => {
                                 let Ok(path) = directories::canonicalizes_path(os, path) else {
                                     return PermissionEvalResult::Ask;
                                 };
                                 let denied_match_set = deny_set.matches(path.as_ref() as &str);
-                                if !denied_match_set.is_empty() {
-                                    return PermissionEvalResult::Deny({
-                                        denied_match_set
-                                            .iter()
-                                            .filter_map(|i| sanitized_deny_list.get(*i).map(|s| (*s).clone()))
-                                            .collect::<Vec<_>>()
-                                    });
+                                let mut denied_rules = denied_match_set
+                                    .iter()
+                                    .filter_map(|i| sanitized_deny_list.get(*i).map(|s| (*s).clone()))
+                                    .collect::<Vec<_>>();
+                                denied_rules.extend(matching_denied_extensions(&path, &denied_extensions));
+                                // A symlink inside an allowed directory may lead to a denied one,
+                                // so the resolved target is checked as well.
+                                let resolved = resolve_permission_path(os, &path);
+                                if let Ok(resolved) = &resolved {
+                                    let resolved_rules = deny_set
+                                        .matches(resolved)
+                                        .iter()
+                                        .filter_map(|i| sanitized_deny_list.get(*i).map(|s| (*s).clone()))
+                                        .chain(matching_denied_extensions(
+                                            &resolved.to_string_lossy(),
+                                            &denied_extensions,
+                                        ))
+                                        .collect::<Vec<_>>();
+                                    for rule in resolved_rules {
+                                        if !denied_rules.contains(&rule) {
+                                            denied_rules.push(rule);
+                                        }
+                                    }
+                                }
+                                // Deleting a directory also deletes the denied paths inside of it.
+                                if let Self::Delete { recursive: true, .. } = self {
+                                    let inside = denied_paths
+                                        .iter()
+                                        .filter(|denied| {
+                                            !denied_rules.contains(denied)
+                                                && directories::canonicalizes_path_relative_to(
+                                                    os,
+                                                    denied,
+                                                    agent.config_dir(),
+                                                )
+                                                .is_ok_and(|denied| Path::new(&denied).starts_with(path.as_str()))
+                                        })
+                                        .cloned()
+                                        .collect::<Vec<_>>();
+                                    denied_rules.extend(inside);
+                                }
+                                // Every entry inside of the directory is checked as well, since a
+                                // glob or an extension may match files at any depth.
+                                let mut unreadable = None;
+                                if let Self::Delete { recursive: true, .. } = self {
+                                    match dir_entries(os, Path::new(path.as_str())) {
+                                        Ok(entries) => {
+                                            for entry in entries {
+                                                let entry_rules = deny_set
+                                                    .matches(&entry)
+                                                    .iter()
+                                                    .filter_map(|i| sanitized_deny_list.get(*i).map(|s| (*s).clone()))
+                                                    .chain(matching_denied_extensions(
+                                                        &entry.to_string_lossy(),
+                                                        &denied_extensions,
+                                                    ))
+                                                    .collect::<Vec<_>>();
+                                                for rule in entry_rules {
+                                                    if !denied_rules.contains(&rule) {
+                                                        denied_rules.push(rule);
+                                                    }
+                                                }
+                                            }
+                                        },
+                                        Err(e) => unreadable = Some(e),
+                                    }
+                                }
+                                if !denied_rules.is_empty() {
+                                    return PermissionEvalResult::Deny(denied_rules);
+                                }
+                                if let Some(e) = unreadable {
+                                    warn!("fs_write could not read the contents of {path}: {e}. Falling back to ask");
+                                    return PermissionEvalResult::Ask;
+                                }
+                                if let Err(reason) = resolved {
+                                    warn!("fs_write could not resolve {path}: {reason}. Falling back to ask");
+                                    return PermissionEvalResult::Ask;
                                 }
-                                if is_in_allowlist || allow_set.is_match(path.as_ref() as &str) {
+                                // Explicitly allowed paths may be outside the workspace, trusting the
+                                // tool as a whole only covers writes inside of it.
+                                if allow_set.is_match(path.as_ref() as &str) {
+                                    return PermissionEvalResult::Allow;
+                                }
+                                if is_in_allowlist
+                                    && !(restrict_to_workspace.unwrap_or(true) && self.is_outside_workspace(os))
+                                {
                                     return PermissionEvalResult::Allow;
                                 }
                             },
+                            Self::Transaction { .. } => {},
                         }
                         PermissionEvalResult::Ask
                     },
@@ -554,10 +879,88 @@ impl FsWrite {
                     },
                 }
             },
-            None if is_in_allowlist => PermissionEvalResult::Allow,
+            None if is_in_allowlist && !self.is_outside_workspace(os) => PermissionEvalResult::Allow,
             _ => PermissionEvalResult::Ask,
         }
     }
+
+    /// Whether the target path, once symlinks are resolved, is outside of the current working
+    /// directory and the worktrees of the git repository it belongs to.
+    pub fn is_outside_workspace(&self, os: &Os) -> bool {
+        if let FsWrite::Transaction { operations, .. } = self {
+            return operations.iter().any(|op| op.is_outside_workspace(os));
+        }
+        match os.env.current_dir() {
+            Ok(cwd) => is_outside_workspace(os, &cwd, &self.path(os)),
+            Err(_) => true,
+        }
+    }
+}
+
+fn is_outside_workspace(os: &Os, cwd: &Path, path: &Path) -> bool {
+    let path = resolve_path(os, cwd, path);
+    !workspace_roots(os, cwd).iter().any(|root| path.starts_with(root))
+}
+
+/// Makes `path` absolute relative to `cwd`, resolving `..` components and symlinks along the way.
+/// Components that do not exist yet, such as the file about to be created, are kept as is.
+fn resolve_path(os: &Os, cwd: &Path, path: &Path) -> PathBuf {
+    let mut resolved = PathBuf::new();
+    for component in unchroot(os, &cwd.join(path)).components() {
+        match component {
+            Component::CurDir => {},
+            Component::ParentDir => {
+                resolved.pop();
+            },
+            component => {
+                resolved.push(component);
+                if let Ok(canonical) = os.fs.canonicalize_sync(&resolved) {
+                    resolved = canonical;
+                }
+            },
+        }
+    }
+    resolved
+}
+
+/// Returns `cwd` along with the root of every worktree of the git repository containing it.
+fn workspace_roots(os: &Os, cwd: &Path) -> Vec<PathBuf> {
+    let cwd = resolve_path(os, cwd, Path::new(""));
+    let mut roots = vec![cwd.clone()];
+    let Some(toplevel) = cwd.ancestors().find(|dir| os.fs.exists(dir.join(".git"))) else {
+        return roots;
+    };
+    roots.push(toplevel.to_path_buf());
+
+    // In a linked worktree `.git` is a file pointing to `<common dir>/worktrees/<name>`.
+    let dot_git = toplevel.join(".git");
+    let common_dir = match os.fs.read_to_string_sync(&dot_git) {
+        Ok(contents) => contents
+            .trim()
+            .strip_prefix("gitdir:")
+            .map(|gitdir| toplevel.join(gitdir.trim()))
+            .and_then(|gitdir| Some(gitdir.parent()?.parent()?.to_path_buf())),
+        Err(_) => Some(dot_git),
+    };
+    let Some(common_dir) = common_dir else {
+        return roots;
+    };
+
+    if let Some(main_worktree) = common_dir.parent() {
+        roots.push(resolve_path(os, &cwd, main_worktree));
+    }
+    if let Ok(entries) = os.fs.read_dir_sync(common_dir.join("worktrees")) {
+        for entry in entries.flatten() {
+            // Each linked worktree records the path to its `.git` file.
+            let Ok(gitdir) = os.fs.read_to_string_sync(entry.path().join("gitdir")) else {
+                continue;
+            };
+            if let Some(worktree) = Path::new(gitdir.trim()).parent() {
+                roots.push(resolve_path(os, &cwd, worktree));
+            }
+        }
+    }
+    roots
 }
 
 /// Writes `content` to `path`, adding a newline if necessary.
@@ -573,6 +976,170 @@ async fn write_to_file(os: &Os, path: impl AsRef<Path>, mut content: String) ->
     Ok(())
 }
 
+/// Applies the operations of a transaction in order. If one of them fails, the files touched by
+/// the transaction are restored from a snapshot taken before the first operation was applied.
+async fn invoke_transaction(
+    os: &Os,
+    output: &mut impl Write,
+    line_tracker: &mut HashMap<String, FileLineTracker>,
+    operations: &[FsWrite],
+) -> Result<InvokeOutput> {
+    let describe =
+        |i: usize, operation: &FsWrite| format!("{}. {} {}", i + 1, operation.command_name(), operation.raw_path());
+
+    let snapshot = TransactionSnapshot::take(os, operations).await?;
+    let line_tracker_before = line_tracker.clone();
+    for (i, operation) in operations.iter().enumerate() {
+        // Boxed since invoking an operation is a recursive call
+        let Err(err) = Box::pin(operation.invoke(os, output, line_tracker)).await else {
+            continue;
+        };
+
+        *line_tracker = line_tracker_before;
+        let restore_errors = snapshot.restore(os).await;
+        let mut message = if restore_errors.is_empty() {
+            "The transaction failed and all of its changes were rolled back.".to_string()
+        } else {
+            "The transaction failed and some of its changes could not be rolled back.".to_string()
+        };
+        if i > 0 {
+            message.push_str("\nRolled back:");
+            for (j, operation) in operations[..i].iter().enumerate() {
+                message.push_str(&format!("\n{}", describe(j, operation)));
+            }
+        }
+        message.push_str(&format!("\nFailed:\n{}: {err}", describe(i, operation)));
+        if i + 1 < operations.len() {
+            message.push_str("\nNot applied:");
+            for (j, operation) in operations.iter().enumerate().skip(i + 1) {
+                message.push_str(&format!("\n{}", describe(j, operation)));
+            }
+        }
+        if !restore_errors.is_empty() {
+            let cwd = os.env.current_dir()?;
+            for (path, err) in restore_errors {
+                message.push_str(&format!("\nFailed to restore {}: {err}", format_path(&cwd, path)));
+            }
+        }
+        bail!(message)
+    }
+
+    let mut message = format!("Applied {} operations:", operations.len());
+    for (i, operation) in operations.iter().enumerate() {
+        message.push_str(&format!("\n{}", describe(i, operation)));
+    }
+    Ok(InvokeOutput {
+        output: OutputKind::Text(message),
+    })
+}
+
+/// The files touched by a transaction as they were before it was applied.
+#[derive(Debug, Default)]
+struct TransactionSnapshot {
+    /// The content of each file, `None` for files that did not exist yet
+    files: Vec<(PathBuf, Option<Vec<u8>>)>,
+    /// Directories that did not exist yet but will be created for new files, deepest first
+    missing_dirs: Vec<PathBuf>,
+}
+
+impl TransactionSnapshot {
+    async fn take(os: &Os, operations: &[FsWrite]) -> Result<Self> {
+        let mut snapshot = Self::default();
+        for operation in operations {
+            let path = operation.path(os);
+            if snapshot.files.iter().any(|(snapshotted, _)| *snapshotted == path) {
+                continue;
+            }
+            let content = match os.fs.symlink_metadata(&path).await {
+                Ok(_) => Some(os.fs.read(&path).await?),
+                Err(_) => None,
+            };
+            if content.is_none() {
+                for dir in path.ancestors().skip(1) {
+                    if os.fs.exists(dir) {
+                        break;
+                    }
+                    if !snapshot.missing_dirs.iter().any(|missing| missing == dir) {
+                        snapshot.missing_dirs.push(dir.to_path_buf());
+                    }
+                }
+            }
+            snapshot.files.push((path, content));
+        }
+        snapshot
+            .missing_dirs
+            .sort_by_key(|dir| std::cmp::Reverse(dir.components().count()));
+        Ok(snapshot)
+    }
+
+    /// Restores every file to its snapshotted content, returning the files that could not be
+    /// restored.
+    async fn restore(&self, os: &Os) -> Vec<(PathBuf, std::io::Error)> {
+        let mut errors = Vec::new();
+        for (path, content) in &self.files {
+            let res = match content {
+                Some(content) => os.fs.write(path, content).await,
+                None if os.fs.symlink_exists(path).await => os.fs.remove_file(path).await,
+                None => Ok(()),
+            };
+            if let Err(err) = res {
+                errors.push((path.clone(), err));
+            }
+        }
+        for dir in &self.missing_dirs {
+            // Directories that are not empty were not only created by the transaction
+            let _ = os.fs.remove_dir(dir).await;
+        }
+        errors
+    }
+}
+
+/// Returns the paths of everything inside the directory at `path`, at any depth, without following
+/// symlinks. A `path` that does not exist or is not a directory has no entries.
+fn dir_entries(os: &Os, path: &Path) -> std::io::Result<Vec<PathBuf>> {
+    let mut entries = Vec::new();
+    let mut dirs = vec![path.to_path_buf()];
+    while let Some(dir) = dirs.pop() {
+        let read_dir = match os.fs.read_dir_sync(&dir) {
+            Ok(read_dir) => read_dir,
+            Err(e) if dir != path => return Err(e),
+            Err(e) => match e.kind() {
+                std::io::ErrorKind::NotFound | std::io::ErrorKind::NotADirectory => return Ok(entries),
+                _ => return Err(e),
+            },
+        };
+        for entry in read_dir {
+            let entry = entry?;
+            // Entries are named after `dir` rather than the chroot path `read_dir` returns
+            let entry_path = dir.join(entry.file_name());
+            if entry.file_type()?.is_dir() {
+                dirs.push(entry_path.clone());
+            }
+            entries.push(entry_path);
+        }
+    }
+    Ok(entries)
+}
+
+/// Returns the entries of `denied_extensions` that `path` ends with, compared case-insensitively.
+/// Entries may be written with or without the leading dot, and a bare dotfile such as `.env` is
+/// treated as having that extension.
+fn matching_denied_extensions(path: &str, denied_extensions: &[String]) -> Vec<String> {
+    let file_name = Path::new(path)
+        .file_name()
+        .map(|name| name.to_string_lossy().to_lowercase())
+        .unwrap_or_default();
+
+    denied_extensions
+        .iter()
+        .filter(|ext| {
+            let ext = ext.trim_start_matches('.').to_lowercase();
+            !ext.is_empty() && file_name.ends_with(&format!(".{ext}"))
+        })
+        .cloned()
+        .collect()
+}
+
 /// Returns a prefix/suffix pair before and after the content dictated by `[start_line, end_line]`
 /// within `content`. The updated start and end lines containing the original context along with
 /// the suffix and prefix are returned.
@@ -630,13 +1197,43 @@ fn get_lines_with_context(
     )
 }
 
+/// How much of a change [print_diff] highlights.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum DiffHighlight {
+    /// No color or escape codes at all.
+    Plain,
+    /// Whole lines are colored as added or removed.
+    Lines,
+    /// Like [DiffHighlight::Lines], additionally emphasizing the changed words of lines that were
+    /// edited in place.
+    Words,
+}
+
+impl DiffHighlight {
+    fn from_settings(os: &Os) -> Self {
+        let settings = &os.database.settings;
+        if plain_output(os) {
+            Self::Plain
+        } else if settings.get_bool(Setting::ChatDisableWordDiff).unwrap_or(false) {
+            Self::Lines
+        } else {
+            Self::Words
+        }
+    }
+}
+
 /// Prints a git-diff style comparison between `old_str` and `new_str`.
 /// - `start_line` - 1-indexed line number that `old_str` and `new_str` start at.
+/// - `highlight` - how the changes are highlighted.
+/// - `width` - the terminal width that lines are soft-wrapped at, with a continuation marker in the
+///   gutter. Lines are not wrapped if [None].
 fn print_diff(
     output: &mut impl Write,
     old_str: &StylizedFile,
     new_str: &StylizedFile,
     start_line: usize,
+    highlight: DiffHighlight,
+    width: Option<usize>,
 ) -> Result<()> {
     let diff = similar::TextDiff::from_lines(&old_str.content, &new_str.content);
 
@@ -653,6 +1250,18 @@ fn print_diff(
     let old_line_num_width = terminal_width_required_for_line_count(max_old_i);
     let new_line_num_width = terminal_width_required_for_line_count(max_new_i);
 
+    // The gutter is the change sign and the line numbers, followed by ": ". Lines are only
+    // wrapped if that leaves a reasonable amount of space for their content.
+    let gutter_width = old_line_num_width + new_line_num_width + 4;
+    let content_width = width
+        .map(|width| width.saturating_sub(gutter_width + 2))
+        .filter(|width| *width >= MIN_WRAPPED_DIFF_WIDTH);
+    let continuation_marker = match highlight {
+        DiffHighlight::Plain => ">",
+        _ => "↪",
+    };
+    let continuation_gutter = format!("{continuation_marker:<gutter_width$}");
+
     // Now, print
     fn fmt_index(i: Option<usize>, start_line: usize) -> String {
         match i {
@@ -660,84 +1269,193 @@ fn print_diff(
             _ => " ".to_string(),
         }
     }
-    for change in diff.iter_all_changes() {
-        // Define the colors per line.
-        let (text_color, gutter_bg_color, line_bg_color) = match (change.tag(), new_str.truecolor) {
-            (similar::ChangeTag::Equal, true) => (style::Color::Reset, new_str.gutter_bg, new_str.line_bg),
-            (similar::ChangeTag::Delete, true) => (
-                style::Color::Reset,
-                style::Color::Rgb { r: 79, g: 40, b: 40 },
-                style::Color::Rgb { r: 36, g: 25, b: 28 },
-            ),
-            (similar::ChangeTag::Insert, true) => (
-                style::Color::Reset,
-                style::Color::Rgb { r: 40, g: 67, b: 43 },
-                style::Color::Rgb { r: 24, g: 38, b: 30 },
-            ),
-            (similar::ChangeTag::Equal, false) => (style::Color::Reset, new_str.gutter_bg, new_str.line_bg),
-            (similar::ChangeTag::Delete, false) => (style::Color::Red, new_str.gutter_bg, new_str.line_bg),
-            (similar::ChangeTag::Insert, false) => (style::Color::Green, new_str.gutter_bg, new_str.line_bg),
-        };
-        // Define the change tag character to print, if any.
-        let sign = match change.tag() {
-            similar::ChangeTag::Equal => " ",
-            similar::ChangeTag::Delete => "-",
-            similar::ChangeTag::Insert => "+",
+    for op in diff.ops() {
+        // Only lines edited in place are diffed word by word, everything else is shown per line.
+        let changes: Vec<InlineChange<'_, str>> = match op.as_tag_tuple() {
+            (similar::DiffTag::Replace, old_range, new_range)
+                if highlight == DiffHighlight::Words && old_range.len() == new_range.len() =>
+            {
+                diff.iter_inline_changes(op).collect()
+            },
+            _ => diff.iter_changes(op).map(InlineChange::from).collect(),
         };
 
-        let old_i_str = fmt_index(change.old_index(), start_line);
-        let new_i_str = fmt_index(change.new_index(), start_line);
-
-        // Print the gutter and line numbers.
-        queue!(output, style::SetBackgroundColor(gutter_bg_color))?;
-        queue!(
-            output,
-            style::SetForegroundColor(text_color),
-            style::Print(sign),
-            style::Print(" ")
-        )?;
-        queue!(
-            output,
-            style::Print(format!(
-                "{:>old_line_num_width$}",
-                old_i_str,
-                old_line_num_width = old_line_num_width
-            ))
-        )?;
-        if sign == " " {
-            queue!(output, style::Print(", "))?;
-        } else {
-            queue!(output, style::Print("  "))?;
+        for change in changes {
+            // Define the change tag character to print, if any.
+            let sign = match change.tag() {
+                similar::ChangeTag::Equal => " ",
+                similar::ChangeTag::Delete => "-",
+                similar::ChangeTag::Insert => "+",
+            };
+
+            let old_i_str = fmt_index(change.old_index(), start_line);
+            let new_i_str = fmt_index(change.new_index(), start_line);
+            let separator = if sign == " " { ", " } else { "  " };
+
+            if highlight == DiffHighlight::Plain {
+                let line = change.to_string();
+                let (text, line_end) = split_line_end(&line);
+                let mut segments = wrap_diff_line(text, content_width).into_iter();
+                queue!(
+                    output,
+                    style::Print(format!(
+                        "{sign} {old_i_str:>old_line_num_width$}{separator}{new_i_str:>new_line_num_width$}: {}",
+                        segments.next().unwrap_or_default()
+                    ))
+                )?;
+                for segment in segments {
+                    queue!(output, style::Print(format!("\n{continuation_gutter}: {segment}")))?;
+                }
+                queue!(output, style::Print(line_end))?;
+                continue;
+            }
+
+            // Define the colors per line.
+            let (text_color, gutter_bg_color, line_bg_color) = match (change.tag(), new_str.truecolor) {
+                (similar::ChangeTag::Equal, true) => (style::Color::Reset, new_str.gutter_bg, new_str.line_bg),
+                (similar::ChangeTag::Delete, true) => (
+                    style::Color::Reset,
+                    style::Color::Rgb { r: 79, g: 40, b: 40 },
+                    style::Color::Rgb { r: 36, g: 25, b: 28 },
+                ),
+                (similar::ChangeTag::Insert, true) => (
+                    style::Color::Reset,
+                    style::Color::Rgb { r: 40, g: 67, b: 43 },
+                    style::Color::Rgb { r: 24, g: 38, b: 30 },
+                ),
+                (similar::ChangeTag::Equal, false) => (style::Color::Reset, new_str.gutter_bg, new_str.line_bg),
+                (similar::ChangeTag::Delete, false) => (style::Color::Red, new_str.gutter_bg, new_str.line_bg),
+                (similar::ChangeTag::Insert, false) => (style::Color::Green, new_str.gutter_bg, new_str.line_bg),
+            };
+            // Changed words stand out with a stronger background, or inverse video without truecolor.
+            let emphasis_bg_color = match change.tag() {
+                similar::ChangeTag::Insert => style::Color::Rgb { r: 46, g: 110, b: 56 },
+                _ => style::Color::Rgb { r: 140, g: 52, b: 52 },
+            };
+
+            // Print the gutter and line numbers.
+            queue!(output, style::SetBackgroundColor(gutter_bg_color))?;
+            queue!(
+                output,
+                style::SetForegroundColor(text_color),
+                style::Print(sign),
+                style::Print(" ")
+            )?;
+            queue!(
+                output,
+                style::Print(format!(
+                    "{:>old_line_num_width$}",
+                    old_i_str,
+                    old_line_num_width = old_line_num_width
+                ))
+            )?;
+            queue!(output, style::Print(separator))?;
+            queue!(
+                output,
+                style::Print(format!(
+                    "{:>new_line_num_width$}",
+                    new_i_str,
+                    new_line_num_width = new_line_num_width
+                ))
+            )?;
+            // Print the line.
+            queue!(
+                output,
+                style::SetForegroundColor(style::Color::Reset),
+                style::Print(":"),
+                style::SetForegroundColor(text_color),
+                style::SetBackgroundColor(line_bg_color),
+                style::Print(" "),
+            )?;
+            // Emphasizing every word of a line that was rewritten entirely adds nothing.
+            let emphasize = change
+                .iter_strings_lossy()
+                .any(|(emphasized, value)| !emphasized && !value.trim().is_empty());
+            let mut line = Vec::new();
+            for (emphasized, value) in change.iter_strings_lossy() {
+                if !(emphasize && emphasized) {
+                    queue!(line, style::Print(value))?;
+                    continue;
+                }
+                let (text, line_end) = split_line_end(&value);
+                if new_str.truecolor {
+                    queue!(
+                        line,
+                        style::SetBackgroundColor(emphasis_bg_color),
+                        style::Print(text),
+                        style::SetBackgroundColor(line_bg_color),
+                        style::Print(line_end),
+                    )?;
+                } else {
+                    queue!(
+                        line,
+                        style::SetAttribute(style::Attribute::Reverse),
+                        style::Print(text),
+                        style::SetAttribute(style::Attribute::NoReverse),
+                        style::Print(line_end),
+                    )?;
+                }
+            }
+            let line = String::from_utf8_lossy(&line);
+            let (text, line_end) = split_line_end(&line);
+            let mut segments = wrap_diff_line(text, content_width).into_iter();
+            queue!(output, style::Print(segments.next().unwrap_or_default()))?;
+            for segment in segments {
+                queue!(
+                    output,
+                    style::Print("\n"),
+                    style::SetBackgroundColor(gutter_bg_color),
+                    style::SetForegroundColor(text_color),
+                    style::Print(&continuation_gutter),
+                    style::SetForegroundColor(style::Color::Reset),
+                    style::Print(":"),
+                    style::SetForegroundColor(text_color),
+                    style::SetBackgroundColor(line_bg_color),
+                    style::Print(" "),
+                    style::Print(segment),
+                )?;
+            }
+            queue!(output, style::Print(line_end))?;
+            if change.missing_newline() {
+                queue!(output, style::Print("\n"))?;
+            }
+            queue!(output, style::ResetColor)?;
         }
+    }
+    if highlight == DiffHighlight::Plain {
+        queue!(output, style::Print("\n"))?;
+    } else {
         queue!(
             output,
-            style::Print(format!(
-                "{:>new_line_num_width$}",
-                new_i_str,
-                new_line_num_width = new_line_num_width
-            ))
-        )?;
-        // Print the line.
-        queue!(
-            output,
-            style::SetForegroundColor(style::Color::Reset),
-            style::Print(":"),
-            style::SetForegroundColor(text_color),
-            style::SetBackgroundColor(line_bg_color),
-            style::Print(" "),
-            style::Print(change),
-            style::ResetColor,
+            crossterm::terminal::Clear(crossterm::terminal::ClearType::UntilNewLine),
+            style::Print("\n"),
         )?;
     }
-    queue!(
-        output,
-        crossterm::terminal::Clear(crossterm::terminal::ClearType::UntilNewLine),
-        style::Print("\n"),
-    )?;
 
     Ok(())
 }
 
+/// Lines of a diff are not wrapped if that would leave less than this many columns for them.
+const MIN_WRAPPED_DIFF_WIDTH: usize = 20;
+
+/// Splits `text` into the segments it is printed as, which is a single one if `width` is [None].
+fn wrap_diff_line(text: &str, width: Option<usize>) -> Vec<String> {
+    match width {
+        Some(width) => wrap_line(text, width),
+        None => vec![text.to_string()],
+    }
+}
+
+const CLEAR_UNTIL_NEWLINE: &str = "\x1b[K";
+
+/// Splits the trailing newline off `value`, along with the escape code clearing the rest of the
+/// line that syntax highlighted lines end with, so emphasis does not extend to the end of the line.
+fn split_line_end(value: &str) -> (&str, &str) {
+    let text = value.trim_end_matches(['\r', '\n']);
+    let text = text.strip_suffix(CLEAR_UNTIL_NEWLINE).unwrap_or(text);
+    value.split_at(text.len())
+}
+
 /// Returns a 1-indexed line number range of the start and end of `needle` inside `file`.
 fn line_number_at(file: impl AsRef<str>, needle: impl AsRef<str>) -> Option<(usize, usize)> {
     let file = file.as_ref();
@@ -864,6 +1582,7 @@ mod tests {
     use crate::cli::chat::util::test::{
         TEST_FILE_CONTENTS,
         TEST_FILE_PATH,
+        TEST_HIDDEN_FILE_PATH,
         setup_test_directory,
     };
 
@@ -909,6 +1628,42 @@ mod tests {
         });
         let fw = serde_json::from_value::<FsWrite>(v).unwrap();
         assert!(matches!(fw, FsWrite::Append { .. }));
+
+        // delete
+        let v = serde_json::json!({
+            "path": path,
+            "command": "delete",
+        });
+        let fw = serde_json::from_value::<FsWrite>(v).unwrap();
+        assert!(matches!(fw, FsWrite::Delete { recursive: false, .. }));
+    }
+
+    #[test]
+    fn test_fs_write_with_content() {
+        let create = serde_json::from_value::<FsWrite>(serde_json::json!({
+            "path": "/my-file",
+            "command": "create",
+            "file_text": "hello world"
+        }))
+        .unwrap();
+        assert_eq!(create.proposed_content(), "hello world");
+        let edited = create.with_content("hello there".to_string());
+        assert_eq!(edited.proposed_content(), "hello there");
+        assert_eq!(edited.canonical_create_command_text(), "hello there");
+
+        let str_replace = serde_json::from_value::<FsWrite>(serde_json::json!({
+            "path": "/my-file",
+            "command": "str_replace",
+            "old_str": "foo",
+            "new_str": "bar"
+        }))
+        .unwrap();
+        assert_eq!(str_replace.proposed_content(), "bar");
+        let edited = str_replace.with_content("baz".to_string());
+        assert!(matches!(
+            edited,
+            FsWrite::StrReplace { ref old_str, ref new_str, .. } if old_str == "foo" && new_str == "baz"
+        ));
     }
 
     #[test]
@@ -1217,6 +1972,119 @@ mod tests {
         assert!(result.is_err(), "Appending to non-existent file should fail");
     }
 
+    #[tokio::test]
+    async fn test_fs_write_tool_delete() {
+        let os = setup_test_directory().await;
+        let mut stdout = std::io::stdout();
+        let mut line_tracker = HashMap::new();
+        let tool_for = |path: &str, recursive: bool| {
+            serde_json::from_value::<FsWrite>(serde_json::json!({
+                "path": path,
+                "command": "delete",
+                "recursive": recursive,
+            }))
+            .unwrap()
+        };
+
+        // Delete a file
+        let mut tool = tool_for(TEST_FILE_PATH, false);
+        tool.validate(&os).await.unwrap();
+        tool.invoke(&os, &mut stdout, &mut line_tracker).await.unwrap();
+        assert!(!os.fs.exists(TEST_FILE_PATH));
+        assert!(
+            tool_for(TEST_FILE_PATH, false).validate(&os).await.is_err(),
+            "Deleting a path that does not exist should fail"
+        );
+
+        // Empty directories can be deleted without `recursive`, others cannot
+        assert!(tool_for("/aaaa1", false).validate(&os).await.is_err());
+        let mut tool = tool_for("/aaaa1/bbbb1/cccc1", false);
+        tool.validate(&os).await.unwrap();
+        tool.invoke(&os, &mut stdout, &mut line_tracker).await.unwrap();
+        assert!(!os.fs.exists("/aaaa1/bbbb1/cccc1"));
+        assert!(os.fs.exists("/aaaa1/bbbb1"));
+
+        let mut tool = tool_for("/aaaa1", true);
+        tool.validate(&os).await.unwrap();
+        tool.invoke(&os, &mut stdout, &mut line_tracker).await.unwrap();
+        assert!(!os.fs.exists("/aaaa1"));
+        assert!(os.fs.exists(TEST_HIDDEN_FILE_PATH));
+
+        // The current working directory is never deleted
+        assert!(tool_for("/", true).validate(&os).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_fs_write_tool_transaction() {
+        let os = setup_test_directory().await;
+        let mut stdout = std::io::stdout();
+        let mut line_tracker = HashMap::new();
+        let transaction = |operations: serde_json::Value| {
+            serde_json::from_value::<FsWrite>(serde_json::json!({
+                "command": "transaction",
+                "operations": operations,
+            }))
+            .unwrap()
+        };
+
+        // All operations are applied
+        let mut tool = transaction(serde_json::json!([
+            { "command": "create", "path": "/new/dir/file.txt", "file_text": "new file" },
+            {
+                "command": "str_replace",
+                "path": TEST_FILE_PATH,
+                "old_str": "1: Hello world!",
+                "new_str": "1: Goodbye world!",
+            },
+        ]));
+        tool.validate(&os).await.unwrap();
+        let output = tool.invoke(&os, &mut stdout, &mut line_tracker).await.unwrap();
+        assert_eq!(
+            output.as_str(),
+            "Applied 2 operations:\n1. create /new/dir/file.txt\n2. str_replace /test_file.txt"
+        );
+        assert_eq!(os.fs.read_to_string("/new/dir/file.txt").await.unwrap(), "new file\n");
+        assert!(
+            os.fs
+                .read_to_string(TEST_FILE_PATH)
+                .await
+                .unwrap()
+                .starts_with("1: Goodbye world!")
+        );
+
+        // A failing operation rolls back the ones applied before it
+        let before = os.fs.read_to_string(TEST_FILE_PATH).await.unwrap();
+        let mut tool = transaction(serde_json::json!([
+            { "command": "append", "path": TEST_FILE_PATH, "new_str": "appended" },
+            { "command": "create", "path": "/other/file.txt", "file_text": "other" },
+            { "command": "delete", "path": TEST_HIDDEN_FILE_PATH },
+            { "command": "str_replace", "path": TEST_FILE_PATH, "old_str": "not in the file", "new_str": "x" },
+            { "command": "append", "path": "/new/dir/file.txt", "new_str": "never applied" },
+        ]));
+        tool.validate(&os).await.unwrap();
+        let err = tool.invoke(&os, &mut stdout, &mut line_tracker).await.unwrap_err();
+        assert_eq!(os.fs.read_to_string(TEST_FILE_PATH).await.unwrap(), before);
+        assert!(!os.fs.exists("/other"));
+        assert!(os.fs.exists(TEST_HIDDEN_FILE_PATH));
+        assert_eq!(os.fs.read_to_string("/new/dir/file.txt").await.unwrap(), "new file\n");
+        let err = err.to_string();
+        assert!(err.starts_with("The transaction failed and all of its changes were rolled back."));
+        assert!(
+            err.contains("Rolled back:\n1. append /test_file.txt\n2. create /other/file.txt\n3. delete /aaaa2/.hidden")
+        );
+        assert!(err.contains("Failed:\n4. str_replace /test_file.txt: no occurrences"));
+        assert!(err.contains("Not applied:\n5. append /new/dir/file.txt"));
+
+        // Transactions cannot be empty, nested, or delete directories
+        assert!(transaction(serde_json::json!([])).validate(&os).await.is_err());
+        let mut tool = transaction(serde_json::json!([
+            { "command": "transaction", "operations": [{ "command": "delete", "path": TEST_FILE_PATH }] },
+        ]));
+        assert!(tool.validate(&os).await.is_err());
+        let mut tool = transaction(serde_json::json!([{ "command": "delete", "path": "/aaaa2", "recursive": true }]));
+        assert!(tool.validate(&os).await.is_err());
+    }
+
     #[test]
     fn test_lines_with_context() {
         let content = "Hello\nWorld!\nhow\nare\nyou\ntoday?";
@@ -1239,6 +2107,115 @@ mod tests {
         assert_eq!(terminal_width_required_for_line_count(999), 3);
     }
 
+    #[test]
+    fn test_print_diff_plain() {
+        let file = |content: &str| StylizedFile {
+            content: content.to_string(),
+            ..Default::default()
+        };
+        let mut output = Vec::new();
+        print_diff(
+            &mut output,
+            &file("a\nb\n"),
+            &file("a\nc\n"),
+            9,
+            DiffHighlight::Plain,
+            None,
+        )
+        .unwrap();
+        let output = String::from_utf8(output).unwrap();
+        assert!(!output.contains('\x1b'), "plain diff contains escape codes: {output:?}");
+        assert_eq!(output, "   9,  9: a\n- 10    : b\n+     10: c\n\n");
+    }
+
+    #[test]
+    fn test_print_diff_wrap() {
+        let file = |content: &str| StylizedFile {
+            content: content.to_string(),
+            ..Default::default()
+        };
+        let diff = |highlight: DiffHighlight, width: Option<usize>| {
+            let mut output = Vec::new();
+            let new = format!("{}\n", "a".repeat(50));
+            print_diff(&mut output, &Default::default(), &file(&new), 1, highlight, width).unwrap();
+            String::from_utf8(output).unwrap()
+        };
+
+        // Long lines continue below with a marker in the gutter, keeping it aligned.
+        assert_eq!(
+            diff(DiffHighlight::Plain, Some(40)),
+            format!("+    1: {}\n>     : {}\n\n", "a".repeat(32), "a".repeat(18))
+        );
+        assert_eq!(
+            diff(DiffHighlight::Plain, None),
+            format!("+    1: {}\n\n", "a".repeat(50))
+        );
+        // Lines are not wrapped when the terminal is too narrow for the gutter.
+        assert_eq!(diff(DiffHighlight::Plain, Some(20)), diff(DiffHighlight::Plain, None));
+
+        let output = diff(DiffHighlight::Lines, Some(40));
+        let lines = output.lines().collect::<Vec<_>>();
+        assert_eq!(lines.len(), 3, "{output:?}");
+        assert!(strip_ansi_escapes::strip_str(lines[0]).ends_with(&"a".repeat(32)));
+        assert!(strip_ansi_escapes::strip_str(lines[1]).starts_with("↪     : "));
+    }
+
+    #[test]
+    fn test_print_diff_word_highlight() {
+        let file = |content: &str, truecolor: bool| StylizedFile {
+            truecolor,
+            content: content.to_string(),
+            ..Default::default()
+        };
+        let diff = |old: &str, new: &str, truecolor: bool, highlight: DiffHighlight| {
+            let mut output = Vec::new();
+            print_diff(
+                &mut output,
+                &file(old, truecolor),
+                &file(new, truecolor),
+                1,
+                highlight,
+                None,
+            )
+            .unwrap();
+            String::from_utf8(output).unwrap()
+        };
+
+        // Without truecolor, the changed words are shown in inverse video.
+        assert_eq!(
+            diff("let x = 1;\n", "let x = 2;\n", false, DiffHighlight::Words),
+            "\x1b[49m\x1b[38;5;9m- 1   \x1b[39m:\x1b[38;5;9m\x1b[49m let x = \x1b[7m1;\x1b[27m\n\x1b[0m\
+             \x1b[49m\x1b[38;5;10m+    1\x1b[39m:\x1b[38;5;10m\x1b[49m let x = \x1b[7m2;\x1b[27m\n\x1b[0m\
+             \x1b[K\n"
+        );
+
+        // With truecolor, the changed words get a stronger background.
+        assert_eq!(
+            diff("a b c\n", "a x c\n", true, DiffHighlight::Words),
+            "\x1b[48;2;79;40;40m\x1b[39m- 1   \x1b[39m:\x1b[39m\x1b[48;2;36;25;28m a \
+             \x1b[48;2;140;52;52mb\x1b[48;2;36;25;28m c\n\x1b[0m\
+             \x1b[48;2;40;67;43m\x1b[39m+    1\x1b[39m:\x1b[39m\x1b[48;2;24;38;30m a \
+             \x1b[48;2;46;110;56mx\x1b[48;2;24;38;30m c\n\x1b[0m\
+             \x1b[K\n"
+        );
+
+        // Line level highlighting is kept when asked for, for pure additions, for lines that were
+        // rewritten entirely, and when the number of lines changes.
+        for (old, new, highlight) in [
+            ("let x = 1;\n", "let x = 2;\n", DiffHighlight::Lines),
+            ("a\n", "a\nb\n", DiffHighlight::Words),
+            ("foo\n", "bar\n", DiffHighlight::Words),
+            ("let x = 1;\n", "let x = 2;\nlet y = 3;\n", DiffHighlight::Words),
+        ] {
+            let output = diff(old, new, false, highlight);
+            assert!(!output.contains("\x1b[7m"), "{old:?} -> {new:?}: {output:?}");
+        }
+
+        assert_eq!(split_line_end("foo\x1b[K\n"), ("foo", "\x1b[K\n"));
+        assert_eq!(split_line_end("foo\n"), ("foo", "\n"));
+        assert_eq!(split_line_end("foo"), ("foo", ""));
+    }
+
     #[tokio::test]
     async fn test_fs_write_with_tilde_paths() {
         // Create a test context
@@ -1459,6 +2436,351 @@ mod tests {
         assert!(matches!(res, PermissionEvalResult::Allow));
     }
 
+    #[tokio::test]
+    async fn test_eval_perm_denied_extensions() {
+        const ALLOW_PATH: &str = "/some/allow/path";
+        const DENIED_PATH: &str = "/some/denied/path";
+
+        let mut agent = Agent {
+            name: "test_agent".to_string(),
+            tools_settings: {
+                let mut map = HashMap::<ToolSettingTarget, serde_json::Value>::new();
+                map.insert(
+                    ToolSettingTarget("fs_write".to_string()),
+                    serde_json::json!({
+                        "allowedPaths": [ALLOW_PATH],
+                        "deniedPaths": [DENIED_PATH],
+                        "deniedExtensions": [".env", "pem", "KEY"]
+                    }),
+                );
+                map
+            },
+            ..Default::default()
+        };
+
+        let os = Os::new().await.unwrap();
+        let tool_for = |path: &str| {
+            serde_json::from_value::<FsWrite>(serde_json::json!({
+                "path": path,
+                "command": "create",
+                "file_text": "content"
+            }))
+            .unwrap()
+        };
+
+        // Dotfiles and mixed case extensions are denied, even in an allowed path
+        let res = tool_for("/some/allow/path/.env").eval_perm(&os, &agent);
+        assert!(matches!(res, PermissionEvalResult::Deny(ref deny_list) if deny_list == &vec![".env".to_string()]));
+
+        let res = tool_for("/some/allow/path/server.PEM").eval_perm(&os, &agent);
+        assert!(matches!(res, PermissionEvalResult::Deny(ref deny_list) if deny_list == &vec!["pem".to_string()]));
+
+        // Extension denies take precedence over the allowed tools list
+        agent.allowed_tools.insert("fs_write".to_string());
+        let res = tool_for("/anywhere/id.key").eval_perm(&os, &agent);
+        assert!(matches!(res, PermissionEvalResult::Deny(ref deny_list) if deny_list == &vec!["KEY".to_string()]));
+
+        // Path and extension denies are reported together
+        let res = tool_for("/some/denied/path/secrets.env").eval_perm(&os, &agent);
+        assert!(matches!(res, PermissionEvalResult::Deny(ref deny_list)
+            if deny_list.contains(&DENIED_PATH.to_string()) && deny_list.contains(&".env".to_string())));
+
+        // Similar looking names are not denied
+        let res = tool_for("/some/allow/path/environment.rs").eval_perm(&os, &agent);
+        assert!(matches!(res, PermissionEvalResult::Allow));
+        let res = tool_for("/some/allow/path/keys.txt").eval_perm(&os, &agent);
+        assert!(matches!(res, PermissionEvalResult::Allow));
+    }
+
+    #[tokio::test]
+    async fn test_eval_perm_delete() {
+        const ALLOW_PATH: &str = "/some/allow/path";
+        const DENIED_PATH: &str = "/some/denied/path";
+
+        let agent = Agent {
+            name: "test_agent".to_string(),
+            tools_settings: {
+                let mut map = HashMap::<ToolSettingTarget, serde_json::Value>::new();
+                map.insert(
+                    ToolSettingTarget("fs_write".to_string()),
+                    serde_json::json!({
+                        "allowedPaths": [ALLOW_PATH],
+                        "deniedPaths": [DENIED_PATH],
+                        "deniedExtensions": [".env"]
+                    }),
+                );
+                map
+            },
+            ..Default::default()
+        };
+
+        let os = Os::new().await.unwrap();
+        let tool_for = |path: &str, recursive: bool| {
+            serde_json::from_value::<FsWrite>(serde_json::json!({
+                "path": path,
+                "command": "delete",
+                "recursive": recursive,
+            }))
+            .unwrap()
+        };
+
+        // Deletes follow the same path rules as writes
+        let res = tool_for("/some/denied/path/file.txt", false).eval_perm(&os, &agent);
+        assert!(
+            matches!(res, PermissionEvalResult::Deny(ref deny_list) if deny_list.contains(&DENIED_PATH.to_string()))
+        );
+        let res = tool_for("/some/allow/path/file.txt", false).eval_perm(&os, &agent);
+        assert!(matches!(res, PermissionEvalResult::Allow));
+        let res = tool_for("/elsewhere/file.txt", false).eval_perm(&os, &agent);
+        assert!(matches!(res, PermissionEvalResult::Ask));
+
+        // Recursively deleting a directory containing a denied path is denied
+        let res = tool_for("/some", true).eval_perm(&os, &agent);
+        assert!(
+            matches!(res, PermissionEvalResult::Deny(ref deny_list) if deny_list == &vec![DENIED_PATH.to_string()])
+        );
+        let res = tool_for("/some", false).eval_perm(&os, &agent);
+        assert!(matches!(res, PermissionEvalResult::Ask));
+        let res = tool_for("/some/allow", true).eval_perm(&os, &agent);
+        assert!(matches!(res, PermissionEvalResult::Ask));
+
+        // So is recursively deleting a directory containing a file with a denied extension
+        os.fs.create_dir_all("/project/app").await.unwrap();
+        os.fs.write("/project/app/.env", "SECRET=1").await.unwrap();
+        let res = tool_for("/project", true).eval_perm(&os, &agent);
+        assert!(matches!(res, PermissionEvalResult::Deny(ref deny_list) if deny_list == &vec![".env".to_string()]));
+        let res = tool_for("/project", false).eval_perm(&os, &agent);
+        assert!(matches!(res, PermissionEvalResult::Ask));
+    }
+
+    #[tokio::test]
+    async fn test_eval_perm_transaction() {
+        const ALLOW_PATH: &str = "/some/allow/path";
+        const DENIED_PATH: &str = "/some/denied/path";
+
+        let agent = Agent {
+            name: "test_agent".to_string(),
+            tools_settings: {
+                let mut map = HashMap::<ToolSettingTarget, serde_json::Value>::new();
+                map.insert(
+                    ToolSettingTarget("fs_write".to_string()),
+                    serde_json::json!({
+                        "allowedPaths": [ALLOW_PATH],
+                        "deniedPaths": [DENIED_PATH]
+                    }),
+                );
+                map
+            },
+            ..Default::default()
+        };
+
+        let os = Os::new().await.unwrap();
+        let transaction = |paths: &[&str]| {
+            let operations = paths
+                .iter()
+                .map(|path| serde_json::json!({ "command": "append", "path": path, "new_str": "x" }))
+                .collect::<Vec<_>>();
+            serde_json::from_value::<FsWrite>(serde_json::json!({
+                "command": "transaction",
+                "operations": operations,
+            }))
+            .unwrap()
+        };
+
+        // The least permitted operation decides for the whole transaction
+        let res = transaction(&["/some/allow/path/a.txt", "/some/allow/path/b.txt"]).eval_perm(&os, &agent);
+        assert!(matches!(res, PermissionEvalResult::Allow));
+        let res = transaction(&["/some/allow/path/a.txt", "/elsewhere/b.txt"]).eval_perm(&os, &agent);
+        assert!(matches!(res, PermissionEvalResult::Ask));
+        let res = transaction(&["/elsewhere/b.txt", "/some/denied/path/a.txt", "/some/denied/path/b.txt"])
+            .eval_perm(&os, &agent);
+        assert!(
+            matches!(res, PermissionEvalResult::Deny(ref deny_list) if deny_list == &vec![DENIED_PATH.to_string()])
+        );
+    }
+
+    #[tokio::test]
+    async fn test_eval_perm_restrict_to_workspace() {
+        let mut agent = Agent {
+            name: "test_agent".to_string(),
+            ..Default::default()
+        };
+        agent.allowed_tools.insert("fs_write".to_string());
+
+        let os = Os::new().await.unwrap();
+        let tool_for = |path: &str| {
+            serde_json::from_value::<FsWrite>(serde_json::json!({
+                "path": path,
+                "command": "create",
+                "file_text": "content"
+            }))
+            .unwrap()
+        };
+
+        // The test cwd is the root of the chroot, so only `..` can escape it
+        assert_eq!(
+            tool_for("/inside.txt").eval_perm(&os, &agent),
+            PermissionEvalResult::Allow
+        );
+        assert_eq!(
+            tool_for("../escape.txt").eval_perm(&os, &agent),
+            PermissionEvalResult::Ask
+        );
+
+        // Explicitly allowed paths are still allowed
+        let escape_path = os.fs.chroot_path_str("../escape.txt");
+        agent.tools_settings.insert(
+            ToolSettingTarget("fs_write".to_string()),
+            serde_json::json!({ "allowedPaths": [escape_path] }),
+        );
+        assert_eq!(
+            tool_for(&escape_path).eval_perm(&os, &agent),
+            PermissionEvalResult::Allow
+        );
+
+        agent.tools_settings.insert(
+            ToolSettingTarget("fs_write".to_string()),
+            serde_json::json!({ "restrictToWorkspace": false }),
+        );
+        assert_eq!(
+            tool_for("../escape.txt").eval_perm(&os, &agent),
+            PermissionEvalResult::Allow
+        );
+    }
+
+    #[tokio::test]
+    async fn test_eval_perm_symlinks() {
+        const ALLOW_PATH: &str = "/allowed";
+        const DENIED_PATH: &str = "/denied";
+
+        let agent = Agent {
+            name: "test_agent".to_string(),
+            tools_settings: {
+                let mut map = HashMap::<ToolSettingTarget, serde_json::Value>::new();
+                map.insert(
+                    ToolSettingTarget("fs_write".to_string()),
+                    serde_json::json!({
+                        "allowedPaths": [ALLOW_PATH],
+                        "deniedPaths": [DENIED_PATH]
+                    }),
+                );
+                map
+            },
+            ..Default::default()
+        };
+
+        let os = Os::new().await.unwrap();
+        os.fs.create_dir_all("/allowed").await.unwrap();
+        os.fs.create_dir_all("/denied").await.unwrap();
+        os.fs.write("/denied/secret.txt", "secret").await.unwrap();
+        os.fs.symlink("/denied", "/allowed/linked").await.unwrap();
+        os.fs.symlink("/denied/secret.txt", "/allowed/link.txt").await.unwrap();
+        os.fs.symlink("/missing", "/allowed/broken_link").await.unwrap();
+
+        let create = |path: &str| {
+            serde_json::from_value::<FsWrite>(serde_json::json!({
+                "path": path,
+                "command": "create",
+                "file_text": "content"
+            }))
+            .unwrap()
+        };
+        let append = |path: &str| {
+            serde_json::from_value::<FsWrite>(serde_json::json!({
+                "path": path,
+                "command": "append",
+                "new_str": "content"
+            }))
+            .unwrap()
+        };
+        let is_denied = |res: PermissionEvalResult| res == PermissionEvalResult::Deny(vec![DENIED_PATH.to_string()]);
+
+        // Writing through a symlinked file or directory is denied, including files that do not
+        // exist yet
+        assert!(is_denied(create("/allowed/link.txt").eval_perm(&os, &agent)));
+        assert!(is_denied(append("/allowed/link.txt").eval_perm(&os, &agent)));
+        assert!(is_denied(create("/allowed/linked/new.txt").eval_perm(&os, &agent)));
+        assert!(is_denied(append("/allowed/linked/secret.txt").eval_perm(&os, &agent)));
+        assert!(is_denied(create("/allowed/linked/x/../new.txt").eval_perm(&os, &agent)));
+
+        // Paths that stay inside the allowed directory are unaffected
+        assert!(matches!(
+            create("/allowed/file.txt").eval_perm(&os, &agent),
+            PermissionEvalResult::Allow
+        ));
+
+        // The target of a broken symlink is unknown, so permission is asked for
+        assert!(matches!(
+            create("/allowed/broken_link").eval_perm(&os, &agent),
+            PermissionEvalResult::Ask
+        ));
+        assert!(resolve_permission_path(&os, "/allowed/broken_link").is_err());
+        assert_eq!(
+            resolve_permission_path(&os, "/allowed/linked/new.txt").unwrap(),
+            PathBuf::from("/denied/new.txt")
+        );
+    }
+
+    #[tokio::test]
+    async fn test_is_outside_workspace() {
+        let os = Os::new().await.unwrap();
+        os.fs.create_dir_all("/project/src").await.unwrap();
+        os.fs.create_dir_all("/elsewhere").await.unwrap();
+        let cwd = PathBuf::from("/project");
+
+        // Relative paths and `..` traversal
+        assert!(!is_outside_workspace(&os, &cwd, Path::new("src/main.rs")));
+        assert!(!is_outside_workspace(&os, &cwd, Path::new("./new_dir/file.txt")));
+        assert!(!is_outside_workspace(&os, &cwd, Path::new("src/../README.md")));
+        assert!(is_outside_workspace(&os, &cwd, Path::new("src/../../escape.txt")));
+        assert!(is_outside_workspace(&os, &cwd, Path::new("/elsewhere/file.txt")));
+
+        // Tilde paths expand to the home directory
+        let home = os.env.home().unwrap();
+        os.fs.create_dir_all(&home).await.unwrap();
+        let tilde_path = serde_json::from_value::<FsWrite>(serde_json::json!({
+            "path": "~/notes.txt",
+            "command": "create",
+            "file_text": "content"
+        }))
+        .unwrap()
+        .path(&os);
+        assert!(is_outside_workspace(&os, &cwd, &tilde_path));
+        assert!(!is_outside_workspace(&os, &home, &tilde_path));
+
+        // Symlinks are resolved before checking
+        os.fs.symlink("/elsewhere", "/project/escape_link").await.unwrap();
+        os.fs.symlink("/project/src", "/project/src_link").await.unwrap();
+        assert!(is_outside_workspace(&os, &cwd, Path::new("escape_link/file.txt")));
+        assert!(!is_outside_workspace(&os, &cwd, Path::new("src_link/file.txt")));
+    }
+
+    #[tokio::test]
+    async fn test_workspace_includes_git_worktrees() {
+        let os = Os::new().await.unwrap();
+        os.fs.create_dir_all("/repo/sub").await.unwrap();
+        os.fs.create_dir_all("/repo/.git/worktrees/feature").await.unwrap();
+        os.fs.create_dir_all("/feature").await.unwrap();
+        os.fs
+            .write("/repo/.git/worktrees/feature/gitdir", "/feature/.git")
+            .await
+            .unwrap();
+        os.fs
+            .write("/feature/.git", "gitdir: /repo/.git/worktrees/feature")
+            .await
+            .unwrap();
+
+        // From a subdirectory of the main worktree
+        let cwd = PathBuf::from("/repo/sub");
+        assert!(!is_outside_workspace(&os, &cwd, Path::new("/repo/file.txt")));
+        assert!(!is_outside_workspace(&os, &cwd, Path::new("/feature/file.txt")));
+
+        // From a linked worktree
+        let cwd = PathBuf::from("/feature");
+        assert!(!is_outside_workspace(&os, &cwd, Path::new("/repo/sub/file.txt")));
+        assert!(is_outside_workspace(&os, &cwd, Path::new("/other/file.txt")));
+    }
+
     #[tokio::test]
     async fn test_line_tracker_updates() {
         let os = setup_test_directory().await;
@@ -19,6 +19,10 @@ use crate::cli::agent::{
 };
 use crate::database::settings::Setting;
 use crate::os::Os;
+use crate::util::knowledge_backend::{
+    KnowledgeBackend,
+    KnowledgeBackendImpl,
+};
 use crate::util::knowledge_store::KnowledgeStore;
 use crate::util::pattern_matching::matches_any_pattern;
 
@@ -27,6 +31,9 @@ use crate::util::pattern_matching::matches_any_pattern;
 ///
 /// This feature can be enabled/disabled via settings:
 /// `q settings chat.enableKnowledge true`
+///
+/// The storage backend is selected with `q settings chat.knowledgeBackend`, see
+/// [KnowledgeBackend].
 #[derive(Debug, Clone, Deserialize)]
 #[serde(tag = "command", rename_all = "lowercase")]
 pub enum Knowledge {
@@ -95,6 +102,15 @@ impl Knowledge {
     }
 
     pub async fn validate(&mut self, os: &Os) -> Result<()> {
+        self.validate_args(os)?;
+        KnowledgeBackend::from_settings(os)
+            .implementation()
+            .validate(os, self)
+            .await
+    }
+
+    /// Validation shared by all backends
+    fn validate_args(&self, os: &Os) -> Result<()> {
         match self {
             Knowledge::Add(add) => {
                 // Check if value is intended to be a path (doesn't contain newlines)
@@ -317,7 +333,20 @@ impl Knowledge {
         os: &Os,
         _updates: &mut impl Write,
         agent: Option<&crate::cli::Agent>,
+        session_id: &str,
     ) -> Result<InvokeOutput> {
+        let result = KnowledgeBackend::from_settings(os)
+            .implementation()
+            .invoke(os, self, agent, session_id)
+            .await?;
+
+        Ok(InvokeOutput {
+            output: OutputKind::Text(result),
+        })
+    }
+
+    /// Executes the command against the [KnowledgeStore] of the semantic search backend
+    async fn invoke_semantic_search(&self, os: &Os, agent: Option<&crate::cli::Agent>) -> Result<String> {
         let async_knowledge_store = KnowledgeStore::get_async_instance(os, agent)
             .await
             .map_err(|e| eyre::eyre!("Failed to access knowledge base: {}", e))?;
@@ -376,19 +405,13 @@ impl Knowledge {
             Knowledge::Update(update) => {
                 // Validate that we have a path and at least one identifier
                 if update.path.is_empty() {
-                    return Ok(InvokeOutput {
-                        output: OutputKind::Text(
-                            "Error: No path provided for update. Please specify a path to update with.".to_string(),
-                        ),
-                    });
+                    return Ok("Error: No path provided for update. Please specify a path to update with.".to_string());
                 }
 
                 // Sanitize the path
                 let path = crate::cli::chat::tools::sanitize_path_tool_arg(os, &update.path);
                 if !path.exists() {
-                    return Ok(InvokeOutput {
-                        output: OutputKind::Text(format!("Error: Path '{}' does not exist", update.path)),
-                    });
+                    return Ok(format!("Error: Path '{}' does not exist", update.path));
                 }
 
                 let sanitized_path = path.to_string_lossy().to_string();
@@ -488,9 +511,7 @@ impl Knowledge {
                 .unwrap_or_else(|e| format!("Failed to cancel operation: {}", e)),
         };
 
-        Ok(InvokeOutput {
-            output: OutputKind::Text(result),
-        })
+        Ok(result)
     }
 
     pub fn eval_perm(&self, os: &Os, agent: &Agent) -> PermissionEvalResult {
@@ -575,3 +596,23 @@ impl Knowledge {
         }
     }
 }
+
+/// [KnowledgeBackendImpl] for [KnowledgeBackend::SemanticSearch], backed by [KnowledgeStore]
+pub struct SemanticSearchBackend;
+
+#[async_trait::async_trait]
+impl KnowledgeBackendImpl for SemanticSearchBackend {
+    async fn validate(&self, _os: &Os, _knowledge: &Knowledge) -> Result<()> {
+        Ok(())
+    }
+
+    async fn invoke(
+        &self,
+        os: &Os,
+        knowledge: &Knowledge,
+        agent: Option<&crate::cli::Agent>,
+        _session_id: &str,
+    ) -> Result<String> {
+        knowledge.invoke_semantic_search(os, agent).await
+    }
+}
@@ -1,3 +1,4 @@
+pub mod agent_delegate;
 pub mod custom_tool;
 pub mod execute;
 pub mod fs_read;
@@ -5,6 +6,7 @@ pub mod fs_write;
 pub mod gh_issue;
 pub mod introspect;
 pub mod knowledge;
+pub mod parse_error;
 pub mod thinking;
 pub mod todo;
 pub mod use_aws;
@@ -16,19 +18,27 @@ use std::borrow::{
 use std::collections::HashMap;
 use std::io::Write;
 use std::path::{
+    Component,
     Path,
     PathBuf,
 };
 
+use agent_delegate::AgentDelegate;
 use crossterm::queue;
 use crossterm::style::{
     self,
     Color,
 };
 use custom_tool::CustomTool;
-use execute::ExecuteCommand;
+use execute::{
+    ExecuteCommand,
+    ExecuteSettings,
+};
 use eyre::Result;
-use fs_read::FsRead;
+use fs_read::{
+    FsRead,
+    FsReadSettings,
+};
 use fs_write::FsWrite;
 use gh_issue::GhIssue;
 use introspect::Introspect;
@@ -50,15 +60,22 @@ use super::consts::{
     USER_AGENT_VERSION_VALUE,
 };
 use super::util::images::RichImageBlocks;
+use super::util::ui::glyph;
 use crate::cli::agent::{
     Agent,
     PermissionEvalResult,
 };
 use crate::cli::chat::line_tracker::FileLineTracker;
+use crate::database::settings::Setting;
 use crate::os::Os;
+use crate::util::env_var::QCLI_AGENT_PATH;
+use crate::util::{
+    MCP_SERVER_TOOL_DELIMITER,
+    directories,
+};
 
 pub const DEFAULT_APPROVE: [&str; 1] = ["fs_read"];
-pub const NATIVE_TOOLS: [&str; 8] = [
+pub const NATIVE_TOOLS: [&str; 9] = [
     "fs_read",
     "fs_write",
     #[cfg(windows)]
@@ -70,6 +87,7 @@ pub const NATIVE_TOOLS: [&str; 8] = [
     "knowledge",
     "thinking",
     "todo_list",
+    "agent_delegate",
 ];
 
 /// Represents an executable tool use.
@@ -86,6 +104,7 @@ pub enum Tool {
     Knowledge(Knowledge),
     Thinking(Thinking),
     Todo(TodoList),
+    AgentDelegate(AgentDelegate),
 }
 
 impl Tool {
@@ -105,10 +124,24 @@ impl Tool {
             Tool::Knowledge(_) => "knowledge",
             Tool::Thinking(_) => "thinking (prerelease)",
             Tool::Todo(_) => "todo_list",
+            Tool::AgentDelegate(_) => agent_delegate::AGENT_DELEGATE_TOOL_NAME,
         }
         .to_owned()
     }
 
+    /// The name under which the tool is configured in an agent's `toolsSettings`, which is
+    /// `@server/tool` for MCP tools.
+    pub fn settings_name(&self) -> String {
+        match self {
+            Tool::Custom(custom_tool) => format!(
+                "@{}{MCP_SERVER_TOOL_DELIMITER}{}",
+                custom_tool.client.get_server_name(),
+                custom_tool.name
+            ),
+            _ => self.display_name(),
+        }
+    }
+
     /// Whether or not the tool should prompt the user to accept before [Self::invoke] is called.
     pub fn requires_acceptance(&self, os: &Os, agent: &Agent) -> PermissionEvalResult {
         match self {
@@ -122,6 +155,7 @@ impl Tool {
             Tool::Thinking(_) => PermissionEvalResult::Allow,
             Tool::Todo(_) => PermissionEvalResult::Allow,
             Tool::Knowledge(knowledge) => knowledge.eval_perm(os, agent),
+            Tool::AgentDelegate(agent_delegate) => agent_delegate.eval_perm(agent),
         }
     }
 
@@ -132,18 +166,31 @@ impl Tool {
         stdout: &mut impl Write,
         line_tracker: &mut HashMap<String, FileLineTracker>,
         agent: Option<&crate::cli::agent::Agent>,
+        conversation_id: &str,
     ) -> Result<InvokeOutput> {
         match self {
-            Tool::FsRead(fs_read) => fs_read.invoke(os, stdout).await,
+            Tool::FsRead(fs_read) => {
+                let max_tokens = FsReadSettings::from_agent(agent).max_result_tokens();
+                fs_read.invoke(os, stdout, max_tokens, agent).await
+            },
             Tool::FsWrite(fs_write) => fs_write.invoke(os, stdout, line_tracker).await,
-            Tool::ExecuteCommand(execute_command) => execute_command.invoke(os, stdout).await,
-            Tool::UseAws(use_aws) => use_aws.invoke(os, stdout).await,
-            Tool::Custom(custom_tool) => custom_tool.invoke(os, stdout).await,
+            Tool::ExecuteCommand(execute_command) => {
+                let settings = ExecuteSettings::from_agent(agent, &self.display_name());
+                execute_command.invoke(os, stdout, &settings).await
+            },
+            Tool::UseAws(use_aws) => {
+                use_aws
+                    .invoke(os, stdout, use_aws::projection(agent).as_deref(), agent)
+                    .await
+            },
+            Tool::Custom(custom_tool) => custom_tool.invoke(os, stdout, agent).await,
             Tool::GhIssue(gh_issue) => gh_issue.invoke(os, stdout).await,
             Tool::Introspect(introspect) => introspect.invoke(os, stdout).await,
-            Tool::Knowledge(knowledge) => knowledge.invoke(os, stdout, agent).await,
+            Tool::Knowledge(knowledge) => knowledge.invoke(os, stdout, agent, conversation_id).await,
             Tool::Thinking(think) => think.invoke(stdout).await,
             Tool::Todo(todo) => todo.invoke(os, stdout).await,
+            // Delegation takes over the conversation, so it is run by the chat session instead.
+            Tool::AgentDelegate(_) => eyre::bail!("agent_delegate can only be run by the chat session"),
         }
     }
 
@@ -152,7 +199,7 @@ impl Tool {
         match self {
             Tool::FsRead(fs_read) => fs_read.queue_description(os, output).await,
             Tool::FsWrite(fs_write) => fs_write.queue_description(os, output),
-            Tool::ExecuteCommand(execute_command) => execute_command.queue_description(output),
+            Tool::ExecuteCommand(execute_command) => execute_command.queue_description(os, output),
             Tool::UseAws(use_aws) => use_aws.queue_description(output),
             Tool::Custom(custom_tool) => custom_tool.queue_description(output),
             Tool::GhIssue(gh_issue) => gh_issue.queue_description(output),
@@ -160,6 +207,7 @@ impl Tool {
             Tool::Knowledge(knowledge) => knowledge.queue_description(os, output).await,
             Tool::Thinking(thinking) => thinking.queue_description(output),
             Tool::Todo(_) => Ok(()),
+            Tool::AgentDelegate(agent_delegate) => agent_delegate.queue_description(output),
         }
     }
 
@@ -176,6 +224,39 @@ impl Tool {
             Tool::Knowledge(knowledge) => knowledge.validate(os).await,
             Tool::Thinking(think) => think.validate(os).await,
             Tool::Todo(todo) => todo.validate(os).await,
+            Tool::AgentDelegate(agent_delegate) => agent_delegate.validate().await,
+        }
+    }
+
+    /// Categorizes an error returned by [Self::invoke] so that the model can decide whether to
+    /// retry or give up.
+    pub fn error_kind(&self, err: &eyre::Report) -> ToolErrorKind {
+        match self {
+            Tool::UseAws(_) => {
+                // The aws cli reports failures through stderr only, so this is a best effort.
+                let message = err.to_string();
+                if [
+                    "AccessDenied",
+                    "UnauthorizedOperation",
+                    "not authorized",
+                    "ExpiredToken",
+                ]
+                .iter()
+                .any(|pat| message.contains(pat))
+                {
+                    ToolErrorKind::PermissionDenied
+                } else if ["NotFound", "NoSuch", "does not exist"]
+                    .iter()
+                    .any(|pat| message.contains(pat))
+                {
+                    ToolErrorKind::NotFound
+                } else if message.contains("timed out") {
+                    ToolErrorKind::Timeout
+                } else {
+                    ToolErrorKind::ExecutionFailed
+                }
+            },
+            _ => ToolErrorKind::from_report(err).unwrap_or(ToolErrorKind::ExecutionFailed),
         }
     }
 
@@ -269,8 +350,15 @@ fn tool_origin() -> ToolOrigin {
 pub struct QueuedTool {
     pub id: String,
     pub name: String,
+    /// The input the model passed to the tool
+    pub args: serde_json::Value,
     pub accepted: bool,
     pub tool: Tool,
+    /// Whether the user edited the tool use before accepting it, in which case [Self::tool] no
+    /// longer matches what the model requested.
+    pub user_edited: bool,
+    /// Number of times in a row the model has made this exact call, including this one.
+    pub consecutive_calls: usize,
 }
 
 /// The schema specification describing a tool's fields.
@@ -297,6 +385,57 @@ impl InvokeOutput {
     }
 }
 
+/// Key of the json content block that carries the [ToolErrorKind] of a failed tool use.
+pub const TOOL_ERROR_KIND_KEY: &str = "errorKind";
+
+/// Machine readable category of a tool use failure, sent to the model alongside the error text.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ToolErrorKind {
+    /// The tool use arguments were malformed or rejected by validation
+    InvalidInput,
+    /// The model requested a tool that does not exist
+    UnknownTool,
+    /// The server providing the tool is not available
+    Unavailable,
+    /// A file, directory, or remote resource the tool operates on does not exist
+    NotFound,
+    /// The tool lacks the permissions required to perform the operation
+    PermissionDenied,
+    /// The operation did not complete in time
+    Timeout,
+    /// The user declined or interrupted the tool use
+    Cancelled,
+    /// Any other failure while running the tool
+    ExecutionFailed,
+}
+
+impl ToolErrorKind {
+    /// Derives the error kind from the underlying causes of `err`, if any of them are recognized.
+    pub fn from_report(err: &eyre::Report) -> Option<Self> {
+        err.chain().find_map(|cause| {
+            if let Some(io_err) = cause.downcast_ref::<std::io::Error>() {
+                match io_err.kind() {
+                    std::io::ErrorKind::NotFound => Some(Self::NotFound),
+                    std::io::ErrorKind::PermissionDenied => Some(Self::PermissionDenied),
+                    std::io::ErrorKind::TimedOut => Some(Self::Timeout),
+                    _ => None,
+                }
+            } else if cause.is::<tokio::time::error::Elapsed>() {
+                Some(Self::Timeout)
+            } else if cause.is::<serde_json::Error>() {
+                Some(Self::InvalidInput)
+            } else {
+                None
+            }
+        })
+    }
+
+    /// The json content to include in the tool result, e.g. `{"errorKind": "NotFound"}`
+    pub fn to_json(self) -> serde_json::Value {
+        serde_json::json!({ TOOL_ERROR_KIND_KEY: self })
+    }
+}
+
 #[non_exhaustive]
 #[derive(Debug)]
 pub enum OutputKind {
@@ -336,6 +475,80 @@ pub fn sanitize_path_tool_arg(os: &Os, path: impl AsRef<Path>) -> PathBuf {
     os.fs.chroot_path(res)
 }
 
+/// Resolves the file that a tool use on `path` actually touches, so that it can be checked against
+/// denied paths. Symlinks are followed on every existing component of the path, including the
+/// file itself, and the components that do not exist yet are resolved from their deepest existing
+/// ancestor. The result is an absolute path as the model would refer to it.
+///
+/// Fails with the reason when the target cannot be determined, such as for a broken symlink or a
+/// directory that cannot be read.
+pub fn resolve_permission_path(os: &Os, path: &str) -> Result<PathBuf, String> {
+    let expanded =
+        directories::canonicalizes_path(os, path).map_err(|e| format!("{path} could not be expanded: {e}"))?;
+    let cwd = os
+        .env
+        .current_dir()
+        .map_err(|e| format!("the current directory could not be read: {e}"))?;
+    let target = os.fs.chroot_path(cwd.join(expanded));
+
+    let mut resolved = PathBuf::new();
+    let mut exists = true;
+    for component in target.components() {
+        match component {
+            Component::CurDir => {},
+            Component::ParentDir => {
+                resolved.pop();
+            },
+            component => {
+                resolved.push(component);
+                if !exists {
+                    continue;
+                }
+                match std::fs::symlink_metadata(&resolved) {
+                    Ok(_) => {
+                        resolved = std::fs::canonicalize(&resolved)
+                            .map_err(|e| format!("{} is a broken symlink: {e}", unchroot(os, &resolved).display()))?;
+                    },
+                    Err(e) if e.kind() == std::io::ErrorKind::NotFound => exists = false,
+                    Err(e) => return Err(format!("{} could not be read: {e}", unchroot(os, &resolved).display())),
+                }
+            },
+        }
+    }
+
+    Ok(unchroot(os, &resolved))
+}
+
+/// Maps a path on a chroot test file system back to the path the model would use.
+fn unchroot(os: &Os, path: &Path) -> PathBuf {
+    if !os.fs.is_chroot() {
+        return path.to_path_buf();
+    }
+    let root = os.fs.chroot_path("/");
+    let root = std::fs::canonicalize(&root).unwrap_or(root);
+    match path.strip_prefix(&root) {
+        Ok(relative) => Path::new("/").join(relative),
+        Err(_) => path.to_path_buf(),
+    }
+}
+
+/// Warns that `path` could not be resolved, in which case the tool use was not checked against
+/// the denied paths and permission is asked for instead.
+pub fn queue_unresolved_path_warning(os: &Os, path: &str, output: &mut impl Write) -> Result<()> {
+    if let Err(reason) = resolve_permission_path(os, path) {
+        queue!(
+            output,
+            style::SetForegroundColor(Color::Yellow),
+            style::Print(glyph(plain_output(os), "⚠ ", "Warning: ")),
+            style::ResetColor,
+            style::Print(format!(
+                "Where this path leads could not be determined ({reason}), so it was not checked against the denied paths\n"
+            )),
+        )?;
+    }
+    Ok(())
+}
+
 /// Converts `path` to a relative path according to the current working directory `cwd`.
 fn absolute_to_relative(cwd: impl AsRef<Path>, path: impl AsRef<Path>) -> Result<PathBuf> {
     let cwd = cwd.as_ref().canonicalize()?;
@@ -381,9 +594,16 @@ fn format_path(cwd: impl AsRef<Path>, path: impl AsRef<Path>) -> String {
         .unwrap_or(path.as_ref().to_string_lossy().to_string())
 }
 
+/// Whether output should use plain ASCII markers without color, for terminals and screen readers
+/// that don't handle spinners, glyphs, or escape codes well.
+pub fn plain_output(os: &Os) -> bool {
+    os.database.settings.get_bool(Setting::ChatPlainOutput).unwrap_or(false)
+}
+
 fn supports_truecolor(os: &Os) -> bool {
     // Simple override to disable truecolor since shell_color doesn't use Context.
-    !os.env.get("Q_DISABLE_TRUECOLOR").is_ok_and(|s| !s.is_empty())
+    !plain_output(os)
+        && !os.env.get("Q_DISABLE_TRUECOLOR").is_ok_and(|s| !s.is_empty())
         && shell_color::get_color_support().contains(shell_color::ColorSupport::TERM24BIT)
 }
 
@@ -412,14 +632,21 @@ pub fn display_purpose(purpose: Option<&String>, updates: &mut impl Write) -> Re
 /// * `updates` - The output to write to
 /// * `is_error` - Whether this is an error message (changes formatting)
 /// * `use_bullet` - Whether to use a bullet point instead of a tick/exclamation
-pub fn queue_function_result(result: &str, updates: &mut impl Write, is_error: bool, use_bullet: bool) -> Result<()> {
+/// * `plain` - Whether to use ASCII instead of the tick/exclamation, see [plain_output]
+pub fn queue_function_result(
+    result: &str,
+    updates: &mut impl Write,
+    is_error: bool,
+    use_bullet: bool,
+    plain: bool,
+) -> Result<()> {
     let lines = result.lines().collect::<Vec<_>>();
 
     // Determine symbol and color
     let (symbol, color) = match (is_error, use_bullet) {
-        (true, _) => (super::ERROR_EXCLAMATION, Color::Red),
+        (true, _) => (glyph(plain, super::ERROR_EXCLAMATION, " [ERROR] "), Color::Red),
         (false, true) => (super::TOOL_BULLET, Color::Reset),
-        (false, false) => (super::SUCCESS_TICK, Color::Green),
+        (false, false) => (glyph(plain, super::SUCCESS_TICK, " [OK] "), Color::Green),
     };
 
     queue!(updates, style::Print("\n"))?;
@@ -476,6 +703,10 @@ pub fn env_vars_with_user_agent(os: &Os) -> std::collections::HashMap<String, St
         env_vars.insert(USER_AGENT_ENV_VAR.to_string(), user_agent_metadata_value);
     }
 
+    if let Some(agent_path) = directories::chat_agent_path_env_var(os) {
+        env_vars.insert(QCLI_AGENT_PATH.to_string(), agent_path.to_string_lossy().into_owned());
+    }
+
     env_vars
 }
 
@@ -552,4 +783,52 @@ mod tests {
         )
         .await;
     }
+
+    #[test]
+    fn test_tool_error_kind_from_report() {
+        let not_found = eyre::Report::new(std::io::Error::from(std::io::ErrorKind::NotFound));
+        assert_eq!(ToolErrorKind::from_report(&not_found), Some(ToolErrorKind::NotFound));
+
+        let denied = eyre::Report::new(std::io::Error::from(std::io::ErrorKind::PermissionDenied))
+            .wrap_err("failed to write file");
+        assert_eq!(
+            ToolErrorKind::from_report(&denied),
+            Some(ToolErrorKind::PermissionDenied)
+        );
+
+        let parse_err = serde_json::from_str::<serde_json::Value>("{").unwrap_err();
+        assert_eq!(
+            ToolErrorKind::from_report(&eyre::Report::new(parse_err)),
+            Some(ToolErrorKind::InvalidInput)
+        );
+
+        assert_eq!(ToolErrorKind::from_report(&eyre::eyre!("something broke")), None);
+        assert_eq!(
+            ToolErrorKind::Timeout.to_json(),
+            serde_json::json!({ "errorKind": "Timeout" })
+        );
+    }
+
+    #[test]
+    fn test_tool_error_kind_use_aws() {
+        let tool = Tool::UseAws(
+            serde_json::from_value(serde_json::json!({
+                "service_name": "s3",
+                "operation_name": "list-buckets",
+                "region": "us-west-2",
+                "label": ""
+            }))
+            .unwrap(),
+        );
+        let kind = |msg: &str| tool.error_kind(&eyre::eyre!(msg.to_string()));
+        assert_eq!(
+            kind("An error occurred (AccessDenied) when calling the ListBuckets operation"),
+            ToolErrorKind::PermissionDenied
+        );
+        assert_eq!(
+            kind("An error occurred (NoSuchBucket) when calling the ListObjects operation"),
+            ToolErrorKind::NotFound
+        );
+        assert_eq!(kind("Unknown options: --foo"), ToolErrorKind::ExecutionFailed);
+    }
 }
@@ -0,0 +1,488 @@
+use serde::Serialize;
+use serde_json::{
+    Map,
+    Value,
+};
+
+use super::InputSchema;
+use crate::cli::chat::cli::suggestions::levenshtein;
+
+/// Unknown fields within this edit distance of a missing field are assumed to be a typo of it.
+const MAX_RENAME_DISTANCE: usize = 2;
+
+/// Describes how the arguments of a tool use don't match the tool's [InputSchema], so that the
+/// model can correct them without the user stepping in.
+///
+/// The `suggestion` is the corrected JSON of the object or array containing the field at
+/// `field_path`, or of the whole arguments if the mismatch is at the root.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(tag = "error_type", rename_all = "snake_case")]
+pub enum ToolParseError {
+    /// A required field is not set
+    MissingField { field_path: String, suggestion: Value },
+    /// A field holds a value of the wrong type
+    WrongType { field_path: String, suggestion: Value },
+    /// A field is not part of the schema
+    UnknownField { field_path: String, suggestion: Value },
+    /// A value is not one of the allowed values, or outside of the allowed range
+    OutOfRange { field_path: String, suggestion: Value },
+}
+
+/// Kinds of mismatch that are found on a value rather than on the object containing it.
+#[derive(Debug, Clone, Copy)]
+enum Kind {
+    WrongType,
+    OutOfRange,
+}
+
+/// A mismatch found while walking the arguments.
+enum Mismatch {
+    /// The value at `field_path` should be replaced by `corrected`. The suggestion is built by the
+    /// object or array containing the value.
+    Value {
+        kind: Kind,
+        field_path: String,
+        corrected: Value,
+    },
+    Resolved(ToolParseError),
+}
+
+impl ToolParseError {
+    /// Finds the first part of `args` that does not match `schema`. Returns [None] if the schema
+    /// does not explain why the arguments were rejected.
+    pub fn from_schema(schema: &InputSchema, args: &Value) -> Option<Self> {
+        Some(match check(&schema.0, args, "")? {
+            Mismatch::Value {
+                kind,
+                field_path,
+                corrected,
+            } => Self::new(kind, field_path, corrected),
+            Mismatch::Resolved(error) => error,
+        })
+    }
+
+    fn new(kind: Kind, field_path: String, suggestion: Value) -> Self {
+        match kind {
+            Kind::WrongType => Self::WrongType { field_path, suggestion },
+            Kind::OutOfRange => Self::OutOfRange { field_path, suggestion },
+        }
+    }
+
+    pub fn to_json(&self) -> Value {
+        serde_json::to_value(self).unwrap_or_default()
+    }
+}
+
+fn check(schema: &Value, value: &Value, path: &str) -> Option<Mismatch> {
+    let mismatch = |kind, corrected| {
+        Some(Mismatch::Value {
+            kind,
+            field_path: path.to_string(),
+            corrected,
+        })
+    };
+
+    if let Some(expected) = schema.get("type").and_then(Value::as_str) {
+        if !has_type(value, expected) {
+            return mismatch(Kind::WrongType, coerce(schema, expected, value));
+        }
+    }
+    if let Some(options) = schema.get("enum").and_then(Value::as_array) {
+        if !options.contains(value) {
+            return mismatch(Kind::OutOfRange, closest_option(options, value));
+        }
+    }
+    if let Some(number) = value.as_f64() {
+        let below = schema
+            .get("minimum")
+            .filter(|min| min.as_f64().is_some_and(|min| number < min));
+        let above = schema
+            .get("maximum")
+            .filter(|max| max.as_f64().is_some_and(|max| number > max));
+        if let Some(limit) = below.or(above) {
+            return mismatch(Kind::OutOfRange, limit.clone());
+        }
+    }
+
+    match value {
+        Value::Object(map) => check_object(schema, map, path),
+        Value::Array(items) => check_array(schema, items, path),
+        _ => None,
+    }
+}
+
+fn check_object(schema: &Value, map: &Map<String, Value>, path: &str) -> Option<Mismatch> {
+    let empty = Map::new();
+    let properties = schema.get("properties").and_then(Value::as_object).unwrap_or(&empty);
+    let unknown = map
+        .keys()
+        .filter(|key| !properties.contains_key(*key))
+        .collect::<Vec<_>>();
+
+    let required = schema.get("required").and_then(Value::as_array);
+    for name in required.into_iter().flatten().filter_map(Value::as_str) {
+        if map.contains_key(name) {
+            continue;
+        }
+        let mut corrected = map.clone();
+        // A field that is missing is often set under a slightly different name
+        if let Some(typo) = unknown.iter().find(|key| is_similar(key, name)) {
+            let value = corrected.remove(typo.as_str()).unwrap_or_default();
+            corrected.insert(name.to_string(), value);
+            return Some(Mismatch::Resolved(ToolParseError::UnknownField {
+                field_path: join(path, typo),
+                suggestion: Value::Object(corrected),
+            }));
+        }
+        corrected.insert(
+            name.to_string(),
+            placeholder(properties.get(name).unwrap_or(&Value::Null)),
+        );
+        return Some(Mismatch::Resolved(ToolParseError::MissingField {
+            field_path: join(path, name),
+            suggestion: Value::Object(corrected),
+        }));
+    }
+
+    for (key, value) in map {
+        let Some(property) = properties.get(key) else {
+            continue;
+        };
+        if let Some(mismatch) = check(property, value, &join(path, key)) {
+            return Some(match mismatch {
+                Mismatch::Value {
+                    kind,
+                    field_path,
+                    corrected,
+                } => {
+                    let mut fixed = map.clone();
+                    fixed.insert(key.clone(), corrected);
+                    Mismatch::Resolved(ToolParseError::new(kind, field_path, Value::Object(fixed)))
+                },
+                resolved => resolved,
+            });
+        }
+    }
+
+    // Unknown fields are only reported last, since most tools ignore them
+    if properties.is_empty() {
+        return None;
+    }
+    let key = unknown.first()?;
+    let mut corrected = map.clone();
+    corrected.remove(*key);
+    Some(Mismatch::Resolved(ToolParseError::UnknownField {
+        field_path: join(path, key),
+        suggestion: Value::Object(corrected),
+    }))
+}
+
+fn check_array(schema: &Value, items: &[Value], path: &str) -> Option<Mismatch> {
+    let item_schema = schema.get("items").unwrap_or(&Value::Null);
+    let min_items = schema.get("minItems").and_then(Value::as_u64).unwrap_or(0) as usize;
+    if items.len() < min_items {
+        let mut corrected = items.to_vec();
+        corrected.resize(min_items, placeholder(item_schema));
+        return Some(Mismatch::Value {
+            kind: Kind::OutOfRange,
+            field_path: path.to_string(),
+            corrected: Value::Array(corrected),
+        });
+    }
+    if let Some(max_items) = schema.get("maxItems").and_then(Value::as_u64) {
+        if items.len() > max_items as usize {
+            return Some(Mismatch::Value {
+                kind: Kind::OutOfRange,
+                field_path: path.to_string(),
+                corrected: Value::Array(items[..max_items as usize].to_vec()),
+            });
+        }
+    }
+
+    for (i, item) in items.iter().enumerate() {
+        if let Some(mismatch) = check(item_schema, item, &format!("{path}[{i}]")) {
+            return Some(match mismatch {
+                Mismatch::Value {
+                    kind,
+                    field_path,
+                    corrected,
+                } => {
+                    let mut fixed = items.to_vec();
+                    fixed[i] = corrected;
+                    Mismatch::Resolved(ToolParseError::new(kind, field_path, Value::Array(fixed)))
+                },
+                resolved => resolved,
+            });
+        }
+    }
+    None
+}
+
+fn join(path: &str, key: &str) -> String {
+    if path.is_empty() {
+        key.to_string()
+    } else {
+        format!("{path}.{key}")
+    }
+}
+
+fn has_type(value: &Value, expected: &str) -> bool {
+    match expected {
+        "object" => value.is_object(),
+        "array" => value.is_array(),
+        "string" => value.is_string(),
+        "integer" => value.is_i64() || value.is_u64(),
+        "number" => value.is_number(),
+        "boolean" => value.is_boolean(),
+        "null" => value.is_null(),
+        _ => true,
+    }
+}
+
+/// Converts `value` to the `expected` type if it holds the same data in another form, e.g. a
+/// number written as a string. Otherwise returns a [placeholder] for the schema.
+fn coerce(schema: &Value, expected: &str, value: &Value) -> Value {
+    let text = match value {
+        Value::String(s) => s.trim().to_string(),
+        other => other.to_string(),
+    };
+    let coerced = match expected {
+        "integer" => text
+            .parse::<i64>()
+            .ok()
+            .or_else(|| value.as_f64().map(|n| n.round() as i64))
+            .map(Value::from),
+        "number" => text
+            .parse::<f64>()
+            .ok()
+            .and_then(|n| serde_json::Number::from_f64(n).map(Value::Number)),
+        "string" if !value.is_object() && !value.is_array() && !value.is_null() => Some(Value::String(text)),
+        "boolean" => text.parse::<bool>().ok().map(Value::Bool),
+        "array" => match value {
+            Value::String(s) => serde_json::from_str::<Value>(s).ok().filter(Value::is_array),
+            Value::Null => None,
+            other => Some(Value::Array(vec![other.clone()])),
+        },
+        "object" => value
+            .as_str()
+            .and_then(|s| serde_json::from_str::<Value>(s).ok())
+            .filter(Value::is_object),
+        _ => None,
+    };
+    coerced.unwrap_or_else(|| placeholder(schema))
+}
+
+/// An example value for `schema`: its default, its first allowed value, or a value of its type.
+fn placeholder(schema: &Value) -> Value {
+    if let Some(default) = schema.get("default") {
+        return default.clone();
+    }
+    if let Some(first) = schema
+        .get("enum")
+        .and_then(Value::as_array)
+        .and_then(|options| options.first())
+    {
+        return first.clone();
+    }
+    match schema.get("type").and_then(Value::as_str) {
+        Some("string") => Value::String("<string>".to_string()),
+        Some("integer" | "number") => schema.get("minimum").cloned().unwrap_or(Value::from(0)),
+        Some("boolean") => Value::Bool(false),
+        Some("array") => {
+            let min_items = schema.get("minItems").and_then(Value::as_u64).unwrap_or(0) as usize;
+            Value::Array(vec![
+                placeholder(schema.get("items").unwrap_or(&Value::Null));
+                min_items
+            ])
+        },
+        Some("object") => {
+            let properties = schema.get("properties").and_then(Value::as_object);
+            let required = schema.get("required").and_then(Value::as_array);
+            Value::Object(
+                required
+                    .into_iter()
+                    .flatten()
+                    .filter_map(Value::as_str)
+                    .map(|name| {
+                        let property = properties.and_then(|p| p.get(name)).unwrap_or(&Value::Null);
+                        (name.to_string(), placeholder(property))
+                    })
+                    .collect(),
+            )
+        },
+        _ => Value::Null,
+    }
+}
+
+/// The allowed value closest to `value`, preferring one that only differs in case.
+fn closest_option(options: &[Value], value: &Value) -> Value {
+    let Some(value) = value.as_str() else {
+        return options.first().cloned().unwrap_or_default();
+    };
+    options
+        .iter()
+        .filter_map(|option| option.as_str().map(|s| (option, s)))
+        .min_by_key(|(_, option)| {
+            if option.eq_ignore_ascii_case(value) {
+                0
+            } else {
+                1 + levenshtein(&option.to_lowercase(), &value.to_lowercase())
+            }
+        })
+        .map(|(option, _)| option.clone())
+        .or_else(|| options.first().cloned())
+        .unwrap_or_default()
+}
+
+/// Whether the field `key` is likely meant to be `name`, e.g. `file_path` or `Path` for `path`.
+fn is_similar(key: &str, name: &str) -> bool {
+    let normalize = |s: &str| s.to_lowercase().replace(['_', '-'], "");
+    let (key, name) = (normalize(key), normalize(name));
+    key == name || levenshtein(&key, &name) <= MAX_RENAME_DISTANCE || (name.len() >= 3 && key.contains(&name))
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    fn schema() -> InputSchema {
+        InputSchema(json!({
+            "type": "object",
+            "properties": {
+                "operations": {
+                    "type": "array",
+                    "minItems": 1,
+                    "items": {
+                        "type": "object",
+                        "properties": {
+                            "mode": { "type": "string", "enum": ["Line", "Directory", "Search"] },
+                            "path": { "type": "string" },
+                            "start_line": { "type": "integer", "default": 1 },
+                            "depth": { "type": "integer", "minimum": 0, "maximum": 10 }
+                        },
+                        "required": ["mode", "path"]
+                    }
+                },
+                "summary": { "type": "string" }
+            },
+            "required": ["operations"]
+        }))
+    }
+
+    #[test]
+    fn test_missing_field() {
+        let error = ToolParseError::from_schema(&schema(), &json!({ "operations": [{ "mode": "Line" }] }));
+        assert_eq!(
+            error,
+            Some(ToolParseError::MissingField {
+                field_path: "operations[0].path".to_string(),
+                suggestion: json!({ "mode": "Line", "path": "<string>" }),
+            })
+        );
+
+        let error = ToolParseError::from_schema(&schema(), &json!({ "summary": "read files" }));
+        assert_eq!(
+            error,
+            Some(ToolParseError::MissingField {
+                field_path: "operations".to_string(),
+                suggestion: json!({ "summary": "read files", "operations": [{ "mode": "Line", "path": "<string>" }] }),
+            })
+        );
+    }
+
+    #[test]
+    fn test_wrong_type() {
+        let args = json!({ "operations": [{ "mode": "Line", "path": "a.rs", "start_line": "10" }] });
+        assert_eq!(
+            ToolParseError::from_schema(&schema(), &args),
+            Some(ToolParseError::WrongType {
+                field_path: "operations[0].start_line".to_string(),
+                suggestion: json!({ "mode": "Line", "path": "a.rs", "start_line": 10 }),
+            })
+        );
+
+        // A single operation is wrapped in an array
+        let args = json!({ "operations": { "mode": "Line", "path": "a.rs" } });
+        assert_eq!(
+            ToolParseError::from_schema(&schema(), &args),
+            Some(ToolParseError::WrongType {
+                field_path: "operations".to_string(),
+                suggestion: json!({ "operations": [{ "mode": "Line", "path": "a.rs" }] }),
+            })
+        );
+    }
+
+    #[test]
+    fn test_unknown_field() {
+        // A misspelled required field is renamed
+        let args = json!({ "operations": [{ "mode": "Line", "file_path": "a.rs" }] });
+        assert_eq!(
+            ToolParseError::from_schema(&schema(), &args),
+            Some(ToolParseError::UnknownField {
+                field_path: "operations[0].file_path".to_string(),
+                suggestion: json!({ "mode": "Line", "path": "a.rs" }),
+            })
+        );
+
+        // Other unknown fields are removed
+        let args = json!({ "operations": [{ "mode": "Line", "path": "a.rs" }], "verbose": true });
+        assert_eq!(
+            ToolParseError::from_schema(&schema(), &args),
+            Some(ToolParseError::UnknownField {
+                field_path: "verbose".to_string(),
+                suggestion: json!({ "operations": [{ "mode": "Line", "path": "a.rs" }] }),
+            })
+        );
+    }
+
+    #[test]
+    fn test_out_of_range() {
+        let args = json!({ "operations": [{ "mode": "line", "path": "a.rs" }] });
+        assert_eq!(
+            ToolParseError::from_schema(&schema(), &args),
+            Some(ToolParseError::OutOfRange {
+                field_path: "operations[0].mode".to_string(),
+                suggestion: json!({ "mode": "Line", "path": "a.rs" }),
+            })
+        );
+
+        let args = json!({ "operations": [{ "mode": "Directory", "path": "src", "depth": 50 }] });
+        assert_eq!(
+            ToolParseError::from_schema(&schema(), &args),
+            Some(ToolParseError::OutOfRange {
+                field_path: "operations[0].depth".to_string(),
+                suggestion: json!({ "mode": "Directory", "path": "src", "depth": 10 }),
+            })
+        );
+
+        let args = json!({ "operations": [] });
+        assert_eq!(
+            ToolParseError::from_schema(&schema(), &args),
+            Some(ToolParseError::OutOfRange {
+                field_path: "operations".to_string(),
+                suggestion: json!({ "operations": [{ "mode": "Line", "path": "<string>" }] }),
+            })
+        );
+    }
+
+    #[test]
+    fn test_valid_args_and_serialization() {
+        let args = json!({ "operations": [{ "mode": "Search", "path": "src", "depth": 2 }], "summary": "search" });
+        assert_eq!(ToolParseError::from_schema(&schema(), &args), None);
+
+        let error = ToolParseError::MissingField {
+            field_path: "operations[0].path".to_string(),
+            suggestion: json!({ "mode": "Line", "path": "<string>" }),
+        };
+        assert_eq!(
+            error.to_json(),
+            json!({
+                "error_type": "missing_field",
+                "field_path": "operations[0].path",
+                "suggestion": { "mode": "Line", "path": "<string>" }
+            })
+        );
+    }
+}
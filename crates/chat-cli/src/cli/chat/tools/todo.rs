@@ -88,17 +88,17 @@ impl TodoListState {
 }
 
 /// Displays a single empty or marked off to-do list task depending on
-/// the completion status
+/// the completion status. Completed tasks are struck through
 fn queue_next_without_newline(output: &mut impl Write, task: String, completed: bool) -> Result<()> {
     if completed {
         queue!(
             output,
             style::SetForegroundColor(style::Color::Green),
             style::Print("[x] "),
-            style::SetAttribute(style::Attribute::Italic),
+            style::SetAttribute(style::Attribute::CrossedOut),
             style::SetForegroundColor(style::Color::DarkGrey),
             style::Print(task),
-            style::SetAttribute(style::Attribute::NoItalic),
+            style::SetAttribute(style::Attribute::NotCrossedOut),
         )?;
     } else {
         queue!(
@@ -480,3 +480,141 @@ where
     let mut seen = HashSet::with_capacity(vec.len());
     vec.iter().any(|item| !seen.insert(item))
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    async fn enabled_os() -> Os {
+        let mut os = Os::new().await.unwrap();
+        os.database.settings.set(Setting::EnabledTodoList, true).await.unwrap();
+        os
+    }
+
+    async fn invoke(os: &Os, mut command: TodoList) -> Result<String> {
+        command.validate(os).await?;
+        let mut output = Vec::new();
+        Ok(command.invoke(os, &mut output).await?.as_str().to_string())
+    }
+
+    fn parse_state(result: &str) -> TodoListState {
+        let state = result
+            .strip_prefix("TODO LIST STATE: ")
+            .and_then(|s| s.split("\n\n").next())
+            .unwrap();
+        serde_json::from_str(state).unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_todo_list_state_transitions() {
+        let os = enabled_os().await;
+
+        let state = parse_state(
+            &invoke(&os, TodoList::Create {
+                tasks: vec!["read".to_string(), "write".to_string(), "test".to_string()],
+                todo_list_description: "implement a feature".to_string(),
+            })
+            .await
+            .unwrap(),
+        );
+        let id = state.id.clone();
+        assert_eq!(state.tasks.len(), 3);
+        assert!(state.tasks.iter().all(|t| !t.completed));
+
+        let state = parse_state(
+            &invoke(&os, TodoList::Complete {
+                completed_indices: vec![0],
+                context_update: "read the code".to_string(),
+                modified_files: None,
+                current_id: id.clone(),
+            })
+            .await
+            .unwrap(),
+        );
+        assert!(state.tasks[0].completed);
+        assert_eq!(state.context, vec!["read the code"]);
+
+        let state = parse_state(
+            &invoke(&os, TodoList::Add {
+                new_tasks: vec!["review".to_string()],
+                insert_indices: vec![3],
+                new_description: None,
+                current_id: id.clone(),
+            })
+            .await
+            .unwrap(),
+        );
+        assert_eq!(state.tasks.last().unwrap().task_description, "review");
+
+        let state = parse_state(
+            &invoke(&os, TodoList::Remove {
+                remove_indices: vec![1, 2],
+                new_description: Some("review a feature".to_string()),
+                current_id: id.clone(),
+            })
+            .await
+            .unwrap(),
+        );
+        let tasks = state
+            .tasks
+            .iter()
+            .map(|t| t.task_description.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(tasks, vec!["read", "review"]);
+        assert_eq!(state.description, "review a feature");
+
+        // The state is persisted and can be loaded again
+        let loaded = parse_state(&invoke(&os, TodoList::Load { load_id: id.clone() }).await.unwrap());
+        assert_eq!(loaded.tasks.len(), 2);
+        assert!(loaded.tasks[0].completed);
+
+        // Invalid transitions are rejected before anything is changed
+        assert!(
+            invoke(&os, TodoList::Complete {
+                completed_indices: vec![2],
+                context_update: "out of bounds".to_string(),
+                modified_files: None,
+                current_id: id.clone(),
+            })
+            .await
+            .is_err()
+        );
+        assert!(
+            invoke(&os, TodoList::Remove {
+                remove_indices: vec![0, 0],
+                new_description: None,
+                current_id: id.clone(),
+            })
+            .await
+            .is_err()
+        );
+        assert_eq!(TodoListState::load(&os, &id).await.unwrap().tasks.len(), 2);
+    }
+
+    #[test]
+    fn test_display_list() {
+        let state = TodoListState {
+            tasks: vec![
+                Task {
+                    task_description: "done".to_string(),
+                    completed: true,
+                },
+                Task {
+                    task_description: "pending".to_string(),
+                    completed: false,
+                },
+            ],
+            ..Default::default()
+        };
+
+        let mut output = Vec::new();
+        state.display_list(&mut output).unwrap();
+        let output = String::from_utf8(output).unwrap();
+
+        assert!(output.contains("[ ] pending"));
+        let done = output.find("[x] ").unwrap();
+        let crossed_out = format!("{}", style::SetAttribute(style::Attribute::CrossedOut));
+        let crossed_out = output[done..].find(&crossed_out).unwrap() + done;
+        assert!(crossed_out < output.find("done").unwrap());
+    }
+}
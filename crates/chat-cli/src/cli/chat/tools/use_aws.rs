@@ -15,6 +15,7 @@ use eyre::{
     Result,
     WrapErr,
 };
+use schemars::JsonSchema;
 use serde::Deserialize;
 use tracing::error;
 
@@ -33,6 +34,94 @@ use crate::util::pattern_matching::matches_any_pattern;
 
 const READONLY_OPS: [&str; 6] = ["get", "describe", "list", "ls", "search", "batch_get"];
 
+/// Key in the `use_aws` `toolsSettings` entry with the fields to keep from JSON responses.
+const PROJECTION_KEY: &str = "projection";
+
+/// Returns the field paths configured by the `projection` key of the `use_aws` `toolsSettings`
+/// entry, or [None] if no projection is set.
+pub fn projection(agent: Option<&Agent>) -> Option<Vec<String>> {
+    let fields: Vec<String> = agent
+        .and_then(|agent| agent.tools_settings.get("use_aws"))
+        .and_then(|settings| settings.get(PROJECTION_KEY))
+        .and_then(|value| value.as_array())?
+        .iter()
+        .filter_map(|value| value.as_str())
+        .map(|field| field.trim().to_string())
+        .filter(|field| !field.is_empty())
+        .collect();
+    (!fields.is_empty()).then_some(fields)
+}
+
+/// Keeps only the parts of `value` selected by `fields`, preserving the surrounding structure.
+///
+/// Each field is a dot separated path of object keys, e.g. `Reservations[].Instances[].InstanceId`.
+/// Paths are applied to every element of the arrays they pass through, and a `[]` suffix on a key
+/// is accepted for readability. Returns [None] if nothing in `value` matches.
+fn project_fields(value: &serde_json::Value, fields: &[String]) -> Option<serde_json::Value> {
+    let paths: Vec<Vec<&str>> = fields
+        .iter()
+        .map(|field| {
+            field
+                .split('.')
+                .map(|key| key.trim_end_matches("[]"))
+                .filter(|key| !key.is_empty())
+                .collect()
+        })
+        .collect();
+    let paths: Vec<&[&str]> = paths.iter().map(Vec::as_slice).collect();
+    project_paths(value, &paths)
+}
+
+fn project_paths(value: &serde_json::Value, paths: &[&[&str]]) -> Option<serde_json::Value> {
+    if paths.iter().any(|path| path.is_empty()) {
+        return Some(value.clone());
+    }
+
+    match value {
+        serde_json::Value::Array(items) => {
+            let items: Vec<_> = items.iter().filter_map(|item| project_paths(item, paths)).collect();
+            (!items.is_empty()).then_some(serde_json::Value::Array(items))
+        },
+        serde_json::Value::Object(map) => {
+            let mut projected = serde_json::Map::new();
+            for (key, child) in map {
+                let child_paths: Vec<&[&str]> = paths
+                    .iter()
+                    .filter(|path| path[0] == key.as_str())
+                    .map(|path| &path[1..])
+                    .collect();
+                if child_paths.is_empty() {
+                    continue;
+                }
+                if let Some(child) = project_paths(child, &child_paths) {
+                    projected.insert(key.clone(), child);
+                }
+            }
+            (!projected.is_empty()).then_some(serde_json::Value::Object(projected))
+        },
+        _ => None,
+    }
+}
+
+/// Whether the AWS CLI failed because the credentials are missing or expired, e.g. because the
+/// SSO session ran out. The CLI exits with 253 for invalid credentials or configuration and 255
+/// for any other error.
+fn is_auth_error(exit_code: Option<i32>, stderr: &str) -> bool {
+    matches!(exit_code, Some(253 | 255)) && (stderr.contains("SSO") || stderr.to_lowercase().contains("credentials"))
+}
+
+/// Settings for the use_aws tool in the `toolsSettings` of an agent.
+#[derive(Debug, Deserialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct UseAwsSettings {
+    /// AWS services that can be accessed without prompting
+    #[serde(default)]
+    allowed_services: Vec<String>,
+    /// AWS services that are denied. Deny rules are evaluated before allow rules
+    #[serde(default)]
+    denied_services: Vec<String>,
+}
+
 // TODO: we should perhaps composite this struct with an interface that we can use to mock the
 // actual cli with. That will allow us to more thoroughly test it.
 #[derive(Debug, Clone, Deserialize)]
@@ -50,36 +139,40 @@ impl UseAws {
         !READONLY_OPS.iter().any(|op| self.operation_name.starts_with(op))
     }
 
-    pub async fn invoke(&self, os: &Os, _updates: impl Write) -> Result<InvokeOutput> {
-        let mut command = tokio::process::Command::new("aws");
+    /// Runs the command. When `projection` is set and the command printed JSON, only the
+    /// projected fields of the response are returned.
+    ///
+    /// If the command fails because the AWS credentials are missing or expired and `use_aws` is
+    /// in the agent's `allowedTools`, the user is offered to run `aws sso login` before the
+    /// command is retried.
+    pub async fn invoke(
+        &self,
+        os: &Os,
+        _updates: impl Write,
+        projection: Option<&[String]>,
+        agent: Option<&Agent>,
+    ) -> Result<InvokeOutput> {
+        let mut output = self.run(os).await?;
+        if !output.status.success()
+            && is_auth_error(output.status.code(), &output.stderr.to_str_lossy())
+            && agent.is_some_and(|agent| matches_any_pattern(&agent.allowed_tools, "use_aws"))
+            && self.sso_login(os).await?
+        {
+            output = self.run(os).await?;
+        }
 
-        // Set up environment variables with user agent metadata for CloudTrail tracking
-        let env_vars = env_vars_with_user_agent(os);
+        let status = output.status.code().unwrap_or(0).to_string();
+        let mut stdout = output.stdout.to_str_lossy();
+        let stderr = output.stderr.to_str_lossy();
 
-        command.envs(env_vars).arg("--region").arg(&self.region);
-        if let Some(profile_name) = self.profile_name.as_deref() {
-            command.arg("--profile").arg(profile_name);
-        }
-        command.arg(&self.service_name).arg(&self.operation_name);
-        if let Some(parameters) = self.cli_parameters() {
-            for (name, val) in parameters {
-                command.arg(name);
-                if !val.is_empty() {
-                    command.arg(val);
-                }
+        if let Some(fields) = projection {
+            if let Ok(response) = serde_json::from_str::<serde_json::Value>(&stdout) {
+                let projected = project_fields(&response, fields).unwrap_or_else(|| serde_json::json!({}));
+                stdout = serde_json::to_string_pretty(&projected)
+                    .unwrap_or_else(|_| projected.to_string())
+                    .into();
             }
         }
-        let output = command
-            .stdout(Stdio::piped())
-            .stderr(Stdio::piped())
-            .spawn()
-            .wrap_err_with(|| format!("Unable to spawn command '{:?}'", self))?
-            .wait_with_output()
-            .await
-            .wrap_err_with(|| format!("Unable to spawn command '{:?}'", self))?;
-        let status = output.status.code().unwrap_or(0).to_string();
-        let stdout = output.stdout.to_str_lossy();
-        let stderr = output.stderr.to_str_lossy();
 
         let stdout = format!(
             "{}{}",
@@ -114,6 +207,80 @@ impl UseAws {
         }
     }
 
+    async fn run(&self, os: &Os) -> Result<std::process::Output> {
+        let mut command = tokio::process::Command::new("aws");
+
+        // Set up environment variables with user agent metadata for CloudTrail tracking
+        let env_vars = env_vars_with_user_agent(os);
+
+        command.envs(env_vars).arg("--region").arg(&self.region);
+        if let Some(profile_name) = self.profile_name.as_deref() {
+            command.arg("--profile").arg(profile_name);
+        }
+        command.arg(&self.service_name).arg(&self.operation_name);
+        if let Some(parameters) = self.cli_parameters() {
+            for (name, val) in parameters {
+                command.arg(name);
+                if !val.is_empty() {
+                    command.arg(val);
+                }
+            }
+        }
+        command
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()
+            .wrap_err_with(|| format!("Unable to spawn command '{:?}'", self))?
+            .wait_with_output()
+            .await
+            .wrap_err_with(|| format!("Unable to spawn command '{:?}'", self))
+    }
+
+    /// Asks the user whether to run `aws sso login` and runs it attached to the terminal.
+    /// Returns whether the login succeeded.
+    async fn sso_login(&self, os: &Os) -> Result<bool> {
+        use std::io::IsTerminal;
+
+        if !std::io::stdin().is_terminal() || !std::io::stderr().is_terminal() {
+            return Ok(false);
+        }
+
+        let mut stderr = std::io::stderr();
+        queue!(
+            stderr,
+            style::Print("\nThe AWS credentials are missing or expired. Would you like to run `aws sso login"),
+            style::Print(match &self.profile_name {
+                Some(profile_name) => format!(" --profile {profile_name}`? [y/N]: "),
+                None => "`? [y/N]: ".to_string(),
+            }),
+        )?;
+        stderr.flush()?;
+
+        // Reading the answer blocks, so keep it off the runtime's worker threads
+        let input = tokio::task::spawn_blocking(|| {
+            let mut input = String::new();
+            std::io::stdin().read_line(&mut input).map(|_| input)
+        })
+        .await??;
+        if !matches!(input.trim().to_lowercase().as_str(), "y" | "yes") {
+            return Ok(false);
+        }
+
+        let mut command = tokio::process::Command::new("aws");
+        command.envs(env_vars_with_user_agent(os)).arg("sso").arg("login");
+        if let Some(profile_name) = self.profile_name.as_deref() {
+            command.arg("--profile").arg(profile_name);
+        }
+        let status = command
+            .stdin(Stdio::inherit())
+            .stdout(Stdio::inherit())
+            .stderr(Stdio::inherit())
+            .status()
+            .await
+            .wrap_err("Unable to run 'aws sso login'")?;
+        Ok(status.success())
+    }
+
     pub fn queue_description(&self, output: &mut impl Write) -> Result<()> {
         queue!(
             output,
@@ -175,20 +342,11 @@ impl UseAws {
     }
 
     pub fn eval_perm(&self, _os: &Os, agent: &Agent) -> PermissionEvalResult {
-        #[derive(Debug, Deserialize)]
-        #[serde(rename_all = "camelCase")]
-        struct Settings {
-            #[serde(default)]
-            allowed_services: Vec<String>,
-            #[serde(default)]
-            denied_services: Vec<String>,
-        }
-
         let Self { service_name, .. } = self;
         let is_in_allowlist = matches_any_pattern(&agent.allowed_tools, "use_aws");
         match agent.tools_settings.get("use_aws") {
             Some(settings) => {
-                let settings = match serde_json::from_value::<Settings>(settings.clone()) {
+                let settings = match serde_json::from_value::<UseAwsSettings>(settings.clone()) {
                     Ok(settings) => settings,
                     Err(e) => {
                         error!("Failed to deserialize tool settings for use_aws: {:?}", e);
@@ -254,6 +412,21 @@ mod tests {
         assert!(cmd.requires_acceptance());
     }
 
+    #[test]
+    fn test_is_auth_error() {
+        assert!(is_auth_error(
+            Some(255),
+            "Error when retrieving token from sso: Token has expired and refresh failed"
+        ));
+        assert!(is_auth_error(Some(253), "Unable to locate credentials."));
+        assert!(!is_auth_error(
+            Some(255),
+            "An error occurred (AccessDenied) when calling the ListBuckets operation"
+        ));
+        assert!(!is_auth_error(Some(1), "Unable to locate credentials."));
+        assert!(!is_auth_error(None, "Unable to locate credentials."));
+    }
+
     #[test]
     fn test_use_aws_deser() {
         let cmd = use_aws! {{
@@ -301,7 +474,7 @@ mod tests {
         assert!(
             serde_json::from_value::<UseAws>(v)
                 .unwrap()
-                .invoke(&os, &mut std::io::stdout())
+                .invoke(&os, &mut std::io::stdout(), None, None)
                 .await
                 .is_err()
         );
@@ -322,7 +495,7 @@ mod tests {
         });
         let out = serde_json::from_value::<UseAws>(v)
             .unwrap()
-            .invoke(&os, &mut std::io::stdout())
+            .invoke(&os, &mut std::io::stdout(), None, None)
             .await
             .unwrap();
 
@@ -390,4 +563,77 @@ mod tests {
         let res = cmd_one.eval_perm(&os, &agent);
         assert!(matches!(res, PermissionEvalResult::Deny(ref services) if services.contains(&"s3".to_string())));
     }
+
+    #[test]
+    fn test_projection_from_settings() {
+        let mut agent = Agent::default();
+        assert_eq!(projection(Some(&agent)), None);
+        assert_eq!(projection(None), None);
+
+        agent.tools_settings.insert(
+            ToolSettingTarget("use_aws".to_string()),
+            serde_json::json!({
+                "projection": ["Reservations[].Instances[].InstanceId", " ", 3]
+            }),
+        );
+        assert_eq!(
+            projection(Some(&agent)),
+            Some(vec!["Reservations[].Instances[].InstanceId".to_string()])
+        );
+    }
+
+    #[test]
+    fn test_project_fields() {
+        let response = serde_json::json!({
+            "Reservations": [
+                {
+                    "ReservationId": "r-1",
+                    "Instances": [
+                        {
+                            "InstanceId": "i-1",
+                            "State": { "Code": 16, "Name": "running" },
+                            "Tags": [{ "Key": "Name", "Value": "web" }]
+                        },
+                        {
+                            "InstanceId": "i-2",
+                            "State": { "Code": 80, "Name": "stopped" }
+                        }
+                    ]
+                }
+            ],
+            "NextToken": "abc"
+        });
+
+        let fields = vec![
+            "Reservations[].Instances[].InstanceId".to_string(),
+            "Reservations.Instances.State.Name".to_string(),
+            "Reservations[].Instances[].Tags".to_string(),
+        ];
+        assert_eq!(
+            project_fields(&response, &fields),
+            Some(serde_json::json!({
+                "Reservations": [
+                    {
+                        "Instances": [
+                            {
+                                "InstanceId": "i-1",
+                                "State": { "Name": "running" },
+                                "Tags": [{ "Key": "Name", "Value": "web" }]
+                            },
+                            {
+                                "InstanceId": "i-2",
+                                "State": { "Name": "stopped" }
+                            }
+                        ]
+                    }
+                ]
+            }))
+        );
+
+        assert_eq!(project_fields(&response, &["Missing.Field".to_string()]), None);
+        assert_eq!(
+            project_fields(&response, &["NextToken".to_string()]),
+            Some(serde_json::json!({ "NextToken": "abc" }))
+        );
+    }
 }
@@ -2,12 +2,17 @@ use std::fs;
 use std::io::Write;
 use std::path::Path;
 use std::str::FromStr;
+use std::time::Duration;
 
 use crossterm::execute;
 use crossterm::style::{
     self,
     Color,
 };
+use eyre::{
+    Result,
+    bail,
+};
 use serde::{
     Deserialize,
     Serialize,
@@ -23,6 +28,9 @@ use crate::cli::chat::consts::{
     MAX_NUMBER_OF_IMAGES_PER_REQUEST,
 };
 
+/// Maximum time allowed for downloading an image from a URL.
+const IMAGE_DOWNLOAD_TIMEOUT: Duration = Duration::from_secs(30);
+
 #[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct ImageMetadata {
     pub filepath: String,
@@ -182,6 +190,86 @@ pub fn get_image_block_from_file_path(maybe_file_path: &str) -> Option<ImageBloc
     Some(image_block)
 }
 
+/// Whether `path` refers to an image on the web rather than a local file.
+pub fn is_image_url(path: &str) -> bool {
+    let path = path.trim().to_lowercase();
+    path.starts_with("http://") || path.starts_with("https://")
+}
+
+/// Returns the image format of a `Content-Type` header value such as `image/png`, or [None] if
+/// the content is not a supported image type.
+fn image_format_from_content_type(content_type: &str) -> Option<ImageFormat> {
+    let mime_type = content_type.split(';').next()?.trim().to_lowercase();
+    ImageFormat::from_str(mime_type.strip_prefix("image/")?).ok()
+}
+
+/// Returns the format of an image from the magic number at the start of its content, or [None]
+/// if the content is not a supported image type.
+pub fn image_format_from_bytes(bytes: &[u8]) -> Option<ImageFormat> {
+    match bytes {
+        [0x89, b'P', b'N', b'G', 0x0d, 0x0a, 0x1a, 0x0a, ..] => Some(ImageFormat::Png),
+        [0xff, 0xd8, 0xff, ..] => Some(ImageFormat::Jpeg),
+        [b'G', b'I', b'F', b'8', b'7' | b'9', b'a', ..] => Some(ImageFormat::Gif),
+        [b'R', b'I', b'F', b'F', _, _, _, _, b'W', b'E', b'B', b'P', ..] => Some(ImageFormat::Webp),
+        _ => None,
+    }
+}
+
+/// Downloads the image at `url`, failing if the response is not a supported image type or is
+/// larger than [MAX_IMAGE_SIZE].
+pub async fn get_image_block_from_url(url: &str) -> Result<RichImageBlock> {
+    let parsed_url = url::Url::parse(url.trim())?;
+    let client = crate::request::new_client()?;
+    let mut response = client
+        .get(parsed_url.clone())
+        .timeout(IMAGE_DOWNLOAD_TIMEOUT)
+        .send()
+        .await?
+        .error_for_status()?;
+
+    let content_type = response
+        .headers()
+        .get(reqwest::header::CONTENT_TYPE)
+        .and_then(|value| value.to_str().ok())
+        .unwrap_or_default()
+        .to_string();
+    let Some(format) = image_format_from_content_type(&content_type) else {
+        bail!("'{url}' is not a supported image type (content type: '{content_type}')");
+    };
+
+    let max_mb = MAX_IMAGE_SIZE / (1024 * 1024);
+    let too_large = || eyre::eyre!("'{url}' exceeds the image size limit ({max_mb}MB)");
+    if response.content_length().unwrap_or_default() as usize > MAX_IMAGE_SIZE {
+        return Err(too_large());
+    }
+    let mut bytes = Vec::new();
+    while let Some(chunk) = response.chunk().await? {
+        if bytes.len() + chunk.len() > MAX_IMAGE_SIZE {
+            return Err(too_large());
+        }
+        bytes.extend_from_slice(&chunk);
+    }
+
+    let filename = parsed_url
+        .path_segments()
+        .and_then(|mut segments| segments.next_back())
+        .filter(|segment| !segment.is_empty())
+        .unwrap_or(parsed_url.as_str())
+        .to_string();
+    let size = bytes.len() as u64;
+    Ok((
+        ImageBlock {
+            format,
+            source: ImageSource::Bytes(bytes),
+        },
+        ImageMetadata {
+            filepath: url.to_string(),
+            size,
+            filename,
+        },
+    ))
+}
+
 #[cfg(test)]
 mod tests {
     use std::str::FromStr;
@@ -220,6 +308,39 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_image_format_from_bytes() {
+        assert_eq!(
+            image_format_from_bytes(b"\x89PNG\r\n\x1a\n\0\0\0\rIHDR"),
+            Some(ImageFormat::Png)
+        );
+        assert_eq!(image_format_from_bytes(b"\xff\xd8\xff\xe0"), Some(ImageFormat::Jpeg));
+        assert_eq!(image_format_from_bytes(b"GIF89a"), Some(ImageFormat::Gif));
+        assert_eq!(
+            image_format_from_bytes(b"RIFF\0\0\0\0WEBPVP8 "),
+            Some(ImageFormat::Webp)
+        );
+        assert_eq!(image_format_from_bytes(b"RIFF\0\0\0\0WAVEfmt "), None);
+        assert_eq!(image_format_from_bytes(b"\x89PN"), None);
+    }
+
+    #[test]
+    fn test_image_urls() {
+        assert!(is_image_url("https://example.com/diagram.png"));
+        assert!(is_image_url("HTTP://example.com/photo"));
+        assert!(!is_image_url("/tmp/https://example.png"));
+        assert!(!is_image_url("diagram.png"));
+
+        assert_eq!(image_format_from_content_type("image/png"), Some(ImageFormat::Png));
+        assert_eq!(
+            image_format_from_content_type("Image/JPEG; charset=binary"),
+            Some(ImageFormat::Jpeg)
+        );
+        assert_eq!(image_format_from_content_type("image/svg+xml"), None);
+        assert_eq!(image_format_from_content_type("text/html; charset=utf-8"), None);
+        assert_eq!(image_format_from_content_type(""), None);
+    }
+
     #[test]
     fn test_handle_images_from_paths() {
         let temp_dir = tempfile::tempdir().unwrap();
@@ -1,7 +1,11 @@
 pub mod images;
 pub mod issue;
+pub mod redact;
+pub mod sanitize;
 #[cfg(test)]
 pub mod test;
+pub mod tokens;
+pub mod tool_display;
 pub mod ui;
 
 use std::io::Write;
@@ -34,6 +38,21 @@ pub fn truncate_safe(s: &str, max_bytes: usize) -> &str {
     &s[..byte_count]
 }
 
+/// Returns the longest suffix of `s` that is at most `max_bytes` long, without splitting a
+/// character.
+pub fn truncate_safe_start(s: &str, max_bytes: usize) -> &str {
+    if s.len() <= max_bytes {
+        return s;
+    }
+
+    let mut start = s.len() - max_bytes;
+    while !s.is_char_boundary(start) {
+        start += 1;
+    }
+
+    &s[start..]
+}
+
 /// Truncates `s` to a maximum length of `max_bytes`, appending `suffix` if `s` was truncated. The
 /// result is always guaranteed to be at least less than `max_bytes`.
 ///
@@ -243,6 +262,16 @@ mod tests {
         assert_eq!(truncate_safe("Hello World", 15), "Hello World");
     }
 
+    #[test]
+    fn test_truncate_safe_start() {
+        assert_eq!(truncate_safe_start("Hello World", 5), "World");
+        assert_eq!(truncate_safe_start("Hello World", 11), "Hello World");
+        assert_eq!(truncate_safe_start("Hello World", 15), "Hello World");
+        // α -> 2 byte length
+        assert_eq!(truncate_safe_start("αααααα", 3), "α");
+        assert_eq!(truncate_safe_start("αααααα", 4), "αα");
+    }
+
     #[test]
     fn test_truncate_safe_in_place() {
         let suffix = "suffix";
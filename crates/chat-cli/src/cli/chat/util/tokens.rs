@@ -0,0 +1,142 @@
+use super::truncate_safe;
+use crate::cli::agent::Agent;
+use crate::cli::chat::consts::MAX_TOOL_RESPONSE_SIZE;
+
+/// Token budget for a single tool result when the tool has no `maxResultTokens` setting.
+pub const DEFAULT_MAX_RESULT_TOKENS: usize = 25_000;
+
+/// Key in a tool's `toolsSettings` entry that overrides [DEFAULT_MAX_RESULT_TOKENS].
+const MAX_RESULT_TOKENS_KEY: &str = "maxResultTokens";
+
+/// Estimates the number of model tokens in `text`.
+///
+/// Runs of letters and digits count as one token per four characters, while every other
+/// non-whitespace character counts as a token of its own. This follows symbol heavy output such as
+/// JSON much more closely than a flat bytes per token ratio.
+pub fn estimate_tokens(text: &str) -> usize {
+    let mut tokens = 0;
+    let mut word_len: usize = 0;
+    for c in text.chars() {
+        if c.is_alphanumeric() || c == '_' {
+            word_len += 1;
+            continue;
+        }
+        tokens += word_len.div_ceil(4);
+        word_len = 0;
+        if !c.is_whitespace() {
+            tokens += 1;
+        }
+    }
+    tokens + word_len.div_ceil(4)
+}
+
+/// Returns the result token budget for the MCP tool `tool_name`, as configured by the
+/// `maxResultTokens` key of the tool's `toolsSettings` entry. Built-in tools read it through their
+/// settings struct instead.
+pub fn max_result_tokens(agent: Option<&Agent>, tool_name: &str) -> usize {
+    agent
+        .and_then(|agent| agent.tools_settings.get(tool_name))
+        .and_then(|settings| settings.get(MAX_RESULT_TOKENS_KEY))
+        .and_then(|value| value.as_u64())
+        .map_or(DEFAULT_MAX_RESULT_TOKENS, |value| value as usize)
+}
+
+/// Truncates `s` to roughly `max_tokens` estimated tokens, returning whether anything was cut.
+///
+/// Whole lines are kept where possible; a line is only split when the first line alone is over
+/// budget. [MAX_TOOL_RESPONSE_SIZE] is always enforced as a hard byte ceiling. When truncated, a
+/// note with the original size in bytes and estimated tokens is appended.
+pub fn truncate_to_tokens(s: &mut String, max_tokens: usize) -> bool {
+    let total_tokens = estimate_tokens(s);
+    if total_tokens <= max_tokens && s.len() <= MAX_TOOL_RESPONSE_SIZE {
+        return false;
+    }
+
+    let note = format!(
+        "... truncated: original output was {} bytes (~{total_tokens} tokens)",
+        s.len()
+    );
+    let max_bytes = MAX_TOOL_RESPONSE_SIZE.saturating_sub(note.len() + 1);
+
+    let mut end = 0;
+    let mut tokens = 0;
+    for line in s.split_inclusive('\n') {
+        let line_tokens = estimate_tokens(line);
+        if tokens + line_tokens > max_tokens || end + line.len() > max_bytes {
+            break;
+        }
+        tokens += line_tokens;
+        end += line.len();
+    }
+
+    if end == 0 {
+        end = longest_prefix_within(s, max_tokens, max_bytes);
+    }
+
+    s.truncate(end);
+    if !s.is_empty() && !s.ends_with('\n') {
+        s.push('\n');
+    }
+    s.push_str(&note);
+    true
+}
+
+/// Length of the longest prefix of `s` on a char boundary that fits both budgets.
+fn longest_prefix_within(s: &str, max_tokens: usize, max_bytes: usize) -> usize {
+    let (mut low, mut high) = (0, s.len().min(max_bytes));
+    while low < high {
+        let mid = truncate_safe(s, (low + high).div_ceil(2)).len();
+        if mid > low && estimate_tokens(&s[..mid]) <= max_tokens {
+            low = mid;
+        } else {
+            high = (low + high).div_ceil(2) - 1;
+        }
+    }
+    low
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_estimate_tokens() {
+        assert_eq!(estimate_tokens(""), 0);
+        assert_eq!(estimate_tokens("hello world"), 4);
+        // Punctuation heavy JSON costs more tokens than its length suggests
+        assert_eq!(estimate_tokens(r#"{"a": 1, "b": [2, 3]}"#), 17);
+    }
+
+    #[test]
+    fn test_truncate_to_tokens_keeps_whole_lines() {
+        let mut s = "line one\nline two\nline three\n".to_string();
+        assert!(!truncate_to_tokens(&mut s, 100));
+
+        let original_len = s.len();
+        let original_tokens = estimate_tokens(&s);
+        assert!(truncate_to_tokens(&mut s, 5));
+        assert_eq!(
+            s,
+            format!(
+                "line one\nline two\n... truncated: original output was {original_len} bytes (~{original_tokens} tokens)"
+            )
+        );
+    }
+
+    #[test]
+    fn test_truncate_to_tokens_splits_long_line() {
+        let mut s = "word ".repeat(100);
+        assert!(truncate_to_tokens(&mut s, 10));
+        let (kept, note) = s.split_once('\n').unwrap();
+        assert!(estimate_tokens(kept) <= 10);
+        assert!(!kept.is_empty());
+        assert!(note.starts_with("... truncated: original output was 500 bytes"));
+    }
+
+    #[test]
+    fn test_truncate_to_tokens_enforces_byte_ceiling() {
+        let mut s = "a\n".repeat(MAX_TOOL_RESPONSE_SIZE);
+        assert!(truncate_to_tokens(&mut s, usize::MAX));
+        assert!(s.len() <= MAX_TOOL_RESPONSE_SIZE);
+    }
+}
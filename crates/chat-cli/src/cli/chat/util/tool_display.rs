@@ -0,0 +1,197 @@
+use std::io::{
+    self,
+    Write,
+};
+
+use clap::ValueEnum;
+use crossterm::queue;
+use crossterm::style::{
+    self,
+    Color,
+};
+
+use crate::cli::agent::Agent;
+
+/// Key in a tool's `toolsSettings` entry that sets its [DisplayOutput].
+const DISPLAY_OUTPUT_KEY: &str = "displayOutput";
+
+/// Number of lines of tool output shown in [DisplayOutput::Summary] mode.
+pub const SUMMARY_LINES: usize = 10;
+
+/// How much of a tool's output is shown in the terminal while it runs. This never changes the
+/// result that is sent to the model.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, ValueEnum)]
+pub enum DisplayOutput {
+    /// Everything the tool prints
+    #[default]
+    Full,
+    /// The first lines the tool prints, followed by the number of lines left out
+    Summary,
+    /// Only whether the tool succeeded
+    Silent,
+}
+
+impl DisplayOutput {
+    /// Returns the mode set by the `displayOutput` key of the tool's `toolsSettings` entry.
+    pub fn from_settings(agent: Option<&Agent>, tool_name: &str) -> Self {
+        agent
+            .and_then(|agent| agent.tools_settings.get(tool_name))
+            .and_then(|settings| settings.get(DISPLAY_OUTPUT_KEY))
+            .and_then(|value| value.as_str())
+            .and_then(|value| Self::from_str(value, true).ok())
+            .unwrap_or_default()
+    }
+}
+
+/// Writes what a tool prints to `inner` according to a [DisplayOutput] mode. Call
+/// [Self::finish] once the tool is done to print how many lines were left out.
+pub struct DisplayWriter<W: Write> {
+    inner: W,
+    mode: DisplayOutput,
+    /// Lines written to [Self::inner] so far
+    shown_lines: usize,
+    /// Complete lines that were left out
+    hidden_lines: usize,
+    /// Whether text after the last hidden line break was left out
+    hidden_partial_line: bool,
+}
+
+impl<W: Write> DisplayWriter<W> {
+    pub fn new(inner: W, mode: DisplayOutput) -> Self {
+        Self {
+            inner,
+            mode,
+            shown_lines: 0,
+            hidden_lines: 0,
+            hidden_partial_line: false,
+        }
+    }
+
+    fn hide(&mut self, buf: &[u8]) {
+        for line in buf.split_inclusive(|b| *b == b'\n') {
+            if line.ends_with(b"\n") {
+                self.hidden_lines += 1;
+                self.hidden_partial_line = false;
+            } else {
+                self.hidden_partial_line = true;
+            }
+        }
+    }
+
+    /// Prints the number of lines left out in [DisplayOutput::Summary] mode.
+    pub fn finish(mut self) -> io::Result<()> {
+        let hidden = self.hidden_lines + usize::from(self.hidden_partial_line);
+        if self.mode == DisplayOutput::Summary && hidden > 0 {
+            queue!(
+                self.inner,
+                style::ResetColor,
+                style::Print("\n"),
+                style::SetForegroundColor(Color::DarkGrey),
+                style::Print(format!(
+                    "   ... {} more line{} hidden\n",
+                    hidden,
+                    if hidden == 1 { "" } else { "s" }
+                )),
+                style::SetForegroundColor(Color::Reset),
+            )?;
+        }
+        self.inner.flush()
+    }
+}
+
+impl<W: Write> Write for DisplayWriter<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        match self.mode {
+            DisplayOutput::Full => return self.inner.write(buf),
+            DisplayOutput::Silent => self.hide(buf),
+            DisplayOutput::Summary => {
+                let mut shown = 0;
+                while shown < buf.len() && self.shown_lines < SUMMARY_LINES {
+                    match buf[shown..].iter().position(|b| *b == b'\n') {
+                        Some(end) => {
+                            shown += end + 1;
+                            self.shown_lines += 1;
+                        },
+                        None => shown = buf.len(),
+                    }
+                }
+                self.inner.write_all(&buf[..shown])?;
+                self.hide(&buf[shown..]);
+            },
+        }
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+    use crate::cli::agent::ToolSettingTarget;
+
+    fn render(mode: DisplayOutput, chunks: &[&str]) -> String {
+        let mut output = Vec::new();
+        let mut writer = DisplayWriter::new(&mut output, mode);
+        for chunk in chunks {
+            writer.write_all(chunk.as_bytes()).unwrap();
+        }
+        writer.finish().unwrap();
+        String::from_utf8(strip_ansi_escapes::strip(output)).unwrap()
+    }
+
+    #[test]
+    fn test_display_writer() {
+        let lines = (1..=15).map(|i| format!("line {i}\n")).collect::<String>();
+
+        assert_eq!(render(DisplayOutput::Full, &[&lines]), lines);
+        assert_eq!(render(DisplayOutput::Silent, &[&lines]), "");
+
+        let first_lines = (1..=SUMMARY_LINES).map(|i| format!("line {i}\n")).collect::<String>();
+        let expected = format!("{first_lines}\n   ... 5 more lines hidden\n");
+        assert_eq!(render(DisplayOutput::Summary, &[&lines]), expected);
+        // Lines split across writes are counted once
+        let (start, end) = lines.split_at(lines.len() / 2 + 3);
+        assert_eq!(render(DisplayOutput::Summary, &[start, end]), expected);
+        assert_eq!(
+            render(DisplayOutput::Summary, &[&lines, "partial"]),
+            format!("{first_lines}\n   ... 6 more lines hidden\n")
+        );
+
+        // Nothing is added when the output is short enough
+        assert_eq!(render(DisplayOutput::Summary, &["short\n"]), "short\n");
+    }
+
+    #[test]
+    fn test_display_output_from_settings() {
+        let mut agent = Agent::default();
+        for (tool_name, mode) in [
+            ("fs_read", "summary"),
+            ("@git/git_log", "Silent"),
+            ("execute_bash", "unknown"),
+        ] {
+            agent.tools_settings.insert(
+                ToolSettingTarget(tool_name.to_string()),
+                json!({ "displayOutput": mode }),
+            );
+        }
+
+        assert_eq!(
+            DisplayOutput::from_settings(Some(&agent), "fs_read"),
+            DisplayOutput::Summary
+        );
+        assert_eq!(
+            DisplayOutput::from_settings(Some(&agent), "@git/git_log"),
+            DisplayOutput::Silent
+        );
+        assert_eq!(
+            DisplayOutput::from_settings(Some(&agent), "execute_bash"),
+            DisplayOutput::Full
+        );
+        assert_eq!(DisplayOutput::from_settings(None, "fs_read"), DisplayOutput::Full);
+    }
+}
@@ -1,4 +1,8 @@
-use std::io::Write;
+use std::borrow::Cow;
+use std::io::{
+    IsTerminal,
+    Write,
+};
 
 use crossterm::style::{
     Color,
@@ -14,7 +18,139 @@ use crossterm::{
     style,
 };
 use eyre::Result;
+use spinners::{
+    Spinner,
+    Spinners,
+};
 use strip_ansi_escapes::strip_str;
+use unicode_width::{
+    UnicodeWidthChar,
+    UnicodeWidthStr,
+};
+
+use crate::os::Os;
+
+/// Environment variable that turns off wrapping and truncating long lines to the terminal width.
+pub const DISABLE_WRAP_ENV_VAR: &str = "Q_DISABLE_WRAP";
+
+/// Width assumed when the size of the terminal cannot be queried.
+const FALLBACK_TERMINAL_WIDTH: usize = 80;
+
+/// Returns the number of columns that long lines should be wrapped or truncated at, or [None] if
+/// stdout is not a terminal or this is turned off with [DISABLE_WRAP_ENV_VAR].
+pub fn wrap_width(os: &Os) -> Option<usize> {
+    if !std::io::stdout().is_terminal() || os.env.get(DISABLE_WRAP_ENV_VAR).is_ok_and(|s| !s.is_empty()) {
+        return None;
+    }
+    match terminal::size() {
+        Ok((columns, _)) if columns > 0 => Some(columns.into()),
+        _ => Some(FALLBACK_TERMINAL_WIDTH),
+    }
+}
+
+/// Starts a spinner showing `message`, or returns [None] when `plain` is set, since spinners are
+/// not shown in plain output mode (see [plain_output](crate::cli::chat::tools::plain_output)).
+pub fn start_spinner(plain: bool, spinner: Spinners, message: String) -> Option<Spinner> {
+    (!plain).then(|| Spinner::new(spinner, message))
+}
+
+/// Returns `glyph`, such as an emoji, or the ASCII `plain_text` shown in its place when `plain` is
+/// set (see [plain_output](crate::cli::chat::tools::plain_output)).
+pub fn glyph<'a>(plain: bool, glyph: &'a str, plain_text: &'a str) -> &'a str {
+    if plain { plain_text } else { glyph }
+}
+
+/// Splits `line` into lines that each take at most `width` columns. ANSI escape codes take no
+/// space, and the ones seen so far are repeated at the start of every continuation line so that
+/// colors carry over even when the continuation is printed after something else.
+pub fn wrap_line(line: &str, width: usize) -> Vec<String> {
+    let width = width.max(1);
+    let mut lines = vec![String::new()];
+    let mut escape_codes = String::new();
+    let mut column = 0;
+    let mut rest = line;
+    while let Some(c) = rest.chars().next() {
+        let len = match c {
+            '\x1b' => escape_code_len(rest),
+            _ => c.len_utf8(),
+        };
+        let (token, remaining) = rest.split_at(len);
+        rest = remaining;
+
+        let current = lines.last_mut().expect("lines is never empty");
+        if c == '\x1b' {
+            escape_codes.push_str(token);
+            current.push_str(token);
+            continue;
+        }
+        let char_width = c.width().unwrap_or(0);
+        if column + char_width > width && column > 0 {
+            lines.push(format!("{escape_codes}{token}"));
+            column = char_width;
+        } else {
+            current.push_str(token);
+            column += char_width;
+        }
+    }
+    lines
+}
+
+/// Length in bytes of the escape code at the start of `text`. Only control sequences, such as
+/// colors, are recognized in full, other escape codes are assumed to be two bytes long.
+fn escape_code_len(text: &str) -> usize {
+    if !text.starts_with("\x1b[") {
+        return text.chars().take(2).map(char::len_utf8).sum();
+    }
+    text.char_indices()
+        .skip(2)
+        .find(|(_, c)| ('\x40'..='\x7e').contains(c))
+        .map_or(text.len(), |(i, _)| i + 1)
+}
+
+/// Shortens `path` so that it fits in the terminal after `prefix`, see
+/// [truncate_path_with_ellipsis].
+pub fn fit_path_to_terminal<'a>(os: &Os, prefix: &str, path: &'a str) -> Cow<'a, str> {
+    match wrap_width(os) {
+        Some(width) => truncate_path_with_ellipsis(path, width.saturating_sub(prefix.width())),
+        None => Cow::Borrowed(path),
+    }
+}
+
+/// Shortens `text` to at most `width` columns by replacing its end with an ellipsis.
+pub fn truncate_with_ellipsis(text: &str, width: usize) -> Cow<'_, str> {
+    if text.width() <= width {
+        return Cow::Borrowed(text);
+    }
+    let mut truncated = String::new();
+    let mut column = 0;
+    for c in text.chars() {
+        column += c.width().unwrap_or(0);
+        if column + 1 > width {
+            break;
+        }
+        truncated.push(c);
+    }
+    truncated.push('…');
+    Cow::Owned(truncated)
+}
+
+/// Shortens `path` to at most `width` columns by replacing its start with an ellipsis, so that
+/// the file name stays visible.
+pub fn truncate_path_with_ellipsis(path: &str, width: usize) -> Cow<'_, str> {
+    if path.width() <= width {
+        return Cow::Borrowed(path);
+    }
+    let mut column = 0;
+    let start = path
+        .char_indices()
+        .rev()
+        .find(|(_, c)| {
+            column += c.width().unwrap_or(0);
+            column + 1 > width
+        })
+        .map_or(0, |(i, c)| i + c.len_utf8());
+    Cow::Owned(format!("…{}", &path[start..]))
+}
 
 pub fn draw_box(
     output: &mut impl Write,
@@ -122,6 +258,52 @@ mod tests {
     use super::*;
     use crate::cli::chat::GREETING_BREAK_POINT;
 
+    #[test]
+    fn test_wrap_line() {
+        let command = "aws s3 cp s3://my-bucket/some/deeply/nested/prefix/archive.tar.gz ./downloads/archive.tar.gz \
+                       --recursive --exclude '*.tmp' --include '*.tar.gz' --region us-west-2 --profile production";
+        for width in [40, 80, 200] {
+            let lines = wrap_line(command, width);
+            assert!(lines.iter().all(|line| line.width() <= width), "{width}: {lines:?}");
+            assert_eq!(lines.concat(), command);
+            assert_eq!(lines.len(), command.width().div_ceil(width));
+        }
+
+        // Escape codes take no space, and are repeated on the continuation lines.
+        let green = "\x1b[38;5;10m";
+        let line = format!("{green}{}\x1b[0m", "x".repeat(60));
+        assert_eq!(wrap_line(&line, 80), vec![line.clone()]);
+        assert_eq!(wrap_line(&line, 40), vec![
+            format!("{green}{}", "x".repeat(40)),
+            format!("{green}{}\x1b[0m", "x".repeat(20)),
+        ]);
+        assert_eq!(wrap_line(&line, 200), vec![line.clone()]);
+
+        // Wide characters are not split across lines.
+        assert_eq!(wrap_line("日本語", 4), vec!["日本".to_string(), "語".to_string()]);
+    }
+
+    #[test]
+    fn test_truncate_with_ellipsis() {
+        let text = "a".repeat(100);
+        assert_eq!(truncate_with_ellipsis(&text, 40), format!("{}…", "a".repeat(39)));
+        assert_eq!(truncate_with_ellipsis(&text, 80), format!("{}…", "a".repeat(79)));
+        assert_eq!(truncate_with_ellipsis(&text, 200), text);
+        assert_eq!(truncate_with_ellipsis("日本語", 4), "日…");
+
+        let path = "/home/user/projects/some-project/crates/some-crate/src/deeply/nested/module/file.rs";
+        assert_eq!(
+            truncate_path_with_ellipsis(path, 40),
+            "…-crate/src/deeply/nested/module/file.rs"
+        );
+        assert_eq!(truncate_path_with_ellipsis(path, 40).width(), 40);
+        assert_eq!(
+            truncate_path_with_ellipsis(path, 80),
+            format!("…{}", &path[path.len() - 79..])
+        );
+        assert_eq!(truncate_path_with_ellipsis(path, 200), path);
+    }
+
     #[tokio::test]
     async fn test_draw_tip_box() {
         let mut output = vec![];
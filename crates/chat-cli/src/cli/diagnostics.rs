@@ -15,12 +15,11 @@ use crossterm::{
     cursor,
     execute,
 };
-use spinners::{
-    Spinner,
-    Spinners,
-};
+use spinners::Spinners;
 
 use super::OutputFormat;
+use crate::cli::chat::tools::plain_output;
+use crate::cli::chat::util::ui::start_spinner;
 use crate::os::Os;
 use crate::os::diagnostics::Diagnostics;
 
@@ -37,7 +36,7 @@ pub struct DiagnosticArgs {
 impl DiagnosticArgs {
     pub async fn execute(&self, os: &Os) -> Result<ExitCode> {
         let spinner = if stdout().is_terminal() {
-            Some(Spinner::new(Spinners::Dots, "Generating...".into()))
+            start_spinner(plain_output(os), Spinners::Dots, "Generating...".into())
         } else {
             None
         };
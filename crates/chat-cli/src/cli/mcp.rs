@@ -5,6 +5,10 @@ use std::collections::{
 use std::io::Write;
 use std::path::PathBuf;
 use std::process::ExitCode;
+use std::time::{
+    Duration,
+    Instant,
+};
 
 use clap::{
     ArgAction,
@@ -19,6 +23,7 @@ use crossterm::{
 use eyre::{
     Result,
     bail,
+    eyre,
 };
 
 use super::agent::{
@@ -29,12 +34,18 @@ use super::agent::{
 };
 use crate::cli::chat::tool_manager::{
     global_mcp_config_path,
+    queue_failure_message,
+    queue_success_message,
     workspace_mcp_config_path,
 };
 use crate::cli::chat::tools::custom_tool::{
+    CustomToolClient,
     CustomToolConfig,
     default_timeout,
 };
+use crate::cli::chat::tools::plain_output;
+use crate::cli::chat::util::ui::glyph;
+use crate::mcp_client::ToolsListResult;
 use crate::os::Os;
 use crate::util::directories;
 
@@ -68,6 +79,8 @@ pub enum McpSubcommand {
     Import(ImportArgs),
     /// Get the status of a configured server
     Status(StatusArgs),
+    /// Launch a single server and list the tools it offers, without starting a chat session
+    Test(TestArgs),
 }
 
 impl McpSubcommand {
@@ -78,6 +91,11 @@ impl McpSubcommand {
             Self::List(args) => args.execute(os, output).await?,
             Self::Import(args) => args.execute(os, output).await?,
             Self::Status(args) => args.execute(os, output).await?,
+            Self::Test(args) => {
+                let exit_code = args.execute(os, output).await?;
+                output.flush()?;
+                return Ok(exit_code);
+            },
         }
 
         output.flush()?;
@@ -145,7 +163,13 @@ impl AddArgs {
                 mcp_servers.insert(self.name.clone(), tool);
                 let json = agent.to_str_pretty()?;
                 os.fs.write(config_path, json).await?;
-                writeln!(output, "✓ Added MCP server '{}' to agent {}\n", self.name, agent_name)?;
+                writeln!(
+                    output,
+                    "{}Added MCP server '{}' to agent {}\n",
+                    glyph(plain_output(os), "✓ ", ""),
+                    self.name,
+                    agent_name
+                )?;
             },
             None => {
                 let legacy_mcp_config_path = match self.scope {
@@ -179,7 +203,8 @@ impl AddArgs {
                 mcp_servers.save_to_file(os, &legacy_mcp_config_path).await?;
                 writeln!(
                     output,
-                    "✓ Added MCP server '{}' to global config in {}\n",
+                    "{}Added MCP server '{}' to global config in {}\n",
+                    glyph(plain_output(os), "✓ ", ""),
                     self.name,
                     legacy_mcp_config_path.display()
                 )?;
@@ -220,8 +245,10 @@ impl RemoveArgs {
                         os.fs.write(config_path, json).await?;
                         writeln!(
                             output,
-                            "\n✓ Removed MCP server '{}' from agent {}\n",
-                            self.name, agent_name,
+                            "\n{}Removed MCP server '{}' from agent {}\n",
+                            glyph(plain_output(os), "✓ ", ""),
+                            self.name,
+                            agent_name,
                         )?;
                     },
                     None => {
@@ -245,7 +272,8 @@ impl RemoveArgs {
                         config.save_to_file(os, &legacy_mcp_config_path).await?;
                         writeln!(
                             output,
-                            "\n✓ Removed MCP server '{}' from global config (path {})\n",
+                            "\n{}Removed MCP server '{}' from global config (path {})\n",
+                            glyph(plain_output(os), "✓ ", ""),
                             self.name,
                             &legacy_mcp_config_path.display(),
                         )?;
@@ -288,7 +316,7 @@ impl ListArgs {
                 }
             }
             writeln!(output)?;
-            writeln!(output, "{}:\n", scope_display(&scope))?;
+            writeln!(output, "{}:\n", scope_display(&scope, plain_output(os)))?;
             for (agent_name, cfg_opt, _) in agents {
                 writeln!(output, "  {}", agent_name.bold())?;
                 match cfg_opt {
@@ -300,7 +328,7 @@ impl ListArgs {
                         servers.sort_by(|a, b| a.0.cmp(&b.0));
                         for (name, tool_cfg) in &servers {
                             let status = if tool_cfg.disabled { " (disabled)" } else { "" };
-                            writeln!(output, "    • {name:<12} {}{}", tool_cfg.command, status)?;
+                            writeln!(output, "    • {name:<12} {}{}", tool_cfg.display_target(), status)?;
                         }
                     },
                     _ => {
@@ -357,8 +385,9 @@ impl ImportArgs {
         dst_cfg.save_to_file(os, &config_path).await?;
         writeln!(
             output,
-            "✓ Imported {added} MCP server(s) into {}\n",
-            scope_display(&scope)
+            "{}Imported {added} MCP server(s) into {}\n",
+            glyph(plain_output(os), "✓ ", ""),
+            scope_display(&scope, plain_output(os))
         )?;
         Ok(())
     }
@@ -382,9 +411,12 @@ impl StatusArgs {
                     execute!(
                         output,
                         style::Print("\n─────────────\n"),
-                        style::Print(format!("Scope   : {}\n", scope_display(&sc))),
+                        style::Print(format!("Scope   : {}\n", scope_display(&sc, plain_output(os)))),
                         style::Print(format!("Agent   : {}\n", name)),
-                        style::Print(format!("Command : {}\n", cfg.command)),
+                        style::Print(match &cfg.url {
+                            Some(_) => format!("Url     : {}\n", cfg.display_target()),
+                            None => format!("Command : {}\n", cfg.command),
+                        }),
                         style::Print(format!("Timeout : {} ms\n", cfg.timeout)),
                         style::Print(format!("Disabled: {}\n", cfg.disabled)),
                         style::Print(format!(
@@ -412,6 +444,138 @@ impl StatusArgs {
     }
 }
 
+#[derive(Debug, Clone, PartialEq, Eq, Args)]
+pub struct TestArgs {
+    /// Name of a configured server, a path to a JSON file with the server config, or the config
+    /// itself as JSON. A file may also contain a single server under "mcpServers"
+    pub server: String,
+    /// Time to wait for the server to initialize and list its tools, in milliseconds. Defaults to
+    /// the timeout of the server config
+    #[arg(long)]
+    pub timeout: Option<u64>,
+}
+
+impl TestArgs {
+    pub async fn execute(self, os: &mut Os, output: &mut impl Write) -> Result<ExitCode> {
+        let (name, config) = resolve_test_server(os, &self.server).await?;
+        let timeout = Duration::from_millis(self.timeout.unwrap_or(config.timeout));
+
+        writeln!(output, "Testing MCP server '{}' ({})", name, config.display_target())?;
+        let start = Instant::now();
+        let result = match CustomToolClient::from_config(name.clone(), config, os) {
+            Ok(client) => {
+                let result = tokio::time::timeout(timeout, list_server_tools(&client))
+                    .await
+                    .unwrap_or_else(|_| Err(eyre!("Timed out after {} ms", timeout.as_millis())));
+                // Dropping the client terminates the server process
+                drop(client);
+                result
+            },
+            Err(err) => Err(err),
+        };
+        let time_taken = format!("{:.2}", start.elapsed().as_secs_f64());
+
+        match result {
+            Ok(tools) => {
+                queue_success_message(&name, &time_taken, plain_output(os), output)?;
+                writeln!(output, "Discovered {} tool(s):", tools.len())?;
+                for (tool_name, description) in tools {
+                    execute!(
+                        output,
+                        style::Print(format!("  - {tool_name}")),
+                        style::SetForegroundColor(style::Color::DarkGrey),
+                        style::Print(match description {
+                            Some(description) => format!(": {description}\n"),
+                            None => "\n".to_string(),
+                        }),
+                        style::ResetColor,
+                    )?;
+                }
+                Ok(ExitCode::SUCCESS)
+            },
+            Err(err) => {
+                queue_failure_message(&name, &err, &time_taken, plain_output(os), output)?;
+                Ok(ExitCode::FAILURE)
+            },
+        }
+    }
+}
+
+/// Resolves the server to test from inline JSON, a config file or the name of a server
+/// configured in one of the agents.
+async fn resolve_test_server(os: &mut Os, server: &str) -> Result<(String, CustomToolConfig)> {
+    if server.trim_start().starts_with('{') {
+        let value = serde_json::from_str::<serde_json::Value>(server)?;
+        return parse_test_server_config(value, "test");
+    }
+
+    let path = expand_path(os, server)?;
+    if os.fs.exists(&path) {
+        let value = serde_json::from_slice::<serde_json::Value>(&os.fs.read(&path).await?)?;
+        let default_name = path.file_stem().map_or("test".into(), |stem| stem.to_string_lossy());
+        return parse_test_server_config(value, &default_name);
+    }
+
+    for agents in get_mcp_server_configs(os).await?.into_values() {
+        for (_, config, _) in agents {
+            if let Some(config) = config.and_then(|c| c.mcp_servers.get(server).cloned()) {
+                return Ok((server.to_string(), config));
+            }
+        }
+    }
+
+    bail!("'{server}' is neither a config file nor the name of a configured MCP server")
+}
+
+/// Parses either a single server config, or a config with exactly one server under "mcpServers".
+fn parse_test_server_config(value: serde_json::Value, default_name: &str) -> Result<(String, CustomToolConfig)> {
+    let Some(servers) = value.get("mcpServers") else {
+        return Ok((default_name.to_string(), serde_json::from_value(value)?));
+    };
+
+    let servers = serde_json::from_value::<HashMap<String, CustomToolConfig>>(servers.clone())?;
+    if servers.len() != 1 {
+        let mut names = servers.into_keys().collect::<Vec<_>>();
+        names.sort();
+        bail!(
+            "The config must contain exactly one server to test, found {}: {}",
+            names.len(),
+            names.join(", ")
+        );
+    }
+    Ok(servers.into_iter().next().expect("config has one server"))
+}
+
+/// Initializes the server and returns the name and description of each of its tools.
+async fn list_server_tools(client: &CustomToolClient) -> Result<Vec<(String, Option<String>)>> {
+    client.init().await?;
+
+    let resp = client.request("tools/list", None).await?;
+    if let Some(error) = resp.error {
+        bail!("Failed to retrieve tool list: {:?}", error);
+    }
+    let Some(result) = resp.result else {
+        bail!("Tool list response is missing result");
+    };
+
+    serde_json::from_value::<ToolsListResult>(result)?
+        .tools
+        .into_iter()
+        .map(|tool| {
+            let name = tool
+                .get("name")
+                .and_then(|name| name.as_str())
+                .ok_or(eyre!("Tool is missing a name: {tool}"))?;
+            let description = tool
+                .get("description")
+                .and_then(|description| description.as_str())
+                .and_then(|description| description.lines().next())
+                .map(str::to_string);
+            Ok((name.to_string(), description))
+        })
+        .collect()
+}
+
 /// Returns a [BTreeMap] for consistent key iteration.
 async fn get_mcp_server_configs(os: &mut Os) -> Result<BTreeMap<Scope, Vec<(String, Option<McpServerConfig>, bool)>>> {
     let mut results = BTreeMap::new();
@@ -423,7 +587,7 @@ async fn get_mcp_server_configs(os: &mut Os) -> Result<BTreeMap<Scope, Vec<(Stri
             true
         },
     };
-    let agents = Agents::load(os, None, true, &mut stderr, mcp_enabled).await.0;
+    let agents = Agents::load(os, None, true, false, &mut stderr, mcp_enabled).await.0;
     let global_path = directories::chat_global_agent_path(os)?;
     for (_, agent) in agents.agents {
         let scope = if agent
@@ -451,11 +615,11 @@ async fn get_mcp_server_configs(os: &mut Os) -> Result<BTreeMap<Scope, Vec<(Stri
     Ok(results)
 }
 
-fn scope_display(scope: &Scope) -> String {
+fn scope_display(scope: &Scope, plain: bool) -> String {
     match scope {
-        Scope::Default => "🤖 default".into(),
-        Scope::Workspace => "📄 workspace".into(),
-        Scope::Global => "🌍 global".into(),
+        Scope::Default => format!("{}default", glyph(plain, "🤖 ", "")),
+        Scope::Workspace => format!("{}workspace", glyph(plain, "📄 ", "")),
+        Scope::Global => format!("{}global", glyph(plain, "🌍 ", "")),
     }
 }
 
@@ -481,7 +645,12 @@ async fn ensure_config_file(os: &Os, path: &PathBuf, output: &mut impl Write) ->
             os.fs.create_dir_all(parent).await?;
         }
         McpServerConfig::default().save_to_file(os, path).await?;
-        writeln!(output, "\n📁 Created MCP config in '{}'", path.display())?;
+        writeln!(
+            output,
+            "\n{}Created MCP config in '{}'",
+            glyph(plain_output(os), "📁 ", ""),
+            path.display()
+        )?;
     }
 
     load_cfg(os, path).await
@@ -680,4 +849,48 @@ mod tests {
             }))
         );
     }
+
+    #[test]
+    fn test_mcp_subcommand_test() {
+        assert_parse!(
+            ["mcp", "test", "git", "--timeout", "5000"],
+            RootSubcommand::Mcp(McpSubcommand::Test(TestArgs {
+                server: "git".into(),
+                timeout: Some(5000),
+            }))
+        );
+    }
+
+    #[tokio::test]
+    async fn test_resolve_test_server() {
+        let mut os = Os::new().await.unwrap();
+
+        let (name, config) = resolve_test_server(&mut os, r#"{"command": "git-mcp", "args": ["--repo", "."]}"#)
+            .await
+            .unwrap();
+        assert_eq!(name, "test");
+        assert_eq!(config.command, "git-mcp");
+        assert_eq!(config.args, vec!["--repo", "."]);
+
+        os.fs
+            .write(
+                "/servers.json",
+                r#"{"mcpServers": {"fetch": {"command": "uvx", "args": ["mcp-server-fetch"]}}}"#,
+            )
+            .await
+            .unwrap();
+        let (name, config) = resolve_test_server(&mut os, "/servers.json").await.unwrap();
+        assert_eq!(name, "fetch");
+        assert_eq!(config.command, "uvx");
+
+        os.fs
+            .write(
+                "/two.json",
+                r#"{"mcpServers": {"a": {"command": "a"}, "b": {"command": "b"}}}"#,
+            )
+            .await
+            .unwrap();
+        assert!(resolve_test_server(&mut os, "/two.json").await.is_err());
+        assert!(resolve_test_server(&mut os, "not_configured").await.is_err());
+    }
 }
@@ -13,6 +13,7 @@ use std::io::{
     Write as _,
     stdout,
 };
+use std::path::PathBuf;
 use std::process::ExitCode;
 
 use agent::AgentArgs;
@@ -21,8 +22,20 @@ pub use agent::{
     DEFAULT_AGENT_NAME,
 };
 use anstream::println;
-pub use chat::ConversationState;
+pub use chat::engine::{
+    ChatEngine,
+    ChatEvent,
+    EventSink,
+    ToolApprover,
+    ToolDecision,
+    ToolRequest,
+};
 pub use chat::tools::todo::TodoListState;
+pub use chat::{
+    ChatError,
+    ChatErrorKind,
+    ConversationState,
+};
 use clap::{
     ArgAction,
     CommandFactory,
@@ -42,7 +55,10 @@ use tracing::{
     debug,
 };
 
-use crate::cli::chat::ChatArgs;
+use crate::cli::chat::{
+    CHAT_EXIT_CODES_HELP,
+    ChatArgs,
+};
 use crate::cli::mcp::McpSubcommand;
 use crate::cli::user::{
     LoginArgs,
@@ -93,6 +109,7 @@ pub enum RootSubcommand {
     /// Manage agents
     Agent(AgentArgs),
     /// AI assistant in your terminal
+    #[command(after_help = CHAT_EXIT_CODES_HELP)]
     Chat(ChatArgs),
     /// Log in to Amazon Q
     Login(LoginArgs),
@@ -205,6 +222,10 @@ pub struct Cli {
     /// Print help for all subcommands
     #[arg(long)]
     help_all: bool,
+    /// Load agents from this directory before the workspace and global agent directories. Can be
+    /// repeated, and is searched before the directories in QCLI_AGENT_PATH
+    #[arg(long = "agent-dir", value_name = "PATH", global = true)]
+    pub agent_dirs: Vec<PathBuf>,
 }
 
 impl Cli {
@@ -243,6 +264,11 @@ impl Cli {
         debug!(command =? std::env::args().collect::<Vec<_>>(), "Command being ran");
 
         let mut os = Os::new().await?;
+        os.agent_dirs = self
+            .agent_dirs
+            .iter()
+            .map(std::path::absolute)
+            .collect::<Result<_, _>>()?;
         let result = subcommand.execute(&mut os).await;
 
         let telemetry_result = os.telemetry.finish().await;
@@ -332,6 +358,7 @@ impl Cli {
 #[cfg(test)]
 mod test {
     use super::*;
+    use crate::cli::chat::OutputFormat;
     use crate::util::CHAT_BINARY_NAME;
     use crate::util::test::assert_parse;
 
@@ -347,35 +374,65 @@ mod test {
             subcommand: None,
             verbose: 1,
             help_all: false,
+            agent_dirs: vec![],
         });
 
         assert_eq!(Cli::parse_from([CHAT_BINARY_NAME, "-vvv"]), Cli {
             subcommand: None,
             verbose: 3,
             help_all: false,
+            agent_dirs: vec![],
         });
 
         assert_eq!(Cli::parse_from([CHAT_BINARY_NAME, "--help-all"]), Cli {
             subcommand: None,
             verbose: 0,
             help_all: true,
+            agent_dirs: vec![],
         });
 
         assert_eq!(Cli::parse_from([CHAT_BINARY_NAME, "chat", "-vv"]), Cli {
             subcommand: Some(RootSubcommand::Chat(ChatArgs {
                 resume: false,
+                resume_session: None,
                 input: None,
                 agent: None,
+                agent_from_stdin: false,
                 model: None,
                 trust_all_tools: false,
                 trust_tools: None,
+                no_tools: false,
                 no_interactive: false,
+                prompt: None,
+                output: OutputFormat::Plain,
+                files: vec![],
+                vars: vec![],
+                event_log: None,
+                event_log_include_text: false,
             })),
             verbose: 2,
             help_all: false,
+            agent_dirs: vec![],
         });
     }
 
+    #[test]
+    fn test_agent_dir_flag() {
+        let cli = Cli::parse_from([
+            CHAT_BINARY_NAME,
+            "--agent-dir",
+            "/etc/qcli/agents",
+            "chat",
+            "--agent-dir",
+            "./agents",
+        ]);
+        assert_eq!(cli.agent_dirs, vec![
+            PathBuf::from("/etc/qcli/agents"),
+            PathBuf::from("./agents")
+        ]);
+        assert!(matches!(cli.subcommand, Some(RootSubcommand::Chat(_))));
+    }
+
     #[test]
     fn test_version_changelog() {
         assert_parse!(["version", "--changelog"], RootSubcommand::Version {
@@ -403,12 +460,21 @@ mod test {
             ["chat", "--profile", "my-profile"],
             RootSubcommand::Chat(ChatArgs {
                 resume: false,
+                resume_session: None,
                 input: None,
                 agent: Some("my-profile".to_string()),
+                agent_from_stdin: false,
                 model: None,
                 trust_all_tools: false,
                 trust_tools: None,
+                no_tools: false,
                 no_interactive: false,
+                prompt: None,
+                output: OutputFormat::Plain,
+                files: vec![],
+                vars: vec![],
+                event_log: None,
+                event_log_include_text: false,
             })
         );
     }
@@ -419,12 +485,21 @@ mod test {
             ["chat", "--profile", "my-profile", "Hello"],
             RootSubcommand::Chat(ChatArgs {
                 resume: false,
+                resume_session: None,
                 input: Some("Hello".to_string()),
                 agent: Some("my-profile".to_string()),
+                agent_from_stdin: false,
                 model: None,
                 trust_all_tools: false,
                 trust_tools: None,
+                no_tools: false,
                 no_interactive: false,
+                prompt: None,
+                output: OutputFormat::Plain,
+                files: vec![],
+                vars: vec![],
+                event_log: None,
+                event_log_include_text: false,
             })
         );
     }
@@ -435,12 +510,21 @@ mod test {
             ["chat", "--profile", "my-profile", "--trust-all-tools"],
             RootSubcommand::Chat(ChatArgs {
                 resume: false,
+                resume_session: None,
                 input: None,
                 agent: Some("my-profile".to_string()),
+                agent_from_stdin: false,
                 model: None,
                 trust_all_tools: true,
                 trust_tools: None,
+                no_tools: false,
                 no_interactive: false,
+                prompt: None,
+                output: OutputFormat::Plain,
+                files: vec![],
+                vars: vec![],
+                event_log: None,
+                event_log_include_text: false,
             })
         );
     }
@@ -451,24 +535,89 @@ mod test {
             ["chat", "--no-interactive", "--resume"],
             RootSubcommand::Chat(ChatArgs {
                 resume: true,
+                resume_session: None,
                 input: None,
                 agent: None,
+                agent_from_stdin: false,
                 model: None,
                 trust_all_tools: false,
                 trust_tools: None,
+                no_tools: false,
                 no_interactive: true,
+                prompt: None,
+                output: OutputFormat::Plain,
+                files: vec![],
+                vars: vec![],
+                event_log: None,
+                event_log_include_text: false,
             })
         );
         assert_parse!(
             ["chat", "--non-interactive", "-r"],
             RootSubcommand::Chat(ChatArgs {
                 resume: true,
+                resume_session: None,
                 input: None,
                 agent: None,
+                agent_from_stdin: false,
                 model: None,
                 trust_all_tools: false,
                 trust_tools: None,
+                no_tools: false,
                 no_interactive: true,
+                prompt: None,
+                output: OutputFormat::Plain,
+                files: vec![],
+                vars: vec![],
+                event_log: None,
+                event_log_include_text: false,
+            })
+        );
+    }
+
+    #[test]
+    fn test_chat_resume_paused_session() {
+        assert_parse!(
+            ["chat", "--resume-session", "20250101-120000-3fa2c1d8"],
+            RootSubcommand::Chat(ChatArgs {
+                resume: false,
+                resume_session: Some("20250101-120000-3fa2c1d8".to_string()),
+                input: None,
+                agent: None,
+                agent_from_stdin: false,
+                model: None,
+                trust_all_tools: false,
+                trust_tools: None,
+                no_tools: false,
+                no_interactive: false,
+                prompt: None,
+                output: OutputFormat::Plain,
+                files: vec![],
+                vars: vec![],
+                event_log: None,
+                event_log_include_text: false,
+            })
+        );
+        // A question after --resume is not taken for a session id
+        assert_parse!(
+            ["chat", "-r", "what was I doing?"],
+            RootSubcommand::Chat(ChatArgs {
+                resume: true,
+                resume_session: None,
+                input: Some("what was I doing?".to_string()),
+                agent: None,
+                agent_from_stdin: false,
+                model: None,
+                trust_all_tools: false,
+                trust_tools: None,
+                no_tools: false,
+                no_interactive: false,
+                prompt: None,
+                output: OutputFormat::Plain,
+                files: vec![],
+                vars: vec![],
+                event_log: None,
+                event_log_include_text: false,
             })
         );
     }
@@ -479,12 +628,21 @@ mod test {
             ["chat", "--trust-all-tools"],
             RootSubcommand::Chat(ChatArgs {
                 resume: false,
+                resume_session: None,
                 input: None,
                 agent: None,
+                agent_from_stdin: false,
                 model: None,
                 trust_all_tools: true,
                 trust_tools: None,
+                no_tools: false,
                 no_interactive: false,
+                prompt: None,
+                output: OutputFormat::Plain,
+                files: vec![],
+                vars: vec![],
+                event_log: None,
+                event_log_include_text: false,
             })
         );
     }
@@ -495,12 +653,21 @@ mod test {
             ["chat", "--trust-tools="],
             RootSubcommand::Chat(ChatArgs {
                 resume: false,
+                resume_session: None,
                 input: None,
                 agent: None,
+                agent_from_stdin: false,
                 model: None,
                 trust_all_tools: false,
                 trust_tools: Some(vec!["".to_string()]),
+                no_tools: false,
                 no_interactive: false,
+                prompt: None,
+                output: OutputFormat::Plain,
+                files: vec![],
+                vars: vec![],
+                event_log: None,
+                event_log_include_text: false,
             })
         );
     }
@@ -511,13 +678,188 @@ mod test {
             ["chat", "--trust-tools=fs_read,fs_write"],
             RootSubcommand::Chat(ChatArgs {
                 resume: false,
+                resume_session: None,
                 input: None,
                 agent: None,
+                agent_from_stdin: false,
                 model: None,
                 trust_all_tools: false,
                 trust_tools: Some(vec!["fs_read".to_string(), "fs_write".to_string()]),
+                no_tools: false,
+                no_interactive: false,
+                prompt: None,
+                output: OutputFormat::Plain,
+                files: vec![],
+                vars: vec![],
+                event_log: None,
+                event_log_include_text: false,
+            })
+        );
+    }
+
+    #[test]
+    fn test_chat_with_no_tools() {
+        assert_parse!(
+            ["chat", "--no-tools"],
+            RootSubcommand::Chat(ChatArgs {
+                resume: false,
+                resume_session: None,
+                input: None,
+                agent: None,
+                agent_from_stdin: false,
+                model: None,
+                trust_all_tools: false,
+                trust_tools: None,
+                no_tools: true,
+                no_interactive: false,
+                prompt: None,
+                output: OutputFormat::Plain,
+                files: vec![],
+                vars: vec![],
+                event_log: None,
+                event_log_include_text: false,
+            })
+        );
+        assert!(Cli::try_parse_from([CHAT_BINARY_NAME, "chat", "--no-tools", "--trust-all-tools"]).is_err());
+    }
+
+    #[test]
+    fn test_chat_with_prompt_and_json_output() {
+        assert_parse!(
+            ["chat", "--prompt", "summarize the changes", "--output", "json"],
+            RootSubcommand::Chat(ChatArgs {
+                resume: false,
+                resume_session: None,
+                input: None,
+                agent: None,
+                agent_from_stdin: false,
+                model: None,
+                trust_all_tools: false,
+                trust_tools: None,
+                no_tools: false,
+                no_interactive: false,
+                prompt: Some("summarize the changes".to_string()),
+                output: OutputFormat::Json,
+                files: vec![],
+                vars: vec![],
+                event_log: None,
+                event_log_include_text: false,
+            })
+        );
+        assert!(Cli::try_parse_from([CHAT_BINARY_NAME, "chat", "--prompt", "a", "b"]).is_err());
+    }
+
+    #[test]
+    fn test_chat_with_agent_from_stdin() {
+        assert_parse!(
+            ["chat", "--agent-from-stdin", "--prompt", "review this diff"],
+            RootSubcommand::Chat(ChatArgs {
+                resume: false,
+                resume_session: None,
+                input: None,
+                agent: None,
+                agent_from_stdin: true,
+                model: None,
+                trust_all_tools: false,
+                trust_tools: None,
+                no_tools: false,
+                no_interactive: false,
+                prompt: Some("review this diff".to_string()),
+                output: OutputFormat::Plain,
+                files: vec![],
+                vars: vec![],
+                event_log: None,
+                event_log_include_text: false,
+            })
+        );
+        assert!(Cli::try_parse_from([CHAT_BINARY_NAME, "chat", "--agent-from-stdin", "--agent=ci"]).is_err());
+    }
+
+    #[test]
+    fn test_chat_with_files() {
+        assert_parse!(
+            [
+                "chat",
+                "--no-interactive",
+                "--file",
+                "a.rs",
+                "--file",
+                "b.rs",
+                "review these"
+            ],
+            RootSubcommand::Chat(ChatArgs {
+                resume: false,
+                resume_session: None,
+                input: Some("review these".to_string()),
+                agent: None,
+                agent_from_stdin: false,
+                model: None,
+                trust_all_tools: false,
+                trust_tools: None,
+                no_tools: false,
+                no_interactive: true,
+                prompt: None,
+                output: OutputFormat::Plain,
+                files: vec![PathBuf::from("a.rs"), PathBuf::from("b.rs")],
+                vars: vec![],
+                event_log: None,
+                event_log_include_text: false,
+            })
+        );
+    }
+
+    #[test]
+    fn test_chat_with_vars() {
+        assert_parse!(
+            ["chat", "--var", "language=rust", "--var", "style=terse=yes"],
+            RootSubcommand::Chat(ChatArgs {
+                resume: false,
+                resume_session: None,
+                input: None,
+                agent: None,
+                agent_from_stdin: false,
+                model: None,
+                trust_all_tools: false,
+                trust_tools: None,
+                no_tools: false,
+                no_interactive: false,
+                prompt: None,
+                output: OutputFormat::Plain,
+                files: vec![],
+                vars: vec![
+                    ("language".to_string(), "rust".to_string()),
+                    ("style".to_string(), "terse=yes".to_string())
+                ],
+                event_log: None,
+                event_log_include_text: false,
+            })
+        );
+        assert!(Cli::try_parse_from([CHAT_BINARY_NAME, "chat", "--var", "language"]).is_err());
+    }
+
+    #[test]
+    fn test_chat_with_event_log() {
+        assert_parse!(
+            ["chat", "--event-log", "events.ndjson", "--event-log-include-text"],
+            RootSubcommand::Chat(ChatArgs {
+                resume: false,
+                resume_session: None,
+                input: None,
+                agent: None,
+                agent_from_stdin: false,
+                model: None,
+                trust_all_tools: false,
+                trust_tools: None,
+                no_tools: false,
                 no_interactive: false,
+                prompt: None,
+                output: OutputFormat::Plain,
+                files: vec![],
+                vars: vec![],
+                event_log: Some(PathBuf::from("events.ndjson")),
+                event_log_include_text: true,
             })
         );
+        assert!(Cli::try_parse_from([CHAT_BINARY_NAME, "chat", "--event-log-include-text"]).is_err());
     }
 }
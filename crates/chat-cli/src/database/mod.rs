@@ -1,7 +1,10 @@
 pub mod settings;
 
 use std::ops::Deref;
-use std::path::Path;
+use std::path::{
+    Path,
+    PathBuf,
+};
 use std::str::FromStr;
 use std::sync::PoisonError;
 
@@ -27,6 +30,7 @@ use serde_json::{
 };
 use settings::Settings;
 use thiserror::Error;
+use time::OffsetDateTime;
 use tracing::{
     error,
     info,
@@ -70,7 +74,8 @@ const MIGRATIONS: &[Migration] = migrations![
     "004_state_table",
     "005_auth_table",
     "006_make_state_blob",
-    "007_conversations_table"
+    "007_conversations_table",
+    "008_saved_conversations_table"
 ];
 
 #[derive(Debug, serde::Deserialize, serde::Serialize)]
@@ -96,6 +101,21 @@ impl From<amzn_codewhisperer_client::types::Profile> for AuthProfile {
     }
 }
 
+/// A conversation saved with `/save`, as listed by `/sessions`.
+#[derive(Clone, Debug, PartialEq, Deserialize, Serialize)]
+pub struct SavedConversation {
+    /// Absolute path of the file the conversation was saved to
+    pub path: PathBuf,
+    pub title: String,
+    /// Directory q chat was running in when the conversation was saved
+    pub workspace: PathBuf,
+    #[serde(with = "time::serde::rfc3339")]
+    pub saved_at: OffsetDateTime,
+    pub message_count: usize,
+    /// Name of the agent that was active
+    pub agent: Option<String>,
+}
+
 #[derive(Clone, PartialEq, Eq, PartialOrd, Ord, serde::Serialize, serde::Deserialize)]
 #[serde(transparent)]
 pub struct Secret(pub String);
@@ -158,6 +178,8 @@ pub enum Table {
     State,
     /// The conversations tables contains user chat conversations.
     Conversations,
+    /// The saved conversations table indexes the conversations saved with `/save`.
+    SavedConversations,
     /// The auth table contains SSO and Builder ID credentials.
     Auth,
 }
@@ -167,6 +189,7 @@ impl std::fmt::Display for Table {
         match self {
             Table::State => write!(f, "state"),
             Table::Conversations => write!(f, "conversations"),
+            Table::SavedConversations => write!(f, "saved_conversations"),
             Table::Auth => write!(f, "auth_kv"),
         }
     }
@@ -355,6 +378,40 @@ impl Database {
         self.set_json_entry(Table::Conversations, path, state)
     }
 
+    /// Get every conversation saved with `/save`, in no particular order.
+    pub fn get_saved_conversations(&self) -> Result<Vec<SavedConversation>, DatabaseError> {
+        Ok(self
+            .all_entries(Table::SavedConversations)?
+            .into_iter()
+            .filter_map(|(key, value)| match serde_json::from_str(value.as_str()?) {
+                Ok(saved) => Some(saved),
+                Err(err) => {
+                    error!(?err, %key, "Invalid saved conversation entry");
+                    None
+                },
+            })
+            .collect())
+    }
+
+    /// Record a conversation saved with `/save`, replacing any previous entry for the same path.
+    pub fn set_saved_conversation(&self, saved: &SavedConversation) -> Result<usize, DatabaseError> {
+        // We would need to encode this to support non utf8 paths.
+        let path = match saved.path.to_str() {
+            Some(path) => path,
+            None => return Ok(0),
+        };
+
+        self.set_json_entry(Table::SavedConversations, path, saved)
+    }
+
+    /// Remove the entry of a saved conversation, e.g. because its file no longer exists.
+    pub fn delete_saved_conversation(&self, path: impl AsRef<Path>) -> Result<(), DatabaseError> {
+        match path.as_ref().to_str() {
+            Some(path) => self.delete_entry(Table::SavedConversations, path),
+            None => Ok(()),
+        }
+    }
+
     pub async fn get_secret(&self, key: &str) -> Result<Option<Secret>, DatabaseError> {
         trace!(key, "getting secret");
         Ok(self.get_entry::<String>(Table::Auth, key)?.map(Into::into))
@@ -587,6 +644,35 @@ mod tests {
         assert!(db.get_entry::<bool>(Table::State, "bool").unwrap().is_some());
     }
 
+    #[tokio::test]
+    async fn saved_conversations_table_tests() {
+        let db = Database::new().await.unwrap();
+        let saved = SavedConversation {
+            path: PathBuf::from("/workspace/debugging.json"),
+            title: "Debugging the release build".to_string(),
+            workspace: PathBuf::from("/workspace"),
+            saved_at: OffsetDateTime::UNIX_EPOCH,
+            message_count: 4,
+            agent: Some("q_cli_default".to_string()),
+        };
+
+        db.set_saved_conversation(&saved).unwrap();
+        db.set_saved_conversation(&SavedConversation {
+            message_count: 6,
+            ..saved.clone()
+        })
+        .unwrap();
+        db.set_entry(Table::SavedConversations, "/workspace/invalid.json", "{}")
+            .unwrap();
+        assert_eq!(db.get_saved_conversations().unwrap(), vec![SavedConversation {
+            message_count: 6,
+            ..saved.clone()
+        }]);
+
+        db.delete_saved_conversation(&saved.path).unwrap();
+        assert!(db.get_saved_conversations().unwrap().is_empty());
+    }
+
     #[tokio::test]
     #[ignore = "not on ci"]
     async fn test_set_password() {
@@ -37,20 +37,32 @@ pub enum Setting {
     KnowledgeChunkSize,
     #[strum(message = "Overlap between text chunks (number)")]
     KnowledgeChunkOverlap,
+    #[strum(message = "How files are split into chunks: fixed, function, or paragraph (string)")]
+    KnowledgeChunkStrategy,
     #[strum(message = "Type of knowledge index to use (string)")]
     KnowledgeIndexType,
+    #[strum(message = "Seconds before an unused knowledge base is unloaded from memory, 0 to disable (number)")]
+    KnowledgeIdleUnloadTimeout,
+    #[strum(message = "Storage backend for the knowledge tool: semanticSearch or sqlite (string)")]
+    KnowledgeBackend,
     #[strum(message = "Key binding for fuzzy search command (single character)")]
     SkimCommandKey,
     #[strum(message = "Enable tangent mode feature (boolean)")]
     EnabledTangentMode,
     #[strum(message = "Key binding for tangent mode toggle (single character)")]
     TangentModeKey,
+    #[strum(message = "Add a summary of the tangent to the main conversation when leaving tangent mode (boolean)")]
+    ChatTangentMergeSummary,
     #[strum(message = "Auto-enter tangent mode for introspect questions (boolean)")]
     IntrospectTangentMode,
     #[strum(message = "Show greeting message on chat start (boolean)")]
     ChatGreetingEnabled,
     #[strum(message = "API request timeout in seconds (number)")]
     ApiTimeout,
+    #[strum(message = "Maximum attempts for throttled chat requests, including the first (number)")]
+    ApiRetryMaxAttempts,
+    #[strum(message = "Maximum total time to wait between retries of a throttled chat request, in seconds (number)")]
+    ApiRetryMaxWaitSeconds,
     #[strum(message = "Enable edit mode for chat interface (boolean)")]
     ChatEditMode,
     #[strum(message = "Enable desktop notifications (boolean)")]
@@ -61,6 +73,10 @@ pub enum Setting {
     ApiQService,
     #[strum(message = "MCP server initialization timeout (number)")]
     McpInitTimeout,
+    #[strum(message = "Maximum number of MCP servers initialized at the same time (number)")]
+    McpInitConcurrency,
+    #[strum(message = "Time to wait for required MCP servers to load, in milliseconds (number)")]
+    McpRequiredInitTimeout,
     #[strum(message = "Non-interactive MCP timeout (number)")]
     McpNoInteractiveTimeout,
     #[strum(message = "Track previously loaded MCP servers (boolean)")]
@@ -73,12 +89,40 @@ pub enum Setting {
     ChatDefaultAgent,
     #[strum(message = "Disable automatic conversation summarization (boolean)")]
     ChatDisableAutoCompaction,
+    #[strum(message = "Context window percentage that triggers summarization before sending (number)")]
+    ChatAutoCompactThreshold,
+    #[strum(message = "Identical tool calls in a row before asking whether to continue, 0 to disable (number)")]
+    ChatToolLoopThreshold,
     #[strum(message = "Show conversation history hints (boolean)")]
     ChatEnableHistoryHints,
     #[strum(message = "Enable the todo list feature (boolean)")]
     EnabledTodoList,
     #[strum(message = "Enable Shift+Enter for newline (boolean)")]
     ChatEnableShiftEnterNewline,
+    #[strum(message = "Minimum risk level flagged before running shell commands: medium, high, or off (string)")]
+    ChatExecuteRiskThreshold,
+    #[strum(message = "How dangerous shell commands are handled even when allowed: ask, strict, or off (string)")]
+    ChatExecuteSafetyPolicy,
+    #[strum(message = "Additional regular expressions for shell commands covered by the safety policy (array)")]
+    ChatExecuteSafetyPolicyCommands,
+    #[strum(message = "Show a summary of turns, tool uses, and tokens when chat exits (boolean)")]
+    ChatShowSessionSummary,
+    #[strum(message = "Save the conversation with a generated title when chat exits, listed by /sessions (boolean)")]
+    ChatSaveOnExit,
+    #[strum(message = "Mask secrets such as access keys and tokens in command output (boolean)")]
+    ChatRedactSecrets,
+    #[strum(message = "Additional regular expressions masked when secrets are redacted (array)")]
+    ChatRedactPatterns,
+    #[strum(message = "Show the estimated context window usage in the prompt (boolean)")]
+    ChatShowContextGauge,
+    #[strum(message = "Context window percentage at which /compact is suggested once (number)")]
+    ChatContextWarningThreshold,
+    #[strum(message = "Model turns a delegated agent may take before its task is returned (number)")]
+    ChatDelegationMaxTurns,
+    #[strum(message = "Use ASCII status markers and no color for MCP loading and file diffs (boolean)")]
+    ChatPlainOutput,
+    #[strum(message = "Only highlight whole lines in file diffs, without emphasizing changed words (boolean)")]
+    ChatDisableWordDiff,
 }
 
 impl AsRef<str> for Setting {
@@ -94,27 +138,49 @@ impl AsRef<str> for Setting {
             Self::KnowledgeMaxFiles => "knowledge.maxFiles",
             Self::KnowledgeChunkSize => "knowledge.chunkSize",
             Self::KnowledgeChunkOverlap => "knowledge.chunkOverlap",
+            Self::KnowledgeChunkStrategy => "knowledge.chunkStrategy",
             Self::KnowledgeIndexType => "knowledge.indexType",
+            Self::KnowledgeIdleUnloadTimeout => "knowledge.idleUnloadTimeout",
+            Self::KnowledgeBackend => "chat.knowledgeBackend",
             Self::SkimCommandKey => "chat.skimCommandKey",
             Self::EnabledTangentMode => "chat.enableTangentMode",
+            Self::ChatTangentMergeSummary => "chat.tangentMergeSummary",
             Self::TangentModeKey => "chat.tangentModeKey",
             Self::IntrospectTangentMode => "introspect.tangentMode",
             Self::ChatGreetingEnabled => "chat.greeting.enabled",
             Self::ApiTimeout => "api.timeout",
+            Self::ApiRetryMaxAttempts => "api.retry.maxAttempts",
+            Self::ApiRetryMaxWaitSeconds => "api.retry.maxWaitSeconds",
             Self::ChatEditMode => "chat.editMode",
             Self::ChatEnableNotifications => "chat.enableNotifications",
             Self::ApiCodeWhispererService => "api.codewhisperer.service",
             Self::ApiQService => "api.q.service",
             Self::McpInitTimeout => "mcp.initTimeout",
+            Self::McpInitConcurrency => "mcp.initConcurrency",
+            Self::McpRequiredInitTimeout => "mcp.requiredInitTimeout",
             Self::McpNoInteractiveTimeout => "mcp.noInteractiveTimeout",
             Self::McpLoadedBefore => "mcp.loadedBefore",
             Self::ChatDefaultModel => "chat.defaultModel",
             Self::ChatDisableMarkdownRendering => "chat.disableMarkdownRendering",
             Self::ChatDefaultAgent => "chat.defaultAgent",
             Self::ChatDisableAutoCompaction => "chat.disableAutoCompaction",
+            Self::ChatAutoCompactThreshold => "chat.autoCompactThreshold",
+            Self::ChatToolLoopThreshold => "chat.toolLoopThreshold",
             Self::ChatEnableHistoryHints => "chat.enableHistoryHints",
             Self::EnabledTodoList => "chat.enableTodoList",
             Self::ChatEnableShiftEnterNewline => "chat.enableShiftEnterNewline",
+            Self::ChatExecuteRiskThreshold => "chat.executeRiskThreshold",
+            Self::ChatExecuteSafetyPolicy => "chat.executeSafetyPolicy",
+            Self::ChatExecuteSafetyPolicyCommands => "chat.executeSafetyPolicyCommands",
+            Self::ChatShowSessionSummary => "chat.showSessionSummary",
+            Self::ChatSaveOnExit => "chat.saveOnExit",
+            Self::ChatRedactSecrets => "chat.redactSecrets",
+            Self::ChatRedactPatterns => "chat.redactPatterns",
+            Self::ChatShowContextGauge => "chat.showContextGauge",
+            Self::ChatContextWarningThreshold => "chat.contextWarningThreshold",
+            Self::ChatDelegationMaxTurns => "chat.delegationMaxTurns",
+            Self::ChatPlainOutput => "chat.plainOutput",
+            Self::ChatDisableWordDiff => "chat.disableWordDiff",
         }
     }
 }
@@ -140,27 +206,49 @@ impl TryFrom<&str> for Setting {
             "knowledge.maxFiles" => Ok(Self::KnowledgeMaxFiles),
             "knowledge.chunkSize" => Ok(Self::KnowledgeChunkSize),
             "knowledge.chunkOverlap" => Ok(Self::KnowledgeChunkOverlap),
+            "knowledge.chunkStrategy" => Ok(Self::KnowledgeChunkStrategy),
             "knowledge.indexType" => Ok(Self::KnowledgeIndexType),
+            "knowledge.idleUnloadTimeout" => Ok(Self::KnowledgeIdleUnloadTimeout),
+            "chat.knowledgeBackend" => Ok(Self::KnowledgeBackend),
             "chat.skimCommandKey" => Ok(Self::SkimCommandKey),
             "chat.enableTangentMode" => Ok(Self::EnabledTangentMode),
+            "chat.tangentMergeSummary" => Ok(Self::ChatTangentMergeSummary),
             "chat.tangentModeKey" => Ok(Self::TangentModeKey),
             "introspect.tangentMode" => Ok(Self::IntrospectTangentMode),
             "chat.greeting.enabled" => Ok(Self::ChatGreetingEnabled),
             "api.timeout" => Ok(Self::ApiTimeout),
+            "api.retry.maxAttempts" => Ok(Self::ApiRetryMaxAttempts),
+            "api.retry.maxWaitSeconds" => Ok(Self::ApiRetryMaxWaitSeconds),
             "chat.editMode" => Ok(Self::ChatEditMode),
             "chat.enableNotifications" => Ok(Self::ChatEnableNotifications),
             "api.codewhisperer.service" => Ok(Self::ApiCodeWhispererService),
             "api.q.service" => Ok(Self::ApiQService),
             "mcp.initTimeout" => Ok(Self::McpInitTimeout),
+            "mcp.initConcurrency" => Ok(Self::McpInitConcurrency),
+            "mcp.requiredInitTimeout" => Ok(Self::McpRequiredInitTimeout),
             "mcp.noInteractiveTimeout" => Ok(Self::McpNoInteractiveTimeout),
             "mcp.loadedBefore" => Ok(Self::McpLoadedBefore),
             "chat.defaultModel" => Ok(Self::ChatDefaultModel),
             "chat.disableMarkdownRendering" => Ok(Self::ChatDisableMarkdownRendering),
             "chat.defaultAgent" => Ok(Self::ChatDefaultAgent),
             "chat.disableAutoCompaction" => Ok(Self::ChatDisableAutoCompaction),
+            "chat.autoCompactThreshold" => Ok(Self::ChatAutoCompactThreshold),
+            "chat.toolLoopThreshold" => Ok(Self::ChatToolLoopThreshold),
             "chat.enableHistoryHints" => Ok(Self::ChatEnableHistoryHints),
             "chat.enableTodoList" => Ok(Self::EnabledTodoList),
             "chat.enableShiftEnterNewline" => Ok(Self::ChatEnableShiftEnterNewline),
+            "chat.executeRiskThreshold" => Ok(Self::ChatExecuteRiskThreshold),
+            "chat.executeSafetyPolicy" => Ok(Self::ChatExecuteSafetyPolicy),
+            "chat.executeSafetyPolicyCommands" => Ok(Self::ChatExecuteSafetyPolicyCommands),
+            "chat.showSessionSummary" => Ok(Self::ChatShowSessionSummary),
+            "chat.saveOnExit" => Ok(Self::ChatSaveOnExit),
+            "chat.redactSecrets" => Ok(Self::ChatRedactSecrets),
+            "chat.redactPatterns" => Ok(Self::ChatRedactPatterns),
+            "chat.showContextGauge" => Ok(Self::ChatShowContextGauge),
+            "chat.contextWarningThreshold" => Ok(Self::ChatContextWarningThreshold),
+            "chat.delegationMaxTurns" => Ok(Self::ChatDelegationMaxTurns),
+            "chat.plainOutput" => Ok(Self::ChatPlainOutput),
+            "chat.disableWordDiff" => Ok(Self::ChatDisableWordDiff),
             _ => Err(DatabaseError::InvalidSetting(value.to_string())),
         }
     }
@@ -0,0 +1,4 @@
+CREATE TABLE saved_conversations (
+    key TEXT PRIMARY KEY,
+    value TEXT
+);
@@ -46,6 +46,14 @@ fn main() -> Result<ExitCode> {
                 eprintln!("{} {err}", "error:".bold().red());
             }
 
+            // Chat errors may suggest how to resolve them, and map to a specific exit code.
+            if let Some(chat_err) = err.downcast_ref::<cli::ChatError>() {
+                if let Some(hint) = chat_err.hint() {
+                    eprintln!("{}", hint.as_ref().dark_grey());
+                }
+                return Ok(ExitCode::from(chat_err.kind().exit_code()));
+            }
+
             Ok(ExitCode::FAILURE)
         },
     }
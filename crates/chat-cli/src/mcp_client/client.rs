@@ -26,6 +26,10 @@ use super::transport::base_protocol::{
     JsonRpcVersion,
 };
 use super::transport::stdio::JsonRpcStdioTransport;
+use super::transport::streamable_http::{
+    DEFAULT_CONNECT_TIMEOUT,
+    JsonRpcHttpTransport,
+};
 use super::transport::{
     self,
     Transport,
@@ -51,6 +55,7 @@ use crate::util::process::{
 
 pub type ClientInfo = serde_json::Value;
 pub type StdioTransport = JsonRpcStdioTransport;
+pub type HttpTransport = JsonRpcHttpTransport;
 
 /// Represents the capabilities of a client in the Model Context Protocol.
 /// This structure is sent to the server during initialization to communicate
@@ -84,6 +89,15 @@ pub struct ClientConfig {
     pub env: Option<HashMap<String, String>>,
 }
 
+#[derive(Debug, Deserialize)]
+pub struct HttpClientConfig {
+    pub server_name: String,
+    pub url: String,
+    pub headers: HashMap<String, String>,
+    pub timeout: u64,
+    pub client_info: serde_json::Value,
+}
+
 #[allow(dead_code)]
 #[derive(Debug, Error)]
 pub enum ClientError {
@@ -266,6 +280,33 @@ impl Client<StdioTransport> {
     }
 }
 
+impl Client<HttpTransport> {
+    pub fn from_http_config(config: HttpClientConfig) -> Result<Self, ClientError> {
+        let HttpClientConfig {
+            server_name,
+            url,
+            headers,
+            timeout,
+            client_info,
+        } = config;
+
+        // Connecting should never take longer than an entire request is allowed to.
+        let connect_timeout = DEFAULT_CONNECT_TIMEOUT.min(Duration::from_millis(timeout));
+        let transport = Arc::new(JsonRpcHttpTransport::client(&url, headers, connect_timeout)?);
+        Ok(Self {
+            server_name,
+            transport,
+            timeout,
+            server_process_id: None,
+            client_info,
+            current_id: Arc::new(AtomicU64::new(0)),
+            messenger: None,
+            prompt_gets: Arc::new(SyncRwLock::new(HashMap::new())),
+            is_prompts_out_of_date: Arc::new(AtomicBool::new(false)),
+        })
+    }
+}
+
 impl<T> Drop for Client<T>
 where
     T: Transport,
@@ -315,32 +356,7 @@ where
             }
         });
 
-        let init_params = Some({
-            let client_cap = ClientCapabilities::from(self.client_info.clone());
-            serde_json::json!(client_cap)
-        });
-        let init_resp = self.request("initialize", init_params).await?;
-        if let Err(e) = examine_server_capabilities(&init_resp) {
-            return Err(ClientError::NegotiationError(format!(
-                "Client {} has failed to negotiate server capabilities with server: {:?}",
-                self.server_name, e
-            )));
-        }
-        let cap = {
-            let result = init_resp.result.ok_or(ClientError::NegotiationError(format!(
-                "Server {} init resp is missing result",
-                self.server_name
-            )))?;
-            let cap = result
-                .get("capabilities")
-                .ok_or(ClientError::NegotiationError(format!(
-                    "Server {} init resp result is missing capabilities",
-                    self.server_name
-                )))?
-                .clone();
-            serde_json::from_value::<ServerCapabilities>(cap)?
-        };
-        self.notify("initialized", None).await?;
+        let cap = self.handshake().await?;
 
         // TODO: group this into examine_server_capabilities
         // Prefetch prompts in the background. We should only do this after the server has been
@@ -360,6 +376,13 @@ where
                 fetch_tools_and_notify_with_messenger(&client_ref, messenger_ref.as_ref()).await;
             });
         }
+        if cap.resources.is_some() {
+            let client_ref = (*self).clone();
+            let messenger_ref = self.messenger.as_ref().map(|m| m.duplicate());
+            tokio::spawn(async move {
+                fetch_resources_and_notify_with_messenger(&client_ref, messenger_ref.as_ref()).await;
+            });
+        }
 
         let transport_ref = self.transport.clone();
         let server_name = self.server_name.clone();
@@ -368,6 +391,7 @@ where
 
         let prompts_list_changed_supported = cap.prompts.as_ref().is_some_and(|p| p.get("listChanged").is_some());
         let tools_list_changed_supported = cap.tools.as_ref().is_some_and(|t| t.get("listChanged").is_some());
+        let resources_list_changed_supported = cap.resources.as_ref().is_some_and(|r| r.get("listChanged").is_some());
         tokio::spawn(async move {
             let mut listener = transport_ref.get_listener();
             loop {
@@ -411,12 +435,11 @@ where
                                     "notifications/prompts/list_changed" | "prompts/list_changed"
                                         if prompts_list_changed_supported =>
                                     {
-                                        // TODO: after we have moved the prompts to the tool
-                                        // manager we follow the same workflow as the list changed
-                                        // for tools
+                                        // The cached prompts are out of date until the re-list
+                                        // below has completed
+                                        client_ref.is_prompts_out_of_date.store(true, Ordering::Release);
                                         fetch_prompts_and_notify_with_messenger(&client_ref, messenger_ref.as_ref())
                                             .await;
-                                        client_ref.is_prompts_out_of_date.store(true, Ordering::Release);
                                     },
                                     "notifications/tools/list_changed" | "tools/list_changed"
                                         if tools_list_changed_supported =>
@@ -424,6 +447,12 @@ where
                                         fetch_tools_and_notify_with_messenger(&client_ref, messenger_ref.as_ref())
                                             .await;
                                     },
+                                    "notifications/resources/list_changed" | "resources/list_changed"
+                                        if resources_list_changed_supported =>
+                                    {
+                                        fetch_resources_and_notify_with_messenger(&client_ref, messenger_ref.as_ref())
+                                            .await;
+                                    },
                                     _ => {},
                                 }
                             },
@@ -451,6 +480,39 @@ where
         Ok(cap)
     }
 
+    /// Negotiates the protocol version and capabilities with the server, which also starts a new
+    /// session for servers that use sessions.
+    async fn handshake(&self) -> Result<ServerCapabilities, ClientError> {
+        let init_params = Some({
+            let client_cap = ClientCapabilities::from(self.client_info.clone());
+            serde_json::json!(client_cap)
+        });
+        let init_resp = self.request("initialize", init_params).await?;
+
+        if let Err(e) = examine_server_capabilities(&init_resp) {
+            return Err(ClientError::NegotiationError(format!(
+                "Client {} has failed to negotiate server capabilities with server: {:?}",
+                self.server_name, e
+            )));
+        }
+        let cap = {
+            let result = init_resp.result.ok_or(ClientError::NegotiationError(format!(
+                "Server {} init resp is missing result",
+                self.server_name
+            )))?;
+            let cap = result
+                .get("capabilities")
+                .ok_or(ClientError::NegotiationError(format!(
+                    "Server {} init resp result is missing capabilities",
+                    self.server_name
+                )))?
+                .clone();
+            serde_json::from_value::<ServerCapabilities>(cap)?
+        };
+        self.notify("initialized", None).await?;
+        Ok(cap)
+    }
+
     /// Sends a request to the server associated.
     /// This call will yield until a response is received.
     pub async fn request(
@@ -469,10 +531,22 @@ where
         };
         tracing::trace!(target: "mcp", "To {}:\n{:#?}", self.server_name, request);
         let msg = JsonRpcMessage::Request(request);
-        time::timeout(Duration::from_millis(self.timeout), self.transport.send(&msg))
-            .await
-            .map_err(send_map_err)??;
+        // The listener needs to be subscribed before sending since some transports (e.g. http)
+        // can surface the response before `send` returns.
         let mut listener = self.transport.get_listener();
+        let sent = time::timeout(Duration::from_millis(self.timeout), self.transport.send(&msg))
+            .await
+            .map_err(send_map_err)?;
+        if let Err(TransportError::SessionExpired(reason)) = sent {
+            // As per the spec, a new session is initialized before the request is sent again
+            tracing::info!("{reason}, initializing a new session with {}", self.server_name);
+            Box::pin(self.handshake()).await?;
+            time::timeout(Duration::from_millis(self.timeout), self.transport.send(&msg))
+                .await
+                .map_err(send_map_err)??;
+        } else {
+            sent?;
+        }
         let mut resp = time::timeout(Duration::from_millis(self.timeout), async {
             // we want to ignore all other messages sent by the server at this point and let the
             // background loop handle them
@@ -587,6 +661,39 @@ where
         )
     }
 
+    /// Shuts the connection down as described in https://spec.modelcontextprotocol.io/specification/2024-11-05/basic/lifecycle/#shutdown.
+    ///
+    /// The transport is closed, which for stdio servers closes their input, and the server
+    /// process is given up to `timeout` to exit on its own so that it can clean up. Servers that
+    /// are still running after that are terminated. Returns whether the server shut down in time.
+    pub async fn shutdown(&self, timeout: Duration) -> bool {
+        let mut listener = self.transport.get_listener();
+        let shutdown = async {
+            self.transport.shutdown().await?;
+            // The output of a stdio server is closed once it has exited, which closes the listener
+            if self.server_process_id.is_some() {
+                loop {
+                    if let Err(TransportError::RecvError(tokio::sync::broadcast::error::RecvError::Closed)) =
+                        listener.recv().await
+                    {
+                        break;
+                    }
+                }
+            }
+            Ok::<_, TransportError>(())
+        };
+
+        let result = time::timeout(timeout, shutdown).await;
+        let shut_down = matches!(result, Ok(Ok(())));
+        if !shut_down {
+            tracing::warn!(target: "mcp", "Server {} did not shut down in time: {:?}", self.server_name, result);
+            if let Some(process_id) = self.server_process_id {
+                let _ = terminate_process(process_id);
+            }
+        }
+        shut_down
+    }
+
     fn get_id(&self) -> u64 {
         self.current_id.fetch_add(1, Ordering::SeqCst)
     }
@@ -631,6 +738,21 @@ where
         Ok::<PromptsListResult, eyre::Report>(prompt_list_result)
     };
 
+    if let Ok(PromptsListResult { prompts, .. }) = &prompt_list_result {
+        let prompt_gets = prompts
+            .iter()
+            .filter_map(|value| serde_json::from_value::<PromptGet>(value.clone()).ok())
+            .map(|prompt_get| (prompt_get.name.clone(), prompt_get))
+            .collect::<HashMap<_, _>>();
+        match client.prompt_gets.write() {
+            Ok(mut cache) => {
+                *cache = prompt_gets;
+                client.is_prompts_out_of_date.store(false, Ordering::Release);
+            },
+            Err(e) => tracing::error!("Failed to update the prompt cache of {}: {:?}", client.server_name, e),
+        }
+    }
+
     if let Some(messenger) = messenger {
         if let Err(e) = messenger.send_prompts_list_result(prompt_list_result).await {
             tracing::error!("Failed to send prompt result through messenger: {:?}", e);
@@ -639,6 +761,45 @@ where
 }
 
 #[allow(clippy::borrowed_box)]
+async fn fetch_resources_and_notify_with_messenger<T>(client: &Client<T>, messenger: Option<&Box<dyn Messenger>>)
+where
+    T: Transport,
+{
+    let resource_list_result = 'resource_list_result: {
+        let resp = match client.request("resources/list", None).await {
+            Ok(resp) => resp,
+            Err(e) => break 'resource_list_result Err(e.into()),
+        };
+        if let Some(error) = resp.error {
+            let msg = format!(
+                "Failed to retrieve resource list for {}: {:?}",
+                client.server_name, error
+            );
+            break 'resource_list_result Err(eyre::eyre!(msg));
+        }
+        let Some(result) = resp.result else {
+            let msg = format!("Resource list response from {} is missing result", client.server_name);
+            break 'resource_list_result Err(eyre::eyre!(msg));
+        };
+        match serde_json::from_value::<ResourcesListResult>(result) {
+            Ok(result) => Ok(result),
+            Err(e) => {
+                let msg = format!(
+                    "Failed to deserialize resource result from {}: {:?}",
+                    client.server_name, e
+                );
+                Err(eyre::eyre!(msg))
+            },
+        }
+    };
+
+    if let Some(messenger) = messenger {
+        if let Err(e) = messenger.send_resources_list_result(resource_list_result).await {
+            tracing::error!("Failed to send resource result through messenger {:?}", e);
+        }
+    }
+}
+
 async fn fetch_tools_and_notify_with_messenger<T>(client: &Client<T>, messenger: Option<&Box<dyn Messenger>>)
 where
     T: Transport,
@@ -701,6 +862,38 @@ mod tests {
         PathBuf::from(workspace_root)
     }
 
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_client_shutdown() {
+        let client = |server_name: &str, args: &[&str]| {
+            Client::<StdioTransport>::from_config(ClientConfig {
+                server_name: server_name.to_string(),
+                bin_path: "sh".to_string(),
+                args: args.iter().map(ToString::to_string).collect(),
+                timeout: 1_000,
+                client_info: serde_json::json!({}),
+                env: None,
+            })
+            .unwrap()
+        };
+
+        // Exits once its input is closed
+        let graceful = client("graceful", &["-c", "cat > /dev/null"]);
+        assert!(graceful.shutdown(Duration::from_secs(5)).await);
+
+        // Ignores its input and has to be terminated
+        let hung = client("hung", &["-c", "exec sleep 30"]);
+        let pid = hung.server_process_id.unwrap();
+        assert!(!hung.shutdown(Duration::from_millis(200)).await);
+        tokio::time::sleep(Duration::from_millis(200)).await;
+        let system = sysinfo::System::new_all();
+        assert!(
+            system
+                .process(pid)
+                .is_none_or(|process| process.status() == sysinfo::ProcessStatus::Zombie)
+        );
+    }
+
     #[tokio::test(flavor = "multi_thread")]
     // For some reason this test is quite flakey when ran in the CI but not on developer's
     // machines. As a result it is hard to debug, hence we are ignoring it for now.
@@ -876,10 +1069,17 @@ mod tests {
             .await
             .expect("Mock new prompt request failed");
         // After we send the signal for the server to clear prompts, we should be receiving signal
-        // to fetch for new prompts, after which we should be getting no prompts.
+        // to fetch for new prompts, after which the cache should hold only the new prompts.
+        let prompt_gets = client.prompt_gets.clone();
         let is_prompts_out_of_date = client.is_prompts_out_of_date.clone();
         let wait_for_new_prompts = async move {
-            while !is_prompts_out_of_date.load(Ordering::Acquire) {
+            loop {
+                let has_new_prompts = prompt_gets
+                    .read()
+                    .is_ok_and(|prompts| fake_prompt_names.iter().all(|name| prompts.contains_key(*name)));
+                if has_new_prompts && !is_prompts_out_of_date.load(Ordering::Acquire) {
+                    break;
+                }
                 tokio::time::sleep(time::Duration::from_millis(100)).await;
             }
         };
@@ -73,6 +73,50 @@ pub struct ResourcesListResult {
     pub next_cursor: Option<String>,
 }
 
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+/// A resource as it is advertised in the result of resources/list
+pub struct ResourceInfo {
+    /// The URI with which the resource is read
+    pub uri: String,
+    /// Human-readable name of the resource
+    pub name: String,
+    /// Optional description of what the resource represents
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    /// Optional MIME type of the resource, if known ahead of reading it
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub mime_type: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+/// Result of the resources/read operation
+pub struct ResourceReadResult {
+    /// The contents of the resource. A single read may yield more than one item (e.g. a directory)
+    pub contents: Vec<ResourceReadContents>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(untagged, rename_all = "camelCase")]
+/// An item in the contents of [ResourceReadResult]
+pub enum ResourceReadContents {
+    /// Textual content
+    Text {
+        uri: String,
+        #[serde(rename = "mimeType", skip_serializing_if = "Option::is_none")]
+        mime_type: Option<String>,
+        text: String,
+    },
+    /// Binary content, base64 encoded
+    Blob {
+        uri: String,
+        #[serde(rename = "mimeType", skip_serializing_if = "Option::is_none")]
+        mime_type: Option<String>,
+        blob: String,
+    },
+}
+
 /// Result of listing resource templates operation
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
@@ -108,6 +152,10 @@ pub struct PromptGetArg {
     /// If not specified, defaults to false
     #[serde(skip_serializing_if = "Option::is_none")]
     pub required: Option<bool>,
+    /// Value suggested when the argument is asked for interactively. This is not part of the
+    /// protocol but is advertised by some servers
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub default: Option<String>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -40,6 +40,10 @@ pub trait Messenger: std::fmt::Debug + Send + Sync + 'static {
     /// Signals to the orchestrator that a server has deinitialized
     fn send_deinit_msg(&self);
 
+    /// Sets the id of the server process once it has been spawned, which is sent along with every
+    /// message from then on
+    fn set_pid(&mut self, pid: Option<u32>);
+
     /// Creates a duplicate of the messenger object
     /// This function is used to create a new instance of the messenger with the same configuration
     fn duplicate(&self) -> Box<dyn Messenger>;
@@ -84,6 +88,8 @@ impl Messenger for NullMessenger {
 
     fn send_deinit_msg(&self) {}
 
+    fn set_pid(&mut self, _pid: Option<u32>) {}
+
     fn duplicate(&self) -> Box<dyn Messenger> {
         Box::new(NullMessenger)
     }
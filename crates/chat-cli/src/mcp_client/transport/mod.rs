@@ -1,10 +1,12 @@
 pub mod base_protocol;
 pub mod stdio;
+pub mod streamable_http;
 
 use std::fmt::Debug;
 
 pub use base_protocol::*;
 pub use stdio::*;
+pub use streamable_http::*;
 use thiserror::Error;
 
 #[derive(Clone, Debug, Error)]
@@ -13,6 +15,12 @@ pub enum TransportError {
     Serialization(String),
     #[error("IO error: {0}")]
     Stdio(String),
+    #[error("HTTP error: {0}")]
+    Http(String),
+    /// The server no longer knows the session the request was sent in, and a new session has to
+    /// be initialized.
+    #[error("Session expired: {0}")]
+    SessionExpired(String),
     #[error("{0}")]
     Custom(String),
     #[error(transparent)]
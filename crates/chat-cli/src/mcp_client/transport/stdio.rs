@@ -28,7 +28,8 @@ use super::{
 #[derive(Debug)]
 pub enum JsonRpcStdioTransport {
     Client {
-        stdin: Arc<Mutex<ChildStdin>>,
+        /// Input of the server process, taken and dropped on shutdown so that the pipe is closed
+        stdin: Arc<Mutex<Option<ChildStdin>>>,
         receiver: broadcast::Receiver<Result<JsonRpcMessage, TransportError>>,
         log_receiver: broadcast::Receiver<String>,
     },
@@ -87,7 +88,7 @@ impl JsonRpcStdioTransport {
                 let _ = log_tx.send(line);
             }
         });
-        let stdin = Arc::new(Mutex::new(stdin));
+        let stdin = Arc::new(Mutex::new(Some(stdin)));
         Self::spawn_reader(stdout, tx);
         Ok(JsonRpcStdioTransport::Client {
             stdin,
@@ -112,6 +113,9 @@ impl Transport for JsonRpcStdioTransport {
                 let mut serialized = serde_json::to_vec(msg)?;
                 serialized.push(b'\n');
                 let mut stdin = stdin.lock().await;
+                let Some(stdin) = stdin.as_mut() else {
+                    return Err(TransportError::Custom("Server input is closed".to_owned()));
+                };
                 stdin
                     .write_all(&serialized)
                     .await
@@ -152,8 +156,11 @@ impl Transport for JsonRpcStdioTransport {
     async fn shutdown(&self) -> Result<(), TransportError> {
         match self {
             JsonRpcStdioTransport::Client { stdin, .. } => {
-                let mut stdin = stdin.lock().await;
-                Ok(stdin.shutdown().await?)
+                // Dropping the handle closes the pipe, which is the signal for the server to exit
+                match stdin.lock().await.take() {
+                    Some(mut stdin) => Ok(stdin.shutdown().await?),
+                    None => Ok(()),
+                }
             },
             JsonRpcStdioTransport::Server { stdout, .. } => {
                 let mut stdout = stdout.lock().await;
@@ -0,0 +1,473 @@
+use std::sync::Arc;
+use std::sync::atomic::{
+    AtomicBool,
+    Ordering,
+};
+use std::time::Duration;
+
+use futures::StreamExt;
+use reqwest::header::{
+    ACCEPT,
+    CONTENT_TYPE,
+    HeaderMap,
+    HeaderName,
+    HeaderValue,
+};
+use reqwest::{
+    StatusCode,
+    Url,
+};
+use tokio::sync::{
+    RwLock,
+    broadcast,
+};
+use tokio_util::sync::CancellationToken;
+
+use super::base_protocol::JsonRpcMessage;
+use super::{
+    Listener,
+    LogListener,
+    Transport,
+    TransportError,
+};
+
+/// Header used by the streamable HTTP transport to associate requests with a session.
+/// See https://modelcontextprotocol.io/specification/2025-03-26/basic/transports#session-management
+const MCP_SESSION_ID_HEADER: &str = "mcp-session-id";
+const LAST_EVENT_ID_HEADER: &str = "last-event-id";
+/// Upper bound on how long establishing a connection to the server may take.
+pub const DEFAULT_CONNECT_TIMEOUT: Duration = Duration::from_secs(10);
+const MAX_RECONNECT_ATTEMPTS: u32 = 5;
+const INITIAL_RECONNECT_DELAY: Duration = Duration::from_millis(500);
+
+/// Client side of the [streamable HTTP transport](https://modelcontextprotocol.io/specification/2025-03-26/basic/transports#streamable-http).
+///
+/// Every message is POSTed to the server url. The server either answers with a single json body
+/// or with an SSE stream carrying one or more messages. Once a session has been established, a
+/// long lived SSE stream is also opened (and reopened if it drops) so the server can send
+/// notifications to the client.
+#[derive(Debug)]
+pub struct JsonRpcHttpTransport {
+    url: Url,
+    client: reqwest::Client,
+    headers: HeaderMap,
+    session_id: Arc<RwLock<Option<String>>>,
+    sender: broadcast::Sender<Result<JsonRpcMessage, TransportError>>,
+    receiver: broadcast::Receiver<Result<JsonRpcMessage, TransportError>>,
+    log_sender: broadcast::Sender<String>,
+    log_receiver: broadcast::Receiver<String>,
+    is_event_stream_spawned: AtomicBool,
+    cancel_token: CancellationToken,
+}
+
+impl JsonRpcHttpTransport {
+    /// Creates a client transport for the server at `url`. `headers` are sent along with every
+    /// request and are expected to already have any environment variables interpolated.
+    pub fn client(
+        url: &str,
+        headers: impl IntoIterator<Item = (String, String)>,
+        connect_timeout: Duration,
+    ) -> Result<Self, TransportError> {
+        let url = Url::parse(url).map_err(|e| TransportError::Custom(format!("Invalid url {url}: {e}")))?;
+        if !matches!(url.scheme(), "http" | "https") {
+            return Err(TransportError::Custom(format!(
+                "Unsupported url scheme {}, expected http or https",
+                url.scheme()
+            )));
+        }
+
+        let mut header_map = HeaderMap::new();
+        for (name, value) in headers {
+            let name = HeaderName::from_bytes(name.as_bytes())
+                .map_err(|e| TransportError::Custom(format!("Invalid header name {name}: {e}")))?;
+            let value = HeaderValue::from_str(&value)
+                .map_err(|e| TransportError::Custom(format!("Invalid value for header {name}: {e}")))?;
+            header_map.insert(name, value);
+        }
+
+        let client = reqwest::Client::builder()
+            .connect_timeout(connect_timeout)
+            .build()
+            .map_err(|e| TransportError::Http(e.to_string()))?;
+
+        let (sender, receiver) = broadcast::channel::<Result<JsonRpcMessage, TransportError>>(100);
+        let (log_sender, log_receiver) = broadcast::channel::<String>(100);
+
+        Ok(Self {
+            url,
+            client,
+            headers: header_map,
+            session_id: Arc::new(RwLock::new(None)),
+            sender,
+            receiver,
+            log_sender,
+            log_receiver,
+            is_event_stream_spawned: AtomicBool::new(false),
+            cancel_token: CancellationToken::new(),
+        })
+    }
+
+    /// Opens the long lived SSE stream used for server initiated messages. The stream is
+    /// reopened with exponential backoff whenever it drops, resuming from the last seen event id.
+    fn spawn_event_stream(&self) {
+        if self.is_event_stream_spawned.swap(true, Ordering::AcqRel) {
+            return;
+        }
+
+        let client = self.client.clone();
+        let url = self.url.clone();
+        let headers = self.headers.clone();
+        let session_id = self.session_id.clone();
+        let sender = self.sender.clone();
+        let log_sender = self.log_sender.clone();
+        let cancel_token = self.cancel_token.clone();
+
+        tokio::spawn(async move {
+            let mut last_event_id = None::<String>;
+            let mut attempts = 0;
+            loop {
+                let mut request = client
+                    .get(url.clone())
+                    .headers(headers.clone())
+                    .header(ACCEPT, "text/event-stream");
+                if let Some(session_id) = session_id.read().await.as_ref() {
+                    request = request.header(MCP_SESSION_ID_HEADER, session_id);
+                }
+                if let Some(last_event_id) = &last_event_id {
+                    request = request.header(LAST_EVENT_ID_HEADER, last_event_id);
+                }
+
+                let response = tokio::select! {
+                    _ = cancel_token.cancelled() => break,
+                    response = request.send() => response,
+                };
+                match response {
+                    Ok(response) if response.status() == StatusCode::METHOD_NOT_ALLOWED => {
+                        let _ = log_sender.send(format!("{url} does not offer an event stream"));
+                        break;
+                    },
+                    Ok(response) if response.status().is_success() => {
+                        attempts = 0;
+                        tokio::select! {
+                            _ = cancel_token.cancelled() => break,
+                            id = read_event_stream(response, &sender) => {
+                                if id.is_some() {
+                                    last_event_id = id;
+                                }
+                            },
+                        }
+                    },
+                    Ok(response) => {
+                        let _ = log_sender.send(format!("Event stream for {url} returned {}", response.status()));
+                    },
+                    Err(e) => {
+                        let _ = log_sender.send(format!("Event stream for {url} failed: {e}"));
+                    },
+                }
+
+                attempts += 1;
+                if attempts > MAX_RECONNECT_ATTEMPTS {
+                    let _ = log_sender.send(format!(
+                        "Giving up on event stream for {url} after {MAX_RECONNECT_ATTEMPTS} attempts"
+                    ));
+                    break;
+                }
+                let delay = INITIAL_RECONNECT_DELAY * 2_u32.pow(attempts - 1);
+                let _ = log_sender.send(format!("Reconnecting to event stream for {url} in {delay:?}"));
+                tokio::select! {
+                    _ = cancel_token.cancelled() => break,
+                    _ = tokio::time::sleep(delay) => {},
+                }
+            }
+        });
+    }
+}
+
+#[async_trait::async_trait]
+impl Transport for JsonRpcHttpTransport {
+    async fn send(&self, msg: &JsonRpcMessage) -> Result<(), TransportError> {
+        let mut request = self
+            .client
+            .post(self.url.clone())
+            .headers(self.headers.clone())
+            .header(ACCEPT, "application/json, text/event-stream")
+            .json(msg);
+        let session_id = self.session_id.read().await.clone();
+        if let Some(session_id) = &session_id {
+            request = request.header(MCP_SESSION_ID_HEADER, session_id);
+        }
+
+        let response = request.send().await.map_err(|e| TransportError::Http(e.to_string()))?;
+        let status = response.status();
+        // The server responds with 404 once it has terminated the session, e.g. after a restart.
+        // The session is dropped so that the next initialize request starts a new one.
+        if status == StatusCode::NOT_FOUND {
+            if let Some(session_id) = session_id {
+                self.session_id.write().await.take();
+                return Err(TransportError::SessionExpired(format!(
+                    "{} no longer knows session {session_id}",
+                    self.url
+                )));
+            }
+        }
+        if !status.is_success() {
+            return Err(TransportError::Http(format!("{} responded with {status}", self.url)));
+        }
+
+        if let Some(session_id) = response
+            .headers()
+            .get(MCP_SESSION_ID_HEADER)
+            .and_then(|v| v.to_str().ok())
+        {
+            self.session_id.write().await.replace(session_id.to_string());
+        }
+        // Server initiated messages can only be received once the server has acknowledged our
+        // initialization.
+        if matches!(msg, JsonRpcMessage::Notification(notif) if notif.method == "notifications/initialized") {
+            self.spawn_event_stream();
+        }
+
+        if status == StatusCode::ACCEPTED {
+            return Ok(());
+        }
+
+        let content_type = response
+            .headers()
+            .get(CONTENT_TYPE)
+            .and_then(|v| v.to_str().ok())
+            .unwrap_or_default()
+            .to_string();
+        if content_type.starts_with("text/event-stream") {
+            let sender = self.sender.clone();
+            tokio::spawn(async move {
+                read_event_stream(response, &sender).await;
+            });
+        } else if content_type.starts_with("application/json") {
+            let body = response
+                .bytes()
+                .await
+                .map_err(|e| TransportError::Http(e.to_string()))?;
+            for msg in parse_json_rpc_body(&body)? {
+                let _ = self.sender.send(Ok(msg));
+            }
+        }
+
+        Ok(())
+    }
+
+    fn get_listener(&self) -> impl Listener {
+        HttpListener {
+            receiver: self.receiver.resubscribe(),
+        }
+    }
+
+    async fn shutdown(&self) -> Result<(), TransportError> {
+        self.cancel_token.cancel();
+        // Explicitly terminate the session so the server can release its resources.
+        if let Some(session_id) = self.session_id.write().await.take() {
+            self.client
+                .delete(self.url.clone())
+                .headers(self.headers.clone())
+                .header(MCP_SESSION_ID_HEADER, session_id)
+                .send()
+                .await
+                .map_err(|e| TransportError::Http(e.to_string()))?;
+        }
+        Ok(())
+    }
+
+    fn get_log_listener(&self) -> impl LogListener {
+        HttpLogListener {
+            receiver: self.log_receiver.resubscribe(),
+        }
+    }
+}
+
+impl Drop for JsonRpcHttpTransport {
+    fn drop(&mut self) {
+        self.cancel_token.cancel();
+    }
+}
+
+pub struct HttpListener {
+    pub receiver: broadcast::Receiver<Result<JsonRpcMessage, TransportError>>,
+}
+
+#[async_trait::async_trait]
+impl Listener for HttpListener {
+    async fn recv(&mut self) -> Result<JsonRpcMessage, TransportError> {
+        self.receiver.recv().await?
+    }
+}
+
+pub struct HttpLogListener {
+    pub receiver: broadcast::Receiver<String>,
+}
+
+#[async_trait::async_trait]
+impl LogListener for HttpLogListener {
+    async fn recv(&mut self) -> Result<String, TransportError> {
+        Ok(self.receiver.recv().await?)
+    }
+}
+
+/// A single event received over an SSE stream.
+#[derive(Debug, Default, PartialEq)]
+struct SseEvent {
+    id: Option<String>,
+    event: Option<String>,
+    data: String,
+}
+
+/// Reads SSE events off of `response` until the stream ends, forwarding every json rpc message
+/// to `sender`. Returns the id of the last event received, if any.
+async fn read_event_stream(
+    response: reqwest::Response,
+    sender: &broadcast::Sender<Result<JsonRpcMessage, TransportError>>,
+) -> Option<String> {
+    let mut last_event_id = None;
+    let mut buffer = Vec::<u8>::new();
+    let mut stream = response.bytes_stream();
+    while let Some(chunk) = stream.next().await {
+        match chunk {
+            Ok(bytes) => {
+                buffer.extend(bytes.iter().filter(|b| **b != b'\r'));
+                for event in drain_sse_events(&mut buffer) {
+                    if event.id.is_some() {
+                        last_event_id = event.id;
+                    }
+                    if event.event.as_deref().is_some_and(|e| e != "message") || event.data.is_empty() {
+                        continue;
+                    }
+                    match parse_json_rpc_body(event.data.as_bytes()) {
+                        Ok(msgs) => {
+                            for msg in msgs {
+                                let _ = sender.send(Ok(msg));
+                            }
+                        },
+                        Err(e) => {
+                            let _ = sender.send(Err(e));
+                        },
+                    }
+                }
+            },
+            Err(e) => {
+                let _ = sender.send(Err(TransportError::Http(e.to_string())));
+                break;
+            },
+        }
+    }
+    last_event_id
+}
+
+/// Removes all complete events (i.e. those terminated by a blank line) from `buffer`.
+/// Carriage returns are expected to have been stripped already.
+fn drain_sse_events(buffer: &mut Vec<u8>) -> Vec<SseEvent> {
+    let mut events = Vec::new();
+    while let Some(pos) = buffer.windows(2).position(|w| w == b"\n\n") {
+        let block = buffer.drain(..pos + 2).collect::<Vec<_>>();
+        let block = String::from_utf8_lossy(&block);
+        let mut event = SseEvent::default();
+        let mut data_lines = Vec::new();
+        for line in block.lines() {
+            // Lines starting with a colon are comments.
+            if line.is_empty() || line.starts_with(':') {
+                continue;
+            }
+            let (field, value) = line.split_once(':').unwrap_or((line, ""));
+            let value = value.strip_prefix(' ').unwrap_or(value);
+            match field {
+                "id" => event.id = Some(value.to_string()),
+                "event" => event.event = Some(value.to_string()),
+                "data" => data_lines.push(value),
+                _ => {},
+            }
+        }
+        event.data = data_lines.join("\n");
+        events.push(event);
+    }
+    events
+}
+
+/// Parses a body that contains either a single json rpc message or a batch of them.
+fn parse_json_rpc_body(body: &[u8]) -> Result<Vec<JsonRpcMessage>, TransportError> {
+    match serde_json::from_slice::<serde_json::Value>(body)? {
+        serde_json::Value::Array(values) => Ok(values
+            .into_iter()
+            .map(serde_json::from_value::<JsonRpcMessage>)
+            .collect::<Result<Vec<_>, _>>()?),
+        value => Ok(vec![serde_json::from_value::<JsonRpcMessage>(value)?]),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_drain_sse_events() {
+        let mut buffer =
+            b": keep alive\n\nid: 1\nevent: message\ndata: {\"a\":\ndata: 1}\n\nid: 2\ndata: partial".to_vec();
+        let events = drain_sse_events(&mut buffer);
+        assert_eq!(events, vec![SseEvent::default(), SseEvent {
+            id: Some("1".to_string()),
+            event: Some("message".to_string()),
+            data: "{\"a\":\n1}".to_string(),
+        }]);
+        assert_eq!(buffer, b"id: 2\ndata: partial");
+    }
+
+    #[test]
+    fn test_parse_json_rpc_body() {
+        let single = br#"{"jsonrpc":"2.0","id":1,"result":{}}"#;
+        assert_eq!(parse_json_rpc_body(single).unwrap().len(), 1);
+
+        let batch =
+            br#"[{"jsonrpc":"2.0","id":1,"result":{}},{"jsonrpc":"2.0","method":"notifications/tools/list_changed"}]"#;
+        assert_eq!(parse_json_rpc_body(batch).unwrap().len(), 2);
+
+        assert!(parse_json_rpc_body(b"not json").is_err());
+    }
+
+    #[tokio::test]
+    async fn test_send_with_expired_session() {
+        use tokio::io::{
+            AsyncReadExt,
+            AsyncWriteExt,
+        };
+
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move {
+            let (mut stream, _) = listener.accept().await.unwrap();
+            let mut buf = [0; 4096];
+            let _ = stream.read(&mut buf).await.unwrap();
+            let _ = stream
+                .write_all(b"HTTP/1.1 404 Not Found\r\ncontent-length: 0\r\nconnection: close\r\n\r\n")
+                .await;
+        });
+
+        let transport =
+            JsonRpcHttpTransport::client(&format!("http://{addr}/mcp"), Vec::new(), DEFAULT_CONNECT_TIMEOUT).unwrap();
+        transport.session_id.write().await.replace("expired".to_string());
+        let msg = serde_json::from_value::<JsonRpcMessage>(serde_json::json!({
+            "jsonrpc": "2.0",
+            "id": 1,
+            "method": "tools/list",
+        }))
+        .unwrap();
+
+        assert!(matches!(
+            transport.send(&msg).await,
+            Err(TransportError::SessionExpired(_))
+        ));
+        assert!(transport.session_id.read().await.is_none());
+    }
+
+    #[test]
+    fn test_client_rejects_invalid_url() {
+        assert!(JsonRpcHttpTransport::client("ftp://example.com/mcp", Vec::new(), DEFAULT_CONNECT_TIMEOUT).is_err());
+        assert!(JsonRpcHttpTransport::client("not a url", Vec::new(), DEFAULT_CONNECT_TIMEOUT).is_err());
+        assert!(JsonRpcHttpTransport::client("https://example.com/mcp", Vec::new(), DEFAULT_CONNECT_TIMEOUT).is_ok());
+    }
+}
@@ -245,6 +245,17 @@ impl Fs {
         }
     }
 
+    /// Removes an empty directory.
+    ///
+    /// This is a proxy to [`tokio::fs::remove_dir`].
+    pub async fn remove_dir(&self, path: impl AsRef<Path>) -> io::Result<()> {
+        match self {
+            Self::Real => fs::remove_dir(path).await,
+            Self::Chroot(root) => fs::remove_dir(append(root.path(), path)).await,
+            Self::Fake(_) => panic!("unimplemented"),
+        }
+    }
+
     /// Removes a directory at this path, after removing all its contents. Use carefully!
     ///
     /// This is a proxy to [`tokio::fs::remove_dir_all`].
@@ -362,6 +373,25 @@ impl Fs {
         }
     }
 
+    /// Query the metadata about a file, following symlinks.
+    ///
+    /// This is a proxy to [`tokio::fs::metadata`]
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error in the following situations, but is not
+    /// limited to just these cases:
+    ///
+    /// * The user lacks permissions to perform `metadata` call on `path`.
+    /// * `path` does not exist.
+    pub async fn metadata(&self, path: impl AsRef<Path>) -> io::Result<std::fs::Metadata> {
+        match self {
+            Self::Real => fs::metadata(path).await,
+            Self::Chroot(root) => fs::metadata(append(root.path(), path)).await,
+            Self::Fake(_) => panic!("unimplemented"),
+        }
+    }
+
     /// Query the metadata about a file without following symlinks.
     ///
     /// This is a proxy to [`tokio::fs::symlink_metadata`]
@@ -381,6 +411,17 @@ impl Fs {
         }
     }
 
+    /// Query the metadata about a file without following symlinks.
+    ///
+    /// This is a proxy to [`std::fs::symlink_metadata`]
+    pub fn symlink_metadata_sync(&self, path: impl AsRef<Path>) -> io::Result<std::fs::Metadata> {
+        match self {
+            Self::Real => std::fs::symlink_metadata(path),
+            Self::Chroot(root) => std::fs::symlink_metadata(append(root.path(), path)),
+            Self::Fake(_) => panic!("unimplemented"),
+        }
+    }
+
     /// Reads a symbolic link, returning the file that the link points to.
     ///
     /// This is a proxy to [`tokio::fs::read_link`].
@@ -403,6 +444,17 @@ impl Fs {
         }
     }
 
+    /// Returns an iterator over the entries within a directory.
+    ///
+    /// This is a proxy to [`std::fs::read_dir`].
+    pub fn read_dir_sync(&self, path: impl AsRef<Path>) -> io::Result<std::fs::ReadDir> {
+        match self {
+            Self::Real => std::fs::read_dir(path),
+            Self::Chroot(root) => std::fs::read_dir(append(root.path(), path)),
+            Self::Fake(_) => panic!("unimplemented"),
+        }
+    }
+
     /// Returns the canonical, absolute form of a path with all intermediate
     /// components normalized and symbolic links resolved.
     ///
@@ -415,6 +467,18 @@ impl Fs {
         }
     }
 
+    /// Returns the canonical, absolute form of a path with all intermediate
+    /// components normalized and symbolic links resolved.
+    ///
+    /// This is a proxy to [`std::fs::canonicalize`].
+    pub fn canonicalize_sync(&self, path: impl AsRef<Path>) -> io::Result<PathBuf> {
+        match self {
+            Self::Real => std::fs::canonicalize(path),
+            Self::Chroot(root) => std::fs::canonicalize(append(root.path(), path)),
+            Self::Fake(_) => panic!("unimplemented"),
+        }
+    }
+
     /// Changes the permissions found on a file or a directory.
     ///
     /// This is a proxy to [`tokio::fs::set_permissions`]
@@ -567,6 +631,10 @@ mod tests {
         assert!(e.unwrap().metadata().await.unwrap().is_dir());
         assert!(read_dir.next_entry().await.unwrap().is_none());
 
+        assert!(fs.remove_dir("/etc/b").await.is_err());
+        fs.remove_dir("/etc/b/c").await.unwrap();
+        assert!(!fs.try_exists("/etc/b/c").await.unwrap());
+
         fs.remove_dir_all("/etc").await.unwrap();
         assert!(!fs.try_exists("/etc").await.unwrap());
 
@@ -5,6 +5,8 @@ mod env;
 mod fs;
 mod sysinfo;
 
+use std::path::PathBuf;
+
 pub use env::Env;
 use eyre::Result;
 pub use fs::Fs;
@@ -38,6 +40,8 @@ pub struct Os {
     pub database: Database,
     pub client: ApiClient,
     pub telemetry: TelemetryThread,
+    /// Directories given with `--agent-dir`, searched for agents before those in `QCLI_AGENT_PATH`
+    pub agent_dirs: Vec<PathBuf>,
 }
 
 impl Os {
@@ -55,6 +59,7 @@ impl Os {
             database,
             client,
             telemetry,
+            agent_dirs: Vec::new(),
         })
     }
 }
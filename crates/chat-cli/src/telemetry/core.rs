@@ -176,6 +176,7 @@ impl Event {
                         model,
                         time_to_first_chunk_ms,
                         time_between_chunks_ms,
+                        output_tokens_per_second,
                         chat_conversation_type,
                         tool_name,
                         tool_use_id,
@@ -204,6 +205,9 @@ impl Event {
                     codewhispererterminal_time_between_chunks_ms: time_between_chunks_ms
                         .map(|v| v.iter().map(|v| format!("{:.3}", v)).collect::<Vec<_>>().join(","))
                         .map(Into::into),
+                    codewhispererterminal_output_tokens_per_second: output_tokens_per_second
+                        .map(|v| format!("{:.3}", v))
+                        .map(Into::into),
                     codewhispererterminal_chat_conversation_type: chat_conversation_type.map(Into::into),
                     codewhispererterminal_tool_name: tool_name.map(Into::into),
                     codewhispererterminal_tool_use_id: tool_use_id.map(Into::into),
@@ -233,6 +237,7 @@ impl Event {
                         reason_desc,
                         status_code,
                         time_to_first_chunks_ms,
+                        output_tokens_per_second,
                         chat_conversation_type,
                         assistant_response_length,
                         user_turn_duration_seconds,
@@ -271,6 +276,14 @@ impl Event {
                             .join(",")
                             .into(),
                     ),
+                    codewhispererterminal_output_tokens_per_second: Some(
+                        output_tokens_per_second
+                            .into_iter()
+                            .map(|v| v.map_or("null".to_string(), |v| format!("{:.3}", v)))
+                            .collect::<Vec<_>>()
+                            .join(",")
+                            .into(),
+                    ),
                     codewhispererterminal_assistant_response_length: Some(assistant_response_length.into()),
                     codewhispererterminal_user_turn_duration_seconds: Some(user_turn_duration_seconds.into()),
                     codewhispererterminal_follow_up_count: Some(follow_up_count.into()),
@@ -325,6 +338,7 @@ impl Event {
                 turn_duration,
                 aws_service_name,
                 aws_operation_name,
+                delegation_path,
             } => Some(
                 CodewhispererterminalToolUseSuggested {
                     create_time: self.created_time,
@@ -357,6 +371,9 @@ impl Event {
                     codewhispererterminal_client_application: self.client_application.map(Into::into),
                     codewhispererterminal_aws_service_name: aws_service_name.map(Into::into),
                     codewhispererterminal_aws_operation_name: aws_operation_name.map(Into::into),
+                    codewhispererterminal_tool_delegation_path: delegation_path
+                        .map(|path| path.join(","))
+                        .map(Into::into),
                 }
                 .into_metric_datum(),
             ),
@@ -527,6 +544,8 @@ pub enum MessageMetaTag {
     GenerateAgent,
     /// A /tangent request
     TangentMode,
+    /// A request for the title of a saved conversation
+    GenerateTitle,
 }
 
 /// Optional fields to add for a chatAddedMessage telemetry event.
@@ -541,6 +560,7 @@ pub struct ChatAddedMessageParams {
     pub model: Option<String>,
     pub time_to_first_chunk_ms: Option<f64>,
     pub time_between_chunks_ms: Option<Vec<f64>>,
+    pub output_tokens_per_second: Option<f64>,
     pub chat_conversation_type: Option<ChatConversationType>,
     pub tool_name: Option<String>,
     pub tool_use_id: Option<String>,
@@ -563,6 +583,7 @@ pub struct RecordUserTurnCompletionArgs {
     pub reason_desc: Option<String>,
     pub status_code: Option<u16>,
     pub time_to_first_chunks_ms: Vec<Option<f64>>,
+    pub output_tokens_per_second: Vec<Option<f64>>,
     pub chat_conversation_type: Option<ChatConversationType>,
     pub user_prompt_length: i64,
     pub assistant_response_length: i64,
@@ -644,6 +665,7 @@ pub enum EventType {
         turn_duration: Option<Duration>,
         aws_service_name: Option<String>,
         aws_operation_name: Option<String>,
+        delegation_path: Option<Vec<String>>,
     },
     AgentContribution {
         conversation_id: String,
@@ -712,6 +734,8 @@ pub struct ToolUseEventBuilder {
     pub turn_duration: Option<Duration>,
     pub aws_service_name: Option<String>,
     pub aws_operation_name: Option<String>,
+    /// MCP tools the call was delegated through, starting with the tool invoked by the model.
+    pub delegation_path: Option<Vec<String>>,
 }
 
 impl ToolUseEventBuilder {
@@ -736,6 +760,7 @@ impl ToolUseEventBuilder {
             turn_duration: None,
             aws_service_name: None,
             aws_operation_name: None,
+            delegation_path: None,
         }
     }
 
@@ -38,6 +38,7 @@ mod tests {
             codewhispererterminal_model: None,
             codewhispererterminal_time_to_first_chunks_ms: Some(40.to_string().into()),
             codewhispererterminal_time_between_chunks_ms: Some("1,2,3".to_string().into()),
+            codewhispererterminal_output_tokens_per_second: Some("42.000".to_string().into()),
             codewhispererterminal_chat_conversation_type: Some(ChatConversationType::NotToolUse.into()),
             codewhispererterminal_tool_use_id: None,
             codewhispererterminal_tool_name: None,
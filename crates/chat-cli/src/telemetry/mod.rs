@@ -366,6 +366,7 @@ impl TelemetryThread {
             turn_duration: event.turn_duration,
             aws_service_name: event.aws_service_name,
             aws_operation_name: event.aws_operation_name,
+            delegation_path: event.delegation_path,
         });
         set_event_metadata(database, &mut telemetry_event).await;
 
@@ -485,6 +486,12 @@ async fn set_event_metadata(database: &Database, event: &mut Event) {
     }
 }
 
+/// Whether the user has opted in to telemetry, i.e. `Q_DISABLE_TELEMETRY` is not set and the
+/// `telemetry.enabled` setting is not turned off.
+pub fn is_telemetry_enabled(env: &Env, database: &Database) -> bool {
+    env.get_os("Q_DISABLE_TELEMETRY").is_none() && database.settings.get_bool(Setting::TelemetryEnabled).unwrap_or(true)
+}
+
 #[derive(Debug)]
 struct TelemetryClient {
     client_id: Uuid,
@@ -495,9 +502,7 @@ struct TelemetryClient {
 
 impl TelemetryClient {
     async fn new(env: &Env, fs: &Fs, database: &mut Database) -> Result<Self, TelemetryError> {
-        let telemetry_enabled = !cfg!(test)
-            && env.get_os("Q_DISABLE_TELEMETRY").is_none()
-            && database.settings.get_bool(Setting::TelemetryEnabled).unwrap_or(true);
+        let telemetry_enabled = !cfg!(test) && is_telemetry_enabled(env, database);
 
         // If telemetry is disabled we do not emit using toolkit_telemetry
         let toolkit_telemetry_client = if telemetry_enabled {
@@ -778,6 +783,19 @@ mod test {
         assert_eq!(context.ide_version.as_deref(), Some(PRODUCT_VERSION));
     }
 
+    #[tokio::test]
+    async fn test_is_telemetry_enabled() {
+        let mut database = Database::new().await.unwrap();
+        assert!(is_telemetry_enabled(&Env::from_slice(&[]), &database));
+        assert!(!is_telemetry_enabled(
+            &Env::from_slice(&[("Q_DISABLE_TELEMETRY", "1")]),
+            &database
+        ));
+
+        database.settings.set(Setting::TelemetryEnabled, false).await.unwrap();
+        assert!(!is_telemetry_enabled(&Env::from_slice(&[]), &database));
+    }
+
     #[tracing_test::traced_test]
     #[tokio::test]
     #[ignore = "needs auth which is not in CI"]
@@ -69,7 +69,11 @@ pub mod env_var {
         Q_BUNDLE_METADATA_PATH = "Q_BUNDLE_METADATA_PATH",
 
         /// Identifier for the client application or service using the chat-cli
-        Q_CLI_CLIENT_APPLICATION = "Q_CLI_CLIENT_APPLICATION"
+        Q_CLI_CLIENT_APPLICATION = "Q_CLI_CLIENT_APPLICATION",
+
+        /// Additional directories to load agents from, searched before the workspace and global
+        /// agent directories
+        QCLI_AGENT_PATH = "QCLI_AGENT_PATH"
     }
 }
 
@@ -1,5 +1,8 @@
 use std::env::VarError;
+use std::ffi::OsString;
 use std::path::{
+    Component,
+    Path,
     PathBuf,
     StripPrefixError,
 };
@@ -12,6 +15,7 @@ use thiserror::Error;
 
 use crate::cli::DEFAULT_AGENT_NAME;
 use crate::os::Os;
+use crate::util::env_var::QCLI_AGENT_PATH;
 
 #[derive(Debug, Error)]
 pub enum DirectoryError {
@@ -169,6 +173,32 @@ pub fn chat_global_agent_path(os: &Os) -> Result<PathBuf> {
     Ok(home_dir(os)?.join(GLOBAL_AGENT_DIR_RELATIVE_TO_HOME))
 }
 
+/// Additional directories containing agents, in the order they are searched. These are the
+/// `--agent-dir` directories followed by those in [QCLI_AGENT_PATH], and take precedence over the
+/// workspace and global directories.
+pub fn chat_extra_agent_dirs(os: &Os) -> Vec<PathBuf> {
+    let from_env = os
+        .env
+        .get_os(QCLI_AGENT_PATH)
+        .map(|paths| {
+            std::env::split_paths(&paths)
+                .filter(|path| !path.as_os_str().is_empty())
+                .collect::<Vec<_>>()
+        })
+        .unwrap_or_default();
+    os.agent_dirs.iter().cloned().chain(from_env).collect()
+}
+
+/// The [QCLI_AGENT_PATH] to set on processes spawned by the chat session, such as hooks, so that
+/// the agents they load are found in the `--agent-dir` directories too. [None] if no
+/// `--agent-dir` was given, in which case they inherit the variable as it is.
+pub fn chat_agent_path_env_var(os: &Os) -> Option<OsString> {
+    if os.agent_dirs.is_empty() {
+        return None;
+    }
+    std::env::join_paths(chat_extra_agent_dirs(os)).ok()
+}
+
 /// The directory to the directory containing config for the `/context` feature in `q chat`.
 pub fn chat_local_agent_dir(os: &Os) -> Result<PathBuf> {
     let cwd = os.env.current_dir()?;
@@ -183,6 +213,29 @@ pub fn canonicalizes_path(os: &Os, path_as_str: &str) -> Result<String> {
     Ok(shellexpand::full_with_context(path_as_str, home_dir, context)?.to_string())
 }
 
+/// Like [canonicalizes_path], but a path starting with `./` is made absolute relative to
+/// `base_dir`, such as the directory of the agent config it is written in. `..` components are
+/// resolved along the way. Other paths, and every path when there is no `base_dir`, are only
+/// expanded.
+pub fn canonicalizes_path_relative_to(os: &Os, path_as_str: &str, base_dir: Option<&Path>) -> Result<String> {
+    let expanded = canonicalizes_path(os, path_as_str)?;
+    let (Some(base_dir), Some(relative)) = (base_dir, expanded.strip_prefix("./")) else {
+        return Ok(expanded);
+    };
+
+    let mut resolved = PathBuf::new();
+    for component in base_dir.join(relative).components() {
+        match component {
+            Component::CurDir => {},
+            Component::ParentDir => {
+                resolved.pop();
+            },
+            component => resolved.push(component),
+        }
+    }
+    Ok(resolved.to_string_lossy().to_string())
+}
+
 /// Given a globset builder and a path, build globs for both the file and directory patterns
 /// This is needed because by default glob does not match children of a dir so we need both
 /// patterns to exist in a globset.
@@ -216,11 +269,26 @@ pub fn chat_profiles_dir(os: &Os) -> Result<PathBuf> {
     Ok(home_dir(os)?.join(".aws").join("amazonq").join("profiles"))
 }
 
+/// The directory conversations are saved to when `q chat` exits with `chat.saveOnExit` enabled.
+pub fn chat_saved_conversations_dir(os: &Os) -> Result<PathBuf> {
+    Ok(home_dir(os)?.join(".aws").join("amazonq").join("saved_conversations"))
+}
+
+/// The directory sessions paused with `/pause` in `q chat` are written to.
+pub fn chat_paused_sessions_dir(os: &Os) -> Result<PathBuf> {
+    Ok(home_dir(os)?.join(".aws").join("amazonq").join("paused_sessions"))
+}
+
 /// The directory for knowledge base storage
 pub fn knowledge_bases_dir(os: &Os) -> Result<PathBuf> {
     Ok(home_dir(os)?.join(".aws").join("amazonq").join("knowledge_bases"))
 }
 
+/// The path to the SQLite database used by the sqlite knowledge backend
+pub fn knowledge_db_path(os: &Os) -> Result<PathBuf> {
+    Ok(home_dir(os)?.join(".aws").join("amazonq").join("knowledge.db"))
+}
+
 /// The directory for agent-specific knowledge base storage
 pub fn agent_knowledge_dir(os: &Os, agent: Option<&crate::cli::Agent>) -> Result<PathBuf> {
     let unique_id = if let Some(agent) = agent {
@@ -418,4 +486,38 @@ mod tests {
         let result = canonicalizes_path(&test_os, "**/middle/**/path").unwrap();
         assert_eq!(result, "**/middle/**/path");
     }
+
+    #[tokio::test]
+    async fn test_canonicalizes_path_relative_to() {
+        let test_os = Os::new().await.unwrap();
+        unsafe {
+            test_os.env.set_var("HOME", "/home/testuser");
+        }
+        let base_dir = Some(Path::new("/repo/.amazonq/agents"));
+
+        assert_eq!(
+            canonicalizes_path_relative_to(&test_os, "./notes/**", base_dir).unwrap(),
+            "/repo/.amazonq/agents/notes/**"
+        );
+        assert_eq!(
+            canonicalizes_path_relative_to(&test_os, "./../../src/**", base_dir).unwrap(),
+            "/repo/src/**"
+        );
+        assert_eq!(
+            canonicalizes_path_relative_to(&test_os, "~/src", base_dir).unwrap(),
+            "/home/testuser/src"
+        );
+        assert_eq!(
+            canonicalizes_path_relative_to(&test_os, "/absolute/path", base_dir).unwrap(),
+            "/absolute/path"
+        );
+        assert_eq!(
+            canonicalizes_path_relative_to(&test_os, "src/**", base_dir).unwrap(),
+            "src/**"
+        );
+        assert_eq!(
+            canonicalizes_path_relative_to(&test_os, "./src/**", None).unwrap(),
+            "./src/**"
+        );
+    }
 }
@@ -0,0 +1,103 @@
+use eyre::Result;
+
+use crate::cli::Agent;
+use crate::cli::chat::tools::knowledge::{
+    Knowledge,
+    SemanticSearchBackend,
+};
+use crate::database::settings::Setting;
+use crate::os::Os;
+use crate::util::knowledge_sqlite::SqliteBackend;
+
+/// Storage backend used by the knowledge tool.
+///
+/// Selected with `q settings chat.knowledgeBackend <semanticSearch|sqlite>`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum KnowledgeBackend {
+    /// Background-indexed contexts managed by the semantic search client
+    #[default]
+    SemanticSearch,
+    /// Entries persisted to `~/.aws/amazonq/knowledge.db`
+    Sqlite,
+}
+
+impl KnowledgeBackend {
+    /// Reads the configured backend, falling back to the default for unknown values
+    pub fn from_settings(os: &Os) -> Self {
+        os.database
+            .settings
+            .get_string(Setting::KnowledgeBackend)
+            .and_then(|s| s.parse().ok())
+            .unwrap_or_default()
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::SemanticSearch => "semanticSearch",
+            Self::Sqlite => "sqlite",
+        }
+    }
+
+    /// Returns the implementation that [Knowledge::validate] and [Knowledge::invoke] dispatch to
+    pub fn implementation(&self) -> &'static dyn KnowledgeBackendImpl {
+        match self {
+            Self::SemanticSearch => &SemanticSearchBackend,
+            Self::Sqlite => &SqliteBackend,
+        }
+    }
+}
+
+impl std::str::FromStr for KnowledgeBackend {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "semanticsearch" | "semantic_search" | "semantic-search" => Ok(Self::SemanticSearch),
+            "sqlite" => Ok(Self::Sqlite),
+            _ => Err(format!("Failed to parse '{}' as a knowledge backend", s)),
+        }
+    }
+}
+
+impl std::fmt::Display for KnowledgeBackend {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// Backend-specific handling of knowledge tool commands.
+#[async_trait::async_trait]
+pub trait KnowledgeBackendImpl: Send + Sync {
+    /// Performs validation that depends on the capabilities of the backend. Arguments common to
+    /// all backends have already been validated by the time this is called.
+    async fn validate(&self, os: &Os, knowledge: &Knowledge) -> Result<()>;
+
+    /// Executes the command, returning the text to hand back to the model.
+    async fn invoke(&self, os: &Os, knowledge: &Knowledge, agent: Option<&Agent>, session_id: &str) -> Result<String>;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_backend_from_str() {
+        assert_eq!("sqlite".parse(), Ok(KnowledgeBackend::Sqlite));
+        assert_eq!("SQLite".parse(), Ok(KnowledgeBackend::Sqlite));
+        assert_eq!("semanticSearch".parse(), Ok(KnowledgeBackend::SemanticSearch));
+        assert!("memory".parse::<KnowledgeBackend>().is_err());
+    }
+
+    #[tokio::test]
+    async fn test_backend_from_settings() {
+        let mut os = Os::new().await.unwrap();
+        assert_eq!(KnowledgeBackend::from_settings(&os), KnowledgeBackend::SemanticSearch);
+
+        os.database
+            .settings
+            .set(Setting::KnowledgeBackend, "sqlite")
+            .await
+            .unwrap();
+        assert_eq!(KnowledgeBackend::from_settings(&os), KnowledgeBackend::Sqlite);
+    }
+}
@@ -0,0 +1,799 @@
+use std::path::{
+    Path,
+    PathBuf,
+};
+use std::sync::{
+    Arc,
+    LazyLock,
+    Mutex,
+};
+
+use chrono::{
+    DateTime,
+    Utc,
+};
+use eyre::Result;
+use rusqlite::{
+    Connection,
+    OptionalExtension,
+    Row,
+    params,
+};
+use semantic_search_client::KnowledgeContext;
+use semantic_search_client::client::embedder_factory::create_embedder;
+use semantic_search_client::config::SemanticSearchConfig;
+use semantic_search_client::embedding::{
+    EmbeddingType,
+    TextEmbedderTrait,
+};
+use tracing::{
+    info,
+    warn,
+};
+use uuid::Uuid;
+
+use crate::cli::chat::tools::knowledge::Knowledge;
+use crate::cli::chat::tools::sanitize_path_tool_arg;
+use crate::cli::{
+    Agent,
+    DEFAULT_AGENT_NAME,
+};
+use crate::database::settings::Setting;
+use crate::os::Os;
+use crate::util::directories;
+use crate::util::knowledge_backend::KnowledgeBackendImpl;
+use crate::util::knowledge_store::KnowledgeStore;
+
+/// Bump this and extend [SqliteKnowledgeStore::migrate_schema] when the schema changes.
+const SCHEMA_VERSION: i32 = 1;
+
+const SCHEMA_V1: &str = "
+CREATE TABLE IF NOT EXISTS knowledge_entries (
+    id TEXT PRIMARY KEY,
+    name TEXT NOT NULL,
+    source_path TEXT,
+    content TEXT NOT NULL,
+    embedding BLOB,
+    created_at INTEGER NOT NULL,
+    agent_name TEXT NOT NULL,
+    session_id TEXT
+);
+CREATE INDEX IF NOT EXISTS knowledge_entries_agent_name ON knowledge_entries (agent_name);
+CREATE TABLE IF NOT EXISTS legacy_imports (
+    agent_name TEXT PRIMARY KEY,
+    imported_at INTEGER NOT NULL
+);
+";
+
+const MAX_SEARCH_RESULTS: usize = 5;
+const MAX_RESULT_CHARS: usize = 2000;
+
+/// A single piece of content stored in the knowledge database.
+#[derive(Debug, Clone, PartialEq)]
+pub struct KnowledgeEntry {
+    pub id: String,
+    pub name: String,
+    pub source_path: Option<String>,
+    pub content: String,
+    pub created_at: DateTime<Utc>,
+    pub agent_name: String,
+    pub session_id: Option<String>,
+}
+
+impl KnowledgeEntry {
+    fn from_row(row: &Row<'_>) -> rusqlite::Result<Self> {
+        let created_at: i64 = row.get("created_at")?;
+        Ok(Self {
+            id: row.get("id")?,
+            name: row.get("name")?,
+            source_path: row.get("source_path")?,
+            content: row.get("content")?,
+            created_at: DateTime::from_timestamp(created_at, 0).unwrap_or_default(),
+            agent_name: row.get("agent_name")?,
+            session_id: row.get("session_id")?,
+        })
+    }
+}
+
+/// Persists knowledge entries for a single agent to a SQLite database.
+///
+/// Entries are embedded on insert when a model-backed index type is configured. With the `fast`
+/// index type no embeddings are stored and search falls back to term matching.
+pub struct SqliteKnowledgeStore {
+    conn: Connection,
+    agent_name: String,
+    embedder: Option<Arc<dyn TextEmbedderTrait>>,
+}
+
+impl SqliteKnowledgeStore {
+    /// Opens the database at `path`, creating it if needed. This blocks on IO and, the first time
+    /// a model-backed `embedding_type` is used, on loading the model.
+    pub fn open(path: &Path, agent_name: &str, embedding_type: EmbeddingType) -> Result<Self> {
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        let conn = Connection::open(path)?;
+        Self::with_connection(conn, agent_name, embedder(embedding_type))
+    }
+
+    pub fn with_connection(
+        conn: Connection,
+        agent_name: &str,
+        embedder: Option<Arc<dyn TextEmbedderTrait>>,
+    ) -> Result<Self> {
+        let mut store = Self {
+            conn,
+            agent_name: agent_name.to_string(),
+            embedder,
+        };
+        store.migrate_schema()?;
+        Ok(store)
+    }
+
+    fn migrate_schema(&mut self) -> Result<()> {
+        let version: i32 = self.conn.query_row("PRAGMA user_version", [], |row| row.get(0))?;
+        if version >= SCHEMA_VERSION {
+            return Ok(());
+        }
+
+        let transaction = self.conn.transaction()?;
+        if version < 1 {
+            transaction.execute_batch(SCHEMA_V1)?;
+        }
+        transaction.pragma_update(None, "user_version", SCHEMA_VERSION)?;
+        transaction.commit()?;
+        info!(
+            from = version,
+            to = SCHEMA_VERSION,
+            "Migrated knowledge database schema"
+        );
+        Ok(())
+    }
+
+    /// Stores a single entry, returning its id
+    pub fn add(
+        &self,
+        name: &str,
+        source_path: Option<&str>,
+        content: &str,
+        session_id: Option<&str>,
+    ) -> Result<String> {
+        let embedding = match &self.embedder {
+            Some(embedder) => Some(encode_embedding(&embedder.embed(content)?)),
+            None => None,
+        };
+        let id = Uuid::new_v4().to_string();
+        self.conn.execute(
+            "INSERT INTO knowledge_entries (id, name, source_path, content, embedding, created_at, agent_name, session_id)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)",
+            params![
+                id,
+                name,
+                source_path,
+                content,
+                embedding,
+                Utc::now().timestamp(),
+                self.agent_name,
+                session_id
+            ],
+        )?;
+        Ok(id)
+    }
+
+    /// Stores the contents of a file, or of every readable text file under a directory. Returns
+    /// the ids of the created entries.
+    pub fn add_path(&self, name: &str, path: &Path, session_id: Option<&str>) -> Result<Vec<String>> {
+        let mut ids = Vec::new();
+        for file in collect_files(path) {
+            let Ok(content) = std::fs::read_to_string(&file) else {
+                // Binary or unreadable files are not useful as knowledge
+                continue;
+            };
+            if content.trim().is_empty() {
+                continue;
+            }
+            let source = file.to_string_lossy();
+            ids.push(self.add(name, Some(source.as_ref()), &content, session_id)?);
+        }
+        Ok(ids)
+    }
+
+    /// Replaces the entries named `name` with the contents of `path`. This is done in a single
+    /// transaction, so the existing entries are kept if any of the new ones fails to be added.
+    pub fn replace_path(&self, name: &str, path: &Path, session_id: Option<&str>) -> Result<Vec<String>> {
+        let transaction = self.conn.unchecked_transaction()?;
+        self.remove_by_name(name)?;
+        let ids = self.add_path(name, path, session_id)?;
+        transaction.commit()?;
+        Ok(ids)
+    }
+
+    /// All entries belonging to the current agent, oldest first
+    pub fn entries(&self) -> Result<Vec<KnowledgeEntry>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT id, name, source_path, content, created_at, agent_name, session_id
+             FROM knowledge_entries WHERE agent_name = ?1 ORDER BY created_at, rowid",
+        )?;
+        let entries = stmt
+            .query_map(params![self.agent_name], KnowledgeEntry::from_row)?
+            .collect::<rusqlite::Result<Vec<_>>>()?;
+        Ok(entries)
+    }
+
+    pub fn remove_by_id(&self, id: &str) -> Result<usize> {
+        Ok(self.conn.execute(
+            "DELETE FROM knowledge_entries WHERE agent_name = ?1 AND id = ?2",
+            params![self.agent_name, id],
+        )?)
+    }
+
+    pub fn remove_by_name(&self, name: &str) -> Result<usize> {
+        Ok(self.conn.execute(
+            "DELETE FROM knowledge_entries WHERE agent_name = ?1 AND name = ?2",
+            params![self.agent_name, name],
+        )?)
+    }
+
+    /// Removes entries created from `path`, including files that were added as part of a
+    /// directory at `path`
+    pub fn remove_by_path(&self, path: &str) -> Result<usize> {
+        let prefix = format!(
+            "{}{}%",
+            path.trim_end_matches(std::path::MAIN_SEPARATOR),
+            std::path::MAIN_SEPARATOR
+        );
+        Ok(self.conn.execute(
+            "DELETE FROM knowledge_entries WHERE agent_name = ?1 AND (source_path = ?2 OR source_path LIKE ?3)",
+            params![self.agent_name, path, prefix],
+        )?)
+    }
+
+    pub fn clear(&self) -> Result<usize> {
+        Ok(self
+            .conn
+            .execute("DELETE FROM knowledge_entries WHERE agent_name = ?1", params![
+                self.agent_name
+            ])?)
+    }
+
+    /// Returns up to `limit` entries ranked by relevance to `query`. `filter` restricts the
+    /// search to entries with a matching id or name.
+    pub fn search(&self, query: &str, filter: Option<&str>, limit: usize) -> Result<Vec<(KnowledgeEntry, f32)>> {
+        let query_embedding = match &self.embedder {
+            Some(embedder) => Some(embedder.embed(query)?),
+            None => None,
+        };
+        let query_terms = terms(query);
+
+        let mut stmt = self.conn.prepare(
+            "SELECT id, name, source_path, content, created_at, agent_name, session_id, embedding
+             FROM knowledge_entries
+             WHERE agent_name = ?1 AND (?2 IS NULL OR id = ?2 OR name = ?2)",
+        )?;
+        let rows = stmt.query_map(params![self.agent_name, filter], |row| {
+            let embedding: Option<Vec<u8>> = row.get("embedding")?;
+            Ok((KnowledgeEntry::from_row(row)?, embedding))
+        })?;
+
+        let mut results = Vec::new();
+        for row in rows {
+            let (entry, embedding) = row?;
+            let score = match (&query_embedding, embedding) {
+                (Some(query_embedding), Some(embedding)) => {
+                    cosine_similarity(query_embedding, &decode_embedding(&embedding))
+                },
+                _ => term_match_score(&query_terms, &entry.content),
+            };
+            if score > 0.0 {
+                results.push((entry, score));
+            }
+        }
+
+        results.sort_by(|a, b| b.1.total_cmp(&a.1));
+        results.truncate(limit);
+        Ok(results)
+    }
+
+    /// Whether entries from the semantic search backend have been imported for this agent yet
+    pub fn needs_legacy_import(&self) -> Result<bool> {
+        let imported = self
+            .conn
+            .query_row(
+                "SELECT 1 FROM legacy_imports WHERE agent_name = ?1",
+                params![self.agent_name],
+                |_| Ok(()),
+            )
+            .optional()?;
+        Ok(imported.is_none())
+    }
+
+    /// Imports the sources of contexts indexed by the semantic search backend, then records that
+    /// the import happened so it is only attempted once per agent. Returns the number of entries
+    /// created.
+    pub fn import_legacy_contexts(&self, contexts: &[KnowledgeContext]) -> Result<usize> {
+        let mut imported = 0;
+        for context in contexts {
+            let Some(source_path) = &context.source_path else {
+                continue;
+            };
+            let path = PathBuf::from(source_path);
+            if !path.exists() {
+                warn!(name = %context.name, %source_path, "Skipping knowledge context whose source no longer exists");
+                continue;
+            }
+            imported += self.add_path(&context.name, &path, None)?.len();
+        }
+
+        self.conn.execute(
+            "INSERT OR REPLACE INTO legacy_imports (agent_name, imported_at) VALUES (?1, ?2)",
+            params![self.agent_name, Utc::now().timestamp()],
+        )?;
+        Ok(imported)
+    }
+}
+
+/// Returns the embedder for `embedding_type`, or [None] when search should match on terms.
+///
+/// Loading a model is expensive, so the embedder is kept around for as long as the configured
+/// index type does not change.
+fn embedder(embedding_type: EmbeddingType) -> Option<Arc<dyn TextEmbedderTrait>> {
+    static EMBEDDER: LazyLock<Mutex<Option<(EmbeddingType, Arc<dyn TextEmbedderTrait>)>>> =
+        LazyLock::new(|| Mutex::new(None));
+
+    if embedding_type.is_bm25() {
+        return None;
+    }
+
+    let mut cached = EMBEDDER.lock().unwrap_or_else(|err| err.into_inner());
+    if let Some((cached_type, embedder)) = cached.as_ref() {
+        if *cached_type == embedding_type {
+            return Some(Arc::clone(embedder));
+        }
+    }
+
+    match create_embedder(embedding_type) {
+        Ok(embedder) => {
+            let embedder: Arc<dyn TextEmbedderTrait> = Arc::from(embedder);
+            cached.replace((embedding_type, Arc::clone(&embedder)));
+            Some(embedder)
+        },
+        Err(err) => {
+            warn!(?err, "Failed to create embedder, falling back to term matching");
+            None
+        },
+    }
+}
+
+/// [KnowledgeBackendImpl] for [crate::util::knowledge_backend::KnowledgeBackend::Sqlite]
+pub struct SqliteBackend;
+
+impl SqliteBackend {
+    /// Runs `f` against the store of `agent`. Sqlite and the embedder block, so the store is only
+    /// ever used on a blocking thread.
+    ///
+    /// Anything previously indexed with the semantic search backend for this agent is brought
+    /// over the first time the store is opened for it.
+    pub async fn with_store<T, F>(os: &Os, agent: Option<&Agent>, f: F) -> Result<T>
+    where
+        T: Send + 'static,
+        F: FnOnce(&SqliteKnowledgeStore) -> Result<T> + Send + 'static,
+    {
+        let path = directories::knowledge_db_path(os)?;
+        let agent_name = agent.map_or(DEFAULT_AGENT_NAME, |a| a.name.as_str()).to_string();
+        let embedding_type = os
+            .database
+            .settings
+            .get_string(Setting::KnowledgeIndexType)
+            .and_then(|s| EmbeddingType::from_str(&s))
+            .unwrap_or_default();
+
+        let (store, needs_legacy_import) = tokio::task::spawn_blocking(move || {
+            let store = SqliteKnowledgeStore::open(&path, &agent_name, embedding_type)?;
+            let needs_legacy_import = store.needs_legacy_import()?;
+            Ok::<_, eyre::Report>((store, needs_legacy_import))
+        })
+        .await??;
+
+        let contexts = if needs_legacy_import {
+            Some(Self::legacy_contexts(os, agent).await?)
+        } else {
+            None
+        };
+
+        tokio::task::spawn_blocking(move || {
+            if let Some(contexts) = contexts {
+                let imported = store.import_legacy_contexts(&contexts)?;
+                if imported > 0 {
+                    info!(
+                        imported,
+                        "Imported existing knowledge base entries into the sqlite backend"
+                    );
+                }
+            }
+            f(&store)
+        })
+        .await?
+    }
+
+    /// Contexts indexed with the semantic search backend for `agent`
+    async fn legacy_contexts(os: &Os, agent: Option<&Agent>) -> Result<Vec<KnowledgeContext>> {
+        let contexts = if os.fs.exists(directories::agent_knowledge_dir(os, agent)?) {
+            let legacy_store = KnowledgeStore::get_async_instance(os, agent)
+                .await
+                .map_err(|e| eyre::eyre!("Failed to access knowledge base: {}", e))?;
+            let contexts = legacy_store.lock().await.get_all().await;
+            contexts.unwrap_or_else(|err| {
+                warn!(%err, "Failed to read existing knowledge contexts");
+                Vec::new()
+            })
+        } else {
+            Vec::new()
+        };
+        Ok(contexts)
+    }
+}
+
+#[async_trait::async_trait]
+impl KnowledgeBackendImpl for SqliteBackend {
+    async fn validate(&self, os: &Os, knowledge: &Knowledge) -> Result<()> {
+        let path = match knowledge {
+            Knowledge::Add(add) if !add.value.contains('\n') => &add.value,
+            Knowledge::Update(update) if !update.path.is_empty() => &update.path,
+            _ => return Ok(()),
+        };
+
+        let path = sanitize_path_tool_arg(os, path);
+        let max_files = os
+            .database
+            .settings
+            .get_int_or(Setting::KnowledgeMaxFiles, SemanticSearchConfig::default().max_files);
+        let file_count = tokio::task::spawn_blocking({
+            let path = path.clone();
+            move || path.is_dir().then(|| collect_files(&path).len())
+        })
+        .await?;
+        if let Some(file_count) = file_count.filter(|count| *count > max_files) {
+            eyre::bail!(
+                "Directory '{}' contains {} files, which exceeds the limit of {}. Increase it with `q settings knowledge.maxFiles <number>`",
+                path.display(),
+                file_count,
+                max_files
+            );
+        }
+        Ok(())
+    }
+
+    async fn invoke(&self, os: &Os, knowledge: &Knowledge, agent: Option<&Agent>, session_id: &str) -> Result<String> {
+        // The store is used on a blocking thread, so path arguments are resolved up front
+        let path = match knowledge {
+            Knowledge::Add(add) => sanitize_path_tool_arg(os, &add.value),
+            Knowledge::Remove(remove) => sanitize_path_tool_arg(os, &remove.path),
+            Knowledge::Update(update) => sanitize_path_tool_arg(os, &update.path),
+            _ => PathBuf::new(),
+        };
+        let knowledge = knowledge.clone();
+        let session_id = session_id.to_string();
+        Self::with_store(os, agent, move |store| execute(store, &knowledge, &path, &session_id)).await
+    }
+}
+
+/// Executes a knowledge tool command against `store`, where `path` is the resolved path argument
+/// of the command
+fn execute(store: &SqliteKnowledgeStore, knowledge: &Knowledge, path: &Path, session_id: &str) -> Result<String> {
+    let result = match knowledge {
+        Knowledge::Add(add) => {
+            if path.exists() {
+                match store.add_path(&add.name, path, Some(session_id)) {
+                    Ok(ids) if ids.is_empty() => {
+                        format!("No readable text content found at '{}'", path.display())
+                    },
+                    Ok(ids) if ids.len() == 1 => {
+                        format!("Added '{}' to knowledge base with ID: {}", add.name, ids[0])
+                    },
+                    Ok(ids) => format!("Added '{}' to knowledge base ({} files)", add.name, ids.len()),
+                    Err(e) => format!("Failed to add to knowledge base: {}", e),
+                }
+            } else {
+                match store.add(&add.name, None, &add.value, Some(session_id)) {
+                    Ok(id) => format!("Added '{}' to knowledge base with ID: {}", add.name, id),
+                    Err(e) => format!("Failed to add to knowledge base: {}", e),
+                }
+            }
+        },
+        Knowledge::Remove(remove) => {
+            let removed = if !remove.context_id.is_empty() {
+                store.remove_by_id(&remove.context_id)
+            } else if !remove.name.is_empty() {
+                store.remove_by_name(&remove.name)
+            } else {
+                store.remove_by_path(path.to_string_lossy().as_ref())
+            };
+            match removed {
+                Ok(0) => "No matching knowledge base entries found".to_string(),
+                Ok(count) => format!("Removed {} entries from knowledge base", count),
+                Err(e) => format!("Failed to remove from knowledge base: {}", e),
+            }
+        },
+        Knowledge::Update(update) => {
+            if update.path.is_empty() {
+                return Ok("Error: No path provided for update. Please specify a path to update with.".to_string());
+            }
+            let path_str = path.to_string_lossy().to_string();
+
+            let existing = store.entries()?.into_iter().find(|entry| {
+                if !update.context_id.is_empty() {
+                    entry.id == update.context_id
+                } else if !update.name.is_empty() {
+                    entry.name == update.name
+                } else {
+                    entry.source_path.as_deref() == Some(path_str.as_str())
+                }
+            });
+
+            match existing {
+                Some(entry) => match store.replace_path(&entry.name, path, Some(session_id)) {
+                    Ok(ids) => format!(
+                        "Updated '{}' using path '{}' ({} entries)",
+                        entry.name,
+                        update.path,
+                        ids.len()
+                    ),
+                    Err(e) => format!("Failed to update knowledge base entry: {}", e),
+                },
+                None => "No matching knowledge base entry found to update".to_string(),
+            }
+        },
+        Knowledge::Clear(_) => match store.clear() {
+            Ok(count) => format!("Cleared {} knowledge base entries", count),
+            Err(e) => format!("Failed to clear knowledge base: {}", e),
+        },
+        Knowledge::Search(search) => {
+            match store.search(&search.query, search.context_id.as_deref(), MAX_SEARCH_RESULTS) {
+                Ok(results) if results.is_empty() => {
+                    format!("No matching entries found for query: \"{}\"", search.query)
+                },
+                Ok(results) => {
+                    let mut output = format!("Search results for \"{}\":\n\n", search.query);
+                    for (entry, _) in results {
+                        let source = entry.source_path.as_deref().unwrap_or("text");
+                        let content: String = entry.content.chars().take(MAX_RESULT_CHARS).collect();
+                        output.push_str(&format!("[{}] ({})\n{}\n\n", entry.name, source, content));
+                    }
+                    output
+                },
+                Err(e) => format!("Search failed: {}", e),
+            }
+        },
+        Knowledge::Show => match store.entries() {
+            Ok(entries) if entries.is_empty() => "No knowledge base entries found".to_string(),
+            Ok(entries) => {
+                let mut output = String::from("Knowledge base entries:\n");
+                for entry in entries {
+                    output.push_str(&format!(
+                        "- ID: {}\n  Name: {}\n  Source: {}\n  Created: {}\n  Session: {}\n\n",
+                        entry.id,
+                        entry.name,
+                        entry.source_path.as_deref().unwrap_or("text"),
+                        entry.created_at.format("%Y-%m-%d %H:%M:%S"),
+                        entry.session_id.as_deref().unwrap_or("-"),
+                    ));
+                }
+                output
+            },
+            Err(e) => format!("Failed to get knowledge base entries: {}", e),
+        },
+        Knowledge::Status | Knowledge::Cancel(_) => {
+            "No active operations. The sqlite knowledge backend indexes content immediately.".to_string()
+        },
+    };
+
+    Ok(result)
+}
+
+/// Files to index for `path`: the path itself if it is a file, otherwise every non-hidden file
+/// beneath it.
+fn collect_files(path: &Path) -> Vec<PathBuf> {
+    if path.is_file() {
+        return vec![path.to_path_buf()];
+    }
+
+    walkdir::WalkDir::new(path)
+        .into_iter()
+        .filter_entry(|entry| entry.depth() == 0 || !entry.file_name().to_string_lossy().starts_with('.'))
+        .filter_map(|entry| entry.ok())
+        .filter(|entry| entry.file_type().is_file())
+        .map(|entry| entry.into_path())
+        .collect()
+}
+
+fn encode_embedding(embedding: &[f32]) -> Vec<u8> {
+    embedding.iter().flat_map(|value| value.to_le_bytes()).collect()
+}
+
+fn decode_embedding(bytes: &[u8]) -> Vec<f32> {
+    bytes
+        .chunks_exact(4)
+        .map(|chunk| f32::from_le_bytes([chunk[0], chunk[1], chunk[2], chunk[3]]))
+        .collect()
+}
+
+fn cosine_similarity(a: &[f32], b: &[f32]) -> f32 {
+    if a.len() != b.len() || a.is_empty() {
+        return 0.0;
+    }
+    let dot: f32 = a.iter().zip(b).map(|(x, y)| x * y).sum();
+    let norm_a = a.iter().map(|x| x * x).sum::<f32>().sqrt();
+    let norm_b = b.iter().map(|x| x * x).sum::<f32>().sqrt();
+    if norm_a == 0.0 || norm_b == 0.0 {
+        return 0.0;
+    }
+    dot / (norm_a * norm_b)
+}
+
+fn terms(text: &str) -> Vec<String> {
+    text.split(|c: char| !c.is_alphanumeric())
+        .filter(|term| !term.is_empty())
+        .map(str::to_lowercase)
+        .collect()
+}
+
+/// Fraction of query terms that appear in `content`
+fn term_match_score(query_terms: &[String], content: &str) -> f32 {
+    if query_terms.is_empty() {
+        return 0.0;
+    }
+    let content_terms = terms(content);
+    let matched = query_terms.iter().filter(|term| content_terms.contains(term)).count();
+    matched as f32 / query_terms.len() as f32
+}
+
+#[cfg(test)]
+mod tests {
+    use semantic_search_client::embedding::MockTextEmbedder;
+
+    use super::*;
+
+    fn store(embedder: Option<Arc<dyn TextEmbedderTrait>>) -> SqliteKnowledgeStore {
+        SqliteKnowledgeStore::with_connection(Connection::open_in_memory().unwrap(), "test_agent", embedder).unwrap()
+    }
+
+    #[test]
+    fn test_schema_migration_is_idempotent() {
+        let mut store = store(None);
+        store.migrate_schema().unwrap();
+        let version: i32 = store
+            .conn
+            .query_row("PRAGMA user_version", [], |row| row.get(0))
+            .unwrap();
+        assert_eq!(version, SCHEMA_VERSION);
+    }
+
+    #[test]
+    fn test_add_and_search_by_terms() {
+        let store = store(None);
+        store
+            .add("rust", None, "Rust uses ownership to manage memory", Some("session"))
+            .unwrap();
+        store.add("python", None, "Python is garbage collected", None).unwrap();
+
+        let results = store.search("how does ownership work", None, 5).unwrap();
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].0.name, "rust");
+        assert_eq!(results[0].0.session_id.as_deref(), Some("session"));
+
+        let results = store.search("memory", Some("python"), 5).unwrap();
+        assert!(results.is_empty());
+    }
+
+    #[test]
+    fn test_embeddings_are_stored_and_searched() {
+        let store = store(Some(Arc::new(MockTextEmbedder::new(8))));
+        store.add("note", None, "some note", None).unwrap();
+
+        let embedding: Vec<u8> = store
+            .conn
+            .query_row("SELECT embedding FROM knowledge_entries", [], |row| row.get(0))
+            .unwrap();
+        assert_eq!(embedding.len(), 8 * 4);
+
+        let results = store.search("some note", None, 5).unwrap();
+        assert_eq!(results.len(), 1);
+        assert!((results[0].1 - 1.0).abs() < 1e-5);
+    }
+
+    #[test]
+    fn test_entries_are_scoped_to_agent() {
+        let conn = Connection::open_in_memory().unwrap();
+        let store = SqliteKnowledgeStore::with_connection(conn, "a", None).unwrap();
+        store.add("note", None, "content", None).unwrap();
+
+        let SqliteKnowledgeStore { conn, .. } = store;
+        let other = SqliteKnowledgeStore::with_connection(conn, "b", None).unwrap();
+        assert!(other.entries().unwrap().is_empty());
+        assert_eq!(other.clear().unwrap(), 0);
+    }
+
+    #[test]
+    fn test_remove() {
+        let store = store(None);
+        let id = store.add("a", None, "first", None).unwrap();
+        store.add("b", Some("/tmp/dir/file.txt"), "second", None).unwrap();
+        store.add("c", None, "third", None).unwrap();
+
+        assert_eq!(store.remove_by_id(&id).unwrap(), 1);
+        assert_eq!(store.remove_by_path("/tmp/dir").unwrap(), 1);
+        assert_eq!(store.remove_by_name("c").unwrap(), 1);
+        assert!(store.entries().unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_import_legacy_contexts() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join("notes.md"), "remember the milk").unwrap();
+        std::fs::write(dir.path().join(".hidden"), "ignored").unwrap();
+
+        let context = KnowledgeContext::new(
+            "ctx".to_string(),
+            "notes",
+            "description",
+            true,
+            Some(dir.path().to_string_lossy().to_string()),
+            (Vec::new(), Vec::new()),
+            1,
+            EmbeddingType::Fast,
+        );
+
+        let store = store(None);
+        assert!(store.needs_legacy_import().unwrap());
+        assert_eq!(store.import_legacy_contexts(&[context]).unwrap(), 1);
+        assert!(!store.needs_legacy_import().unwrap());
+
+        let entries = store.entries().unwrap();
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0].name, "notes");
+        assert_eq!(entries[0].content, "remember the milk");
+    }
+
+    /// Fails to embed anything, to exercise the error paths of the store
+    struct FailingEmbedder;
+
+    impl TextEmbedderTrait for FailingEmbedder {
+        fn embed(&self, _text: &str) -> semantic_search_client::error::Result<Vec<f32>> {
+            Err(semantic_search_client::error::SemanticSearchError::EmbeddingError(
+                "failed".to_string(),
+            ))
+        }
+
+        fn embed_batch(&self, texts: &[String]) -> semantic_search_client::error::Result<Vec<Vec<f32>>> {
+            texts.iter().map(|text| self.embed(text)).collect()
+        }
+    }
+
+    #[test]
+    fn test_replace_path() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join("notes.md"), "new notes").unwrap();
+
+        let store = store(None);
+        store.add("notes", None, "old notes", None).unwrap();
+        assert_eq!(store.replace_path("notes", dir.path(), None).unwrap().len(), 1);
+        let entries = store.entries().unwrap();
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0].content, "new notes");
+
+        // The existing entries are kept when the new ones can't be added
+        let SqliteKnowledgeStore { conn, .. } = store;
+        let store = SqliteKnowledgeStore::with_connection(conn, "test_agent", Some(Arc::new(FailingEmbedder))).unwrap();
+        std::fs::write(dir.path().join("notes.md"), "newer notes").unwrap();
+        assert!(store.replace_path("notes", dir.path(), None).is_err());
+        let entries = store.entries().unwrap();
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0].content, "new notes");
+    }
+
+    #[test]
+    fn test_embedding_round_trip() {
+        let embedding = vec![0.5, -1.25, 3.0];
+        assert_eq!(decode_embedding(&encode_embedding(&embedding)), embedding);
+    }
+}
@@ -10,12 +10,15 @@ use semantic_search_client::client::AsyncSemanticSearchClient;
 use semantic_search_client::embedding::EmbeddingType;
 use semantic_search_client::types::{
     AddContextRequest,
+    IndexSince,
     SearchResult,
 };
 use tokio::sync::Mutex;
 use uuid::Uuid;
 
 use crate::cli::DEFAULT_AGENT_NAME;
+use crate::cli::chat::tools::plain_output;
+use crate::cli::chat::util::ui::glyph;
 use crate::os::Os;
 use crate::util::directories;
 
@@ -108,6 +111,8 @@ impl std::error::Error for KnowledgeError {}
 pub struct KnowledgeStore {
     agent_client: AsyncSemanticSearchClient,
     agent_dir: PathBuf,
+    /// Whether messages are written without emoji, see [plain_output]
+    plain: bool,
 }
 
 impl KnowledgeStore {
@@ -140,7 +145,7 @@ impl KnowledgeStore {
 
             if needs_reinit {
                 // Check for migration before initializing the client
-                Self::migrate_legacy_knowledge_base(&current_agent_dir).await;
+                Self::migrate_legacy_knowledge_base(&current_agent_dir, plain_output(os)).await;
 
                 let store = Self::new_with_os_settings(os, agent)
                     .await
@@ -148,12 +153,15 @@ impl KnowledgeStore {
                 *instance_guard = Some(Arc::new(Mutex::new(store)));
             }
 
-            Ok(instance_guard.as_ref().unwrap().clone())
+            let store = instance_guard.as_ref().unwrap().clone();
+            // Pick up changes to chat.plainOutput made since the store was created
+            store.lock().await.plain = plain_output(os);
+            Ok(store)
         }
     }
 
     /// Migrate legacy knowledge base from old location if needed
-    async fn migrate_legacy_knowledge_base(agent_dir: &PathBuf) -> bool {
+    async fn migrate_legacy_knowledge_base(agent_dir: &PathBuf, plain: bool) -> bool {
         let mut migrated = false;
 
         // Extract agent identifier from the directory path (last component)
@@ -173,7 +181,8 @@ impl KnowledgeStore {
             }
             if std::fs::rename(&old_flat_dir, agent_dir).is_ok() {
                 println!(
-                    "✅ Migrated knowledge base from {} to {}",
+                    "{}Migrated knowledge base from {} to {}",
+                    glyph(plain, "✅ ", ""),
                     old_flat_dir.display(),
                     agent_dir.display()
                 );
@@ -212,6 +221,7 @@ impl KnowledgeStore {
         os: &crate::os::Os,
         base_dir: PathBuf,
     ) -> semantic_search_client::config::SemanticSearchConfig {
+        use semantic_search_client::ChunkStrategy;
         use semantic_search_client::config::SemanticSearchConfig;
         use semantic_search_client::embedding::EmbeddingType;
 
@@ -236,6 +246,17 @@ impl KnowledgeStore {
             .database
             .settings
             .get_int_or(Setting::KnowledgeMaxFiles, default_config.max_files);
+        let idle_unload_timeout_secs = os.database.settings.get_int_or(
+            Setting::KnowledgeIdleUnloadTimeout,
+            default_config.idle_unload_timeout_secs as usize,
+        ) as u64;
+
+        let chunk_strategy = os.database.settings.get_string(Setting::KnowledgeChunkStrategy);
+        let chunk_strategy = match chunk_strategy.as_deref() {
+            Some("function") => Some(ChunkStrategy::Function),
+            Some("paragraph") => Some(ChunkStrategy::Paragraph),
+            _ => None,
+        };
 
         // Get embedding type from settings
         let embedding_type = os
@@ -248,7 +269,9 @@ impl KnowledgeStore {
         SemanticSearchConfig {
             chunk_size,
             chunk_overlap,
+            chunk_strategy,
             max_files,
+            idle_unload_timeout_secs,
             embedding_type,
             base_dir,
             ..default_config
@@ -266,6 +289,7 @@ impl KnowledgeStore {
         let store = Self {
             agent_client,
             agent_dir,
+            plain: plain_output(os),
         };
         Ok(store)
     }
@@ -275,7 +299,7 @@ impl KnowledgeStore {
         let path_buf = std::path::PathBuf::from(path_str);
         let canonical_path = path_buf
             .canonicalize()
-            .map_err(|_io_error| format!("❌ Path does not exist: {}", path_str))?;
+            .map_err(|_io_error| format!("{}Path does not exist: {}", glyph(self.plain, "❌ ", ""), path_str))?;
 
         // Use provided description or generate default
         let description = options
@@ -323,20 +347,26 @@ impl KnowledgeStore {
         match self.agent_client.add_context(request).await {
             Ok((operation_id, _)) => {
                 let mut message = format!(
-                    "🚀 Started indexing '{}'\n📁 Path: {}\n🆔 Operation ID: {}",
+                    "{}Started indexing '{}'\n{}Path: {}\n{}Operation ID: {}",
+                    glyph(self.plain, "🚀 ", ""),
                     name,
+                    glyph(self.plain, "📁 ", ""),
                     canonical_path.display(),
+                    glyph(self.plain, "🆔 ", ""),
                     &operation_id.to_string()[..8]
                 );
                 if !options.include_patterns.is_empty() || !options.exclude_patterns.is_empty() {
-                    message.push_str("\n📋 Pattern filtering applied:");
+                    message.push_str(&format!("\n{}Pattern filtering applied:", glyph(self.plain, "📋 ", "")));
                     if !options.include_patterns.is_empty() {
                         message.push_str(&format!("\n   Include: {}", options.include_patterns.join(", ")));
                     }
                     if !options.exclude_patterns.is_empty() {
                         message.push_str(&format!("\n   Exclude: {}", options.exclude_patterns.join(", ")));
                     }
-                    message.push_str("\n✅ Only matching files will be indexed");
+                    message.push_str(&format!(
+                        "\n{}Only matching files will be indexed",
+                        glyph(self.plain, "✅ ", "")
+                    ));
                 }
                 Ok(message)
             },
@@ -436,7 +466,10 @@ impl KnowledgeStore {
     pub async fn clear(&mut self) -> Result<String, String> {
         match self.agent_client.clear_all().await {
             Ok((operation_id, _cancel_token)) => Ok(format!(
-                "🚀 Started clearing all contexts in background.\n📊 Use 'knowledge status' to check progress.\n🆔 Operation ID: {}",
+                "{}Started clearing all contexts in background.\n{}Use 'knowledge status' to check progress.\n{}Operation ID: {}",
+                glyph(self.plain, "🚀 ", ""),
+                glyph(self.plain, "📊 ", ""),
+                glyph(self.plain, "🆔 ", ""),
                 &operation_id.to_string()[..8]
             )),
             Err(e) => Err(format!("Failed to start clear operation: {}", e)),
@@ -446,7 +479,11 @@ impl KnowledgeStore {
     /// Clear all contexts immediately (synchronous operation)
     pub async fn clear_immediate(&mut self) -> Result<String, String> {
         match self.agent_client.clear_all_immediate().await {
-            Ok(count) => Ok(format!("✅ Successfully cleared {} knowledge base entries", count)),
+            Ok(count) => Ok(format!(
+                "{}Successfully cleared {} knowledge base entries",
+                glyph(self.plain, "✅ ", ""),
+                count
+            )),
             Err(e) => Err(format!("Failed to clear knowledge base: {}", e)),
         }
     }
@@ -515,6 +552,36 @@ impl KnowledgeStore {
         }
     }
 
+    /// Re-index only the files of the context for a path that changed since `since`
+    pub async fn update_by_path_since(&mut self, path_str: &str, since: IndexSince) -> Result<String, String> {
+        let response = self
+            .agent_client
+            .index_since(path_str, since)
+            .await
+            .map_err(|e| e.to_string())?;
+
+        let mut message = format!(
+            "{}Considered {} files: {} changed",
+            glyph(self.plain, "🔍 ", ""),
+            response.files_considered,
+            response.files_embedded
+        );
+        if response.files_removed > 0 {
+            message.push_str(&format!(", {} deleted", response.files_removed));
+        }
+        match response.operation {
+            Some((operation_id, _)) => message.push_str(&format!(
+                "\n{}Started re-indexing '{}'\n{}Operation ID: {}",
+                glyph(self.plain, "🚀 ", ""),
+                path_str,
+                glyph(self.plain, "🆔 ", ""),
+                &operation_id.to_string()[..8]
+            )),
+            None => message.push_str(&format!("\n{}Nothing to re-index", glyph(self.plain, "✅ ", ""))),
+        }
+        Ok(message)
+    }
+
     /// Update context by ID
     pub async fn update_context_by_id(&mut self, context_id: &str, path_str: &str) -> Result<String, String> {
         let contexts = self.get_all().await.map_err(|e| e.clone())?;
@@ -1,5 +1,7 @@
 pub mod consts;
 pub mod directories;
+pub mod knowledge_backend;
+pub mod knowledge_sqlite;
 pub mod knowledge_store;
 pub mod open;
 pub mod pattern_matching;
@@ -6,6 +6,13 @@ pub fn terminate_process(pid: Pid) -> Result<(), String> {
     nix::sys::signal::kill(nix_pid, Signal::SIGTERM).map_err(|e| format!("Failed to terminate process: {}", e))
 }
 
+/// Kills a process along with every process it started. The process must lead its own process
+/// group, e.g. by being spawned with `process_group(0)`.
+pub fn terminate_process_tree(pid: Pid) -> Result<(), String> {
+    let nix_pid = nix::unistd::Pid::from_raw(pid.as_u32() as i32);
+    nix::sys::signal::killpg(nix_pid, Signal::SIGKILL).map_err(|e| format!("Failed to terminate process group: {}", e))
+}
+
 #[cfg(test)]
 #[cfg(not(windows))]
 mod tests {
@@ -50,6 +57,29 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_terminate_process_tree() {
+        use std::os::unix::process::CommandExt;
+
+        // The shell leads its own group and starts a sleep of its own
+        let mut child = Command::new("sh")
+            .args(["-c", "sleep 30 & wait"])
+            .process_group(0)
+            .spawn()
+            .expect("Failed to spawn test process");
+        let pid = Pid::from_u32(child.id());
+        std::thread::sleep(Duration::from_millis(100));
+
+        let result = terminate_process_tree(pid);
+        assert!(result.is_ok(), "Process tree termination failed: {:?}", result.err());
+
+        std::thread::sleep(Duration::from_millis(100));
+        assert!(
+            matches!(child.try_wait(), Ok(Some(_))),
+            "Process is still running after termination"
+        );
+    }
+
     #[test]
     fn test_terminate_nonexistent_process() {
         // Use a likely invalid PID
@@ -28,6 +28,24 @@ pub fn terminate_process(pid: Pid) -> Result<(), String> {
     }
 }
 
+/// Kills a process along with every process it started, using `taskkill /T`
+pub fn terminate_process_tree(pid: Pid) -> Result<(), String> {
+    let status = std::process::Command::new("taskkill")
+        .args(["/T", "/F", "/PID", &pid.as_u32().to_string()])
+        .stdout(std::process::Stdio::null())
+        .stderr(std::process::Stdio::null())
+        .status()
+        .map_err(|e| format!("Failed to run taskkill: {}", e))?;
+    if status.success() {
+        Ok(())
+    } else {
+        Err(format!(
+            "Failed to terminate process tree: taskkill exited with {}",
+            status
+        ))
+    }
+}
+
 struct SafeHandle(HANDLE);
 
 impl SafeHandle {
@@ -2,12 +2,17 @@ use std::path::{
     Path,
     PathBuf,
 };
+use std::time::Duration;
 
 use tokio::sync::mpsc;
-use tokio_util::sync::CancellationToken;
+use tokio_util::sync::{
+    CancellationToken,
+    DropGuard,
+};
 use uuid::Uuid;
 
 use super::background::BackgroundWorker;
+use super::background::file_processor::FileProcessor;
 // Use the new modular structure
 use super::context::ContextManager;
 use super::model::ModelDownloader;
@@ -32,6 +37,8 @@ pub struct AsyncSemanticSearchClient {
     job_tx: mpsc::UnboundedSender<IndexingJob>,
     context_manager: ContextManager,
     operation_manager: OperationManager,
+    /// Stops unloading idle contexts when the client is dropped
+    _idle_unload_guard: Option<DropGuard>,
 }
 
 impl AsyncSemanticSearchClient {
@@ -100,6 +107,14 @@ impl AsyncSemanticSearchClient {
 
         tokio::spawn(worker.run());
 
+        let idle_unload_guard = match config.idle_unload_timeout_secs {
+            0 => None,
+            secs => Some(Self::spawn_idle_unloader(
+                context_manager.clone(),
+                Duration::from_secs(secs),
+            )),
+        };
+
         let client = Self {
             base_dir,
             embedder,
@@ -107,12 +122,30 @@ impl AsyncSemanticSearchClient {
             job_tx,
             context_manager,
             operation_manager,
+            _idle_unload_guard: idle_unload_guard,
         };
 
         client.context_manager.load_persistent_contexts().await?;
         Ok(client)
     }
 
+    /// Periodically unloads contexts that have been idle for `idle_timeout`, until the returned
+    /// guard is dropped.
+    fn spawn_idle_unloader(context_manager: ContextManager, idle_timeout: Duration) -> DropGuard {
+        let cancel_token = CancellationToken::new();
+        let cancelled = cancel_token.clone();
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval((idle_timeout / 4).max(Duration::from_secs(1)));
+            loop {
+                tokio::select! {
+                    _ = cancelled.cancelled() => break,
+                    _ = interval.tick() => context_manager.unload_idle_contexts(idle_timeout).await,
+                }
+            }
+        });
+        cancel_token.drop_guard()
+    }
+
     /// Creates a new AsyncSemanticSearchClient with default configuration.
     ///
     /// This is a convenience method that creates a client with default settings
@@ -319,6 +352,110 @@ impl AsyncSemanticSearchClient {
         Ok((operation_id, cancel_token))
     }
 
+    /// Re-indexes only the files of an existing context that changed since a point in time.
+    ///
+    /// If the path is inside a git repository, the files reported by `git diff --name-only`
+    /// (plus untracked files) take precedence; otherwise files are selected by modification time.
+    /// The chunks of changed and deleted files are removed from the context, and the changed files
+    /// are embedded again in the background. The context's include and exclude patterns apply.
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - The source path of a context that was added with
+    ///   [AsyncSemanticSearchClient::add_context]
+    /// * `since` - A time, or a git ref when the path is inside a git repository
+    ///
+    /// # Returns
+    ///
+    /// Returns an `IndexSinceResponse` with the number of files considered, embedded and removed,
+    /// and the operation ID and cancellation token of the background re-index if any files
+    /// changed.
+    ///
+    /// # Errors
+    ///
+    /// This method will return an error if:
+    /// - The specified path does not exist or is not accessible
+    /// - No context was added for the path
+    /// - `since` is a git ref but the path is not inside a git repository, or git fails
+    /// - The background worker is unavailable
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use semantic_search_client::AsyncSemanticSearchClient;
+    /// use semantic_search_client::types::IndexSince;
+    ///
+    /// # async fn example() -> Result<(), Box<dyn std::error::Error>> {
+    /// let client = AsyncSemanticSearchClient::new_with_default_dir().await?;
+    ///
+    /// let since = "HEAD~5".parse::<IndexSince>()?;
+    /// let response = client.index_since("/path/to/repo", since).await?;
+    /// println!(
+    ///     "{} files considered, {} re-embedded",
+    ///     response.files_considered, response.files_embedded
+    /// );
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn index_since(&self, path: impl AsRef<Path>, since: IndexSince) -> Result<IndexSinceResponse> {
+        let path = path.as_ref();
+        let canonical_path = path.canonicalize().map_err(|_e| {
+            SemanticSearchError::InvalidPath(format!("Path does not exist or is not accessible: {}", path.display()))
+        })?;
+
+        let context = self
+            .context_manager
+            .get_context_by_path(&canonical_path.to_string_lossy())
+            .await
+            .ok_or_else(|| SemanticSearchError::ContextNotFound(format!("No context for path {}", path.display())))?;
+
+        let include_patterns = (!context.include_patterns.is_empty()).then(|| context.include_patterns.clone());
+        let exclude_patterns = (!context.exclude_patterns.is_empty()).then(|| context.exclude_patterns.clone());
+        let changed_files =
+            FileProcessor::find_changed_files(&canonical_path, &since, &include_patterns, &exclude_patterns)
+                .await
+                .map_err(SemanticSearchError::InvalidArgument)?;
+
+        let mut response = IndexSinceResponse {
+            operation: None,
+            files_considered: changed_files.considered,
+            files_embedded: changed_files.changed.len(),
+            files_removed: changed_files.removed.len(),
+        };
+        if changed_files.changed.is_empty() && changed_files.removed.is_empty() {
+            return Ok(response);
+        }
+
+        let operation_id = Uuid::new_v4();
+        let cancel_token = CancellationToken::new();
+
+        self.operation_manager
+            .register_operation(
+                operation_id,
+                OperationType::Indexing {
+                    name: context.name.clone(),
+                    path: canonical_path.to_string_lossy().to_string(),
+                },
+                cancel_token.clone(),
+            )
+            .await;
+
+        let job = IndexingJob::UpdateFiles {
+            id: operation_id,
+            cancel: cancel_token.clone(),
+            context_id: context.id,
+            changed: changed_files.changed,
+            removed: changed_files.removed,
+        };
+
+        self.job_tx
+            .send(job)
+            .map_err(|_send_error| SemanticSearchError::OperationFailed("Background worker unavailable".to_string()))?;
+
+        response.operation = Some((operation_id, cancel_token));
+        Ok(response)
+    }
+
     /// Retrieves all available contexts in the knowledge base.
     ///
     /// This method returns a list of all contexts (both persistent and volatile)
@@ -591,6 +728,19 @@ impl AsyncSemanticSearchClient {
             .await
     }
 
+    /// Drops a context's vectors from memory.
+    ///
+    /// Any unsaved data is written to disk first, and the context is loaded again
+    /// transparently the next time it is searched. Contexts are also unloaded automatically
+    /// after `idle_unload_timeout_secs` without use.
+    ///
+    /// # Arguments
+    ///
+    /// * `context_id` - The unique identifier of the context to unload
+    pub async fn unload_context(&self, context_id: &str) -> Result<()> {
+        self.context_manager.unload_context(context_id).await
+    }
+
     /// Retrieves a context by its source path.
     ///
     /// This method finds a context that was created from the specified file or directory path.
@@ -1,8 +1,10 @@
 use std::path::PathBuf;
 use std::sync::Arc;
 
+use chrono::Utc;
 use tokio::sync::{
     Semaphore,
+    SemaphorePermit,
     mpsc,
 };
 use tokio_util::sync::CancellationToken;
@@ -49,7 +51,7 @@ impl BackgroundWorker {
     ) -> crate::error::Result<Self> {
         let embedder = embedder_factory::create_embedder(config.embedding_type)?;
         let file_processor = FileProcessor::new(config.clone());
-        let context_creator = ContextCreator::new();
+        let context_creator = ContextCreator::with_batch_size(config.embedding_batch_size);
 
         Ok(Self {
             job_rx,
@@ -93,6 +95,16 @@ impl BackgroundWorker {
 
                     self.process_add_directory(id, params, cancel).await;
                 },
+                IndexingJob::UpdateFiles {
+                    id,
+                    cancel,
+                    context_id,
+                    changed,
+                    removed,
+                } => {
+                    self.process_update_files(id, &context_id, &changed, &removed, cancel)
+                        .await;
+                },
                 IndexingJob::Clear { id, cancel } => {
                     self.process_clear(id, cancel).await;
                 },
@@ -114,14 +126,35 @@ impl BackgroundWorker {
             return;
         }
 
+        let Some(_permit) = self.acquire_indexing_permit(operation_id).await else {
+            return;
+        };
+
+        let result = self.perform_indexing(operation_id, params, cancel_token).await;
+
+        match result {
+            Ok(context_id) => {
+                debug!("Successfully indexed context: {}", context_id);
+                self.mark_operation_completed(operation_id).await;
+            },
+            Err(e) => {
+                tracing::error!("Indexing failed: {}", e);
+                self.mark_operation_failed(operation_id, e).await;
+            },
+        }
+    }
+
+    /// Wait for one of the [MAX_CONCURRENT_OPERATIONS] indexing slots, marking the operation as
+    /// failed if none can be acquired
+    async fn acquire_indexing_permit(&self, operation_id: Uuid) -> Option<SemaphorePermit<'_>> {
         self.update_operation_status(operation_id, "Waiting in queue...".to_string())
             .await;
 
-        let _permit = match self.indexing_semaphore.try_acquire() {
+        match self.indexing_semaphore.try_acquire() {
             Ok(permit) => {
                 self.update_operation_status(operation_id, "Acquired slot, starting indexing...".to_string())
                     .await;
-                permit
+                Some(permit)
             },
             Err(_) => {
                 self.update_operation_status(
@@ -136,28 +169,15 @@ impl BackgroundWorker {
                     Ok(permit) => {
                         self.update_operation_status(operation_id, "Acquired slot, starting indexing...".to_string())
                             .await;
-                        permit
+                        Some(permit)
                     },
                     Err(_) => {
                         self.mark_operation_failed(operation_id, "Semaphore unavailable".to_string())
                             .await;
-                        return;
+                        None
                     },
                 }
             },
-        };
-
-        let result = self.perform_indexing(operation_id, params, cancel_token).await;
-
-        match result {
-            Ok(context_id) => {
-                debug!("Successfully indexed context: {}", context_id);
-                self.mark_operation_completed(operation_id).await;
-            },
-            Err(e) => {
-                tracing::error!("Indexing failed: {}", e);
-                self.mark_operation_failed(operation_id, e).await;
-            },
         }
     }
 
@@ -269,6 +289,113 @@ impl BackgroundWorker {
         Ok(context_id)
     }
 
+    async fn process_update_files(
+        &self,
+        operation_id: Uuid,
+        context_id: &str,
+        changed: &[PathBuf],
+        removed: &[PathBuf],
+        cancel_token: CancellationToken,
+    ) {
+        debug!(
+            "Processing UpdateFiles job: {} ({} changed, {} removed)",
+            context_id,
+            changed.len(),
+            removed.len()
+        );
+
+        if cancel_token.is_cancelled() {
+            self.mark_operation_cancelled(operation_id).await;
+            return;
+        }
+
+        let Some(_permit) = self.acquire_indexing_permit(operation_id).await else {
+            return;
+        };
+
+        match self
+            .perform_update(operation_id, context_id, changed, removed, &cancel_token)
+            .await
+        {
+            Ok(()) => {
+                debug!(
+                    "Re-indexed {} changed and {} removed files in context {}",
+                    changed.len(),
+                    removed.len(),
+                    context_id
+                );
+                self.mark_operation_completed(operation_id).await;
+            },
+            Err(e) => {
+                tracing::error!("Re-indexing failed: {}", e);
+                self.mark_operation_failed(operation_id, e).await;
+            },
+        }
+    }
+
+    async fn perform_update(
+        &self,
+        operation_id: Uuid,
+        context_id: &str,
+        changed: &[PathBuf],
+        removed: &[PathBuf],
+        cancel_token: &CancellationToken,
+    ) -> std::result::Result<(), String> {
+        let embedding_type = self
+            .context_manager
+            .get_contexts_ref()
+            .read()
+            .await
+            .get(context_id)
+            .map(|context| context.embedding_type)
+            .ok_or_else(|| format!("Context '{}' not found", context_id))?;
+
+        let items = self
+            .file_processor
+            .process_files(changed, operation_id, cancel_token, &self.operation_manager)
+            .await?;
+
+        if cancel_token.is_cancelled() {
+            return Err("Failed: Operation was cancelled before updating the context".to_string());
+        }
+
+        let stale_paths = changed
+            .iter()
+            .chain(removed)
+            .map(|path| path.to_string_lossy().to_string())
+            .collect();
+
+        self.context_creator
+            .update_context(
+                context_id,
+                &items,
+                &stale_paths,
+                embedding_type,
+                operation_id,
+                cancel_token,
+                &self.operation_manager,
+                &*self.embedder,
+                &self.context_manager,
+            )
+            .await?;
+
+        let persistent = {
+            let mut contexts = self.context_manager.get_contexts_ref().write().await;
+            contexts.get_mut(context_id).is_some_and(|context| {
+                context.updated_at = Utc::now();
+                context.persistent
+            })
+        };
+        if persistent {
+            self.context_manager
+                .save_contexts_metadata(&self.base_dir)
+                .await
+                .map_err(|e| format!("Failed to save contexts metadata: {}", e))?;
+        }
+
+        Ok(())
+    }
+
     async fn process_clear(&self, operation_id: Uuid, cancel_token: CancellationToken) {
         debug!("Processing Clear job");
 
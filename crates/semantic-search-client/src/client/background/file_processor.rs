@@ -1,11 +1,28 @@
-use std::path::Path;
+use std::path::{
+    Path,
+    PathBuf,
+};
+use std::process::Command;
+use std::time::UNIX_EPOCH;
 
 use tokio_util::sync::CancellationToken;
 use uuid::Uuid;
 
 use super::super::operation::OperationManager;
 use crate::config::SemanticSearchConfig;
-use crate::processing::process_file_with_config;
+use crate::processing::process_file_with_strategy;
+use crate::types::IndexSince;
+
+/// Files of a context that changed since a point in time
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct ChangedFiles {
+    /// Number of files checked for changes
+    pub considered: usize,
+    /// Files that were added or modified
+    pub changed: Vec<PathBuf>,
+    /// Files that were deleted
+    pub removed: Vec<PathBuf>,
+}
 
 /// File processor for handling directory operations
 pub struct FileProcessor {
@@ -138,7 +155,7 @@ impl FileProcessor {
                 continue;
             }
 
-            match process_file_with_config(path, Some(self.config.chunk_size), Some(self.config.chunk_overlap)) {
+            match process_file_with_strategy(path, &self.config.chunk_strategy(), Some(self.config.chunk_overlap)) {
                 Ok(mut file_items) => items.append(&mut file_items),
                 Err(_) => continue,
             }
@@ -160,6 +177,205 @@ impl FileProcessor {
         Ok(items)
     }
 
+    /// Process the given files into items, skipping files that can't be processed
+    pub async fn process_files(
+        &self,
+        files: &[PathBuf],
+        operation_id: Uuid,
+        cancel_token: &CancellationToken,
+        operation_manager: &OperationManager,
+    ) -> std::result::Result<Vec<serde_json::Value>, String> {
+        self.update_operation_status(
+            operation_manager,
+            operation_id,
+            format!("Starting re-indexing ({} files)", files.len()),
+        )
+        .await;
+
+        let mut items = Vec::new();
+        for (index, path) in files.iter().enumerate() {
+            if cancel_token.is_cancelled() {
+                return Err("Operation was cancelled during file processing".to_string());
+            }
+
+            if let Ok(mut file_items) =
+                process_file_with_strategy(path, &self.config.chunk_strategy(), Some(self.config.chunk_overlap))
+            {
+                items.append(&mut file_items);
+            }
+
+            if (index + 1) % 10 == 0 {
+                self.update_operation_progress(
+                    operation_manager,
+                    operation_id,
+                    (index + 1) as u64,
+                    files.len() as u64,
+                    format!("Re-indexing files ({}/{})", index + 1, files.len()),
+                )
+                .await;
+            }
+        }
+
+        Ok(items)
+    }
+
+    /// Find the files under `path` that changed since `since`
+    ///
+    /// If `path` is inside a git work tree, the files reported by `git diff --name-only` against
+    /// the given ref, or against the last commit before the given time, are used together with
+    /// untracked files. Otherwise files are selected by their modification time, which requires
+    /// `since` to be a time. Hidden files and files not matching the patterns are ignored.
+    pub async fn find_changed_files(
+        path: &Path,
+        since: &IndexSince,
+        include_patterns: &Option<Vec<String>>,
+        exclude_patterns: &Option<Vec<String>>,
+    ) -> std::result::Result<ChangedFiles, String> {
+        let path = path.to_path_buf();
+        let since = since.clone();
+        let pattern_filter = Self::create_pattern_filter(include_patterns, exclude_patterns)?;
+
+        let result = tokio::task::spawn_blocking(move || {
+            let is_indexed = |file: &Path| {
+                !file
+                    .file_name()
+                    .and_then(|n| n.to_str())
+                    .is_some_and(|s| s.starts_with('.'))
+                    && pattern_filter.as_ref().is_none_or(|filter| filter.should_include(file))
+            };
+
+            if let Some(reported) = Self::git_changed_files(&path, &since)? {
+                let mut changed_files = ChangedFiles {
+                    considered: reported.len(),
+                    ..Default::default()
+                };
+                for file in reported.into_iter().filter(|file| is_indexed(file)) {
+                    if file.is_file() {
+                        changed_files.changed.push(file);
+                    } else if !file.exists() {
+                        changed_files.removed.push(file);
+                    }
+                }
+                return Ok(changed_files);
+            }
+
+            let since = match since {
+                IndexSince::Time(time) => time,
+                IndexSince::GitRef(git_ref) => {
+                    return Err(format!(
+                        "Can't find changes since '{}': '{}' is not inside a git repository",
+                        git_ref,
+                        path.display()
+                    ));
+                },
+            };
+
+            let mut changed_files = ChangedFiles::default();
+            for entry in walkdir::WalkDir::new(&path)
+                .follow_links(true)
+                .into_iter()
+                .filter_map(|e| e.ok())
+                .filter(|e| e.file_type().is_file())
+                .filter(|e| is_indexed(e.path()))
+            {
+                changed_files.considered += 1;
+                let modified = entry.metadata().ok().and_then(|metadata| metadata.modified().ok());
+                if modified.is_some_and(|modified| modified > since) {
+                    changed_files.changed.push(entry.into_path());
+                }
+            }
+            Ok(changed_files)
+        })
+        .await;
+
+        match result {
+            Ok(result) => result,
+            Err(e) => Err(format!("Change detection task failed: {}", e)),
+        }
+    }
+
+    /// Absolute paths of the files under `path` that git reports as changed since `since`, or
+    /// `None` if `path` is not inside a git work tree or no commit is older than the given time
+    fn git_changed_files(path: &Path, since: &IndexSince) -> std::result::Result<Option<Vec<PathBuf>>, String> {
+        let dir = if path.is_dir() {
+            path
+        } else {
+            match path.parent() {
+                Some(parent) => parent,
+                None => return Ok(None),
+            }
+        };
+        let git = |args: &[&str]| Command::new("git").arg("-C").arg(dir).args(args).output();
+
+        match git(&["rev-parse", "--is-inside-work-tree"]) {
+            Ok(output) if output.status.success() && output.stdout.trim_ascii() == b"true" => {},
+            _ => return Ok(None),
+        }
+
+        let base = match since {
+            IndexSince::GitRef(git_ref) => {
+                if git_ref.starts_with('-') {
+                    return Err(format!("Invalid git ref '{}': refs can't start with '-'", git_ref));
+                }
+                let output = git(&["rev-parse", "--verify", "--quiet", &format!("{}^{{commit}}", git_ref)])
+                    .map_err(|e| format!("Failed to run git: {}", e))?;
+                let commit = String::from_utf8_lossy(&output.stdout).trim().to_string();
+                if !output.status.success() || commit.is_empty() {
+                    return Err(format!("'{}' is not a commit in '{}'", git_ref, dir.display()));
+                }
+                commit
+            },
+            IndexSince::Time(time) => {
+                let secs = time.duration_since(UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or_default();
+                let output = git(&["rev-list", "-1", &format!("--before={}", secs), "HEAD"])
+                    .map_err(|e| format!("Failed to run git: {}", e))?;
+                let commit = String::from_utf8_lossy(&output.stdout).trim().to_string();
+                if !output.status.success() || commit.is_empty() {
+                    return Ok(None);
+                }
+                commit
+            },
+        };
+
+        // `--end-of-options` keeps the base from ever being read as an option
+        let mut files = Vec::new();
+        for args in [
+            &[
+                "diff",
+                "--name-only",
+                "--relative",
+                "-z",
+                "--end-of-options",
+                base.as_str(),
+                "--",
+            ][..],
+            &["ls-files", "--others", "--exclude-standard", "-z"][..],
+        ] {
+            let output = git(args).map_err(|e| format!("Failed to run git: {}", e))?;
+            if !output.status.success() {
+                return Err(format!(
+                    "git {} failed: {}",
+                    args[0],
+                    String::from_utf8_lossy(&output.stderr).trim()
+                ));
+            }
+            files.extend(
+                output
+                    .stdout
+                    .split(|b| *b == 0)
+                    .filter(|name| !name.is_empty())
+                    .map(|name| dir.join(String::from_utf8_lossy(name).as_ref())),
+            );
+        }
+        files.sort();
+        files.dedup();
+        if !path.is_dir() {
+            files.retain(|file| file == path);
+        }
+
+        Ok(Some(files))
+    }
+
     fn create_pattern_filter(
         include_patterns: &Option<Vec<String>>,
         exclude_patterns: &Option<Vec<String>>,
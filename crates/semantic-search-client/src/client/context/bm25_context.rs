@@ -1,3 +1,4 @@
+use std::collections::HashSet;
 use std::fs::{
     self,
     File,
@@ -95,6 +96,36 @@ impl BM25Context {
         Ok(count)
     }
 
+    /// Remove all data points that were created from one of the given files, returning how many
+    /// were removed
+    ///
+    /// The remaining data points are renumbered, since search results are looked up by position.
+    pub fn remove_data_points_by_path(&mut self, paths: &HashSet<String>) -> Result<usize> {
+        let count = self.data_points.len();
+        self.data_points.retain(|point| {
+            !point
+                .payload
+                .get("path")
+                .and_then(|path| path.as_str())
+                .is_some_and(|path| paths.contains(path))
+        });
+        let removed = count - self.data_points.len();
+        if removed == 0 {
+            return Ok(0);
+        }
+
+        for (i, point) in self.data_points.iter_mut().enumerate() {
+            point.id = i;
+        }
+        if self.data_points.is_empty() {
+            self.index = None;
+        } else {
+            self.rebuild_index()?;
+        }
+
+        Ok(removed)
+    }
+
     /// Search the context
     pub fn search(&self, query: &str, limit: usize) -> Vec<(usize, f32)> {
         match &self.index {
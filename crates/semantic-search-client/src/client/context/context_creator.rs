@@ -1,4 +1,7 @@
-use std::collections::HashMap;
+use std::collections::{
+    HashMap,
+    HashSet,
+};
 use std::path::Path;
 use std::sync::Arc;
 
@@ -12,18 +15,25 @@ use super::{
     BM25Context,
     SemanticContext,
 };
+use crate::config::default_embedding_batch_size;
 use crate::embedding::{
     EmbeddingType,
     TextEmbedderTrait,
 };
-use crate::error::Result;
+use crate::error::{
+    Result,
+    SemanticSearchError,
+};
 use crate::types::{
     BM25DataPoint,
     DataPoint,
 };
 
 /// Context creator utility
-pub struct ContextCreator;
+pub struct ContextCreator {
+    /// Number of items embedded together when creating a semantic context
+    batch_size: usize,
+}
 
 impl Default for ContextCreator {
     fn default() -> Self {
@@ -34,7 +44,14 @@ impl Default for ContextCreator {
 impl ContextCreator {
     /// Create new context creator
     pub fn new() -> Self {
-        Self
+        Self::with_batch_size(default_embedding_batch_size())
+    }
+
+    /// Create new context creator that embeds `batch_size` items at a time
+    pub fn with_batch_size(batch_size: usize) -> Self {
+        Self {
+            batch_size: batch_size.max(1),
+        }
     }
 
     /// Create context
@@ -170,28 +187,27 @@ impl ContextCreator {
         let mut semantic_context = SemanticContext::new(context_dir.join("data.json"))
             .map_err(|e| format!("Failed to create semantic context: {}", e))?;
 
-        let mut data_points = Vec::new();
+        let mut data_points = Vec::with_capacity(items.len());
         let total_items = items.len();
 
-        for (i, item) in items.iter().enumerate() {
+        for (batch_index, batch) in items.chunks(self.batch_size).enumerate() {
             if cancel_token.is_cancelled() {
                 return Err("Operation was cancelled during embedding generation".to_string());
             }
 
-            if i % 10 == 0 {
-                self.update_operation_progress(
-                    operation_manager,
-                    operation_id,
-                    i as u64,
-                    total_items as u64,
-                    format!("Generating embeddings ({}/{})", i, total_items),
-                )
-                .await;
-            }
+            let offset = batch_index * self.batch_size;
+            self.update_operation_progress(
+                operation_manager,
+                operation_id,
+                offset as u64,
+                total_items as u64,
+                format!("Generating embeddings ({}/{})", offset, total_items),
+            )
+            .await;
 
-            let data_point = Self::create_data_point_from_item(item, i, embedder)
-                .map_err(|e| format!("Failed to create data point: {}", e))?;
-            data_points.push(data_point);
+            let batch_data_points = Self::create_data_points_from_items(batch, offset, embedder)
+                .map_err(|e| format!("Failed to create data points: {}", e))?;
+            data_points.extend(batch_data_points);
         }
 
         if cancel_token.is_cancelled() {
@@ -223,6 +239,116 @@ impl ContextCreator {
         Ok(())
     }
 
+    /// Update an existing context in place, replacing the data points of `stale_paths` with the
+    /// data points for `items`
+    ///
+    /// New embeddings are generated before the context is locked, so that searches are only
+    /// blocked while the data points are swapped.
+    #[allow(clippy::too_many_arguments)]
+    pub async fn update_context(
+        &self,
+        context_id: &str,
+        items: &[serde_json::Value],
+        stale_paths: &HashSet<String>,
+        embedding_type: EmbeddingType,
+        operation_id: Uuid,
+        cancel_token: &CancellationToken,
+        operation_manager: &OperationManager,
+        embedder: &dyn TextEmbedderTrait,
+        context_manager: &ContextManager,
+    ) -> std::result::Result<(), String> {
+        if embedding_type.is_bm25() {
+            let context_arc = context_manager
+                .get_bm25_contexts_ref()
+                .read()
+                .await
+                .get(context_id)
+                .cloned()
+                .ok_or_else(|| format!("Context '{}' is not loaded", context_id))?;
+
+            let mut data_points = items
+                .iter()
+                .enumerate()
+                .map(|(i, item)| Self::create_bm25_data_point_from_item(item, i))
+                .collect::<Result<Vec<_>>>()
+                .map_err(|e| format!("Failed to create BM25 data point: {}", e))?;
+
+            if cancel_token.is_cancelled() {
+                return Err("Operation was cancelled before updating BM25 index".to_string());
+            }
+
+            self.update_operation_status(operation_manager, operation_id, "Updating BM25 index...".to_string())
+                .await;
+
+            let mut context = context_arc.lock().await;
+            context
+                .remove_data_points_by_path(stale_paths)
+                .map_err(|e| format!("Failed to remove BM25 data points: {}", e))?;
+            let first_id = context.get_data_points().len();
+            for point in &mut data_points {
+                point.id += first_id;
+            }
+            context
+                .add_data_points(data_points)
+                .map_err(|e| format!("Failed to add BM25 data points: {}", e))?;
+            context
+                .save()
+                .map_err(|e| format!("Failed to save BM25 context: {}", e))
+        } else {
+            let context_arc = context_manager
+                .get_volatile_contexts_ref()
+                .read()
+                .await
+                .get(context_id)
+                .cloned()
+                .ok_or_else(|| format!("Context '{}' is not loaded", context_id))?;
+
+            let mut data_points = Vec::with_capacity(items.len());
+            for (batch_index, batch) in items.chunks(self.batch_size).enumerate() {
+                if cancel_token.is_cancelled() {
+                    return Err("Operation was cancelled during embedding generation".to_string());
+                }
+
+                let offset = batch_index * self.batch_size;
+                self.update_operation_progress(
+                    operation_manager,
+                    operation_id,
+                    offset as u64,
+                    items.len() as u64,
+                    format!("Generating embeddings ({}/{})", offset, items.len()),
+                )
+                .await;
+
+                let batch_data_points = Self::create_data_points_from_items(batch, offset, embedder)
+                    .map_err(|e| format!("Failed to create data points: {}", e))?;
+                data_points.extend(batch_data_points);
+            }
+
+            if cancel_token.is_cancelled() {
+                return Err("Operation was cancelled before updating index".to_string());
+            }
+
+            self.update_operation_status(operation_manager, operation_id, "Updating vector index...".to_string())
+                .await;
+
+            let mut context = context_arc.lock().await;
+            context
+                .remove_data_points_by_path(stale_paths)
+                .map_err(|e| format!("Failed to remove data points: {}", e))?;
+            let first_id = context
+                .get_data_points()
+                .map_err(|e| format!("Failed to load data points: {}", e))?
+                .len();
+            for point in &mut data_points {
+                point.id += first_id;
+            }
+            context
+                .add_data_points(data_points)
+                .map_err(|e| format!("Failed to add data points: {}", e))?;
+            context.save().map_err(|e| format!("Failed to save context: {}", e))
+        }
+    }
+
     fn create_bm25_data_point_from_item(item: &serde_json::Value, id: usize) -> Result<BM25DataPoint> {
         let text = item.get("text").and_then(|v| v.as_str()).unwrap_or("");
 
@@ -241,23 +367,48 @@ impl ContextCreator {
         })
     }
 
-    fn create_data_point_from_item(
-        item: &serde_json::Value,
-        id: usize,
+    /// Embeds the text of all `items` in a single batch, numbering the data points from
+    /// `first_id`
+    fn create_data_points_from_items(
+        items: &[serde_json::Value],
+        first_id: usize,
         embedder: &dyn TextEmbedderTrait,
-    ) -> Result<DataPoint> {
-        let text = item.get("text").and_then(|v| v.as_str()).unwrap_or("");
-        let vector = embedder.embed(text)?;
+    ) -> Result<Vec<DataPoint>> {
+        let texts: Vec<String> = items
+            .iter()
+            .map(|item| item.get("text").and_then(|v| v.as_str()).unwrap_or("").to_string())
+            .collect();
+        let vectors = embedder.embed_batch(&texts)?;
+        if vectors.len() != items.len() {
+            return Err(SemanticSearchError::EmbeddingError(format!(
+                "Expected {} embeddings but got {}",
+                items.len(),
+                vectors.len()
+            )));
+        }
 
-        let payload: HashMap<String, serde_json::Value> = if let serde_json::Value::Object(map) = item {
-            map.clone().into_iter().collect()
-        } else {
-            let mut map = HashMap::new();
-            map.insert("text".to_string(), item.clone());
-            map
-        };
+        let data_points = items
+            .iter()
+            .zip(vectors)
+            .enumerate()
+            .map(|(i, (item, vector))| {
+                let payload: HashMap<String, serde_json::Value> = if let serde_json::Value::Object(map) = item {
+                    map.clone().into_iter().collect()
+                } else {
+                    let mut map = HashMap::new();
+                    map.insert("text".to_string(), item.clone());
+                    map
+                };
+
+                DataPoint {
+                    id: first_id + i,
+                    payload,
+                    vector,
+                }
+            })
+            .collect();
 
-        Ok(DataPoint { id, payload, vector })
+        Ok(data_points)
     }
 
     async fn update_operation_status(&self, operation_manager: &OperationManager, operation_id: Uuid, message: String) {
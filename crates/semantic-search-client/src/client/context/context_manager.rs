@@ -162,24 +162,27 @@ impl ContextManager {
         embedder: &dyn TextEmbedderTrait,
     ) -> Result<Option<SearchResults>> {
         let query_vector = embedder.embed(query_text)?;
-        let volatile_contexts = tokio::time::timeout(Duration::from_millis(100), self.volatile_contexts.read())
-            .await
-            .map_err(|_timeout| SemanticSearchError::OperationFailed("Timeout accessing contexts".to_string()))?;
-
-        if let Some(context_arc) = volatile_contexts.get(context_id) {
-            if let Ok(context_guard) = context_arc.try_lock() {
-                match context_guard.search(&query_vector, limit) {
-                    Ok(results) => Ok(if results.is_empty() { None } else { Some(results) }),
-                    Err(e) => {
-                        warn!("Failed to search context {}: {}", context_id, e);
-                        Ok(None)
-                    },
-                }
-            } else {
+        let context_arc = {
+            let volatile_contexts = tokio::time::timeout(Duration::from_millis(100), self.volatile_contexts.read())
+                .await
+                .map_err(|_timeout| SemanticSearchError::OperationFailed("Timeout accessing contexts".to_string()))?;
+            volatile_contexts.get(context_id).cloned()
+        };
+
+        let Some(Ok(mut context_guard)) = context_arc.map(Mutex::try_lock_owned) else {
+            return Ok(None);
+        };
+        // Searching reads the context from disk first if it is not loaded
+        match tokio::task::spawn_blocking(move || context_guard.search(&query_vector, limit)).await {
+            Ok(Ok(results)) => Ok(if results.is_empty() { None } else { Some(results) }),
+            Ok(Err(e)) => {
+                warn!("Failed to search context {}: {}", context_id, e);
                 Ok(None)
-            }
-        } else {
-            Ok(None)
+            },
+            Err(e) => Err(SemanticSearchError::OperationFailed(format!(
+                "Search task failed: {}",
+                e
+            ))),
         }
     }
 
@@ -307,6 +310,64 @@ impl ContextManager {
         Ok(())
     }
 
+    /// Drop a semantic context's data from memory, saving it first. It is loaded again on the next
+    /// search or add.
+    pub async fn unload_context(&self, context_id: &str) -> Result<()> {
+        let context_arc = {
+            let volatile_contexts = self.volatile_contexts.read().await;
+            volatile_contexts.get(context_id).cloned()
+        };
+
+        let Some(context_arc) = context_arc else {
+            return Err(SemanticSearchError::ContextNotFound(context_id.to_string()));
+        };
+        let mut context = context_arc.lock_owned().await;
+        tokio::task::spawn_blocking(move || context.unload())
+            .await
+            .map_err(|e| SemanticSearchError::OperationFailed(format!("Unload task failed: {}", e)))?
+    }
+
+    /// Unload the semantic contexts that have not been used for at least `idle_timeout`.
+    /// Contexts that are busy are skipped.
+    pub async fn unload_idle_contexts(&self, idle_timeout: Duration) {
+        let context_arcs: Vec<_> = {
+            let volatile_contexts = self.volatile_contexts.read().await;
+            volatile_contexts
+                .iter()
+                .map(|(context_id, context_arc)| (context_id.clone(), context_arc.clone()))
+                .collect()
+        };
+
+        for (context_id, context_arc) in context_arcs {
+            let Ok(mut context) = context_arc.try_lock_owned() else {
+                continue;
+            };
+            if !context.is_loaded() || context.last_used().elapsed() < idle_timeout {
+                continue;
+            }
+            match tokio::task::spawn_blocking(move || context.unload()).await {
+                Ok(Ok(())) => {},
+                Ok(Err(e)) => warn!("Failed to unload idle context {}: {}", context_id, e),
+                Err(e) => warn!("Failed to unload idle context {}: {}", context_id, e),
+            }
+        }
+    }
+
+    /// Estimated memory used by each loaded semantic context, in bytes. Contexts that are not
+    /// loaded are omitted.
+    pub async fn get_memory_usage(&self) -> HashMap<ContextId, usize> {
+        let volatile_contexts = self.volatile_contexts.read().await;
+        volatile_contexts
+            .iter()
+            .filter_map(|(context_id, context_arc)| {
+                let context = context_arc.try_lock().ok()?;
+                context
+                    .is_loaded()
+                    .then(|| (context_id.clone(), context.memory_footprint()))
+            })
+            .collect()
+    }
+
     /// Clear all contexts immediately
     pub async fn clear_all_immediate(&self, base_dir: &Path) -> Result<usize> {
         let context_count = {
@@ -1,12 +1,17 @@
+use std::collections::HashSet;
 use std::fs::{
     self,
     File,
+    OpenOptions,
 };
 use std::io::{
+    BufRead,
     BufReader,
     BufWriter,
+    Write,
 };
 use std::path::PathBuf;
+use std::time::Instant;
 
 use crate::error::Result;
 use crate::index::VectorIndex;
@@ -16,6 +21,11 @@ use crate::types::{
 };
 
 /// A semantic context containing data points and a vector index
+///
+/// Data points are only read from disk on the first search or add, and can be dropped from memory
+/// again with [SemanticContext::unload]. On disk, the context is stored as a JSON array of data
+/// points plus a JSON lines delta file that [SemanticContext::save] appends new points to, so that
+/// small additions don't rewrite the whole index.
 pub struct SemanticContext {
     /// The data points stored in the index
     pub(crate) data_points: Vec<DataPoint>,
@@ -23,48 +33,153 @@ pub struct SemanticContext {
     index: Option<VectorIndex>,
     /// Path to save/load the data points
     data_path: PathBuf,
+    /// Whether the data points have been read from disk
+    loaded: bool,
+    /// Number of data points stored in the main data file
+    base_count: usize,
+    /// Number of data points stored on disk, in the main data file and the delta file
+    persisted_count: usize,
+    /// Whether data points were removed since the last save, so the whole context must be saved
+    needs_full_save: bool,
+    /// Estimated memory used by the loaded data points and index, in bytes
+    memory_estimate: usize,
+    /// When the context was last searched or added to
+    last_used: Instant,
 }
 
 impl SemanticContext {
     /// Create a new semantic context
+    ///
+    /// No data is read until the context is first searched or added to.
     pub fn new(data_path: PathBuf) -> Result<Self> {
         // Create the directory if it doesn't exist
         if let Some(parent) = data_path.parent() {
             fs::create_dir_all(parent)?;
         }
 
-        // Create a new instance
-        let mut context = Self {
+        Ok(Self {
             data_points: Vec::new(),
             index: None,
-            data_path: data_path.clone(),
-        };
+            data_path,
+            loaded: false,
+            base_count: 0,
+            persisted_count: 0,
+            needs_full_save: false,
+            memory_estimate: 0,
+            last_used: Instant::now(),
+        })
+    }
+
+    /// Path of the delta file that new data points are appended to
+    fn delta_path(&self) -> PathBuf {
+        self.data_path.with_extension("delta.jsonl")
+    }
+
+    /// Read the data points from disk and build the index, if not done already
+    pub fn load(&mut self) -> Result<()> {
+        self.last_used = Instant::now();
+        if self.loaded {
+            return Ok(());
+        }
+
+        let mut data_points: Vec<DataPoint> = Vec::new();
+        if self.data_path.exists() {
+            let reader = BufReader::new(File::open(&self.data_path)?);
+            data_points = serde_json::from_reader(reader)?;
+        }
+        let base_count = data_points.len();
 
-        // Load data points if the file exists
-        if data_path.exists() {
-            let file = File::open(&data_path)?;
-            let reader = BufReader::new(file);
-            context.data_points = serde_json::from_reader(reader)?;
+        let delta_path = self.delta_path();
+        if delta_path.exists() {
+            for line in BufReader::new(File::open(&delta_path)?).lines() {
+                let line = line?;
+                if !line.trim().is_empty() {
+                    data_points.push(serde_json::from_str(&line)?);
+                }
+            }
         }
 
+        self.base_count = base_count;
+        self.persisted_count = data_points.len();
+        self.memory_estimate = data_points.iter().map(estimate_memory).sum();
+        self.data_points = data_points;
+        self.loaded = true;
+
         // If we have data points, rebuild the index
-        if !context.data_points.is_empty() {
-            context.rebuild_index()?;
+        if !self.data_points.is_empty() {
+            self.rebuild_index()?;
         }
 
-        Ok(context)
+        Ok(())
     }
 
-    /// Save data points to disk
-    pub fn save(&self) -> Result<()> {
-        // Save the data points as JSON
-        let file = File::create(&self.data_path)?;
-        let writer = BufWriter::new(file);
-        serde_json::to_writer(writer, &self.data_points)?;
+    /// Save data points to disk, saving the entire context only when data points were removed or
+    /// there were more points appended since the last full save than there are in the main data
+    /// file
+    pub fn save(&mut self) -> Result<()> {
+        if !self.loaded || (!self.needs_full_save && self.persisted_count == self.data_points.len()) {
+            return Ok(());
+        }
+
+        if self.needs_full_save || self.base_count == 0 || self.data_points.len() - self.base_count > self.base_count {
+            // Save the data points as JSON
+            let file = File::create(&self.data_path)?;
+            let mut writer = BufWriter::new(file);
+            serde_json::to_writer(&mut writer, &self.data_points)?;
+            writer.flush()?;
+
+            let delta_path = self.delta_path();
+            if delta_path.exists() {
+                fs::remove_file(delta_path)?;
+            }
+            self.base_count = self.data_points.len();
+            self.needs_full_save = false;
+        } else {
+            let file = OpenOptions::new().create(true).append(true).open(self.delta_path())?;
+            let mut writer = BufWriter::new(file);
+            for point in &self.data_points[self.persisted_count..] {
+                serde_json::to_writer(&mut writer, point)?;
+                writer.write_all(b"\n")?;
+            }
+            writer.flush()?;
+        }
+
+        self.persisted_count = self.data_points.len();
+        Ok(())
+    }
+
+    /// Save any unsaved data points and drop the data points and index from memory
+    ///
+    /// The context is transparently loaded again on the next search or add.
+    pub fn unload(&mut self) -> Result<()> {
+        if !self.loaded {
+            return Ok(());
+        }
 
+        self.save()?;
+        self.data_points = Vec::new();
+        self.index = None;
+        self.memory_estimate = 0;
+        self.loaded = false;
         Ok(())
     }
 
+    /// Whether the data points are currently in memory
+    pub fn is_loaded(&self) -> bool {
+        self.loaded
+    }
+
+    /// When the context was last searched or added to
+    pub fn last_used(&self) -> Instant {
+        self.last_used
+    }
+
+    /// Estimated memory used by the data points and index, in bytes. This is zero while the
+    /// context is not loaded.
+    pub fn memory_footprint(&self) -> usize {
+        self.memory_estimate
+    }
+
     /// Rebuild the index from the current data points
     pub fn rebuild_index(&mut self) -> Result<()> {
         // Create a new index with the current data points
@@ -90,8 +205,11 @@ impl SemanticContext {
             return Ok(0);
         }
 
+        self.load()?;
+
         // Add the new points to our data store
         let start_idx = self.data_points.len();
+        self.memory_estimate += data_points.iter().map(estimate_memory).sum::<usize>();
         self.data_points.extend(data_points);
         let end_idx = self.data_points.len();
 
@@ -101,6 +219,36 @@ impl SemanticContext {
         Ok(count)
     }
 
+    /// Remove all data points that were created from one of the given files, returning how many
+    /// were removed
+    ///
+    /// The remaining data points are renumbered and the index is rebuilt.
+    pub fn remove_data_points_by_path(&mut self, paths: &HashSet<String>) -> Result<usize> {
+        self.load()?;
+
+        let count = self.data_points.len();
+        self.data_points.retain(|point| {
+            !point
+                .payload
+                .get("path")
+                .and_then(|path| path.as_str())
+                .is_some_and(|path| paths.contains(path))
+        });
+        let removed = count - self.data_points.len();
+        if removed == 0 {
+            return Ok(0);
+        }
+
+        for (i, point) in self.data_points.iter_mut().enumerate() {
+            point.id = i;
+        }
+        self.memory_estimate = self.data_points.iter().map(estimate_memory).sum();
+        self.needs_full_save = true;
+        self.rebuild_index()?;
+
+        Ok(removed)
+    }
+
     /// Update the index with data points in a specific range
     pub fn update_index_by_range(&mut self, start_idx: usize, end_idx: usize) -> Result<()> {
         // If we don't have an index yet, or if the index is small and we're adding many points,
@@ -122,7 +270,9 @@ impl SemanticContext {
     }
 
     /// Search for similar items to the given vector
-    pub fn search(&self, query_vector: &[f32], limit: usize) -> Result<Vec<SearchResult>> {
+    pub fn search(&mut self, query_vector: &[f32], limit: usize) -> Result<Vec<SearchResult>> {
+        self.load()?;
+
         let index = match &self.index {
             Some(idx) => idx,
             None => return Ok(Vec::new()), // Return empty results if no index
@@ -143,8 +293,21 @@ impl SemanticContext {
         Ok(search_results)
     }
 
-    /// Get the data points for serialization
-    pub fn get_data_points(&self) -> &Vec<DataPoint> {
-        &self.data_points
+    /// Get the data points for serialization, loading them if needed
+    pub fn get_data_points(&mut self) -> Result<&Vec<DataPoint>> {
+        self.load()?;
+        Ok(&self.data_points)
     }
 }
+
+/// Rough number of bytes a data point takes up in memory, counting its vector twice since the
+/// index keeps its own copy.
+fn estimate_memory(point: &DataPoint) -> usize {
+    let vector = point.vector.len() * size_of::<f32>() * 2;
+    let payload = point
+        .payload
+        .iter()
+        .map(|(key, value)| key.len() + value.to_string().len())
+        .sum::<usize>();
+    size_of::<DataPoint>() + vector + payload
+}
@@ -22,7 +22,7 @@ use crate::error::{
     Result,
     SemanticSearchError,
 };
-use crate::processing::process_file_with_config;
+use crate::processing::process_file_with_strategy;
 use crate::types::{
     ContextId,
     ContextMap,
@@ -295,7 +295,11 @@ impl SemanticSearchClient {
         }
 
         // Process the file
-        let items = process_file_with_config(file_path, Some(self.config.chunk_size), Some(self.config.chunk_overlap))?;
+        let items = process_file_with_strategy(
+            file_path,
+            &self.config.chunk_strategy(),
+            Some(self.config.chunk_overlap),
+        )?;
 
         // Notify progress: Indexing
         if let Some(ref callback) = progress_callback {
@@ -440,7 +444,7 @@ impl SemanticSearchClient {
             }
 
             // Process the file
-            match process_file_with_config(path, Some(config.chunk_size), Some(config.chunk_overlap)) {
+            match process_file_with_strategy(path, &config.chunk_strategy(), Some(config.chunk_overlap)) {
                 Ok(mut file_items) => items.append(&mut file_items),
                 Err(_) => continue, // Skip files that fail to process
             }
@@ -520,10 +524,10 @@ impl SemanticSearchClient {
         description: &str,
         persistent: bool,
         source_path: Option<String>,
-        semantic_context: SemanticContext,
+        mut semantic_context: SemanticContext,
     ) -> Result<()> {
         // Notify progress: Finalizing (90% progress point)
-        let item_count = semantic_context.get_data_points().len();
+        let item_count = semantic_context.get_data_points()?.len();
 
         // Save to disk if persistent
         if persistent {
@@ -733,7 +737,7 @@ impl SemanticSearchClient {
 
         // Search in all volatile contexts
         for (context_id, context) in &self.volatile_contexts {
-            let context_guard = context.lock().map_err(|e| {
+            let mut context_guard = context.lock().map_err(|e| {
                 SemanticSearchError::OperationFailed(format!("Failed to acquire lock on context: {}", e))
             })?;
 
@@ -807,7 +811,7 @@ impl SemanticSearchClient {
             .get(context_id)
             .ok_or_else(|| SemanticSearchError::ContextNotFound(context_id.to_string()))?;
 
-        let context_guard = context
+        let mut context_guard = context
             .lock()
             .map_err(|e| SemanticSearchError::OperationFailed(format!("Failed to acquire lock on context: {}", e)))?;
 
@@ -859,13 +863,13 @@ impl SemanticSearchClient {
         fs::create_dir_all(&persistent_dir)?;
 
         // Get the context data
-        let context_guard = context
+        let mut context_guard = context
             .lock()
             .map_err(|e| SemanticSearchError::OperationFailed(format!("Failed to acquire lock on context: {}", e)))?;
 
         // Save the data to the persistent directory
         let data_path = persistent_dir.join("data.json");
-        utils::save_json_to_file(&data_path, context_guard.get_data_points())?;
+        utils::save_json_to_file(&data_path, context_guard.get_data_points()?)?;
 
         // Create the context metadata
         let context_meta = KnowledgeContext::new(
@@ -875,7 +879,7 @@ impl SemanticSearchClient {
             true,
             None,
             (vec![], vec![]),
-            context_guard.get_data_points().len(),
+            context_guard.get_data_points()?.len(),
             self.config.embedding_type, // Use client default
         );
 
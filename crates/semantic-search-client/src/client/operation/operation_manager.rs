@@ -234,6 +234,7 @@ impl OperationManager {
             active_count,
             waiting_count,
             max_concurrent: MAX_CONCURRENT_OPERATIONS,
+            context_memory: context_manager.get_memory_usage().await,
         })
     }
 
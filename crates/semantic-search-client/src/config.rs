@@ -17,6 +17,7 @@ use serde::{
 };
 
 use crate::embedding::EmbeddingType;
+use crate::processing::ChunkStrategy;
 
 /// Main configuration structure for the semantic search client.
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -47,6 +48,27 @@ pub struct SemanticSearchConfig {
 
     /// Embedding engine type to use
     pub embedding_type: EmbeddingType,
+
+    /// Strategy used to split files into chunks, see [Self::chunk_strategy]
+    #[serde(default)]
+    pub chunk_strategy: Option<ChunkStrategy>,
+
+    /// Seconds after which an unused context is unloaded from memory by the async client, 0 to
+    /// keep contexts loaded
+    #[serde(default = "default_idle_unload_timeout_secs")]
+    pub idle_unload_timeout_secs: u64,
+
+    /// Number of chunks embedded together when building a semantic index
+    #[serde(default = "default_embedding_batch_size")]
+    pub embedding_batch_size: usize,
+}
+
+fn default_idle_unload_timeout_secs() -> u64 {
+    600
+}
+
+pub(crate) fn default_embedding_batch_size() -> usize {
+    32
 }
 
 impl SemanticSearchConfig {
@@ -79,6 +101,14 @@ impl SemanticSearchConfig {
         self.max_files = max_files;
         self
     }
+
+    /// Strategy used to split files into chunks, which defaults to windows of
+    /// [Self::chunk_size] tokens
+    pub fn chunk_strategy(&self) -> ChunkStrategy {
+        self.chunk_strategy
+            .clone()
+            .unwrap_or(ChunkStrategy::FixedTokens { size: self.chunk_size })
+    }
 }
 
 impl Default for SemanticSearchConfig {
@@ -93,6 +123,9 @@ impl Default for SemanticSearchConfig {
             max_files: 10000, // Default limit of 10000 files
             hosted_models_base_url: "https://desktop-release.q.us-east-1.amazonaws.com/models".to_string(),
             embedding_type: EmbeddingType::default(),
+            chunk_strategy: None,
+            idle_unload_timeout_secs: default_idle_unload_timeout_secs(),
+            embedding_batch_size: default_embedding_batch_size(),
         }
     }
 }
@@ -321,6 +354,10 @@ mod tests {
 
         let loaded_config = load_or_create_config(&config_path).unwrap();
         assert_eq!(loaded_config.chunk_size, 1024);
+        // Without a strategy, files are chunked with the configured chunk size
+        assert_eq!(loaded_config.chunk_strategy(), ChunkStrategy::FixedTokens {
+            size: 1024
+        });
     }
 
     #[test]
@@ -341,6 +378,9 @@ mod tests {
             max_files: 10000,
             hosted_models_base_url: "http://test.example.com/models".to_string(),
             embedding_type: EmbeddingType::default(),
+            chunk_strategy: Some(ChunkStrategy::Function),
+            idle_unload_timeout_secs: 60,
+            embedding_batch_size: 16,
         };
 
         // Update the config
@@ -355,6 +395,8 @@ mod tests {
         assert_eq!(loaded_config.chunk_overlap, 256);
         assert_eq!(loaded_config.default_results, 10);
         assert_eq!(loaded_config.model_name, "different-model");
+        assert_eq!(loaded_config.chunk_strategy(), ChunkStrategy::Function);
+        assert_eq!(loaded_config.embedding_batch_size, 16);
     }
 
     #[test]
@@ -5,12 +5,16 @@
 
 use std::env;
 
-use crate::embedding::run_standard_benchmark;
 #[cfg(not(all(target_os = "linux", target_arch = "aarch64")))]
 use crate::embedding::{
     CandleTextEmbedder,
     ModelType,
 };
+use crate::embedding::{
+    MockTextEmbedder,
+    run_batch_benchmark,
+    run_standard_benchmark,
+};
 
 /// Helper function to check if real embedder tests should be skipped
 fn should_skip_real_embedder_tests() -> bool {
@@ -51,6 +55,27 @@ fn benchmark_candle_model(model_type: ModelType) {
     }
 }
 
+/// Run a batch benchmark on a larger document set for a Candle model
+#[cfg(not(all(target_os = "linux", target_arch = "aarch64")))]
+fn benchmark_candle_model_batch(model_type: ModelType, num_texts: usize) {
+    match CandleTextEmbedder::with_model_type(model_type) {
+        Ok(embedder) => {
+            println!("Benchmarking Candle model {:?} with {} texts", model_type, num_texts);
+            let results = run_batch_benchmark(&embedder, num_texts);
+            println!(
+                "Model: {}, Sequential time: {:?}, Batch time: {:?}, Avg per text: {:?}",
+                results.model_name,
+                results.single_time,
+                results.batch_time,
+                results.avg_time_per_text()
+            );
+        },
+        Err(e) => {
+            println!("Failed to load Candle model {:?}: {}", model_type, e);
+        },
+    }
+}
+
 /// Standardized benchmark test for embedding models
 #[test]
 fn test_standard_benchmark() {
@@ -71,3 +96,22 @@ fn test_standard_benchmark() {
     println!("--------------------------------------------------------");
     println!("Benchmark tests completed");
 }
+
+/// Batch benchmark test for embedding large document sets
+#[test]
+fn test_batch_benchmark() {
+    // The mock embedder needs no model download, so this part always runs
+    let results = run_batch_benchmark(&MockTextEmbedder::new(384), 100);
+    assert_eq!(results.model_name, "Mock");
+    assert_eq!(results.batch_size, 100);
+
+    if should_skip_real_embedder_tests() {
+        return;
+    }
+
+    #[cfg(not(all(target_os = "linux", target_arch = "aarch64")))]
+    {
+        benchmark_candle_model_batch(ModelType::MiniLML6V2, 256);
+        benchmark_candle_model_batch(ModelType::MiniLML12V2, 256);
+    }
+}
@@ -21,6 +21,41 @@ pub fn create_standard_test_data() -> Vec<String> {
     ]
 }
 
+/// Test data made of texts paired with a near duplicate of them: the same content reworded, with
+/// different casing, inflections or punctuation. A useful embedder should find each text closest
+/// to its near duplicate rather than to the other texts, several of which share a topic.
+pub fn create_near_duplicate_test_data() -> Vec<(String, String)> {
+    [
+        (
+            "The quick brown fox jumps over the lazy dog.",
+            "A quick brown fox jumped over a lazy dog",
+        ),
+        (
+            "The cat sits outside on the warm porch.",
+            "Cats sitting outside on a warm porch",
+        ),
+        (
+            "Machine learning models process text to generate embeddings.",
+            "Embeddings are generated by machine learning models processing text.",
+        ),
+        (
+            "Install the package with cargo and run the tests.",
+            "Run the tests after installing the package with Cargo.",
+        ),
+        (
+            "Semantic search ranks documents by the similarity of their embeddings.",
+            "Documents are ranked by embedding similarity in semantic search.",
+        ),
+        (
+            "The server returned an error because the request timed out.",
+            "The request timed out, so the server returned an error.",
+        ),
+    ]
+    .into_iter()
+    .map(|(text, near_duplicate)| (text.to_string(), near_duplicate.to_string()))
+    .collect()
+}
+
 /// Benchmark results for embedding operations
 #[derive(Debug, Clone)]
 pub struct BenchmarkResults {
@@ -129,3 +164,48 @@ pub fn run_standard_benchmark<E: BenchmarkableEmbedder>(embedder: &E) -> Benchma
         texts.len(),
     )
 }
+
+/// Run a benchmark that embeds a larger document set, comparing one-at-a-time embedding with
+/// batch embedding
+///
+/// # Arguments
+///
+/// * `embedder` - The embedder to benchmark
+/// * `num_texts` - The number of texts to embed, taken repeatedly from the standard test data
+///
+/// # Returns
+///
+/// The benchmark results, where `single_time` is the time to embed all texts one at a time
+pub fn run_batch_benchmark<E: BenchmarkableEmbedder>(embedder: &E, num_texts: usize) -> BenchmarkResults {
+    let texts: Vec<String> = create_standard_test_data()
+        .into_iter()
+        .cycle()
+        .take(num_texts)
+        .collect();
+
+    // Warm-up run
+    let _ = embedder.embed_batch(&texts);
+
+    // Measure sequential embedding performance
+    let start = Instant::now();
+    let sequential_results: Vec<Vec<f32>> = texts.iter().map(|text| embedder.embed_single(text)).collect();
+    let sequential_duration = start.elapsed();
+
+    // Measure batch embedding performance
+    let start = Instant::now();
+    let batch_results = embedder.embed_batch(&texts);
+    let batch_duration = start.elapsed();
+
+    // Verify results
+    assert_eq!(sequential_results.len(), texts.len());
+    assert_eq!(batch_results.len(), texts.len());
+    assert!(batch_results.iter().all(|e| e.len() == embedder.embedding_dim()));
+
+    BenchmarkResults::new(
+        embedder.model_name(),
+        embedder.embedding_dim(),
+        sequential_duration,
+        batch_duration,
+        texts.len(),
+    )
+}
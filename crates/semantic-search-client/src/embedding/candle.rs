@@ -118,6 +118,20 @@ impl CandleTextEmbedder {
         Self::with_model_config(model_path, tokenizer_path, config)
     }
 
+    /// Set the number of texts that are run through the model together by [Self::embed_batch]
+    ///
+    /// # Arguments
+    ///
+    /// * `batch_size` - Number of texts per batch, values below 1 are treated as 1
+    ///
+    /// # Returns
+    ///
+    /// Self for method chaining
+    pub fn with_batch_size(mut self, batch_size: usize) -> Self {
+        self.config.batch_size = batch_size.max(1);
+        self
+    }
+
     /// Generate an embedding for a text
     ///
     /// # Arguments
@@ -1,15 +1,36 @@
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{
+    Hash,
+    Hasher,
+};
+
+use bm25::{
+    DefaultTokenizer,
+    Language,
+    Tokenizer,
+};
+
 use crate::error::Result;
 
 /// Mock text embedder for testing
+///
+/// Texts are embedded as normalized bag-of-words term frequency vectors, using the same tokenizer
+/// as the BM25 index. Texts that share more terms therefore have a higher cosine similarity, which
+/// makes search and ranking tests meaningful without downloading a model.
 pub struct MockTextEmbedder {
     /// Fixed embedding dimension
     dimension: usize,
+    /// Splits texts into lowercased, stemmed terms without stop words
+    tokenizer: DefaultTokenizer,
 }
 
 impl MockTextEmbedder {
     /// Create a new MockTextEmbedder
     pub fn new(dimension: usize) -> Self {
-        Self { dimension }
+        Self {
+            dimension,
+            tokenizer: DefaultTokenizer::new(Language::English),
+        }
     }
 
     /// Generate a deterministic embedding for a text
@@ -22,17 +43,18 @@ impl MockTextEmbedder {
     ///
     /// A vector of floats representing the text embedding
     pub fn embed(&self, text: &str) -> Result<Vec<f32>> {
-        // Generate a deterministic embedding based on the text
-        // This avoids downloading any models while providing consistent results
-        let mut embedding = Vec::with_capacity(self.dimension);
-
-        // Use a simple hash of the text to seed the embedding values
-        let hash = text.chars().fold(0u32, |acc, c| acc.wrapping_add(c as u32));
+        let mut terms = self.tokenizer.tokenize(text);
+        if terms.is_empty() {
+            // Texts made only of stop words or punctuation still get a distinct unit vector
+            terms.push(text.to_lowercase());
+        }
 
-        for i in 0..self.dimension {
-            // Generate a deterministic but varied value for each dimension
-            let value = ((hash.wrapping_add(i as u32)).wrapping_mul(16807) % 65536) as f32 / 65536.0;
-            embedding.push(value);
+        // Each term is hashed to a dimension and counted there
+        let mut embedding = vec![0.0; self.dimension];
+        for term in terms {
+            let mut hasher = DefaultHasher::new();
+            term.hash(&mut hasher);
+            embedding[(hasher.finish() % self.dimension as u64) as usize] += 1.0;
         }
 
         // Normalize the embedding to unit length
@@ -62,9 +84,32 @@ impl MockTextEmbedder {
     }
 }
 
+impl crate::embedding::BenchmarkableEmbedder for MockTextEmbedder {
+    fn model_name(&self) -> String {
+        "Mock".to_string()
+    }
+
+    fn embedding_dim(&self) -> usize {
+        self.dimension
+    }
+
+    fn embed_single(&self, text: &str) -> Vec<f32> {
+        self.embed(text).unwrap()
+    }
+
+    fn embed_batch(&self, texts: &[String]) -> Vec<Vec<f32>> {
+        self.embed_batch(texts).unwrap()
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::embedding::create_near_duplicate_test_data;
+
+    fn cosine_similarity(a: &[f32], b: &[f32]) -> f32 {
+        a.iter().zip(b).map(|(x, y)| x * y).sum()
+    }
 
     #[test]
     fn test_mock_embed_single() {
@@ -110,4 +155,44 @@ mod tests {
             assert_eq!(embedding1[i], embedding2[i]);
         }
     }
+
+    #[test]
+    fn test_mock_embed_similarity() {
+        let embedder = MockTextEmbedder::new(384);
+        let cat = embedder.embed("The cat sits outside").unwrap();
+
+        // Inflections and stop words do not change the embedding
+        let cats = embedder.embed("the cats are sitting outside").unwrap();
+        assert!(cosine_similarity(&cat, &cats) > 0.99);
+
+        let overlap = embedder.embed("A cat is playing guitar").unwrap();
+        let unrelated = embedder.embed("A man is playing guitar").unwrap();
+        assert!(cosine_similarity(&cat, &overlap) > cosine_similarity(&cat, &unrelated));
+
+        // Texts without any terms are still embedded as unit vectors
+        let norm: f32 = embedder.embed("...").unwrap().iter().map(|x| x * x).sum::<f32>().sqrt();
+        assert!((norm - 1.0).abs() < 1e-5);
+    }
+
+    #[test]
+    fn test_mock_embed_near_duplicates() {
+        let embedder = MockTextEmbedder::new(384);
+        let pairs = create_near_duplicate_test_data();
+        let originals = embedder
+            .embed_batch(&pairs.iter().map(|(original, _)| original.clone()).collect::<Vec<_>>())
+            .unwrap();
+        let duplicates = embedder
+            .embed_batch(&pairs.iter().map(|(_, duplicate)| duplicate.clone()).collect::<Vec<_>>())
+            .unwrap();
+
+        // Each text is closer to its near duplicate than to any other text of the corpus
+        for (i, original) in originals.iter().enumerate() {
+            let closest = duplicates
+                .iter()
+                .enumerate()
+                .max_by(|(_, a), (_, b)| cosine_similarity(original, a).total_cmp(&cosine_similarity(original, b)))
+                .map(|(j, _)| j);
+            assert_eq!(closest, Some(i), "closest match of {:?}", pairs[i].0);
+        }
+    }
 }
@@ -11,7 +11,9 @@ mod trait_def;
 pub use benchmark_utils::{
     BenchmarkResults,
     BenchmarkableEmbedder,
+    create_near_duplicate_test_data,
     create_standard_test_data,
+    run_batch_benchmark,
     run_standard_benchmark,
 };
 #[cfg(not(all(target_os = "linux", target_arch = "aarch64")))]
@@ -36,6 +36,7 @@ pub use error::{
     Result,
     SemanticSearchError,
 };
+pub use processing::ChunkStrategy;
 pub use types::{
     BM25DataPoint,
     DataPoint,
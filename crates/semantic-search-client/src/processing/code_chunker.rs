@@ -0,0 +1,190 @@
+use std::path::Path;
+
+use tree_sitter::{
+    Language,
+    Node,
+    Parser,
+};
+
+use crate::config;
+use crate::processing::text_chunker::{
+    Chunk,
+    chunk_paragraphs,
+    chunk_text,
+};
+
+/// Node kinds treated as functions in JavaScript and TypeScript
+const JS_FUNCTION_KINDS: &[&str] = &[
+    "function_declaration",
+    "generator_function_declaration",
+    "method_definition",
+];
+
+/// Get the tree-sitter grammar and function node kinds for a source file
+fn language_for(path: &Path) -> Option<(Language, &'static [&'static str])> {
+    let extension = path.extension()?.to_str()?.to_lowercase();
+    let (language, kinds): (Language, &'static [&'static str]) = match extension.as_str() {
+        "rs" => (tree_sitter_rust::LANGUAGE.into(), &["function_item"]),
+        "py" => (tree_sitter_python::LANGUAGE.into(), &["function_definition"]),
+        "js" | "jsx" => (tree_sitter_javascript::LANGUAGE.into(), JS_FUNCTION_KINDS),
+        "ts" => (tree_sitter_typescript::LANGUAGE_TYPESCRIPT.into(), JS_FUNCTION_KINDS),
+        "tsx" => (tree_sitter_typescript::LANGUAGE_TSX.into(), JS_FUNCTION_KINDS),
+        "go" => (tree_sitter_go::LANGUAGE.into(), &[
+            "function_declaration",
+            "method_declaration",
+        ]),
+        "java" => (tree_sitter_java::LANGUAGE.into(), &[
+            "method_declaration",
+            "constructor_declaration",
+        ]),
+        _ => return None,
+    };
+    Some((language, kinds))
+}
+
+/// Chunk source code at function and method boundaries
+///
+/// Each function becomes its own chunk carrying the function signature. Functions larger than the
+/// configured chunk size are split further, and every piece keeps the signature. Code between
+/// functions (imports, type definitions, etc.) is chunked by paragraph.
+///
+/// # Arguments
+///
+/// * `text` - The source code to chunk
+/// * `path` - Path of the source file, used to pick the grammar
+///
+/// # Returns
+///
+/// The chunks, or None if the language is not supported or no functions were found
+pub fn chunk_functions(text: &str, path: &Path) -> Option<Vec<Chunk>> {
+    let (language, kinds) = language_for(path)?;
+    let mut parser = Parser::new();
+    parser.set_language(&language).ok()?;
+    let tree = parser.parse(text, None)?;
+
+    let mut functions = Vec::new();
+    collect_functions(tree.root_node(), kinds, &mut functions);
+    if functions.is_empty() {
+        return None;
+    }
+
+    let max_words = config::get_config().chunk_size;
+    let mut chunks = Vec::new();
+    let mut offset = 0;
+
+    for node in functions {
+        push_paragraphs(&mut chunks, &text[offset..node.start_byte()]);
+
+        let body = &text[node.start_byte()..node.end_byte()];
+        let signature = function_signature(node, text);
+        if body.split_whitespace().count() > max_words {
+            for piece in chunk_text(body, None, None) {
+                chunks.push(Chunk {
+                    text: piece,
+                    signature: Some(signature.clone()),
+                });
+            }
+        } else {
+            chunks.push(Chunk {
+                text: body.to_string(),
+                signature: Some(signature),
+            });
+        }
+
+        offset = node.end_byte();
+    }
+    push_paragraphs(&mut chunks, &text[offset..]);
+
+    Some(chunks)
+}
+
+/// Collect the outermost function nodes below `node`, in source order
+fn collect_functions<'a>(node: Node<'a>, kinds: &[&str], functions: &mut Vec<Node<'a>>) {
+    let mut cursor = node.walk();
+    for child in node.children(&mut cursor) {
+        if kinds.contains(&child.kind()) {
+            functions.push(child);
+        } else {
+            collect_functions(child, kinds, functions);
+        }
+    }
+}
+
+/// Get the text of a function up to its body, with whitespace collapsed
+fn function_signature(node: Node<'_>, text: &str) -> String {
+    let end = node
+        .child_by_field_name("body")
+        .map_or(node.end_byte(), |body| body.start_byte());
+    text[node.start_byte()..end]
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+fn push_paragraphs(chunks: &mut Vec<Chunk>, text: &str) {
+    chunks.extend(
+        chunk_paragraphs(text)
+            .into_iter()
+            .map(|text| Chunk { text, signature: None }),
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const RUST_SOURCE: &str = r#"use std::fmt;
+
+struct Greeter {
+    name: String,
+}
+
+impl Greeter {
+    pub fn new(name: String) -> Self {
+        Self { name }
+    }
+
+    pub fn greet(&self,
+                 punctuation: char) -> String {
+        format!("Hello, {}{}", self.name, punctuation)
+    }
+}
+"#;
+
+    #[test]
+    fn test_chunk_rust_functions() {
+        let chunks = chunk_functions(RUST_SOURCE, Path::new("greeter.rs")).unwrap();
+        let signatures: Vec<_> = chunks.iter().filter_map(|c| c.signature.as_deref()).collect();
+
+        assert_eq!(signatures, vec![
+            "pub fn new(name: String) -> Self",
+            "pub fn greet(&self, punctuation: char) -> String"
+        ]);
+
+        // Code outside of functions is kept without a signature
+        assert!(chunks[0].text.starts_with("use std::fmt;"));
+        assert!(chunks[0].signature.is_none());
+
+        let greet = chunks.iter().find(|c| c.text.contains("format!")).unwrap();
+        assert!(greet.text.starts_with("pub fn greet"));
+        assert!(greet.text.ends_with('}'));
+    }
+
+    #[test]
+    fn test_chunk_python_functions() {
+        let source = "import os\n\nclass Walker:\n    def walk(self, root):\n        return os.listdir(root)\n";
+        let chunks = chunk_functions(source, Path::new("walker.py")).unwrap();
+
+        assert!(
+            chunks
+                .iter()
+                .any(|c| c.signature.as_deref() == Some("def walk(self, root):"))
+        );
+    }
+
+    #[test]
+    fn test_unsupported_language() {
+        assert!(chunk_functions("puts 'hello'", Path::new("hello.rb")).is_none());
+        assert!(chunk_functions("no functions here", Path::new("empty.rs")).is_none());
+    }
+}
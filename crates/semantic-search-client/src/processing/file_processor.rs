@@ -3,11 +3,15 @@ use std::path::Path;
 
 use serde_json::Value;
 
+use crate::config;
 use crate::error::{
     Result,
     SemanticSearchError,
 };
-use crate::processing::text_chunker::chunk_text;
+use crate::processing::text_chunker::{
+    ChunkStrategy,
+    chunk_with_strategy,
+};
 use crate::types::FileType;
 
 /// Determine the file type based on extension
@@ -104,6 +108,30 @@ pub fn process_file_with_config(
     path: &Path,
     chunk_size: Option<usize>,
     chunk_overlap: Option<usize>,
+) -> Result<Vec<Value>> {
+    let size = chunk_size.unwrap_or(config::get_config().chunk_size);
+    process_file_with_strategy(path, &ChunkStrategy::FixedTokens { size }, chunk_overlap)
+}
+
+/// Process a file using the given chunking strategy
+///
+/// Non-code files are always chunked by paragraph when the strategy is
+/// [`ChunkStrategy::Function`]. Chunks taken from a function carry its signature
+/// in the `signature` metadata field.
+///
+/// # Arguments
+///
+/// * `path` - Path to the file
+/// * `strategy` - Strategy used to split the file content
+/// * `chunk_overlap` - Optional chunk overlap for fixed-size chunks (uses default if None)
+///
+/// # Returns
+///
+/// A vector of JSON objects representing the file content
+pub fn process_file_with_strategy(
+    path: &Path,
+    strategy: &ChunkStrategy,
+    chunk_overlap: Option<usize>,
 ) -> Result<Vec<Value>> {
     if !path.exists() {
         return Err(SemanticSearchError::InvalidPath(format!(
@@ -123,8 +151,12 @@ pub fn process_file_with_config(
     match file_type {
         FileType::Text | FileType::Markdown | FileType::Code | FileType::Json => {
             // For text-based files (including JSON), chunk the content and create multiple data points
-            // Use the configured chunk size and overlap
-            let chunks = chunk_text(&content, chunk_size, chunk_overlap);
+            // Function-level chunking only applies to source code
+            let strategy = match strategy {
+                ChunkStrategy::Function if file_type != FileType::Code => &ChunkStrategy::Paragraph,
+                strategy => strategy,
+            };
+            let chunks = chunk_with_strategy(&content, path, strategy, chunk_overlap);
             let path_str = path.to_string_lossy().to_string();
             let file_type_str = format!("{:?}", file_type);
 
@@ -132,12 +164,16 @@ pub fn process_file_with_config(
 
             for (i, chunk) in chunks.iter().enumerate() {
                 let mut metadata = serde_json::Map::new();
-                metadata.insert("text".to_string(), Value::String(chunk.clone()));
+                metadata.insert("text".to_string(), Value::String(chunk.text.clone()));
                 metadata.insert("path".to_string(), Value::String(path_str.clone()));
                 metadata.insert("file_type".to_string(), Value::String(file_type_str.clone()));
                 metadata.insert("chunk_index".to_string(), Value::Number((i as u64).into()));
                 metadata.insert("total_chunks".to_string(), Value::Number((chunks.len() as u64).into()));
 
+                if let Some(signature) = &chunk.signature {
+                    metadata.insert("signature".to_string(), Value::String(signature.clone()));
+                }
+
                 // For code files, add additional metadata
                 if file_type == FileType::Code {
                     metadata.insert(
@@ -277,6 +313,26 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_process_file_with_function_strategy() {
+        let dir = tempfile::tempdir().unwrap();
+        let code_path = dir.path().join("lib.rs");
+        fs::write(&code_path, "fn add(a: i32, b: i32) -> i32 {\n    a + b\n}\n").unwrap();
+        let text_path = dir.path().join("notes.txt");
+        fs::write(&text_path, "First note.\n\nSecond note.").unwrap();
+
+        let items = process_file_with_strategy(&code_path, &ChunkStrategy::Function, None).unwrap();
+        assert_eq!(items.len(), 1);
+        assert_eq!(items[0]["signature"], "fn add(a: i32, b: i32) -> i32");
+        assert_eq!(items[0]["language"], "rs");
+
+        // Non-code files fall back to paragraph chunking
+        let items = process_file_with_strategy(&text_path, &ChunkStrategy::Function, None).unwrap();
+        assert_eq!(items.len(), 1);
+        assert_eq!(items[0]["text"], "First note.\n\nSecond note.");
+        assert!(items[0].get("signature").is_none());
+    }
+
     #[test]
     fn test_unknown_file_types() {
         // Binary files and unsupported formats
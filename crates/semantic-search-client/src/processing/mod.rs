@@ -1,3 +1,5 @@
+/// Function-level chunking for source code using tree-sitter
+pub mod code_chunker;
 /// File processing utilities for handling different file types and extracting content
 pub mod file_processor;
 /// Text chunking utilities for breaking down text into manageable pieces for embedding
@@ -8,5 +10,11 @@ pub use file_processor::{
     process_directory,
     process_file,
     process_file_with_config,
+    process_file_with_strategy,
+};
+pub use text_chunker::{
+    Chunk,
+    ChunkStrategy,
+    chunk_text,
+    chunk_with_strategy,
 };
-pub use text_chunker::chunk_text;
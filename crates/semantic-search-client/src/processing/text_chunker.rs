@@ -1,4 +1,122 @@
+use std::path::Path;
+
+use serde::{
+    Deserialize,
+    Serialize,
+};
+
 use crate::config;
+use crate::processing::code_chunker;
+
+/// Strategy used to split file content into chunks before embedding
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ChunkStrategy {
+    /// Split into windows of a fixed number of whitespace-separated tokens
+    FixedTokens {
+        /// Number of tokens per chunk
+        size: usize,
+    },
+    /// Split source files at function and method boundaries using tree-sitter.
+    /// Files in unsupported languages fall back to [`ChunkStrategy::Paragraph`].
+    Function,
+    /// Split on blank lines, merging adjacent paragraphs up to the configured chunk size
+    Paragraph,
+}
+
+/// A chunk of text produced by [`chunk_with_strategy`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Chunk {
+    /// The chunk content
+    pub text: String,
+    /// Signature of the function this chunk belongs to, if any
+    pub signature: Option<String>,
+}
+
+impl Chunk {
+    fn plain(text: String) -> Self {
+        Self { text, signature: None }
+    }
+}
+
+/// Chunk the content of a file using the given strategy
+///
+/// # Arguments
+///
+/// * `text` - The text to chunk
+/// * `path` - Path of the file the text came from, used to detect the source language
+/// * `strategy` - The chunking strategy to apply
+/// * `overlap` - Optional overlap for fixed-size windows (if None, uses config value)
+///
+/// # Returns
+///
+/// A vector of chunks
+pub fn chunk_with_strategy(text: &str, path: &Path, strategy: &ChunkStrategy, overlap: Option<usize>) -> Vec<Chunk> {
+    match strategy {
+        ChunkStrategy::FixedTokens { size } => {
+            let size = (*size).max(1);
+            let overlap = overlap.unwrap_or(config::get_config().chunk_overlap).min(size - 1);
+            plain_chunks(chunk_text(text, Some(size), Some(overlap)))
+        },
+        ChunkStrategy::Function => {
+            code_chunker::chunk_functions(text, path).unwrap_or_else(|| plain_chunks(chunk_paragraphs(text)))
+        },
+        ChunkStrategy::Paragraph => plain_chunks(chunk_paragraphs(text)),
+    }
+}
+
+fn plain_chunks(chunks: Vec<String>) -> Vec<Chunk> {
+    chunks.into_iter().map(Chunk::plain).collect()
+}
+
+/// Chunk text on blank lines, merging adjacent paragraphs up to the configured chunk size
+///
+/// Paragraphs larger than the chunk size are split further with [`chunk_text`].
+///
+/// # Arguments
+///
+/// * `text` - The text to chunk
+///
+/// # Returns
+///
+/// A vector of string chunks
+pub fn chunk_paragraphs(text: &str) -> Vec<String> {
+    let max_words = config::get_config().chunk_size;
+
+    let mut chunks = Vec::new();
+    let mut current = String::new();
+    let mut current_words = 0;
+
+    for paragraph in text.split("\n\n").map(str::trim).filter(|p| !p.is_empty()) {
+        let words = paragraph.split_whitespace().count();
+
+        if words > max_words {
+            if !current.is_empty() {
+                chunks.push(std::mem::take(&mut current));
+                current_words = 0;
+            }
+            chunks.extend(chunk_text(paragraph, None, None));
+            continue;
+        }
+
+        if current_words + words > max_words && !current.is_empty() {
+            chunks.push(std::mem::take(&mut current));
+            current_words = 0;
+        }
+
+        if !current.is_empty() {
+            current.push_str("\n\n");
+        }
+        current.push_str(paragraph);
+        current_words += words;
+    }
+
+    if !current.is_empty() {
+        chunks.push(current);
+    }
+
+    chunks
+}
 
 /// Chunk text into smaller pieces with overlap
 ///
@@ -62,6 +180,9 @@ mod tests {
                     max_files: 1000, // Add missing max_files field
                     hosted_models_base_url: "http://test.example.com/models".to_string(),
                     embedding_type: crate::embedding::EmbeddingType::default(),
+                    chunk_strategy: ChunkStrategy::default(),
+                    idle_unload_timeout_secs: 600,
+                    embedding_batch_size: 32,
                 };
                 // Use a different approach that doesn't access private static
                 let _ = crate::config::init_config(&std::env::temp_dir());
@@ -118,4 +239,42 @@ mod tests {
         // Should use the config values (50, 10) set in setup()
         assert!(!chunks.is_empty());
     }
+
+    #[test]
+    fn test_chunk_paragraphs() {
+        setup();
+        let text = "First paragraph.\nStill first.\n\n\nSecond paragraph.\n\n   \n";
+        let chunks = chunk_paragraphs(text);
+
+        // Both paragraphs fit in one chunk and are kept separated by a blank line
+        assert_eq!(chunks, vec![
+            "First paragraph.\nStill first.\n\nSecond paragraph.".to_string()
+        ]);
+        assert!(chunk_paragraphs("").is_empty());
+    }
+
+    #[test]
+    fn test_chunk_with_strategy_fixed_tokens() {
+        setup();
+        let words: Vec<String> = (0..20).map(|i| format!("word{}", i)).collect();
+        let text = words.join(" ");
+
+        // An overlap larger than the window is clamped instead of underflowing
+        let strategy = ChunkStrategy::FixedTokens { size: 5 };
+        let chunks = chunk_with_strategy(&text, Path::new("notes.txt"), &strategy, Some(50));
+        assert!(chunks.len() > 1);
+        assert!(chunks.iter().all(|c| c.signature.is_none()));
+    }
+
+    #[test]
+    fn test_chunk_strategy_serialization() {
+        assert_eq!(
+            serde_json::to_string(&ChunkStrategy::FixedTokens { size: 256 }).unwrap(),
+            r#"{"type":"fixed_tokens","size":256}"#
+        );
+        assert_eq!(
+            serde_json::from_str::<ChunkStrategy>(r#"{"type":"function"}"#).unwrap(),
+            ChunkStrategy::Function
+        );
+    }
 }
@@ -1,5 +1,6 @@
 use std::collections::HashMap;
 use std::path::PathBuf;
+use std::str::FromStr;
 use std::sync::{
     Arc,
     Mutex,
@@ -8,6 +9,7 @@ use std::time::SystemTime;
 
 use chrono::{
     DateTime,
+    NaiveDate,
     Utc,
 };
 use serde::{
@@ -36,6 +38,60 @@ pub struct AddContextRequest {
     pub embedding_type: Option<EmbeddingType>,
 }
 
+/// Point in time from which [crate::AsyncSemanticSearchClient::index_since] re-indexes changed
+/// files
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum IndexSince {
+    /// Files modified after this time
+    Time(SystemTime),
+    /// Files changed since this git revision, e.g. a commit, branch or tag
+    GitRef(String),
+}
+
+impl FromStr for IndexSince {
+    type Err = String;
+
+    /// Parses an RFC 3339 timestamp or a `YYYY-MM-DD` date (midnight UTC) as a time, and anything
+    /// else as a git revision. Revisions starting with `-` are rejected so they can't be read as
+    /// git options
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
+        if s.is_empty() {
+            return Err("Expected a timestamp, a date or a git ref".to_string());
+        }
+
+        if let Ok(time) = DateTime::parse_from_rfc3339(s) {
+            return Ok(Self::Time(time.into()));
+        }
+        if let Some(time) = NaiveDate::parse_from_str(s, "%Y-%m-%d")
+            .ok()
+            .and_then(|date| date.and_hms_opt(0, 0, 0))
+        {
+            return Ok(Self::Time(time.and_utc().into()));
+        }
+
+        if s.starts_with('-') {
+            return Err(format!("Invalid git ref '{}': refs can't start with '-'", s));
+        }
+        Ok(Self::GitRef(s.to_string()))
+    }
+}
+
+/// Summary of an incremental re-index started by
+/// [crate::AsyncSemanticSearchClient::index_since]
+#[derive(Debug)]
+pub struct IndexSinceResponse {
+    /// Operation ID and cancellation token of the background re-index, or `None` if no files
+    /// changed
+    pub operation: Option<(Uuid, CancellationToken)>,
+    /// Number of files checked for changes
+    pub files_considered: usize,
+    /// Number of changed files that are re-embedded
+    pub files_embedded: usize,
+    /// Number of deleted files whose entries are removed from the context
+    pub files_removed: usize,
+}
+
 /// Parameters for indexing operations (internal use)
 use crate::embedding::EmbeddingType;
 
@@ -304,6 +360,8 @@ pub struct SystemStatus {
     pub waiting_count: usize,
     /// Maximum concurrent operations allowed
     pub max_concurrent: usize,
+    /// Estimated memory used by each context that is loaded, in bytes
+    pub context_memory: HashMap<ContextId, usize>,
 }
 
 /// Progress information for operations
@@ -391,6 +449,19 @@ pub enum IndexingJob {
         /// Embedding type
         embedding_type: Option<EmbeddingType>,
     },
+    /// Re-index changed files of an existing context job
+    UpdateFiles {
+        /// Operation ID
+        id: Uuid,
+        /// Cancellation token
+        cancel: CancellationToken,
+        /// ID of the context to update
+        context_id: String,
+        /// Files to re-embed
+        changed: Vec<PathBuf>,
+        /// Deleted files to remove from the context
+        removed: Vec<PathBuf>,
+    },
     /// Clear all contexts job
     Clear {
         /// Operation ID
@@ -444,3 +515,28 @@ mod progress_tests {
         assert!(progress.calculate_eta().is_none());
     }
 }
+
+#[cfg(test)]
+mod index_since_tests {
+    use std::time::Duration;
+
+    use super::*;
+
+    #[test]
+    fn test_index_since_from_str() {
+        assert_eq!(
+            IndexSince::from_str("2024-01-02T03:04:05Z").unwrap(),
+            IndexSince::Time(SystemTime::UNIX_EPOCH + Duration::from_secs(1_704_164_645))
+        );
+        assert_eq!(
+            IndexSince::from_str("2024-01-02").unwrap(),
+            IndexSince::Time(SystemTime::UNIX_EPOCH + Duration::from_secs(1_704_153_600))
+        );
+        assert_eq!(
+            IndexSince::from_str("HEAD~3").unwrap(),
+            IndexSince::GitRef("HEAD~3".to_string())
+        );
+        assert!(IndexSince::from_str("  ").is_err());
+        assert!(IndexSince::from_str("--output=/tmp/file").is_err());
+    }
+}
@@ -1,11 +1,17 @@
 use std::path::Path;
+use std::time::{
+    Duration,
+    SystemTime,
+};
 use std::{
     env,
     fs,
 };
 
+use semantic_search_client::client::background::file_processor::FileProcessor;
 use semantic_search_client::config;
 use semantic_search_client::processing::file_processor::process_file;
+use semantic_search_client::types::IndexSince;
 
 #[test]
 fn test_process_text_file() {
@@ -119,3 +125,44 @@ fn test_process_binary_file() {
     // Clean up
     fs::remove_dir_all(temp_dir).unwrap_or(());
 }
+
+#[tokio::test]
+async fn test_find_changed_files_by_mtime() {
+    // Create a temporary directory for the test, outside of any git repository
+    let temp_dir = env::temp_dir().join("memory_bank_test_changed_files");
+    fs::remove_dir_all(&temp_dir).unwrap_or(());
+    fs::create_dir_all(&temp_dir).unwrap();
+
+    fs::write(temp_dir.join("notes.md"), "# Notes").unwrap();
+    fs::write(temp_dir.join("data.tmp"), "scratch").unwrap();
+    fs::write(temp_dir.join(".hidden.md"), "# Hidden").unwrap();
+    let exclude_patterns = Some(vec!["*.tmp".to_string()]);
+
+    // Every indexed file changed since an hour ago
+    let since = IndexSince::Time(SystemTime::now() - Duration::from_secs(3600));
+    let changed = FileProcessor::find_changed_files(&temp_dir, &since, &None, &exclude_patterns)
+        .await
+        .unwrap();
+    assert_eq!(changed.considered, 1);
+    assert_eq!(changed.changed, vec![temp_dir.join("notes.md")]);
+    assert!(changed.removed.is_empty());
+
+    // Nothing changed since an hour from now
+    let since = IndexSince::Time(SystemTime::now() + Duration::from_secs(3600));
+    let changed = FileProcessor::find_changed_files(&temp_dir, &since, &None, &exclude_patterns)
+        .await
+        .unwrap();
+    assert_eq!(changed.considered, 1);
+    assert!(changed.changed.is_empty());
+
+    // A git ref can't be resolved outside of a git repository
+    let since = IndexSince::GitRef("HEAD~1".to_string());
+    assert!(
+        FileProcessor::find_changed_files(&temp_dir, &since, &None, &None)
+            .await
+            .is_err()
+    );
+
+    // Clean up
+    fs::remove_dir_all(temp_dir).unwrap_or(());
+}
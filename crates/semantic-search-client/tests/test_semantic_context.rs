@@ -1,4 +1,7 @@
-use std::collections::HashMap;
+use std::collections::{
+    HashMap,
+    HashSet,
+};
 use std::{
     env,
     fs,
@@ -17,10 +20,10 @@ fn test_semantic_context_creation() {
     let data_path = temp_dir.join("data.json");
 
     // Create a new semantic context
-    let semantic_context = SemanticContext::new(data_path).unwrap();
+    let mut semantic_context = SemanticContext::new(data_path).unwrap();
 
     // Verify the context was created successfully
-    assert_eq!(semantic_context.get_data_points().len(), 0);
+    assert_eq!(semantic_context.get_data_points().unwrap().len(), 0);
 
     // Clean up
     fs::remove_dir_all(temp_dir).unwrap_or(());
@@ -79,7 +82,7 @@ fn test_add_data_points() {
 
     // Verify the data points were added
     assert_eq!(count, 2);
-    assert_eq!(semantic_context.get_data_points().len(), 2);
+    assert_eq!(semantic_context.get_data_points().unwrap().len(), 2);
 
     // Test search functionality
     let query_vector = vec![0.15; 384]; // Query vector between the two data points
@@ -92,8 +95,153 @@ fn test_add_data_points() {
     semantic_context.save().unwrap();
 
     // Load the context again to verify persistence
-    let loaded_context = SemanticContext::new(data_path).unwrap();
-    assert_eq!(loaded_context.get_data_points().len(), 2);
+    let mut loaded_context = SemanticContext::new(data_path).unwrap();
+    assert_eq!(loaded_context.get_data_points().unwrap().len(), 2);
+
+    // Clean up
+    fs::remove_dir_all(temp_dir).unwrap_or(());
+}
+
+fn test_data_point(id: usize) -> DataPoint {
+    let mut payload = HashMap::new();
+    payload.insert("text".to_string(), Value::String(format!("Test data point {id}")));
+
+    DataPoint {
+        id,
+        payload,
+        vector: vec![0.1 * (id + 1) as f32; 384],
+    }
+}
+
+#[test]
+fn test_lazy_load_and_unload() {
+    // Create a temporary directory for the test
+    let temp_dir = env::temp_dir().join("memory_bank_test_lazy_load");
+    fs::remove_dir_all(&temp_dir).unwrap_or(());
+    fs::create_dir_all(&temp_dir).unwrap();
+
+    let data_path = temp_dir.join("data.json");
+
+    let mut semantic_context = SemanticContext::new(data_path.clone()).unwrap();
+    semantic_context
+        .add_data_points((0..3).map(test_data_point).collect())
+        .unwrap();
+    semantic_context.save().unwrap();
+
+    // Nothing is read from disk until the context is used
+    let mut semantic_context = SemanticContext::new(data_path).unwrap();
+    assert!(!semantic_context.is_loaded());
+    assert_eq!(semantic_context.memory_footprint(), 0);
+
+    let results = semantic_context.search(&[0.2; 384], 3).unwrap();
+    assert_eq!(results.len(), 3);
+    assert!(semantic_context.is_loaded());
+    assert!(semantic_context.memory_footprint() > 0);
+
+    // Unloading frees the data, which is loaded again on the next use
+    semantic_context.unload().unwrap();
+    assert!(!semantic_context.is_loaded());
+    assert_eq!(semantic_context.memory_footprint(), 0);
+    assert_eq!(semantic_context.get_data_points().unwrap().len(), 3);
+
+    // Clean up
+    fs::remove_dir_all(temp_dir).unwrap_or(());
+}
+
+#[test]
+fn test_incremental_save() {
+    // Create a temporary directory for the test
+    let temp_dir = env::temp_dir().join("memory_bank_test_incremental_save");
+    fs::remove_dir_all(&temp_dir).unwrap_or(());
+    fs::create_dir_all(&temp_dir).unwrap();
+
+    let data_path = temp_dir.join("data.json");
+    let delta_path = temp_dir.join("data.delta.jsonl");
+
+    let mut semantic_context = SemanticContext::new(data_path.clone()).unwrap();
+    semantic_context
+        .add_data_points((0..4).map(test_data_point).collect())
+        .unwrap();
+    semantic_context.save().unwrap();
+    assert!(!delta_path.exists());
+    let saved_data = fs::read_to_string(&data_path).unwrap();
+
+    // Small additions are appended without rewriting the main data file
+    semantic_context.add_data_points(vec![test_data_point(4)]).unwrap();
+    semantic_context.save().unwrap();
+    semantic_context.add_data_points(vec![test_data_point(5)]).unwrap();
+    semantic_context.unload().unwrap();
+    assert_eq!(fs::read_to_string(&data_path).unwrap(), saved_data);
+    assert_eq!(fs::read_to_string(&delta_path).unwrap().lines().count(), 2);
+
+    let ids: Vec<usize> = semantic_context
+        .get_data_points()
+        .unwrap()
+        .iter()
+        .map(|point| point.id)
+        .collect();
+    assert_eq!(ids, vec![0, 1, 2, 3, 4, 5]);
+
+    // Once the appended points outnumber the main data file, everything is saved again
+    semantic_context
+        .add_data_points((6..11).map(test_data_point).collect())
+        .unwrap();
+    semantic_context.save().unwrap();
+    assert!(!delta_path.exists());
+
+    let mut loaded_context = SemanticContext::new(data_path).unwrap();
+    assert_eq!(loaded_context.get_data_points().unwrap().len(), 11);
+
+    // Clean up
+    fs::remove_dir_all(temp_dir).unwrap_or(());
+}
+
+#[test]
+fn test_remove_data_points_by_path() {
+    // Create a temporary directory for the test
+    let temp_dir = env::temp_dir().join("memory_bank_test_remove_by_path");
+    fs::remove_dir_all(&temp_dir).unwrap_or(());
+    fs::create_dir_all(&temp_dir).unwrap();
+
+    let data_path = temp_dir.join("data.json");
+    let delta_path = temp_dir.join("data.delta.jsonl");
+
+    let mut semantic_context = SemanticContext::new(data_path.clone()).unwrap();
+    let points = (0..4)
+        .map(|id| {
+            let mut point = test_data_point(id);
+            let path = if id % 2 == 0 { "/docs/a.md" } else { "/docs/b.md" };
+            point
+                .payload
+                .insert("path".to_string(), Value::String(path.to_string()));
+            point
+        })
+        .collect();
+    semantic_context.add_data_points(points).unwrap();
+    semantic_context.save().unwrap();
+    semantic_context.add_data_points(vec![test_data_point(4)]).unwrap();
+    semantic_context.save().unwrap();
+    assert!(delta_path.exists());
+
+    let stale_paths = HashSet::from(["/docs/a.md".to_string(), "/docs/missing.md".to_string()]);
+    assert_eq!(semantic_context.remove_data_points_by_path(&stale_paths).unwrap(), 2);
+    assert_eq!(semantic_context.remove_data_points_by_path(&stale_paths).unwrap(), 0);
+
+    // The remaining points are renumbered and searchable
+    let ids: Vec<usize> = semantic_context
+        .get_data_points()
+        .unwrap()
+        .iter()
+        .map(|point| point.id)
+        .collect();
+    assert_eq!(ids, vec![0, 1, 2]);
+    assert_eq!(semantic_context.search(&[0.2; 384], 5).unwrap().len(), 3);
+
+    // Removals rewrite the whole context on the next save
+    semantic_context.save().unwrap();
+    assert!(!delta_path.exists());
+    let mut loaded_context = SemanticContext::new(data_path).unwrap();
+    assert_eq!(loaded_context.get_data_points().unwrap().len(), 3);
 
     // Clean up
     fs::remove_dir_all(temp_dir).unwrap_or(());